@@ -0,0 +1,66 @@
+//! Binary payloads carried inside JSON messages.
+//!
+//! Terminal content is not guaranteed to be UTF-8, so byte-exact data travels as standard
+//! base64 in a `*_b64` field alongside (or instead of) its lossy text form.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Raw bytes that serialize as a base64 string
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Base64Bytes(pub Vec<u8>);
+
+impl Base64Bytes {
+    pub fn encode(&self) -> String {
+        STANDARD.encode(&self.0)
+    }
+
+    pub fn decode(encoded: &str) -> Result<Self, base64::DecodeError> {
+        STANDARD.decode(encoded.trim()).map(Self)
+    }
+
+    /// Text form with invalid UTF-8 sequences replaced by U+FFFD
+    pub fn to_lossy_string(&self) -> String {
+        String::from_utf8_lossy(&self.0).into_owned()
+    }
+}
+
+impl From<Vec<u8>> for Base64Bytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl Serialize for Base64Bytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.encode())
+    }
+}
+
+impl<'de> Deserialize<'de> for Base64Bytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        Self::decode(&encoded).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64_round_trip_preserves_invalid_utf8() {
+        let bytes = Base64Bytes(vec![b'o', b'k', 0xff, 0xfe, b'\n']);
+        let json = serde_json::to_string(&bytes).unwrap();
+        assert_eq!(json, "\"b2v//go=\"");
+        let parsed: Base64Bytes = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, bytes);
+        assert_eq!(parsed.to_lossy_string(), "ok\u{fffd}\u{fffd}\n");
+    }
+
+    #[test]
+    fn test_base64_rejects_garbage() {
+        assert!(serde_json::from_str::<Base64Bytes>("\"not base64!\"").is_err());
+    }
+}
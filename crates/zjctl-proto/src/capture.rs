@@ -0,0 +1,306 @@
+//! Windowed pane captures: the last N lines, or only what was added since an earlier capture.
+//!
+//! An offset token (`"<lines>-<hash>"`) marks the end of the complete lines of a capture; the
+//! hash of the line just before it lets a later capture find that point again after scrollback
+//! was trimmed, and report a reset when it is gone (cleared screen, different pane). The last
+//! line of a capture may still be growing, so it always comes back in the next delta.
+//!
+//! These are the `lines` / `since_offset` parameters of a pane capture. Zellij has no
+//! pane-contents API for plugins yet, so the CLI applies them to `dump-screen` output; the
+//! plugin can take the same parameters once it can read panes itself.
+//!
+//! A [`CaptureRegion`] instead cuts a rectangle out of the visible screen, so a script can
+//! capture or wait on one part of a TUI (a summary line, a status area).
+
+use std::ops::Range;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Which part of a capture to return
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaptureWindow {
+    /// Only the last N lines
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lines: Option<usize>,
+    /// Only the lines after this offset token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since_offset: Option<String>,
+}
+
+/// A windowed capture
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaptureSlice {
+    pub text: String,
+    /// Token for the next `since_offset`
+    pub offset: String,
+    /// `since_offset` could not be found, so `text` starts from the beginning
+    #[serde(default)]
+    pub reset: bool,
+}
+
+impl CaptureWindow {
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_none() && self.since_offset.is_none()
+    }
+
+    /// Cut `text` (a full capture) down to this window; trailing blank lines are dropped
+    pub fn apply(&self, text: &str) -> CaptureSlice {
+        let mut lines: Vec<&str> = text.lines().collect();
+        while lines.last().is_some_and(|line| line.trim().is_empty()) {
+            lines.pop();
+        }
+        // Everything but the last line is complete.
+        let complete = lines.len().saturating_sub(1);
+        let offset = offset_token(&lines, complete);
+
+        let (mut start, mut reset) = (0, false);
+        if let Some(token) = &self.since_offset {
+            match find_offset(&lines[..complete], token) {
+                Some(found) => start = found,
+                None => reset = true,
+            }
+        }
+        if let Some(n) = self.lines {
+            start = start.max(lines.len().saturating_sub(n));
+        }
+
+        let window = &lines[start..];
+        let mut text = window.join("\n");
+        if !window.is_empty() {
+            text.push('\n');
+        }
+        CaptureSlice {
+            text,
+            offset,
+            reset,
+        }
+    }
+}
+
+/// Rows and columns of the visible screen: `top:5`, `bottom:2`, `rows 10-20`, `cols 0-80`, or
+/// a row part and a column part joined by a comma (`rows 10-20, cols 0-80`). Ranges are 0-based
+/// and end-exclusive; `rows:10-20` works too.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CaptureRegion {
+    pub rows: Option<RowSpan>,
+    pub cols: Option<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowSpan {
+    /// The first N rows
+    Top(usize),
+    /// The last N rows
+    Bottom(usize),
+    Range(usize, usize),
+}
+
+impl FromStr for CaptureRegion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut region = CaptureRegion::default();
+        for part in s.split(',').map(str::trim) {
+            let (kind, value) = part
+                .split_once(|c: char| c == ':' || c.is_whitespace())
+                .map(|(kind, value)| (kind.trim(), value.trim()))
+                .ok_or_else(|| format!("invalid region part `{part}` (expected e.g. top:5)"))?;
+            let count = || {
+                value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| format!("invalid row count in `{part}`"))
+            };
+            let span = match kind {
+                "top" => RowSpan::Top(count()?),
+                "bottom" => RowSpan::Bottom(count()?),
+                "rows" => {
+                    let (start, end) = parse_range(value, part)?;
+                    RowSpan::Range(start, end)
+                }
+                "cols" => {
+                    if region.cols.is_some() {
+                        return Err(format!("region `{s}` has more than one column range"));
+                    }
+                    region.cols = Some(parse_range(value, part)?);
+                    continue;
+                }
+                _ => {
+                    return Err(format!(
+                        "unknown region part `{kind}` (use top, bottom, rows, or cols)"
+                    ))
+                }
+            };
+            if region.rows.replace(span).is_some() {
+                return Err(format!("region `{s}` has more than one row range"));
+            }
+        }
+        Ok(region)
+    }
+}
+
+/// `10-20`, with `start < end`
+fn parse_range(value: &str, part: &str) -> Result<(usize, usize), String> {
+    let invalid = || format!("invalid range in `{part}` (expected START-END, e.g. 10-20)");
+    let (start, end) = value.split_once('-').ok_or_else(invalid)?;
+    let start: usize = start.trim().parse().map_err(|_| invalid())?;
+    let end: usize = end.trim().parse().map_err(|_| invalid())?;
+    if start >= end {
+        return Err(format!("empty range in `{part}` (the end is exclusive)"));
+    }
+    Ok((start, end))
+}
+
+impl CaptureRegion {
+    /// Row and column ranges on a `rows` x `cols` screen; errors when the region does not fit
+    pub fn bounds(&self, rows: usize, cols: usize) -> Result<(Range<usize>, Range<usize>), String> {
+        let too_big = |what: &str, size: usize| {
+            format!("region is outside the pane: {what} (the pane is {rows} rows x {cols} cols, {size} max)")
+        };
+        let row_range = match self.rows {
+            None => 0..rows,
+            Some(RowSpan::Top(n)) if n <= rows => 0..n,
+            Some(RowSpan::Bottom(n)) if n <= rows => rows - n..rows,
+            Some(RowSpan::Range(start, end)) if end <= rows => start..end,
+            Some(RowSpan::Top(n) | RowSpan::Bottom(n)) => {
+                return Err(too_big(&format!("{n} rows"), rows))
+            }
+            Some(RowSpan::Range(_, end)) => {
+                return Err(too_big(&format!("rows up to {end}"), rows))
+            }
+        };
+        let col_range = match self.cols {
+            None => 0..cols,
+            Some((start, end)) if end <= cols => start..end,
+            Some((_, end)) => return Err(too_big(&format!("cols up to {end}"), cols)),
+        };
+        Ok((row_range, col_range))
+    }
+
+    /// The region of `screen`, a dump of the visible screen of a `rows` x `cols` pane. A size of
+    /// 0 (a plugin that reports no geometry) is taken from the dump instead.
+    pub fn cut(&self, screen: &str, rows: usize, cols: usize) -> Result<String, String> {
+        let lines: Vec<&str> = screen.lines().collect();
+        let rows = if rows == 0 { lines.len() } else { rows };
+        let cols = if cols == 0 {
+            lines
+                .iter()
+                .map(|line| line.chars().count())
+                .max()
+                .unwrap_or(0)
+        } else {
+            cols
+        };
+        let (row_range, col_range) = self.bounds(rows, cols)?;
+        let mut text = String::new();
+        for row in row_range {
+            let line = lines.get(row).copied().unwrap_or_default();
+            let cut: String = line
+                .chars()
+                .skip(col_range.start)
+                .take(col_range.len())
+                .collect();
+            text.push_str(cut.trim_end());
+            text.push('\n');
+        }
+        Ok(text)
+    }
+}
+
+fn offset_token(lines: &[&str], at: usize) -> String {
+    let anchor = at.checked_sub(1).map_or(0, |i| fnv1a(lines[i]));
+    format!("{at}-{anchor:016x}")
+}
+
+/// Line index just past the token's anchor line: where the token says it is, otherwise the
+/// last matching line before that (scrollback trimmed from the top)
+fn find_offset(lines: &[&str], token: &str) -> Option<usize> {
+    let (at, anchor) = token.split_once('-')?;
+    let at: usize = at.parse().ok()?;
+    let anchor = u64::from_str_radix(anchor, 16).ok()?;
+    if at == 0 {
+        return Some(0);
+    }
+    if at <= lines.len() && fnv1a(lines[at - 1]) == anchor {
+        return Some(at);
+    }
+    lines[..at.min(lines.len())]
+        .iter()
+        .rposition(|line| fnv1a(line) == anchor)
+        .map(|i| i + 1)
+}
+
+/// Stable across builds, unlike `DefaultHasher`
+fn fnv1a(line: &str) -> u64 {
+    line.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn since_offset_returns_new_lines_and_the_open_line() {
+        let first = CaptureWindow::default().apply("a\nb\n$ \n\n");
+        assert_eq!(first.text, "a\nb\n$ \n");
+        let window = CaptureWindow {
+            since_offset: Some(first.offset),
+            ..Default::default()
+        };
+        let next = window.apply("a\nb\n$ make\nok\n$ \n");
+        assert_eq!(next.text, "$ make\nok\n$ \n");
+        assert!(!next.reset);
+
+        // Scrollback trimmed from the top: the anchor line is found further up.
+        let trimmed = window.apply("b\n$ make\nok\n$ \n");
+        assert_eq!(trimmed.text, "$ make\nok\n$ \n");
+        // Cleared screen: everything comes back, flagged as a reset.
+        let cleared = window.apply("$ \n");
+        assert!(cleared.reset);
+        assert_eq!(cleared.text, "$ \n");
+    }
+
+    #[test]
+    fn lines_keeps_the_tail() {
+        let window = CaptureWindow {
+            lines: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(window.apply("1\n2\n3\n\n").text, "2\n3\n");
+        assert_eq!(window.apply("").text, "");
+        assert!(CaptureWindow::default().is_empty());
+    }
+
+    #[test]
+    fn regions_parse_cut_and_check_bounds() {
+        let region: CaptureRegion = "rows 1-3, cols 2-5".parse().unwrap();
+        assert_eq!(region.rows, Some(RowSpan::Range(1, 3)));
+        assert_eq!(region.cols, Some((2, 5)));
+        assert_eq!(
+            region.cut("0123456\nabcdefg\nABCDEFG\n", 4, 7).unwrap(),
+            "cde\nCDE\n"
+        );
+
+        let bottom: CaptureRegion = "bottom:2".parse().unwrap();
+        assert_eq!(bottom.cut("a\nb\n", 3, 10).unwrap(), "b\n\n");
+        assert_eq!(
+            "top:5".parse(),
+            Ok(CaptureRegion {
+                rows: Some(RowSpan::Top(5)),
+                cols: None,
+            })
+        );
+        assert!("top:5"
+            .parse::<CaptureRegion>()
+            .unwrap()
+            .bounds(4, 80)
+            .is_err());
+        assert!("rows 5-5".parse::<CaptureRegion>().is_err());
+        assert!("top:1, bottom:1".parse::<CaptureRegion>().is_err());
+        assert!("left:3".parse::<CaptureRegion>().is_err());
+    }
+}
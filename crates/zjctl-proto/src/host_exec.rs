@@ -0,0 +1,48 @@
+//! The `host.exec` allowlist and where the plugin keeps it.
+//!
+//! Zellij runs one plugin instance per URL and configuration. zjctl pipes carry their own
+//! configuration (`zjctl_session=…`), so the instance that answers them is never the one the
+//! user configured with `host_exec_allow` in config.kdl or a layout. That instance publishes
+//! its list to [`HOST_EXEC_ALLOW_FILE`], in the `/data` folder Zellij shares between all
+//! instances of the plugin, and the answering instance reads it from there. No RPC writes it.
+
+/// The published allowlist, one program per line
+pub const HOST_EXEC_ALLOW_FILE: &str = "/data/host_exec_allow";
+
+/// How long `host.exec` waits for a command when the call does not say
+pub const HOST_EXEC_WAIT_MS: u64 = 30_000;
+/// Longest `wait_ms` a `host.exec` call may ask for
+pub const MAX_HOST_EXEC_WAIT_MS: u64 = 600_000;
+
+/// Program names from a `host_exec_allow` value (`prog1:prog2`) or the published file (one
+/// per line)
+pub fn parse_host_exec_allow(list: &str) -> Vec<String> {
+    let mut programs: Vec<String> = Vec::new();
+    for name in list.split([':', '\n']).map(str::trim) {
+        if !name.is_empty() && !programs.iter().any(|known| known == name) {
+            programs.push(name.to_string());
+        }
+    }
+    programs
+}
+
+/// Contents of [`HOST_EXEC_ALLOW_FILE`] for `programs`
+pub fn format_host_exec_allow(programs: &[String]) -> String {
+    programs.iter().map(|name| format!("{name}\n")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configured_list_round_trips_through_the_published_file() {
+        let programs = parse_host_exec_allow("pgrep:readlink::pgrep");
+        assert_eq!(programs, ["pgrep", "readlink"]);
+        let file = format_host_exec_allow(&programs);
+        assert_eq!(file, "pgrep\nreadlink\n");
+        assert_eq!(parse_host_exec_allow(&file), programs);
+        assert!(parse_host_exec_allow("").is_empty());
+        assert!(format_host_exec_allow(&[]).is_empty());
+    }
+}
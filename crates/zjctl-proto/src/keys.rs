@@ -0,0 +1,219 @@
+//! Symbolic key names (`C-c`, `M-x`, `Enter`, `Up`, `F5`) and the bytes a terminal sends for them.
+//!
+//! Names follow tmux `send-keys`: `C-`, `M-`, and `S-` modifier prefixes, named keys matched
+//! case-insensitively, and any other single character sent as itself. Modified cursor and
+//! function keys use the xterm `CSI 1;<mod>` encoding.
+
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum KeyError {
+    #[error("empty key name")]
+    Empty,
+    #[error("unknown key: {0}")]
+    Unknown(String),
+    #[error("cannot apply Ctrl to {0}")]
+    NoControlCode(String),
+}
+
+/// Terminal input for one key name
+pub fn encode_key(name: &str) -> Result<String, KeyError> {
+    if name.is_empty() {
+        return Err(KeyError::Empty);
+    }
+    let (modifiers, key) = split_modifiers(name);
+    let Modifiers { ctrl, alt, shift } = modifiers;
+
+    if let Some(sequence) = special_key(key) {
+        let encoded = match (sequence, ctrl || alt || shift) {
+            (Special::Csi(code), false) => format!("\x1b[{code}"),
+            (Special::Csi(code), true) => format!("\x1b[1;{}{code}", modifiers.param()),
+            (Special::Ss3(code), false) => format!("\x1bO{code}"),
+            (Special::Ss3(code), true) => format!("\x1b[1;{}{code}", modifiers.param()),
+            (Special::Tilde(number), false) => format!("\x1b[{number}~"),
+            (Special::Tilde(number), true) => format!("\x1b[{number};{}~", modifiers.param()),
+            (Special::Byte(b'\t'), _) if shift && !ctrl && !alt => "\x1b[Z".to_string(),
+            (Special::Byte(byte), _) => {
+                let byte = if ctrl {
+                    control_byte(byte).ok_or_else(|| KeyError::NoControlCode(name.to_string()))?
+                } else {
+                    byte
+                };
+                with_alt(alt, char::from(byte).to_string())
+            }
+        };
+        return Ok(encoded);
+    }
+
+    let mut chars = key.chars();
+    let (Some(c), None) = (chars.next(), chars.next()) else {
+        return Err(KeyError::Unknown(name.to_string()));
+    };
+    let text = if ctrl {
+        let byte = u8::try_from(c)
+            .ok()
+            .and_then(control_byte)
+            .ok_or_else(|| KeyError::NoControlCode(name.to_string()))?;
+        char::from(byte).to_string()
+    } else if shift {
+        c.to_uppercase().collect()
+    } else {
+        c.to_string()
+    };
+    Ok(with_alt(alt, text))
+}
+
+/// Terminal input for a sequence of key names, concatenated
+pub fn encode_keys<S: AsRef<str>>(names: &[S]) -> Result<String, KeyError> {
+    names.iter().map(|name| encode_key(name.as_ref())).collect()
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Modifiers {
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+}
+
+impl Modifiers {
+    /// xterm modifier parameter: 1 + shift + 2*alt + 4*ctrl
+    fn param(self) -> u8 {
+        1 + u8::from(self.shift) + 2 * u8::from(self.alt) + 4 * u8::from(self.ctrl)
+    }
+}
+
+/// Strip `C-`/`M-`/`S-` prefixes; a lone trailing `-` (as in `C--`) is the key itself
+fn split_modifiers(name: &str) -> (Modifiers, &str) {
+    let mut modifiers = Modifiers::default();
+    let mut rest = name;
+    while rest.len() > 2 && rest.as_bytes()[1] == b'-' {
+        match rest.as_bytes()[0] {
+            b'C' | b'c' => modifiers.ctrl = true,
+            b'M' | b'm' | b'A' | b'a' => modifiers.alt = true,
+            b'S' | b's' => modifiers.shift = true,
+            _ => break,
+        }
+        rest = &rest[2..];
+    }
+    (modifiers, rest)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Special {
+    /// A single byte (Enter, Tab, Escape, ...)
+    Byte(u8),
+    /// `CSI <code>` (cursor keys)
+    Csi(char),
+    /// `SS3 <code>` (F1-F4)
+    Ss3(char),
+    /// `CSI <number> ~`
+    Tilde(u8),
+}
+
+fn special_key(key: &str) -> Option<Special> {
+    let special = match key.to_ascii_lowercase().as_str() {
+        "enter" | "return" | "cr" => Special::Byte(b'\r'),
+        "tab" => Special::Byte(b'\t'),
+        "btab" => return Some(Special::Csi('Z')),
+        "escape" | "esc" => Special::Byte(0x1b),
+        "space" => Special::Byte(b' '),
+        "bspace" | "backspace" => Special::Byte(0x7f),
+        "up" => Special::Csi('A'),
+        "down" => Special::Csi('B'),
+        "right" => Special::Csi('C'),
+        "left" => Special::Csi('D'),
+        "home" => Special::Csi('H'),
+        "end" => Special::Csi('F'),
+        "insert" | "ic" => Special::Tilde(2),
+        "delete" | "dc" => Special::Tilde(3),
+        "pageup" | "pgup" | "ppage" => Special::Tilde(5),
+        "pagedown" | "pgdn" | "npage" => Special::Tilde(6),
+        "f1" => Special::Ss3('P'),
+        "f2" => Special::Ss3('Q'),
+        "f3" => Special::Ss3('R'),
+        "f4" => Special::Ss3('S'),
+        "f5" => Special::Tilde(15),
+        "f6" => Special::Tilde(17),
+        "f7" => Special::Tilde(18),
+        "f8" => Special::Tilde(19),
+        "f9" => Special::Tilde(20),
+        "f10" => Special::Tilde(21),
+        "f11" => Special::Tilde(23),
+        "f12" => Special::Tilde(24),
+        _ => return None,
+    };
+    Some(special)
+}
+
+/// Control code for Ctrl+`byte` (`C-c` = 0x03, `C-[` = Escape, `C-Space`/`C-@` = NUL)
+fn control_byte(byte: u8) -> Option<u8> {
+    match byte {
+        b'a'..=b'z' => Some(byte - b'a' + 1),
+        b'@'..=b'_' => Some(byte - b'@'),
+        b' ' | b'2' => Some(0),
+        b'?' => Some(0x7f),
+        _ => None,
+    }
+}
+
+fn with_alt(alt: bool, text: String) -> String {
+    if alt {
+        format!("\x1b{text}")
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn control_and_meta_combinations() {
+        assert_eq!(encode_key("C-c").unwrap(), "\x03");
+        assert_eq!(encode_key("C-C").unwrap(), "\x03");
+        assert_eq!(encode_key("C-[").unwrap(), "\x1b");
+        assert_eq!(encode_key("C-Space").unwrap(), "\0");
+        assert_eq!(encode_key("M-x").unwrap(), "\x1bx");
+        assert_eq!(encode_key("C-M-a").unwrap(), "\x1b\x01");
+        assert_eq!(encode_key("M-Enter").unwrap(), "\x1b\r");
+        assert_eq!(
+            encode_key("C--"),
+            Err(KeyError::NoControlCode("C--".to_string()))
+        );
+    }
+
+    #[test]
+    fn named_keys() {
+        assert_eq!(encode_key("Enter").unwrap(), "\r");
+        assert_eq!(encode_key("escape").unwrap(), "\x1b");
+        assert_eq!(encode_key("Up").unwrap(), "\x1b[A");
+        assert_eq!(encode_key("F1").unwrap(), "\x1bOP");
+        assert_eq!(encode_key("F5").unwrap(), "\x1b[15~");
+        assert_eq!(encode_key("PageDown").unwrap(), "\x1b[6~");
+        assert_eq!(encode_key("BTab").unwrap(), "\x1b[Z");
+        assert_eq!(encode_key("S-Tab").unwrap(), "\x1b[Z");
+    }
+
+    #[test]
+    fn modified_special_keys_use_xterm_parameters() {
+        assert_eq!(encode_key("C-Up").unwrap(), "\x1b[1;5A");
+        assert_eq!(encode_key("S-Right").unwrap(), "\x1b[1;2C");
+        assert_eq!(encode_key("M-F1").unwrap(), "\x1b[1;3P");
+        assert_eq!(encode_key("C-Delete").unwrap(), "\x1b[3;5~");
+    }
+
+    #[test]
+    fn single_characters_and_errors() {
+        assert_eq!(encode_key("q").unwrap(), "q");
+        assert_eq!(encode_key("-").unwrap(), "-");
+        assert_eq!(encode_key("é").unwrap(), "é");
+        assert_eq!(encode_key("S-a").unwrap(), "A");
+        assert_eq!(encode_key(""), Err(KeyError::Empty));
+        assert_eq!(
+            encode_key("Hyper"),
+            Err(KeyError::Unknown("Hyper".to_string()))
+        );
+        assert_eq!(encode_keys(&["i", "x", "Escape"]).unwrap(), "ix\x1b");
+    }
+}
@@ -2,8 +2,18 @@
 //!
 //! Uses newline-delimited JSON (jsonl) for transport over Zellij pipes.
 
+mod binary;
+mod capture;
+mod host_exec;
+mod keys;
 mod protocol;
 mod selector;
+mod update;
 
+pub use binary::*;
+pub use capture::*;
+pub use host_exec::*;
+pub use keys::*;
 pub use protocol::*;
 pub use selector::*;
+pub use update::*;
@@ -18,16 +18,48 @@ pub struct RpcRequest {
     /// Method parameters
     #[serde(default)]
     pub params: serde_json::Value,
+    /// W3C trace ID (32 hex chars) of the caller's span, when tracing is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+}
+
+/// Source of request IDs
+pub trait IdProvider {
+    fn next_id(&self) -> Uuid;
+}
+
+/// Random v4 request IDs
+#[cfg(feature = "v4")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomIds;
+
+#[cfg(feature = "v4")]
+impl IdProvider for RandomIds {
+    fn next_id(&self) -> Uuid {
+        Uuid::new_v4()
+    }
 }
 
 impl RpcRequest {
-    /// Create a new RPC request
+    /// Create a new RPC request with a random ID
+    #[cfg(feature = "v4")]
     pub fn new(method: impl Into<String>) -> Self {
+        Self::with_id(method, RandomIds.next_id())
+    }
+
+    /// Create a new RPC request with an ID from the given provider
+    pub fn from_provider(method: impl Into<String>, ids: &impl IdProvider) -> Self {
+        Self::with_id(method, ids.next_id())
+    }
+
+    /// Create a new RPC request with an explicit ID
+    pub fn with_id(method: impl Into<String>, id: Uuid) -> Self {
         Self {
             v: PROTOCOL_VERSION,
-            id: Uuid::new_v4(),
+            id,
             method: method.into(),
             params: serde_json::Value::Null,
+            trace_id: None,
         }
     }
 
@@ -36,6 +68,31 @@ impl RpcRequest {
         self.params = serde_json::to_value(params)?;
         Ok(self)
     }
+
+    /// Attach the trace ID of the span issuing this request
+    pub fn with_trace_id(mut self, trace_id: Option<String>) -> Self {
+        self.trace_id = trace_id;
+        self
+    }
+}
+
+/// Payload of one pipe invocation: a single request, or a batch (JSON array) of requests that
+/// the plugin handles in order, writing one response per request to the same stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RpcEnvelope {
+    Batch(Vec<RpcRequest>),
+    Single(RpcRequest),
+}
+
+impl RpcEnvelope {
+    /// Requests in the order they are handled
+    pub fn into_requests(self) -> Vec<RpcRequest> {
+        match self {
+            RpcEnvelope::Batch(requests) => requests,
+            RpcEnvelope::Single(request) => vec![request],
+        }
+    }
 }
 
 /// RPC response from zrpc plugin to zjctl CLI
@@ -86,6 +143,9 @@ pub struct RpcError {
     pub code: RpcErrorCode,
     /// Human-readable error message
     pub message: String,
+    /// Concrete next steps (e.g. commands to run) to resolve the error
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hints: Vec<String>,
 }
 
 impl RpcError {
@@ -93,8 +153,15 @@ impl RpcError {
         Self {
             code,
             message: message.into(),
+            hints: Vec::new(),
         }
     }
+
+    /// Append a fix hint
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hints.push(hint.into());
+        self
+    }
 }
 
 /// Standard RPC error codes
@@ -113,15 +180,53 @@ pub enum RpcErrorCode {
     AmbiguousMatch,
     /// Internal error
     Internal,
+    /// Target pane is focused in scroll or search mode, where input would be lost
+    ScrollMode,
+    /// Caller's token is missing or unknown
+    Unauthorized,
+    /// The method is not allowed here: read-only mode, or a token scope that does not cover it
+    PolicyDenied,
 }
 
 /// RPC methods
 pub mod methods {
     pub const PANES_LIST: &str = "panes.list";
+    pub const PANES_HISTORY: &str = "panes.history";
     pub const PANE_SEND: &str = "pane.send";
+    pub const PANE_SEND_KEYS: &str = "pane.send_keys";
+    pub const PANE_BROADCAST: &str = "pane.broadcast";
     pub const PANE_FOCUS: &str = "pane.focus";
+    pub const PANE_FOCUS_PREVIOUS: &str = "pane.focus_previous";
     pub const PANE_RENAME: &str = "pane.rename";
     pub const PANE_RESIZE: &str = "pane.resize";
+    pub const PANE_SCROLL: &str = "pane.scroll";
+    pub const PANE_CLOSE: &str = "pane.close";
+    pub const PANE_RERUN: &str = "pane.rerun";
+    pub const PANE_HISTORY: &str = "pane.history";
+    pub const PANE_LAUNCH: &str = "pane.launch";
+    pub const PANE_SET_CWD: &str = "pane.set_cwd";
+    pub const PANE_SET_TOKEN: &str = "pane.set_token";
+    pub const PANE_TOGGLE_FLOAT: &str = "pane.toggle_float";
+    pub const PANE_TOGGLE_FULLSCREEN: &str = "pane.toggle_fullscreen";
+    pub const PANE_PIN: &str = "pane.pin";
+    pub const PANE_MOVE: &str = "pane.move";
+    pub const PANE_CLEAR: &str = "pane.clear";
+    pub const MARK_SET: &str = "mark.set";
+    pub const MARK_REMOVE: &str = "mark.remove";
+    pub const MARKS_LIST: &str = "marks.list";
+    pub const TAG_ADD: &str = "tag.add";
+    pub const TAG_REMOVE: &str = "tag.remove";
+    pub const TAGS_LIST: &str = "tags.list";
+    pub const TABS_LIST: &str = "tabs.list";
+    pub const EVENTS_POLL: &str = "events.poll";
+    pub const TAB_RENAME: &str = "tab.rename";
+    pub const TAB_NEW: &str = "tab.new";
+    pub const TAB_CLOSE: &str = "tab.close";
+    pub const TAB_FOCUS: &str = "tab.focus";
+    pub const HOST_EXEC: &str = "host.exec";
+    pub const SYSTEM_CHECK_UPDATE: &str = "system.check_update";
+    pub const SYSTEM_READY: &str = "system.ready";
+    pub const SYSTEM_CONFIGURE: &str = "system.configure";
 }
 
 #[cfg(test)]
@@ -129,6 +234,26 @@ mod tests {
     use super::*;
 
     #[test]
+    #[cfg(feature = "v4")]
+    fn test_envelope_accepts_single_and_batch() {
+        let single = serde_json::to_string(&RpcRequest::new(methods::PANES_LIST)).unwrap();
+        let envelope: RpcEnvelope = serde_json::from_str(&single).unwrap();
+        assert!(matches!(envelope, RpcEnvelope::Single(_)));
+
+        let batch = serde_json::to_string(&vec![
+            RpcRequest::new(methods::PANE_FOCUS),
+            RpcRequest::new(methods::PANE_RENAME),
+        ])
+        .unwrap();
+        let requests = serde_json::from_str::<RpcEnvelope>(&batch)
+            .unwrap()
+            .into_requests();
+        let methods: Vec<_> = requests.iter().map(|r| r.method.as_str()).collect();
+        assert_eq!(methods, ["pane.focus", "pane.rename"]);
+    }
+
+    #[test]
+    #[cfg(feature = "v4")]
     fn test_request_serialization() {
         let req = RpcRequest::new("panes.list");
         let json = serde_json::to_string(&req).unwrap();
@@ -138,6 +263,7 @@ mod tests {
         assert_eq!(parsed["v"], 1);
         assert_eq!(parsed["method"], "panes.list");
         assert!(parsed["id"].is_string());
+        assert!(parsed.get("trace_id").is_none());
 
         // Round-trip
         let req2: RpcRequest = serde_json::from_str(&json).unwrap();
@@ -146,6 +272,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "v4")]
     fn test_request_with_params() {
         let req = RpcRequest::new("pane.send")
             .with_params(serde_json::json!({
@@ -161,9 +288,36 @@ mod tests {
         assert_eq!(parsed["params"]["text"], "hello");
     }
 
+    #[test]
+    fn test_request_from_provider() {
+        struct Fixed;
+        impl IdProvider for Fixed {
+            fn next_id(&self) -> Uuid {
+                Uuid::from_u128(7)
+            }
+        }
+
+        let req = RpcRequest::from_provider("panes.list", &Fixed);
+        assert_eq!(req.id, Uuid::from_u128(7));
+        assert_eq!(req.method, "panes.list");
+    }
+
+    #[test]
+    #[cfg(feature = "v4")]
+    fn test_request_trace_id_round_trip() {
+        let req = RpcRequest::new("pane.send")
+            .with_trace_id(Some("4bf92f3577b34da6a3ce929d0e0e4736".to_string()));
+        let json = serde_json::to_string(&req).unwrap();
+        let parsed: RpcRequest = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            parsed.trace_id.as_deref(),
+            Some("4bf92f3577b34da6a3ce929d0e0e4736")
+        );
+    }
+
     #[test]
     fn test_response_success() {
-        let id = Uuid::new_v4();
+        let id = Uuid::from_u128(1);
         let resp = RpcResponse::success(id, serde_json::json!({"count": 5})).unwrap();
 
         assert!(resp.ok);
@@ -179,7 +333,7 @@ mod tests {
 
     #[test]
     fn test_response_error() {
-        let id = Uuid::new_v4();
+        let id = Uuid::from_u128(1);
         let error = RpcError::new(RpcErrorCode::NoMatch, "no panes found");
         let resp = RpcResponse::error(id, error);
 
@@ -194,6 +348,23 @@ mod tests {
         assert_eq!(resp2.error.unwrap().message, "no panes found");
     }
 
+    #[test]
+    fn test_error_hints_serialization() {
+        let error = RpcError::new(RpcErrorCode::NoMatch, "no panes match selector");
+        let json = serde_json::to_value(&error).unwrap();
+        assert!(json.get("hints").is_none());
+
+        let error = error.with_hint("run: zjctl panes ls");
+        let json = serde_json::to_string(&error).unwrap();
+        let error2: RpcError = serde_json::from_str(&json).unwrap();
+        assert_eq!(error2.hints, vec!["run: zjctl panes ls".to_string()]);
+
+        // Errors from plugins without hints still parse
+        let error3: RpcError =
+            serde_json::from_str(r#"{"code":"no_match","message":"none"}"#).unwrap();
+        assert!(error3.hints.is_empty());
+    }
+
     #[test]
     fn test_error_code_serialization() {
         let error = RpcError::new(RpcErrorCode::AmbiguousMatch, "multiple matches");
@@ -13,6 +13,7 @@ pub enum SelectorError {
     InvalidPaneType(String),
     #[error("invalid pane id: {0}")]
     InvalidPaneId(String),
+    #[cfg(feature = "matchers")]
     #[error("invalid regex pattern: {0}")]
     InvalidRegex(#[from] regex::Error),
 }
@@ -25,12 +26,88 @@ pub enum PaneSelector {
     Id { pane_type: PaneType, id: u32 },
     /// Select the currently focused pane: `focused`
     Focused,
-    /// Select by title pattern: `title:/regex/` or `title:substring`
+    /// Select by title pattern (pane name or terminal title): `title:/regex/` or `title:substring`
     Title { pattern: StringPattern },
+    /// Select by user-set pane name only: `name:/regex/` or `name:substring`
+    Name { pattern: StringPattern },
+    /// Select by dynamic terminal title only: `termtitle:/regex/` or `termtitle:substring`
+    TerminalTitle { pattern: StringPattern },
     /// Select by command pattern: `cmd:/regex/` or `cmd:substring`
     Command { pattern: StringPattern },
+    /// Select by working directory, where zjctl knows it: `cwd:/regex/` or `cwd:substring`
+    Cwd { pattern: StringPattern },
+    /// Select the pane bookmarked under a mark (`zjctl mark set`): `mark:1`, or `alias:api`
+    /// for the same registry under its `zjctl alias` name
+    Mark { mark: String },
+    /// Select every pane carrying a tag (`zjctl tag add`): `tag:backend`
+    Tag { tag: String },
+    /// Select the pane launched with `pane launch --token`: `token:VALUE`, matching the
+    /// `ZJCTL_PANE_TOKEN` exported into it
+    Token { token: String },
     /// Select by tab index and pane index within tab: `tab:N:index:M`
     TabIndex { tab: usize, index: usize },
+    /// Select every pane in a tab: `tab:N`
+    Tab { tab: usize },
+    /// Select floating panes: `floating`
+    Floating,
+    /// Select panes matching every predicate: `title:server+tab:2`, `cmd:python&!floating`
+    All { selectors: Vec<PaneSelector> },
+    /// Select panes not matching a predicate: `!floating`, `not:title:/^log/`
+    Not { selector: Box<PaneSelector> },
+    /// Select panes matching any alternative: `cmd:uvicorn,cmd:redis` (binds looser than `+`)
+    Any { selectors: Vec<PaneSelector> },
+}
+
+/// Prefixes that may start a predicate; a `+`/`&` only separates predicates when one follows,
+/// so patterns like `cmd:c++` or `title:/a+b/` stay intact.
+const PREDICATE_PREFIXES: &[&str] = &[
+    "!",
+    "not:",
+    "focused",
+    "floating",
+    "id:",
+    "terminal:",
+    "plugin:",
+    "title:",
+    "name:",
+    "termtitle:",
+    "cmd:",
+    "cwd:",
+    "mark:",
+    "alias:",
+    "tag:",
+    "token:",
+    "tab:",
+    "group:",
+];
+
+/// Deepest nesting of `group:` selectors inside group definitions
+const MAX_GROUP_DEPTH: usize = 8;
+
+/// Split a selector on `+`/`&` separators that are followed by another predicate
+fn split_conjunction(s: &str) -> Vec<&str> {
+    split_before_predicate(s, &['+', '&'])
+}
+
+/// Split a selector on `,` separators that are followed by another predicate
+fn split_union(s: &str) -> Vec<&str> {
+    split_before_predicate(s, &[','])
+}
+
+fn split_before_predicate<'a>(s: &'a str, separators: &[char]) -> Vec<&'a str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        if separators.contains(&c) && i > start {
+            let rest = &s[i + 1..];
+            if PREDICATE_PREFIXES.iter().any(|p| rest.starts_with(p)) {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+        }
+    }
+    parts.push(&s[start..]);
+    parts
 }
 
 /// Pane type discriminator
@@ -54,7 +131,7 @@ impl FromStr for PaneType {
 }
 
 /// String matching pattern - either substring or regex
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "kind", rename_all = "snake_case")]
 pub enum StringPattern {
     /// Substring match (case-insensitive)
@@ -63,6 +140,7 @@ pub enum StringPattern {
     Regex { pattern: String },
 }
 
+#[cfg(feature = "matchers")]
 impl StringPattern {
     /// Test if this pattern matches the given string
     pub fn matches(&self, s: &str) -> Result<bool, regex::Error> {
@@ -78,88 +156,303 @@ impl StringPattern {
     }
 }
 
+impl FromStr for StringPattern {
+    type Err = SelectorError;
+
+    /// `/regex/` or a plain substring, as in `title:` selectors
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_string_pattern(s)
+    }
+}
+
+/// Narrowing for `panes.list` (`params.filter`); a pane is listed when it passes every field
+/// that is set
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaneFilter {
+    /// Only panes in this tab
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tab: Option<usize>,
+    /// Only panes in the focused pane's tab
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub current_tab: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pane_type: Option<PaneType>,
+    /// Matched against the pane name and title, like `title:`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<StringPattern>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<StringPattern>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub floating: Option<bool>,
+    /// Only the focused pane
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub focused: bool,
+}
+
+impl PaneFilter {
+    /// Whether the filter lets every pane through
+    pub fn is_empty(&self) -> bool {
+        *self == PaneFilter::default()
+    }
+}
+
 impl FromStr for PaneSelector {
     type Err = SelectorError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let s = s.trim();
 
-        // focused
-        if s == "focused" {
-            return Ok(PaneSelector::Focused);
+        // a,b
+        let alternatives = split_union(s);
+        if alternatives.len() > 1 {
+            let selectors = alternatives
+                .into_iter()
+                .map(parse_conjunction)
+                .collect::<Result<_, _>>()?;
+            return Ok(PaneSelector::Any { selectors });
         }
+        parse_conjunction(s)
+    }
+}
+
+/// a+b or a&b
+fn parse_conjunction(s: &str) -> Result<PaneSelector, SelectorError> {
+    let parts = split_conjunction(s.trim());
+    if parts.len() > 1 {
+        let selectors = parts
+            .into_iter()
+            .map(parse_predicate)
+            .collect::<Result<_, _>>()?;
+        return Ok(PaneSelector::All { selectors });
+    }
+    parse_predicate(s)
+}
+
+/// Replace every `group:NAME` predicate in `selector` with the selector the group was saved
+/// as (`lookup`), keeping the meaning of the surrounding `+`/`,`: `group:web+floating` with
+/// `web` = `cmd:a,cmd:b` becomes `cmd:a+floating,cmd:b+floating`. Groups cannot be negated.
+pub fn expand_groups(
+    selector: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<String, SelectorError> {
+    expand_groups_at(selector, lookup, 0)
+}
 
-        // terminal:N or plugin:N (shorthand for id:terminal:N / id:plugin:N)
-        {
-            let parts: Vec<&str> = s.splitn(2, ':').collect();
-            if parts.len() == 2 && (parts[0] == "terminal" || parts[0] == "plugin") {
-                let pane_type = PaneType::from_str(parts[0])?;
-                if !parts[1].chars().all(|c| c.is_ascii_digit()) {
-                    return Err(SelectorError::InvalidPaneId(parts[1].to_string()));
+fn expand_groups_at(
+    selector: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    depth: usize,
+) -> Result<String, SelectorError> {
+    if depth > MAX_GROUP_DEPTH {
+        return Err(SelectorError::InvalidFormat(
+            "groups nest too deeply (does a group include itself?)".to_string(),
+        ));
+    }
+    let mut alternatives = Vec::new();
+    for alternative in split_union(selector.trim()) {
+        // Each predicate becomes a list of conjunctions to pick from.
+        let mut combined = vec![String::new()];
+        for part in split_conjunction(alternative.trim()) {
+            let part = part.trim();
+            let options = match part.strip_prefix("group:") {
+                Some(name) => {
+                    let members = lookup(name).ok_or_else(|| {
+                        SelectorError::InvalidFormat(format!("unknown group: {name}"))
+                    })?;
+                    let members = expand_groups_at(&members, lookup, depth + 1)?;
+                    split_union(&members)
+                        .into_iter()
+                        .map(|member| member.trim().to_string())
+                        .collect()
                 }
-                let id: u32 = parts[1]
-                    .parse()
-                    .map_err(|_| SelectorError::InvalidPaneId(parts[1].to_string()))?;
-                return Ok(PaneSelector::Id { pane_type, id });
-            }
+                None if is_negated_group(part) => {
+                    return Err(SelectorError::InvalidFormat(format!(
+                        "{part}: groups cannot be negated"
+                    )));
+                }
+                None => vec![part.to_string()],
+            };
+            combined = combined
+                .iter()
+                .flat_map(|prefix| {
+                    options.iter().map(move |option| {
+                        if prefix.is_empty() {
+                            option.clone()
+                        } else {
+                            format!("{prefix}+{option}")
+                        }
+                    })
+                })
+                .collect();
         }
+        alternatives.extend(combined);
+    }
+    Ok(alternatives.join(","))
+}
 
-        // id:terminal:N or id:plugin:N
-        if let Some(rest) = s.strip_prefix("id:") {
-            let parts: Vec<&str> = rest.splitn(2, ':').collect();
-            if parts.len() != 2 {
-                return Err(SelectorError::InvalidFormat(
-                    "id selector requires format id:terminal:N or id:plugin:N".to_string(),
-                ));
-            }
+fn is_negated_group(part: &str) -> bool {
+    let mut rest = part;
+    while let Some(inner) = rest.strip_prefix('!').or_else(|| rest.strip_prefix("not:")) {
+        rest = inner;
+    }
+    rest != part && rest.starts_with("group:")
+}
+
+/// Parse one predicate of a (possibly composed) selector
+fn parse_predicate(s: &str) -> Result<PaneSelector, SelectorError> {
+    let s = s.trim();
+
+    // !X or not:X
+    if let Some(rest) = s.strip_prefix('!').or_else(|| s.strip_prefix("not:")) {
+        let selector = parse_predicate(rest)?;
+        return Ok(PaneSelector::Not {
+            selector: Box::new(selector),
+        });
+    }
+
+    // focused
+    if s == "focused" {
+        return Ok(PaneSelector::Focused);
+    }
+
+    // floating
+    if s == "floating" {
+        return Ok(PaneSelector::Floating);
+    }
+
+    // terminal:N or plugin:N (shorthand for id:terminal:N / id:plugin:N)
+    {
+        let parts: Vec<&str> = s.splitn(2, ':').collect();
+        if parts.len() == 2 && (parts[0] == "terminal" || parts[0] == "plugin") {
             let pane_type = PaneType::from_str(parts[0])?;
+            if !parts[1].chars().all(|c| c.is_ascii_digit()) {
+                return Err(SelectorError::InvalidPaneId(parts[1].to_string()));
+            }
             let id: u32 = parts[1]
                 .parse()
                 .map_err(|_| SelectorError::InvalidPaneId(parts[1].to_string()))?;
             return Ok(PaneSelector::Id { pane_type, id });
         }
+    }
 
-        // title:/regex/ or title:substring
-        if let Some(rest) = s.strip_prefix("title:") {
-            let pattern = parse_string_pattern(rest)?;
-            return Ok(PaneSelector::Title { pattern });
+    // id:terminal:N or id:plugin:N
+    if let Some(rest) = s.strip_prefix("id:") {
+        let parts: Vec<&str> = rest.splitn(2, ':').collect();
+        if parts.len() != 2 {
+            return Err(SelectorError::InvalidFormat(
+                "id selector requires format id:terminal:N or id:plugin:N".to_string(),
+            ));
         }
+        let pane_type = PaneType::from_str(parts[0])?;
+        let id: u32 = parts[1]
+            .parse()
+            .map_err(|_| SelectorError::InvalidPaneId(parts[1].to_string()))?;
+        return Ok(PaneSelector::Id { pane_type, id });
+    }
 
-        // cmd:/regex/ or cmd:substring
-        if let Some(rest) = s.strip_prefix("cmd:") {
-            let pattern = parse_string_pattern(rest)?;
-            return Ok(PaneSelector::Command { pattern });
+    // title:/regex/ or title:substring
+    if let Some(rest) = s.strip_prefix("title:") {
+        let pattern = parse_string_pattern(rest)?;
+        return Ok(PaneSelector::Title { pattern });
+    }
+
+    // name:/regex/ or name:substring
+    if let Some(rest) = s.strip_prefix("name:") {
+        let pattern = parse_string_pattern(rest)?;
+        return Ok(PaneSelector::Name { pattern });
+    }
+
+    // termtitle:/regex/ or termtitle:substring
+    if let Some(rest) = s.strip_prefix("termtitle:") {
+        let pattern = parse_string_pattern(rest)?;
+        return Ok(PaneSelector::TerminalTitle { pattern });
+    }
+
+    // cmd:/regex/ or cmd:substring
+    if let Some(rest) = s.strip_prefix("cmd:") {
+        let pattern = parse_string_pattern(rest)?;
+        return Ok(PaneSelector::Command { pattern });
+    }
+
+    // cwd:/regex/ or cwd:substring
+    if let Some(rest) = s.strip_prefix("cwd:") {
+        let pattern = parse_string_pattern(rest)?;
+        return Ok(PaneSelector::Cwd { pattern });
+    }
+
+    // mark:NAME or alias:NAME
+    if let Some(mark) = s.strip_prefix("mark:").or_else(|| s.strip_prefix("alias:")) {
+        if mark.is_empty() {
+            return Err(SelectorError::InvalidFormat("empty mark".to_string()));
         }
+        return Ok(PaneSelector::Mark {
+            mark: mark.to_string(),
+        });
+    }
 
-        // tab:N:index:M
-        if let Some(rest) = s.strip_prefix("tab:") {
-            let parts: Vec<&str> = rest.split(':').collect();
-            if parts.len() == 3 && parts[1] == "index" {
-                let tab: usize = parts[0]
-                    .parse()
-                    .map_err(|_| SelectorError::InvalidFormat("invalid tab index".to_string()))?;
-                let index: usize = parts[2]
-                    .parse()
-                    .map_err(|_| SelectorError::InvalidFormat("invalid pane index".to_string()))?;
-                return Ok(PaneSelector::TabIndex { tab, index });
-            }
-            return Err(SelectorError::InvalidFormat(
-                "tab selector requires format tab:N:index:M".to_string(),
-            ));
+    // tag:NAME
+    if let Some(tag) = s.strip_prefix("tag:") {
+        if tag.is_empty() {
+            return Err(SelectorError::InvalidFormat("empty tag".to_string()));
         }
+        return Ok(PaneSelector::Tag {
+            tag: tag.to_string(),
+        });
+    }
+
+    // group:NAME never reaches the plugin; zjctl expands it first
+    if let Some(name) = s.strip_prefix("group:") {
+        return Err(SelectorError::InvalidFormat(format!(
+            "group:{name} must be expanded by zjctl (see `zjctl group ls`)"
+        )));
+    }
+
+    // token:VALUE
+    if let Some(token) = s.strip_prefix("token:") {
+        if token.is_empty() {
+            return Err(SelectorError::InvalidFormat("empty token".to_string()));
+        }
+        return Ok(PaneSelector::Token {
+            token: token.to_string(),
+        });
+    }
 
-        Err(SelectorError::InvalidFormat(format!(
-            "unknown selector format: {}",
-            s
-        )))
+    // tab:N:index:M or tab:N
+    if let Some(rest) = s.strip_prefix("tab:") {
+        let parts: Vec<&str> = rest.split(':').collect();
+        if let [tab] = parts.as_slice() {
+            let tab: usize = tab
+                .parse()
+                .map_err(|_| SelectorError::InvalidFormat("invalid tab index".to_string()))?;
+            return Ok(PaneSelector::Tab { tab });
+        }
+        if parts.len() == 3 && parts[1] == "index" {
+            let tab: usize = parts[0]
+                .parse()
+                .map_err(|_| SelectorError::InvalidFormat("invalid tab index".to_string()))?;
+            let index: usize = parts[2]
+                .parse()
+                .map_err(|_| SelectorError::InvalidFormat("invalid pane index".to_string()))?;
+            return Ok(PaneSelector::TabIndex { tab, index });
+        }
+        return Err(SelectorError::InvalidFormat(
+            "tab selector requires format tab:N or tab:N:index:M".to_string(),
+        ));
     }
+
+    Err(SelectorError::InvalidFormat(format!(
+        "unknown selector format: {}",
+        s
+    )))
 }
 
 /// Parse a string pattern - /regex/ or plain substring
 fn parse_string_pattern(s: &str) -> Result<StringPattern, SelectorError> {
     if s.starts_with('/') && s.ends_with('/') && s.len() > 2 {
         let pattern = &s[1..s.len() - 1];
-        // Validate the regex
+        // Validate the regex; without `matchers` it is left to whoever does the matching.
+        #[cfg(feature = "matchers")]
         regex::Regex::new(pattern)?;
         Ok(StringPattern::Regex {
             pattern: pattern.to_string(),
@@ -175,6 +468,27 @@ fn parse_string_pattern(s: &str) -> Result<StringPattern, SelectorError> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn pane_filter_serializes_only_set_fields() {
+        let filter = PaneFilter {
+            current_tab: true,
+            title: Some("/^log/".parse().unwrap()),
+            ..PaneFilter::default()
+        };
+        let json = serde_json::to_value(&filter).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "current_tab": true,
+                "title": {"kind": "regex", "pattern": "^log"},
+            })
+        );
+        let back: PaneFilter = serde_json::from_value(json).unwrap();
+        assert_eq!(back, filter);
+        assert!(!back.is_empty());
+        assert!(serde_json::from_str::<PaneFilter>("{}").unwrap().is_empty());
+    }
+
     #[test]
     fn test_parse_focused() {
         let sel: PaneSelector = "focused".parse().unwrap();
@@ -275,6 +589,83 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_name_and_termtitle() {
+        let sel: PaneSelector = "name:api".parse().unwrap();
+        assert!(matches!(
+            sel,
+            PaneSelector::Name {
+                pattern: StringPattern::Substring { .. }
+            }
+        ));
+
+        let sel: PaneSelector = "termtitle:/^vim/".parse().unwrap();
+        assert!(matches!(
+            sel,
+            PaneSelector::TerminalTitle {
+                pattern: StringPattern::Regex { .. }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_parse_cwd() {
+        let sel: PaneSelector = "cwd:zjctl".parse().unwrap();
+        assert!(matches!(
+            sel,
+            PaneSelector::Cwd {
+                pattern: StringPattern::Substring { .. }
+            }
+        ));
+
+        let sel: PaneSelector = "cwd:/projects/api$/+!floating".parse().unwrap();
+        let PaneSelector::All { selectors } = sel else {
+            panic!("expected All selector");
+        };
+        assert!(matches!(
+            selectors[0],
+            PaneSelector::Cwd {
+                pattern: StringPattern::Regex { .. }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_parse_mark() {
+        let sel: PaneSelector = "mark:1".parse().unwrap();
+        assert!(matches!(sel, PaneSelector::Mark { mark } if mark == "1"));
+        let sel: PaneSelector = "mark:api+!floating".parse().unwrap();
+        let PaneSelector::All { selectors } = sel else {
+            panic!("expected All selector");
+        };
+        assert!(matches!(&selectors[0], PaneSelector::Mark { mark } if mark == "api"));
+        assert!("mark:".parse::<PaneSelector>().is_err());
+        let sel: PaneSelector = "alias:api".parse().unwrap();
+        assert!(matches!(sel, PaneSelector::Mark { mark } if mark == "api"));
+    }
+
+    #[test]
+    fn test_parse_tag() {
+        let sel: PaneSelector = "tag:backend,tag:logs".parse().unwrap();
+        let PaneSelector::Any { selectors } = sel else {
+            panic!("expected Any selector");
+        };
+        assert!(matches!(&selectors[0], PaneSelector::Tag { tag } if tag == "backend"));
+        assert!(matches!(&selectors[1], PaneSelector::Tag { tag } if tag == "logs"));
+        assert!("tag:".parse::<PaneSelector>().is_err());
+        assert!(matches!(
+            "tab:2".parse::<PaneSelector>().unwrap(),
+            PaneSelector::Tab { tab: 2 }
+        ));
+    }
+
+    #[test]
+    fn test_parse_token() {
+        let sel: PaneSelector = "token:3f9a2c1b7e4d".parse().unwrap();
+        assert!(matches!(sel, PaneSelector::Token { token } if token == "3f9a2c1b7e4d"));
+        assert!("token:".parse::<PaneSelector>().is_err());
+    }
+
     #[test]
     fn test_parse_cmd_substring() {
         let sel: PaneSelector = "cmd:cargo".parse().unwrap();
@@ -299,6 +690,128 @@ mod tests {
     }
 
     #[test]
+    fn test_parse_tab_and_floating() {
+        assert!(matches!(
+            "tab:2".parse::<PaneSelector>().unwrap(),
+            PaneSelector::Tab { tab: 2 }
+        ));
+        assert!(matches!(
+            "floating".parse::<PaneSelector>().unwrap(),
+            PaneSelector::Floating
+        ));
+        assert!("tab:x".parse::<PaneSelector>().is_err());
+    }
+
+    #[test]
+    fn test_parse_conjunction() {
+        let sel: PaneSelector = "title:server+tab:2".parse().unwrap();
+        match sel {
+            PaneSelector::All { selectors } => {
+                assert_eq!(selectors.len(), 2);
+                assert!(matches!(selectors[0], PaneSelector::Title { .. }));
+                assert!(matches!(selectors[1], PaneSelector::Tab { tab: 2 }));
+            }
+            _ => panic!("expected All selector"),
+        }
+
+        let sel: PaneSelector = "cmd:python&!floating".parse().unwrap();
+        match sel {
+            PaneSelector::All { selectors } => {
+                assert!(matches!(selectors[0], PaneSelector::Command { .. }));
+                match &selectors[1] {
+                    PaneSelector::Not { selector } => {
+                        assert!(matches!(**selector, PaneSelector::Floating))
+                    }
+                    _ => panic!("expected Not selector"),
+                }
+            }
+            _ => panic!("expected All selector"),
+        }
+    }
+
+    #[test]
+    fn test_parse_negation() {
+        let sel: PaneSelector = "not:title:/^log/".parse().unwrap();
+        match sel {
+            PaneSelector::Not { selector } => assert!(matches!(
+                *selector,
+                PaneSelector::Title {
+                    pattern: StringPattern::Regex { .. }
+                }
+            )),
+            _ => panic!("expected Not selector"),
+        }
+    }
+
+    #[test]
+    fn test_conjunction_keeps_separators_inside_patterns() {
+        let sel: PaneSelector = "cmd:g++".parse().unwrap();
+        match sel {
+            PaneSelector::Command {
+                pattern: StringPattern::Substring { value },
+            } => assert_eq!(value, "g++"),
+            _ => panic!("expected Command selector"),
+        }
+
+        let sel: PaneSelector = "title:/a+b/&tab:1".parse().unwrap();
+        match sel {
+            PaneSelector::All { selectors } => match &selectors[0] {
+                PaneSelector::Title {
+                    pattern: StringPattern::Regex { pattern },
+                } => assert_eq!(pattern, "a+b"),
+                _ => panic!("expected Title selector"),
+            },
+            _ => panic!("expected All selector"),
+        }
+        assert_eq!(
+            split_conjunction("title:rock&roll"),
+            vec!["title:rock&roll"]
+        );
+    }
+
+    #[test]
+    fn test_parse_union_binds_looser_than_conjunction() {
+        let sel: PaneSelector = "cmd:uvicorn,cmd:redis+tab:1".parse().unwrap();
+        match sel {
+            PaneSelector::Any { selectors } => {
+                assert!(matches!(selectors[0], PaneSelector::Command { .. }));
+                assert!(
+                    matches!(&selectors[1], PaneSelector::All { selectors } if selectors.len() == 2)
+                );
+            }
+            _ => panic!("expected Any selector"),
+        }
+        assert_eq!(split_union("title:/a{1,2}/"), vec!["title:/a{1,2}/"]);
+        assert!("group:web".parse::<PaneSelector>().is_err());
+    }
+
+    #[test]
+    fn test_expand_groups() {
+        let lookup = |name: &str| match name {
+            "web" => Some("cmd:uvicorn,cmd:redis".to_string()),
+            "all" => Some("group:web,title:db".to_string()),
+            "loop" => Some("group:loop".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            expand_groups("group:web", &lookup).unwrap(),
+            "cmd:uvicorn,cmd:redis"
+        );
+        assert_eq!(
+            expand_groups("group:web+floating,focused", &lookup).unwrap(),
+            "cmd:uvicorn+floating,cmd:redis+floating,focused"
+        );
+        assert_eq!(
+            expand_groups("tab:1&group:all", &lookup).unwrap(),
+            "tab:1+cmd:uvicorn,tab:1+cmd:redis,tab:1+title:db"
+        );
+        assert!(expand_groups("group:nope", &lookup).is_err());
+        assert!(expand_groups("!group:web", &lookup).is_err());
+        assert!(expand_groups("group:loop", &lookup).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "matchers")]
     fn test_pattern_matching() {
         let substr = StringPattern::Substring {
             value: "vim".to_string(),
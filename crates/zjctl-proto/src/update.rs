@@ -0,0 +1,67 @@
+//! Plugin version vs. latest release (`system.check_update`)
+
+use serde::{Deserialize, Serialize};
+
+/// Result of `system.check_update`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpdateCheck {
+    /// Version of the running plugin
+    pub current: String,
+    /// Latest released version (without the leading `v`)
+    pub latest: String,
+    pub update_available: bool,
+    /// Release page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+impl UpdateCheck {
+    pub fn new(current: &str, latest_tag: &str, url: Option<String>) -> Self {
+        let latest = latest_tag.trim_start_matches('v').to_string();
+        Self {
+            current: current.to_string(),
+            update_available: is_newer(&latest, current),
+            latest,
+            url,
+        }
+    }
+}
+
+/// Whether `latest` is a newer `major.minor.patch` than `current` (pre-release suffixes ignored)
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    match (parse_version(latest), parse_version(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let core = version.trim_start_matches('v').split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+    let major = parts.next()??;
+    let minor = parts.next().unwrap_or(Some(0))?;
+    let patch = parts.next().unwrap_or(Some(0))?;
+    Some((major, minor, patch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_newer_compares_numerically() {
+        assert!(is_newer("0.1.10", "0.1.9"));
+        assert!(is_newer("v1.0.0", "0.9.9"));
+        assert!(!is_newer("0.1.3", "0.1.3"));
+        assert!(!is_newer("0.1.2", "0.1.3"));
+        assert!(!is_newer("0.2.0-rc.1", "0.2.0"));
+        assert!(!is_newer("nightly", "0.1.3"));
+    }
+
+    #[test]
+    fn update_check_strips_tag_prefix() {
+        let check = UpdateCheck::new("0.1.3", "v0.2.0", None);
+        assert_eq!(check.latest, "0.2.0");
+        assert!(check.update_available);
+    }
+}
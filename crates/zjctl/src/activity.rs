@@ -0,0 +1,128 @@
+//! What counts as a change when polling a pane's screen (`pane wait-idle`, `pane on-change`)
+//!
+//! TUIs keep redrawing without doing anything: a blinking cursor, a spinner, a clock in a
+//! status bar. Comparing raw dumps makes such panes look busy forever, so screens are compared
+//! after masking: trailing whitespace (where only the cursor moved), spinner and cursor glyphs,
+//! and clock-like times are ignored, as are rows picked with `--ignore-rows`. `--exact`
+//! compares the dumps byte for byte again (rows are still skipped).
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangeMask {
+    /// Rows left out of the comparison, from the top (0) or the bottom (-1 is the last row)
+    pub ignore_rows: Vec<i64>,
+    /// Only skip `ignore_rows`; compare everything else as is
+    pub exact: bool,
+}
+
+impl ChangeMask {
+    /// The part of `screen` that counts as activity
+    pub fn normalize(&self, screen: &str) -> String {
+        let lines: Vec<&str> = screen.lines().collect();
+        let count = lines.len() as i64;
+        let kept = lines.iter().enumerate().filter(|(row, _)| {
+            !self.ignore_rows.iter().any(|&ignored| {
+                let ignored = if ignored < 0 {
+                    count + ignored
+                } else {
+                    ignored
+                };
+                ignored == *row as i64
+            })
+        });
+        if self.exact {
+            return kept.map(|(_, line)| *line).collect::<Vec<_>>().join("\n");
+        }
+        let mut masked: Vec<String> = kept.map(|(_, line)| mask_line(line)).collect();
+        while masked.last().is_some_and(String::is_empty) {
+            masked.pop();
+        }
+        masked.join("\n")
+    }
+
+    /// Hash of [`ChangeMask::normalize`]; equal fingerprints mean "nothing happened"
+    pub fn fingerprint(&self, screen: &[u8]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.normalize(&String::from_utf8_lossy(screen))
+            .hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Spinner frames that hardly appear in ordinary output (braille dots, rotating circles)
+fn is_spinner_glyph(c: char) -> bool {
+    ('\u{2800}'..='\u{28FF}').contains(&c) || "◐◓◑◒◴◷◶◵◜◝◞◟".contains(c)
+}
+
+/// Spinner frames and cursor blocks that only count when they stand alone
+fn is_standalone_glyph(c: char) -> bool {
+    "|/-\\_█▌▋▍▎▏".contains(c)
+}
+
+fn clock() -> &'static Regex {
+    static CLOCK: OnceLock<Regex> = OnceLock::new();
+    CLOCK.get_or_init(|| {
+        Regex::new(r"\b\d{1,2}:\d{2}(:\d{2})?(\.\d+)?\b").expect("valid clock regex")
+    })
+}
+
+fn mask_line(line: &str) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let blank = |i: Option<usize>| {
+        i.and_then(|i| chars.get(i))
+            .is_none_or(|c| c.is_whitespace())
+    };
+    let masked: String = chars
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| {
+            let alone = blank(i.checked_sub(1)) && blank(Some(i + 1));
+            if is_spinner_glyph(c) || (alone && is_standalone_glyph(c)) {
+                ' '
+            } else {
+                c
+            }
+        })
+        .collect();
+    clock().replace_all(&masked, "00:00").trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_spinners_clocks_and_ignored_rows_do_not_count() {
+        let mask = ChangeMask::default();
+        assert_eq!(mask.normalize("$ ls █\n\n"), mask.normalize("$ ls   \n"));
+        assert_eq!(
+            mask.normalize("⠋ building 12:00:01"),
+            mask.normalize("⠙ building 12:00:02")
+        );
+        assert_eq!(mask.normalize("waiting |"), mask.normalize("waiting /"));
+        assert_ne!(mask.normalize("a-b"), mask.normalize("a/b"));
+        assert_ne!(mask.normalize("$ ls"), mask.normalize("$ ls\nfile"));
+
+        let status_bar = ChangeMask {
+            ignore_rows: vec![-1],
+            exact: true,
+        };
+        assert_eq!(
+            status_bar.fingerprint(b"out\nCPU 3%"),
+            status_bar.fingerprint(b"out\nCPU 4%")
+        );
+        assert_ne!(
+            ChangeMask {
+                exact: true,
+                ..ChangeMask::default()
+            }
+            .normalize("$ █"),
+            "$"
+        );
+    }
+}
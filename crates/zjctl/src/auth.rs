@@ -0,0 +1,346 @@
+//! Token authentication for `zjctl daemon --auth-tokens`
+//!
+//! Each token has a name and a scope: `read` (listings, history, events), `send` (input,
+//! focus, layout changes), or `destructive` (closing panes and tabs, host commands, stopping
+//! the daemon). A scope includes the ones before it. Only SHA-256 hashes are written to the
+//! tokens file (`~/.config/zjctl/tokens`, one `name scope sha256:HEX` per line), so the file
+//! does not hand out working tokens. Clients pass their token in `ZJCTL_TOKEN`; the metrics
+//! endpoint takes it as `Authorization: Bearer TOKEN`.
+//!
+//! This authenticates callers of the daemon's socket and metrics endpoint only, not the
+//! session: anything that can run `zellij pipe` as the same user (zjctl included, with
+//! `ZJCTL_NO_DAEMON=1`) reaches the plugin without a token. There is no TLS listener, so the
+//! metrics token travels in cleartext and mTLS is not offered; the daemon binds metrics to
+//! loopback unless `--metrics-allow-remote` is given. Pane control itself has no network
+//! listener at all: the socket is 0600, so tokens only narrow what local processes of the same
+//! user may do.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use sha2::{Digest, Sha256};
+use zjctl_proto::methods;
+
+use crate::paths;
+
+/// Prefix of generated tokens, so they are easy to spot in logs and secret scanners
+const TOKEN_PREFIX: &str = "zjt_";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    Read,
+    Send,
+    Destructive,
+}
+
+impl Scope {
+    /// Scope needed to call `method`; methods not listed here need `destructive`
+    pub fn for_method(method: &str) -> Self {
+        match method {
+            methods::PANES_LIST
+            | methods::PANES_HISTORY
+            | methods::TABS_LIST
+            | methods::PANE_HISTORY
+            | methods::MARKS_LIST
+            | methods::TAGS_LIST
+            | methods::EVENTS_POLL
+            | methods::SYSTEM_READY
+            | methods::SYSTEM_CHECK_UPDATE
+            | methods::SYSTEM_CONFIGURE => Scope::Read,
+            methods::PANE_SEND
+            | methods::PANE_SEND_KEYS
+            | methods::PANE_BROADCAST
+            | methods::PANE_FOCUS
+            | methods::PANE_FOCUS_PREVIOUS
+            | methods::PANE_RENAME
+            | methods::PANE_RESIZE
+            | methods::PANE_SCROLL
+            | methods::PANE_RERUN
+            | methods::PANE_LAUNCH
+            | methods::PANE_SET_CWD
+            | methods::PANE_SET_TOKEN
+            | methods::PANE_TOGGLE_FLOAT
+            | methods::PANE_TOGGLE_FULLSCREEN
+            | methods::PANE_PIN
+            | methods::PANE_MOVE
+            | methods::PANE_CLEAR
+            | methods::MARK_SET
+            | methods::MARK_REMOVE
+            | methods::TAG_ADD
+            | methods::TAG_REMOVE
+            | methods::TAB_RENAME
+            | methods::TAB_NEW
+            | methods::TAB_FOCUS => Scope::Send,
+            _ => Scope::Destructive,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::Send => "send",
+            Scope::Destructive => "destructive",
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "read" => Ok(Scope::Read),
+            "send" => Ok(Scope::Send),
+            "destructive" => Ok(Scope::Destructive),
+            other => Err(format!(
+                "unknown scope: {other} (expected read, send, or destructive)"
+            )),
+        }
+    }
+}
+
+/// One line of the tokens file
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct TokenEntry {
+    pub name: String,
+    pub scope: Scope,
+    #[serde(skip)]
+    hash: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TokenStore {
+    pub entries: Vec<TokenEntry>,
+}
+
+impl TokenStore {
+    /// A missing file is an empty store
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => {
+                Self::parse(&contents).map_err(|err| format!("{}: {err}", path.display()))
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(format!("failed to read {}: {err}", path.display())),
+        }
+    }
+
+    /// `name scope sha256:HEX` lines; blank lines and lines starting with `#` are skipped
+    fn parse(contents: &str) -> Result<Self, String> {
+        let entries = contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(|(i, line)| {
+                let expected = || format!("line {}: expected `name scope sha256:HEX`", i + 1);
+                let [name, scope, hash] = line.split_whitespace().collect::<Vec<_>>()[..] else {
+                    return Err(expected());
+                };
+                let hash = hash.strip_prefix("sha256:").ok_or_else(expected)?;
+                if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    return Err(expected());
+                }
+                Ok(TokenEntry {
+                    name: name.to_string(),
+                    scope: scope
+                        .parse()
+                        .map_err(|err| format!("line {}: {err}", i + 1))?,
+                    hash: hash.to_ascii_lowercase(),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { entries })
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::from("# zjctl daemon tokens: name scope sha256:HEX\n");
+        for entry in &self.entries {
+            out.push_str(&format!(
+                "{} {} sha256:{}\n",
+                entry.name, entry.scope, entry.hash
+            ));
+        }
+        out
+    }
+
+    /// Write the store with owner-only permissions. The contents go to a file created 0600 and
+    /// renamed over `path`, so they are never readable by others, not even for a moment or
+    /// through an older file's looser mode.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        use std::io::Write;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|err| format!("failed to create {}: {err}", parent.display()))?;
+        }
+        let tmp = path.with_extension("tmp");
+        let _ = std::fs::remove_file(&tmp);
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let written = options
+            .open(&tmp)
+            .and_then(|mut file| file.write_all(self.render().as_bytes()))
+            .and_then(|()| std::fs::rename(&tmp, path));
+        if let Err(err) = written {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(format!("failed to write {}: {err}", path.display()));
+        }
+        Ok(())
+    }
+
+    /// Add a token named `name` (replacing one with that name) and return its secret
+    pub fn issue(&mut self, name: &str, scope: Scope) -> String {
+        let token = generate();
+        self.entries.retain(|entry| entry.name != name);
+        self.entries.push(TokenEntry {
+            name: name.to_string(),
+            scope,
+            hash: hash_token(&token),
+        });
+        token
+    }
+
+    pub fn revoke(&mut self, name: &str) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|entry| entry.name != name);
+        self.entries.len() != before
+    }
+
+    /// Entry for `token`, if it is known
+    pub fn authenticate(&self, token: &str) -> Option<&TokenEntry> {
+        let hash = hash_token(token.trim());
+        self.entries.iter().find(|entry| entry.hash == hash)
+    }
+}
+
+/// A tokens file as the daemon sees it, re-read whenever it changes so `daemon token rm`
+/// takes effect without a restart
+#[derive(Debug)]
+pub struct TokenFile {
+    pub path: PathBuf,
+    modified: Option<SystemTime>,
+    store: TokenStore,
+}
+
+impl TokenFile {
+    pub fn open(path: PathBuf) -> Result<Self, String> {
+        if !path.is_file() {
+            return Err(format!(
+                "no tokens file at {} (create a token with: zjctl daemon token new NAME)",
+                path.display()
+            ));
+        }
+        let store = TokenStore::load(&path)?;
+        Ok(Self {
+            modified: modified(&path),
+            path,
+            store,
+        })
+    }
+
+    /// The current tokens; a file that became unreadable or invalid accepts none
+    pub fn current(&mut self) -> &TokenStore {
+        let modified = modified(&self.path);
+        if modified != self.modified {
+            self.modified = modified;
+            self.store = TokenStore::load(&self.path).unwrap_or_default();
+        }
+        &self.store
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+/// `~/.config/zjctl/tokens`
+pub fn default_tokens_path() -> Option<PathBuf> {
+    paths::config_dir().map(|dir| dir.join("zjctl").join("tokens"))
+}
+
+/// Token to present to a daemon (`ZJCTL_TOKEN`)
+pub fn client_token() -> Option<String> {
+    std::env::var("ZJCTL_TOKEN")
+        .ok()
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty())
+}
+
+fn generate() -> String {
+    format!(
+        "{TOKEN_PREFIX}{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn issued_tokens_round_trip_through_the_file_as_hashes() {
+        let mut store = TokenStore::default();
+        let token = store.issue("ci", Scope::Send);
+        assert!(token.starts_with(TOKEN_PREFIX));
+        let contents = store.render();
+        assert!(!contents.contains(&token));
+
+        let loaded = TokenStore::parse(&contents).unwrap();
+        let entry = loaded.authenticate(&token).unwrap();
+        assert_eq!((entry.name.as_str(), entry.scope), ("ci", Scope::Send));
+        assert!(loaded.authenticate("zjt_wrong").is_none());
+        assert!(TokenStore::parse("ci send deadbeef").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn saved_store_is_owner_only_even_over_a_loose_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("zjctl-tokens-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        let mut store = TokenStore::default();
+        let token = store.issue("ci", Scope::Read);
+        store.save(&path).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        let loaded = TokenStore::parse(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(loaded.authenticate(&token).is_some());
+    }
+
+    #[test]
+    fn scopes_are_ordered_by_method_risk() {
+        assert_eq!(Scope::for_method(methods::PANES_LIST), Scope::Read);
+        assert_eq!(Scope::for_method(methods::PANE_SEND), Scope::Send);
+        assert_eq!(Scope::for_method(methods::PANE_CLOSE), Scope::Destructive);
+        assert_eq!(Scope::for_method("future.method"), Scope::Destructive);
+        assert!(Scope::Destructive >= Scope::Send && Scope::Send >= Scope::Read);
+    }
+}
@@ -1,11 +1,21 @@
 //! RPC client for communicating with zrpc plugin via Zellij pipes.
 
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 use thiserror::Error;
-use zjctl_proto::{RpcRequest, RpcResponse};
-
+use zjctl_proto::{methods, RpcError, RpcErrorCode, RpcRequest, RpcResponse};
+
+use crate::auth::Scope;
+use crate::commands::install;
+use crate::daemon;
+use crate::groups;
+use crate::interrupt::{self, Interrupted};
+use crate::outcome::Failures;
+use crate::paths;
+use crate::telemetry;
 use crate::zellij;
 
 #[derive(Debug, Error)]
@@ -28,68 +38,322 @@ pub enum ClientError {
         launch_cmd: String,
     },
     #[error(
-        "no response from zrpc plugin\n\nMake sure it is loaded in your Zellij session:\n  {launch_cmd}\n  # or add to config.kdl load_plugins\n\nIf prompted, accept ReadCliPipes permissions.\nRun `zjctl doctor` for more checks."
+        "no response from zrpc plugin\n\nMake sure it is loaded in your Zellij session:\n  {launch_cmd}\n  # or add to config.kdl load_plugins\n\nIf prompted, accept ReadCliPipes permissions.\nPass --auto-start (or set ZJCTL_AUTO_START=1) to launch it when needed.\nRun `zjctl doctor` for more checks."
     )]
     PluginNotLoaded { launch_cmd: String },
     #[error(
         "zellij pipe exited with error{exit_note}\n{stderr}\n\nRun `zjctl doctor` for more checks."
     )]
     PipeError { exit_note: String, stderr: String },
-    #[error("RPC error: {0}")]
-    RpcError(String),
+    #[error(
+        "no response from zrpc plugin within {timeout:?} (--timeout)\n\nThe plugin may be stuck; reload it with:\n  zjctl install --load\n\nRun `zjctl doctor` for more checks."
+    )]
+    Timeout { timeout: Duration },
+    #[error("zjctl daemon: {0}\n\nStop the daemon or set ZJCTL_NO_DAEMON=1 to bypass it.")]
+    Daemon(String),
+    #[error("RPC error: {}{}", .0.message, format_hints(&.0.hints))]
+    RpcError(RpcError),
+    /// A selector resolved on the CLI side matched no panes or too many
+    #[error("{message}")]
+    Match { code: RpcErrorCode, message: String },
+}
+
+impl ClientError {
+    pub fn no_match(message: impl Into<String>) -> Self {
+        ClientError::Match {
+            code: RpcErrorCode::NoMatch,
+            message: message.into(),
+        }
+    }
+
+    pub fn ambiguous(message: impl Into<String>) -> Self {
+        ClientError::Match {
+            code: RpcErrorCode::AmbiguousMatch,
+            message: message.into(),
+        }
+    }
+
+    fn code(&self) -> Option<RpcErrorCode> {
+        match self {
+            ClientError::RpcError(err) => Some(err.code),
+            ClientError::Match { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// Stable, snake_case name of the failure for `--json-errors`
+    pub fn kind(&self) -> &'static str {
+        if let Some(code) = self.code() {
+            return match code {
+                RpcErrorCode::InvalidRequest => "invalid_request",
+                RpcErrorCode::MethodNotFound => "method_not_found",
+                RpcErrorCode::InvalidParams => "invalid_params",
+                RpcErrorCode::NoMatch => "no_match",
+                RpcErrorCode::AmbiguousMatch => "ambiguous_match",
+                RpcErrorCode::Internal => "internal",
+                RpcErrorCode::ScrollMode => "scroll_mode",
+                RpcErrorCode::Unauthorized => "unauthorized",
+                RpcErrorCode::PolicyDenied => "policy_denied",
+            };
+        }
+        match self {
+            ClientError::ZellijMissing => "zellij_missing",
+            ClientError::Spawn(_) => "spawn_failed",
+            ClientError::Io(_) => "io",
+            ClientError::Serialize(_) => "serialize",
+            ClientError::PluginNotInstalled { .. } => "plugin_not_installed",
+            ClientError::PluginNotLoaded { .. } => "plugin_not_loaded",
+            ClientError::PipeError { .. } => "pipe_error",
+            ClientError::Timeout { .. } => "timeout",
+            ClientError::Daemon(_) => "daemon",
+            ClientError::RpcError(_) | ClientError::Match { .. } => "error",
+        }
+    }
+
+    /// Process exit status (listed under "Exit codes" in `zjctl --help`)
+    pub fn exit_code(&self) -> i32 {
+        if let Some(code) = self.code() {
+            return match code {
+                RpcErrorCode::NoMatch => 3,
+                RpcErrorCode::AmbiguousMatch => 4,
+                RpcErrorCode::MethodNotFound => 5,
+                RpcErrorCode::InvalidParams => 6,
+                RpcErrorCode::InvalidRequest => 7,
+                RpcErrorCode::Internal => 8,
+                RpcErrorCode::ScrollMode => 9,
+                RpcErrorCode::Unauthorized => 14,
+                RpcErrorCode::PolicyDenied => 15,
+            };
+        }
+        match self {
+            ClientError::ZellijMissing => 10,
+            ClientError::PluginNotInstalled { .. } => 11,
+            ClientError::PluginNotLoaded { .. } => 12,
+            ClientError::PipeError { .. } | ClientError::Spawn(_) | ClientError::Daemon(_) => 13,
+            ClientError::Timeout { .. } => 17,
+            _ => 1,
+        }
+    }
+}
+
+/// Exit status for a failed command: the mapped code for client and RPC errors, 16 when
+/// items of a multi-item operation failed, 130 after Ctrl-C, else 1
+pub fn exit_code(err: &(dyn std::error::Error + 'static)) -> i32 {
+    if err.is::<Failures>() {
+        return 16;
+    }
+    if err.is::<Interrupted>() {
+        return interrupt::EXIT_CODE;
+    }
+    err.downcast_ref::<ClientError>()
+        .map_or(1, ClientError::exit_code)
+}
+
+/// `{"error": {kind, message, hints, exit_code}}`, printed by `--json-errors`; failed items
+/// of a multi-item operation add `report: {summary, items}`
+pub fn error_json(err: &(dyn std::error::Error + 'static)) -> serde_json::Value {
+    let client = err.downcast_ref::<ClientError>();
+    let (message, hints) = match client {
+        // The Display form appends the hints; keep them separate here.
+        Some(ClientError::RpcError(rpc)) => (rpc.message.clone(), rpc.hints.clone()),
+        _ => (err.to_string(), Vec::new()),
+    };
+    let failures = err.downcast_ref::<Failures>();
+    let kind = match (client, failures) {
+        (Some(client), _) => client.kind(),
+        (None, Some(_)) => "partial_failure",
+        (None, None) if err.is::<Interrupted>() => "interrupted",
+        (None, None) => "error",
+    };
+    let mut json = serde_json::json!({
+        "error": {
+            "kind": kind,
+            "message": message,
+            "hints": hints,
+            "exit_code": exit_code(err),
+        }
+    });
+    if let Some(failures) = failures {
+        json["error"]["report"] = failures.report();
+    }
+    json
+}
+
+fn format_hints(hints: &[String]) -> String {
+    hints
+        .iter()
+        .map(|hint| format!("\n  hint: {hint}"))
+        .collect()
 }
 
 pub fn default_plugin_url() -> String {
-    format!("file:{}", default_plugin_path().display())
+    paths::file_url(&default_plugin_path())
 }
 
 pub const DEFAULT_PLUGIN_DOWNLOAD_URL: &str =
     "https://github.com/mrshu/zjctl/releases/latest/download/zrpc.wasm";
 
-fn pipe_plugin_configuration() -> String {
-    let session = std::env::var("ZELLIJ_SESSION_NAME").unwrap_or_else(|_| "unknown".to_string());
+pub(crate) fn pipe_plugin_configuration() -> String {
+    let session = zellij::session_name().unwrap_or_else(|| "unknown".to_string());
     pipe_plugin_configuration_for(&session)
 }
 
-fn pipe_plugin_configuration_for(session: &str) -> String {
-    let sanitized = session
+/// `ZJCTL_UPDATE_CHECK` opts into calling `system.check_update`; the plugin asks for WebAccess
+/// on the first such call
+pub(crate) fn update_check_enabled() -> bool {
+    std::env::var("ZJCTL_UPDATE_CHECK").is_ok_and(|v| !v.is_empty() && v != "0")
+}
+
+/// Session name reduced to characters safe in plugin configuration and file names
+pub(crate) fn sanitize_session(session: &str) -> String {
+    session
         .chars()
         .map(|c| match c {
             'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
             _ => '_',
         })
-        .collect::<String>();
-    format!("zjctl_session={sanitized}")
+        .collect()
 }
 
-pub fn default_plugin_path() -> PathBuf {
-    let rel = Path::new("zellij").join("plugins").join("zrpc.wasm");
+/// Fixed for the session: Zellij keys plugin instances by URL and configuration, so anything
+/// varying per call would start another zrpc instance with its own marks, tags, and tokens.
+/// Per-call settings go in request params or `system.configure` instead. `host_exec_allow` is
+/// never part of it: whoever can run `zellij pipe` could pick their own allowlist, so the
+/// plugin only takes it from the `load_plugins` entry the user writes in Zellij, which publishes
+/// it to the plugin's `/data` folder for this instance.
+fn pipe_plugin_configuration_for(session: &str) -> String {
+    format!("zjctl_session={}", sanitize_session(session))
+}
+
+/// `system.configure` carrying `ZJCTL_CLIENT_POLL` (`adaptive:200-5000`, `fixed:500`, `off`),
+/// sent ahead of each call while it is set; the plugin falls back to adaptive on nonsense
+fn client_poll_request(client_poll: Option<&str>) -> Option<RpcRequest> {
+    let poll = client_poll.filter(|poll| !poll.is_empty())?;
+    RpcRequest::new(methods::SYSTEM_CONFIGURE)
+        .with_params(serde_json::json!({ "client_poll": poll }))
+        .ok()
+}
 
-    if let Ok(dir) = std::env::var("XDG_CONFIG_HOME") {
-        return PathBuf::from(dir).join(rel);
+pub fn default_plugin_path() -> PathBuf {
+    match paths::config_dir() {
+        Some(dir) => dir.join("zellij").join("plugins").join("zrpc.wasm"),
+        None => PathBuf::from("zrpc.wasm"),
     }
+}
 
-    if cfg!(windows) {
-        if let Ok(dir) = std::env::var("APPDATA") {
-            return PathBuf::from(dir).join(rel);
-        }
-        if let Ok(dir) = std::env::var("USERPROFILE") {
-            return PathBuf::from(dir).join(rel);
-        }
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// Refuse every later call that could change the session (`--read-only`)
+pub fn set_read_only(read_only: bool) {
+    READ_ONLY.store(read_only, Ordering::Relaxed);
+}
+
+pub fn is_read_only() -> bool {
+    READ_ONLY.load(Ordering::Relaxed)
+}
+
+/// `policy_denied` for `what` when running with `--read-only`
+pub fn ensure_writable(what: &str) -> Result<(), ClientError> {
+    if is_read_only() {
+        return Err(ClientError::RpcError(RpcError::new(
+            RpcErrorCode::PolicyDenied,
+            format!("read-only mode: {what} is not allowed"),
+        )));
     }
+    Ok(())
+}
 
-    if let Ok(home) = std::env::var("HOME") {
-        return PathBuf::from(home).join(".config").join(rel);
+static AUTO_START: AtomicBool = AtomicBool::new(false);
+
+/// Launch the plugin when it does not answer, then retry (`--auto-start`)
+pub fn set_auto_start(auto_start: bool) {
+    AUTO_START.store(auto_start, Ordering::Relaxed);
+}
+
+/// `--timeout` in milliseconds; 0 waits as long as the plugin takes
+static TIMEOUT_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Give up on a `zellij pipe` call that gets no answer within `timeout` (`--timeout`)
+pub fn set_timeout(timeout: Option<Duration>) {
+    let ms = timeout.map_or(0, |timeout| (timeout.as_millis() as u64).max(1));
+    TIMEOUT_MS.store(ms, Ordering::Relaxed);
+}
+
+fn timeout() -> Option<Duration> {
+    match TIMEOUT_MS.load(Ordering::Relaxed) {
+        0 => None,
+        ms => Some(Duration::from_millis(ms)),
     }
+}
 
-    PathBuf::from("zrpc.wasm")
+/// `timeout` plus the longest `wait_ms` among `requests`: the plugin holds those calls
+/// (`events.poll`, `system.ready`, `host.exec`) on purpose, so their wait does not count against it
+pub(crate) fn call_budget(requests: &[RpcRequest], timeout: Duration) -> Duration {
+    let held = requests
+        .iter()
+        .filter_map(|request| request.params.get("wait_ms")?.as_u64())
+        .max()
+        .unwrap_or(0);
+    timeout + Duration::from_millis(held)
 }
 
+/// How long `--auto-start` waits for a launched plugin to answer
+const AUTO_START_TIMEOUT: Duration = Duration::from_secs(15);
+/// First delay between retries; doubled after each one up to [`MAX_RETRY_BACKOFF`]
+const RETRY_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(2);
+
 /// Send an RPC request to the zrpc plugin and wait for response
 pub fn call(request: &RpcRequest, plugin_path: Option<&str>) -> Result<RpcResponse, ClientError> {
+    let mut responses = call_batch(std::slice::from_ref(request), plugin_path)?;
+    Ok(responses.remove(0))
+}
+
+/// Send several requests in one pipe round trip; the plugin handles them in order and the
+/// responses are returned in request order
+pub fn call_batch(
+    requests: &[RpcRequest],
+    plugin_path: Option<&str>,
+) -> Result<Vec<RpcResponse>, ClientError> {
+    if let Some(request) = requests
+        .iter()
+        .find(|request| Scope::for_method(&request.method) > Scope::Read)
+    {
+        ensure_writable(&request.method)?;
+    }
+    send_batch(requests, plugin_path)
+}
+
+/// `pane.focus` before a screen dump, and back after it. `--read-only` lets it through because
+/// zjctl makes it itself and hands focus back once the screen is read; requests a caller
+/// writes never get that pass, and a daemon still applies its own policy.
+pub fn capture_focus(plugin_path: Option<&str>, selector: &str) -> Result<(), ClientError> {
+    let request = RpcRequest::new(methods::PANE_FOCUS)
+        .with_params(serde_json::json!({ "selector": selector }))?;
+    let response = send_batch(std::slice::from_ref(&request), plugin_path)?.remove(0);
+    match response.error {
+        Some(err) if !response.ok => Err(ClientError::RpcError(err)),
+        _ => Ok(()),
+    }
+}
+
+/// [`call_batch`] without the `--read-only` check
+fn send_batch(
+    requests: &[RpcRequest],
+    plugin_path: Option<&str>,
+) -> Result<Vec<RpcResponse>, ClientError> {
+    if requests.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut expanded = groups::expand_requests(requests).map_err(ClientError::RpcError)?;
+    let configure = client_poll_request(std::env::var("ZJCTL_CLIENT_POLL").ok().as_deref());
+    let configured = configure.is_some();
+    if let Some(configure) = configure {
+        expanded.to_mut().insert(0, configure);
+    }
+    let requests = &*expanded;
     let default_url = default_plugin_url();
     let plugin_url = plugin_path.unwrap_or(default_url.as_str());
-    let request_json = serde_json::to_string(request)?;
     let plugin_configuration = pipe_plugin_configuration();
 
     if let Some(path) = plugin_file_path(plugin_url) {
@@ -105,6 +369,78 @@ pub fn call(request: &RpcRequest, plugin_path: Option<&str>) -> Result<RpcRespon
         }
     }
 
+    // A running `zjctl daemon` saves spawning a new zellij process for this call.
+    let result = match daemon::call(plugin_url, &plugin_configuration, requests, timeout()) {
+        Some(reply) => reply,
+        None => match pipe_batch(requests, plugin_url, &plugin_configuration) {
+            Err(err @ ClientError::PluginNotLoaded { .. })
+                if AUTO_START.load(Ordering::Relaxed) =>
+            {
+                auto_start(requests, plugin_url, &plugin_configuration, err)
+            }
+            result => result,
+        },
+    };
+    // Older plugins answer `system.configure` with method_not_found; either way it is dropped.
+    result.map(|mut responses| {
+        if configured && !responses.is_empty() {
+            responses.remove(0);
+        }
+        responses
+    })
+}
+
+/// Launch the plugin that did not answer, poll `system.ready` with exponential backoff until
+/// it does, then send `requests` again. They are only resent once the plugin answers, so
+/// nothing runs twice; `err` is returned when the launch fails or the plugin stays silent.
+fn auto_start(
+    requests: &[RpcRequest],
+    plugin_url: &str,
+    plugin_configuration: &str,
+    err: ClientError,
+) -> Result<Vec<RpcResponse>, ClientError> {
+    let launch_url = plugin_launch_url(plugin_url, plugin_file_path(plugin_url).as_deref());
+    eprintln!("zjctl: no response from the zrpc plugin; launching {launch_url}");
+    if let Err(launch_err) = install::launch_plugin(&launch_url) {
+        eprintln!("zjctl: {launch_err}");
+        return Err(err);
+    }
+
+    let deadline = Instant::now() + AUTO_START_TIMEOUT;
+    let mut backoff = RETRY_BACKOFF;
+    let probe = RpcRequest::new(methods::SYSTEM_READY)
+        .with_params(serde_json::json!({ "wait_ms": MAX_RETRY_BACKOFF.as_millis() as u64 }))?;
+    loop {
+        match pipe_batch(
+            std::slice::from_ref(&probe),
+            plugin_url,
+            plugin_configuration,
+        ) {
+            // Plugins from before `system.ready` answer with method_not_found, which still
+            // shows they are running.
+            Ok(_) => return pipe_batch(requests, plugin_url, plugin_configuration),
+            Err(ClientError::PluginNotLoaded { .. }) if Instant::now() + backoff < deadline => {
+                std::thread::sleep(backoff);
+                backoff = (backoff * 2).min(MAX_RETRY_BACKOFF);
+            }
+            Err(ClientError::PluginNotLoaded { .. }) => return Err(err),
+            Err(other) => return Err(other),
+        }
+    }
+}
+
+/// One `zellij pipe` round trip
+fn pipe_batch(
+    requests: &[RpcRequest],
+    plugin_url: &str,
+    plugin_configuration: &str,
+) -> Result<Vec<RpcResponse>, ClientError> {
+    // A single request goes out bare so older plugins without batch support still answer it.
+    let request_json = match requests {
+        [request] => serde_json::to_string(request)?,
+        _ => serde_json::to_string(requests)?,
+    };
+
     // Use zellij pipe to send message to plugin
     // The plugin name in the pipe message will match the payload we send
     let mut child = zellij::command()
@@ -113,7 +449,7 @@ pub fn call(request: &RpcRequest, plugin_path: Option<&str>) -> Result<RpcRespon
             "--plugin",
             plugin_url,
             "--plugin-configuration",
-            plugin_configuration.as_str(),
+            plugin_configuration,
             "--name",
             "zjctl-rpc",
         ])
@@ -131,7 +467,11 @@ pub fn call(request: &RpcRequest, plugin_path: Option<&str>) -> Result<RpcRespon
         writeln!(stdin, "{}", request_json)?;
     }
 
-    let output = child.wait_with_output()?;
+    let output = match timeout() {
+        Some(timeout) => wait_with_deadline(child, call_budget(requests, timeout))?
+            .ok_or(ClientError::Timeout { timeout })?,
+        None => child.wait_with_output()?,
+    };
     let stdout = String::from_utf8_lossy(&output.stdout);
     let stderr = String::from_utf8_lossy(&output.stderr);
 
@@ -151,12 +491,20 @@ pub fn call(request: &RpcRequest, plugin_path: Option<&str>) -> Result<RpcRespon
         });
     }
 
-    for resp in serde_json::Deserializer::from_str(&stdout)
+    let mut received: Vec<RpcResponse> = serde_json::Deserializer::from_str(&stdout)
         .into_iter::<RpcResponse>()
         .flatten()
-    {
-        if resp.id == request.id {
-            return Ok(resp);
+        .collect();
+    let ordered: Option<Vec<RpcResponse>> = requests
+        .iter()
+        .map(|request| {
+            let index = received.iter().position(|resp| resp.id == request.id)?;
+            Some(received.swap_remove(index))
+        })
+        .collect();
+    if let Some(responses) = ordered {
+        if !responses.is_empty() {
+            return Ok(responses);
         }
     }
 
@@ -164,50 +512,82 @@ pub fn call(request: &RpcRequest, plugin_path: Option<&str>) -> Result<RpcRespon
     Err(ClientError::PluginNotLoaded { launch_cmd })
 }
 
+/// `wait_with_output`, but the child is killed once `budget` runs out (`None`)
+fn wait_with_deadline(
+    mut child: std::process::Child,
+    budget: Duration,
+) -> Result<Option<std::process::Output>, ClientError> {
+    // Drained on threads so a chatty child cannot block on a full pipe while we poll.
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let deadline = Instant::now() + budget;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+    Ok(Some(std::process::Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    }))
+}
+
+fn drain(pipe: Option<impl Read + Send + 'static>) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
 /// Helper to create and send a request
 pub fn rpc_call(
     plugin: Option<&str>,
     method: &str,
     params: impl serde::Serialize,
 ) -> Result<serde_json::Value, ClientError> {
-    let request = RpcRequest::new(method).with_params(params)?;
+    let span = telemetry::rpc_span(method);
+    let request = RpcRequest::new(method)
+        .with_params(params)?
+        .with_trace_id(span.trace_id());
 
-    let response = call(&request, plugin)?;
+    let response = call(&request, plugin);
+    span.end(response.as_ref().is_ok_and(|response| response.ok));
+    let response = response?;
 
     if response.ok {
         Ok(response.result.unwrap_or(serde_json::Value::Null))
     } else {
         let err = response
             .error
-            .map(|e| e.message)
-            .unwrap_or_else(|| "unknown error".to_string());
+            .unwrap_or_else(|| RpcError::new(RpcErrorCode::Internal, "unknown error"));
         Err(ClientError::RpcError(err))
     }
 }
 
-pub fn plugin_file_path(plugin_url: &str) -> Option<PathBuf> {
-    if plugin_url.contains("://") && !plugin_url.starts_with("file:") {
-        return None;
+/// Wait up to `timeout` for a freshly loaded plugin to receive its first pane, tab, and client
+/// updates. Plugins from before `system.ready` count as ready.
+pub fn wait_ready(plugin: Option<&str>, timeout: Duration) -> Result<bool, ClientError> {
+    let params = serde_json::json!({ "wait_ms": timeout.as_millis() as u64 });
+    match rpc_call(plugin, methods::SYSTEM_READY, params) {
+        Ok(result) => Ok(result["ready"].as_bool().unwrap_or(false)),
+        Err(ClientError::RpcError(err)) if err.code == RpcErrorCode::MethodNotFound => Ok(true),
+        Err(err) => Err(err),
     }
-    let raw = plugin_url.strip_prefix("file:").unwrap_or(plugin_url);
-    if raw.is_empty() {
-        return None;
-    }
-    Some(expand_tilde(raw))
 }
 
-fn expand_tilde(path: &str) -> PathBuf {
-    if path == "~" {
-        if let Some(home) = std::env::var_os("HOME") {
-            return PathBuf::from(home);
-        }
-    }
-    if let Some(rest) = path.strip_prefix("~/") {
-        if let Some(home) = std::env::var_os("HOME") {
-            return PathBuf::from(home).join(rest);
-        }
-    }
-    PathBuf::from(path)
+pub fn plugin_file_path(plugin_url: &str) -> Option<PathBuf> {
+    paths::file_url_path(plugin_url)
 }
 
 pub(crate) fn plugin_launch_command(plugin_url: &str, plugin_path: Option<&Path>) -> String {
@@ -220,26 +600,52 @@ pub(crate) fn plugin_launch_url(plugin_url: &str, plugin_path: Option<&Path>) ->
         return plugin_url.to_string();
     }
     if let Some(path) = plugin_path {
-        return format!("file:{}", path.display());
+        return paths::file_url(path);
     }
     if plugin_url.starts_with("file:") {
         plugin_url.to_string()
     } else {
-        format!("file:{}", plugin_url)
+        paths::file_url(Path::new(plugin_url))
     }
 }
 
+/// Manual install commands (create dir, download, launch) for the platform's shell; Windows
+/// gets PowerShell equivalents since `mkdir -p` and `curl -o` don't behave there.
 pub(crate) fn plugin_install_commands(
     plugin_url: &str,
     plugin_path: &Path,
+) -> (String, String, String) {
+    install_commands_for(cfg!(windows), plugin_url, plugin_path)
+}
+
+fn install_commands_for(
+    powershell: bool,
+    plugin_url: &str,
+    plugin_path: &Path,
 ) -> (String, String, String) {
     let dir = plugin_path.parent().unwrap_or_else(|| Path::new("."));
-    let install_cmd = format!("mkdir -p \"{}\"", dir.display());
-    let download_cmd = format!(
-        "curl -L {} -o \"{}\"",
-        DEFAULT_PLUGIN_DOWNLOAD_URL,
-        plugin_path.display()
-    );
+    let (install_cmd, download_cmd) = if powershell {
+        (
+            format!(
+                "New-Item -ItemType Directory -Force -Path \"{}\" | Out-Null",
+                dir.display()
+            ),
+            format!(
+                "Invoke-WebRequest -Uri {} -OutFile \"{}\"",
+                DEFAULT_PLUGIN_DOWNLOAD_URL,
+                plugin_path.display()
+            ),
+        )
+    } else {
+        (
+            format!("mkdir -p \"{}\"", dir.display()),
+            format!(
+                "curl -L {} -o \"{}\"",
+                DEFAULT_PLUGIN_DOWNLOAD_URL,
+                plugin_path.display()
+            ),
+        )
+    };
     let launch_cmd = plugin_launch_command(plugin_url, Some(plugin_path));
     (install_cmd, download_cmd, launch_cmd)
 }
@@ -328,9 +734,120 @@ mod tests {
         assert!(launch_cmd.contains("zellij action launch-plugin"));
     }
 
+    #[test]
+    fn powershell_install_commands_avoid_posix_tools() {
+        let path = Path::new(r"C:\Users\me\AppData\Roaming\zellij\plugins\zrpc.wasm");
+        let (install_cmd, download_cmd, launch_cmd) =
+            install_commands_for(true, "file:C:/ignored.wasm", path);
+        assert!(install_cmd.starts_with("New-Item -ItemType Directory -Force"));
+        assert!(download_cmd.starts_with("Invoke-WebRequest"));
+        assert!(download_cmd.contains(DEFAULT_PLUGIN_DOWNLOAD_URL));
+        assert!(
+            launch_cmd.contains("\"file:C:/Users/me/AppData/Roaming/zellij/plugins/zrpc.wasm\"")
+        );
+    }
+
+    #[test]
+    fn rpc_error_display_includes_hints() {
+        let err = ClientError::RpcError(
+            RpcError::new(RpcErrorCode::NoMatch, "no panes match selector")
+                .with_hint("run: zjctl panes ls"),
+        );
+        assert_eq!(
+            err.to_string(),
+            "RPC error: no panes match selector\n  hint: run: zjctl panes ls"
+        );
+    }
+
+    #[test]
+    fn errors_map_to_exit_codes_and_json() {
+        let err: Box<dyn std::error::Error> = Box::new(ClientError::RpcError(
+            RpcError::new(RpcErrorCode::AmbiguousMatch, "2 panes match selector")
+                .with_hint("add --all"),
+        ));
+        assert_eq!(exit_code(err.as_ref()), 4);
+        assert_eq!(
+            error_json(err.as_ref()),
+            serde_json::json!({
+                "error": {
+                    "kind": "ambiguous_match",
+                    "message": "2 panes match selector",
+                    "hints": ["add --all"],
+                    "exit_code": 4,
+                }
+            })
+        );
+
+        let err: Box<dyn std::error::Error> = Box::new(ClientError::RpcError(RpcError::new(
+            RpcErrorCode::ScrollMode,
+            "terminal:3 is in scroll/search mode",
+        )));
+        assert_eq!(exit_code(err.as_ref()), 9);
+        assert_eq!(error_json(err.as_ref())["error"]["kind"], "scroll_mode");
+
+        let err: Box<dyn std::error::Error> = Box::new(ClientError::PluginNotLoaded {
+            launch_cmd: String::new(),
+        });
+        assert_eq!(exit_code(err.as_ref()), 12);
+        assert_eq!(
+            error_json(err.as_ref())["error"]["kind"],
+            "plugin_not_loaded"
+        );
+
+        let err: Box<dyn std::error::Error> =
+            Box::new(ClientError::no_match("no panes match selector: title:x"));
+        assert_eq!(err.to_string(), "no panes match selector: title:x");
+        assert_eq!(exit_code(err.as_ref()), 3);
+
+        let err: Box<dyn std::error::Error> = Box::new(ClientError::Timeout {
+            timeout: Duration::from_secs(5),
+        });
+        assert_eq!(exit_code(err.as_ref()), 17);
+        assert_eq!(error_json(err.as_ref())["error"]["kind"], "timeout");
+
+        let err: Box<dyn std::error::Error> = "timed out".into();
+        assert_eq!(exit_code(err.as_ref()), 1);
+        assert_eq!(error_json(err.as_ref())["error"]["message"], "timed out");
+
+        let mut outcomes = crate::outcome::Outcomes::with_policy(Default::default());
+        outcomes.ok("id:terminal:1");
+        outcomes.failed("id:terminal:2", "no panes match");
+        let err: Box<dyn std::error::Error> = Box::new(outcomes.finish("panes").unwrap_err());
+        assert_eq!(exit_code(err.as_ref()), 16);
+        let json = error_json(err.as_ref());
+        assert_eq!(json["error"]["kind"], "partial_failure");
+        assert_eq!(json["error"]["report"]["summary"]["succeeded"], 1);
+        assert_eq!(json["error"]["report"]["items"][1]["status"], "failed");
+
+        let err: Box<dyn std::error::Error> = Box::new(crate::interrupt::Interrupted);
+        assert_eq!(exit_code(err.as_ref()), 130);
+        assert_eq!(error_json(err.as_ref())["error"]["kind"], "interrupted");
+    }
+
+    #[test]
+    fn call_budget_allows_for_held_calls() {
+        let timeout = Duration::from_secs(5);
+        let list = RpcRequest::new(methods::PANES_LIST);
+        let poll = RpcRequest::new(methods::EVENTS_POLL)
+            .with_params(serde_json::json!({ "since": 0, "wait_ms": 25_000 }))
+            .unwrap();
+        assert_eq!(call_budget(std::slice::from_ref(&list), timeout), timeout);
+        assert_eq!(call_budget(&[list, poll], timeout), Duration::from_secs(30));
+    }
+
     #[test]
     fn pipe_plugin_configuration_sanitizes_session() {
         let config = pipe_plugin_configuration_for("weird/session name!");
         assert_eq!(config, "zjctl_session=weird_session_name_");
     }
+
+    #[test]
+    fn client_poll_is_sent_as_a_setting() {
+        assert_eq!(pipe_plugin_configuration_for("dev"), "zjctl_session=dev");
+        let request = client_poll_request(Some("adaptive:100-2000")).unwrap();
+        assert_eq!(request.method, methods::SYSTEM_CONFIGURE);
+        assert_eq!(request.params["client_poll"], "adaptive:100-2000");
+        assert!(client_poll_request(Some("")).is_none());
+        assert!(client_poll_request(None).is_none());
+    }
 }
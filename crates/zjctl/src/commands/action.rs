@@ -1,5 +1,6 @@
 //! Pass-through to zellij action
 
+use crate::client;
 use crate::zellij;
 
 pub fn run(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
@@ -11,6 +12,7 @@ pub fn run(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
         return Err(format!("zellij action exited with code: {:?}", status.code()).into());
     }
 
+    client::ensure_writable("zellij action")?;
     let status = zellij::command().arg("action").args(args).status()?;
 
     if !status.success() {
@@ -0,0 +1,244 @@
+//! `zjctl assert panes`: check the pane inventory and fail with a report when it is off
+//!
+//! Meant for CI and bootstrap scripts: every check is evaluated against one snapshot, failed
+//! checks are printed as `-` expected / `+` found pairs, and zjctl exits 1 if any failed.
+
+use serde::Serialize;
+
+use crate::commands::pane::{pane_id_to_selector, CommandExit};
+use crate::commands::panes::{self, PaneInfo};
+use crate::output::{self, Style};
+
+/// `assert panes` flags
+#[derive(Debug, Default)]
+pub struct PaneExpectations<'a> {
+    /// Panes the checks apply to (`--where`); every pane when absent
+    pub selector: Option<&'a str>,
+    pub count: Option<usize>,
+    pub count_at_least: Option<usize>,
+    pub count_at_most: Option<usize>,
+    /// No matched pane's command has exited
+    pub running: bool,
+    /// Every matched pane is in this tab (name or index)
+    pub tab: Option<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+struct Outcome {
+    check: &'static str,
+    ok: bool,
+    expected: String,
+    actual: String,
+}
+
+#[derive(Serialize)]
+struct AssertReport<'a> {
+    ok: bool,
+    selector: Option<&'a str>,
+    checks: &'a [Outcome],
+    panes: Vec<&'a str>,
+}
+
+pub fn panes(
+    plugin: Option<&str>,
+    expect: &PaneExpectations,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let matched = match expect.selector {
+        Some(selector) => panes::list_matching(plugin, selector)?,
+        None => panes::list(plugin)?,
+    };
+    let outcomes = evaluate(expect, &matched);
+    let ok = outcomes.iter().all(|outcome| outcome.ok);
+
+    if json {
+        let report = AssertReport {
+            ok,
+            selector: expect.selector,
+            checks: &outcomes,
+            panes: matched.iter().map(|pane| pane.id.as_str()).collect(),
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        let subject = expect.selector.map_or_else(
+            || "panes".to_string(),
+            |selector| format!("panes {selector}"),
+        );
+        let failed = outcomes.iter().filter(|outcome| !outcome.ok).count();
+        if failed == 0 {
+            println!(
+                "assert {subject}: {} ({} checks, {} panes)",
+                output::paint(Style::Green, "ok"),
+                outcomes.len(),
+                matched.len()
+            );
+            return Ok(());
+        }
+        println!(
+            "assert {subject}: {} ({failed} of {} checks)",
+            output::paint(Style::Red, "FAILED"),
+            outcomes.len()
+        );
+        for outcome in &outcomes {
+            if outcome.ok {
+                println!("  {}", outcome.expected);
+            } else {
+                println!(
+                    "{}",
+                    output::paint(Style::Red, &format!("- {}", outcome.expected))
+                );
+                println!(
+                    "{}",
+                    output::paint(Style::Green, &format!("+ {}", outcome.actual))
+                );
+            }
+        }
+        if !matched.is_empty() {
+            println!("matched:");
+            for pane in &matched {
+                println!("  {}", describe(pane));
+            }
+        }
+    }
+
+    if ok {
+        Ok(())
+    } else {
+        Err(Box::new(CommandExit(1)))
+    }
+}
+
+/// Run every requested check; with none requested, at least one pane has to match
+fn evaluate(expect: &PaneExpectations, matched: &[PaneInfo]) -> Vec<Outcome> {
+    let count = matched.len();
+    let mut outcomes = Vec::new();
+    let mut count_check = |check: &'static str, op: &str, want: usize, ok: bool| {
+        outcomes.push(Outcome {
+            check,
+            ok,
+            expected: format!("count {op} {want}"),
+            actual: format!("count = {count}"),
+        });
+    };
+    if let Some(want) = expect.count {
+        count_check("count", "=", want, count == want);
+    }
+    if let Some(want) = expect.count_at_least {
+        count_check("count_at_least", ">=", want, count >= want);
+    }
+    if let Some(want) = expect.count_at_most {
+        count_check("count_at_most", "<=", want, count <= want);
+    }
+    let requested_count =
+        expect.count.is_some() || expect.count_at_least.is_some() || expect.count_at_most.is_some();
+    if !requested_count && !expect.running && expect.tab.is_none() {
+        count_check("count_at_least", ">=", 1, count >= 1);
+    }
+
+    if expect.running {
+        let exited: Vec<String> = matched
+            .iter()
+            .filter(|pane| pane.exited || pane.held)
+            .map(|pane| match pane.exit_status {
+                Some(status) => format!("{} (exit {status})", short_id(pane)),
+                None => short_id(pane),
+            })
+            .collect();
+        outcomes.push(Outcome {
+            check: "running",
+            ok: exited.is_empty(),
+            expected: "all running".to_string(),
+            actual: format!("exited: {}", exited.join(", ")),
+        });
+    }
+
+    if let Some(tab) = expect.tab {
+        let elsewhere: Vec<String> = matched
+            .iter()
+            .filter(|pane| pane.tab_name != tab && pane.tab_index.to_string() != tab)
+            .map(|pane| format!("{} in tab {}", short_id(pane), pane.tab_name))
+            .collect();
+        outcomes.push(Outcome {
+            check: "tab",
+            ok: elsewhere.is_empty(),
+            expected: format!("all in tab {tab}"),
+            actual: elsewhere.join(", "),
+        });
+    }
+    outcomes
+}
+
+fn short_id(pane: &PaneInfo) -> String {
+    pane_id_to_selector(&pane.id).unwrap_or_else(|| pane.id.clone())
+}
+
+/// `id:terminal:3  tab dev  node server.js`
+fn describe(pane: &PaneInfo) -> String {
+    let what = pane.command.as_deref().unwrap_or(&pane.title);
+    format!("{:<20} tab {:<12} {what}", short_id(pane), pane.tab_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pane(id: &str, tab: &str, exited: bool) -> PaneInfo {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "pane_type": "terminal",
+            "title": "sh",
+            "command": null,
+            "tab_index": 0,
+            "tab_name": tab,
+            "focused": false,
+            "floating": false,
+            "suppressed": false,
+            "exited": exited,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn counts_and_predicates_report_expected_and_found() {
+        let matched = [
+            pane("terminal:1", "dev", false),
+            pane("terminal:2", "logs", true),
+        ];
+        let expect = PaneExpectations {
+            count_at_least: Some(3),
+            count_at_most: Some(2),
+            running: true,
+            tab: Some("dev"),
+            ..Default::default()
+        };
+        let outcomes = evaluate(&expect, &matched);
+        let failed: Vec<(&str, &str)> = outcomes
+            .iter()
+            .filter(|outcome| !outcome.ok)
+            .map(|outcome| (outcome.expected.as_str(), outcome.actual.as_str()))
+            .collect();
+        assert_eq!(
+            failed,
+            [
+                ("count >= 3", "count = 2"),
+                ("all running", "exited: id:terminal:2"),
+                ("all in tab dev", "id:terminal:2 in tab logs"),
+            ]
+        );
+        assert_eq!(outcomes.len(), 4);
+    }
+
+    #[test]
+    fn no_checks_means_something_matches() {
+        let outcomes = evaluate(&PaneExpectations::default(), &[]);
+        assert_eq!(outcomes.len(), 1);
+        assert!(!outcomes[0].ok);
+        assert!(
+            evaluate(
+                &PaneExpectations::default(),
+                &[pane("terminal:1", "dev", false)]
+            )[0]
+            .ok
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! Several RPC calls in one `zellij pipe` round trip
+//!
+//! With `--fail-fast` the calls are sent one at a time instead, so that none is made after
+//! the first one that fails.
+
+use std::io::Read;
+
+use serde::{Deserialize, Serialize};
+use zjctl_proto::{RpcError, RpcRequest, RpcResponse};
+
+use crate::client;
+use crate::outcome::{self, FailurePolicy, Outcomes};
+use crate::telemetry;
+
+/// One call: `{"method": "pane.send", "params": {...}}`
+#[derive(Debug, Deserialize)]
+struct BatchItem {
+    method: String,
+    #[serde(default)]
+    params: serde_json::Value,
+}
+
+/// Printed once per call, in input order
+#[derive(Debug, Serialize)]
+struct BatchOutput<'a> {
+    method: &'a str,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+    /// Not sent because an earlier call failed (`--fail-fast`)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    skipped: bool,
+}
+
+pub fn run(plugin: Option<&str>, file: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let input = match file {
+        Some("-") | None => {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input)?;
+            input
+        }
+        Some(path) => {
+            std::fs::read_to_string(path).map_err(|err| format!("failed to read {path}: {err}"))?
+        }
+    };
+    let items = parse_items(&input)?;
+    if items.is_empty() {
+        return Err("no requests in batch input".into());
+    }
+
+    let span = telemetry::rpc_span("batch");
+    let requests = items
+        .iter()
+        .map(|item| {
+            RpcRequest::new(&item.method)
+                .with_params(&item.params)
+                .map(|request| request.with_trace_id(span.trace_id()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let outcomes = send(plugin, &items, &requests);
+    span.end(
+        outcomes
+            .as_ref()
+            .is_ok_and(|outcomes| outcomes.summary().failed == 0),
+    );
+    outcomes?.finish("requests")?;
+    Ok(())
+}
+
+/// Send the calls and print one line per call as the responses come in
+fn send(
+    plugin: Option<&str>,
+    items: &[BatchItem],
+    requests: &[RpcRequest],
+) -> Result<Outcomes, Box<dyn std::error::Error>> {
+    let mut outcomes = Outcomes::new();
+    let mut responses = if outcome::policy() == FailurePolicy::FailFast {
+        None
+    } else {
+        Some(client::call_batch(requests, plugin)?.into_iter())
+    };
+    for (i, (item, request)) in items.iter().zip(requests).enumerate() {
+        let label = format!("#{} {}", i + 1, item.method);
+        if !outcomes.keep_going() {
+            outcomes.skipped(label);
+            let output = BatchOutput {
+                method: &item.method,
+                ok: false,
+                result: None,
+                error: None,
+                skipped: true,
+            };
+            println!("{}", serde_json::to_string(&output)?);
+            continue;
+        }
+        let response: Option<RpcResponse> = match &mut responses {
+            Some(responses) => responses.next(),
+            None => client::call_batch(std::slice::from_ref(request), plugin)?.pop(),
+        };
+        let response = response.ok_or("missing response in batch")?;
+        match &response.error {
+            Some(error) if !response.ok => outcomes.failed(label, &error.message),
+            _ if !response.ok => outcomes.failed(label, "request failed"),
+            _ => outcomes.ok(label),
+        }
+        let output = BatchOutput {
+            method: &item.method,
+            ok: response.ok,
+            result: response.result,
+            error: response.error,
+            skipped: false,
+        };
+        println!("{}", serde_json::to_string(&output)?);
+    }
+    Ok(outcomes)
+}
+
+/// A JSON array of calls, or one call per line (JSON Lines)
+fn parse_items(input: &str) -> Result<Vec<BatchItem>, String> {
+    if input.trim_start().starts_with('[') {
+        return serde_json::from_str(input).map_err(|err| format!("invalid batch: {err}"));
+    }
+    serde_json::Deserializer::from_str(input)
+        .into_iter::<BatchItem>()
+        .collect::<Result<_, _>>()
+        .map_err(|err| format!("invalid batch: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_items_accepts_array_and_json_lines() {
+        let lines = r#"{"method":"pane.send","params":{"selector":"focused","text":"ls\n"}}
+{"method":"pane.focus_previous"}
+"#;
+        let items = parse_items(lines).expect("json lines");
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1].method, "pane.focus_previous");
+        assert!(items[1].params.is_null());
+
+        let array = r#"[{"method":"pane.rename","params":{"selector":"focused","name":"x"}}]"#;
+        let items = parse_items(array).expect("array");
+        assert_eq!(items[0].params["name"], "x");
+
+        assert!(parse_items(r#"{"params":{}}"#).is_err());
+    }
+}
@@ -0,0 +1,74 @@
+//! `zjctl broadcast`: type the same text into every terminal pane of a tab
+//!
+//! The plugin writes to each pane itself (`pane.broadcast`), so one call covers the whole tab
+//! regardless of focus. Exited command panes are skipped, since text sent to them would only
+//! re-run or close them.
+
+use serde::{Deserialize, Serialize};
+use zjctl_proto::methods;
+
+use crate::client;
+use crate::commands::pane::EnterKey;
+use crate::commands::tab;
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Broadcast {
+    sent_to: usize,
+    panes: Vec<PaneResult>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct PaneResult {
+    pane: String,
+    ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    skipped: Option<String>,
+}
+
+pub struct BroadcastOptions<'a> {
+    /// `current`, a 0-based index, or a tab name
+    pub tab: Option<&'a str>,
+    pub all_tabs: bool,
+    /// Press Enter after the text, with the same bytes as `pane send --enter-key`
+    pub enter: Option<EnterKey>,
+    pub json: bool,
+}
+
+pub fn run(
+    plugin: Option<&str>,
+    options: BroadcastOptions<'_>,
+    text: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut text = text.join(" ");
+    if text.is_empty() {
+        return Err("nothing to send (pass the text after --)".into());
+    }
+    if let Some(enter) = options.enter {
+        text.push_str(enter.sequence());
+    }
+    let tab = if options.all_tabs {
+        None
+    } else {
+        Some(tab::resolve(plugin, options.tab)?)
+    };
+
+    let params = serde_json::json!({
+        "tab": tab,
+        "all_tabs": options.all_tabs,
+        "text": text,
+    });
+    let result: Broadcast =
+        serde_json::from_value(client::rpc_call(plugin, methods::PANE_BROADCAST, params)?)?;
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(());
+    }
+    for pane in &result.panes {
+        match &pane.skipped {
+            Some(reason) => println!("{} skipped ({reason})", pane.pane),
+            None => println!("{} sent", pane.pane),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,295 @@
+//! `zjctl daemon`: keep one `zellij pipe` open for faster calls
+
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::auth::{self, Scope, TokenStore};
+use crate::client;
+use crate::commands::pane::CommandExit;
+use crate::daemon::{self, DaemonStatus, ServeOptions};
+use crate::output;
+use crate::zellij;
+
+/// Default `--cache-ttl` for a restarted daemon when the old one could not be asked
+const DEFAULT_CACHE_TTL_MS: u64 = 500;
+
+pub fn run(plugin: Option<&str>, options: ServeOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let default_url = client::default_plugin_url();
+    let plugin_url = plugin.unwrap_or(default_url.as_str());
+    daemon::serve(plugin_url, client::pipe_plugin_configuration(), options)
+}
+
+/// `--auth-tokens` / `--file`, defaulting to `~/.config/zjctl/tokens`
+pub fn tokens_path(path: Option<&Path>) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    match path {
+        Some(path) => Ok(path.to_path_buf()),
+        None => auth::default_tokens_path().ok_or_else(|| {
+            "unable to determine config directory (set HOME or XDG_CONFIG_HOME)".into()
+        }),
+    }
+}
+
+/// Add (or replace) a token and print its secret, which is not stored anywhere
+pub fn token_new(
+    path: Option<&Path>,
+    name: &str,
+    scope: Scope,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err("token names cannot be empty or contain whitespace".into());
+    }
+    let path = tokens_path(path)?;
+    let mut store = TokenStore::load(&path)?;
+    let token = store.issue(name, scope);
+    store.save(&path)?;
+    println!("{token}");
+    eprintln!(
+        "token {name} ({scope}) added to {}; it is shown only once",
+        path.display()
+    );
+    Ok(())
+}
+
+pub fn token_ls(path: Option<&Path>, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let store = TokenStore::load(&tokens_path(path)?)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&store.entries)?);
+        return Ok(());
+    }
+    if store.entries.is_empty() {
+        println!("No tokens");
+        return Ok(());
+    }
+    println!("{}", output::header(&format!("{:<24} SCOPE", "NAME")));
+    println!("{}", output::rule(36));
+    for entry in &store.entries {
+        println!("{:<24} {}", entry.name, entry.scope);
+    }
+    Ok(())
+}
+
+pub fn token_rm(path: Option<&Path>, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let path = tokens_path(path)?;
+    let mut store = TokenStore::load(&path)?;
+    if !store.revoke(name) {
+        return Err(format!("no token named {name}").into());
+    }
+    store.save(&path)?;
+    println!("removed {name}");
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct StatusReport<'a> {
+    running: bool,
+    socket: String,
+    #[serde(flatten)]
+    daemon: Option<&'a DaemonStatus>,
+}
+
+/// Describe the daemon for this session; exits 1 when none is running
+pub fn status(json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let session = zellij::session_name();
+    let socket = daemon::socket_path(session.as_deref())
+        .ok_or("unable to determine state directory (set HOME or XDG_STATE_HOME)")?;
+    let found = daemon::status(session.as_deref()).transpose()?;
+
+    if json {
+        let report = StatusReport {
+            running: found.is_some(),
+            socket: socket.display().to_string(),
+            daemon: found.as_ref(),
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if let Some(daemon) = &found {
+        println!("daemon:   running (pid {})", daemon.pid);
+        println!("socket:   {}", socket.display());
+        println!("plugin:   {}", daemon.plugin);
+        println!("uptime:   {}", format_uptime(daemon.uptime_secs));
+        let snapshot = match daemon.cache_age_ms {
+            Some(age) => format!("snapshot {age}ms old"),
+            None => "no snapshot".to_string(),
+        };
+        println!("cache:    ttl {}ms, {snapshot}", daemon.cache_ttl_ms);
+        println!(
+            "requests: {} served, {} other client(s) connected, {} waiting on the plugin",
+            daemon.requests, daemon.clients, daemon.pending
+        );
+        println!(
+            "pipe:     {} ({} started)",
+            if daemon.pipe_running {
+                "running"
+            } else {
+                "not started"
+            },
+            daemon.pipes_spawned
+        );
+        if let Some(addr) = &daemon.metrics_listen {
+            println!("metrics:  http://{addr}/metrics");
+        }
+        if let Some(tokens) = &daemon.auth_tokens {
+            println!("auth:     tokens from {tokens}");
+        }
+        if daemon.read_only {
+            println!("mode:     read-only");
+        }
+        if !daemon.capture_history.is_empty() {
+            println!(
+                "history:  {} every {}s, last {} per pane",
+                daemon.capture_history.join(" "),
+                daemon.capture_interval_secs,
+                daemon.capture_keep
+            );
+        }
+        for worker in &daemon.workers {
+            println!(
+                "worker:   {} {} (pid {}, {})",
+                worker.kind,
+                worker.target,
+                worker.pid,
+                format_uptime(worker.running_secs)
+            );
+        }
+    } else {
+        println!("daemon:   not running");
+        println!("socket:   {}", socket.display());
+    }
+
+    if found.is_some() {
+        Ok(())
+    } else {
+        Err(Box::new(CommandExit(1)))
+    }
+}
+
+pub fn stop() -> Result<(), Box<dyn std::error::Error>> {
+    match daemon::stop(zellij::session_name().as_deref()) {
+        Some(result) => {
+            result?;
+            println!("daemon: stopped");
+        }
+        None => println!("daemon: not running"),
+    }
+    Ok(())
+}
+
+/// Stop the running daemon (if any) and start a new one in the background. The new daemon
+/// keeps the old one's cache TTL, metrics address (and its remote opt-in), and tokens file
+/// unless new ones are given, its capture history settings (not the snapshots), and stays
+/// read-only if the old one was.
+pub fn restart(
+    plugin: Option<&str>,
+    cache_ttl: Option<u64>,
+    metrics_listen: Option<SocketAddr>,
+    metrics_allow_remote: bool,
+    auth_tokens: Option<PathBuf>,
+    read_only: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let session = zellij::session_name();
+    let previous = daemon::status(session.as_deref()).and_then(Result::ok);
+    if daemon::stop(session.as_deref()).transpose()?.is_some() {
+        println!("daemon: stopped");
+    }
+    let cache_ttl = cache_ttl
+        .or(previous.as_ref().map(|daemon| daemon.cache_ttl_ms))
+        .unwrap_or(DEFAULT_CACHE_TTL_MS);
+    // A kept non-loopback address was already allowed when the old daemon started.
+    let (metrics_listen, metrics_allow_remote) = match metrics_listen {
+        Some(addr) => (Some(addr.to_string()), metrics_allow_remote),
+        None => {
+            let kept = previous
+                .as_ref()
+                .and_then(|daemon| daemon.metrics_listen.clone());
+            let remote = kept
+                .as_deref()
+                .and_then(|addr| addr.parse::<SocketAddr>().ok())
+                .is_some_and(|addr| !addr.ip().is_loopback());
+            (kept, remote)
+        }
+    };
+
+    let mut command = std::process::Command::new(std::env::current_exe()?);
+    if let Some(plugin) = plugin {
+        command.args(["--plugin", plugin]);
+    }
+    command.args(["daemon", "--cache-ttl", &cache_ttl.to_string()]);
+    if let Some(addr) = &metrics_listen {
+        command.args(["--metrics-listen", addr]);
+        if metrics_allow_remote {
+            command.arg("--metrics-allow-remote");
+        }
+    }
+    let auth_tokens = auth_tokens.or(previous
+        .as_ref()
+        .and_then(|daemon| daemon.auth_tokens.as_ref().map(PathBuf::from)));
+    if let Some(path) = &auth_tokens {
+        command.arg("--auth-tokens").arg(path);
+    }
+    if read_only || previous.as_ref().is_some_and(|daemon| daemon.read_only) {
+        command.arg("--read-only");
+    }
+    if let Some(previous) = &previous {
+        for selector in &previous.capture_history {
+            command.args(["--capture-history", selector]);
+        }
+        if !previous.capture_history.is_empty() {
+            command.args([
+                "--capture-interval",
+                &previous.capture_interval_secs.to_string(),
+                "--capture-keep",
+                &previous.capture_keep.to_string(),
+            ]);
+        }
+    }
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    let mut child = command
+        .spawn()
+        .map_err(|err| format!("failed to start daemon: {err}"))?;
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        if let Some(status) = daemon::status(session.as_deref()) {
+            let status = status?;
+            println!("daemon: started (pid {})", status.pid);
+            return Ok(());
+        }
+        if let Some(exit) = child.try_wait()? {
+            return Err(format!("daemon exited during startup ({exit})").into());
+        }
+        if Instant::now() >= deadline {
+            return Err("daemon did not start listening within 5s".into());
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// `3h07m12s`, `4m05s`, or `9s`
+fn format_uptime(secs: u64) -> String {
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}h{minutes:02}m{seconds:02}s")
+    } else if minutes > 0 {
+        format!("{minutes}m{seconds:02}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_uptime_drops_leading_zero_units() {
+        assert_eq!(format_uptime(9), "9s");
+        assert_eq!(format_uptime(245), "4m05s");
+        assert_eq!(format_uptime(3 * 3600 + 7 * 60 + 12), "3h07m12s");
+    }
+}
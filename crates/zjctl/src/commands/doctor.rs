@@ -1,10 +1,20 @@
 //! Diagnostics and setup checks
+//!
+//! `--fix` carries out the remedies for the checks that have one (install the plugin, add it
+//! to `load_plugins`, launch it in this session) instead of printing commands to copy.
 
+use std::io::Write;
+use std::path::PathBuf;
 use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::client::{self, ClientError};
+use crate::commands::{install, report};
+use crate::formats::{self, OutputFormat};
+use crate::output::{self, Style};
+use crate::zellij;
 use serde::Serialize;
-use zjctl_proto::methods;
+use zjctl_proto::{methods, UpdateCheck};
 
 #[derive(Serialize)]
 struct DoctorReport {
@@ -12,9 +22,20 @@ struct DoctorReport {
     zjctl_version: String,
     plugin_url: String,
     plugin_path: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fixes: Vec<FixReport>,
     checks: Vec<CheckReport>,
 }
 
+#[derive(Serialize)]
+struct FixReport {
+    check: &'static str,
+    action: String,
+    /// `false` with `--dry-run`
+    applied: bool,
+    error: Option<String>,
+}
+
 #[derive(Serialize)]
 struct CheckReport {
     name: String,
@@ -28,6 +49,62 @@ struct Check {
     status: &'static str,
     detail: Option<String>,
     commands: Vec<String>,
+    /// What `--fix` does about this check
+    fix: Option<Fix>,
+}
+
+/// A remedy `--fix` can carry out itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Fix {
+    /// Write (or download) zrpc.wasm to the plugin path
+    InstallPlugin(PathBuf),
+    /// Add the plugin to `load_plugins` in config.kdl
+    AutoLoad { config: PathBuf, url: String },
+    /// Launch the plugin in the current session and wait for it to prime its state
+    LaunchPlugin(String),
+}
+
+impl Fix {
+    fn describe(&self) -> String {
+        match self {
+            Fix::InstallPlugin(path) => format!("install the plugin to {}", path.display()),
+            Fix::AutoLoad { config, url } => {
+                format!("add {url} to load_plugins in {}", config.display())
+            }
+            Fix::LaunchPlugin(url) => format!("launch {url} in this session"),
+        }
+    }
+
+    fn apply(&self, plugin: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Fix::InstallPlugin(path) => install::install_plugin(path),
+            Fix::AutoLoad { config, url } => {
+                install::ensure_auto_load_config(config, url).map(|_| ())
+            }
+            Fix::LaunchPlugin(url) => {
+                install::launch_plugin(url)?;
+                if !client::wait_ready(plugin, LAUNCH_READY_TIMEOUT)? {
+                    return Err("plugin launched but has not seen the session state yet \
+                                (permissions not granted?)"
+                        .into());
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// How long a launched plugin gets to report ready before `--fix` moves on
+const LAUNCH_READY_TIMEOUT: Duration = Duration::from_secs(5);
+/// Fixing one check can turn up the next (no plugin file hides whether it is loaded)
+const MAX_FIX_PASSES: usize = 3;
+
+/// `--fix` and `--dry-run`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixMode {
+    Off,
+    Apply,
+    DryRun,
 }
 
 fn push_check(
@@ -46,15 +123,248 @@ fn push_check(
         status,
         detail,
         commands,
+        fix: None,
     });
 }
 
-pub fn run(plugin: Option<&str>, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+/// Attach a `--fix` remedy to the check just pushed
+fn with_fix(checks: &mut [Check], fix: Fix) {
+    if let Some(check) = checks.last_mut() {
+        check.fix = Some(fix);
+    }
+}
+
+pub fn run(
+    plugin: Option<&str>,
+    output_format: OutputFormat,
+    fix: FixMode,
+) -> Result<(), Box<dyn std::error::Error>> {
     let zjctl_version = env!("CARGO_PKG_VERSION").to_string();
     let default_url = client::default_plugin_url();
     let plugin_url = plugin.unwrap_or(default_url.as_str()).to_string();
     let plugin_path = client::plugin_file_path(&plugin_url);
     let plugin_path_display = plugin_path.as_ref().map(|path| path.display().to_string());
+    let (mut ok, mut checks) = collect(plugin, &plugin_url, &plugin_path, true);
+
+    let mut fixes = Vec::new();
+    if fix != FixMode::Off {
+        let mut attempted: Vec<Fix> = Vec::new();
+        for _ in 0..MAX_FIX_PASSES {
+            let pending: Vec<(&'static str, Fix)> = checks
+                .iter()
+                .filter_map(|check| Some((check.name, check.fix.clone()?)))
+                .filter(|(_, fix)| !attempted.contains(fix))
+                .collect();
+            if pending.is_empty() {
+                break;
+            }
+            for (check, remedy) in pending {
+                let error = match fix {
+                    FixMode::Apply => remedy.apply(plugin).err().map(|err| err.to_string()),
+                    _ => None,
+                };
+                if output_format == OutputFormat::Table {
+                    let line = match (fix, &error) {
+                        (FixMode::DryRun, _) => format!("would fix {check}: {}", remedy.describe()),
+                        (_, None) => format!("fixed {check}: {}", remedy.describe()),
+                        (_, Some(err)) => format!(
+                            "{} {check}: {}: {err}",
+                            output::paint(Style::Red, "could not fix"),
+                            remedy.describe()
+                        ),
+                    };
+                    println!("{line}");
+                }
+                fixes.push(FixReport {
+                    check,
+                    action: remedy.describe(),
+                    applied: fix == FixMode::Apply && error.is_none(),
+                    error,
+                });
+                attempted.push(remedy);
+            }
+            // A dry run changes nothing, so checking again would find the same fixes.
+            if fix == FixMode::DryRun {
+                break;
+            }
+            (ok, checks) = collect(plugin, &plugin_url, &plugin_path, true);
+        }
+        if output_format == OutputFormat::Table && !fixes.is_empty() {
+            println!();
+        }
+    }
+
+    if output_format == OutputFormat::Tsv {
+        // name, status, detail
+        for check in &checks {
+            println!(
+                "{}",
+                formats::tsv_row([
+                    check.name,
+                    check.status,
+                    check.detail.as_deref().unwrap_or_default()
+                ])
+            );
+        }
+    } else if output_format != OutputFormat::Table {
+        let report = DoctorReport {
+            ok,
+            zjctl_version: zjctl_version.clone(),
+            plugin_url: plugin_url.clone(),
+            plugin_path: plugin_path_display,
+            fixes,
+            checks: checks
+                .iter()
+                .map(|check| CheckReport {
+                    name: check.name.to_string(),
+                    status: check.status.to_string(),
+                    detail: check.detail.clone(),
+                    commands: check.commands.clone(),
+                })
+                .collect(),
+        };
+        formats::print_document(output_format, &report)?;
+    } else {
+        println!("zjctl doctor v{zjctl_version}");
+        println!("============");
+        println!("plugin url: {plugin_url}");
+        if let Some(path) = &plugin_path_display {
+            println!("plugin path: {path}");
+        }
+
+        for check in &checks {
+            let status = paint_status(check.status);
+            match &check.detail {
+                Some(detail) => println!("{}: {status} ({detail})", check.name),
+                None => println!("{}: {status}", check.name),
+            }
+
+            for cmd in &check.commands {
+                println!("  fix: {cmd}");
+            }
+        }
+    }
+
+    if ok {
+        Ok(())
+    } else {
+        Err("doctor found issues".into())
+    }
+}
+
+#[derive(Serialize)]
+struct Transition<'a> {
+    time: String,
+    check: &'a str,
+    /// `None` for the first pass
+    from: Option<&'a str>,
+    status: &'a str,
+    detail: Option<&'a str>,
+}
+
+/// Re-run the checks every `interval` and print only the ones whose status changed, with a
+/// timestamp, until interrupted or stdout closes; JSON output is one object per line
+pub fn watch(
+    plugin: Option<&str>,
+    interval: Duration,
+    output_format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if output_format == OutputFormat::Yaml {
+        return Err("doctor --watch supports --output table, json, or tsv".into());
+    }
+    let default_url = client::default_plugin_url();
+    let plugin_url = plugin.unwrap_or(default_url.as_str()).to_string();
+    let plugin_path = client::plugin_file_path(&plugin_url);
+    let mut stdout = std::io::stdout();
+    let mut previous: Vec<Check> = Vec::new();
+
+    loop {
+        let (_, checks) = collect(plugin, &plugin_url, &plugin_path, false);
+        let time = report::utc_timestamp(unix_time());
+        for (check, from) in transitions(&previous, &checks) {
+            let line = if output_format == OutputFormat::Json {
+                serde_json::to_string(&Transition {
+                    time: time.clone(),
+                    check: check.name,
+                    from,
+                    status: check.status,
+                    detail: check.detail.as_deref(),
+                })?
+            } else if output_format == OutputFormat::Tsv {
+                // time, check, previous status, status, detail
+                formats::tsv_row([
+                    time.as_str(),
+                    check.name,
+                    from.unwrap_or_default(),
+                    check.status,
+                    check.detail.as_deref().unwrap_or_default(),
+                ])
+            } else {
+                let change = match from {
+                    Some(from) => {
+                        format!("{} -> {}", paint_status(from), paint_status(check.status))
+                    }
+                    None => paint_status(check.status),
+                };
+                match &check.detail {
+                    Some(detail) => format!("{time} {}: {change} ({detail})", check.name),
+                    None => format!("{time} {}: {change}", check.name),
+                }
+            };
+            match writeln!(stdout, "{line}").and_then(|()| stdout.flush()) {
+                Ok(()) => {}
+                Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => return Ok(()),
+                Err(err) => return Err(err.into()),
+            }
+        }
+        previous = checks;
+        std::thread::sleep(interval);
+    }
+}
+
+/// Checks in `current` whose status differs from `previous`, with the old status (`None` when
+/// the check was not run before)
+fn transitions<'a>(
+    previous: &'a [Check],
+    current: &'a [Check],
+) -> Vec<(&'a Check, Option<&'static str>)> {
+    current
+        .iter()
+        .filter_map(|check| {
+            let before = previous.iter().find(|p| p.name == check.name);
+            match before {
+                Some(before) if before.status == check.status => None,
+                _ => Some((check, before.map(|before| before.status))),
+            }
+        })
+        .collect()
+}
+
+fn paint_status(status: &str) -> String {
+    match status {
+        "ok" => output::paint(Style::Green, "ok"),
+        "fail" => output::paint(Style::Red, "fail"),
+        "warn" => output::paint(Style::Yellow, "warn"),
+        "skip" => output::paint(Style::Dim, "skip"),
+        other => other.to_string(),
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Run every check once. `update_check` is off for `--watch`, which would otherwise ask the
+/// release server on every pass.
+fn collect(
+    plugin: Option<&str>,
+    plugin_url: &str,
+    plugin_path: &Option<PathBuf>,
+    update_check: bool,
+) -> (bool, Vec<Check>) {
     let mut ok = true;
     let mut checks = Vec::new();
 
@@ -98,7 +408,7 @@ pub fn run(plugin: Option<&str>, json: bool) -> Result<(), Box<dyn std::error::E
         }
     }
 
-    let plugin_file_ok = match &plugin_path {
+    let plugin_file_ok = match plugin_path {
         Some(path) if path.is_file() => {
             push_check(
                 &mut checks,
@@ -112,7 +422,7 @@ pub fn run(plugin: Option<&str>, json: bool) -> Result<(), Box<dyn std::error::E
         }
         Some(path) => {
             let (install_cmd, download_cmd, launch_cmd) =
-                client::plugin_install_commands(&plugin_url, path);
+                client::plugin_install_commands(plugin_url, path);
             push_check(
                 &mut checks,
                 &mut ok,
@@ -121,6 +431,7 @@ pub fn run(plugin: Option<&str>, json: bool) -> Result<(), Box<dyn std::error::E
                 Some(path.display().to_string()),
                 vec![install_cmd, download_cmd, launch_cmd],
             );
+            with_fix(&mut checks, Fix::InstallPlugin(path.clone()));
             false
         }
         None => {
@@ -136,16 +447,40 @@ pub fn run(plugin: Option<&str>, json: bool) -> Result<(), Box<dyn std::error::E
         }
     };
 
+    // Without a load_plugins entry the plugin only runs in sessions where it was launched by
+    // hand (or by the first zjctl call).
+    if let Some(path) = plugin_path {
+        let config = install::config_file_path();
+        let url = install::plugin_url_for_config(plugin_url, path);
+        if install::auto_load_configured(&config, &url) {
+            push_check(
+                &mut checks,
+                &mut ok,
+                "auto-load",
+                "ok",
+                Some(config.display().to_string()),
+                Vec::new(),
+            );
+        } else {
+            push_check(
+                &mut checks,
+                &mut ok,
+                "auto-load",
+                "warn",
+                Some(format!("{url} not in load_plugins of {}", config.display())),
+                vec!["zjctl install".to_string()],
+            );
+            with_fix(&mut checks, Fix::AutoLoad { config, url });
+        }
+    }
+
     let mut sessions_ok = false;
     if zellij_ok {
-        match Command::new("zellij").arg("list-sessions").output() {
-            Ok(output) if output.status.success() => {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                let sessions: Vec<&str> = stdout
-                    .lines()
-                    .filter(|line| !line.trim().is_empty())
-                    .collect();
-                if sessions.is_empty() {
+        match zellij::list_session_entries() {
+            Ok(sessions) => {
+                let running = sessions.iter().filter(|session| !session.exited).count();
+                let exited = sessions.len() - running;
+                if running == 0 {
                     push_check(
                         &mut checks,
                         &mut ok,
@@ -156,41 +491,39 @@ pub fn run(plugin: Option<&str>, json: bool) -> Result<(), Box<dyn std::error::E
                     );
                 } else {
                     sessions_ok = true;
-                    let count = sessions.len();
-                    let suffix = if count == 1 { "" } else { "s" };
+                    let detail = if exited > 0 {
+                        format!("{running} running, {exited} exited")
+                    } else {
+                        let suffix = if running == 1 { "" } else { "s" };
+                        format!("{running} session{suffix}")
+                    };
                     push_check(
                         &mut checks,
                         &mut ok,
                         "sessions",
                         "ok",
-                        Some(format!("{count} session{suffix}")),
+                        Some(detail),
                         Vec::new(),
                     );
                 }
+                let (status, detail, commands) = target_session_check(&sessions);
+                push_check(&mut checks, &mut ok, "session", status, detail, commands);
             }
-            Ok(output) => {
-                let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-                let note = if stderr.is_empty() {
-                    "exit code non-zero".to_string()
-                } else {
-                    stderr
-                };
+            Err(err) => {
                 push_check(
                     &mut checks,
                     &mut ok,
                     "sessions",
                     "fail",
-                    Some(note),
+                    Some(err),
                     Vec::new(),
                 );
-            }
-            Err(err) => {
                 push_check(
                     &mut checks,
                     &mut ok,
-                    "sessions",
-                    "fail",
-                    Some(err.to_string()),
+                    "session",
+                    "skip",
+                    Some("sessions unavailable".to_string()),
                     Vec::new(),
                 );
             }
@@ -204,27 +537,48 @@ pub fn run(plugin: Option<&str>, json: bool) -> Result<(), Box<dyn std::error::E
             Some("zellij missing".to_string()),
             Vec::new(),
         );
+        push_check(
+            &mut checks,
+            &mut ok,
+            "session",
+            "skip",
+            Some("zellij missing".to_string()),
+            Vec::new(),
+        );
     }
 
+    let mut rpc_ok = false;
     if zellij_ok && plugin_file_ok && sessions_ok {
         match client::rpc_call(plugin, methods::PANES_LIST, serde_json::json!({})) {
-            Ok(_) => push_check(
-                &mut checks,
-                &mut ok,
-                "rpc",
-                "ok",
-                Some("plugin responding".to_string()),
-                Vec::new(),
-            ),
-            Err(err) => match err {
-                ClientError::PluginNotLoaded { launch_cmd } => push_check(
+            Ok(_) => {
+                rpc_ok = true;
+                push_check(
                     &mut checks,
                     &mut ok,
                     "rpc",
-                    "fail",
-                    Some("no response from plugin".to_string()),
-                    vec![launch_cmd],
-                ),
+                    "ok",
+                    Some("plugin responding".to_string()),
+                    Vec::new(),
+                );
+            }
+            Err(err) => match err {
+                ClientError::PluginNotLoaded { launch_cmd } => {
+                    push_check(
+                        &mut checks,
+                        &mut ok,
+                        "rpc",
+                        "fail",
+                        Some("no response from plugin".to_string()),
+                        vec![launch_cmd],
+                    );
+                    with_fix(
+                        &mut checks,
+                        Fix::LaunchPlugin(client::plugin_launch_url(
+                            plugin_url,
+                            plugin_path.as_deref(),
+                        )),
+                    );
+                }
                 ClientError::PipeError { stderr, .. } => push_check(
                     &mut checks,
                     &mut ok,
@@ -271,66 +625,129 @@ pub fn run(plugin: Option<&str>, json: bool) -> Result<(), Box<dyn std::error::E
         );
     }
 
-    if json {
-        let report = DoctorReport {
-            ok,
-            zjctl_version: zjctl_version.clone(),
-            plugin_url: plugin_url.clone(),
-            plugin_path: plugin_path_display,
-            checks: checks
-                .iter()
-                .map(|check| CheckReport {
-                    name: check.name.to_string(),
-                    status: check.status.to_string(),
-                    detail: check.detail.clone(),
-                    commands: check.commands.clone(),
-                })
-                .collect(),
-        };
-        println!("{}", serde_json::to_string_pretty(&report)?);
-    } else {
-        println!("zjctl doctor v{zjctl_version}");
-        println!("============");
-        println!("plugin url: {plugin_url}");
-        if let Some(path) = &plugin_path_display {
-            println!("plugin path: {path}");
+    if !update_check {
+        push_check(
+            &mut checks,
+            &mut ok,
+            "plugin-update",
+            "skip",
+            Some("not checked while watching".to_string()),
+            Vec::new(),
+        );
+    } else if !client::update_check_enabled() {
+        push_check(
+            &mut checks,
+            &mut ok,
+            "plugin-update",
+            "skip",
+            Some("disabled (set ZJCTL_UPDATE_CHECK=1)".to_string()),
+            Vec::new(),
+        );
+    } else if rpc_ok {
+        match check_update(plugin) {
+            Ok(check) if check.update_available => push_check(
+                &mut checks,
+                &mut ok,
+                "plugin-update",
+                "warn",
+                Some(format!(
+                    "plugin update available: {} -> {}",
+                    check.current, check.latest
+                )),
+                vec!["zjctl install --force".to_string()],
+            ),
+            Ok(check) => push_check(
+                &mut checks,
+                &mut ok,
+                "plugin-update",
+                "ok",
+                Some(format!("{} is the latest release", check.current)),
+                Vec::new(),
+            ),
+            // Being offline is not a setup problem.
+            Err(err) => push_check(
+                &mut checks,
+                &mut ok,
+                "plugin-update",
+                "skip",
+                Some(err.to_string()),
+                Vec::new(),
+            ),
         }
+    } else {
+        push_check(
+            &mut checks,
+            &mut ok,
+            "plugin-update",
+            "skip",
+            Some("plugin not responding".to_string()),
+            Vec::new(),
+        );
+    }
 
-        for check in &checks {
-            match check.status {
-                "ok" => {
-                    if let Some(detail) = &check.detail {
-                        println!("{}: ok ({detail})", check.name);
-                    } else {
-                        println!("{}: ok", check.name);
-                    }
-                }
-                "fail" => {
-                    if let Some(detail) = &check.detail {
-                        println!("{}: fail ({detail})", check.name);
-                    } else {
-                        println!("{}: fail", check.name);
-                    }
-                }
-                "skip" => {
-                    if let Some(detail) = &check.detail {
-                        println!("{}: skip ({detail})", check.name);
-                    } else {
-                        println!("{}: skip", check.name);
-                    }
-                }
-                other => println!("{}: {other}", check.name),
-            }
+    (ok, checks)
+}
 
-            for cmd in &check.commands {
-                println!("  fix: {cmd}");
-            }
+/// The session zjctl targets (`--session`, else the one it runs inside) is running
+fn target_session_check(
+    sessions: &[zellij::SessionEntry],
+) -> (&'static str, Option<String>, Vec<String>) {
+    let Some(name) = zellij::session_name() else {
+        return (
+            "skip",
+            Some("not inside a Zellij session (pass --session)".to_string()),
+            Vec::new(),
+        );
+    };
+    match sessions.iter().find(|session| session.name == name) {
+        Some(session) if session.exited => (
+            "fail",
+            Some(format!("{name} has exited")),
+            vec![format!("zellij attach {name}")],
+        ),
+        Some(_) => ("ok", Some(name), Vec::new()),
+        None => (
+            "fail",
+            Some(format!("no session named {name}")),
+            vec!["zjctl session ls".to_string()],
+        ),
+    }
+}
+
+/// Ask the plugin to compare itself against the latest release
+pub(crate) fn check_update(
+    plugin: Option<&str>,
+) -> Result<UpdateCheck, Box<dyn std::error::Error>> {
+    let value = client::rpc_call(plugin, methods::SYSTEM_CHECK_UPDATE, serde_json::json!({}))?;
+    Ok(serde_json::from_value(value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(name: &'static str, status: &'static str) -> Check {
+        Check {
+            name,
+            status,
+            detail: None,
+            commands: Vec::new(),
+            fix: None,
         }
     }
 
-    if ok {
-        Ok(())
-    } else {
-        Err("doctor found issues".into())
+    #[test]
+    fn transitions_report_only_status_changes() {
+        let before = [check("zellij", "ok"), check("rpc", "ok")];
+        let first = transitions(&[], &before);
+        assert_eq!(first.len(), 2);
+        assert!(first.iter().all(|(_, from)| from.is_none()));
+
+        let after = [check("zellij", "ok"), check("rpc", "fail")];
+        let changed = transitions(&before, &after);
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].0.name, "rpc");
+        assert_eq!(changed[0].0.status, "fail");
+        assert_eq!(changed[0].1, Some("ok"));
     }
 }
@@ -0,0 +1,114 @@
+//! `zjctl events`: stream pane, tab, and focus changes as JSON lines
+//!
+//! The plugin keeps a short log of changes it observed in PaneUpdate/TabUpdate and client
+//! focus. Each `events.poll` call names the last sequence number seen and is held by the
+//! plugin until something newer happens (or a wait limit passes), so the stream reacts
+//! immediately without polling `panes ls`.
+
+use std::io::Write;
+
+use serde::Deserialize;
+use zjctl_proto::methods;
+
+use crate::client;
+use crate::daemon;
+
+/// How long one `events.poll` may be held by the plugin
+const POLL_WAIT_MS: u64 = 25_000;
+
+#[derive(Debug, Deserialize)]
+struct PollResult {
+    seq: u64,
+    events: Vec<serde_json::Value>,
+    /// Events dropped from the plugin's log before this call could see them
+    #[serde(default)]
+    missed: u64,
+}
+
+/// Events from the plugin's log, starting after whatever happened before [`EventStream::start`]
+pub struct EventStream<'a> {
+    plugin: Option<&'a str>,
+    since: u64,
+}
+
+impl<'a> EventStream<'a> {
+    pub fn start(plugin: Option<&'a str>) -> Result<Self, Box<dyn std::error::Error>> {
+        let first: PollResult = serde_json::from_value(client::rpc_call(
+            plugin,
+            methods::EVENTS_POLL,
+            serde_json::json!({}),
+        )?)?;
+        Ok(Self {
+            plugin,
+            since: first.seq,
+        })
+    }
+
+    /// The next batch of events, preceded by a `missed` or `resync` notice when the log was
+    /// overflowed or restarted; empty when the plugin's wait limit passed quietly
+    pub fn next_batch(&mut self) -> Result<Vec<serde_json::Value>, Box<dyn std::error::Error>> {
+        let params = serde_json::json!({ "since": self.since, "wait_ms": POLL_WAIT_MS });
+        let result: PollResult =
+            serde_json::from_value(client::rpc_call(self.plugin, methods::EVENTS_POLL, params)?)?;
+
+        let mut lines = Vec::new();
+        if result.seq < self.since {
+            // The plugin was reloaded and started a new log.
+            lines.push(serde_json::json!({ "event": "resync", "seq": result.seq }));
+        } else if result.missed > 0 {
+            lines.push(serde_json::json!({ "event": "missed", "count": result.missed }));
+        }
+        lines.extend(result.events);
+        self.since = result.seq;
+        Ok(lines)
+    }
+}
+
+/// Print events until interrupted or stdout closes; `only` keeps just these event names
+pub fn run(plugin: Option<&str>, only: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut stream = EventStream::start(plugin)?;
+    let _registration = daemon::register(
+        "events",
+        &if only.is_empty() {
+            "all".to_string()
+        } else {
+            only.join(",")
+        },
+    );
+    let mut stdout = std::io::stdout();
+
+    loop {
+        let lines = stream.next_batch()?;
+        for line in lines.iter().filter(|line| wanted(line, only)) {
+            match writeln!(stdout, "{line}").and_then(|()| stdout.flush()) {
+                Ok(()) => {}
+                // `zjctl events | head` closing the pipe is a normal way to stop.
+                Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => return Ok(()),
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+}
+
+/// Whether `event` passes `--event`; the stream's own `missed`/`resync` notices always do
+fn wanted(event: &serde_json::Value, only: &[String]) -> bool {
+    let name = event["event"].as_str().unwrap_or_default();
+    only.is_empty() || matches!(name, "missed" | "resync") || only.iter().any(|o| o == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wanted_filters_by_event_name_but_keeps_notices() {
+        let only = vec!["pane_opened".to_string()];
+        let opened = serde_json::json!({"event": "pane_opened", "pane": "terminal:1"});
+        let focused = serde_json::json!({"event": "pane_focused", "pane": "terminal:1"});
+        let missed = serde_json::json!({"event": "missed", "count": 3});
+        assert!(wanted(&opened, &only));
+        assert!(!wanted(&focused, &only));
+        assert!(wanted(&missed, &only));
+        assert!(wanted(&focused, &[]));
+    }
+}
@@ -0,0 +1,180 @@
+//! ssh fleets (`zjctl fleet open`)
+//!
+//! Opens one ssh pane per host, named after the host, so the panes can be addressed with
+//! `name:` selectors by `pane send --all` and `pane run --all`. Hosts that already have a pane
+//! under their name are skipped, so reopening a fleet only fills in the missing ones.
+
+use std::io::Read;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::commands::pane::{self, LaunchOptions};
+use crate::commands::panes;
+
+/// How the ssh panes are arranged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FleetLayout {
+    /// Tiled panes in the current tab, arranged by Zellij
+    Grid,
+    /// Floating panes
+    Floating,
+}
+
+/// One `hosts.txt` line: the ssh destination and any per-host ssh arguments
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Host {
+    destination: String,
+    args: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Opened<'a> {
+    host: &'a str,
+    name: String,
+    selector: String,
+    /// `created` or `existing`
+    action: &'a str,
+}
+
+pub struct FleetOptions<'a> {
+    /// Hosts file, or `-` for stdin
+    pub hosts: &'a str,
+    pub layout: FleetLayout,
+    /// Extra ssh arguments for every host (whitespace-separated)
+    pub ssh_args: Option<&'a str>,
+    /// Prepended to each host to form the pane name
+    pub prefix: &'a str,
+    pub json: bool,
+}
+
+pub fn open(
+    plugin: Option<&str>,
+    options: FleetOptions<'_>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let contents = if options.hosts == "-" {
+        let mut contents = String::new();
+        std::io::stdin().read_to_string(&mut contents)?;
+        contents
+    } else {
+        std::fs::read_to_string(options.hosts)
+            .map_err(|err| format!("failed to read {}: {err}", options.hosts))?
+    };
+    let hosts = parse_hosts(&contents);
+    if hosts.is_empty() {
+        return Err(format!("no hosts in {}", options.hosts).into());
+    }
+    let shared_args: Vec<String> = options
+        .ssh_args
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect();
+
+    let existing = panes::list(plugin)?;
+    let mut opened = Vec::new();
+    for host in &hosts {
+        let name = format!("{}{}", options.prefix, host.destination);
+        let found = existing
+            .iter()
+            .find(|p| p.name.as_deref() == Some(name.as_str()));
+        let (selector, action) = match found {
+            Some(pane) => (
+                pane::pane_id_to_selector(&pane.id).unwrap_or_else(|| pane.id.clone()),
+                "existing",
+            ),
+            None => {
+                let command = ssh_command(host, &shared_args);
+                let selector = pane::launch_pane(
+                    plugin,
+                    &LaunchOptions {
+                        direction: None,
+                        floating: options.layout == FleetLayout::Floating,
+                        name: Some(&name),
+                        cwd: None,
+                        close_on_exit: false,
+                        in_place: false,
+                        start_suspended: false,
+                        command: &command,
+                    },
+                )?;
+                (selector, "created")
+            }
+        };
+        let entry = Opened {
+            host: &host.destination,
+            name,
+            selector,
+            action,
+        };
+        if !options.json {
+            println!("{:<8} {:<24} {}", entry.action, entry.name, entry.selector);
+        }
+        opened.push(entry);
+    }
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&opened)?);
+    }
+    Ok(())
+}
+
+/// One host per line; blank lines and `#` comments are skipped. Words after the destination
+/// are extra ssh arguments for that host.
+fn parse_hosts(contents: &str) -> Vec<Host> {
+    contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .filter_map(|line| {
+            let mut words = line.split_whitespace().map(str::to_string);
+            Some(Host {
+                destination: words.next()?,
+                args: words.collect(),
+            })
+        })
+        .collect()
+}
+
+/// `ssh [shared args] [host args] destination`
+fn ssh_command(host: &Host, shared_args: &[String]) -> Vec<String> {
+    std::iter::once("ssh".to_string())
+        .chain(shared_args.iter().cloned())
+        .chain(host.args.iter().cloned())
+        .chain(std::iter::once(host.destination.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_hosts_skips_comments_and_keeps_host_args() {
+        let hosts = parse_hosts("# web tier\nweb1\n\n  admin@web2 -p 2222  # bastion\n");
+        assert_eq!(
+            hosts,
+            vec![
+                Host {
+                    destination: "web1".to_string(),
+                    args: Vec::new(),
+                },
+                Host {
+                    destination: "admin@web2".to_string(),
+                    args: vec!["-p".to_string(), "2222".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn ssh_command_puts_destination_last() {
+        let host = Host {
+            destination: "web2".to_string(),
+            args: vec!["-p".to_string(), "2222".to_string()],
+        };
+        assert_eq!(
+            ssh_command(&host, &["-A".to_string()]),
+            vec!["ssh", "-A", "-p", "2222", "web2"]
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! `zjctl group`: name recurring multi-pane targets for the `group:NAME` selector
+
+use std::path::PathBuf;
+
+use zjctl_proto::PaneSelector;
+
+use crate::formats::{self, OutputFormat};
+use crate::groups::{self, GroupStore};
+use crate::output;
+
+fn store_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    groups::groups_path().ok_or_else(|| "cannot locate the config directory (set HOME)".into())
+}
+
+/// Save `selector` as group `name`, replacing an existing group of that name
+pub fn create(name: &str, selector: &str) -> Result<(), Box<dyn std::error::Error>> {
+    groups::validate_name(name)?;
+    let path = store_path()?;
+    let mut store = GroupStore::load(&path)?;
+    store.set(name, selector);
+    // Check the selector now rather than on first use; it may refer to other groups.
+    store
+        .expand(&format!("group:{name}"))?
+        .parse::<PaneSelector>()
+        .map_err(|err| format!("invalid selector: {err}"))?;
+    store.save(&path)?;
+    println!("group:{name} -> {selector}");
+    Ok(())
+}
+
+pub fn ls(output: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
+    let store = GroupStore::load(&store_path()?)?;
+    if output == OutputFormat::Tsv {
+        // name, selector
+        for group in &store.groups {
+            println!("{}", formats::tsv_row([&group.name, &group.selector]));
+        }
+        return Ok(());
+    }
+    if formats::print_document(output, &store.groups)? {
+        return Ok(());
+    }
+    if store.groups.is_empty() {
+        println!("No groups (create one with: zjctl group create NAME --panes SELECTOR)");
+        return Ok(());
+    }
+    let selector_width = output::flex_width(17, 12, 60);
+    println!("{}", output::header(&format!("{:<16} SELECTOR", "GROUP")));
+    println!("{}", output::rule(17 + selector_width));
+    for group in &store.groups {
+        println!(
+            "{:<16} {}",
+            group.name,
+            output::truncate(&group.selector, selector_width)
+        );
+    }
+    Ok(())
+}
+
+pub fn rm(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let path = store_path()?;
+    let mut store = GroupStore::load(&path)?;
+    if !store.remove(name) {
+        return Err(format!("no group named {name} (see: zjctl group ls)").into());
+    }
+    store.save(&path)?;
+    println!("removed group:{name}");
+    Ok(())
+}
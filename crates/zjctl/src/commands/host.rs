@@ -0,0 +1,56 @@
+//! Commands run on the host by the plugin (`host.exec`)
+//!
+//! Disabled unless `host_exec_allow` in a `load_plugins` entry for zrpc in config.kdl lists the
+//! programs the plugin may run. That instance publishes the list in the plugin's `/data` folder
+//! for the instance zjctl talks to; no RPC can set or widen it.
+
+use std::io::Write;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use zjctl_proto::methods;
+
+use crate::client;
+use crate::commands::pane::CommandExit;
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct HostExecResult {
+    /// `None` when the command could not be started or was killed by a signal
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub stdout: String,
+    #[serde(default)]
+    pub stderr: String,
+}
+
+pub fn exec(
+    plugin: Option<&str>,
+    argv: &[String],
+    cwd: Option<&str>,
+    timeout: f64,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if argv.is_empty() {
+        return Err("nothing to run (pass the command after --)".into());
+    }
+    let wait = Duration::try_from_secs_f64(timeout)
+        .map_err(|_| format!("invalid --timeout: {timeout}"))?;
+    let mut params = serde_json::json!({ "argv": argv, "wait_ms": wait.as_millis() as u64 });
+    if let Some(cwd) = cwd {
+        params["cwd"] = serde_json::Value::String(cwd.to_string());
+    }
+    let result: HostExecResult =
+        serde_json::from_value(client::rpc_call(plugin, methods::HOST_EXEC, params)?)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    } else {
+        std::io::stdout().write_all(result.stdout.as_bytes())?;
+        std::io::stderr().write_all(result.stderr.as_bytes())?;
+    }
+    match result.exit_code {
+        Some(0) => Ok(()),
+        Some(code) => Err(Box::new(CommandExit(code))),
+        None => Err(format!("{} did not exit normally", argv[0]).into()),
+    }
+}
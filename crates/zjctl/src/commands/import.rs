@@ -0,0 +1,1094 @@
+//! `zjctl import tmux-script`: translate a shell script of tmux commands
+//!
+//! Each `tmux` command the translator understands (new-session, new-window, split-window,
+//! send-keys, select-pane, select-window, rename-window, select-layout, kill-pane) becomes the
+//! zjctl calls that do the same in the Zellij session the script runs in: windows become
+//! tabs, and each pane's selector is kept in a shell variable so later `-t` targets can name
+//! it. Other shell lines are copied as they are. With `--to layout` the panes (and the
+//! commands typed into them) become a file for `zjctl layout apply` instead.
+//!
+//! Anything that can't be translated is kept as a `# not translated:` comment and reported
+//! on stderr with its line number.
+
+use std::io::Read;
+
+use clap::ValueEnum;
+use zjctl_proto::encode_key;
+
+use crate::output;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ImportFormat {
+    /// A shell script of zjctl commands
+    #[default]
+    Script,
+    /// A KDL layout for `zjctl layout apply` (panes and their commands only)
+    Layout,
+}
+
+pub fn tmux_script(path: &str, format: ImportFormat) -> Result<(), Box<dyn std::error::Error>> {
+    let source = if path == "-" {
+        let mut source = String::new();
+        std::io::stdin().read_to_string(&mut source)?;
+        source
+    } else {
+        std::fs::read_to_string(path).map_err(|err| format!("failed to read {path}: {err}"))?
+    };
+    let translation = translate(&source, path, format);
+    print!("{}", translation.output);
+    for warning in &translation.warnings {
+        output::warn(warning);
+    }
+    Ok(())
+}
+
+struct Translation {
+    output: String,
+    warnings: Vec<String>,
+}
+
+fn translate(source: &str, origin: &str, format: ImportFormat) -> Translation {
+    let mut translator = Translator::new(format);
+    for (line_no, line) in logical_lines(source) {
+        translator.line_no = line_no;
+        if line_no == 1 && line.starts_with("#!") {
+            continue;
+        }
+        translator.line(&line);
+    }
+    let output = match format {
+        ImportFormat::Script => translator.script(origin),
+        ImportFormat::Layout => translator.layout(origin),
+    };
+    Translation {
+        output,
+        warnings: translator.warnings,
+    }
+}
+
+/// Lines with backslash-newline continuations joined, numbered by where they start
+fn logical_lines(source: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut pending: Option<(usize, String)> = None;
+    for (index, line) in source.lines().enumerate() {
+        let (start, mut text) = pending.take().unwrap_or((index + 1, String::new()));
+        let trailing = line.len() - line.trim_end_matches('\\').len();
+        if trailing % 2 == 1 {
+            text.push_str(&line[..line.len() - 1]);
+            pending = Some((start, text));
+        } else {
+            text.push_str(line);
+            lines.push((start, text));
+        }
+    }
+    lines.extend(pending);
+    lines
+}
+
+/// One shell word; `expand` when it held a `$` the shell would expand
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Word {
+    text: String,
+    expand: bool,
+}
+
+impl Word {
+    fn literal(text: &str) -> Self {
+        Word {
+            text: text.to_string(),
+            expand: false,
+        }
+    }
+
+    /// Quoted for a POSIX shell, keeping `$` expansions working
+    fn quoted(&self) -> String {
+        let plain = |c: char| c.is_ascii_alphanumeric() || "_-./:=@%+,~".contains(c);
+        if !self.text.is_empty() && self.text.chars().all(plain) {
+            self.text.clone()
+        } else if self.expand {
+            let mut quoted = String::from("\"");
+            for c in self.text.chars() {
+                if matches!(c, '"' | '\\' | '`') {
+                    quoted.push('\\');
+                }
+                quoted.push(c);
+            }
+            quoted.push('"');
+            quoted
+        } else {
+            format!("'{}'", self.text.replace('\'', r"'\''"))
+        }
+    }
+}
+
+/// Split a line into commands (separated by `;` or `&&`) of shell words, or `None` when the
+/// line uses more of the shell than that (pipes, redirections, substitutions, ...)
+fn split_words(line: &str) -> Option<Vec<Vec<Word>>> {
+    let mut commands = Vec::new();
+    let mut words = Vec::new();
+    let mut word: Option<Word> = None;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' => words.extend(word.take()),
+            '#' if word.is_none() => break,
+            ';' => {
+                words.extend(word.take());
+                commands.push(std::mem::take(&mut words));
+            }
+            '&' if chars.peek() == Some(&'&') => {
+                chars.next();
+                words.extend(word.take());
+                commands.push(std::mem::take(&mut words));
+            }
+            '&' | '|' | '<' | '>' | '(' | ')' | '`' => return None,
+            '\'' => {
+                let word = word.get_or_insert_with(|| Word::literal(""));
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        c => word.text.push(c),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(|| Word::literal(""));
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => match chars.next()? {
+                            c @ ('$' | '`' | '"' | '\\') => word.text.push(c),
+                            c => {
+                                word.text.push('\\');
+                                word.text.push(c);
+                            }
+                        },
+                        '`' => return None,
+                        '$' if chars.peek() == Some(&'(') => return None,
+                        '$' => {
+                            word.expand = true;
+                            word.text.push('$');
+                        }
+                        c => word.text.push(c),
+                    }
+                }
+            }
+            '\\' => {
+                let escaped = chars.next()?;
+                word.get_or_insert_with(|| Word::literal(""))
+                    .text
+                    .push(escaped);
+            }
+            '$' => {
+                if chars.peek() == Some(&'(') {
+                    return None;
+                }
+                let word = word.get_or_insert_with(|| Word::literal(""));
+                word.expand = true;
+                word.text.push('$');
+            }
+            c => word.get_or_insert_with(|| Word::literal("")).text.push(c),
+        }
+    }
+    words.extend(word);
+    commands.push(words);
+    commands.retain(|words| !words.is_empty());
+    Some(commands)
+}
+
+/// A tmux command's flags (`-t target`, `-dh`) and the arguments after them
+struct Args {
+    flags: Vec<(char, Option<Word>)>,
+    positional: Vec<Word>,
+}
+
+impl Args {
+    /// `with_value` lists the flags that take a value, as in getopt
+    fn parse(words: &[Word], with_value: &str) -> Result<Self, String> {
+        let mut flags = Vec::new();
+        let mut rest = words.iter();
+        let mut positional = Vec::new();
+        while let Some(word) = rest.next() {
+            if word.text == "--" {
+                break;
+            }
+            if !word.text.starts_with('-') || word.text.len() < 2 {
+                positional.push(word.clone());
+                break;
+            }
+            for (index, flag) in word.text[1..].char_indices() {
+                if !with_value.contains(flag) {
+                    flags.push((flag, None));
+                    continue;
+                }
+                let attached = &word.text[1 + index + flag.len_utf8()..];
+                let value = if attached.is_empty() {
+                    rest.next()
+                        .cloned()
+                        .ok_or_else(|| format!("-{flag} needs a value"))?
+                } else {
+                    Word {
+                        text: attached.to_string(),
+                        expand: word.expand,
+                    }
+                };
+                flags.push((flag, Some(value)));
+                break;
+            }
+        }
+        positional.extend(rest.cloned());
+        Ok(Args { flags, positional })
+    }
+
+    fn has(&self, flag: char) -> bool {
+        self.flags.iter().any(|(f, _)| *f == flag)
+    }
+
+    fn value(&self, flag: char) -> Option<&Word> {
+        self.flags
+            .iter()
+            .rev()
+            .find(|(f, _)| *f == flag)
+            .and_then(|(_, value)| value.as_ref())
+    }
+
+    /// The trailing shell command of new-session/new-window/split-window as one string
+    fn command(&self) -> Option<Word> {
+        match self.positional.as_slice() {
+            [] => None,
+            [word] => Some(word.clone()),
+            words => Some(Word {
+                text: words.iter().map(Word::quoted).collect::<Vec<_>>().join(" "),
+                expand: words.iter().any(|word| word.expand),
+            }),
+        }
+    }
+}
+
+struct Window {
+    /// As written in the script, so `$VAR` in it still expands
+    name: Word,
+    /// Indexes into `Translator::panes`, in tmux pane order
+    panes: Vec<usize>,
+    active: usize,
+}
+
+struct Pane {
+    window: usize,
+    /// Title from `select-pane -T`
+    title: Option<String>,
+    direction: Option<&'static str>,
+    cwd: Option<Word>,
+    /// The command tmux ran instead of a shell
+    command: Option<Word>,
+    /// Lines typed with send-keys (`--to layout`)
+    typed: Vec<String>,
+    closed: bool,
+}
+
+impl Pane {
+    fn var(index: usize) -> String {
+        format!("\"$pane{index}\"")
+    }
+}
+
+struct Translator {
+    format: ImportFormat,
+    session: Option<String>,
+    windows: Vec<Window>,
+    current: Option<usize>,
+    panes: Vec<Pane>,
+    base_index: usize,
+    pane_base_index: usize,
+    lines: Vec<String>,
+    warnings: Vec<String>,
+    line_no: usize,
+}
+
+impl Translator {
+    fn new(format: ImportFormat) -> Self {
+        Translator {
+            format,
+            session: None,
+            windows: Vec::new(),
+            current: None,
+            panes: Vec::new(),
+            base_index: 0,
+            pane_base_index: 0,
+            lines: Vec::new(),
+            warnings: Vec::new(),
+            line_no: 0,
+        }
+    }
+
+    fn warn(&mut self, message: impl std::fmt::Display) {
+        self.warnings
+            .push(format!("line {}: {message}", self.line_no));
+    }
+
+    fn emit(&mut self, line: String) {
+        self.lines.push(line);
+    }
+
+    fn line(&mut self, line: &str) {
+        let Some(commands) = split_words(line) else {
+            if mentions_tmux(line) {
+                self.warn("tmux used in a pipeline or substitution; copied as is");
+            }
+            self.emit(line.to_string());
+            return;
+        };
+        if !commands.iter().any(|words| words[0].text == "tmux") {
+            // Blank lines, comments, and plain shell are copied untouched.
+            self.emit(line.to_string());
+            return;
+        }
+        for words in commands {
+            if words[0].text == "tmux" {
+                self.tmux(&words[1..]);
+            } else {
+                let shell = words.iter().map(Word::quoted).collect::<Vec<_>>().join(" ");
+                self.emit(shell);
+            }
+        }
+    }
+
+    /// One `tmux ...` invocation: global options, then commands separated by `;`
+    fn tmux(&mut self, words: &[Word]) {
+        let mut words = words;
+        while let Some(flag) = words.first().filter(|word| word.text.starts_with('-')) {
+            let takes_value = matches!(flag.text.as_str(), "-L" | "-S" | "-f" | "-c" | "-T");
+            words = &words[if takes_value { 2 } else { 1 }.min(words.len())..];
+        }
+        if words.is_empty() {
+            self.tmux_command("new-session", &[]);
+            return;
+        }
+        for command in words.split(|word| word.text == ";") {
+            if let Some((name, args)) = command.split_first() {
+                self.tmux_command(&name.text, args);
+            }
+        }
+    }
+
+    fn tmux_command(&mut self, name: &str, args: &[Word]) {
+        let original = std::iter::once(Word::literal(name))
+            .chain(args.iter().cloned())
+            .map(|word| word.quoted())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let result = match name {
+            "new-session" | "new" => self.new_window(args, true),
+            "new-window" | "neww" => self.new_window(args, false),
+            "split-window" | "splitw" => self.split_window(args),
+            "send-keys" | "send" => self.send_keys(args),
+            "select-pane" | "selectp" => self.select_pane(args),
+            "select-window" | "selectw" => self.select_window(args),
+            "rename-window" | "renamew" => self.rename_window(args),
+            "select-layout" | "selectl" => self.select_layout(args),
+            "kill-pane" | "killp" => self.kill_pane(args),
+            "set-option" | "set" | "set-window-option" | "setw" => {
+                self.set_option(args);
+                self.emit(format!("# not translated: tmux {original}"));
+                Ok(())
+            }
+            "attach-session" | "attach" | "a" | "at" | "switch-client" | "switchc" => {
+                self.emit(format!(
+                    "# not needed: tmux {original} (the script runs inside Zellij)"
+                ));
+                Ok(())
+            }
+            other => Err(format!("tmux {other} has no zjctl equivalent")),
+        };
+        if let Err(err) = result {
+            self.warn(err);
+            self.emit(format!("# not translated: tmux {original}"));
+        }
+    }
+
+    fn new_window(&mut self, words: &[Word], session: bool) -> Result<(), String> {
+        let args = if session {
+            Args::parse(words, "cefnstxyF")?
+        } else {
+            Args::parse(words, "cenFt")?
+        };
+        if session {
+            let name = args.value('s').map(|word| word.text.clone());
+            match (&self.session, name) {
+                (None, Some(name)) => {
+                    self.emit(format!(
+                        "# tmux session {name}: its windows open as tabs of this Zellij session"
+                    ));
+                    self.session = Some(name);
+                }
+                (Some(_), _) => {
+                    self.warn("several tmux sessions; every window opens in this Zellij session")
+                }
+                (None, None) => {}
+            }
+        }
+
+        let index = self.windows.len();
+        let name = args
+            .value('n')
+            .cloned()
+            .unwrap_or_else(|| Word::literal(&format!("window-{}", self.base_index + index)));
+        let pane = self.panes.len();
+        self.panes.push(Pane {
+            window: index,
+            title: None,
+            direction: None,
+            cwd: args.value('c').cloned(),
+            command: args.command(),
+            typed: Vec::new(),
+            closed: false,
+        });
+        self.windows.push(Window {
+            name: name.clone(),
+            panes: vec![pane],
+            active: pane,
+        });
+        let previous = self.current;
+        let detached = args.has('d') && previous.is_some();
+        if !detached {
+            self.current = Some(index);
+        }
+
+        if self.format == ImportFormat::Script {
+            let mut tab_new = format!("zjctl tab new --name {}", name.quoted());
+            if let Some(cwd) = args.value('c') {
+                tab_new.push_str(&format!(" --cwd {}", cwd.quoted()));
+            }
+            self.emit(tab_new);
+            self.emit(format!("pane{pane}=$(tab_pane {})", name.quoted()));
+            if let Some(command) = args.command() {
+                self.emit(format!(
+                    "zjctl pane send --pane {} -- {}",
+                    Pane::var(pane),
+                    command.quoted()
+                ));
+            }
+            if let (true, Some(previous)) = (detached, previous) {
+                let previous = self.windows[previous].name.quoted();
+                self.emit(format!("zjctl tab focus {previous}"));
+            }
+        }
+        Ok(())
+    }
+
+    fn split_window(&mut self, words: &[Word]) -> Result<(), String> {
+        let args = Args::parse(words, "celptF")?;
+        let target = self.resolve_pane(args.value('t'))?;
+        if args.value('l').is_some() || args.value('p').is_some() || args.has('f') {
+            self.warn("split-window sizes (-l, -p, -f) are not translated; use zjctl pane resize");
+        }
+        let direction = match (args.has('h'), args.has('b')) {
+            (true, false) => "right",
+            (true, true) => "left",
+            (false, false) => "down",
+            (false, true) => "up",
+        };
+        self.focus_pane(target);
+
+        let window = self.panes[target].window;
+        let pane = self.panes.len();
+        self.panes.push(Pane {
+            window,
+            title: None,
+            direction: Some(direction),
+            cwd: args.value('c').cloned(),
+            command: args.command(),
+            typed: Vec::new(),
+            closed: false,
+        });
+        self.windows[window].panes.push(pane);
+
+        if self.format == ImportFormat::Script {
+            let mut launch = format!("pane{pane}=$(zjctl pane launch --direction {direction}");
+            if let Some(cwd) = args.value('c') {
+                launch.push_str(&format!(" --cwd {}", cwd.quoted()));
+            }
+            if let Some(command) = args.command() {
+                // tmux closes the pane when its command exits.
+                launch.push_str(&format!(" --close-on-exit -- sh -c {}", command.quoted()));
+            }
+            launch.push(')');
+            self.emit(launch);
+        }
+        if args.has('d') {
+            if self.format == ImportFormat::Script {
+                self.emit(format!("zjctl pane focus --pane {}", Pane::var(target)));
+            }
+        } else {
+            self.windows[window].active = pane;
+        }
+        Ok(())
+    }
+
+    fn send_keys(&mut self, words: &[Word]) -> Result<(), String> {
+        let args = Args::parse(words, "cNt")?;
+        if args.has('X') || args.has('H') || args.has('M') {
+            return Err("send-keys -X, -H, and -M are not supported".to_string());
+        }
+        let target = self.resolve_pane(args.value('t'))?;
+        let repeat = match args.value('N') {
+            Some(count) => count
+                .text
+                .parse::<usize>()
+                .map_err(|_| format!("invalid send-keys -N {}", count.text))?,
+            None => 1,
+        };
+        let keys = vec![args.positional.clone(); repeat].concat();
+        let sends = group_keys(&keys, args.has('l'));
+
+        if self.format == ImportFormat::Layout {
+            for send in sends {
+                match send {
+                    Send::Text { text, enter: true } => self.panes[target].typed.push(text.text),
+                    Send::Text { text, enter: false } => self.warn(format!(
+                        "text typed without Enter is dropped: {}",
+                        text.text
+                    )),
+                    Send::Keys(keys) => self.warn(format!(
+                        "keys are dropped in a layout: {}",
+                        keys.iter()
+                            .map(|key| key.text.as_str())
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    )),
+                }
+            }
+            return Ok(());
+        }
+        let pane = Pane::var(target);
+        for send in sends {
+            let line = match send {
+                Send::Text { text, enter: true } => {
+                    format!("zjctl pane send --pane {pane} -- {}", text.quoted())
+                }
+                Send::Text { text, enter: false } => {
+                    format!(
+                        "zjctl pane send --pane {pane} --enter=false -- {}",
+                        text.quoted()
+                    )
+                }
+                Send::Keys(keys) => format!(
+                    "zjctl pane send-keys --pane {pane} {}",
+                    keys.iter().map(Word::quoted).collect::<Vec<_>>().join(" ")
+                ),
+            };
+            self.emit(line);
+        }
+        Ok(())
+    }
+
+    fn select_pane(&mut self, words: &[Word]) -> Result<(), String> {
+        let args = Args::parse(words, "Tt")?;
+        if ['L', 'R', 'U', 'D', 'l'].iter().any(|flag| args.has(*flag)) {
+            return Err("select-pane -L/-R/-U/-D/-l has no zjctl equivalent".to_string());
+        }
+        let target = self.resolve_pane(args.value('t'))?;
+        if let Some(title) = args.value('T') {
+            self.panes[target].title = Some(title.text.clone());
+            if self.format == ImportFormat::Script {
+                self.emit(format!(
+                    "zjctl pane rename --pane {} {}",
+                    Pane::var(target),
+                    title.quoted()
+                ));
+            }
+        }
+        self.focus_pane(target);
+        Ok(())
+    }
+
+    fn select_window(&mut self, words: &[Word]) -> Result<(), String> {
+        let args = Args::parse(words, "t")?;
+        if ['l', 'n', 'p'].iter().any(|flag| args.has(*flag)) {
+            return Err("select-window -l/-n/-p has no zjctl equivalent".to_string());
+        }
+        let window = self.resolve_window(args.value('t'))?;
+        self.focus_window(window);
+        Ok(())
+    }
+
+    fn rename_window(&mut self, words: &[Word]) -> Result<(), String> {
+        let args = Args::parse(words, "t")?;
+        let window = self.resolve_window(args.value('t'))?;
+        let [name] = args.positional.as_slice() else {
+            return Err("rename-window needs one name".to_string());
+        };
+        let old = std::mem::replace(&mut self.windows[window].name, name.clone());
+        if self.format == ImportFormat::Script {
+            self.emit(format!(
+                "zjctl tab rename --tab {} {}",
+                old.quoted(),
+                name.quoted()
+            ));
+        }
+        Ok(())
+    }
+
+    fn select_layout(&mut self, words: &[Word]) -> Result<(), String> {
+        let args = Args::parse(words, "t")?;
+        let window = self.resolve_window(args.value('t'))?;
+        let preset = match args.positional.first().map(|word| word.text.as_str()) {
+            Some(
+                preset
+                @ ("even-horizontal" | "even-vertical" | "main-horizontal" | "main-vertical"),
+            ) => preset,
+            Some("tiled") => "grid",
+            Some(other) => return Err(format!("layout {other} has no zjctl tab layout preset")),
+            None => return Err("select-layout without a layout name".to_string()),
+        };
+        if self.format == ImportFormat::Layout {
+            self.warn("select-layout is not part of a layout file; run zjctl tab layout after");
+            return Ok(());
+        }
+        self.focus_window(window);
+        self.emit(format!("zjctl tab layout {preset}"));
+        Ok(())
+    }
+
+    fn kill_pane(&mut self, words: &[Word]) -> Result<(), String> {
+        let args = Args::parse(words, "t")?;
+        if args.has('a') {
+            return Err("kill-pane -a has no zjctl equivalent".to_string());
+        }
+        let target = self.resolve_pane(args.value('t'))?;
+        let window = self.panes[target].window;
+        let window = &mut self.windows[window];
+        window.panes.retain(|pane| *pane != target);
+        if window.active == target {
+            window.active = window.panes.last().copied().unwrap_or(target);
+        }
+        self.panes[target].closed = true;
+        if self.format == ImportFormat::Script {
+            self.emit(format!(
+                "zjctl pane close --pane {} --force",
+                Pane::var(target)
+            ));
+        }
+        Ok(())
+    }
+
+    /// `base-index` and `pane-base-index` change what `-t` numbers mean
+    fn set_option(&mut self, words: &[Word]) {
+        let Ok(args) = Args::parse(words, "t") else {
+            return;
+        };
+        if let [option, value] = args.positional.as_slice() {
+            let value = value.text.parse().ok();
+            match (option.text.as_str(), value) {
+                ("base-index", Some(value)) => self.base_index = value,
+                ("pane-base-index", Some(value)) => self.pane_base_index = value,
+                _ => {}
+            }
+        }
+    }
+
+    /// Make `pane` the focused pane, as tmux does before splitting it
+    fn focus_pane(&mut self, pane: usize) {
+        let window = self.panes[pane].window;
+        if self.current == Some(window) && self.windows[window].active == pane {
+            return;
+        }
+        self.current = Some(window);
+        self.windows[window].active = pane;
+        if self.format == ImportFormat::Script {
+            self.emit(format!("zjctl pane focus --pane {}", Pane::var(pane)));
+        }
+    }
+
+    fn focus_window(&mut self, window: usize) {
+        if self.current == Some(window) {
+            return;
+        }
+        self.current = Some(window);
+        if self.format == ImportFormat::Script {
+            let name = self.windows[window].name.quoted();
+            self.emit(format!("zjctl tab focus {name}"));
+        }
+    }
+
+    /// `session:window` (or a bare window) to an index into `windows`
+    fn resolve_window(&self, target: Option<&Word>) -> Result<usize, String> {
+        let current = self.current.ok_or("no tmux window has been created yet")?;
+        let Some(target) = target else {
+            return Ok(current);
+        };
+        let text = target.text.trim_start_matches('=');
+        let window = match text.split_once(':') {
+            Some((_, window)) => window,
+            None if self.session.as_deref() == Some(text) => "",
+            None => text,
+        };
+        let window = window.split_once('.').map_or(window, |(window, _)| window);
+        if window.is_empty() {
+            return Ok(current);
+        }
+        if let Some(index) = self.windows.iter().position(|w| w.name.text == window) {
+            return Ok(index);
+        }
+        window
+            .parse::<usize>()
+            .ok()
+            .and_then(|number| number.checked_sub(self.base_index))
+            .filter(|index| *index < self.windows.len())
+            .ok_or_else(|| format!("unknown tmux target {}", target.text))
+    }
+
+    /// `session:window.pane` (or any shorter form) to an index into `panes`
+    fn resolve_pane(&self, target: Option<&Word>) -> Result<usize, String> {
+        if let Some(target) = target.filter(|target| target.text.starts_with('%')) {
+            return Err(format!(
+                "tmux pane ids like {} are not supported",
+                target.text
+            ));
+        }
+        // A bare number is a pane of the current window (`select-pane -t 1`).
+        if let Some(target) = target.filter(|target| target.text.parse::<usize>().is_ok()) {
+            let current = self.resolve_window(None)?;
+            let dotted = Word::literal(&format!(".{}", target.text));
+            return self.pane_in(current, Some(&dotted));
+        }
+        let window = self.resolve_window(target)?;
+        self.pane_in(window, target)
+    }
+
+    fn pane_in(&self, window: usize, target: Option<&Word>) -> Result<usize, String> {
+        let pane = target
+            .map(|target| {
+                target
+                    .text
+                    .rsplit_once(':')
+                    .map_or(&*target.text, |(_, rest)| rest)
+            })
+            .and_then(|rest| rest.split_once('.'))
+            .map(|(_, pane)| pane);
+        let window = &self.windows[window];
+        match pane {
+            None | Some("") => Ok(window.active),
+            Some(pane) => pane
+                .parse::<usize>()
+                .ok()
+                .and_then(|number| number.checked_sub(self.pane_base_index))
+                .and_then(|index| window.panes.get(index).copied())
+                .ok_or_else(|| format!("unknown tmux pane {pane} in window {}", window.name.text)),
+        }
+    }
+
+    fn script(&self, origin: &str) -> String {
+        let mut script = format!(
+            "#!/bin/sh\n# Translated from {origin} by `zjctl import tmux-script`; \
+             run it inside Zellij.\n\n{SCRIPT_HELPERS}\n"
+        );
+        for line in &self.lines {
+            script.push_str(line);
+            script.push('\n');
+        }
+        script
+    }
+
+    fn layout(&mut self, origin: &str) -> String {
+        if self.windows.len() > 1 {
+            self.warnings.push(format!(
+                "{} tmux windows; zjctl layout apply opens every pane in the current tab",
+                self.windows.len()
+            ));
+        }
+        let mut names: Vec<String> = Vec::new();
+        let mut layout =
+            format!("// Translated from {origin} by `zjctl import tmux-script`\nlayout {{\n");
+        for (index, pane) in self
+            .panes
+            .iter()
+            .enumerate()
+            .filter(|(_, pane)| !pane.closed)
+        {
+            let window = &self.windows[pane.window];
+            let position = window.panes.iter().position(|p| *p == index);
+            let fallback = match position {
+                Some(0) | None => window.name.text.clone(),
+                Some(index) => format!("{}-{index}", window.name.text),
+            };
+            let name = pane
+                .title
+                .clone()
+                .filter(|title| !names.contains(title))
+                .unwrap_or(fallback);
+            names.push(name.clone());
+
+            let mut node = format!("    pane name={}", kdl_string(&name));
+            if let Some(direction) = pane.direction {
+                node.push_str(&format!(" direction={}", kdl_string(direction)));
+            }
+            if let Some(cwd) = &pane.cwd {
+                node.push_str(&format!(" cwd={}", kdl_string(&cwd.text)));
+            }
+            let script = match (&pane.command, pane.typed.is_empty()) {
+                (Some(command), _) => Some(command.text.clone()),
+                // Typed lines run in a shell that stays open afterwards, as in tmux.
+                (None, false) => Some(format!(
+                    "{}; exec \"${{SHELL:-sh}}\"",
+                    pane.typed.join("; ")
+                )),
+                (None, true) => None,
+            };
+            if let Some(script) = script {
+                if pane.command.is_some() {
+                    node.push_str(" close_on_exit=true");
+                }
+                node.push_str(&format!(
+                    " command=\"sh\" {{\n        args \"-c\" {}\n    }}",
+                    kdl_string(&script)
+                ));
+            }
+            layout.push_str(&node);
+            layout.push('\n');
+        }
+        layout.push_str("}\n");
+        layout
+    }
+}
+
+/// Put ahead of the translated lines: new tabs open asynchronously, so their first pane is
+/// looked up by tab name until it shows
+const SCRIPT_HELPERS: &str = r#"# Selector of the first terminal pane in tab $1, once the tab has opened
+tab_pane() {
+    for _ in 1 2 3 4 5 6 7 8 9 10; do
+        pane=$(zjctl panes ls --type terminal -o tsv | awk -F'\t' -v tab="$1" '$4 == tab { print "id:" $1; exit }')
+        [ -n "$pane" ] && { echo "$pane"; return; }
+        sleep 0.2
+    done
+    echo "tab $1 did not open" >&2
+    return 1
+}
+"#;
+
+fn mentions_tmux(line: &str) -> bool {
+    line.split(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+        .any(|word| word == "tmux")
+}
+
+/// A double-quoted KDL string (its escapes match JSON's for everything scripts contain)
+fn kdl_string(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_default()
+}
+
+/// What one run of send-keys arguments turns into
+#[derive(Debug, PartialEq, Eq)]
+enum Send {
+    /// Literal text, followed by Enter when `enter`
+    Text {
+        text: Word,
+        enter: bool,
+    },
+    Keys(Vec<Word>),
+}
+
+/// Split send-keys arguments into literal text and key names, as tmux does: an argument that
+/// names a key (`Enter`, `C-c`, `Up`) is a key, anything else is typed as is
+fn group_keys(args: &[Word], literal: bool) -> Vec<Send> {
+    let is_key = |word: &Word| {
+        !literal && !word.expand && word.text.chars().count() > 1 && encode_key(&word.text).is_ok()
+    };
+    let is_enter = |word: &Word| {
+        word.text.eq_ignore_ascii_case("enter") || word.text.eq_ignore_ascii_case("c-m")
+    };
+    let mut sends = Vec::new();
+    let mut text: Option<Word> = None;
+    for word in args {
+        if !is_key(word) {
+            let text = text.get_or_insert_with(|| Word::literal(""));
+            text.text.push_str(&word.text);
+            text.expand |= word.expand;
+            continue;
+        }
+        if let Some(text) = text.take() {
+            if is_enter(word) {
+                sends.push(Send::Text { text, enter: true });
+                continue;
+            }
+            sends.push(Send::Text { text, enter: false });
+        }
+        match sends.last_mut() {
+            Some(Send::Keys(keys)) => keys.push(word.clone()),
+            _ => sends.push(Send::Keys(vec![word.clone()])),
+        }
+    }
+    if let Some(text) = text {
+        sends.push(Send::Text { text, enter: false });
+    }
+    sends
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(line: &str) -> Vec<String> {
+        split_words(line).unwrap()[0]
+            .iter()
+            .map(|word| word.text.clone())
+            .collect()
+    }
+
+    #[test]
+    fn split_words_handles_quotes_escapes_and_tmux_separators() {
+        assert_eq!(
+            words(r#"tmux send-keys -t dev:0 "npm run dev" C-m \; select-pane -t 1 # go"#),
+            vec![
+                "tmux",
+                "send-keys",
+                "-t",
+                "dev:0",
+                "npm run dev",
+                "C-m",
+                ";",
+                "select-pane",
+                "-t",
+                "1"
+            ]
+        );
+        assert_eq!(words("echo 'it'\\''s'"), vec!["echo", "it's"]);
+        assert!(split_words(r#"echo "$HOME""#).unwrap()[0][1].expand);
+        assert_eq!(split_words("cd app; tmux new -d").unwrap().len(), 2);
+        assert!(split_words("tmux has-session -t dev 2>/dev/null").is_none());
+        assert!(split_words("x=$(tmux display -p '#S')").is_none());
+    }
+
+    #[test]
+    fn send_keys_split_into_text_and_keys() {
+        let args: Vec<Word> = ["git status", "Enter", "C-c", "Up", "q"]
+            .iter()
+            .map(|text| Word::literal(text))
+            .collect();
+        assert_eq!(
+            group_keys(&args, false),
+            vec![
+                Send::Text {
+                    text: Word::literal("git status"),
+                    enter: true
+                },
+                Send::Keys(vec![Word::literal("C-c"), Word::literal("Up")]),
+                Send::Text {
+                    text: Word::literal("q"),
+                    enter: false
+                },
+            ]
+        );
+        assert_eq!(
+            group_keys(&args[..2], true),
+            vec![Send::Text {
+                text: Word::literal("git statusEnter"),
+                enter: false
+            }]
+        );
+    }
+
+    const SCRIPT: &str = r#"#!/bin/bash
+cd ~/app
+tmux new-session -d -s dev -n editor -c ~/app
+tmux send-keys -t dev:editor "vim ." C-m
+tmux split-window -h -t dev:editor -c "$HOME/app"
+tmux send-keys -t dev:editor.1 'npm run dev' Enter
+tmux new-window -t dev -n logs 'tail -f app.log'
+tmux select-window -t dev:editor \; select-pane -t 0
+tmux bind-key C-a send-prefix
+tmux attach -t dev
+"#;
+
+    #[test]
+    fn translates_a_tmux_script_to_zjctl_calls() {
+        let translation = translate(SCRIPT, "dev.sh", ImportFormat::Script);
+        let body: Vec<&str> = translation
+            .output
+            .split_once("}\n\n")
+            .unwrap()
+            .1
+            .lines()
+            .collect();
+        assert_eq!(
+            body,
+            vec![
+                "cd ~/app",
+                "# tmux session dev: its windows open as tabs of this Zellij session",
+                "zjctl tab new --name editor --cwd ~/app",
+                "pane0=$(tab_pane editor)",
+                r#"zjctl pane send --pane "$pane0" -- 'vim .'"#,
+                r#"pane1=$(zjctl pane launch --direction right --cwd "$HOME/app")"#,
+                r#"zjctl pane send --pane "$pane1" -- 'npm run dev'"#,
+                "zjctl tab new --name logs",
+                "pane2=$(tab_pane logs)",
+                r#"zjctl pane send --pane "$pane2" -- 'tail -f app.log'"#,
+                "zjctl tab focus editor",
+                r#"zjctl pane focus --pane "$pane0""#,
+                "# not translated: tmux bind-key C-a send-prefix",
+                "# not needed: tmux attach -t dev (the script runs inside Zellij)",
+            ]
+        );
+        assert_eq!(
+            translation.warnings,
+            vec!["line 9: tmux bind-key has no zjctl equivalent"]
+        );
+    }
+
+    #[test]
+    fn translates_a_tmux_script_to_a_layout() {
+        let translation = translate(SCRIPT, "dev.sh", ImportFormat::Layout);
+        let specs = crate::commands::layout::parse_layout("dev.kdl", &translation.output).unwrap();
+        let summary: Vec<(&str, Option<&str>, Vec<&str>)> = specs
+            .iter()
+            .map(|spec| {
+                (
+                    spec.name.as_str(),
+                    spec.direction.as_deref(),
+                    spec.command.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "editor",
+                    None,
+                    vec!["sh", "-c", "vim .; exec \"${SHELL:-sh}\""]
+                ),
+                (
+                    "editor-1",
+                    Some("right"),
+                    vec!["sh", "-c", "npm run dev; exec \"${SHELL:-sh}\""]
+                ),
+                ("logs", None, vec!["sh", "-c", "tail -f app.log"]),
+            ]
+        );
+        assert!(specs[2].close_on_exit);
+        assert!(translation
+            .warnings
+            .iter()
+            .any(|w| w.contains("2 tmux windows")));
+    }
+
+    #[test]
+    fn window_names_keep_their_expansions() {
+        let script = "tmux new-window -n \"build $USER\"\ntmux rename-window 'ci $USER'\n";
+        let translation = translate(script, "ci.sh", ImportFormat::Script);
+        let body: Vec<&str> = translation
+            .output
+            .split_once("}\n\n")
+            .unwrap()
+            .1
+            .lines()
+            .collect();
+        assert_eq!(
+            body,
+            vec![
+                r#"zjctl tab new --name "build $USER""#,
+                r#"pane0=$(tab_pane "build $USER")"#,
+                r#"zjctl tab rename --tab "build $USER" 'ci $USER'"#,
+            ]
+        );
+    }
+}
@@ -1,18 +1,40 @@
 //! Install the zrpc plugin
+//!
+//! Builds with the `embedded-plugin` feature carry the zrpc.wasm they were released with
+//! (`ZJCTL_EMBED_WASM` names the file at build time), so installing writes the matching plugin
+//! without curl or network access. Other builds download the latest release.
 
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::Duration;
 
 use crate::client;
+use crate::output;
+use crate::paths;
+use crate::progress::{Progress, ProgressFormat};
 use crate::zellij;
 
+/// zrpc.wasm built into this binary
+#[cfg(feature = "embedded-plugin")]
+const EMBEDDED_PLUGIN: Option<&[u8]> = Some(include_bytes!(env!(
+    "ZJCTL_EMBED_WASM",
+    "set ZJCTL_EMBED_WASM to the zrpc.wasm to embed (build it with: cargo build --release -p zjctl-zrpc --target wasm32-wasip1)"
+)));
+#[cfg(not(feature = "embedded-plugin"))]
+const EMBEDDED_PLUGIN: Option<&[u8]> = None;
+
+/// How long `--load` waits for the new plugin to see the session's panes, tabs, and clients
+const READY_TIMEOUT: Duration = Duration::from_secs(5);
+
 pub fn run(
     plugin: Option<&str>,
     print: bool,
     force: bool,
+    download: bool,
     load: bool,
     auto_load: bool,
+    progress: Option<ProgressFormat>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let default_url = client::default_plugin_url();
     let plugin_url = plugin.unwrap_or(default_url.as_str());
@@ -24,9 +46,20 @@ pub fn run(
     let config_path = config_file_path();
     let config_url = plugin_url_for_config(plugin_url, &plugin_path);
 
+    let embedded = EMBEDDED_PLUGIN.filter(|_| !download);
+
     if print {
-        println!("install: {install_cmd}");
-        println!("install: {download_cmd}");
+        match embedded {
+            Some(bytes) => println!(
+                "install: write the embedded plugin ({} bytes) to {}",
+                bytes.len(),
+                plugin_path.display()
+            ),
+            None => {
+                println!("install: {install_cmd}");
+                println!("install: {download_cmd}");
+            }
+        }
         println!("load: {launch_cmd}");
         if auto_load {
             println!(
@@ -38,31 +71,56 @@ pub fn run(
         return Ok(());
     }
 
+    let mut progress = Progress::new("install", progress);
     if let Some(parent) = plugin_path.parent() {
         fs::create_dir_all(parent)?;
     }
 
     if plugin_path.exists() && !force {
         println!("plugin file exists: {}", plugin_path.display());
+    } else if let Some(bytes) = embedded {
+        progress.event(
+            "writing",
+            serde_json::json!({ "path": plugin_path.display().to_string() }),
+        );
+        fs::write(&plugin_path, bytes)?;
+        println!(
+            "plugin installed: {} (embedded, zjctl {})",
+            plugin_path.display(),
+            env!("CARGO_PKG_VERSION")
+        );
     } else {
+        progress.event(
+            "downloading",
+            serde_json::json!({ "path": plugin_path.display().to_string() }),
+        );
         download_plugin(&plugin_path)?;
         println!("plugin installed: {}", plugin_path.display());
     }
 
     if load {
-        let launch_url = client::plugin_launch_url(plugin_url, Some(&plugin_path));
-        let status = zellij::command()
-            .args(["action", "launch-plugin", &launch_url])
-            .status()
-            .map_err(|err| format!("failed to run zellij: {err}"))?;
-        if !status.success() {
-            return Err(format!("zellij action launch-plugin failed: {status:?}").into());
+        progress.event("loading", serde_json::json!({}));
+        launch_plugin(&client::plugin_launch_url(plugin_url, Some(&plugin_path)))?;
+        // launch-plugin returns before the plugin has seen any state; commands run straight
+        // after install would otherwise find no panes.
+        progress.event("waiting", serde_json::json!({}));
+        if client::wait_ready(Some(plugin_url), READY_TIMEOUT)? {
+            println!("plugin: ready");
+        } else {
+            output::warn(format!(
+                "plugin loaded but has not seen the session state after {}s (permissions not granted yet?)",
+                READY_TIMEOUT.as_secs()
+            ));
         }
     } else {
         println!("load: {launch_cmd}");
     }
 
     if auto_load {
+        progress.event(
+            "configuring",
+            serde_json::json!({ "config": config_path.display().to_string() }),
+        );
         let updated = ensure_auto_load_config(&config_path, &config_url)?;
         if updated {
             println!("config: updated {}", config_path.display());
@@ -71,6 +129,31 @@ pub fn run(
         }
     }
 
+    progress.event("done", serde_json::json!({}));
+    Ok(())
+}
+
+/// Write the embedded plugin to `path`, or download the latest release when this build has none
+pub(crate) fn install_plugin(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    match EMBEDDED_PLUGIN {
+        Some(bytes) => fs::write(path, bytes)?,
+        None => download_plugin(path)?,
+    }
+    Ok(())
+}
+
+/// `zellij action launch-plugin` in the current session
+pub(crate) fn launch_plugin(launch_url: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let status = zellij::command()
+        .args(["action", "launch-plugin", launch_url])
+        .status()
+        .map_err(|err| format!("failed to run zellij: {err}"))?;
+    if !status.success() {
+        return Err(format!("zellij action launch-plugin failed: {status:?}").into());
+    }
     Ok(())
 }
 
@@ -87,54 +170,33 @@ fn download_plugin(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
     }
 }
 
-fn config_file_path() -> PathBuf {
+pub(crate) fn config_file_path() -> PathBuf {
     if let Ok(path) = std::env::var("ZELLIJ_CONFIG_FILE") {
         return PathBuf::from(path);
     }
     if let Ok(dir) = std::env::var("ZELLIJ_CONFIG_DIR") {
         return PathBuf::from(dir).join("config.kdl");
     }
-    let base = if let Ok(dir) = std::env::var("XDG_CONFIG_HOME") {
-        PathBuf::from(dir)
-    } else if cfg!(windows) {
-        if let Ok(dir) = std::env::var("APPDATA") {
-            PathBuf::from(dir)
-        } else if let Ok(dir) = std::env::var("USERPROFILE") {
-            PathBuf::from(dir)
-        } else {
-            PathBuf::from(".")
-        }
-    } else if let Ok(home) = std::env::var("HOME") {
-        PathBuf::from(home).join(".config")
-    } else {
-        PathBuf::from(".")
-    };
-    base.join("zellij").join("config.kdl")
+    paths::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("zellij")
+        .join("config.kdl")
 }
 
-fn plugin_url_for_config(plugin_url: &str, plugin_path: &Path) -> String {
+pub(crate) fn plugin_url_for_config(plugin_url: &str, plugin_path: &Path) -> String {
     if plugin_url.contains("://") && !plugin_url.starts_with("file:") {
         return plugin_url.to_string();
     }
-    let path = shorten_home(plugin_path);
-    format!("file:{}", path)
+    format!("file:{}", paths::shorten_home(plugin_path))
 }
 
-fn shorten_home(path: &Path) -> String {
-    if let Ok(home) = std::env::var("HOME") {
-        let home_path = PathBuf::from(home);
-        if let Ok(stripped) = path.strip_prefix(&home_path) {
-            let rel = stripped.display().to_string();
-            if rel.is_empty() {
-                return "~".to_string();
-            }
-            return format!("~/{}", rel);
-        }
-    }
-    path.display().to_string()
+/// Whether `config.kdl` at `path` already loads `plugin_url` (quoted, as
+/// [`ensure_auto_load_config`] writes it)
+pub(crate) fn auto_load_configured(path: &Path, plugin_url: &str) -> bool {
+    fs::read_to_string(path).is_ok_and(|contents| contents.contains(&format!("\"{plugin_url}\"")))
 }
 
-fn ensure_auto_load_config(
+pub(crate) fn ensure_auto_load_config(
     path: &Path,
     plugin_url: &str,
 ) -> Result<bool, Box<dyn std::error::Error>> {
@@ -191,6 +253,18 @@ mod tests {
         let _ = fs::remove_file(path);
     }
 
+    #[test]
+    fn auto_load_configured_after_ensure() {
+        let path = std::env::temp_dir().join(format!("zjctl-config-{}.kdl", uuid::Uuid::new_v4()));
+        let plugin_url = "file:/tmp/zrpc.wasm";
+
+        assert!(!auto_load_configured(&path, plugin_url));
+        ensure_auto_load_config(&path, plugin_url).expect("write config");
+        assert!(auto_load_configured(&path, plugin_url));
+
+        let _ = fs::remove_file(path);
+    }
+
     #[test]
     fn ensure_auto_load_config_quotes_plugin_url() {
         let path = std::env::temp_dir().join(format!("zjctl-config-{}.kdl", uuid::Uuid::new_v4()));
@@ -0,0 +1,366 @@
+//! Declarative pane layouts (`zjctl layout apply`)
+//!
+//! A layout lists named panes; applying it launches the ones that do not exist yet and leaves
+//! panes that already carry the name alone, so applying twice is a no-op. An existing pane
+//! whose command or directory differs from the layout is reported, not restarted: close it and
+//! apply again to recreate it.
+
+use std::path::Path;
+
+use kdl::{KdlDocument, KdlNode};
+use serde::{Deserialize, Serialize};
+
+use crate::commands::pane::{self, LaunchOptions};
+use crate::commands::panes::{self, PaneInfo};
+use crate::output;
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+pub struct PaneSpec {
+    pub name: String,
+    /// Program and arguments; empty opens the default shell
+    #[serde(default)]
+    pub command: Vec<String>,
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// Split direction relative to the focused pane (`right`, `down`, ...)
+    #[serde(default)]
+    pub direction: Option<String>,
+    #[serde(default)]
+    pub floating: bool,
+    #[serde(default)]
+    pub close_on_exit: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct LayoutFile {
+    panes: Vec<PaneSpec>,
+}
+
+/// Printed once per pane in the layout
+#[derive(Debug, Serialize)]
+struct Applied<'a> {
+    name: &'a str,
+    selector: String,
+    /// `created`, `existing`, or `differs` (an existing pane not matching the layout)
+    action: &'a str,
+    /// How an existing pane differs from the layout
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    differences: Vec<String>,
+}
+
+pub fn apply(
+    plugin: Option<&str>,
+    path: &str,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|err| format!("failed to read {path}: {err}"))?;
+    let specs = parse_layout(path, &contents)?;
+
+    let existing = panes::list(plugin)?;
+    let mut applied = Vec::new();
+    for spec in &specs {
+        let found = existing
+            .iter()
+            .find(|p| p.name.as_deref() == Some(spec.name.as_str()));
+        let entry = match found {
+            Some(pane) => {
+                let cwd = spec.cwd.as_deref().map(pane::absolute_cwd).transpose()?;
+                let differences = differences(spec, cwd.as_deref(), pane);
+                for difference in &differences {
+                    output::warn(format_args!(
+                        "{}: {difference} (close the pane and apply again to recreate it)",
+                        spec.name
+                    ));
+                }
+                Applied {
+                    name: &spec.name,
+                    selector: pane::pane_id_to_selector(&pane.id)
+                        .unwrap_or_else(|| pane.id.clone()),
+                    action: if differences.is_empty() {
+                        "existing"
+                    } else {
+                        "differs"
+                    },
+                    differences,
+                }
+            }
+            None => Applied {
+                name: &spec.name,
+                selector: pane::launch_pane(
+                    plugin,
+                    &LaunchOptions {
+                        direction: spec.direction.as_deref(),
+                        floating: spec.floating,
+                        name: Some(&spec.name),
+                        cwd: spec.cwd.as_deref(),
+                        close_on_exit: spec.close_on_exit,
+                        in_place: false,
+                        start_suspended: false,
+                        command: &spec.command,
+                    },
+                )?,
+                action: "created",
+                differences: Vec::new(),
+            },
+        };
+        if !json {
+            println!("{:<8} {:<24} {}", entry.action, entry.name, entry.selector);
+        }
+        applied.push(entry);
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&applied)?);
+    }
+    Ok(())
+}
+
+/// Where an existing pane does not match `spec` (`cwd` is the spec's, made absolute). A pane
+/// without a known directory is not reported for it; a shell pane has no command.
+fn differences(spec: &PaneSpec, cwd: Option<&str>, pane: &PaneInfo) -> Vec<String> {
+    let mut differences = Vec::new();
+    let running: Vec<&str> = pane
+        .command
+        .as_deref()
+        .map(|command| command.split_whitespace().collect())
+        .unwrap_or_default();
+    let program = |arg: &str| Path::new(arg).file_name().map(|name| name.to_os_string());
+    let same_command = running.len() == spec.command.len()
+        && running
+            .iter()
+            .zip(&spec.command)
+            .enumerate()
+            .all(|(index, (running, wanted))| {
+                *running == wanted || (index == 0 && program(running) == program(wanted))
+            });
+    if !same_command {
+        let describe = |command: String| {
+            if command.is_empty() {
+                "a shell".to_string()
+            } else {
+                command
+            }
+        };
+        differences.push(format!(
+            "runs {}, the layout has {}",
+            describe(running.join(" ")),
+            describe(spec.command.join(" "))
+        ));
+    }
+    if let (Some(wanted), Some(actual)) = (cwd, pane.cwd.as_deref()) {
+        if Path::new(wanted) != Path::new(actual) {
+            differences.push(format!("is in {actual}, the layout has {wanted}"));
+        }
+    }
+    differences
+}
+
+/// `.json` files are parsed as `{"panes": [...]}`; anything else as KDL
+pub(crate) fn parse_layout(path: &str, contents: &str) -> Result<Vec<PaneSpec>, String> {
+    let specs = if Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+    {
+        serde_json::from_str::<LayoutFile>(contents)
+            .map_err(|err| format!("invalid layout {path}: {err}"))?
+            .panes
+    } else {
+        parse_kdl(contents).map_err(|err| format!("invalid layout {path}: {err}"))?
+    };
+    validate(&specs)?;
+    Ok(specs)
+}
+
+/// Pane nodes, optionally wrapped in a `layout { ... }` node:
+///
+/// ```kdl
+/// layout {
+///     pane name="server" command="npm" cwd="~/app" {
+///         args "run" "dev"
+///     }
+///     pane name="logs" direction="down" command="tail" {
+///         args "-f" "app.log"
+///     }
+/// }
+/// ```
+fn parse_kdl(contents: &str) -> Result<Vec<PaneSpec>, String> {
+    let document: KdlDocument = contents.parse().map_err(|err| format!("{err}"))?;
+    let nodes = match document.nodes() {
+        [layout] if layout.name().value() == "layout" => layout
+            .children()
+            .map(KdlDocument::nodes)
+            .unwrap_or_default(),
+        nodes => nodes,
+    };
+    nodes
+        .iter()
+        .map(|node| match node.name().value() {
+            "pane" => parse_pane(node),
+            other => Err(format!("unexpected node `{other}` (expected `pane`)")),
+        })
+        .collect()
+}
+
+fn parse_pane(node: &KdlNode) -> Result<PaneSpec, String> {
+    let string = |key: &str| -> Result<Option<String>, String> {
+        node.get(key)
+            .map(|entry| {
+                entry
+                    .value()
+                    .as_string()
+                    .map(str::to_string)
+                    .ok_or_else(|| format!("pane `{key}` must be a string"))
+            })
+            .transpose()
+    };
+    let flag = |key: &str| -> Result<bool, String> {
+        node.get(key).map_or(Ok(false), |entry| {
+            entry
+                .value()
+                .as_bool()
+                .ok_or_else(|| format!("pane `{key}` must be true or false"))
+        })
+    };
+
+    let mut command: Vec<String> = string("command")?.into_iter().collect();
+    let args = node
+        .children()
+        .and_then(|children| children.get("args"))
+        .map(|args| args.entries())
+        .unwrap_or_default();
+    for arg in args {
+        let value = arg.value();
+        command.push(match value.as_string() {
+            Some(text) => text.to_string(),
+            None => value.to_string(),
+        });
+    }
+    if command.is_empty() && !args.is_empty() {
+        return Err("pane `args` given without `command`".to_string());
+    }
+
+    Ok(PaneSpec {
+        name: string("name")?.ok_or("every pane needs a `name`")?,
+        command,
+        cwd: string("cwd")?,
+        direction: string("direction")?,
+        floating: flag("floating")?,
+        close_on_exit: flag("close_on_exit")?,
+    })
+}
+
+fn validate(specs: &[PaneSpec]) -> Result<(), String> {
+    if specs.is_empty() {
+        return Err("layout has no panes".to_string());
+    }
+    for (index, spec) in specs.iter().enumerate() {
+        if spec.name.trim().is_empty() {
+            return Err("pane names must not be empty".to_string());
+        }
+        if specs[..index].iter().any(|other| other.name == spec.name) {
+            return Err(format!("duplicate pane name `{}`", spec.name));
+        }
+        if let Some(direction) = &spec.direction {
+            if !matches!(direction.as_str(), "right" | "left" | "up" | "down") {
+                return Err(format!(
+                    "pane `{}`: invalid direction `{direction}` (expected right, left, up, or down)",
+                    spec.name
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_kdl_layout_with_args() {
+        let specs = parse_layout(
+            "dev.kdl",
+            r#"
+            layout {
+                pane name="server" command="npm" cwd="~/app" {
+                    args "run" "dev"
+                }
+                pane name="shell" direction="down" floating=true
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            specs,
+            vec![
+                PaneSpec {
+                    name: "server".to_string(),
+                    command: vec!["npm".to_string(), "run".to_string(), "dev".to_string()],
+                    cwd: Some("~/app".to_string()),
+                    ..PaneSpec::default()
+                },
+                PaneSpec {
+                    name: "shell".to_string(),
+                    direction: Some("down".to_string()),
+                    floating: true,
+                    ..PaneSpec::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_kdl_without_layout_wrapper() {
+        let specs = parse_layout("dev.kdl", r#"pane name="a"; pane name="b""#).unwrap();
+        assert_eq!(specs.len(), 2);
+    }
+
+    #[test]
+    fn parse_json_layout() {
+        let specs = parse_layout(
+            "dev.json",
+            r#"{"panes": [{"name": "logs", "command": ["tail", "-f", "app.log"]}]}"#,
+        )
+        .unwrap();
+        assert_eq!(specs[0].command, vec!["tail", "-f", "app.log"]);
+    }
+
+    #[test]
+    fn differences_report_command_and_cwd_mismatches() {
+        let spec = PaneSpec {
+            name: "server".to_string(),
+            command: vec!["npm".to_string(), "run".to_string(), "dev".to_string()],
+            cwd: Some("/srv/app".to_string()),
+            ..PaneSpec::default()
+        };
+        let pane = PaneInfo {
+            command: Some("/usr/bin/npm run dev".to_string()),
+            cwd: Some("/srv/app/".to_string()),
+            ..PaneInfo::default()
+        };
+        assert!(differences(&spec, Some("/srv/app"), &pane).is_empty());
+
+        let pane = PaneInfo {
+            command: None,
+            cwd: Some("/tmp".to_string()),
+            ..PaneInfo::default()
+        };
+        assert_eq!(
+            differences(&spec, Some("/srv/app"), &pane),
+            [
+                "runs a shell, the layout has npm run dev",
+                "is in /tmp, the layout has /srv/app"
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_layout_rejects_invalid_specs() {
+        assert!(parse_layout("a.kdl", r#"pane command="npm""#).is_err());
+        assert!(parse_layout("a.kdl", r#"pane name="a"; pane name="a""#).is_err());
+        assert!(parse_layout("a.kdl", r#"pane name="a" direction="sideways""#).is_err());
+        assert!(parse_layout("a.kdl", r#"tab name="a""#).is_err());
+        assert!(parse_layout("a.kdl", "").is_err());
+    }
+}
@@ -0,0 +1,92 @@
+//! Pane bookmarks (`zjctl mark`)
+//!
+//! Marks live in the plugin next to pane names, so they last as long as the session's plugin
+//! instance and are dropped when their pane closes. `mark:NAME` selects a marked pane anywhere
+//! a selector is accepted. `zjctl alias` is the same registry under the `alias:NAME` spelling.
+
+use serde::{Deserialize, Serialize};
+use zjctl_proto::{methods, RpcErrorCode};
+
+use crate::client::{self, ClientError};
+use crate::output;
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Mark {
+    mark: String,
+    pane: String,
+    #[serde(default)]
+    title: Option<String>,
+}
+
+pub fn set(
+    plugin: Option<&str>,
+    mark: &str,
+    selector: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let params = serde_json::json!({ "mark": mark, "selector": selector });
+    let result = client::rpc_call(plugin, methods::MARK_SET, params)?;
+    println!(
+        "{} -> {}",
+        mark,
+        result["pane"].as_str().unwrap_or_default()
+    );
+    Ok(())
+}
+
+/// Focus the pane under `mark`
+pub fn jump(plugin: Option<&str>, mark: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let params = serde_json::json!({ "selector": format!("mark:{mark}") });
+    match client::rpc_call(plugin, methods::PANE_FOCUS, params) {
+        Ok(_) => Ok(()),
+        Err(ClientError::RpcError(err)) if err.code == RpcErrorCode::NoMatch => Err(Box::new(
+            ClientError::no_match(format!("no pane is marked {mark} (see: zjctl mark ls)")),
+        )),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn ls(plugin: Option<&str>, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let marks: Vec<Mark> = serde_json::from_value(client::rpc_call(
+        plugin,
+        methods::MARKS_LIST,
+        serde_json::json!({}),
+    )?)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&marks)?);
+        return Ok(());
+    }
+    if marks.is_empty() {
+        println!("No marks");
+        return Ok(());
+    }
+    let title_width = output::flex_width(37, 12, 40);
+    println!(
+        "{}",
+        output::header(&format!("{:<16} {:<20} TITLE", "MARK", "PANE"))
+    );
+    println!("{}", output::rule(37 + title_width));
+    for mark in &marks {
+        println!(
+            "{:<16} {:<20} {}",
+            mark.mark,
+            mark.pane,
+            output::truncate(mark.title.as_deref().unwrap_or("-"), title_width)
+        );
+    }
+    Ok(())
+}
+
+pub fn rm(plugin: Option<&str>, mark: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let result = client::rpc_call(
+        plugin,
+        methods::MARK_REMOVE,
+        serde_json::json!({ "mark": mark }),
+    )?;
+    if result["pane"].is_null() {
+        return Err(Box::new(ClientError::no_match(format!(
+            "no mark named {mark}"
+        ))));
+    }
+    println!("removed {mark}");
+    Ok(())
+}
@@ -1,8 +1,31 @@
 //! Command implementations
 
 pub mod action;
+pub mod assert;
+pub mod batch;
+pub mod broadcast;
+pub mod daemon;
 pub mod doctor;
+pub mod events;
+pub mod fleet;
+pub mod group;
+pub mod host;
+pub mod import;
 pub mod install;
+pub mod layout;
+pub mod mark;
+pub mod on;
 pub mod pane;
 pub mod panes;
+pub mod prompt;
+pub mod report;
+pub mod run;
+pub mod schedule;
+pub mod selftest;
+pub mod session;
 pub mod status;
+pub mod statusline;
+pub mod tab;
+pub mod tag;
+pub mod wait_for;
+pub mod watchdog;
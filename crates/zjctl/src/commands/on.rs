@@ -0,0 +1,178 @@
+//! `zjctl on EVENT -- CMD`: run a command when a pane event fires
+//!
+//! Built on the `zjctl events` stream, so scripts get a trap instead of a JSONL loop. The
+//! selector is resolved when the trap is set and again after each batch of events, so panes
+//! that close are still recognised and panes that open later can match. The command runs
+//! directly (no shell) with the event in its environment.
+
+use std::collections::BTreeSet;
+
+use clap::ValueEnum;
+
+use crate::client::ClientError;
+use crate::commands::events::EventStream;
+use crate::commands::pane::CommandExit;
+use crate::commands::panes;
+use crate::daemon;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PaneEvent {
+    #[value(name = "pane-opened")]
+    Opened,
+    #[value(name = "pane-closed")]
+    Closed,
+    #[value(name = "pane-exited")]
+    Exited,
+    #[value(name = "pane-focused")]
+    Focused,
+    #[value(name = "pane-title-changed")]
+    TitleChanged,
+}
+
+impl PaneEvent {
+    /// The event's name in the `zjctl events` stream
+    fn name(self) -> &'static str {
+        match self {
+            PaneEvent::Opened => "pane_opened",
+            PaneEvent::Closed => "pane_closed",
+            PaneEvent::Exited => "pane_exited",
+            PaneEvent::Focused => "pane_focused",
+            PaneEvent::TitleChanged => "pane_title_changed",
+        }
+    }
+
+    /// Events about panes that have to exist when the trap is set
+    fn needs_existing_pane(self) -> bool {
+        self != PaneEvent::Opened
+    }
+}
+
+pub struct OnOptions<'a> {
+    pub event: PaneEvent,
+    /// Only fire for panes matching this selector
+    pub selector: Option<&'a str>,
+    /// Keep running the command for every matching event instead of exiting after the first
+    pub persistent: bool,
+    pub command: &'a [String],
+}
+
+pub fn run(plugin: Option<&str>, options: &OnOptions) -> Result<(), Box<dyn std::error::Error>> {
+    if options.command.is_empty() {
+        return Err("no command given (usage: zjctl on EVENT -- <command>...)".into());
+    }
+
+    // Subscribe before resolving the selector so nothing slips in between.
+    let mut stream = EventStream::start(plugin)?;
+    let target = match options.selector {
+        Some(selector) => format!("{} {selector}", options.event.name()),
+        None => options.event.name().to_string(),
+    };
+    let _registration = daemon::register("on", &target);
+    let mut tracked: Option<BTreeSet<String>> = None;
+    if let Some(selector) = options.selector {
+        let matching = panes::list_matching(plugin, selector)?;
+        if matching.is_empty() && options.event.needs_existing_pane() {
+            return Err(Box::new(ClientError::no_match(format!(
+                "no panes match {selector}"
+            ))));
+        }
+        if !options.persistent && options.event == PaneEvent::Exited {
+            // The command may have exited before the trap was set.
+            if let Some(pane) = matching.iter().find(|pane| pane.exited) {
+                let event = serde_json::json!({
+                    "event": "pane_exited",
+                    "pane": pane.id,
+                    "exit_status": pane.exit_status,
+                });
+                return finish(fire(&event, options.command)?);
+            }
+        }
+        tracked = Some(matching.into_iter().map(|pane| pane.id).collect());
+    }
+
+    loop {
+        let batch = stream.next_batch()?;
+        if let (Some(selector), Some(tracked)) = (options.selector, tracked.as_mut()) {
+            tracked.extend(
+                panes::list_matching(plugin, selector)?
+                    .into_iter()
+                    .map(|pane| pane.id),
+            );
+        }
+        for event in batch
+            .iter()
+            .filter(|event| fires(event, options.event, tracked.as_ref()))
+        {
+            let code = fire(event, options.command)?;
+            if !options.persistent {
+                return finish(code);
+            }
+            if code != 0 {
+                eprintln!(
+                    "warning: {} exited with status {code} for {}",
+                    options.command[0],
+                    event["pane"].as_str().unwrap_or_default()
+                );
+            }
+        }
+    }
+}
+
+/// Whether `event` is `kind` for one of the `tracked` panes (any pane without a selector)
+fn fires(event: &serde_json::Value, kind: PaneEvent, tracked: Option<&BTreeSet<String>>) -> bool {
+    if event["event"].as_str() != Some(kind.name()) {
+        return false;
+    }
+    match (tracked, event["pane"].as_str()) {
+        (None, _) => true,
+        (Some(tracked), Some(pane)) => tracked.contains(pane),
+        (Some(_), None) => false,
+    }
+}
+
+/// Run the trap's command for `event`; returns its exit status
+fn fire(event: &serde_json::Value, command: &[String]) -> Result<i32, Box<dyn std::error::Error>> {
+    let mut child = std::process::Command::new(&command[0]);
+    child
+        .args(&command[1..])
+        .env("ZJCTL_EVENT", event["event"].as_str().unwrap_or_default())
+        .env("ZJCTL_EVENT_JSON", event.to_string());
+    if let Some(pane) = event["pane"].as_str() {
+        child.env("ZJCTL_PANE", pane);
+    }
+    if let Some(status) = event["exit_status"].as_i64() {
+        child.env("ZJCTL_EXIT_STATUS", status.to_string());
+    }
+    let status = child
+        .status()
+        .map_err(|err| format!("failed to run {}: {err}", command[0]))?;
+    // No code means the command was killed by a signal.
+    Ok(status.code().unwrap_or(1))
+}
+
+fn finish(code: i32) -> Result<(), Box<dyn std::error::Error>> {
+    if code == 0 {
+        Ok(())
+    } else {
+        Err(Box::new(CommandExit(code)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fires_only_for_the_event_and_tracked_panes() {
+        let closed = serde_json::json!({"event": "pane_closed", "pane": "terminal:3"});
+        let tracked: BTreeSet<String> = ["terminal:3".to_string()].into();
+        let others: BTreeSet<String> = ["terminal:4".to_string()].into();
+        assert!(fires(&closed, PaneEvent::Closed, Some(&tracked)));
+        assert!(fires(&closed, PaneEvent::Closed, None));
+        assert!(!fires(&closed, PaneEvent::Closed, Some(&others)));
+        assert!(!fires(&closed, PaneEvent::Exited, None));
+
+        let missed = serde_json::json!({"event": "missed", "count": 2});
+        assert!(!fires(&missed, PaneEvent::Closed, None));
+    }
+}
@@ -1,158 +1,2384 @@
 //! Individual pane operation commands
 
-use crate::client;
+use crate::activity::ChangeMask;
+use crate::client::{self, ClientError};
 use crate::commands::panes;
-use std::collections::hash_map::DefaultHasher;
+use crate::commands::prompt::PromptProfile;
+use crate::commands::report;
+use crate::commands::tab;
+use crate::daemon;
+use crate::history;
+use crate::interrupt;
+use crate::outcome::{self, FailurePolicy, Failures, Outcomes};
+use crate::output;
+use crate::paths;
+use serde::Deserialize;
 use std::fs;
-use std::hash::{Hash, Hasher};
-use std::io::Write;
+use std::io::{IsTerminal, Write};
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use std::thread::sleep;
 use std::time::{Duration, Instant};
-use zjctl_proto::methods;
-use zjctl_proto::{PaneSelector, PaneType};
+use zjctl_proto::{methods, RpcErrorCode};
+use zjctl_proto::{
+    Base64Bytes, CaptureRegion, CaptureSlice, CaptureWindow, PaneSelector, PaneType,
+};
 
+use crate::progress::{Progress, ProgressFormat};
 use crate::zellij;
+use clap::ValueEnum;
+
+/// Byte sequence sent for Enter; TUIs disagree on what they accept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum EnterKey {
+    /// Carriage return (`\r`)
+    Cr,
+    /// Line feed (`\n`)
+    #[default]
+    Lf,
+    /// `\r\n`
+    Crlf,
+    /// Kitty keyboard protocol Enter (`CSI 13 u`)
+    Kitty,
+}
+
+/// `[[enter-key]]` rules from the config file: selector and the Enter bytes for its panes
+static ENTER_KEY_RULES: OnceLock<Vec<(String, EnterKey)>> = OnceLock::new();
+
+/// Install the config file's `[[enter-key]]` rules for `pane send`
+pub fn set_enter_key_rules(rules: Vec<(String, EnterKey)>) {
+    let _ = ENTER_KEY_RULES.set(rules);
+}
+
+/// Enter bytes from the first `[[enter-key]]` rule whose selector matches every pane `selector`
+/// targets; `lf` when none does
+fn rule_enter_key(
+    plugin: Option<&str>,
+    selector: &str,
+) -> Result<EnterKey, Box<dyn std::error::Error>> {
+    let rules = ENTER_KEY_RULES.get().map(Vec::as_slice).unwrap_or_default();
+    if rules.is_empty() {
+        return Ok(EnterKey::default());
+    }
+    let targets = panes::list_matching(plugin, selector)?;
+    for (pane, key) in rules {
+        let matched = panes::list_matching(plugin, pane)?;
+        if covers(&targets, &matched) {
+            return Ok(*key);
+        }
+    }
+    Ok(EnterKey::default())
+}
+
+/// Whether a rule matching `matched` covers all of `targets` (and there are targets)
+fn covers(targets: &[panes::PaneInfo], matched: &[panes::PaneInfo]) -> bool {
+    !targets.is_empty()
+        && targets
+            .iter()
+            .all(|target| matched.iter().any(|pane| pane.id == target.id))
+}
+
+impl EnterKey {
+    pub fn sequence(self) -> &'static str {
+        match self {
+            EnterKey::Cr => "\r",
+            EnterKey::Lf => "\n",
+            EnterKey::Crlf => "\r\n",
+            EnterKey::Kitty => "\u{1b}[13u",
+        }
+    }
+}
+
+/// How `--clear-line` discards partially typed input before a send
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ClearLine {
+    /// Ctrl+E then Ctrl+U: move to end of line and kill it (readline, zsh)
+    #[default]
+    CtrlU,
+    /// Ctrl+C: abandon the line; works in most REPLs and at non-readline prompts
+    CtrlC,
+}
+
+impl ClearLine {
+    fn sequence(self) -> &'static str {
+        match self {
+            ClearLine::CtrlU => "\u{5}\u{15}",
+            ClearLine::CtrlC => "\u{3}",
+        }
+    }
+
+    /// Ctrl+C makes the program redraw its prompt; typeahead sent too early can be discarded.
+    fn settle(self) -> Duration {
+        match self {
+            ClearLine::CtrlU => Duration::ZERO,
+            ClearLine::CtrlC => Duration::from_millis(100),
+        }
+    }
+}
+
+pub struct SendOptions<'a> {
+    pub selector: &'a str,
+    pub all: bool,
+    pub enter: bool,
+    /// `None`: from the `[[enter-key]]` rules, else `lf`
+    pub enter_key: Option<EnterKey>,
+    pub delay_enter: f64,
+    pub bytes: &'a [String],
+    pub interactive: bool,
+    /// Split text longer than this many bytes into separate writes (0 = never)
+    pub chunk_size: usize,
+    /// Pause between chunks (seconds)
+    pub chunk_delay: f64,
+    /// Wait for the pane's screen to settle after each chunk
+    pub chunk_verify: bool,
+    /// Read the text from stdin, sending it as it arrives
+    pub stdin: bool,
+    /// Refuse to deliver again if this key was already used within its window
+    pub idempotency_key: Option<IdempotencyKey<'a>>,
+    /// Clear partially typed input first
+    pub clear_line: Option<ClearLine>,
+    pub scroll: ScrollGuard,
+    /// How the text after `--` becomes bytes
+    pub encoding: SendEncoding,
+}
+
+/// `--raw` / `--hex`: send exact bytes (as `bytes_b64`) rather than text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SendEncoding {
+    #[default]
+    Text,
+    /// Backslash escapes (`\e`, `\x1b`, `\n`...) in the text; stdin is sent byte for byte
+    Raw,
+    /// Hex digits, whitespace ignored (`1b 5b 41`)
+    Hex,
+}
+
+/// What the plugin does when a target is the focused pane and Zellij is in scroll or search
+/// mode, where typed text never reaches the program
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScrollGuard {
+    /// Refuse with a `scroll_mode` error
+    #[default]
+    Fail,
+    /// Leave scroll mode (back to the live screen), then send
+    Exit,
+    /// Send anyway
+    Force,
+}
+
+impl ScrollGuard {
+    /// From `--exit-scroll` / `--force`
+    pub fn from_flags(exit_scroll: bool, force: bool) -> Self {
+        if force {
+            ScrollGuard::Force
+        } else if exit_scroll {
+            ScrollGuard::Exit
+        } else {
+            ScrollGuard::Fail
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            ScrollGuard::Fail => "fail",
+            ScrollGuard::Exit => "exit",
+            ScrollGuard::Force => "force",
+        }
+    }
+}
+
+/// Dedupe key checked by the plugin before the first write of a send
+#[derive(Debug, Clone, Copy)]
+pub struct IdempotencyKey<'a> {
+    pub key: &'a str,
+    /// How long the key is remembered (seconds)
+    pub window: f64,
+}
+
+/// Read size for `--stdin` when chunking is disabled
+const STDIN_READ_SIZE: usize = 64 * 1024;
+
+/// How long `pane clear --scrollback` lets the program redraw before dropping the scrollback
+const CLEAR_REDRAW_DELAY: Duration = Duration::from_millis(150);
+
+pub fn send(
+    plugin: Option<&str>,
+    options: SendOptions<'_>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let SendOptions {
+        selector,
+        all,
+        enter,
+        enter_key,
+        delay_enter,
+        bytes,
+        interactive,
+        chunk_size,
+        chunk_delay,
+        chunk_verify,
+        stdin,
+        idempotency_key,
+        clear_line,
+        scroll,
+        encoding,
+    } = options;
+    let text = bytes.join(" ");
+    if chunk_delay < 0.0 {
+        return Err("chunk_delay must be >= 0".into());
+    }
+    if chunk_verify && all {
+        return Err("--chunk-verify needs a single target pane (drop --all)".into());
+    }
+    if stdin && !bytes.is_empty() {
+        return Err("--stdin cannot be combined with text after --".into());
+    }
+    if idempotency_key.is_some_and(|key| key.window <= 0.0) {
+        return Err("idempotency window must be > 0".into());
+    }
+    if idempotency_key.is_some() && interactive {
+        return Err("--idempotency-key cannot be combined with --interactive".into());
+    }
+    let enter = match (enter, enter_key) {
+        (false, _) => None,
+        (true, Some(enter_key)) => Some(enter_key),
+        (true, None) => Some(rule_enter_key(plugin, selector)?),
+    };
+    let chunk_delay = Duration::from_secs_f64(chunk_delay);
+    let mut key = idempotency_key;
+
+    if encoding != SendEncoding::Text {
+        let payload = match encoding {
+            SendEncoding::Hex => decode_hex(&text)?,
+            _ if stdin || (bytes.is_empty() && !std::io::stdin().is_terminal()) => {
+                let mut payload = Vec::new();
+                std::io::Read::read_to_end(&mut std::io::stdin().lock(), &mut payload)
+                    .map_err(|err| format!("failed to read stdin: {err}"))?;
+                payload
+            }
+            _ => decode_escapes(&text)?,
+        };
+        if payload.is_empty() {
+            return Err("nothing to send".into());
+        }
+        let pieces: Vec<&[u8]> = if chunk_size == 0 {
+            vec![&payload]
+        } else {
+            payload.chunks(chunk_size).collect()
+        };
+        for (i, piece) in pieces.into_iter().enumerate() {
+            if i > 0 && !chunk_delay.is_zero() {
+                sleep(chunk_delay);
+            }
+            let mut params = serde_json::json!({
+                "selector": selector,
+                "all": all,
+                "bytes_b64": Base64Bytes(piece.to_vec()).encode(),
+                "scroll_mode": scroll.as_str(),
+            });
+            if let Some(key) = key.take() {
+                params["idempotency_key"] = serde_json::json!(key.key);
+                params["idempotency_window"] = serde_json::json!(key.window);
+            }
+            let result = client::rpc_call(plugin, methods::PANE_SEND, params)?;
+            if result["duplicate"].as_bool().unwrap_or(false) {
+                return report_delivery(false, idempotency_key);
+            }
+        }
+        return Ok(());
+    }
+
+    // Piped input with nothing after `--` is sent as if --stdin was given.
+    if stdin || (bytes.is_empty() && !interactive && !std::io::stdin().is_terminal()) {
+        let delivered = run_steps(
+            plugin,
+            &[selector.to_string()],
+            all,
+            clear_steps(clear_line),
+            &mut key,
+            scroll,
+        )? && send_stdin(
+            plugin,
+            selector,
+            all,
+            StdinChunking {
+                size: chunk_size,
+                delay: chunk_delay,
+                verify: chunk_verify,
+            },
+            &mut key,
+            scroll,
+        )? && run_steps(
+            plugin,
+            &[selector.to_string()],
+            all,
+            build_send_steps("", enter, delay_enter)?,
+            &mut key,
+            scroll,
+        )?;
+        return report_delivery(delivered, idempotency_key);
+    }
+
+    let mut steps = clear_steps(clear_line);
+    steps.extend(chunk_steps(
+        build_send_steps(&text, enter, delay_enter)?,
+        chunk_size,
+        chunk_delay,
+        chunk_verify,
+    ));
+    let targets = if interactive {
+        let chosen = choose_targets(plugin, selector)?;
+        if chosen.is_empty() {
+            eprintln!("Nothing sent");
+            return Ok(());
+        }
+        chosen
+    } else {
+        vec![selector.to_string()]
+    };
+    let delivered = run_steps(
+        plugin,
+        &targets,
+        all && !interactive,
+        steps,
+        &mut key,
+        scroll,
+    )?;
+    report_delivery(delivered, idempotency_key)
+}
+
+fn report_delivery(
+    delivered: bool,
+    key: Option<IdempotencyKey<'_>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let (false, Some(key)) = (delivered, key) {
+        eprintln!(
+            "Skipped: already delivered with idempotency key {:?} within the last {}s",
+            key.key, key.window
+        );
+    }
+    Ok(())
+}
+
+/// `--chunk-size`, `--chunk-delay`, and `--chunk-verify` for `--stdin`
+struct StdinChunking {
+    size: usize,
+    delay: Duration,
+    verify: bool,
+}
+
+/// Stream stdin into the pane: each read is sent (split into `chunk_size` pieces) as soon as it
+/// arrives, so slow producers are forwarded live and large pastes are paced by `delay`.
+fn send_stdin(
+    plugin: Option<&str>,
+    selector: &str,
+    all: bool,
+    chunking: StdinChunking,
+    key: &mut Option<IdempotencyKey<'_>>,
+    scroll: ScrollGuard,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let StdinChunking {
+        size: chunk_size,
+        delay,
+        verify,
+    } = chunking;
+    let targets = [selector.to_string()];
+    let mut stdin = std::io::stdin().lock();
+    let read_size = if chunk_size == 0 {
+        STDIN_READ_SIZE
+    } else {
+        chunk_size
+    };
+    let mut buffer = vec![0u8; read_size];
+    let mut pending = Vec::new();
+    let mut sent_any = false;
+    loop {
+        let read = match std::io::Read::read(&mut stdin, &mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(format!("failed to read stdin: {err}").into()),
+        };
+        pending.extend_from_slice(&buffer[..read]);
+        let Some(text) = take_utf8_prefix(&mut pending) else {
+            continue;
+        };
+        if sent_any && !delay.is_zero() {
+            sleep(delay);
+        }
+        let steps = chunk_steps(vec![SendStep::Text(text)], chunk_size, delay, verify);
+        if !run_steps(plugin, &targets, all, steps, key, scroll)? {
+            return Ok(false);
+        }
+        if verify {
+            wait_rendered(plugin, selector)?;
+        }
+        sent_any = true;
+    }
+    if !pending.is_empty() {
+        // Input ended mid-character: send what is left rather than dropping it.
+        let text = String::from_utf8_lossy(&pending).into_owned();
+        return run_steps(
+            plugin,
+            &targets,
+            all,
+            vec![SendStep::Text(text)],
+            key,
+            scroll,
+        );
+    }
+    Ok(true)
+}
+
+/// `--raw` text: `\e` (ESC), `\xHH`, `\0`, `\a`, `\b`, `\t`, `\n`, `\r`, and `\\`; any other
+/// escape is an error rather than being sent literally
+fn decode_escapes(text: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find('\\') {
+        out.extend_from_slice(&rest.as_bytes()[..index]);
+        let escape = &rest[index + 1..];
+        let (byte, used) = match escape.chars().next() {
+            Some('e') => (0x1b, 1),
+            Some('0') => (0, 1),
+            Some('a') => (0x07, 1),
+            Some('b') => (0x08, 1),
+            Some('t') => (b'\t', 1),
+            Some('n') => (b'\n', 1),
+            Some('r') => (b'\r', 1),
+            Some('\\') => (b'\\', 1),
+            Some('x') => {
+                let hex = escape
+                    .get(1..3)
+                    .filter(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()));
+                let hex = hex.ok_or_else(|| {
+                    format!(
+                        "invalid escape \\{}: expected two hex digits after \\x",
+                        escape.chars().take(3).collect::<String>()
+                    )
+                })?;
+                (u8::from_str_radix(hex, 16).map_err(|e| e.to_string())?, 3)
+            }
+            Some(other) => {
+                return Err(format!(
+                    "unknown escape \\{other} (use \\\\ for a backslash)"
+                ))
+            }
+            None => return Err("trailing backslash (use \\\\ for a backslash)".to_string()),
+        };
+        out.push(byte);
+        rest = &escape[used..];
+    }
+    out.extend_from_slice(rest.as_bytes());
+    Ok(out)
+}
+
+/// `--hex` text: pairs of hex digits; whitespace between them is ignored
+fn decode_hex(text: &str) -> Result<Vec<u8>, String> {
+    let digits: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err("odd number of hex digits".to_string());
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let pair: String = pair.iter().collect();
+            u8::from_str_radix(&pair, 16).map_err(|_| format!("invalid hex byte: {pair}"))
+        })
+        .collect()
+}
+
+/// Remove and return the longest valid UTF-8 prefix of `pending`, keeping a trailing partial
+/// character for the next read. Invalid bytes are replaced rather than stalling the stream.
+fn take_utf8_prefix(pending: &mut Vec<u8>) -> Option<String> {
+    let valid = match std::str::from_utf8(pending) {
+        Ok(_) => pending.len(),
+        Err(err) if err.error_len().is_none() => err.valid_up_to(),
+        Err(_) => {
+            let text = String::from_utf8_lossy(pending).into_owned();
+            pending.clear();
+            return Some(text);
+        }
+    };
+    if valid == 0 {
+        return None;
+    }
+    let rest = pending.split_off(valid);
+    let text = String::from_utf8(std::mem::replace(pending, rest)).ok()?;
+    Some(text)
+}
+
+/// Run the steps in order; the idempotency key (if any) rides on the first write and is then
+/// consumed. Returns `false` when the plugin refused that write as a duplicate.
+fn run_steps(
+    plugin: Option<&str>,
+    targets: &[String],
+    all: bool,
+    steps: Vec<SendStep>,
+    key: &mut Option<IdempotencyKey<'_>>,
+    scroll: ScrollGuard,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    for step in steps {
+        match step {
+            SendStep::Text(text) => {
+                for target in targets {
+                    if !send_keyed(plugin, target, all, &text, key.take(), scroll)? {
+                        return Ok(false);
+                    }
+                }
+            }
+            SendStep::Delay(duration) => sleep(duration),
+            SendStep::Settle => {
+                for target in targets {
+                    wait_rendered(plugin, target)?;
+                }
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// Wait (up to a few seconds) until two consecutive screen dumps of the pane are identical,
+/// so the next chunk is not written while the previous one is still being processed.
+fn wait_rendered(plugin: Option<&str>, selector: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let deadline = Instant::now() + Duration::from_secs(3);
+    let mask = ChangeMask::default();
+    let mut last = mask.fingerprint(&capture_bytes(plugin, selector, false)?);
+    while Instant::now() < deadline {
+        sleep(Duration::from_millis(50));
+        let current = mask.fingerprint(&capture_bytes(plugin, selector, false)?);
+        if current == last {
+            return Ok(());
+        }
+        last = current;
+    }
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+enum Answer {
+    Yes,
+    No,
+    All,
+    Quit,
+}
+
+fn parse_answer(input: &str) -> Option<Answer> {
+    match input.trim().to_lowercase().as_str() {
+        "y" | "yes" => Some(Answer::Yes),
+        "n" | "no" | "" => Some(Answer::No),
+        "a" | "all" => Some(Answer::All),
+        "q" | "quit" => Some(Answer::Quit),
+        _ => None,
+    }
+}
+
+/// Preview each matching pane and ask whether to send to it
+fn choose_targets(
+    plugin: Option<&str>,
+    selector: &str,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        return Err("--interactive requires a terminal on stdin".into());
+    }
+
+    let matched = panes::list_matching(plugin, selector)?;
+    if matched.is_empty() {
+        return Err(ClientError::no_match(format!("no panes match selector: {selector}")).into());
+    }
+
+    let total = matched.len();
+    let mut chosen = Vec::new();
+    for (index, pane) in matched.iter().enumerate() {
+        let target = pane_id_to_selector(&pane.id).unwrap_or_else(|| pane.id.clone());
+        let screen = capture_bytes(plugin, &target, false)?;
+        let last_line = String::from_utf8_lossy(&screen)
+            .lines()
+            .map(str::trim_end)
+            .rfind(|line| !line.is_empty())
+            .map(|line| output::truncate(line, 60))
+            .unwrap_or_default();
+
+        eprintln!(
+            "[{}/{total}] {}  {}",
+            index + 1,
+            pane.id,
+            output::truncate(&pane.title, 40)
+        );
+        eprintln!("    | {last_line}");
+
+        let answer = loop {
+            eprint!("Send? [y]es/[n]o/[a]ll/[q]uit ");
+            std::io::stderr().flush()?;
+            let mut input = String::new();
+            if stdin.read_line(&mut input)? == 0 {
+                break Answer::Quit;
+            }
+            if let Some(answer) = parse_answer(&input) {
+                break answer;
+            }
+        };
+
+        match answer {
+            Answer::Yes => chosen.push(target),
+            Answer::No => {}
+            Answer::All => {
+                chosen.extend(
+                    matched[index..]
+                        .iter()
+                        .map(|p| pane_id_to_selector(&p.id).unwrap_or_else(|| p.id.clone())),
+                );
+                break;
+            }
+            Answer::Quit => return Ok(Vec::new()),
+        }
+    }
+    Ok(chosen)
+}
+
+pub fn send_keys(
+    plugin: Option<&str>,
+    selector: &str,
+    all: bool,
+    keys: &[String],
+    scroll: ScrollGuard,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if keys.is_empty() {
+        return Err("no keys given".into());
+    }
+    // Validate locally so typos fail before anything is sent.
+    zjctl_proto::encode_keys(keys)?;
+    let params = serde_json::json!({
+        "selector": selector,
+        "all": all,
+        "keys": keys,
+        "scroll_mode": scroll.as_str(),
+    });
+    client::rpc_call(plugin, methods::PANE_SEND_KEYS, params)?;
+    Ok(())
+}
+
+pub fn interrupt(
+    plugin: Option<&str>,
+    selector: &str,
+    all: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    send_raw(plugin, selector, all, "\u{3}")
+}
+
+pub fn escape(
+    plugin: Option<&str>,
+    selector: &str,
+    all: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    send_raw(plugin, selector, all, "\u{1b}")
+}
+
+#[derive(serde::Serialize)]
+struct CaptureReport<'a> {
+    selector: &'a str,
+    #[serde(flatten)]
+    slice: CaptureSlice,
+}
+
+pub struct CaptureOptions<'a> {
+    pub selector: &'a str,
+    pub full: bool,
+    pub no_restore: bool,
+    pub bytes_base64: bool,
+    pub window: CaptureWindow,
+    /// Only this part of the visible screen
+    pub region: Option<CaptureRegion>,
+    pub json: bool,
+}
+
+/// `window` cuts the capture down to its last lines or to what is new since an offset token;
+/// an offset always refers to the full scrollback, so `since_offset` implies `full`.
+pub fn capture(
+    plugin: Option<&str>,
+    options: CaptureOptions<'_>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let CaptureOptions {
+        selector,
+        full,
+        no_restore,
+        bytes_base64,
+        window,
+        region,
+        json,
+    } = options;
+    // Checked against the pane's size before focusing anything.
+    let geometry = region
+        .map(|region| region_geometry(plugin, selector, &region))
+        .transpose()?;
+    let selection = resolve_selection(plugin, selector)?;
+    let restore = if no_restore {
+        None
+    } else {
+        selection.restore_selector.clone()
+    };
+
+    let dump = capture_focus_selection(plugin, &selection)?;
+    let mut output = dump.read(full || window.since_offset.is_some())?;
+
+    if let (Some(selector), Dump::Focused) = (restore, &dump) {
+        let _ = capture_focus(plugin, &selector);
+    }
+    if let (Some(region), Some((rows, cols))) = (region, geometry) {
+        output = region
+            .cut(&String::from_utf8_lossy(&output), rows, cols)?
+            .into_bytes();
+    }
+
+    let mut stdout = std::io::stdout();
+    if json || !window.is_empty() {
+        let slice = window.apply(&String::from_utf8_lossy(&output));
+        if json {
+            let report = CaptureReport {
+                selector: &selection.target_selector,
+                slice,
+            };
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            stdout.write_all(slice.text.as_bytes())?;
+        }
+        return Ok(());
+    }
+    stdout.write_all(capture_output(output, bytes_base64).as_bytes())?;
+    Ok(())
+}
+
+/// Capture every pane matching `selector`, each under a `==> id (title) <==` header (or as a
+/// JSON array); focus goes back to where it was after each pane
+pub fn capture_all(
+    plugin: Option<&str>,
+    selector: &str,
+    full: bool,
+    window: &CaptureWindow,
+    region: Option<CaptureRegion>,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let matched = panes::list_matching(plugin, selector)?;
+    if matched.is_empty() {
+        return Err(Box::new(ClientError::no_match(format!(
+            "no panes match {selector}"
+        ))));
+    }
+    let mut reports = Vec::new();
+    let mut outcomes = Outcomes::new();
+    let mut stdout = std::io::stdout();
+    let mut printed = false;
+    for pane in &matched {
+        let target = pane_id_to_selector(&pane.id).unwrap_or_else(|| pane.id.clone());
+        if !outcomes.keep_going() {
+            outcomes.skipped(target);
+            continue;
+        }
+        let screen = capture_bytes(plugin, &target, full).and_then(|screen| {
+            let screen = String::from_utf8_lossy(&screen).into_owned();
+            match region {
+                Some(region) => Ok(region.cut(&screen, pane.rows, pane.cols)?),
+                None => Ok(screen),
+            }
+        });
+        let screen = match screen {
+            Ok(screen) => screen,
+            Err(err) => {
+                if json {
+                    reports.push(serde_json::json!({
+                        "selector": target,
+                        "title": pane.title,
+                        "error": err.to_string(),
+                    }));
+                } else {
+                    output::warn(format_args!("{target}: {err}"));
+                }
+                outcomes.failed(target, err);
+                continue;
+            }
+        };
+        let slice = window.apply(&screen);
+        if json {
+            reports.push(serde_json::json!({
+                "selector": target,
+                "title": pane.title,
+                "text": slice.text,
+                "offset": slice.offset,
+            }));
+        } else {
+            if printed {
+                writeln!(stdout)?;
+            }
+            writeln!(stdout, "==> {target} ({}) <==", pane.title)?;
+            stdout.write_all(slice.text.as_bytes())?;
+            printed = true;
+        }
+        outcomes.ok(target);
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+    }
+    outcomes.finish("panes")?;
+    Ok(())
+}
+
+/// `--at WHEN` prints one snapshot kept by `zjctl daemon --capture-history`; without it the
+/// kept snapshots are listed
+pub fn capture_history(
+    plugin: Option<&str>,
+    selector: &str,
+    at: Option<&str>,
+    window: &CaptureWindow,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let ago = at.map(history::parse_ago).transpose()?;
+    // Snapshots are kept by pane id, so the pane has to be resolved first.
+    let matched = panes::list_matching(plugin, selector)?;
+    let pane = match matched.as_slice() {
+        [pane] => pane,
+        [] => {
+            return Err(
+                ClientError::no_match(format!("no panes match selector: {selector}")).into(),
+            )
+        }
+        _ => {
+            return Err(ClientError::ambiguous(format!(
+                "{} panes match selector: {selector}",
+                matched.len()
+            ))
+            .into())
+        }
+    };
+    let snapshots = daemon::history(zellij::session_name().as_deref(), &pane.id).ok_or(
+        "no daemon is running (start one with: zjctl daemon --capture-history SELECTOR)",
+    )??;
+    let now = history::now_ms();
+
+    let Some(ago) = ago else {
+        if json {
+            println!("{}", serde_json::to_string_pretty(&snapshots)?);
+            return Ok(());
+        }
+        if snapshots.is_empty() {
+            println!("No snapshots of {} yet", pane.id);
+            return Ok(());
+        }
+        println!(
+            "{}",
+            output::header(&format!("{:<26} {:<10} LINES", "TIME", "AGE"))
+        );
+        println!("{}", output::rule(44));
+        for snapshot in &snapshots {
+            println!(
+                "{:<26} {:<10} {}",
+                report::utc_timestamp(snapshot.time_ms / 1000),
+                format!("{}s ago", now.saturating_sub(snapshot.time_ms) / 1000),
+                snapshot.text.lines().count()
+            );
+        }
+        return Ok(());
+    };
+
+    let target = now.saturating_sub(ago.as_millis() as u64);
+    let snapshot = history::at(&snapshots, target).ok_or_else(|| {
+        let oldest = snapshots.first().map_or("none kept yet".to_string(), |s| {
+            format!("oldest is {}s old", now.saturating_sub(s.time_ms) / 1000)
+        });
+        ClientError::no_match(format!("no snapshot of {} that old ({oldest})", pane.id))
+    })?;
+    let slice = window.apply(&snapshot.text);
+    if json {
+        let report = serde_json::json!({
+            "pane": pane.id,
+            "time_ms": snapshot.time_ms,
+            "text": slice.text,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        std::io::stdout().write_all(slice.text.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// One entry of `pane.history`
+#[derive(Debug, Clone, PartialEq, Deserialize, serde::Serialize)]
+pub struct SentText {
+    /// Unix timestamp in milliseconds
+    pub time_ms: u64,
+    pub text: String,
+}
+
+#[derive(Debug, Deserialize, serde::Serialize)]
+struct HistoryResult {
+    pane: String,
+    entries: Vec<SentText>,
+}
+
+pub fn history(
+    plugin: Option<&str>,
+    selector: &str,
+    limit: Option<usize>,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut params = serde_json::json!({ "selector": selector });
+    if let Some(limit) = limit {
+        params["limit"] = serde_json::json!(limit);
+    }
+    let result: HistoryResult =
+        serde_json::from_value(client::rpc_call(plugin, methods::PANE_HISTORY, params)?)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(());
+    }
+    if result.entries.is_empty() {
+        println!("No text sent to {} through zjctl", result.pane);
+        return Ok(());
+    }
+    for entry in &result.entries {
+        println!("{}", format_history_entry(entry));
+    }
+    Ok(())
+}
+
+/// `2024-05-01T12:00:00.250Z  make test\n` (control characters escaped)
+fn format_history_entry(entry: &SentText) -> String {
+    format!(
+        "{}  {}",
+        format_time_ms(entry.time_ms),
+        entry.text.escape_debug()
+    )
+}
+
+/// `2024-05-01T12:00:00.250Z` for a Unix timestamp in milliseconds
+pub(crate) fn format_time_ms(time_ms: u64) -> String {
+    let timestamp = report::utc_timestamp(time_ms / 1000);
+    format!("{}.{:03}Z", timestamp.trim_end_matches('Z'), time_ms % 1000)
+}
+
+/// Dump a pane's viewport (or scrollback with `full`), then restore the previous focus.
+///
+/// There is no pane-contents API for plugins yet, so the pane is briefly focused.
+pub fn capture_bytes(
+    plugin: Option<&str>,
+    selector: &str,
+    full: bool,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let selection = resolve_selection(plugin, selector)?;
+    let dump = capture_focus_selection(plugin, &selection)?;
+    let output = dump.read(full);
+    if let (Some(selector), Dump::Focused) = (&selection.restore_selector, &dump) {
+        let _ = capture_focus(plugin, selector);
+    }
+    output
+}
+
+/// Captured text is lossy UTF-8 by default; `--bytes-base64` preserves the exact bytes.
+fn capture_output(output: Vec<u8>, bytes_base64: bool) -> String {
+    let bytes = Base64Bytes::from(output);
+    if bytes_base64 {
+        format!("{}\n", bytes.encode())
+    } else {
+        bytes.to_lossy_string()
+    }
+}
+
+pub struct WaitIdleOptions<'a> {
+    pub selector: &'a str,
+    /// How long the screen must stay unchanged (seconds)
+    pub idle_time: f64,
+    pub timeout: f64,
+    pub full: bool,
+    pub no_restore: bool,
+    /// What is not counted as a change
+    pub mask: ChangeMask,
+    /// Only watch this part of the visible screen
+    pub region: Option<CaptureRegion>,
+    pub progress: Option<ProgressFormat>,
+}
+
+pub fn wait_idle(
+    plugin: Option<&str>,
+    options: WaitIdleOptions<'_>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let WaitIdleOptions {
+        selector,
+        idle_time,
+        timeout,
+        full,
+        no_restore,
+        mask,
+        region,
+        progress,
+    } = options;
+    if idle_time <= 0.0 {
+        return Err("idle_time must be greater than 0".into());
+    }
+    if timeout <= 0.0 {
+        return Err("timeout must be greater than 0".into());
+    }
+
+    let geometry = region
+        .map(|region| region_geometry(plugin, selector, &region))
+        .transpose()?;
+    let selection = resolve_selection(plugin, selector)?;
+    interrupt::install();
+    let mut restore = RestoreFocus {
+        plugin,
+        selector: if no_restore {
+            None
+        } else {
+            selection.restore_selector.clone()
+        },
+        capture: true,
+    };
+
+    let dump = restore.focus(&selection)?;
+    let screen = || -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let output = dump.read(full)?;
+        match (region, geometry) {
+            (Some(region), Some((rows, cols))) => Ok(region
+                .cut(&String::from_utf8_lossy(&output), rows, cols)?
+                .into_bytes()),
+            _ => Ok(output),
+        }
+    };
+
+    let idle_duration = Duration::from_secs_f64(idle_time);
+    let timeout_duration = Duration::from_secs_f64(timeout);
+    let poll_interval = poll_interval(idle_time);
+
+    let mut progress = Progress::new("wait-idle", progress).with_status_line();
+    let start = Instant::now();
+    let mut last_change = Instant::now();
+    let mut last_hash = mask.fingerprint(&screen()?);
+
+    loop {
+        let since_change = last_change.elapsed().as_secs_f64();
+        if last_change.elapsed() >= idle_duration {
+            progress.event("idle", serde_json::json!({ "since_change": since_change }));
+            break;
+        }
+        if start.elapsed() >= timeout_duration {
+            progress.event(
+                "timeout",
+                serde_json::json!({ "since_change": since_change }),
+            );
+            return Err(format!("timed out after {timeout:.1}s").into());
+        }
+        progress.update(
+            "waiting",
+            serde_json::json!({ "since_change": since_change }),
+        );
+
+        sleep(poll_interval);
+        interrupt::check()?;
+        let current_hash = mask.fingerprint(&screen()?);
+        if current_hash != last_hash {
+            last_hash = current_hash;
+            last_change = Instant::now();
+        }
+    }
+
+    Ok(())
+}
+
+pub struct WaitMatchOptions<'a> {
+    pub selector: &'a str,
+    pub pattern: &'a str,
+    pub timeout: f64,
+    pub full: bool,
+    /// Only look in this part of the visible screen
+    pub region: Option<CaptureRegion>,
+    pub json: bool,
+    pub progress: Option<ProgressFormat>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct MatchResult<'a> {
+    pane: &'a str,
+    line: String,
+    #[serde(rename = "match")]
+    matched: String,
+    elapsed: f64,
+}
+
+/// Poll a pane's content until a regex matches a line written after the wait started, then
+/// print the matching line.
+pub fn wait_match(
+    plugin: Option<&str>,
+    options: WaitMatchOptions<'_>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if options.timeout <= 0.0 {
+        return Err("timeout must be greater than 0".into());
+    }
+    let re = regex::Regex::new(options.pattern).map_err(|err| format!("invalid --match: {err}"))?;
+
+    let target = pin_unique(plugin, options.selector)?;
+    let geometry = options
+        .region
+        .map(|region| region_geometry(plugin, &target, &region))
+        .transpose()?;
+    let selection = resolve_selection(plugin, &target)?;
+    interrupt::install();
+    let mut restore = RestoreFocus::after_capture(plugin, &selection);
+    let dump = restore.focus(&selection)?;
+
+    let mut progress = Progress::new("pane-wait-for", options.progress).with_status_line();
+    let timeout = Duration::from_secs_f64(options.timeout);
+    let start = Instant::now();
+    // The first dump is the baseline: a match already on screen is from before the wait.
+    let mut tail = LineTail::default();
+    let found = loop {
+        interrupt::check()?;
+        let screen = dump.read(options.full)?;
+        let mut screen = String::from_utf8_lossy(&screen).into_owned();
+        if let (Some(region), Some((rows, cols))) = (options.region, geometry) {
+            screen = region.cut(&screen, rows, cols)?;
+        }
+        if let Some(found) = new_match(&mut tail, &re, &screen) {
+            progress.event("matched", serde_json::json!({ "pane": target }));
+            break Ok(found);
+        }
+        if start.elapsed() >= timeout {
+            progress.event("timeout", serde_json::json!({ "pane": target }));
+            break Err(format!(
+                "timed out after {:.1}s (no line in {target} matched /{}/)",
+                options.timeout, options.pattern
+            ));
+        }
+        progress.update("waiting", serde_json::json!({ "pane": target }));
+        sleep(Duration::from_millis(250));
+    };
+
+    drop(restore);
+    let (line, matched) = found?;
+
+    if options.json {
+        let result = MatchResult {
+            pane: &target,
+            line,
+            matched,
+            elapsed: start.elapsed().as_secs_f64(),
+        };
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    } else {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+/// A line matching `re` among those `screen` added since the previous dump fed to `tail`
+/// (none for the first, which sets the baseline), with the matched text
+fn new_match(tail: &mut LineTail, re: &regex::Regex, screen: &str) -> Option<(String, String)> {
+    let (lines, matched) = watch_feed(tail, Some(re), screen);
+    if !matched {
+        return None;
+    }
+    last_match(lines.last()?, re)
+}
+
+/// The most recent line matching `re`, with the matched text
+fn last_match(text: &str, re: &regex::Regex) -> Option<(String, String)> {
+    text.lines().rev().find_map(|line| {
+        let line = line.trim_end();
+        re.find(line)
+            .map(|m| (line.to_string(), m.as_str().to_string()))
+    })
+}
+
+pub struct WaitPromptOptions<'a> {
+    pub selector: &'a str,
+    pub profile: &'a PromptProfile,
+    pub timeout: f64,
+    pub json: bool,
+    pub progress: Option<ProgressFormat>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct PromptResult<'a> {
+    pane: &'a str,
+    profile: &'a str,
+    prompt: String,
+    elapsed: f64,
+}
+
+/// Wait until the last non-blank line of the pane is the profile's prompt, then print it.
+pub fn wait_prompt(
+    plugin: Option<&str>,
+    options: WaitPromptOptions<'_>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if options.timeout <= 0.0 {
+        return Err("timeout must be greater than 0".into());
+    }
+
+    let target = pin_unique(plugin, options.selector)?;
+    let selection = resolve_selection(plugin, &target)?;
+    interrupt::install();
+    let mut restore = RestoreFocus::after_capture(plugin, &selection);
+    let dump = restore.focus(&selection)?;
+
+    let mut progress = Progress::new("pane-wait-prompt", options.progress).with_status_line();
+    let timeout = Duration::from_secs_f64(options.timeout);
+    let start = Instant::now();
+    let found = loop {
+        interrupt::check()?;
+        let screen = dump.read(false)?;
+        if let Some(prompt) = options.profile.at_prompt(&String::from_utf8_lossy(&screen)) {
+            progress.event("prompt", serde_json::json!({ "pane": target }));
+            break Ok(prompt);
+        }
+        if start.elapsed() >= timeout {
+            progress.event("timeout", serde_json::json!({ "pane": target }));
+            break Err(format!(
+                "timed out after {:.1}s (no {} prompt in {target})",
+                options.timeout, options.profile.name
+            ));
+        }
+        progress.update("waiting", serde_json::json!({ "pane": target }));
+        sleep(Duration::from_millis(250));
+    };
+
+    drop(restore);
+    let prompt = found?;
+
+    if options.json {
+        let result = PromptResult {
+            pane: &target,
+            profile: &options.profile.name,
+            prompt,
+            elapsed: start.elapsed().as_secs_f64(),
+        };
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    } else {
+        println!("{prompt}");
+    }
+    Ok(())
+}
+
+pub fn wait_closed(
+    plugin: Option<&str>,
+    selector: &str,
+    timeout: f64,
+    allow_missing: bool,
+    progress: Option<ProgressFormat>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if timeout <= 0.0 {
+        return Err("timeout must be greater than 0".into());
+    }
+
+    let mut progress = Progress::new("wait-closed", progress).with_status_line();
+    // Pin the selector to a concrete pane first so title/command changes don't look like a close.
+    let matched = panes::list_matching(plugin, selector)?;
+    let target = match matched.as_slice() {
+        // A typo in the selector must not read as "closed".
+        [] if allow_missing => {
+            progress.event("closed", serde_json::json!({}));
+            return Ok(());
+        }
+        [] => {
+            return Err(ClientError::no_match(format!(
+                "no panes match selector: {selector} (pass --allow-missing if it may already be closed)"
+            ))
+            .into());
+        }
+        [pane] => pane_id_to_selector(&pane.id).unwrap_or_else(|| selector.to_string()),
+        _ => {
+            return Err(
+                ClientError::ambiguous(format!("{} panes match selector", matched.len())).into(),
+            );
+        }
+    };
+
+    let timeout_duration = Duration::from_secs_f64(timeout);
+    let start = Instant::now();
+
+    loop {
+        if panes::list_matching(plugin, &target)?.is_empty() {
+            progress.event("closed", serde_json::json!({ "pane": target }));
+            return Ok(());
+        }
+        if start.elapsed() >= timeout_duration {
+            progress.event("timeout", serde_json::json!({ "pane": target }));
+            return Err(format!("timed out after {timeout:.1}s ({target} still open)").into());
+        }
+        progress.update("waiting", serde_json::json!({ "pane": target }));
+        sleep(Duration::from_millis(250));
+    }
+}
+
+/// How a pane's command ended (`pane wait-exit --json`)
+#[derive(Debug, serde::Serialize)]
+struct ExitReport {
+    pane: String,
+    /// Unknown when the pane closed with its command (`--close-on-exit`)
+    exit_code: Option<i32>,
+    closed: bool,
+    /// Seconds waited
+    duration: f64,
+}
+
+/// Block until the command of a command pane exits and report its exit status
+///
+/// Zellij only tracks the process a pane was started with, so shell panes (no `command`) are
+/// rejected; `wait-idle` or `wait-prompt` cover commands typed into a shell.
+pub fn wait_exit(
+    plugin: Option<&str>,
+    selector: &str,
+    timeout: f64,
+    json: bool,
+    progress: Option<ProgressFormat>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if timeout <= 0.0 {
+        return Err("timeout must be greater than 0".into());
+    }
+
+    let mut progress = Progress::new("wait-exit", progress).with_status_line();
+    let matched = panes::list_matching(plugin, selector)?;
+    let pane = match matched.as_slice() {
+        [] => {
+            return Err(
+                ClientError::no_match(format!("no panes match selector: {selector}")).into(),
+            )
+        }
+        [pane] => pane,
+        _ => {
+            return Err(
+                ClientError::ambiguous(format!("{} panes match selector", matched.len())).into(),
+            );
+        }
+    };
+    // Pin the selector to the pane so a changing title does not lose it.
+    let target = pane_id_to_selector(&pane.id).unwrap_or_else(|| selector.to_string());
+    if pane.command.is_none() {
+        return Err(format!(
+            "{target} was not started with a command, so Zellij does not report its exit \
+             (use `pane wait-idle` or `pane wait-prompt` for commands typed into a shell)"
+        )
+        .into());
+    }
+
+    let timeout_duration = Duration::from_secs_f64(timeout);
+    let start = Instant::now();
+    let report = loop {
+        let report = |exit_code, closed| ExitReport {
+            pane: target.clone(),
+            exit_code,
+            closed,
+            duration: start.elapsed().as_secs_f64(),
+        };
+        match panes::list_matching(plugin, &target)?.first() {
+            None => break report(None, true),
+            Some(info) if info.exited || info.held => break report(info.exit_status, false),
+            Some(_) => {}
+        }
+        if start.elapsed() >= timeout_duration {
+            progress.event("timeout", serde_json::json!({ "pane": target }));
+            return Err(format!("timed out after {timeout:.1}s ({target} still running)").into());
+        }
+        progress.update("waiting", serde_json::json!({ "pane": target }));
+        sleep(Duration::from_millis(250));
+    };
+
+    progress.event(
+        "exited",
+        serde_json::json!({ "pane": report.pane, "exit_code": report.exit_code }),
+    );
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        match (report.exit_code, report.closed) {
+            (Some(code), _) => println!("{target} exited with status {code}"),
+            (None, true) => println!("{target} exited and closed"),
+            (None, false) => println!("{target} exited"),
+        }
+    }
+    Ok(())
+}
+
+/// A command run through `pane exec` finished with a non-zero status; zjctl exits with it.
+#[derive(Debug)]
+pub struct CommandExit(pub i32);
+
+impl std::fmt::Display for CommandExit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "command exited with status {}", self.0)
+    }
+}
+
+impl std::error::Error for CommandExit {}
+
+#[derive(Debug, serde::Serialize)]
+struct ExecResult<'a> {
+    pane: &'a str,
+    exit_code: i32,
+    output: String,
+    duration: f64,
+}
+
+/// Run a shell command in a pane and wait for it by bracketing its output with sentinel lines.
+pub fn exec(
+    plugin: Option<&str>,
+    selector: &str,
+    command: &[String],
+    profile: &PromptProfile,
+    timeout: f64,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if timeout <= 0.0 {
+        return Err("timeout must be greater than 0".into());
+    }
+    let command = command.join(" ");
+    if command.trim().is_empty() {
+        return Err("nothing to run (pass the command after --)".into());
+    }
+
+    let target = pin_unique(plugin, selector)?;
+    let selection = resolve_selection(plugin, &target)?;
+    let token = uuid::Uuid::new_v4().simple().to_string()[..12].to_string();
+
+    interrupt::install();
+    let restore = RestoreFocus::after(plugin, &selection);
+    focus_selection(plugin, &selection)?;
+    let baseline = if profile.needs_baseline() {
+        profile.echoes(&String::from_utf8_lossy(&dump_screen(true)?), &command)
+    } else {
+        0
+    };
+    send_raw(
+        plugin,
+        &target,
+        false,
+        &profile.command_line(&command, &token),
+    )?;
+
+    let start = Instant::now();
+    let timeout_duration = Duration::from_secs_f64(timeout);
+    let result = (|| -> Result<(String, i32), Box<dyn std::error::Error>> {
+        loop {
+            interrupt::check()?;
+            let dump = dump_screen(true)?;
+            let dump = String::from_utf8_lossy(&dump);
+            if let Some(done) = profile.extract(&dump, &token, &command, baseline) {
+                return Ok(done);
+            }
+            if start.elapsed() >= timeout_duration {
+                return Err(format!(
+                    "timed out after {timeout:.1}s (the command may still be running in {target})"
+                )
+                .into());
+            }
+            sleep(Duration::from_millis(200));
+        }
+    })();
+
+    if interrupt::interrupted() {
+        // Stop the command zjctl started rather than leave it running unattended.
+        let _ = send_raw(plugin, &target, false, "\u{3}");
+    }
+    drop(restore);
+    let (output, exit_code): (String, i32) = result?;
+
+    if json {
+        let result = ExecResult {
+            pane: &target,
+            exit_code,
+            output,
+            duration: start.elapsed().as_secs_f64(),
+        };
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    } else {
+        std::io::stdout().write_all(output.as_bytes())?;
+    }
+
+    if exit_code == 0 {
+        Ok(())
+    } else {
+        Err(Box::new(CommandExit(exit_code)))
+    }
+}
+
+/// POSIX shell line that prints `__ZJCTL_BEGIN_<token>`, evals the command, then prints
+/// `__ZJCTL_END_<token>_<status>`. The markers are assembled by printf so the echoed input
+/// line never contains them verbatim; the leading space keeps it out of shell history.
+pub(crate) fn exec_command_line(command: &str, token: &str) -> String {
+    let quoted = format!("'{}'", command.replace('\'', r"'\''"));
+    format!(
+        " printf '%s_%s\\n' __ZJCTL_BEGIN {token}; eval {quoted}; printf '\\n%s_%s_%d\\n' __ZJCTL_END {token} \"$?\"\n"
+    )
+}
+
+/// Output between the last begin marker and its end marker, plus the exit status
+pub(crate) fn extract_exec_output(dump: &str, token: &str) -> Option<(String, i32)> {
+    let begin = format!("__ZJCTL_BEGIN_{token}");
+    let end_prefix = format!("__ZJCTL_END_{token}_");
+    let lines: Vec<&str> = dump.lines().map(str::trim_end).collect();
+
+    let start = lines.iter().rposition(|line| *line == begin)? + 1;
+    let (offset, status) = lines[start..].iter().enumerate().find_map(|(i, line)| {
+        let status = line.strip_prefix(&end_prefix)?.parse().ok()?;
+        Some((i, status))
+    })?;
+
+    let mut body = &lines[start..start + offset];
+    // The end marker's printf starts with a newline in case the output did not end in one.
+    if body.last() == Some(&"") {
+        body = &body[..body.len() - 1];
+    }
+    let mut output = body.join("\n");
+    if !body.is_empty() {
+        output.push('\n');
+    }
+    Some((output, status))
+}
+
+pub struct RunOptions<'a> {
+    pub selector: &'a str,
+    pub all: bool,
+    /// Maximum number of panes running the command at once
+    pub parallel: usize,
+    /// Per-pane limit (seconds)
+    pub timeout: f64,
+    pub json: bool,
+    pub progress: Option<ProgressFormat>,
+    pub profile: &'a PromptProfile,
+}
+
+/// Outcome of the command in one pane
+#[derive(Debug, serde::Serialize)]
+struct RunPaneResult {
+    pane: String,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exit_code: Option<i32>,
+    output: String,
+    duration: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct RunSummary {
+    passed: usize,
+    failed: usize,
+    /// Panes the command was not started in after a failure (`--fail-fast`)
+    skipped: usize,
+    results: Vec<RunPaneResult>,
+}
+
+/// A pane whose command has been sent but whose end marker has not shown up yet
+struct RunningPane {
+    index: usize,
+    target: String,
+    token: String,
+    /// Echoes of the command on screen before it was sent (REPL profiles)
+    baseline: usize,
+    start: Instant,
+}
+
+/// Run a shell command in every matching pane, at most `parallel` at a time, like
+/// [`exec`] fanned out. Completions are reported as they happen; the exit status is 1 when
+/// any pane failed or timed out (under `--allow-partial`, when every pane did). With
+/// `--fail-fast` no more panes are started after the first failure.
+pub fn run(
+    plugin: Option<&str>,
+    options: RunOptions<'_>,
+    command: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    if options.timeout <= 0.0 {
+        return Err("timeout must be greater than 0".into());
+    }
+    if options.parallel == 0 {
+        return Err("--parallel must be at least 1".into());
+    }
+    let command = command.join(" ");
+    if command.trim().is_empty() {
+        return Err("nothing to run (pass the command after --)".into());
+    }
+
+    let matched = panes::list_matching(plugin, options.selector)?;
+    if matched.is_empty() {
+        return Err(ClientError::no_match(format!(
+            "no panes match selector: {}",
+            options.selector
+        ))
+        .into());
+    }
+    if matched.len() > 1 && !options.all {
+        return Err(ClientError::ambiguous(format!(
+            "{} panes match selector: {} (use --all to run in each)",
+            matched.len(),
+            options.selector
+        ))
+        .into());
+    }
+    let targets: Vec<String> = matched
+        .iter()
+        .map(|pane| pane_id_to_selector(&pane.id).unwrap_or_else(|| pane.id.clone()))
+        .collect();
+
+    let restore = resolve_selection(plugin, "focused")?.target_selector;
+    let mut progress = Progress::new("pane-run", options.progress);
+    let results = run_in_panes(plugin, &targets, &command, &options, &mut progress);
+    if restore != "focused" {
+        let _ = focus_target(plugin, &restore);
+    }
+    let results = results?;
+
+    let mut outcomes = Outcomes::new();
+    for (target, result) in targets.iter().zip(&results) {
+        match result {
+            Some(result) if result.ok => outcomes.ok(target),
+            Some(result) => match (&result.error, result.exit_code) {
+                (Some(error), _) => outcomes.failed(target, error),
+                (None, Some(code)) => outcomes.failed(target, format!("exit {code}")),
+                (None, None) => outcomes.failed(target, "failed"),
+            },
+            None => outcomes.skipped(target),
+        }
+    }
+    let counts = outcomes.summary();
+    let summary = RunSummary {
+        passed: counts.succeeded,
+        failed: counts.failed,
+        skipped: counts.skipped,
+        results: results.into_iter().flatten().collect(),
+    };
+    progress.event(
+        "done",
+        serde_json::json!({
+            "passed": summary.passed,
+            "failed": summary.failed,
+            "skipped": summary.skipped,
+        }),
+    );
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+    } else if summary.skipped > 0 {
+        println!(
+            "{} passed, {} failed, {} skipped",
+            summary.passed, summary.failed, summary.skipped
+        );
+    } else {
+        println!("{} passed, {} failed", summary.passed, summary.failed);
+    }
+
+    match outcomes.finish("panes") {
+        Ok(()) => Ok(()),
+        Err(_) => Err(Box::new(CommandExit(1))),
+    }
+}
+
+/// Results in `targets` order; `None` for panes skipped after a failure (`--fail-fast`)
+fn run_in_panes(
+    plugin: Option<&str>,
+    targets: &[String],
+    command: &str,
+    options: &RunOptions<'_>,
+    progress: &mut Progress,
+) -> Result<Vec<Option<RunPaneResult>>, Box<dyn std::error::Error>> {
+    let timeout = Duration::from_secs_f64(options.timeout);
+    let fail_fast = outcome::policy() == FailurePolicy::FailFast;
+    let mut queue = targets.iter().enumerate();
+    let mut running: Vec<RunningPane> = Vec::new();
+    let mut results: Vec<Option<RunPaneResult>> = targets.iter().map(|_| None).collect();
+
+    loop {
+        let failed = results.iter().flatten().any(|result| !result.ok);
+        while running.len() < options.parallel && !(fail_fast && failed) {
+            let Some((index, target)) = queue.next() else {
+                break;
+            };
+            let token = uuid::Uuid::new_v4().simple().to_string()[..12].to_string();
+            let baseline = if options.profile.needs_baseline() {
+                focus_target(plugin, target)?;
+                options
+                    .profile
+                    .echoes(&String::from_utf8_lossy(&dump_screen(true)?), command)
+            } else {
+                0
+            };
+            send_raw(
+                plugin,
+                target,
+                false,
+                &options.profile.command_line(command, &token),
+            )?;
+            progress.event("started", serde_json::json!({ "pane": target }));
+            running.push(RunningPane {
+                index,
+                target: target.clone(),
+                token,
+                baseline,
+                start: Instant::now(),
+            });
+        }
+        if running.is_empty() {
+            break;
+        }
+
+        // dump-screen only sees the focused pane, so the running panes are checked in turn.
+        let mut i = 0;
+        while i < running.len() {
+            let pane = &running[i];
+            focus_target(plugin, &pane.target)?;
+            let dump = dump_screen(true)?;
+            let done = options.profile.extract(
+                &String::from_utf8_lossy(&dump),
+                &pane.token,
+                command,
+                pane.baseline,
+            );
+            let elapsed = pane.start.elapsed();
+            let result = match done {
+                Some((output, exit_code)) => RunPaneResult {
+                    pane: pane.target.clone(),
+                    ok: exit_code == 0,
+                    exit_code: Some(exit_code),
+                    output,
+                    duration: elapsed.as_secs_f64(),
+                    error: None,
+                },
+                None if elapsed >= timeout => RunPaneResult {
+                    pane: pane.target.clone(),
+                    ok: false,
+                    exit_code: None,
+                    output: String::new(),
+                    duration: elapsed.as_secs_f64(),
+                    error: Some(format!(
+                        "timed out after {:.1}s (the command may still be running)",
+                        options.timeout
+                    )),
+                },
+                None => {
+                    i += 1;
+                    continue;
+                }
+            };
+            let pane = running.swap_remove(i);
+            progress.event(
+                if result.ok { "passed" } else { "failed" },
+                serde_json::json!({
+                    "pane": result.pane,
+                    "exit_code": result.exit_code,
+                    "duration": result.duration,
+                }),
+            );
+            if !options.json {
+                println!("{}", run_result_line(&result));
+            }
+            results[pane.index] = Some(result);
+        }
+        if !running.is_empty() {
+            sleep(Duration::from_millis(200));
+        }
+    }
+
+    Ok(results)
+}
+
+fn run_result_line(result: &RunPaneResult) -> String {
+    let status = match (result.ok, &result.error, result.exit_code) {
+        (true, _, _) => "ok".to_string(),
+        (false, Some(error), _) => format!("FAILED ({error})"),
+        (false, None, Some(code)) => format!("FAILED (exit {code})"),
+        (false, None, None) => "FAILED".to_string(),
+    };
+    format!("{} {status} {:.1}s", result.pane, result.duration)
+}
+
+pub struct PipeOptions<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+    pub filter: Option<&'a str>,
+    pub interval: f64,
+}
+
+/// Forward new output lines of one pane into another until either pane closes.
+pub fn pipe(
+    plugin: Option<&str>,
+    options: PipeOptions<'_>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if options.interval <= 0.0 {
+        return Err("interval must be greater than 0".into());
+    }
+    let filter = options
+        .filter
+        .map(regex::Regex::new)
+        .transpose()
+        .map_err(|err| format!("invalid --filter: {err}"))?;
+
+    let from = pin_unique(plugin, options.from)?;
+    let to = pin_unique(plugin, options.to)?;
+    if from == to {
+        return Err("--from and --to resolve to the same pane".into());
+    }
+
+    eprintln!("pipe: {from} -> {to} (Ctrl+C to stop)");
+    let _registration = daemon::register("pipe", &format!("{from} -> {to}"));
+    let interval = Duration::from_secs_f64(options.interval);
+    let mut tail = LineTail::default();
+
+    loop {
+        if panes::list_matching(plugin, &to)?.is_empty() {
+            eprintln!("pipe: {to} closed");
+            return Ok(());
+        }
+        if panes::list_matching(plugin, &from)?.is_empty() {
+            eprintln!("pipe: {from} closed");
+            return Ok(());
+        }
+
+        let output = capture_bytes(plugin, &from, true)?;
+        let lines = tail.feed(&String::from_utf8_lossy(&output));
+        let forwarded: String = lines
+            .iter()
+            .filter(|line| filter.as_ref().is_none_or(|re| re.is_match(line)))
+            .map(|line| format!("{line}\n"))
+            .collect();
+        if !forwarded.is_empty() {
+            send_raw(plugin, &to, false, &forwarded)?;
+        }
+
+        sleep(interval);
+    }
+}
+
+/// Resolve a selector that must match exactly one pane to its stable `id:` form
+fn pin_unique(plugin: Option<&str>, selector: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let matched = panes::list_matching(plugin, selector)?;
+    match matched.as_slice() {
+        [] => Err(ClientError::no_match(format!("no panes match selector: {selector}")).into()),
+        [pane] => Ok(pane_id_to_selector(&pane.id).unwrap_or_else(|| selector.to_string())),
+        _ => Err(ClientError::ambiguous(format!(
+            "{} panes match selector: {selector}",
+            matched.len()
+        ))
+        .into()),
+    }
+}
+
+/// Most trailing lines of the previous dump [`LineTail`] looks for in the next one
+const TAIL_ANCHOR_LINES: usize = 8;
+
+/// Tracks which complete lines of a scrollback dump were already seen.
+///
+/// Progress is found by content, not line count: once scrollback reaches Zellij's
+/// `scroll_buffer_size` the dump slides instead of growing, and a redraw can keep the count
+/// while changing lines. The last lines seen are looked up in the new dump and what follows
+/// them is new; when they are gone (cleared screen, redraw), what differs from the previous
+/// dump is.
+///
+/// The last non-empty line is held back until another follows it, since it may still be
+/// written to (a prompt or a progress line).
+#[derive(Debug, Default)]
+struct LineTail {
+    /// Complete lines of the previous dump (`None` before the first)
+    seen: Option<Vec<String>>,
+    /// The held-back last line
+    partial: Option<String>,
+}
+
+impl LineTail {
+    fn feed(&mut self, dump: &str) -> Vec<String> {
+        let mut lines: Vec<String> = dump
+            .lines()
+            .map(|line| line.trim_end().to_string())
+            .collect();
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        self.partial = lines.pop();
+
+        // Start at the end: only output produced after the tail started is new.
+        let new = match &self.seen {
+            None => Vec::new(),
+            Some(seen) => lines[new_lines_from(seen, &lines)..].to_vec(),
+        };
+        self.seen = Some(lines);
+        new
+    }
+}
+
+/// Where the lines not in `seen` start in `lines`: after the last lines of `seen`, which only
+/// move up as scrollback is trimmed, or else after the lines both start with
+fn new_lines_from(seen: &[String], lines: &[String]) -> usize {
+    for len in (1..=seen.len().min(TAIL_ANCHOR_LINES)).rev() {
+        let anchor = &seen[seen.len() - len..];
+        let latest = seen.len() - len;
+        if let Some(at) = (0..=latest.min(lines.len().saturating_sub(len)))
+            .rev()
+            .find(|&at| lines[at..].starts_with(anchor))
+        {
+            return at + len;
+        }
+    }
+    seen.iter()
+        .zip(lines)
+        .take_while(|(seen, line)| seen == line)
+        .count()
+}
+
+pub struct WatchOptions<'a> {
+    pub selector: &'a str,
+    pub until: Option<&'a str>,
+    pub interval: f64,
+    pub timeout: Option<f64>,
+}
+
+/// Stream lines newly written to a pane, like `tail -f`
+pub fn watch(
+    plugin: Option<&str>,
+    options: WatchOptions<'_>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if options.interval <= 0.0 {
+        return Err("interval must be greater than 0".into());
+    }
+    let until = options
+        .until
+        .map(regex::Regex::new)
+        .transpose()
+        .map_err(|err| format!("invalid --until: {err}"))?;
+    let timeout = options.timeout.map(Duration::from_secs_f64);
+
+    let target = pin_unique(plugin, options.selector)?;
+    let _registration = daemon::register("watch", &target);
+    let interval = Duration::from_secs_f64(options.interval);
+    let start = Instant::now();
+    let mut tail = LineTail::default();
+    let mut stdout = std::io::stdout();
+
+    // Ctrl-C is how a watch usually ends; let a capture in progress give focus back first.
+    interrupt::install();
+    loop {
+        interrupt::check()?;
+        if panes::list_matching(plugin, &target)?.is_empty() {
+            if until.is_some() {
+                return Err(format!("{target} closed before --until matched").into());
+            }
+            return Ok(());
+        }
+
+        let dump = capture_bytes(plugin, &target, true)?;
+        let (lines, matched) =
+            watch_feed(&mut tail, until.as_ref(), &String::from_utf8_lossy(&dump));
+        for line in &lines {
+            writeln!(stdout, "{line}")?;
+        }
+        stdout.flush()?;
+        if matched {
+            return Ok(());
+        }
+
+        if timeout.is_some_and(|timeout| start.elapsed() >= timeout) {
+            return Err(format!(
+                "timed out after {:.1}s",
+                options.timeout.unwrap_or_default()
+            )
+            .into());
+        }
+        sleep(interval);
+    }
+}
+
+/// The lines `pane watch` prints for one dump, and whether the last of them matched `until`
+fn watch_feed(
+    tail: &mut LineTail,
+    until: Option<&regex::Regex>,
+    dump: &str,
+) -> (Vec<String>, bool) {
+    let before = tail.partial.clone();
+    let mut lines = tail.feed(dump);
+    let fresh = before.is_some() && (!lines.is_empty() || before != tail.partial);
+    let Some(re) = until else {
+        return (lines, false);
+    };
+    if let Some(at) = lines.iter().position(|line| re.is_match(line)) {
+        lines.truncate(at + 1);
+        return (lines, true);
+    }
+    // The held-back last line (e.g. "Listening on :3000" or a prompt) can satisfy --until
+    // before anything follows it.
+    match &tail.partial {
+        Some(partial) if fresh && re.is_match(partial) => {
+            lines.push(partial.clone());
+            (lines, true)
+        }
+        _ => (lines, false),
+    }
+}
+
+pub struct OnChangeOptions<'a> {
+    pub selector: &'a str,
+    /// Shell command; `{}` is replaced with the path of a file holding the changed lines
+    pub exec: &'a str,
+    /// Quiet period after a change before the command runs (seconds)
+    pub debounce: f64,
+    pub interval: f64,
+    /// Compare the full scrollback instead of the visible screen
+    pub full: bool,
+    /// What is not counted as a change
+    pub mask: ChangeMask,
+}
 
-pub fn send(
+/// Run a command each time a pane's content changes and then settles; stops when the pane
+/// closes.
+///
+/// The changed lines (everything between the first and last line that differ from the content
+/// the command last saw) go to the command's stdin and to the file substituted for `{}`.
+pub fn on_change(
     plugin: Option<&str>,
-    selector: &str,
-    all: bool,
-    enter: bool,
-    delay_enter: f64,
-    bytes: &[String],
+    options: OnChangeOptions<'_>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let text = bytes.join(" ");
+    if options.interval <= 0.0 {
+        return Err("interval must be greater than 0".into());
+    }
+    if options.debounce < 0.0 {
+        return Err("debounce must not be negative".into());
+    }
+    if options.exec.trim().is_empty() {
+        return Err("--exec must not be empty".into());
+    }
+    let interval = Duration::from_secs_f64(options.interval);
+    let debounce = Duration::from_secs_f64(options.debounce);
 
-    let steps = build_send_steps(&text, enter, delay_enter)?;
-    for step in steps {
-        match step {
-            SendStep::Text(text) => send_raw(plugin, selector, all, &text)?,
-            SendStep::Delay(duration) => sleep(duration),
+    let target = pin_unique(plugin, options.selector)?;
+    let capture = |target: &str| -> Result<String, Box<dyn std::error::Error>> {
+        Ok(String::from_utf8_lossy(&capture_bytes(plugin, target, options.full)?).into_owned())
+    };
+    let mut seen = capture(&target)?;
+    let mut latest = seen.clone();
+    let mut changed_at: Option<Instant> = None;
+
+    loop {
+        sleep(interval);
+        if panes::list_matching(plugin, &target)?.is_empty() {
+            return Ok(());
         }
+        let content = capture(&target)?;
+        if options.mask.normalize(&content) != options.mask.normalize(&latest) {
+            latest = content;
+            changed_at = Some(Instant::now());
+            continue;
+        }
+        if !changed_at.is_some_and(|at| at.elapsed() >= debounce) {
+            continue;
+        }
+        changed_at = None;
+        if let Some(region) = changed_region(&seen, &latest) {
+            if let Err(err) = run_change_callback(options.exec, &target, &region) {
+                eprintln!("on-change: {err}");
+            }
+        }
+        seen = latest.clone();
+    }
+}
+
+/// Lines of `new` between the first and the last line that differ from `old`; `None` when the
+/// visible text is the same
+fn changed_region(old: &str, new: &str) -> Option<String> {
+    let old: Vec<&str> = old.lines().map(str::trim_end).collect();
+    let new: Vec<&str> = new.lines().map(str::trim_end).collect();
+    if old == new {
+        return None;
+    }
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut region = new[prefix..new.len() - suffix].join("\n");
+    if !region.is_empty() {
+        region.push('\n');
+    }
+    Some(region)
+}
+
+fn run_change_callback(
+    exec: &str,
+    target: &str,
+    region: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::temp_dir().join(format!("zjctl-change-{}.txt", uuid::Uuid::new_v4()));
+    fs::write(&path, region)?;
+    let quoted = shell_quote(&path.display().to_string());
+    let argv = crate::commands::watchdog::shell_command(&exec.replace("{}", &quoted));
+
+    let result = (|| -> Result<std::process::ExitStatus, Box<dyn std::error::Error>> {
+        let mut child = std::process::Command::new(&argv[0])
+            .args(&argv[1..])
+            .env("ZJCTL_PANE", target)
+            .env("ZJCTL_CHANGED_FILE", &path)
+            .stdin(std::process::Stdio::piped())
+            .spawn()
+            .map_err(|err| format!("failed to run {}: {err}", argv[0]))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // A command that ignores stdin may exit before reading it.
+            let _ = stdin.write_all(region.as_bytes());
+        }
+        Ok(child.wait()?)
+    })();
+    let _ = fs::remove_file(&path);
+
+    let status = result?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("command failed: {status}").into())
     }
+}
+
+pub fn focus(plugin: Option<&str>, selector: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let params = serde_json::json!({
+        "selector": selector,
+    });
+
+    client::rpc_call(plugin, methods::PANE_FOCUS, params)?;
     Ok(())
 }
 
-pub fn interrupt(
+/// Focus the match after the focused pane (in tab, then id order), wrapping around; the
+/// first match when the focused pane is not one of them
+pub fn focus_next(plugin: Option<&str>, selector: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut matched = panes::list_matching(plugin, selector)?;
+    if matched.is_empty() {
+        return Err(Box::new(ClientError::no_match(format!(
+            "no panes match {selector}"
+        ))));
+    }
+    matched.sort_by_key(|pane| (pane.tab_index, pane_sort_key(&pane.id)));
+    let focused = panes::list_matching(plugin, "focused")?;
+    let current = focused
+        .first()
+        .and_then(|focused| matched.iter().position(|pane| pane.id == focused.id));
+    let next = match current {
+        Some(index) => &matched[(index + 1) % matched.len()],
+        None => &matched[0],
+    };
+    let target = pane_id_to_selector(&next.id).unwrap_or_else(|| next.id.clone());
+    focus(plugin, &target)?;
+    println!("{target}");
+    Ok(())
+}
+
+/// `terminal:3` before `terminal:10`, terminals before plugins
+fn pane_sort_key(id: &str) -> (bool, u32) {
+    let (kind, number) = id.split_once(':').unwrap_or((id, ""));
+    (kind == "plugin", number.parse().unwrap_or(u32::MAX))
+}
+
+pub fn toggle(plugin: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    client::rpc_call(plugin, methods::PANE_FOCUS_PREVIOUS, serde_json::json!({}))?;
+    Ok(())
+}
+
+pub fn rename(
     plugin: Option<&str>,
     selector: &str,
-    all: bool,
+    name: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    send_raw(plugin, selector, all, "\u{3}")
+    let params = serde_json::json!({
+        "selector": selector,
+        "name": name,
+    });
+
+    client::rpc_call(plugin, methods::PANE_RENAME, params)?;
+    Ok(())
 }
 
-pub fn escape(
+/// Float tiled panes and embed floating ones
+pub fn toggle_float(
     plugin: Option<&str>,
     selector: &str,
     all: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    send_raw(plugin, selector, all, "\u{1b}")
+    let params = serde_json::json!({
+        "selector": selector,
+        "all": all,
+    });
+    let result = client::rpc_call(plugin, methods::PANE_TOGGLE_FLOAT, params)?;
+    for entry in result["results"].as_array().into_iter().flatten() {
+        let id = entry["id"].as_str().unwrap_or_default();
+        let selector = pane_id_to_selector(id).unwrap_or_else(|| id.to_string());
+        if entry["floating"].as_bool().unwrap_or(false) {
+            println!("floated {selector}");
+        } else {
+            println!("embedded {selector}");
+        }
+    }
+    Ok(())
 }
 
-pub fn capture(
+pub fn toggle_fullscreen(
     plugin: Option<&str>,
     selector: &str,
-    full: bool,
-    no_restore: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let selection = resolve_selection(plugin, selector)?;
-    let restore = if no_restore {
-        None
-    } else {
-        selection.restore_selector.clone()
-    };
-
-    focus_target(plugin, &selection.target_selector)?;
-    let output = dump_screen(full)?;
-
-    if let Some(selector) = restore {
-        let _ = focus_target(plugin, &selector);
-    }
+    let params = serde_json::json!({ "selector": selector });
+    client::rpc_call(plugin, methods::PANE_TOGGLE_FULLSCREEN, params)?;
+    Ok(())
+}
 
-    let mut stdout = std::io::stdout();
-    stdout.write_all(&output)?;
+/// Pin floating panes above the tiled ones (or unpin them)
+pub fn pin(
+    plugin: Option<&str>,
+    selector: &str,
+    all: bool,
+    pinned: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let params = serde_json::json!({
+        "selector": selector,
+        "all": all,
+        "pinned": pinned,
+    });
+    client::rpc_call(plugin, methods::PANE_PIN, params)?;
     Ok(())
 }
 
-pub fn wait_idle(
+/// Clear the screen of the matched panes, and their scrollback with `scrollback`
+///
+/// The screen is cleared first and the scrollback after the program had a moment to redraw,
+/// so lines pushed off the screen by the redraw do not survive in the scrollback.
+pub fn clear(
     plugin: Option<&str>,
     selector: &str,
-    idle_time: f64,
-    timeout: f64,
-    full: bool,
-    no_restore: bool,
+    all: bool,
+    screen: bool,
+    scrollback: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    if idle_time <= 0.0 {
-        return Err("idle_time must be greater than 0".into());
+    if screen {
+        let params = serde_json::json!({
+            "selector": selector,
+            "all": all,
+            "screen": true,
+            "scrollback": false,
+        });
+        client::rpc_call(plugin, methods::PANE_CLEAR, params)?;
     }
-    if timeout <= 0.0 {
-        return Err("timeout must be greater than 0".into());
+    if scrollback {
+        if screen {
+            std::thread::sleep(CLEAR_REDRAW_DELAY);
+        }
+        let params = serde_json::json!({
+            "selector": selector,
+            "all": all,
+            "screen": false,
+            "scrollback": true,
+        });
+        client::rpc_call(plugin, methods::PANE_CLEAR, params)?;
     }
+    Ok(())
+}
 
-    let selection = resolve_selection(plugin, selector)?;
-    let restore = if no_restore {
-        None
-    } else {
-        selection.restore_selector.clone()
-    };
-
-    focus_target(plugin, &selection.target_selector)?;
-
-    let idle_duration = Duration::from_secs_f64(idle_time);
-    let timeout_duration = Duration::from_secs_f64(timeout);
-    let poll_interval = poll_interval(idle_time);
-
-    let start = Instant::now();
-    let mut last_change = Instant::now();
-    let mut last_hash = hash_bytes(&dump_screen(full)?);
+pub struct MoveOptions<'a> {
+    /// Tab index, name, `current`, or `new`
+    pub to_tab: Option<&'a str>,
+    /// `left`, `right`, `up`, or `down`
+    pub position: Option<&'a str>,
+    /// Name for the tab created by `to_tab: Some("new")`
+    pub tab_name: Option<&'a str>,
+    pub all: bool,
+    /// Follow the panes to their new tab
+    pub focus: bool,
+}
 
-    loop {
-        if last_change.elapsed() >= idle_duration {
-            break;
-        }
-        if start.elapsed() >= timeout_duration {
-            if let Some(selector) = restore {
-                let _ = focus_target(plugin, &selector);
+/// Move panes to another tab and/or swap them with a neighbour, without focusing them
+pub fn move_panes(
+    plugin: Option<&str>,
+    selector: &str,
+    options: &MoveOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut params = serde_json::json!({
+        "selector": selector,
+        "all": options.all,
+        "focus": options.focus,
+    });
+    match options.to_tab {
+        Some("new") => {
+            params["tab"] = serde_json::json!("new");
+            if let Some(name) = options.tab_name {
+                params["tab_name"] = serde_json::json!(name);
             }
-            return Err(format!("timed out after {timeout:.1}s").into());
         }
-
-        sleep(poll_interval);
-        let current_hash = hash_bytes(&dump_screen(full)?);
-        if current_hash != last_hash {
-            last_hash = current_hash;
-            last_change = Instant::now();
+        Some(spec) => {
+            let tabs = tab::list(plugin)?;
+            params["tab"] = serde_json::json!(tab::resolve_tab_spec(spec, &tabs)?);
         }
+        None => {}
     }
-
-    if let Some(selector) = restore {
-        let _ = focus_target(plugin, &selector);
+    if let Some(direction) = options.position {
+        params["direction"] = serde_json::json!(direction);
     }
-
+    client::rpc_call(plugin, methods::PANE_MOVE, params)?;
     Ok(())
 }
 
-pub fn focus(plugin: Option<&str>, selector: &str) -> Result<(), Box<dyn std::error::Error>> {
+/// Record a pane's working directory for `cwd:` selectors
+pub fn set_cwd(
+    plugin: Option<&str>,
+    selector: &str,
+    cwd: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
     let params = serde_json::json!({
         "selector": selector,
+        "cwd": absolute_cwd(cwd)?,
     });
-
-    client::rpc_call(plugin, methods::PANE_FOCUS, params)?;
+    client::rpc_call(plugin, methods::PANE_SET_CWD, params)?;
     Ok(())
 }
 
-pub fn rename(
+/// `~` expanded and relative paths resolved against zjctl's own working directory, since the
+/// plugin runs elsewhere
+pub(crate) fn absolute_cwd(cwd: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let path = std::path::absolute(paths::expand_tilde(cwd))
+        .map_err(|err| format!("invalid directory {cwd}: {err}"))?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+pub struct ScrollOptions<'a> {
+    pub selector: &'a str,
+    pub up: bool,
+    pub down: bool,
+    pub top: bool,
+    pub bottom: bool,
+    pub page: bool,
+    pub half_page: bool,
+    /// Lines, or pages with `page`/`half_page`
+    pub count: usize,
+}
+
+/// Scroll a pane's viewport, e.g. before capturing part of its backlog
+pub fn scroll(
     plugin: Option<&str>,
-    selector: &str,
-    name: &str,
+    options: ScrollOptions<'_>,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    if options.count == 0 {
+        return Err("count must be >= 1".into());
+    }
+    let direction = match (options.up, options.down, options.top, options.bottom) {
+        (true, _, _, _) => "up",
+        (_, true, _, _) => "down",
+        (_, _, true, _) => "top",
+        (_, _, _, true) => "bottom",
+        _ => return Err("must specify --up, --down, --top, or --bottom".into()),
+    };
+    let unit = if options.page {
+        "page"
+    } else if options.half_page {
+        "half_page"
+    } else {
+        "line"
+    };
+
     let params = serde_json::json!({
-        "selector": selector,
-        "name": name,
+        "selector": options.selector,
+        "direction": direction,
+        "unit": unit,
+        "count": options.count,
     });
 
-    client::rpc_call(plugin, methods::PANE_RENAME, params)?;
+    client::rpc_call(plugin, methods::PANE_SCROLL, params)?;
     Ok(())
 }
 
@@ -205,7 +2431,7 @@ pub fn resize(
     Ok(())
 }
 
-fn resize_to(
+pub fn resize_to(
     plugin: Option<&str>,
     selector: &str,
     cols: Option<usize>,
@@ -421,21 +2647,163 @@ fn pane_matches_id(selector: &str, pane: &panes::PaneInfo) -> bool {
 pub fn close(
     plugin: Option<&str>,
     selector: &str,
+    all: bool,
     force: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let selection = resolve_selection(plugin, selector)?;
-    check_close_allowed(&selection, force)?;
+    if all {
+        return close_all(plugin, selector, force);
+    }
+
+    // The plugin closes by id, so suppressed panes, plugin panes, and panes in other tabs close
+    // without moving focus.
+    let params = serde_json::json!({
+        "selector": selector,
+        "force": force,
+    });
+    let result = client::rpc_call(plugin, methods::PANE_CLOSE, params)?;
+    let results: Vec<CloseResult> = serde_json::from_value(result["results"].clone())?;
+    close_outcome(&results).map_err(Into::into)
+}
+
+/// Per-pane outcome of a pane.close request
+#[derive(Debug, Deserialize)]
+struct CloseResult {
+    id: String,
+    closed: bool,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+fn close_all(
+    plugin: Option<&str>,
+    selector: &str,
+    force: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let params = serde_json::json!({
+        "selector": selector,
+        "all": true,
+        "force": force,
+    });
+
+    let result = client::rpc_call(plugin, methods::PANE_CLOSE, params)?;
+    let results: Vec<CloseResult> = serde_json::from_value(result["results"].clone())?;
+
+    for result in &results {
+        let selector = pane_id_to_selector(&result.id).unwrap_or_else(|| result.id.clone());
+        match (result.closed, &result.error) {
+            (true, _) => println!("closed {selector}"),
+            (false, Some(error)) => println!("skipped {selector} ({error})"),
+            (false, None) => println!("skipped {selector}"),
+        }
+    }
+
+    close_summary(&results, outcome::policy()).map_err(Into::into)
+}
+
+/// Outcome of closing a single pane
+fn close_outcome(results: &[CloseResult]) -> Result<(), String> {
+    match results {
+        [result] if result.closed => Ok(()),
+        [result] => Err(result
+            .error
+            .clone()
+            .unwrap_or_else(|| format!("{} was not closed", result.id))),
+        _ => Err(format!(
+            "expected one pane.close result, got {}",
+            results.len()
+        )),
+    }
+}
+
+/// The plugin closes every pane in one call, so `--fail-fast` has nothing left to skip
+fn close_summary(results: &[CloseResult], policy: FailurePolicy) -> Result<(), Failures> {
+    let mut outcomes = Outcomes::with_policy(policy);
+    for result in results {
+        let selector = pane_id_to_selector(&result.id).unwrap_or_else(|| result.id.clone());
+        match (result.closed, &result.error) {
+            (true, _) => outcomes.ok(selector),
+            (false, Some(error)) => outcomes.failed(selector, error),
+            (false, None) => outcomes.failed(selector, "not closed"),
+        }
+    }
+    outcomes.finish("panes")
+}
+
+pub fn only(
+    plugin: Option<&str>,
+    selector: &str,
+    yes: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let matched = panes::list_matching(plugin, selector)?;
+    let target = match matched.as_slice() {
+        [] => return Err(ClientError::no_match("no panes match selector").into()),
+        [pane] => pane.clone(),
+        _ => {
+            return Err(
+                ClientError::ambiguous(format!("{} panes match selector", matched.len())).into(),
+            )
+        }
+    };
+
+    let others = other_panes_in_tab(&panes::list(plugin)?, &target);
+    if others.is_empty() {
+        println!("No other panes in tab {}", target.tab_name);
+        return Ok(());
+    }
 
-    focus_target(plugin, &selection.target_selector)?;
-    run_close_pane_action()?;
+    if !yes {
+        for pane in &others {
+            eprintln!("  {} {}", pane.id, pane.title);
+        }
+        let prompt = format!(
+            "Close {} other pane(s) in tab {}?",
+            others.len(),
+            target.tab_name
+        );
+        if !confirm(&prompt)? {
+            return Err("aborted".into());
+        }
+    }
 
-    if let Some(selector) = selection.restore_selector {
-        let _ = focus_target(plugin, &selector);
+    let target_selector = pane_id_to_selector(&target.id).unwrap_or_else(|| target.id.clone());
+    focus(plugin, &target_selector)?;
+
+    for pane in &others {
+        let selector = pane_id_to_selector(&pane.id).unwrap_or_else(|| pane.id.clone());
+        let params = serde_json::json!({
+            "selector": selector,
+            "force": true,
+        });
+        client::rpc_call(plugin, methods::PANE_CLOSE, params)?;
+        println!("closed {selector}");
     }
 
     Ok(())
 }
 
+/// Visible terminal panes sharing the target's tab (plugin panes like the tab bar are kept)
+fn other_panes_in_tab(panes: &[panes::PaneInfo], target: &panes::PaneInfo) -> Vec<panes::PaneInfo> {
+    panes
+        .iter()
+        .filter(|p| p.tab_index == target.tab_index && p.id != target.id)
+        .filter(|p| p.pane_type == "terminal" && !p.suppressed)
+        .cloned()
+        .collect()
+}
+
+/// Ask a yes/no question on the terminal; refuses when stdin is not interactive
+fn confirm(prompt: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        return Err("confirmation required but stdin is not a terminal (use --yes)".into());
+    }
+    eprint!("{prompt} [y/N] ");
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    stdin.read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
 pub struct LaunchOptions<'a> {
     pub direction: Option<&'a str>,
     pub floating: bool,
@@ -447,10 +2815,132 @@ pub struct LaunchOptions<'a> {
     pub command: &'a [String],
 }
 
+/// Resolver `pane launch --cwd-query` runs unless `--cwd-resolver` says otherwise
+pub const DEFAULT_CWD_RESOLVER: &str = "zoxide query -- {}";
+
+/// Directory for `pane launch --cwd-query QUERY`: the first line `resolver` prints
+///
+/// `resolver` is a shell command with `{}` standing for the quoted query (appended when there
+/// is no `{}`), so anything from `zoxide query` to `fd | fzf --filter` can be plugged in.
+pub fn resolve_cwd(resolver: &str, query: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let quoted = shell_quote(query);
+    let command = if resolver.contains("{}") {
+        resolver.replace("{}", &quoted)
+    } else {
+        format!("{resolver} {quoted}")
+    };
+    let argv = crate::commands::watchdog::shell_command(&command);
+    let output = std::process::Command::new(&argv[0])
+        .args(&argv[1..])
+        .stdin(std::process::Stdio::null())
+        .stderr(std::process::Stdio::inherit())
+        .output()
+        .map_err(|err| format!("failed to run {}: {err}", argv[0]))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let dir = stdout.lines().map(str::trim).find(|line| !line.is_empty());
+    let dir = match dir {
+        Some(dir) if output.status.success() => dir,
+        _ => {
+            return Err(format!(
+                "no directory found for `{query}` (`{command}` {}); \
+                 is the resolver installed? (--cwd-resolver, ZJCTL_CWD_RESOLVER)",
+                if output.status.success() {
+                    "printed nothing".to_string()
+                } else {
+                    format!("failed: {}", output.status)
+                }
+            )
+            .into())
+        }
+    };
+    if !Path::new(dir).is_dir() {
+        return Err(format!("`{command}` printed {dir}, which is not a directory").into());
+    }
+    Ok(dir.to_string())
+}
+
+fn shell_quote(arg: &str) -> String {
+    if cfg!(windows) {
+        format!("\"{arg}\"")
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// Environment variable `pane launch --token` exports into the new pane
+pub const PANE_TOKEN_ENV: &str = "ZJCTL_PANE_TOKEN";
+
+/// Open a pane and print its selector. With `token` (empty: generate one), the pane's command
+/// runs under `env ZJCTL_PANE_TOKEN=<token>`, the plugin records the token, and `token:<token>`
+/// is printed instead; the token keeps naming the pane whatever its title or program becomes.
 pub fn launch(
     plugin: Option<&str>,
     options: LaunchOptions<'_>,
+    token: Option<&str>,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(token) = token else {
+        println!("{}", launch_pane(plugin, &options)?);
+        return Ok(());
+    };
+    let token = if token.is_empty() {
+        uuid::Uuid::new_v4().simple().to_string()[..12].to_string()
+    } else if token
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        token.to_string()
+    } else {
+        return Err("token may only contain letters, digits, '-', '_', and '.'".into());
+    };
+
+    // A shell pane becomes a command pane running the shell; close it with the shell as usual.
+    let shell = std::env::var("SHELL").unwrap_or_else(|_| "sh".to_string());
+    let command = token_command(options.command, &token, &shell);
+    let selector = launch_pane(
+        plugin,
+        &LaunchOptions {
+            command: &command,
+            close_on_exit: options.close_on_exit || options.command.is_empty(),
+            ..options
+        },
+    )?;
+    client::rpc_call(
+        plugin,
+        methods::PANE_SET_TOKEN,
+        serde_json::json!({ "selector": selector, "token": token }),
+    )?;
+    println!("token:{token}");
+    Ok(())
+}
+
+/// `env ZJCTL_PANE_TOKEN=<token> <command>`, running `shell` when there is no command
+fn token_command(command: &[String], token: &str, shell: &str) -> Vec<String> {
+    let program = if command.is_empty() {
+        vec![shell.to_string()]
+    } else {
+        command.to_vec()
+    };
+    ["env".to_string(), format!("{PANE_TOKEN_ENV}={token}")]
+        .into_iter()
+        .chain(program)
+        .collect()
+}
+
+/// Open a new pane and return its `id:` selector
+pub fn launch_pane(
+    plugin: Option<&str>,
+    options: &LaunchOptions<'_>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    // `zellij action new-pane --cwd` takes `~/app` literally; expand it like the RPC path does.
+    let cwd = options.cwd.map(absolute_cwd).transpose()?;
+    let options = &LaunchOptions {
+        cwd: cwd.as_deref(),
+        ..*options
+    };
+    if launches_over_rpc(options) {
+        return launch_over_rpc(plugin, options);
+    }
+
     let before = panes::list(plugin)?;
     let focused_tab_index = before.iter().find(|p| p.focused).map(|p| p.tab_index);
     let before_max_terminal_id = before
@@ -459,7 +2949,7 @@ pub fn launch(
         .max()
         .unwrap_or(0);
 
-    run_new_pane_action(&options)?;
+    run_new_pane_action(options)?;
 
     let start = Instant::now();
     let timeout = Duration::from_secs(180);
@@ -468,7 +2958,7 @@ pub fn launch(
     let pane = loop {
         let after = panes::list(plugin)?;
         if let Some(pane) =
-            find_new_terminal_pane(&after, focused_tab_index, &options, before_max_terminal_id)
+            find_new_terminal_pane(&after, focused_tab_index, options, before_max_terminal_id)
         {
             break pane;
         }
@@ -482,13 +2972,45 @@ pub fn launch(
         sleep(interval);
     };
 
-    if let Some(selector) = pane_id_to_selector(&pane.id) {
-        println!("{selector}");
-    } else {
-        println!("{}", pane.id);
+    let selector = pane_id_to_selector(&pane.id).unwrap_or_else(|| pane.id.clone());
+
+    // Register the name with the plugin so `name:` selectors can tell it apart from the title.
+    if let Some(name) = options.name {
+        rename(plugin, &selector, name)?;
+    }
+    if let Some(cwd) = options.cwd {
+        set_cwd(plugin, &selector, cwd)?;
     }
 
-    Ok(())
+    Ok(selector)
+}
+
+/// The plugin opens command panes and learns their id directly, but its API has no split
+/// direction, close-on-exit, or suspended start; those and plain shell panes go through
+/// `zellij action` and are found by diffing the pane list.
+fn launches_over_rpc(options: &LaunchOptions<'_>) -> bool {
+    !options.command.is_empty()
+        && options.direction.is_none()
+        && !options.close_on_exit
+        && !options.start_suspended
+}
+
+fn launch_over_rpc(
+    plugin: Option<&str>,
+    options: &LaunchOptions<'_>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let params = serde_json::json!({
+        "command": options.command,
+        "cwd": options.cwd,
+        "name": options.name,
+        "floating": options.floating,
+        "in_place": options.in_place,
+    });
+    let result = client::rpc_call(plugin, methods::PANE_LAUNCH, params)?;
+    let id = result["id"]
+        .as_str()
+        .ok_or("pane.launch returned no pane id")?;
+    Ok(pane_id_to_selector(id).unwrap_or_else(|| id.to_string()))
 }
 
 fn find_new_terminal_pane(
@@ -531,43 +3053,114 @@ fn parse_terminal_id(id: &str) -> Option<u32> {
     numeric.parse().ok()
 }
 
-fn send_raw(
+pub fn send_raw(
     plugin: Option<&str>,
     selector: &str,
     all: bool,
     text: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let params = serde_json::json!({
+    send_keyed(plugin, selector, all, text, None, ScrollGuard::Fail)?;
+    Ok(())
+}
+
+/// `pane.send` with an optional idempotency key; `false` if the plugin skipped it as a duplicate
+fn send_keyed(
+    plugin: Option<&str>,
+    selector: &str,
+    all: bool,
+    text: &str,
+    key: Option<IdempotencyKey<'_>>,
+    scroll: ScrollGuard,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut params = serde_json::json!({
         "selector": selector,
         "all": all,
         "text": text,
+        "scroll_mode": scroll.as_str(),
     });
-
-    client::rpc_call(plugin, methods::PANE_SEND, params)?;
-    Ok(())
-}
-
-fn run_close_pane_action() -> Result<(), Box<dyn std::error::Error>> {
-    let status = zellij::command()
-        .args(["action", "close-pane"])
-        .status()
-        .map_err(|err| format!("failed to run zellij: {err}"))?;
-    if status.success() {
-        Ok(())
-    } else {
-        Err(format!("zellij action close-pane failed: {status:?}").into())
+    if let Some(key) = key {
+        params["idempotency_key"] = serde_json::json!(key.key);
+        params["idempotency_window"] = serde_json::json!(key.window);
     }
+
+    let result = client::rpc_call(plugin, methods::PANE_SEND, params)?;
+    Ok(!result["duplicate"].as_bool().unwrap_or(false))
 }
 
 #[derive(Debug)]
 enum SendStep {
     Text(String),
     Delay(Duration),
+    /// Wait for the target pane to finish rendering
+    Settle,
+}
+
+/// Split large text writes into `size`-byte chunks (on character boundaries) separated by
+/// `delay`, since a single huge write can drop input in some applications.
+fn chunk_steps(steps: Vec<SendStep>, size: usize, delay: Duration, verify: bool) -> Vec<SendStep> {
+    if size == 0 {
+        return steps;
+    }
+    let mut chunked = Vec::new();
+    for step in steps {
+        let SendStep::Text(text) = step else {
+            chunked.push(step);
+            continue;
+        };
+        if text.len() <= size {
+            chunked.push(SendStep::Text(text));
+            continue;
+        }
+        let chunks = split_chunks(&text, size);
+        let last = chunks.len() - 1;
+        for (index, chunk) in chunks.into_iter().enumerate() {
+            chunked.push(SendStep::Text(chunk.to_string()));
+            if index < last {
+                if verify {
+                    chunked.push(SendStep::Settle);
+                }
+                if !delay.is_zero() {
+                    chunked.push(SendStep::Delay(delay));
+                }
+            }
+        }
+    }
+    chunked
+}
+
+fn split_chunks(text: &str, size: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let mut end = size.min(rest.len());
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        // A character wider than the chunk size still has to go out whole.
+        if end == 0 {
+            end = rest.chars().next().map_or(rest.len(), char::len_utf8);
+        }
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks
+}
+
+fn clear_steps(clear_line: Option<ClearLine>) -> Vec<SendStep> {
+    let Some(clear) = clear_line else {
+        return Vec::new();
+    };
+    let mut steps = vec![SendStep::Text(clear.sequence().to_string())];
+    if !clear.settle().is_zero() {
+        steps.push(SendStep::Delay(clear.settle()));
+    }
+    steps
 }
 
 fn build_send_steps(
     text: &str,
-    enter: bool,
+    enter: Option<EnterKey>,
     delay_enter: f64,
 ) -> Result<Vec<SendStep>, Box<dyn std::error::Error>> {
     if delay_enter < 0.0 {
@@ -579,17 +3172,18 @@ fn build_send_steps(
         steps.push(SendStep::Text(text.to_string()));
     }
 
-    if enter {
+    if let Some(key) = enter {
         if delay_enter > 0.0 {
             steps.push(SendStep::Delay(Duration::from_secs_f64(delay_enter)));
         }
-        steps.push(SendStep::Text("\n".to_string()));
+        steps.push(SendStep::Text(key.sequence().to_string()));
     }
 
     Ok(steps)
 }
 
 fn run_new_pane_action(options: &LaunchOptions<'_>) -> Result<(), Box<dyn std::error::Error>> {
+    client::ensure_writable("opening a pane")?;
     let mut cmd = zellij::command();
     if options.command.is_empty() {
         cmd.args(["action", "new-pane"]);
@@ -659,6 +3253,37 @@ fn find_new_pane(
 struct Selection {
     target_selector: String,
     restore_selector: Option<String>,
+    /// The target is the focused pane, so it needs no `pane.focus` (which read-only mode refuses)
+    focused: bool,
+}
+
+/// Content size of the one pane `selector` matches, after checking that `region` fits in it
+fn region_geometry(
+    plugin: Option<&str>,
+    selector: &str,
+    region: &CaptureRegion,
+) -> Result<(usize, usize), Box<dyn std::error::Error>> {
+    let matched = panes::list_matching(plugin, selector)?;
+    let pane = match matched.as_slice() {
+        [pane] => pane,
+        [] => {
+            return Err(
+                ClientError::no_match(format!("no panes match selector: {selector}")).into(),
+            )
+        }
+        _ => {
+            return Err(ClientError::ambiguous(format!(
+                "{} panes match selector: {selector}",
+                matched.len()
+            ))
+            .into())
+        }
+    };
+    // Plugins that report no size leave the check to the dump.
+    if pane.rows > 0 && pane.cols > 0 {
+        region.bounds(pane.rows, pane.cols)?;
+    }
+    Ok((pane.rows, pane.cols))
 }
 
 fn resolve_selection(
@@ -677,14 +3302,75 @@ fn resolve_selection(
         selector.to_string()
     };
 
+    let focused = target_selector == "focused"
+        || focused_selector.as_deref() == Some(target_selector.as_str());
     let restore_selector = focused_selector.filter(|focused| *focused != target_selector);
 
     Ok(Selection {
         target_selector,
         restore_selector,
+        focused,
     })
 }
 
+/// Focuses the pane that had focus again when dropped, so timeouts, errors, and Ctrl-C all
+/// hand the user's focus back
+struct RestoreFocus<'a> {
+    plugin: Option<&'a str>,
+    selector: Option<String>,
+    /// Restoring after a capture, see [`capture_focus`]
+    capture: bool,
+}
+
+impl<'a> RestoreFocus<'a> {
+    fn after(plugin: Option<&'a str>, selection: &Selection) -> Self {
+        Self {
+            plugin,
+            selector: selection.restore_selector.clone(),
+            capture: false,
+        }
+    }
+
+    fn after_capture(plugin: Option<&'a str>, selection: &Selection) -> Self {
+        Self {
+            plugin,
+            selector: selection.restore_selector.clone(),
+            capture: true,
+        }
+    }
+
+    /// [`capture_focus_selection`]; there is nothing to restore when the daemon reads the pane
+    fn focus(&mut self, selection: &Selection) -> Result<Dump, Box<dyn std::error::Error>> {
+        let dump = capture_focus_selection(self.plugin, selection)?;
+        if let Dump::Daemon(_) = dump {
+            self.selector = None;
+        }
+        Ok(dump)
+    }
+}
+
+impl Drop for RestoreFocus<'_> {
+    fn drop(&mut self) {
+        if let Some(selector) = self.selector.take() {
+            let _ = if self.capture {
+                capture_focus(self.plugin, &selector)
+            } else {
+                focus_target(self.plugin, &selector)
+            };
+        }
+    }
+}
+
+fn focus_selection(
+    plugin: Option<&str>,
+    selection: &Selection,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if selection.focused {
+        return Ok(());
+    }
+    focus_target(plugin, &selection.target_selector)
+}
+
 fn focus_target(plugin: Option<&str>, selector: &str) -> Result<(), Box<dyn std::error::Error>> {
     if selector == "focused" {
         return Ok(());
@@ -692,7 +3378,55 @@ fn focus_target(plugin: Option<&str>, selector: &str) -> Result<(), Box<dyn std:
     focus(plugin, selector)
 }
 
-fn pane_id_to_selector(id: &str) -> Option<String> {
+/// How a pane's screen is read after [`capture_focus_selection`]
+enum Dump {
+    /// The pane has focus, so `zellij action dump-screen` reads it
+    Focused,
+    /// The daemon refused to focus the pane for a caller that may only read; it reads the pane
+    /// (by this selector) itself
+    Daemon(String),
+}
+
+impl Dump {
+    fn read(&self, full: bool) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        match self {
+            Dump::Focused => dump_screen(full),
+            Dump::Daemon(selector) => match daemon::capture(selector, full) {
+                Some(output) => Ok(output?),
+                None => Err("the daemon that refused to focus the pane is gone".into()),
+            },
+        }
+    }
+}
+
+/// Focus the selected pane for a screen dump. When a daemon refuses that (read-only, or a
+/// `read` token), the daemon reads the pane instead.
+fn capture_focus_selection(
+    plugin: Option<&str>,
+    selection: &Selection,
+) -> Result<Dump, Box<dyn std::error::Error>> {
+    if selection.focused {
+        return Ok(Dump::Focused);
+    }
+    match client::capture_focus(plugin, &selection.target_selector) {
+        Ok(()) => Ok(Dump::Focused),
+        Err(ClientError::RpcError(err)) if err.code == RpcErrorCode::PolicyDenied => {
+            Ok(Dump::Daemon(selection.target_selector.clone()))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Focus `selector` for a screen dump, or back afterwards, see [`client::capture_focus`]
+fn capture_focus(plugin: Option<&str>, selector: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if selector == "focused" {
+        return Ok(());
+    }
+    client::capture_focus(plugin, selector)?;
+    Ok(())
+}
+
+pub fn pane_id_to_selector(id: &str) -> Option<String> {
     let mut parts = id.split(':');
     let pane_type = parts.next()?;
     let numeric = parts.next()?;
@@ -738,54 +3472,329 @@ fn dump_path() -> PathBuf {
     std::env::temp_dir().join(filename)
 }
 
-fn hash_bytes(bytes: &[u8]) -> u64 {
-    let mut hasher = DefaultHasher::new();
-    bytes.hash(&mut hasher);
-    hasher.finish()
-}
-
 fn poll_interval(idle_time: f64) -> Duration {
     let mut interval = idle_time / 4.0;
     interval = interval.clamp(0.1, 1.0);
     Duration::from_secs_f64(interval)
 }
 
-fn check_close_allowed(
-    selection: &Selection,
-    force: bool,
-) -> Result<(), Box<dyn std::error::Error>> {
-    if !force {
-        let focused_selector = selection
-            .restore_selector
-            .as_deref()
-            .unwrap_or(&selection.target_selector);
-        if focused_selector == selection.target_selector {
-            return Err("refusing to close focused pane (use --force)".into());
-        }
-    }
-    Ok(())
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn raw_and_hex_payloads_decode_to_exact_bytes() {
+        assert_eq!(
+            decode_escapes(r"\e[A\x00ok\\\r").unwrap(),
+            b"\x1b[A\x00ok\\\r"
+        );
+        assert!(decode_escapes(r"\q").is_err());
+        assert!(decode_escapes(r"\x1").is_err());
+        assert!(decode_escapes("end\\").is_err());
+        assert_eq!(decode_hex("1b 5b41\n0d").unwrap(), [0x1b, 0x5b, 0x41, 0x0d]);
+        assert!(decode_hex("1b5").is_err());
+        assert!(decode_hex("zz").is_err());
+    }
+
+    #[test]
+    fn take_utf8_prefix_keeps_partial_characters() {
+        let mut pending = "aé".as_bytes().to_vec();
+        pending.truncate(2); // 'a' + first byte of 'é'
+        assert_eq!(take_utf8_prefix(&mut pending).as_deref(), Some("a"));
+        assert_eq!(pending, vec![0xc3]);
+        assert_eq!(take_utf8_prefix(&mut pending), None);
+        pending.push(0xa9);
+        assert_eq!(take_utf8_prefix(&mut pending).as_deref(), Some("é"));
+        assert!(pending.is_empty());
+
+        let mut invalid = vec![b'x', 0xff, b'y'];
+        assert_eq!(
+            take_utf8_prefix(&mut invalid).as_deref(),
+            Some("x\u{FFFD}y")
+        );
+    }
+
+    #[test]
+    fn history_entry_shows_millis_and_escapes_text() {
+        let entry = SentText {
+            time_ms: 1_709_210_096_250,
+            text: "make test\n".to_string(),
+        };
+        assert_eq!(
+            format_history_entry(&entry),
+            "2024-02-29T12:34:56.250Z  make test\\n"
+        );
+    }
+
     fn pane(id: &str) -> panes::PaneInfo {
         panes::PaneInfo {
             id: id.to_string(),
             pane_type: "terminal".to_string(),
             title: String::new(),
+            name: None,
+            terminal_title: None,
             command: None,
+            cwd: None,
+            token: None,
+            tags: Vec::new(),
             tab_index: 0,
             tab_name: "tab".to_string(),
             focused: false,
             floating: false,
             suppressed: false,
+            exited: false,
+            exit_status: None,
+            held: false,
+            x: 0,
+            y: 0,
             rows: 0,
             cols: 0,
         }
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn cwd_resolver_gets_the_quoted_query_and_must_print_a_directory() {
+        let dir = std::env::temp_dir();
+        let dir = dir.to_str().unwrap();
+        assert_eq!(resolve_cwd("echo", dir).unwrap(), dir);
+        assert_eq!(
+            resolve_cwd("test {} = \"it's\" && echo /", "it's").unwrap(),
+            "/"
+        );
+        assert!(resolve_cwd("echo", "/no/such/dir").is_err());
+        assert!(resolve_cwd("false", "api").is_err());
+    }
+
+    #[test]
+    fn exec_command_line_quotes_and_hides_markers() {
+        let line = exec_command_line("echo 'hi' && false", "abc");
+        assert_eq!(
+            line,
+            " printf '%s_%s\\n' __ZJCTL_BEGIN abc; eval 'echo '\\''hi'\\'' && false'; printf '\\n%s_%s_%d\\n' __ZJCTL_END abc \"$?\"\n"
+        );
+        assert!(!line.contains("__ZJCTL_BEGIN_abc"));
+        assert!(!line.contains("__ZJCTL_END_abc"));
+    }
+
+    #[test]
+    fn run_result_line_reports_status() {
+        let mut result = RunPaneResult {
+            pane: "id:terminal:3".to_string(),
+            ok: true,
+            exit_code: Some(0),
+            output: String::new(),
+            duration: 1.25,
+            error: None,
+        };
+        assert_eq!(run_result_line(&result), "id:terminal:3 ok 1.2s");
+        result.ok = false;
+        result.exit_code = Some(2);
+        assert_eq!(
+            run_result_line(&result),
+            "id:terminal:3 FAILED (exit 2) 1.2s"
+        );
+        result.exit_code = None;
+        result.error = Some("timed out after 5.0s".to_string());
+        assert_eq!(
+            run_result_line(&result),
+            "id:terminal:3 FAILED (timed out after 5.0s) 1.2s"
+        );
+    }
+
+    #[test]
+    fn token_command_wraps_command_or_shell_in_env() {
+        let command = vec!["htop".to_string(), "-d".to_string(), "5".to_string()];
+        assert_eq!(
+            token_command(&command, "abc", "/bin/zsh"),
+            vec!["env", "ZJCTL_PANE_TOKEN=abc", "htop", "-d", "5"]
+        );
+        assert_eq!(
+            token_command(&[], "abc", "/bin/zsh"),
+            vec!["env", "ZJCTL_PANE_TOKEN=abc", "/bin/zsh"]
+        );
+    }
+
+    #[test]
+    fn launch_uses_rpc_only_when_the_plugin_api_covers_the_options() {
+        let command = vec!["htop".to_string()];
+        let options = LaunchOptions {
+            direction: None,
+            floating: true,
+            name: Some("top"),
+            cwd: Some("/tmp"),
+            close_on_exit: false,
+            in_place: false,
+            start_suspended: false,
+            command: &command,
+        };
+        assert!(launches_over_rpc(&options));
+        assert!(!launches_over_rpc(&LaunchOptions {
+            direction: Some("right"),
+            ..options
+        }));
+        assert!(!launches_over_rpc(&LaunchOptions {
+            close_on_exit: true,
+            ..options
+        }));
+        assert!(!launches_over_rpc(&LaunchOptions {
+            command: &[],
+            ..options
+        }));
+    }
+
+    #[test]
+    fn changed_region_spans_first_to_last_difference() {
+        assert_eq!(changed_region("a\nb\nc\n", "a\nb  \nc"), None);
+        assert_eq!(
+            changed_region("a\nb\nc\nd\n", "a\nB\nc\nD\n").as_deref(),
+            Some("B\nc\nD\n")
+        );
+        assert_eq!(
+            changed_region("$ \n", "$ make\nok\n$ \n").as_deref(),
+            Some("$ make\nok\n")
+        );
+        assert_eq!(changed_region("a\nb\n", "a\n").as_deref(), Some(""));
+    }
+
+    #[test]
+    fn extract_exec_output_between_markers() {
+        let dump = "$  printf ... __ZJCTL_BEGIN abc; eval 'ls'\n__ZJCTL_BEGIN_abc\na.txt  \nb.txt\n\n__ZJCTL_END_abc_2\n$ \n";
+        assert_eq!(
+            extract_exec_output(dump, "abc"),
+            Some(("a.txt\nb.txt\n".to_string(), 2))
+        );
+        assert_eq!(extract_exec_output(dump, "other"), None);
+    }
+
+    #[test]
+    fn extract_exec_output_waits_for_end_marker() {
+        assert_eq!(
+            extract_exec_output("__ZJCTL_BEGIN_abc\nstill going\n", "abc"),
+            None
+        );
+        assert_eq!(
+            extract_exec_output("__ZJCTL_BEGIN_abc\n\n__ZJCTL_END_abc_0\n", "abc"),
+            Some((String::new(), 0))
+        );
+    }
+
+    #[test]
+    fn parse_answer_accepts_short_and_long_forms() {
+        assert_eq!(parse_answer("y\n"), Some(Answer::Yes));
+        assert_eq!(parse_answer("ALL"), Some(Answer::All));
+        assert_eq!(parse_answer(""), Some(Answer::No));
+        assert_eq!(parse_answer("q"), Some(Answer::Quit));
+        assert_eq!(parse_answer("maybe"), None);
+    }
+
+    #[test]
+    fn new_match_ignores_a_match_already_on_screen() {
+        let re = regex::Regex::new(r"Listening on :(\d+)").unwrap();
+        let mut tail = LineTail::default();
+        let stale = "Listening on :3000\n$ npm start\n";
+        assert_eq!(new_match(&mut tail, &re, stale), None);
+        assert_eq!(new_match(&mut tail, &re, stale), None);
+        assert_eq!(
+            new_match(
+                &mut tail,
+                &re,
+                "Listening on :3000\n$ npm start\nListening on :3001\n"
+            ),
+            Some((
+                "Listening on :3001".to_string(),
+                "Listening on :3001".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn last_match_prefers_most_recent_line() {
+        let re = regex::Regex::new(r"Listening on :(\d+)").unwrap();
+        let screen = "Listening on :3000\nrestarting\nListening on :3001   \n$ \n";
+        assert_eq!(
+            last_match(screen, &re),
+            Some((
+                "Listening on :3001".to_string(),
+                "Listening on :3001".to_string()
+            ))
+        );
+        assert_eq!(last_match("nothing here\n", &re), None);
+    }
+
+    #[test]
+    fn line_tail_forwards_only_new_complete_lines() {
+        let mut tail = LineTail::default();
+        assert!(tail.feed("old 1\nold 2\n$ \n\n").is_empty());
+        assert!(tail.feed("old 1\nold 2\n$ make\n").is_empty());
+        assert_eq!(
+            tail.feed("old 1\nold 2\n$ make\nerror: x  \nwarn\n$ \n"),
+            vec!["$ make", "error: x", "warn"]
+        );
+        assert!(tail
+            .feed("old 1\nold 2\n$ make\nerror: x\nwarn\n$ \n")
+            .is_empty());
+    }
+
+    #[test]
+    fn line_tail_tracks_held_back_line() {
+        let mut tail = LineTail::default();
+        tail.feed("$ make\n");
+        assert_eq!(tail.partial.as_deref(), Some("$ make"));
+        tail.feed("$ make\nbuilding...  \n");
+        assert_eq!(tail.partial.as_deref(), Some("building..."));
+    }
+
+    #[test]
+    fn line_tail_follows_scrollback_at_its_cap() {
+        let mut tail = LineTail::default();
+        assert!(tail.feed("a\nb\nc\n").is_empty());
+        assert_eq!(tail.feed("b\nc\nd\n"), vec!["c"]);
+        assert_eq!(tail.feed("d\ne\nf\n"), vec!["d", "e"]);
+    }
+
+    #[test]
+    fn watch_feed_follows_capped_scrollback_to_until() {
+        let until = regex::Regex::new("Listening").unwrap();
+        let mut tail = LineTail::default();
+        let (lines, matched) = watch_feed(&mut tail, Some(&until), "a\nb\nc\n");
+        assert!(lines.is_empty() && !matched);
+        // Same line count from here on: the scrollback is full and slides.
+        let (lines, matched) = watch_feed(&mut tail, Some(&until), "b\nc\nd\n");
+        assert_eq!((lines, matched), (vec!["c".to_string()], false));
+        let (lines, matched) = watch_feed(&mut tail, Some(&until), "d\nListening\n$ \n");
+        assert_eq!(
+            (lines, matched),
+            (vec!["d".to_string(), "Listening".to_string()], true)
+        );
+
+        let mut tail = LineTail::default();
+        watch_feed(&mut tail, Some(&until), "x\ny\nz\n");
+        let (lines, matched) = watch_feed(&mut tail, Some(&until), "y\nz\nListening\n");
+        assert_eq!(
+            (lines, matched),
+            (vec!["z".to_string(), "Listening".to_string()], true)
+        );
+    }
+
+    #[test]
+    fn line_tail_keeps_repeated_output_and_redraws() {
+        let mut tail = LineTail::default();
+        tail.feed("ok\n$ \n");
+        assert_eq!(tail.feed("ok\n$ \nok\n$ \n"), vec!["$", "ok"]);
+        let mut tail = LineTail::default();
+        tail.feed("title\n50%\nstatus\n");
+        assert_eq!(tail.feed("title\n90%\nstatus\n"), vec!["90%"]);
+    }
+
+    #[test]
+    fn line_tail_resyncs_after_clear() {
+        let mut tail = LineTail::default();
+        tail.feed("a\nb\nc\nd\n");
+        assert!(tail.feed("$ \n").is_empty());
+        assert_eq!(tail.feed("$ \nnew\n$ \n"), vec!["$", "new"]);
+    }
+
     #[test]
     fn pane_id_to_selector_parses_terminal() {
         assert_eq!(
@@ -816,42 +3825,71 @@ mod tests {
     }
 
     #[test]
-    fn close_refuses_focused_without_force() {
-        let selection = Selection {
-            target_selector: "focused".to_string(),
-            restore_selector: None,
+    fn close_outcome_reports_plugin_refusal() {
+        let refused = CloseResult {
+            id: "terminal:1".to_string(),
+            closed: false,
+            error: Some("refusing to close focused pane (use --force)".to_string()),
         };
-
-        let err = check_close_allowed(&selection, false).expect_err("expected error");
         assert_eq!(
-            err.to_string(),
+            close_outcome(std::slice::from_ref(&refused)).unwrap_err(),
             "refusing to close focused pane (use --force)"
         );
-    }
 
-    #[test]
-    fn close_allows_non_focused() {
-        let selection = Selection {
-            target_selector: "id:terminal:2".to_string(),
-            restore_selector: Some("id:terminal:1".to_string()),
+        let closed = CloseResult {
+            id: "terminal:2".to_string(),
+            closed: true,
+            error: None,
         };
-
-        check_close_allowed(&selection, false).expect("allowed");
+        close_outcome(std::slice::from_ref(&closed)).expect("closed");
+        assert!(close_outcome(&[]).is_err());
     }
 
     #[test]
-    fn close_allows_force() {
-        let selection = Selection {
-            target_selector: "focused".to_string(),
-            restore_selector: None,
-        };
+    fn close_summary_counts_skipped_panes() {
+        let results = vec![
+            CloseResult {
+                id: "terminal:1".to_string(),
+                closed: false,
+                error: Some("refusing to close focused pane (use --force)".to_string()),
+            },
+            CloseResult {
+                id: "terminal:2".to_string(),
+                closed: true,
+                error: None,
+            },
+        ];
+
+        let err = close_summary(&results, FailurePolicy::Complete).expect_err("expected error");
+        assert_eq!(
+            err.to_string(),
+            "1 of 2 panes failed; first failure: id:terminal:1: \
+             refusing to close focused pane (use --force)"
+        );
+        close_summary(&results[1..], FailurePolicy::Complete).expect("all closed");
+        close_summary(&results, FailurePolicy::AllowPartial).expect("one closed");
+    }
 
-        check_close_allowed(&selection, true).expect("allowed");
+    #[test]
+    fn other_panes_in_tab_keeps_target_plugins_and_other_tabs() {
+        let target = pane("terminal:1");
+        let sibling = pane("terminal:2");
+        let mut other_tab = pane("terminal:3");
+        other_tab.tab_index = 1;
+        let mut plugin = pane("plugin:4");
+        plugin.pane_type = "plugin".to_string();
+        let mut suppressed = pane("terminal:5");
+        suppressed.suppressed = true;
+
+        let panes = vec![target.clone(), sibling, other_tab, plugin, suppressed];
+        let others = other_panes_in_tab(&panes, &target);
+        let ids: Vec<&str> = others.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["terminal:2"]);
     }
 
     #[test]
     fn build_send_steps_with_enter_and_delay() {
-        let steps = build_send_steps("echo hi", true, 0.5).expect("steps");
+        let steps = build_send_steps("echo hi", Some(EnterKey::Lf), 0.5).expect("steps");
         assert_eq!(steps.len(), 3);
         matches!(steps[0], SendStep::Text(_));
         matches!(steps[1], SendStep::Delay(_));
@@ -860,22 +3898,104 @@ mod tests {
 
     #[test]
     fn build_send_steps_without_enter() {
-        let steps = build_send_steps("echo hi", false, 1.0).expect("steps");
+        let steps = build_send_steps("echo hi", None, 1.0).expect("steps");
         assert_eq!(steps.len(), 1);
         matches!(steps[0], SendStep::Text(_));
     }
 
     #[test]
     fn build_send_steps_without_delay() {
-        let steps = build_send_steps("echo hi", true, 0.0).expect("steps");
+        let steps = build_send_steps("echo hi", Some(EnterKey::Lf), 0.0).expect("steps");
         assert_eq!(steps.len(), 2);
         matches!(steps[0], SendStep::Text(_));
         matches!(steps[1], SendStep::Text(_));
     }
 
+    #[test]
+    fn build_send_steps_uses_enter_key_sequence() {
+        let steps = build_send_steps("", Some(EnterKey::Kitty), 0.0).expect("steps");
+        match steps.as_slice() {
+            [SendStep::Text(text)] => assert_eq!(text, "\u{1b}[13u"),
+            other => panic!("unexpected steps: {other:?}"),
+        }
+        assert_eq!(EnterKey::Crlf.sequence(), "\r\n");
+        assert_eq!(EnterKey::default().sequence(), "\n");
+    }
+
+    #[test]
+    fn clear_steps_precede_text() {
+        assert!(clear_steps(None).is_empty());
+        match clear_steps(Some(ClearLine::CtrlU)).as_slice() {
+            [SendStep::Text(text)] => assert_eq!(text, "\u{5}\u{15}"),
+            other => panic!("unexpected steps: {other:?}"),
+        }
+        match clear_steps(Some(ClearLine::CtrlC)).as_slice() {
+            [SendStep::Text(text), SendStep::Delay(delay)] => {
+                assert_eq!(text, "\u{3}");
+                assert_eq!(*delay, Duration::from_millis(100));
+            }
+            other => panic!("unexpected steps: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn split_chunks_respects_char_boundaries() {
+        assert_eq!(split_chunks("abcdefg", 3), vec!["abc", "def", "g"]);
+        assert_eq!(split_chunks("aé b", 2), vec!["a", "é", " b"]);
+        assert_eq!(split_chunks("€", 1), vec!["€"]);
+    }
+
+    #[test]
+    fn chunk_steps_off_sends_large_text_in_one_write() {
+        let text = "x".repeat(64 * 1024);
+        let steps = chunk_steps(
+            vec![SendStep::Text(text.clone())],
+            0,
+            Duration::from_millis(50),
+            true,
+        );
+        assert!(matches!(steps.as_slice(), [SendStep::Text(sent)] if *sent == text));
+    }
+
+    #[test]
+    fn chunk_steps_splits_only_large_text() {
+        let steps = vec![
+            SendStep::Text("x".repeat(10)),
+            SendStep::Delay(Duration::from_secs(1)),
+            SendStep::Text("\n".to_string()),
+        ];
+        let chunked = chunk_steps(steps, 4, Duration::from_millis(20), true);
+        let kinds: Vec<String> = chunked
+            .iter()
+            .map(|step| match step {
+                SendStep::Text(text) => text.clone(),
+                SendStep::Delay(d) => format!("delay {}ms", d.as_millis()),
+                SendStep::Settle => "settle".to_string(),
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                "xxxx",
+                "settle",
+                "delay 20ms",
+                "xxxx",
+                "settle",
+                "delay 20ms",
+                "xx",
+                "delay 1000ms",
+                "\n"
+            ]
+        );
+        assert_eq!(
+            chunk_steps(vec![SendStep::Text("abc".into())], 0, Duration::ZERO, false).len(),
+            1
+        );
+    }
+
     #[test]
     fn build_send_steps_rejects_negative_delay() {
-        let err = build_send_steps("echo hi", true, -1.0).expect_err("error");
+        let err = build_send_steps("echo hi", Some(EnterKey::Lf), -1.0).expect_err("error");
         assert_eq!(err.to_string(), "delay_enter must be >= 0");
     }
 
@@ -888,6 +4008,13 @@ mod tests {
         assert_eq!(found.id, "terminal:2");
     }
 
+    #[test]
+    fn capture_output_replaces_invalid_utf8_unless_base64() {
+        let raw = vec![b'h', b'i', 0xff, b'\n'];
+        assert_eq!(capture_output(raw.clone(), false), "hi\u{fffd}\n");
+        assert_eq!(capture_output(raw, true), "aGn/Cg==\n");
+    }
+
     #[test]
     fn find_new_pane_errors_when_none_added() {
         let before = vec![pane("terminal:1")];
@@ -1,29 +1,120 @@
 //! Panes listing commands
 
 use crate::client;
+use crate::commands::pane;
+use crate::formats::{self, OutputFormat};
+use crate::output::{self, Style};
+use crate::template::Template;
+use crate::zellij;
 use serde::{Deserialize, Serialize};
 use std::time::{Duration, Instant};
-use zjctl_proto::methods;
+use zjctl_proto::{methods, PaneFilter, PaneType};
 
 /// Pane info returned from list
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct PaneInfo {
     pub id: String,
     pub pane_type: String,
     pub title: String,
+    /// User-set pane name (if set through zjctl)
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Dynamic terminal title (absent while Zellij displays the user-set name)
+    #[serde(default)]
+    pub terminal_title: Option<String>,
     pub command: Option<String>,
+    /// Working directory, if reported through zjctl (launch `--cwd`, `pane set-cwd`)
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// Identity token exported as `ZJCTL_PANE_TOKEN` (`pane launch --token`)
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Tags added with `zjctl tag add`
+    #[serde(default)]
+    pub tags: Vec<String>,
     pub tab_index: usize,
     pub tab_name: String,
     pub focused: bool,
     pub floating: bool,
     pub suppressed: bool,
+    /// Whether the pane's command exited
+    #[serde(default)]
+    pub exited: bool,
+    /// Exit status of the pane's command, if known
+    #[serde(default)]
+    pub exit_status: Option<i32>,
+    /// Whether the pane is held (exited and waiting to be re-run or closed)
+    #[serde(default)]
+    pub held: bool,
+    /// Pane column offset on screen
+    #[serde(default)]
+    pub x: usize,
+    /// Pane row offset on screen
+    #[serde(default)]
+    pub y: usize,
     #[serde(default)]
     pub rows: usize,
     #[serde(default)]
     pub cols: usize,
 }
 
+/// panes.list result envelope
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PaneList {
+    /// Session the snapshot was taken from
+    #[serde(default)]
+    pub session: Option<String>,
+    /// Zellij version reported by the plugin
+    #[serde(default)]
+    pub zellij_version: Option<String>,
+    /// Version of the zrpc plugin that answered (absent from older plugins)
+    #[serde(default)]
+    pub plugin_version: Option<String>,
+    /// Plugin state generation (increases on every observed state change)
+    #[serde(default)]
+    pub generation: u64,
+    pub panes: Vec<PaneInfo>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PaneListResult {
+    Envelope(PaneList),
+    // Plugins predating the envelope return a bare array of panes.
+    Bare(Vec<PaneInfo>),
+}
+
+fn parse_list_result(value: serde_json::Value) -> Result<PaneList, serde_json::Error> {
+    Ok(match serde_json::from_value(value)? {
+        PaneListResult::Envelope(list) => list,
+        PaneListResult::Bare(panes) => PaneList {
+            panes,
+            ..PaneList::default()
+        },
+    })
+}
+
 pub fn list(plugin: Option<&str>) -> Result<Vec<PaneInfo>, Box<dyn std::error::Error>> {
+    Ok(snapshot(plugin)?.panes)
+}
+
+/// List the panes passing `filter`; the plugin does the filtering
+pub fn list_filtered(
+    plugin: Option<&str>,
+    filter: &PaneFilter,
+) -> Result<Vec<PaneInfo>, Box<dyn std::error::Error>> {
+    Ok(snapshot_filtered(plugin, filter)?.panes)
+}
+
+/// List panes along with session metadata
+pub fn snapshot(plugin: Option<&str>) -> Result<PaneList, Box<dyn std::error::Error>> {
+    snapshot_filtered(plugin, &PaneFilter::default())
+}
+
+fn snapshot_filtered(
+    plugin: Option<&str>,
+    filter: &PaneFilter,
+) -> Result<PaneList, Box<dyn std::error::Error>> {
     // When zrpc is auto-launched via `zellij pipe`, it can receive the pipe message before it has
     // processed its first PaneUpdate. In that window, panes.list can return an incomplete snapshot.
     // We poll briefly until the pane IDs stabilize.
@@ -31,32 +122,93 @@ pub fn list(plugin: Option<&str>) -> Result<Vec<PaneInfo>, Box<dyn std::error::E
     let timeout = Duration::from_millis(500);
     let interval = Duration::from_millis(50);
 
-    let mut panes = list_once(plugin)?;
-    let mut ids = pane_ids(&panes);
+    let mut list = list_once_filtered(plugin, filter)?;
+    let mut ids = pane_ids(&list.panes);
 
     loop {
         if start.elapsed() >= timeout {
-            return Ok(panes);
+            return Ok(list);
         }
 
         std::thread::sleep(interval);
 
-        let next = list_once(plugin)?;
-        let next_ids = pane_ids(&next);
+        let next = list_once_filtered(plugin, filter)?;
+        let next_ids = pane_ids(&next.panes);
 
         if next_ids == ids {
             return Ok(next);
         }
 
-        panes = next;
+        list = next;
         ids = next_ids;
     }
 }
 
-fn list_once(plugin: Option<&str>) -> Result<Vec<PaneInfo>, Box<dyn std::error::Error>> {
-    let result = client::rpc_call(plugin, methods::PANES_LIST, serde_json::json!({}))?;
-    let panes: Vec<PaneInfo> = serde_json::from_value(result)?;
-    Ok(panes)
+pub fn list_once(plugin: Option<&str>) -> Result<PaneList, Box<dyn std::error::Error>> {
+    list_once_filtered(plugin, &PaneFilter::default())
+}
+
+fn list_once_filtered(
+    plugin: Option<&str>,
+    filter: &PaneFilter,
+) -> Result<PaneList, Box<dyn std::error::Error>> {
+    // An empty filter is left out so the call stays identical to a plain listing (and can be
+    // answered from the daemon's snapshot).
+    let params = if filter.is_empty() {
+        serde_json::json!({})
+    } else {
+        serde_json::json!({ "filter": filter })
+    };
+    let result = client::rpc_call(plugin, methods::PANES_LIST, params)?;
+    Ok(parse_list_result(result)?)
+}
+
+/// `panes ls` filter flags as given on the command line
+#[derive(Debug, Default)]
+pub struct LsFilter<'a> {
+    /// `current` or a tab index
+    pub tab: Option<&'a str>,
+    pub pane_type: Option<PaneType>,
+    /// Substring or `/regex/`, matched against name and title
+    pub title: Option<&'a str>,
+    /// Substring or `/regex/`
+    pub command: Option<&'a str>,
+    pub floating: bool,
+    pub focused_only: bool,
+}
+
+impl LsFilter<'_> {
+    pub fn to_filter(&self) -> Result<PaneFilter, Box<dyn std::error::Error>> {
+        let (tab, current_tab) = match self.tab {
+            Some("current") => (None, true),
+            Some(tab) => (
+                Some(tab.parse().map_err(|_| {
+                    format!("invalid tab: {tab} (expected 'current' or a tab index)")
+                })?),
+                false,
+            ),
+            None => (None, false),
+        };
+        Ok(PaneFilter {
+            tab,
+            current_tab,
+            pane_type: self.pane_type,
+            title: self.title.map(str::parse).transpose()?,
+            command: self.command.map(str::parse).transpose()?,
+            floating: self.floating.then_some(true),
+            focused: self.focused_only,
+        })
+    }
+}
+
+/// List only the panes a selector resolves to (single snapshot, no stabilization)
+pub fn list_matching(
+    plugin: Option<&str>,
+    selector: &str,
+) -> Result<Vec<PaneInfo>, Box<dyn std::error::Error>> {
+    let params = serde_json::json!({ "selector": selector });
+    let result = client::rpc_call(plugin, methods::PANES_LIST, params)?;
+    Ok(parse_list_result(result)?.panes)
 }
 
 fn pane_ids(panes: &[PaneInfo]) -> Vec<String> {
@@ -65,53 +217,271 @@ fn pane_ids(panes: &[PaneInfo]) -> Vec<String> {
     ids
 }
 
-pub fn ls(plugin: Option<&str>, json: bool) -> Result<(), Box<dyn std::error::Error>> {
-    if json {
-        let panes = list(plugin)?;
-        println!("{}", serde_json::to_string_pretty(&panes)?);
+pub fn ls(
+    plugin: Option<&str>,
+    output: OutputFormat,
+    format: Option<&str>,
+    filter: &PaneFilter,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let panes = list_filtered(plugin, filter)?;
+    if let Some(format) = format {
+        let template = Template::parse(format)?;
+        for pane in &panes {
+            println!("{}", template.render(pane)?);
+        }
+    } else {
+        match output {
+            OutputFormat::Table => print_table(&panes),
+            OutputFormat::Tsv => {
+                for pane in &panes {
+                    println!("{}", formats::tsv_row(tsv_fields(pane)));
+                }
+            }
+            _ => {
+                formats::print_document(output, &panes)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// `panes ls --output tsv` columns: id, type, tab index, tab name, focused, floating, exited,
+/// exit status, command, title
+fn tsv_fields(pane: &PaneInfo) -> [String; 10] {
+    [
+        pane.id.clone(),
+        pane.pane_type.clone(),
+        pane.tab_index.to_string(),
+        pane.tab_name.clone(),
+        pane.focused.to_string(),
+        pane.floating.to_string(),
+        pane.exited.to_string(),
+        formats::tsv_opt(pane.exit_status),
+        pane.command.clone().unwrap_or_default(),
+        pane.title.clone(),
+    ]
+}
+
+/// A pane tagged with the session it was listed from
+#[derive(Debug, Serialize)]
+struct SessionPane<'a> {
+    session: &'a str,
+    #[serde(flatten)]
+    pane: &'a PaneInfo,
+}
+
+/// `panes ls --all-sessions`: query each running session's plugin in turn
+pub fn ls_all_sessions(
+    plugin: Option<&str>,
+    output: OutputFormat,
+    format: Option<&str>,
+    filter: &PaneFilter,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let template = format.map(Template::parse).transpose()?;
+    let mut listed = Vec::new();
+    for session in zellij::list_sessions()? {
+        zellij::set_session(Some(session.clone()));
+        let result = list_filtered(plugin, filter);
+        zellij::set_session(None);
+        match result {
+            Ok(panes) => listed.push((session, panes)),
+            // One unreachable session (plugin not loaded, permissions pending) shouldn't hide
+            // the rest.
+            Err(err) => output::warn(format_args!("session {session}: {err}")),
+        }
+    }
+
+    let tagged = || {
+        listed.iter().flat_map(|(session, panes)| {
+            panes.iter().map(move |pane| SessionPane { session, pane })
+        })
+    };
+    if let Some(template) = template {
+        for pane in tagged() {
+            println!("{}", template.render(&pane)?);
+        }
+    } else if output == OutputFormat::Tsv {
+        // The session comes first, followed by the `panes ls --output tsv` columns
+        for SessionPane { session, pane } in tagged() {
+            let fields = std::iter::once(session.to_string()).chain(tsv_fields(pane));
+            println!("{}", formats::tsv_row(fields));
+        }
+    } else if output != OutputFormat::Table {
+        formats::print_document(output, &tagged().collect::<Vec<_>>())?;
+    } else if listed.is_empty() {
+        println!("No sessions found");
     } else {
-        let panes = list(plugin)?;
-        print_table(&panes);
+        for (index, (session, panes)) in listed.iter().enumerate() {
+            if index > 0 {
+                println!();
+            }
+            println!("Session: {session}");
+            print_table(panes);
+        }
+    }
+    Ok(())
+}
+
+/// Close all exited/held panes, optionally limited to one tab
+pub fn prune(
+    plugin: Option<&str>,
+    tab: Option<&str>,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let panes = list(plugin)?;
+    let tab_index = tab.map(|tab| resolve_tab(tab, &panes)).transpose()?;
+
+    let dead: Vec<&PaneInfo> = panes
+        .iter()
+        .filter(|p| p.exited || p.held)
+        .filter(|p| tab_index.is_none_or(|tab| p.tab_index == tab))
+        .collect();
+
+    if dead.is_empty() {
+        println!("No exited panes");
+        return Ok(());
+    }
+
+    for pane in dead {
+        let status = match pane.exit_status {
+            Some(code) => format!("exit {code}"),
+            None => "held".to_string(),
+        };
+        if dry_run {
+            println!("would close {} ({status})", pane.id);
+            continue;
+        }
+        // Exited panes are dead weight, so closing them is allowed even when focused.
+        let params = serde_json::json!({
+            "selector": format!("id:{}", pane.id),
+            "force": true,
+        });
+        client::rpc_call(plugin, methods::PANE_CLOSE, params)?;
+        println!("closed {} ({status})", pane.id);
     }
 
     Ok(())
 }
 
+/// Resolve a `--tab` value (`current` or a tab index) against a pane listing
+pub fn resolve_tab(tab: &str, panes: &[PaneInfo]) -> Result<usize, String> {
+    if tab == "current" {
+        return panes
+            .iter()
+            .find(|p| p.focused)
+            .map(|p| p.tab_index)
+            .ok_or_else(|| "no focused pane; cannot determine current tab".to_string());
+    }
+    tab.parse()
+        .map_err(|_| format!("invalid tab: {tab} (expected 'current' or a tab index)"))
+}
+
 pub fn print_table(panes: &[PaneInfo]) {
     if panes.is_empty() {
         println!("No panes found");
         return;
     }
 
-    println!(
-        "{:<20} {:<10} {:<30} {:<15} {:<8}",
-        "ID", "TAB", "TITLE", "COMMAND", "FLAGS"
+    // The title gets whatever the terminal leaves after the other columns.
+    let title_width = output::flex_width(70, 12, 30);
+    let header = format!(
+        "{:<20} {:<10} {:<title_width$} {:<15} {:<9} {:<6} EXIT",
+        "ID", "TAB", "TITLE", "COMMAND", "SIZE", "FLAGS"
     );
-    println!("{}", "-".repeat(90));
+    println!("{}", output::header(&header));
+    println!("{}", output::rule(70 + title_width));
 
     for pane in panes {
-        let flags = format!(
-            "{}{}{}",
-            if pane.focused { "F" } else { "-" },
-            if pane.floating { "f" } else { "-" },
-            if pane.suppressed { "s" } else { "-" }
-        );
+        let exit = match pane.exit_status {
+            Some(0) => output::paint(Style::Green, "0"),
+            Some(code) => output::paint(Style::Red, &code.to_string()),
+            None => "-".to_string(),
+        };
         println!(
-            "{:<20} {:<10} {:<30} {:<15} {:<8}",
+            "{:<20} {:<10} {:<title_width$} {:<15} {:<9} {:<6} {exit}",
             pane.id,
             pane.tab_name,
-            truncate(&pane.title, 28),
-            truncate(&pane.command.clone().unwrap_or_default(), 13),
-            flags
+            output::truncate(&pane.title, title_width - 2),
+            output::truncate(&pane.command.clone().unwrap_or_default(), 13),
+            size(pane),
+            flags(pane),
+        );
+    }
+}
+
+/// A title or command a pane showed from `time_ms` on
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TitleChange {
+    /// Unix timestamp in milliseconds
+    pub time_ms: u64,
+    pub title: String,
+    pub command: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TitleHistory {
+    pane: String,
+    /// Newest command the pane ran, even if it has exited since
+    last_command: Option<String>,
+    entries: Vec<TitleChange>,
+}
+
+/// Titles and commands a pane has shown since the plugin loaded
+pub fn history(
+    plugin: Option<&str>,
+    selector: &str,
+    limit: Option<usize>,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut params = serde_json::json!({ "selector": selector });
+    if let Some(limit) = limit {
+        params["limit"] = serde_json::json!(limit);
+    }
+    let result: TitleHistory =
+        serde_json::from_value(client::rpc_call(plugin, methods::PANES_HISTORY, params)?)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(());
+    }
+    if let Some(command) = &result.last_command {
+        println!("Last command: {command}");
+    }
+    for entry in &result.entries {
+        let command = match &entry.command {
+            Some(command) => format!("  [{command}]"),
+            None => String::new(),
+        };
+        println!(
+            "{}  {}{command}",
+            pane::format_time_ms(entry.time_ms),
+            entry.title
         );
     }
+    Ok(())
+}
+
+/// `F`ocused, `f`loating, `s`uppressed, `h`eld, e`x`ited (`-` when unset)
+fn flags(pane: &PaneInfo) -> String {
+    [
+        (pane.focused, 'F'),
+        (pane.floating, 'f'),
+        (pane.suppressed, 's'),
+        (pane.held, 'h'),
+        (pane.exited, 'x'),
+    ]
+    .iter()
+    .map(|&(set, flag)| if set { flag } else { '-' })
+    .collect()
 }
 
-pub fn truncate(s: &str, max: usize) -> String {
-    if s.len() <= max {
-        s.to_string()
+/// `colsxrows`, or `-` when the plugin did not report a size
+fn size(pane: &PaneInfo) -> String {
+    if pane.cols == 0 || pane.rows == 0 {
+        "-".to_string()
     } else {
-        format!("{}...", &s[..max - 3])
+        format!("{}x{}", pane.cols, pane.rows)
     }
 }
 
@@ -119,17 +489,43 @@ pub fn truncate(s: &str, max: usize) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn table_flags_and_size() {
+        let pane = PaneInfo {
+            focused: true,
+            held: true,
+            exited: true,
+            cols: 80,
+            rows: 24,
+            ..PaneInfo::default()
+        };
+        assert_eq!(flags(&pane), "F--hx");
+        assert_eq!(size(&pane), "80x24");
+        assert_eq!(flags(&PaneInfo::default()), "-----");
+        assert_eq!(size(&PaneInfo::default()), "-");
+    }
+
     fn pane(id: &str) -> PaneInfo {
         PaneInfo {
             id: id.to_string(),
             pane_type: "terminal".to_string(),
             title: String::new(),
+            name: None,
+            terminal_title: None,
             command: None,
+            cwd: None,
+            token: None,
+            tags: Vec::new(),
             tab_index: 0,
             tab_name: "tab".to_string(),
             focused: false,
             floating: false,
             suppressed: false,
+            exited: false,
+            exit_status: None,
+            held: false,
+            x: 0,
+            y: 0,
             rows: 0,
             cols: 0,
         }
@@ -154,4 +550,73 @@ mod tests {
         let b = vec![pane("terminal:2"), pane("terminal:1")];
         assert_eq!(pane_ids(&a), pane_ids(&b));
     }
+
+    #[test]
+    fn ls_filter_parses_tab_and_patterns() {
+        let filter = LsFilter {
+            tab: Some("current"),
+            title: Some("/^log/"),
+            floating: true,
+            ..LsFilter::default()
+        }
+        .to_filter()
+        .unwrap();
+        assert!(filter.current_tab);
+        assert_eq!(filter.tab, None);
+        assert_eq!(filter.title, Some("/^log/".parse().unwrap()));
+        assert_eq!(filter.floating, Some(true));
+
+        let filter = LsFilter {
+            tab: Some("2"),
+            ..LsFilter::default()
+        }
+        .to_filter()
+        .unwrap();
+        assert_eq!(filter.tab, Some(2));
+        assert!(!filter.current_tab);
+        assert!(LsFilter::default().to_filter().unwrap().is_empty());
+        assert!(LsFilter {
+            tab: Some("first"),
+            ..LsFilter::default()
+        }
+        .to_filter()
+        .is_err());
+    }
+
+    #[test]
+    fn resolve_tab_current_uses_focused_pane() {
+        let mut focused = pane("terminal:2");
+        focused.tab_index = 3;
+        focused.focused = true;
+        let panes = vec![pane("terminal:1"), focused];
+
+        assert_eq!(resolve_tab("current", &panes), Ok(3));
+        assert_eq!(resolve_tab("1", &panes), Ok(1));
+        assert!(resolve_tab("current", &panes[..1]).is_err());
+        assert!(resolve_tab("first", &panes).is_err());
+    }
+
+    #[test]
+    fn parse_list_result_reads_envelope() {
+        let value = serde_json::json!({
+            "session": "work",
+            "zellij_version": "0.43.1",
+            "generation": 7,
+            "panes": [serde_json::to_value(pane("terminal:1")).unwrap()],
+        });
+        let list = parse_list_result(value).expect("envelope");
+        assert_eq!(list.session.as_deref(), Some("work"));
+        assert_eq!(list.zellij_version.as_deref(), Some("0.43.1"));
+        assert_eq!(list.generation, 7);
+        assert_eq!(list.panes.len(), 1);
+    }
+
+    #[test]
+    fn parse_list_result_accepts_bare_array() {
+        let value = serde_json::json!([serde_json::to_value(pane("terminal:1")).unwrap()]);
+        let list = parse_list_result(value).expect("bare array");
+        assert!(list.session.is_none());
+        assert_eq!(list.generation, 0);
+        assert_eq!(list.panes[0].id, "terminal:1");
+    }
 }
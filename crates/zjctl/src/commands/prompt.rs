@@ -0,0 +1,293 @@
+//! Prompt-detection profiles for `pane wait-prompt`, `pane exec`, and `pane run`
+//!
+//! A profile pairs a regex for "the pane is waiting for input" (tested against the last
+//! non-blank screen line) with the dialect used to run a command and read back its output
+//! and exit status. Shell dialects wrap the command in begin/end markers; REPL dialects type
+//! the command as-is and take everything up to the next prompt as its output.
+//!
+//! Extra profiles, or different regexes for the built-in ones, can be listed one per line as
+//! `name = regex` in `~/.config/zjctl/prompts`. A new name runs commands the REPL way.
+
+use std::path::PathBuf;
+
+use regex::Regex;
+
+use crate::commands::pane::{exec_command_line, extract_exec_output};
+use crate::paths;
+
+/// Profile used when `--profile` is not given: any POSIX-ish shell prompt
+pub const DEFAULT_PROFILE: &str = "sh";
+
+/// How a command is typed into the pane and how its result is recognised
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    /// bash, zsh, dash...: `eval` between printf markers, status from `$?`
+    Posix,
+    /// fish: like POSIX, status from `$status`
+    Fish,
+    /// PowerShell: `Invoke-Expression` between markers, status from `$LASTEXITCODE`/`$?`
+    PowerShell,
+    /// Interactive interpreters: no exit status, output ends at the next prompt
+    Repl,
+}
+
+const BUILTIN: &[(&str, Dialect, &str)] = &[
+    ("sh", Dialect::Posix, r"[$#%>❯]\s*$"),
+    ("bash", Dialect::Posix, r"[$#]\s*$"),
+    ("zsh", Dialect::Posix, r"[%#$❯➜]\s*$"),
+    ("fish", Dialect::Fish, r"[>#❯]\s*$"),
+    ("powershell", Dialect::PowerShell, r"^PS .*>\s*$"),
+    ("python", Dialect::Repl, r"^>>>\s*$"),
+    ("node", Dialect::Repl, r"^>\s*$"),
+    ("psql", Dialect::Repl, r"^\S*=[#>]\s*$"),
+];
+
+/// Names accepted by `--profile` without a prompts file
+pub fn builtin_names() -> impl Iterator<Item = &'static str> {
+    BUILTIN.iter().map(|(name, _, _)| *name)
+}
+
+#[derive(Debug, Clone)]
+pub struct PromptProfile {
+    pub name: String,
+    pub dialect: Dialect,
+    prompt: Regex,
+}
+
+impl PromptProfile {
+    /// Whether the last non-blank line of `screen` is this profile's prompt
+    pub fn at_prompt(&self, screen: &str) -> Option<String> {
+        let line = screen
+            .lines()
+            .map(str::trim_end)
+            .rfind(|line| !line.is_empty())?;
+        self.prompt.is_match(line).then(|| line.to_string())
+    }
+
+    /// Text to type into the pane to run `command`
+    pub fn command_line(&self, command: &str, token: &str) -> String {
+        match self.dialect {
+            Dialect::Posix => exec_command_line(command, token),
+            Dialect::Fish => fish_command_line(command, token),
+            Dialect::PowerShell => powershell_command_line(command, token),
+            Dialect::Repl => format!("{command}\n"),
+        }
+    }
+
+    /// Whether [`extract`](Self::extract) needs to know how often the command was already on
+    /// screen before it was sent
+    pub fn needs_baseline(&self) -> bool {
+        self.dialect == Dialect::Repl
+    }
+
+    /// Lines of `dump` that echo `command` (the baseline for a REPL run)
+    pub fn echoes(&self, dump: &str, command: &str) -> usize {
+        let Some(first) = first_line(command) else {
+            return 0;
+        };
+        dump.lines()
+            .filter(|line| line.trim_end().ends_with(first))
+            .count()
+    }
+
+    /// Output and exit status once the command has finished. REPLs report status 0.
+    pub fn extract(
+        &self,
+        dump: &str,
+        token: &str,
+        command: &str,
+        baseline: usize,
+    ) -> Option<(String, i32)> {
+        match self.dialect {
+            Dialect::Repl => self
+                .extract_repl_output(dump, command, baseline)
+                .map(|output| (output, 0)),
+            _ => extract_exec_output(dump, token),
+        }
+    }
+
+    /// Lines between the `baseline + 1`th echo of the command and the prompt that follows
+    fn extract_repl_output(&self, dump: &str, command: &str, baseline: usize) -> Option<String> {
+        let first = first_line(command)?;
+        let lines: Vec<&str> = dump.lines().map(str::trim_end).collect();
+        let echo = lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line.ends_with(first))
+            .nth(baseline)?
+            .0;
+        let end = echo
+            + 1
+            + lines[echo + 1..]
+                .iter()
+                .position(|line| !line.is_empty() && self.prompt.is_match(line))?;
+        let body = &lines[echo + 1..end];
+        let mut output = body.join("\n");
+        if !body.is_empty() {
+            output.push('\n');
+        }
+        Some(output)
+    }
+}
+
+fn first_line(command: &str) -> Option<&str> {
+    command.lines().map(str::trim).find(|line| !line.is_empty())
+}
+
+/// fish has no `$?`; quotes escape with backslashes instead of `'\''`
+fn fish_command_line(command: &str, token: &str) -> String {
+    let quoted = format!("'{}'", command.replace('\\', r"\\").replace('\'', r"\'"));
+    format!(
+        " printf '%s_%s\\n' __ZJCTL_BEGIN {token}; eval {quoted}; printf '\\n%s_%s_%d\\n' __ZJCTL_END {token} $status\n"
+    )
+}
+
+/// `$LASTEXITCODE` for native programs, `$?` for cmdlets; markers are concatenated so the
+/// echoed input never contains them verbatim
+fn powershell_command_line(command: &str, token: &str) -> String {
+    let quoted = format!("'{}'", command.replace('\'', "''"));
+    format!(
+        " $global:LASTEXITCODE = 0; Write-Output ('__ZJCTL_BEGIN' + '_{token}'); Invoke-Expression {quoted}; $zjctlOk = $?; $zjctlStatus = if ($LASTEXITCODE) {{ $LASTEXITCODE }} elseif ($zjctlOk) {{ 0 }} else {{ 1 }}; Write-Output (\"`n__ZJCTL_END\" + \"_{token}_$zjctlStatus\")\n"
+    )
+}
+
+/// Look up `--profile` (built-in or from the prompts file); `--prompt` replaces its regex
+pub fn resolve(
+    profile: Option<&str>,
+    prompt: Option<&str>,
+) -> Result<PromptProfile, Box<dyn std::error::Error>> {
+    let name = profile.unwrap_or(DEFAULT_PROFILE);
+    let custom = match prompts_file() {
+        Some(path) if path.is_file() => {
+            let contents = std::fs::read_to_string(&path)
+                .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+            parse_prompts(&contents).map_err(|err| format!("{}: {err}", path.display()))?
+        }
+        _ => Vec::new(),
+    };
+    let (dialect, pattern) = lookup(name, &custom).ok_or_else(|| {
+        let mut known: Vec<&str> = builtin_names().collect();
+        for (custom, _) in &custom {
+            if !known.contains(&custom.as_str()) {
+                known.push(custom);
+            }
+        }
+        format!(
+            "unknown prompt profile: {name} (known: {})",
+            known.join(", ")
+        )
+    })?;
+    let pattern = prompt.unwrap_or(&pattern);
+    let prompt = Regex::new(pattern).map_err(|err| format!("invalid prompt regex: {err}"))?;
+    Ok(PromptProfile {
+        name: name.to_string(),
+        dialect,
+        prompt,
+    })
+}
+
+/// The prompts file wins over the built-in regex but keeps the built-in dialect
+fn lookup(name: &str, custom: &[(String, String)]) -> Option<(Dialect, String)> {
+    let builtin = BUILTIN.iter().find(|(builtin, _, _)| *builtin == name);
+    let custom = custom.iter().rev().find(|(custom, _)| custom == name);
+    match (builtin, custom) {
+        (Some((_, dialect, _)), Some((_, pattern))) => Some((*dialect, pattern.clone())),
+        (Some((_, dialect, pattern)), None) => Some((*dialect, pattern.to_string())),
+        (None, Some((_, pattern))) => Some((Dialect::Repl, pattern.clone())),
+        (None, None) => None,
+    }
+}
+
+fn prompts_file() -> Option<PathBuf> {
+    paths::config_dir().map(|dir| dir.join("zjctl").join("prompts"))
+}
+
+/// `name = regex` lines; blank lines and lines starting with `#` are skipped
+fn parse_prompts(contents: &str) -> Result<Vec<(String, String)>, String> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(i, line)| {
+            let (name, pattern) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `name = regex`", i + 1))?;
+            let (name, pattern) = (name.trim(), pattern.trim());
+            if name.is_empty() || pattern.is_empty() {
+                return Err(format!("line {}: expected `name = regex`", i + 1));
+            }
+            Ok((name.to_string(), pattern.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builtin(name: &str) -> PromptProfile {
+        let (dialect, pattern) = lookup(name, &[]).unwrap();
+        PromptProfile {
+            name: name.to_string(),
+            dialect,
+            prompt: Regex::new(&pattern).unwrap(),
+        }
+    }
+
+    #[test]
+    fn builtin_profiles_recognise_their_prompts() {
+        let cases = [
+            ("bash", "user@host:~/src$ "),
+            ("zsh", "~/src % "),
+            ("fish", "user@host ~/src> "),
+            ("powershell", "PS C:\\Users\\me> "),
+            ("python", ">>> "),
+            ("node", "> "),
+            ("psql", "app=# "),
+        ];
+        for (name, prompt) in cases {
+            let screen = format!("some output\n{prompt}\n\n");
+            assert!(builtin(name).at_prompt(&screen).is_some(), "{name}");
+        }
+        assert!(builtin("python").at_prompt("... ").is_none());
+        assert!(builtin("bash").at_prompt("Compiling foo\n").is_none());
+    }
+
+    #[test]
+    fn repl_output_is_read_up_to_the_next_prompt() {
+        let python = builtin("python");
+        let before = ">>> print(1)\n1\n>>> ";
+        let baseline = python.echoes(before, "print(1)");
+        let running = format!("{before}print(1)\n");
+        assert_eq!(python.extract(&running, "", "print(1)", baseline), None);
+        let done = format!("{before}print(1)\n1\n>>> ");
+        assert_eq!(
+            python.extract(&done, "", "print(1)", baseline),
+            Some(("1\n".to_string(), 0))
+        );
+    }
+
+    #[test]
+    fn shell_dialects_wrap_with_markers() {
+        let fish = fish_command_line("echo 'hi'", "abc");
+        assert!(fish.contains(r"eval 'echo \'hi\''"));
+        assert!(fish.contains("$status"));
+        let pwsh = powershell_command_line("echo 'hi'", "abc");
+        assert!(pwsh.contains("Invoke-Expression 'echo ''hi'''"));
+        assert!(!pwsh.contains("__ZJCTL_BEGIN_abc"));
+    }
+
+    #[test]
+    fn prompts_file_overrides_regex_and_adds_repls() {
+        let custom = parse_prompts("# mine\nzsh = λ\\s*$\n\nirb = ^irb.*> ?$\n").unwrap();
+        assert_eq!(
+            lookup("zsh", &custom),
+            Some((Dialect::Posix, "λ\\s*$".to_string()))
+        );
+        assert_eq!(
+            lookup("irb", &custom),
+            Some((Dialect::Repl, "^irb.*> ?$".to_string()))
+        );
+        assert!(parse_prompts("no equals sign").is_err());
+    }
+}
@@ -0,0 +1,136 @@
+//! `zjctl run`: launch a throwaway pane, run one command in it, print its output, close it
+//!
+//! The command runs as the pane's own process (no shell prompt to drive), so completion and
+//! exit status come straight from the plugin's pane list. The output is the pane's scrollback,
+//! captured once the command has exited.
+
+use std::io::Write;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use zjctl_proto::{methods, CaptureWindow};
+
+use crate::client;
+use crate::commands::pane::{self, CommandExit, LaunchOptions};
+use crate::commands::panes;
+use crate::interrupt;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+pub struct RunOptions<'a> {
+    pub cwd: Option<&'a str>,
+    pub name: Option<&'a str>,
+    /// Open a tiled pane instead of a floating one
+    pub tiled: bool,
+    /// Seconds to wait for the command; the pane is left open on timeout
+    pub timeout: f64,
+    /// Leave the pane open after the command exits
+    pub keep: bool,
+    pub json: bool,
+}
+
+#[derive(Serialize)]
+struct RunResult<'a> {
+    pane: &'a str,
+    exit_code: i32,
+    output: String,
+    duration: f64,
+    closed: bool,
+}
+
+pub fn run(
+    plugin: Option<&str>,
+    command: &[String],
+    options: &RunOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if command.is_empty() {
+        return Err("no command given (usage: zjctl run -- <command>...)".into());
+    }
+    if options.timeout <= 0.0 {
+        return Err("timeout must be greater than 0".into());
+    }
+
+    interrupt::install();
+    let start = Instant::now();
+    let selector = pane::launch_pane(
+        plugin,
+        &LaunchOptions {
+            direction: None,
+            floating: !options.tiled,
+            name: options.name,
+            cwd: options.cwd,
+            close_on_exit: false,
+            in_place: false,
+            start_suspended: false,
+            command,
+        },
+    )?;
+
+    let timeout = Duration::from_secs_f64(options.timeout);
+    let exit_status = loop {
+        if interrupt::interrupted() {
+            // Ctrl-C: don't leave the half-run command behind in a pane nobody asked for.
+            if !options.keep {
+                let _ = client::rpc_call(
+                    plugin,
+                    methods::PANE_CLOSE,
+                    serde_json::json!({ "selector": selector, "force": true }),
+                );
+            }
+            return Err(Box::new(interrupt::Interrupted));
+        }
+        let Some(info) = panes::list_matching(plugin, &selector)?.into_iter().next() else {
+            return Err(format!("{selector} closed before its command finished").into());
+        };
+        if info.exited || info.held {
+            break info.exit_status;
+        }
+        if start.elapsed() >= timeout {
+            return Err(format!(
+                "timed out after {:.1}s; the command is still running in {selector}",
+                options.timeout
+            )
+            .into());
+        }
+        sleep(POLL_INTERVAL);
+    };
+    // Command panes report no status when the command was killed by a signal.
+    let exit_code = exit_status.unwrap_or(1);
+
+    let screen = pane::capture_bytes(plugin, &selector, true)?;
+    let output = CaptureWindow::default()
+        .apply(&String::from_utf8_lossy(&screen))
+        .text;
+
+    // The pane may still be focused (floating panes open focused), hence `force`.
+    let closed = !options.keep
+        && client::rpc_call(
+            plugin,
+            methods::PANE_CLOSE,
+            serde_json::json!({ "selector": selector, "force": true }),
+        )
+        .is_ok();
+
+    if options.json {
+        let result = RunResult {
+            pane: &selector,
+            exit_code,
+            output,
+            duration: start.elapsed().as_secs_f64(),
+            closed,
+        };
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    } else {
+        std::io::stdout().write_all(output.as_bytes())?;
+        if !options.keep && !closed {
+            eprintln!("warning: could not close {selector}");
+        }
+    }
+
+    if exit_code == 0 {
+        Ok(())
+    } else {
+        Err(Box::new(CommandExit(exit_code)))
+    }
+}
@@ -0,0 +1,328 @@
+//! Periodic sends into visible panes (`zjctl schedule`)
+//!
+//! Schedules are stored in `schedules.json` under the state directory; `schedule run` is the
+//! foreground runner that delivers them.
+
+use std::fs;
+use std::path::PathBuf;
+use std::thread::sleep;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::pane;
+use crate::daemon;
+use crate::output;
+use crate::paths;
+use crate::zellij;
+
+const TICK: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Schedule {
+    pub id: u32,
+    pub pane: String,
+    /// Interval in seconds
+    pub every: u64,
+    /// Text sent to the pane (escapes already expanded)
+    pub text: String,
+    /// Session the schedule was added from
+    #[serde(default)]
+    pub session: Option<String>,
+    /// Unix time the schedule was added
+    pub created: u64,
+    /// Unix time of the last delivery attempt
+    #[serde(default)]
+    pub last_run: Option<u64>,
+}
+
+impl Schedule {
+    fn next_run(&self) -> u64 {
+        self.last_run.unwrap_or(self.created) + self.every
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ScheduleFile {
+    #[serde(default)]
+    schedules: Vec<Schedule>,
+}
+
+/// One line of the runner log printed to stdout
+#[derive(Debug, Serialize)]
+struct RunRecord<'a> {
+    time: u64,
+    id: u32,
+    pane: &'a str,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+pub fn add(pane: &str, every: &str, text: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let every = parse_interval(every)?;
+    let text = unescape(&text.join(" "));
+    if text.is_empty() {
+        return Err("nothing to send (pass the text after --)".into());
+    }
+
+    let mut file = load()?;
+    let id = file.schedules.iter().map(|s| s.id).max().unwrap_or(0) + 1;
+    file.schedules.push(Schedule {
+        id,
+        pane: pane.to_string(),
+        every: every.as_secs(),
+        text,
+        session: zellij::session_name(),
+        created: unix_time(),
+        last_run: None,
+    });
+    save(&file)?;
+    println!("{id}");
+    Ok(())
+}
+
+pub fn ls(json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let file = load()?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&file.schedules)?);
+        return Ok(());
+    }
+    if file.schedules.is_empty() {
+        println!("No schedules");
+        return Ok(());
+    }
+
+    let header = format!(
+        "{:<4} {:<8} {:<24} {:<16} TEXT",
+        "ID", "EVERY", "PANE", "SESSION"
+    );
+    println!("{}", output::header(&header));
+    let text_width = output::flex_width(56, 16, 32);
+    println!("{}", output::rule(56 + text_width));
+    for schedule in &file.schedules {
+        println!(
+            "{:<4} {:<8} {:<24} {:<16} {}",
+            schedule.id,
+            format_interval(schedule.every),
+            output::truncate(&schedule.pane, 22),
+            output::truncate(schedule.session.as_deref().unwrap_or("-"), 14),
+            output::truncate(&schedule.text.escape_default().to_string(), text_width),
+        );
+    }
+    Ok(())
+}
+
+pub fn rm(id: u32) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = load()?;
+    let before = file.schedules.len();
+    file.schedules.retain(|s| s.id != id);
+    if file.schedules.len() == before {
+        return Err(format!("no schedule with id {id}").into());
+    }
+    save(&file)?;
+    Ok(())
+}
+
+/// Deliver due schedules until interrupted; the file is re-read every tick so `add`/`rm`
+/// take effect without restarting.
+pub fn run(plugin: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    eprintln!("schedule: running (Ctrl+C to stop)");
+    let _registration = daemon::register("schedule", &schedule_path()?.display().to_string());
+    let default_session = zellij::session_name();
+    loop {
+        let now = unix_time();
+        let file = load()?;
+        let due: Vec<Schedule> = file
+            .schedules
+            .into_iter()
+            .filter(|s| s.next_run() <= now)
+            .collect();
+
+        if !due.is_empty() {
+            for schedule in &due {
+                zellij::set_session(schedule.session.clone().or_else(|| default_session.clone()));
+                let result = pane::send_raw(plugin, &schedule.pane, false, &schedule.text);
+                let record = RunRecord {
+                    time: now,
+                    id: schedule.id,
+                    pane: &schedule.pane,
+                    ok: result.is_ok(),
+                    error: result.err().map(|err| err.to_string()),
+                };
+                println!("{}", serde_json::to_string(&record)?);
+            }
+
+            // Reload so schedules added or removed while sending are kept.
+            let mut file = load()?;
+            for schedule in &mut file.schedules {
+                if due.iter().any(|d| d.id == schedule.id) {
+                    schedule.last_run = Some(now);
+                }
+            }
+            save(&file)?;
+        }
+
+        sleep(TICK);
+    }
+}
+
+/// Parse `90`, `30s`, `15m`, `2h`, `1d`, or combinations like `1h30m`
+pub fn parse_interval(input: &str) -> Result<Duration, String> {
+    let input = input.trim();
+    if let Ok(secs) = input.parse::<u64>() {
+        return validate_interval(secs, input);
+    }
+
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            _ => return Err(format!("invalid interval `{input}` (unknown unit `{c}`)")),
+        };
+        let value: u64 = digits
+            .parse()
+            .map_err(|_| format!("invalid interval `{input}`"))?;
+        total += value * unit;
+        digits.clear();
+    }
+    if !digits.is_empty() {
+        return Err(format!(
+            "invalid interval `{input}` (missing unit after {digits})"
+        ));
+    }
+    validate_interval(total, input)
+}
+
+fn validate_interval(secs: u64, input: &str) -> Result<Duration, String> {
+    if secs == 0 {
+        return Err(format!("invalid interval `{input}` (must be at least 1s)"));
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+fn format_interval(secs: u64) -> String {
+    let mut out = String::new();
+    let mut rest = secs;
+    for (unit, size) in [("d", 86400), ("h", 3600), ("m", 60), ("s", 1)] {
+        if rest >= size {
+            out.push_str(&format!("{}{unit}", rest / size));
+            rest %= size;
+        }
+    }
+    out
+}
+
+/// Expand `\n`, `\r`, `\t`, and `\\` so Enter can be written inline (`"git fetch\n"`)
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some('\\') => out.push('\\'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+fn schedule_path() -> Result<PathBuf, String> {
+    paths::state_dir()
+        .map(|dir| dir.join("schedules.json"))
+        .ok_or_else(|| "unable to determine state directory (set HOME or XDG_STATE_HOME)".into())
+}
+
+fn load() -> Result<ScheduleFile, Box<dyn std::error::Error>> {
+    let path = schedule_path()?;
+    match fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|err| format!("invalid {}: {err}", path.display()).into()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(ScheduleFile::default()),
+        Err(err) => Err(format!("failed to read {}: {err}", path.display()).into()),
+    }
+}
+
+fn save(file: &ScheduleFile) -> Result<(), Box<dyn std::error::Error>> {
+    let path = schedule_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Write-then-rename so a concurrent `schedule run` never reads a partial file.
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_string_pretty(file)?)?;
+    fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_interval_units_and_combinations() {
+        assert_eq!(parse_interval("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_interval("15m"), Ok(Duration::from_secs(900)));
+        assert_eq!(parse_interval("1h30m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_interval("1d"), Ok(Duration::from_secs(86400)));
+        assert!(parse_interval("0s").is_err());
+        assert!(parse_interval("15").is_ok());
+        assert!(parse_interval("15x").is_err());
+        assert!(parse_interval("1h30").is_err());
+        assert!(parse_interval("m").is_err());
+    }
+
+    #[test]
+    fn format_interval_round_trips() {
+        assert_eq!(format_interval(900), "15m");
+        assert_eq!(format_interval(5400), "1h30m");
+        assert_eq!(format_interval(45), "45s");
+    }
+
+    #[test]
+    fn unescape_expands_common_escapes() {
+        assert_eq!(unescape(r"git fetch\n"), "git fetch\n");
+        assert_eq!(unescape(r"a\tb\\n\q"), "a\tb\\n\\q");
+    }
+
+    #[test]
+    fn next_run_counts_from_last_run() {
+        let mut schedule = Schedule {
+            id: 1,
+            pane: "title:git".to_string(),
+            every: 60,
+            text: "git fetch\n".to_string(),
+            session: None,
+            created: 1000,
+            last_run: None,
+        };
+        assert_eq!(schedule.next_run(), 1060);
+        schedule.last_run = Some(1200);
+        assert_eq!(schedule.next_run(), 1260);
+    }
+}
@@ -0,0 +1,365 @@
+//! `zjctl selftest`: drive a scratch pane through the whole stack and report each step
+//!
+//! Launches a floating shell pane, types a command that prints a marker, waits for the marker
+//! to show up, captures and checks the screen, resizes and renames the pane, and closes it.
+//! Each step is timed; after a failure the remaining steps are skipped, except that the pane
+//! is still closed. A failure here points at zjctl, the plugin, or Zellij rather than at the
+//! scripts built on them.
+
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use zjctl_proto::{methods, CaptureWindow};
+
+use crate::client;
+use crate::commands::pane::{self, LaunchOptions};
+use crate::commands::panes;
+use crate::formats::{self, OutputFormat};
+use crate::interrupt;
+use crate::outcome::Status;
+use crate::output::{self, Style};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+type StepFn = fn(&mut Scratch) -> StepResult;
+
+const STEPS: [(&str, StepFn); 8] = [
+    ("launch", Scratch::launch),
+    ("send", Scratch::send),
+    ("wait", Scratch::wait),
+    ("capture", Scratch::capture),
+    ("assert", Scratch::check),
+    ("resize", Scratch::resize),
+    ("rename", Scratch::rename),
+    ("close", Scratch::close),
+];
+
+pub struct SelftestOptions {
+    /// Seconds to wait for the pane to appear, print the marker, and close
+    pub timeout: f64,
+    /// Leave the pane open at the end (the close step is skipped)
+    pub keep: bool,
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Serialize)]
+struct StepReport {
+    step: &'static str,
+    status: Status,
+    /// Seconds
+    duration: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct SelftestReport {
+    ok: bool,
+    pane: Option<String>,
+    marker: String,
+    /// Seconds
+    duration: f64,
+    steps: Vec<StepReport>,
+}
+
+type StepResult = Result<Option<String>, Box<dyn std::error::Error>>;
+
+/// Scratch-pane state shared by the steps
+struct Scratch {
+    plugin: Option<String>,
+    timeout: Duration,
+    marker: String,
+    pane: Option<String>,
+    screen: String,
+}
+
+impl Scratch {
+    fn pane(&self) -> Result<&str, Box<dyn std::error::Error>> {
+        self.pane.as_deref().ok_or_else(|| "no scratch pane".into())
+    }
+
+    fn launch(&mut self) -> StepResult {
+        let command = ["sh".to_string()];
+        let selector = pane::launch_pane(
+            self.plugin.as_deref(),
+            &LaunchOptions {
+                direction: None,
+                floating: true,
+                name: Some("zjctl-selftest"),
+                cwd: None,
+                close_on_exit: true,
+                in_place: false,
+                start_suspended: false,
+                command: &command,
+            },
+        )?;
+        self.pane = Some(selector.clone());
+        Ok(Some(selector))
+    }
+
+    /// Typed as `printf '%s-%s\n' zjctl-selftest TOKEN` so the echoed input never contains
+    /// the marker itself, only the command's output does
+    fn send(&mut self) -> StepResult {
+        let token = self.marker.trim_start_matches("zjctl-selftest-");
+        let text = format!("printf '%s-%s\\n' zjctl-selftest {token}\n");
+        client::rpc_call(
+            self.plugin.as_deref(),
+            methods::PANE_SEND,
+            serde_json::json!({ "selector": self.pane()?, "text": text }),
+        )?;
+        Ok(None)
+    }
+
+    fn wait(&mut self) -> StepResult {
+        let start = Instant::now();
+        loop {
+            interrupt::check()?;
+            let screen = self.read_screen()?;
+            if screen.contains(&self.marker) {
+                return Ok(None);
+            }
+            if start.elapsed() >= self.timeout {
+                return Err(format!(
+                    "marker did not appear within {:.1}s",
+                    self.timeout.as_secs_f64()
+                )
+                .into());
+            }
+            sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn capture(&mut self) -> StepResult {
+        self.screen = self.read_screen()?;
+        Ok(Some(format!("{} lines", self.screen.lines().count())))
+    }
+
+    fn check(&mut self) -> StepResult {
+        if self
+            .screen
+            .lines()
+            .any(|line| line.trim_end() == self.marker)
+        {
+            Ok(None)
+        } else {
+            Err(format!("no line reading exactly `{}` in the capture", self.marker).into())
+        }
+    }
+
+    fn resize(&mut self) -> StepResult {
+        let pane = self.pane()?.to_string();
+        pane::resize(
+            self.plugin.as_deref(),
+            pane::ResizeOptions {
+                selector: &pane,
+                increase: true,
+                decrease: false,
+                cols: None,
+                rows: None,
+                direction: None,
+                step: 1,
+                max_steps: 1,
+            },
+        )?;
+        let info = self.info()?;
+        Ok(Some(format!("{}x{}", info.cols, info.rows)))
+    }
+
+    fn rename(&mut self) -> StepResult {
+        let pane = self.pane()?.to_string();
+        let name = format!("{}-renamed", self.marker);
+        pane::rename(self.plugin.as_deref(), &pane, &name)?;
+        let start = Instant::now();
+        loop {
+            interrupt::check()?;
+            let info = self.info()?;
+            if info.name.as_deref() == Some(name.as_str()) || info.title == name {
+                return Ok(None);
+            }
+            if start.elapsed() >= self.timeout {
+                return Err(format!("pane still shows `{}` after renaming", info.title).into());
+            }
+            sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn close(&mut self) -> StepResult {
+        let pane = self.pane()?.to_string();
+        // Floating panes open focused, hence `force`.
+        client::rpc_call(
+            self.plugin.as_deref(),
+            methods::PANE_CLOSE,
+            serde_json::json!({ "selector": pane, "force": true }),
+        )?;
+        let start = Instant::now();
+        while !panes::list_matching(self.plugin.as_deref(), &pane)?.is_empty() {
+            if start.elapsed() >= self.timeout {
+                return Err(format!("{pane} is still open").into());
+            }
+            sleep(POLL_INTERVAL);
+        }
+        Ok(None)
+    }
+
+    fn read_screen(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let screen = pane::capture_bytes(self.plugin.as_deref(), self.pane()?, false)?;
+        Ok(CaptureWindow::default()
+            .apply(&String::from_utf8_lossy(&screen))
+            .text)
+    }
+
+    fn info(&self) -> Result<panes::PaneInfo, Box<dyn std::error::Error>> {
+        let pane = self.pane()?;
+        panes::list_matching(self.plugin.as_deref(), pane)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("{pane} disappeared").into())
+    }
+}
+
+pub fn run(
+    plugin: Option<&str>,
+    options: &SelftestOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if options.timeout <= 0.0 {
+        return Err("timeout must be greater than 0".into());
+    }
+    interrupt::install();
+    let start = Instant::now();
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    let mut scratch = Scratch {
+        plugin: plugin.map(str::to_string),
+        timeout: Duration::from_secs_f64(options.timeout),
+        marker: format!("zjctl-selftest-{}", &token[..8]),
+        pane: None,
+        screen: String::new(),
+    };
+
+    let mut steps = Vec::new();
+    let mut failed = false;
+    for (name, step) in STEPS {
+        // Close whatever was launched even after a failure, unless asked to keep it.
+        let skip = if name == "close" {
+            options.keep || scratch.pane.is_none()
+        } else {
+            failed
+        };
+        if skip {
+            steps.push(StepReport {
+                step: name,
+                status: Status::Skipped,
+                duration: 0.0,
+                detail: None,
+                error: None,
+            });
+            continue;
+        }
+        let step_start = Instant::now();
+        let result = step(&mut scratch);
+        let duration = step_start.elapsed().as_secs_f64();
+        let (status, detail, error) = match result {
+            Ok(detail) => (Status::Ok, detail, None),
+            Err(err) => {
+                failed = true;
+                (Status::Failed, None, Some(err.to_string()))
+            }
+        };
+        steps.push(StepReport {
+            step: name,
+            status,
+            duration,
+            detail,
+            error,
+        });
+    }
+
+    let report = SelftestReport {
+        ok: !failed,
+        pane: scratch.pane,
+        marker: scratch.marker,
+        duration: start.elapsed().as_secs_f64(),
+        steps,
+    };
+    match options.output {
+        OutputFormat::Table => print_table(&report),
+        OutputFormat::Tsv => {
+            // step, status, duration, detail, error
+            for step in &report.steps {
+                println!(
+                    "{}",
+                    formats::tsv_row([
+                        step.step.to_string(),
+                        status_name(step.status).to_string(),
+                        format!("{:.3}", step.duration),
+                        step.detail.clone().unwrap_or_default(),
+                        step.error.clone().unwrap_or_default(),
+                    ])
+                );
+            }
+        }
+        format => {
+            formats::print_document(format, &report)?;
+        }
+    }
+
+    match report
+        .steps
+        .iter()
+        .find(|step| step.status == Status::Failed)
+    {
+        Some(step) => Err(format!(
+            "selftest failed at {}: {}",
+            step.step,
+            step.error.as_deref().unwrap_or("unknown error")
+        )
+        .into()),
+        None => Ok(()),
+    }
+}
+
+fn status_name(status: Status) -> &'static str {
+    match status {
+        Status::Ok => "ok",
+        Status::Failed => "failed",
+        Status::Skipped => "skipped",
+    }
+}
+
+fn print_table(report: &SelftestReport) {
+    println!(
+        "{}",
+        output::header(&format!(
+            "{:<9} {:<8} {:>8}  DETAIL",
+            "STEP", "STATUS", "TIME"
+        ))
+    );
+    println!("{}", output::rule(40));
+    for step in &report.steps {
+        let status = match step.status {
+            Status::Ok => output::paint(Style::Green, "ok"),
+            Status::Failed => output::paint(Style::Red, "failed"),
+            Status::Skipped => output::paint(Style::Dim, "skipped"),
+        };
+        let padding = " ".repeat(8usize.saturating_sub(status_name(step.status).len()));
+        let detail = step
+            .error
+            .as_deref()
+            .or(step.detail.as_deref())
+            .unwrap_or("");
+        println!(
+            "{:<9} {status}{padding} {:>7.0}ms  {detail}",
+            step.step,
+            step.duration * 1000.0
+        );
+    }
+    let verdict = if report.ok {
+        output::paint(Style::Green, "passed")
+    } else {
+        output::paint(Style::Red, "failed")
+    };
+    println!();
+    println!("selftest {verdict} in {:.2}s", report.duration);
+}
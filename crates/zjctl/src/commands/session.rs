@@ -0,0 +1,181 @@
+//! `zjctl session`: list, attach to, kill, and rename Zellij sessions
+//!
+//! Wraps `zellij list-sessions`, `kill-session`/`delete-session`, and
+//! `action rename-session`, whose plain-text output is meant for people, so scripts get
+//! structured output and exit codes instead.
+
+use std::process::Command;
+
+use serde::Serialize;
+
+use crate::client::{self, ClientError};
+use crate::formats::{self, OutputFormat};
+use crate::output::{self, Style};
+use crate::zellij::{self, SessionEntry};
+
+#[derive(Debug, Serialize)]
+struct SessionChange<'a> {
+    session: &'a str,
+    /// `killed`, `deleted` (an exited session), or `renamed`
+    action: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    renamed_to: Option<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+struct AttachHint<'a> {
+    session: &'a str,
+    command: String,
+    exited: bool,
+    /// zjctl itself runs inside a Zellij session, which has to be detached first
+    inside_zellij: bool,
+}
+
+pub fn ls(output: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
+    let sessions = zellij::list_session_entries()?;
+    if output == OutputFormat::Tsv {
+        // name, created, current, exited
+        for session in &sessions {
+            println!(
+                "{}",
+                formats::tsv_row([
+                    session.name.clone(),
+                    session.created.clone().unwrap_or_default(),
+                    session.current.to_string(),
+                    session.exited.to_string(),
+                ])
+            );
+        }
+        return Ok(());
+    }
+    if formats::print_document(output, &sessions)? {
+        return Ok(());
+    }
+    if sessions.is_empty() {
+        println!("No sessions found");
+        return Ok(());
+    }
+    println!(
+        "{}",
+        output::header(&format!("  {:<24} {:<9} CREATED", "NAME", "STATUS"))
+    );
+    println!("{}", output::rule(52));
+    for session in &sessions {
+        let marker = if session.current { "*" } else { " " };
+        let status = if session.exited {
+            output::paint(Style::Dim, "exited ")
+        } else {
+            output::paint(Style::Green, "running")
+        };
+        println!(
+            "{marker} {:<24} {status}   {}",
+            output::truncate(&session.name, 24),
+            session.created.as_deref().unwrap_or("")
+        );
+    }
+    Ok(())
+}
+
+/// Print the command that attaches to `name`; zjctl can't take over the terminal itself
+pub fn attach(name: &str, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let session = find(name)?;
+    let hint = AttachHint {
+        session: &session.name,
+        command: format!("zellij attach {}", session.name),
+        exited: session.exited,
+        inside_zellij: std::env::var_os("ZELLIJ").is_some(),
+    };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&hint)?);
+        return Ok(());
+    }
+    println!("{}", hint.command);
+    // On stderr, so `$(zjctl session attach NAME)` gets just the command.
+    if hint.inside_zellij {
+        eprintln!("Detach from this session first (Ctrl+o d), then run the command above.");
+    }
+    if hint.exited {
+        eprintln!("{name} has exited; attaching resurrects it.");
+    }
+    Ok(())
+}
+
+/// Kill a running session, or delete an exited one
+pub fn kill(name: &str, force: bool, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    client::ensure_writable("killing a session")?;
+    let session = find(name)?;
+    if !force && zellij::session_name().as_deref() == Some(name) {
+        return Err(format!(
+            "{name} is the session zjctl is targeting; pass --force to kill it anyway"
+        )
+        .into());
+    }
+    let (subcommand, action) = if session.exited {
+        ("delete-session", "deleted")
+    } else {
+        ("kill-session", "killed")
+    };
+    run_zellij(Command::new("zellij").args([subcommand, name]))?;
+    print_change(
+        &SessionChange {
+            session: name,
+            action,
+            renamed_to: None,
+        },
+        json,
+    )
+}
+
+/// Rename the session zjctl targets (the current one, or `--session`)
+pub fn rename(new_name: &str, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    client::ensure_writable("renaming a session")?;
+    let Some(current) = zellij::session_name() else {
+        return Err("not inside a Zellij session; pass --session NAME".into());
+    };
+    let sessions = zellij::list_session_entries()?;
+    if sessions.iter().any(|session| session.name == new_name) {
+        return Err(format!("a session named {new_name} already exists").into());
+    }
+    run_zellij(zellij::command().args(["action", "rename-session", new_name]))?;
+    print_change(
+        &SessionChange {
+            session: &current,
+            action: "renamed",
+            renamed_to: Some(new_name),
+        },
+        json,
+    )
+}
+
+fn find(name: &str) -> Result<SessionEntry, Box<dyn std::error::Error>> {
+    zellij::list_session_entries()?
+        .into_iter()
+        .find(|session| session.name == name)
+        .ok_or_else(|| {
+            Box::new(ClientError::no_match(format!(
+                "no session named {name} (see: zjctl session ls)"
+            ))) as Box<dyn std::error::Error>
+        })
+}
+
+fn run_zellij(command: &mut Command) -> Result<(), Box<dyn std::error::Error>> {
+    let output = command
+        .output()
+        .map_err(|err| format!("failed to run zellij: {err}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("zellij failed: {}", stderr.trim()).into());
+    }
+    Ok(())
+}
+
+fn print_change(change: &SessionChange, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(change)?);
+    } else if let Some(new_name) = change.renamed_to {
+        println!("{} -> {new_name}", change.session);
+    } else {
+        println!("{} {}", change.action, change.session);
+    }
+    Ok(())
+}
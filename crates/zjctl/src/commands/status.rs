@@ -2,24 +2,139 @@
 
 use serde::Serialize;
 
+use zjctl_proto::UpdateCheck;
+
+use crate::client;
+use crate::commands::doctor;
 use crate::commands::panes::{self, PaneInfo};
+use crate::commands::tab::{self, ClientInfo, TabInfo};
+use crate::formats::{self, OutputFormat};
+use crate::output;
 
 #[derive(Serialize)]
 struct StatusReport {
+    session: Option<String>,
+    zellij_version: Option<String>,
+    /// zrpc plugin version (absent from plugins that do not report it)
+    plugin_version: Option<String>,
+    zjctl_version: &'static str,
+    generation: u64,
+    active_tab: Option<usize>,
+    tabs: Vec<TabSummary>,
+    clients: Vec<ClientInfo>,
     focused: Option<PaneInfo>,
     panes: Vec<PaneInfo>,
+    /// Present when the update check is enabled (`ZJCTL_UPDATE_CHECK`) and succeeded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    update: Option<UpdateCheck>,
+}
+
+/// One tab with counts of its panes
+#[derive(Debug, Serialize, PartialEq)]
+struct TabSummary {
+    index: usize,
+    name: String,
+    active: bool,
+    /// Terminal panes (tiled and floating)
+    panes: usize,
+    floating_panes: usize,
+    /// Command panes whose command has exited
+    exited_panes: usize,
+    /// The tab's focused pane
+    focused_pane: Option<String>,
+}
+
+fn summarize(tabs: &[TabInfo], panes: &[PaneInfo]) -> Vec<TabSummary> {
+    tabs.iter()
+        .map(|tab| {
+            let in_tab = || panes.iter().filter(|pane| pane.tab_index == tab.index);
+            TabSummary {
+                index: tab.index,
+                name: tab.name.clone(),
+                active: tab.active,
+                panes: tab.panes,
+                floating_panes: tab.floating_panes,
+                exited_panes: in_tab().filter(|pane| pane.exited).count(),
+                focused_pane: in_tab()
+                    .find(|pane| pane.focused)
+                    .map(|pane| pane.id.clone()),
+            }
+        })
+        .collect()
 }
 
-pub fn run(plugin: Option<&str>, json: bool) -> Result<(), Box<dyn std::error::Error>> {
-    let panes = panes::list(plugin)?;
-    let focused = panes.iter().find(|pane| pane.focused).cloned();
+pub fn run(plugin: Option<&str>, format: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
+    let snapshot = panes::snapshot(plugin)?;
+    let tab_list = tab::snapshot(plugin)?;
+    let panes = snapshot.panes;
+    let tabs = summarize(&tab_list.tabs, &panes);
+    let active_tab = tabs.iter().find(|tab| tab.active).map(|tab| tab.index);
+    let focused = panes
+        .iter()
+        .find(|pane| pane.focused && Some(pane.tab_index) == active_tab)
+        .or_else(|| panes.iter().find(|pane| pane.focused))
+        .cloned();
+    let update = client::update_check_enabled()
+        .then(|| doctor::check_update(plugin).ok())
+        .flatten();
 
-    if json {
-        let report = StatusReport { focused, panes };
-        println!("{}", serde_json::to_string_pretty(&report)?);
+    if format == OutputFormat::Tsv {
+        let focused = focused.map(|pane| pane.id);
+        print_tsv(&[
+            ("session", snapshot.session),
+            ("zellij_version", snapshot.zellij_version),
+            ("plugin_version", snapshot.plugin_version),
+            ("zjctl_version", Some(env!("CARGO_PKG_VERSION").to_string())),
+            ("generation", Some(snapshot.generation.to_string())),
+            ("active_tab", active_tab.map(|index| index.to_string())),
+            ("tabs", Some(tabs.len().to_string())),
+            ("clients", Some(tab_list.clients.len().to_string())),
+            ("focused", focused),
+            ("panes", Some(panes.len().to_string())),
+            (
+                "update_available",
+                update.map(|update| update.update_available.to_string()),
+            ),
+        ]);
+        return Ok(());
+    }
+    if format != OutputFormat::Table {
+        let report = StatusReport {
+            session: snapshot.session,
+            zellij_version: snapshot.zellij_version,
+            plugin_version: snapshot.plugin_version,
+            zjctl_version: env!("CARGO_PKG_VERSION"),
+            generation: snapshot.generation,
+            active_tab,
+            tabs,
+            clients: tab_list.clients,
+            focused,
+            panes,
+            update,
+        };
+        formats::print_document(format, &report)?;
         return Ok(());
     }
 
+    if let Some(update) = update.filter(|update| update.update_available) {
+        println!(
+            "Plugin update available: {} -> {} (run `zjctl install --force`)",
+            update.current, update.latest
+        );
+    }
+
+    println!(
+        "Session: {} (zellij {}, zrpc {}, zjctl {})",
+        snapshot.session.as_deref().unwrap_or("-"),
+        snapshot.zellij_version.as_deref().unwrap_or("?"),
+        snapshot.plugin_version.as_deref().unwrap_or("?"),
+        env!("CARGO_PKG_VERSION")
+    );
+    println!("Clients: {}", format_clients(&tab_list.clients));
+    println!();
+    print_tabs(&tabs);
+    println!();
+
     if let Some(focused) = focused {
         let command = focused.command.clone().unwrap_or_default();
         println!(
@@ -43,3 +158,111 @@ pub fn run(plugin: Option<&str>, json: bool) -> Result<(), Box<dyn std::error::E
 
     Ok(())
 }
+
+/// `status --output tsv`: one `key<TAB>value` line per field, in a fixed order (an empty
+/// value when unknown)
+fn print_tsv(fields: &[(&str, Option<String>)]) {
+    for (key, value) in fields {
+        println!(
+            "{}",
+            formats::tsv_row([*key, value.as_deref().unwrap_or_default()])
+        );
+    }
+}
+
+/// `2 (1: terminal:3 vim*, 2: terminal:5)`; `*` marks the client zjctl acts for
+fn format_clients(clients: &[ClientInfo]) -> String {
+    if clients.is_empty() {
+        return "0".to_string();
+    }
+    let list: Vec<String> = clients
+        .iter()
+        .map(|client| {
+            let mut entry = format!("{}: {}", client.id, client.pane);
+            if let Some(command) = &client.command {
+                entry.push(' ');
+                entry.push_str(command);
+            }
+            if client.current {
+                entry.push('*');
+            }
+            entry
+        })
+        .collect();
+    format!("{} ({})", clients.len(), list.join(", "))
+}
+
+fn print_tabs(tabs: &[TabSummary]) {
+    let name_width = output::flex_width(50, 12, 24);
+    let header = format!(
+        "{:<6} {:<name_width$} {:<6} {:<9} {:<7} FOCUSED",
+        "TAB", "NAME", "PANES", "FLOATING", "EXITED"
+    );
+    println!("{}", output::header(&header));
+    println!("{}", output::rule(50 + name_width));
+    for tab in tabs {
+        let index = if tab.active {
+            format!("{}*", tab.index)
+        } else {
+            tab.index.to_string()
+        };
+        println!(
+            "{:<6} {:<name_width$} {:<6} {:<9} {:<7} {}",
+            index,
+            output::truncate(&tab.name, name_width - 2),
+            tab.panes,
+            tab.floating_panes,
+            tab.exited_panes,
+            tab.focused_pane.as_deref().unwrap_or("-")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summaries_count_exited_panes_and_find_each_tabs_focus() {
+        let tabs = vec![
+            TabInfo {
+                index: 0,
+                name: "editor".into(),
+                active: true,
+                panes: 2,
+                ..TabInfo::default()
+            },
+            TabInfo {
+                index: 1,
+                name: "build".into(),
+                panes: 1,
+                ..TabInfo::default()
+            },
+        ];
+        let pane = |id: &str, tab_index, focused, exited| PaneInfo {
+            id: id.into(),
+            tab_index,
+            focused,
+            exited,
+            ..PaneInfo::default()
+        };
+        let panes = vec![
+            pane("terminal:1", 0, false, false),
+            pane("terminal:2", 0, true, false),
+            pane("terminal:3", 1, true, true),
+        ];
+        let summary = summarize(&tabs, &panes);
+        assert_eq!(summary[0].focused_pane.as_deref(), Some("terminal:2"));
+        assert_eq!(summary[0].exited_panes, 0);
+        assert_eq!(summary[1].exited_panes, 1);
+        assert_eq!(
+            format_clients(&[ClientInfo {
+                id: 1,
+                pane: "terminal:2".into(),
+                command: Some("vim".into()),
+                current: true,
+            }]),
+            "1 (1: terminal:2 vim*)"
+        );
+    }
+}
@@ -0,0 +1,130 @@
+//! Compact one-line summary for status bars and shell prompts
+
+use serde::Serialize;
+
+use crate::commands::panes::{self, PaneList};
+use crate::template::Template;
+
+/// Fields available to `statusline --format`
+#[derive(Debug, Default, PartialEq, Serialize)]
+struct Summary {
+    session: Option<String>,
+    tab: Option<String>,
+    tab_index: Option<usize>,
+    /// Focused pane ID, e.g. `terminal:3`
+    focused: Option<String>,
+    title: Option<String>,
+    command: Option<String>,
+    /// Terminal panes across all tabs
+    panes: usize,
+    /// Command panes whose command is still running
+    busy: usize,
+    /// Panes whose command exited (including held panes)
+    exited: usize,
+}
+
+pub fn run(plugin: Option<&str>, format: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    // Prompts redraw constantly: one panes.list call, no snapshot stabilization.
+    let summary = summarize(&panes::list_once(plugin)?);
+    let line = match format {
+        Some(format) => Template::parse(format)?.render(&summary)?,
+        None => default_line(&summary),
+    };
+    println!("{line}");
+    Ok(())
+}
+
+fn summarize(list: &PaneList) -> Summary {
+    let terminals = || list.panes.iter().filter(|p| p.pane_type == "terminal");
+    let focused = list.panes.iter().find(|p| p.focused);
+    Summary {
+        session: list.session.clone(),
+        tab: focused.map(|p| p.tab_name.clone()),
+        tab_index: focused.map(|p| p.tab_index),
+        focused: focused.map(|p| p.id.clone()),
+        title: focused.map(|p| p.title.clone()),
+        command: focused.and_then(|p| p.command.clone()),
+        panes: terminals().count(),
+        busy: terminals()
+            .filter(|p| p.command.is_some() && !p.exited && !p.held)
+            .count(),
+        exited: terminals().filter(|p| p.exited || p.held).count(),
+    }
+}
+
+fn default_line(summary: &Summary) -> String {
+    let mut line = match (&summary.title, &summary.command) {
+        (Some(title), Some(command)) if title != command => format!("{title} ({command})"),
+        (Some(title), _) => title.clone(),
+        (None, _) => "-".to_string(),
+    };
+    line.push_str(&format!(" | {} panes", summary.panes));
+    if summary.busy > 0 {
+        line.push_str(&format!(", {} busy", summary.busy));
+    }
+    if summary.exited > 0 {
+        line.push_str(&format!(", {} exited", summary.exited));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::panes::PaneInfo;
+
+    fn pane(id: &str, command: Option<&str>) -> PaneInfo {
+        PaneInfo {
+            id: id.to_string(),
+            pane_type: "terminal".to_string(),
+            title: id.to_string(),
+            tab_name: "main".to_string(),
+            command: command.map(str::to_string),
+            ..PaneInfo::default()
+        }
+    }
+
+    #[test]
+    fn summarize_counts_busy_and_exited_terminals() {
+        let mut focused = pane("terminal:1", None);
+        focused.focused = true;
+        let mut done = pane("terminal:3", Some("make"));
+        done.exited = true;
+        let plugin = PaneInfo {
+            pane_type: "plugin".to_string(),
+            ..pane("plugin:1", None)
+        };
+        let list = PaneList {
+            panes: vec![
+                focused,
+                pane("terminal:2", Some("cargo watch")),
+                done,
+                plugin,
+            ],
+            ..PaneList::default()
+        };
+
+        let summary = summarize(&list);
+        assert_eq!(summary.focused.as_deref(), Some("terminal:1"));
+        assert_eq!(summary.tab.as_deref(), Some("main"));
+        assert_eq!(summary.panes, 3);
+        assert_eq!(summary.busy, 1);
+        assert_eq!(summary.exited, 1);
+    }
+
+    #[test]
+    fn default_line_omits_zero_counts() {
+        let summary = Summary {
+            title: Some("server".to_string()),
+            command: Some("npm run dev".to_string()),
+            panes: 4,
+            busy: 1,
+            ..Summary::default()
+        };
+        assert_eq!(
+            default_line(&summary),
+            "server (npm run dev) | 4 panes, 1 busy"
+        );
+        assert_eq!(default_line(&Summary::default()), "- | 0 panes");
+    }
+}
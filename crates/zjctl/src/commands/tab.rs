@@ -0,0 +1,1032 @@
+//! Tab-level commands
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::thread::sleep;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use zjctl_proto::methods;
+
+use crate::client;
+use crate::commands::pane::{self, pane_id_to_selector, LaunchOptions};
+use crate::commands::panes::{self, PaneInfo};
+use crate::formats::{self, OutputFormat};
+use crate::outcome::Outcomes;
+use crate::output;
+use crate::template::Template;
+use crate::zellij;
+
+/// Tab info returned from tabs.list
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TabInfo {
+    pub index: usize,
+    pub name: String,
+    pub active: bool,
+    /// Terminal panes in the tab (tiled and floating)
+    pub panes: usize,
+    pub floating_panes: usize,
+    pub fullscreen: bool,
+    pub sync_panes: bool,
+    pub floating_visible: bool,
+    pub swap_layout: Option<String>,
+}
+
+/// A client attached to the session, from tabs.list
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClientInfo {
+    pub id: u16,
+    /// Pane the client has focused, e.g. `terminal:3`
+    pub pane: String,
+    pub command: Option<String>,
+    /// The client the plugin acts for
+    pub current: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TabList {
+    pub tabs: Vec<TabInfo>,
+    /// Empty from plugins that predate client reporting
+    #[serde(default)]
+    pub clients: Vec<ClientInfo>,
+}
+
+pub fn list(plugin: Option<&str>) -> Result<Vec<TabInfo>, Box<dyn std::error::Error>> {
+    Ok(snapshot(plugin)?.tabs)
+}
+
+/// Tabs along with the attached clients
+pub fn snapshot(plugin: Option<&str>) -> Result<TabList, Box<dyn std::error::Error>> {
+    let result = client::rpc_call(plugin, methods::TABS_LIST, serde_json::json!({}))?;
+    Ok(serde_json::from_value(result)?)
+}
+
+pub fn ls(
+    plugin: Option<&str>,
+    output: OutputFormat,
+    format: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let tabs = list(plugin)?;
+    if let Some(format) = format {
+        let template = Template::parse(format)?;
+        for tab in &tabs {
+            println!("{}", template.render(tab)?);
+        }
+        return Ok(());
+    }
+    match output {
+        OutputFormat::Table => print_table(&tabs),
+        // index, name, active, panes, floating panes
+        OutputFormat::Tsv => {
+            for tab in &tabs {
+                println!(
+                    "{}",
+                    formats::tsv_row([
+                        tab.index.to_string(),
+                        tab.name.clone(),
+                        tab.active.to_string(),
+                        tab.panes.to_string(),
+                        tab.floating_panes.to_string(),
+                    ])
+                );
+            }
+        }
+        _ => {
+            formats::print_document(output, &tabs)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn new(
+    plugin: Option<&str>,
+    name: Option<&str>,
+    cwd: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let params = serde_json::json!({
+        "name": name,
+        "cwd": cwd,
+    });
+    client::rpc_call(plugin, methods::TAB_NEW, params)?;
+    Ok(())
+}
+
+pub fn rename(
+    plugin: Option<&str>,
+    tab: Option<&str>,
+    name: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let index = resolve(plugin, tab)?;
+    let params = serde_json::json!({
+        "tab": index,
+        "name": name,
+    });
+    client::rpc_call(plugin, methods::TAB_RENAME, params)?;
+    Ok(())
+}
+
+pub fn close(
+    plugin: Option<&str>,
+    tab: Option<&str>,
+    force: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let index = resolve(plugin, tab)?;
+    let params = serde_json::json!({
+        "tab": index,
+        "force": force,
+    });
+    client::rpc_call(plugin, methods::TAB_CLOSE, params)?;
+    Ok(())
+}
+
+pub fn focus(plugin: Option<&str>, tab: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let index = resolve(plugin, Some(tab))?;
+    let params = serde_json::json!({ "tab": index });
+    client::rpc_call(plugin, methods::TAB_FOCUS, params)?;
+    Ok(())
+}
+
+/// Move a tab left/right or to an absolute position.
+///
+/// The plugin API has no tab-move call, so this focuses the tab over RPC and then steps it with
+/// `zellij action move-tab`; the moved tab stays focused.
+pub fn move_tab(
+    plugin: Option<&str>,
+    tab: Option<&str>,
+    to: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let tabs = list(plugin)?;
+    let index = resolve_tab_spec(tab.unwrap_or("current"), &tabs)?;
+    let offset = move_offset(to, index, tabs.len())?;
+    if offset == 0 {
+        return Ok(());
+    }
+
+    client::rpc_call(
+        plugin,
+        methods::TAB_FOCUS,
+        serde_json::json!({ "tab": index }),
+    )?;
+    let direction = if offset < 0 { "left" } else { "right" };
+    for _ in 0..offset.unsigned_abs() {
+        let status = zellij::command()
+            .args(["action", "move-tab", direction])
+            .status()
+            .map_err(|err| format!("failed to run zellij: {err}"))?;
+        if !status.success() {
+            return Err(format!("zellij action move-tab failed: {status:?}").into());
+        }
+    }
+    Ok(())
+}
+
+/// Resolve an optional tab spec against the live tab list (default: current tab)
+pub(crate) fn resolve(
+    plugin: Option<&str>,
+    tab: Option<&str>,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let tabs = list(plugin)?;
+    Ok(resolve_tab_spec(tab.unwrap_or("current"), &tabs)?)
+}
+
+/// `current`, a 0-based tab index, or an exact tab name
+pub fn resolve_tab_spec(spec: &str, tabs: &[TabInfo]) -> Result<usize, String> {
+    if spec == "current" {
+        return tabs
+            .iter()
+            .find(|t| t.active)
+            .map(|t| t.index)
+            .ok_or_else(|| "no active tab".to_string());
+    }
+    if let Ok(index) = spec.parse::<usize>() {
+        return tabs
+            .iter()
+            .find(|t| t.index == index)
+            .map(|t| t.index)
+            .ok_or_else(|| format!("no tab {index} ({} tabs)", tabs.len()));
+    }
+    match tabs.iter().filter(|t| t.name == spec).collect::<Vec<_>>()[..] {
+        [tab] => Ok(tab.index),
+        [] => Err(format!(
+            "no tab named {spec:?} (tabs: {})",
+            tabs.iter()
+                .map(|t| format!("{}={:?}", t.index, t.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+        _ => Err(format!(
+            "several tabs are named {spec:?}; use the tab index"
+        )),
+    }
+}
+
+/// Signed number of single-step moves for `tab move`
+fn move_offset(to: &str, index: usize, count: usize) -> Result<i64, String> {
+    match to {
+        "left" => Ok(-1),
+        "right" => Ok(1),
+        position => {
+            let target: usize = position.parse().map_err(|_| {
+                format!("invalid position: {position} (expected left, right, or a tab index)")
+            })?;
+            if target >= count {
+                return Err(format!("position {target} out of range ({count} tabs)"));
+            }
+            Ok(target as i64 - index as i64)
+        }
+    }
+}
+
+fn print_table(tabs: &[TabInfo]) {
+    if tabs.is_empty() {
+        println!("No tabs found");
+        return;
+    }
+
+    let name_width = output::flex_width(40, 12, 24);
+    let header = format!(
+        "{:<6} {:<name_width$} {:<6} {:<16} {:<8}",
+        "INDEX", "NAME", "PANES", "LAYOUT", "FLAGS"
+    );
+    println!("{}", output::header(&header));
+    println!("{}", output::rule(40 + name_width));
+
+    for tab in tabs {
+        let flags = format!(
+            "{}{}{}{}",
+            if tab.active { "A" } else { "-" },
+            if tab.fullscreen { "Z" } else { "-" },
+            if tab.sync_panes { "S" } else { "-" },
+            if tab.floating_visible { "f" } else { "-" }
+        );
+        println!(
+            "{:<6} {:<name_width$} {:<6} {:<16} {:<8}",
+            tab.index,
+            output::truncate(&tab.name, name_width - 2),
+            tab.panes,
+            output::truncate(&tab.swap_layout.clone().unwrap_or_default(), 14),
+            flags
+        );
+    }
+}
+
+/// Version written to `tab export` files
+const EXPORT_VERSION: u32 = 1;
+
+/// One tab's panes, as written by `tab export` and read by `tab import`
+#[derive(Debug, Serialize, Deserialize)]
+struct TabExport {
+    version: u32,
+    name: String,
+    panes: Vec<ExportedPane>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ExportedPane {
+    /// User-set name, else the title (made unique within the tab)
+    name: String,
+    /// Program and arguments; empty for a shell pane
+    #[serde(default)]
+    command: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cwd: Option<String>,
+    #[serde(default)]
+    floating: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cols: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rows: Option<usize>,
+}
+
+/// Write one tab's terminal panes (names, commands, cwds, sizes) as JSON
+pub fn export(
+    plugin: Option<&str>,
+    tab: Option<&str>,
+    output: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let tabs = list(plugin)?;
+    let index = resolve_tab_spec(tab.unwrap_or("current"), &tabs)?;
+    let name = tabs
+        .iter()
+        .find(|t| t.index == index)
+        .map(|t| t.name.clone())
+        .unwrap_or_default();
+    let export = TabExport {
+        version: EXPORT_VERSION,
+        name,
+        panes: export_panes(&panes::list(plugin)?, index),
+    };
+
+    let json = serde_json::to_string_pretty(&export)?;
+    match output {
+        Some(path) if path != "-" => std::fs::write(path, format!("{json}\n"))
+            .map_err(|err| format!("failed to write {path}: {err}"))?,
+        _ => println!("{json}"),
+    }
+    Ok(())
+}
+
+/// Terminal panes of `tab_index`, tiled ones in screen order, then floating ones
+fn export_panes(panes: &[PaneInfo], tab_index: usize) -> Vec<ExportedPane> {
+    let mut in_tab: Vec<&PaneInfo> = panes
+        .iter()
+        .filter(|p| p.tab_index == tab_index && p.pane_type == "terminal" && !p.suppressed)
+        .collect();
+    in_tab.sort_by_key(|p| (p.floating, p.y, p.x));
+
+    let mut exported: Vec<ExportedPane> = Vec::new();
+    for pane in in_tab {
+        let base = pane.name.clone().unwrap_or_else(|| pane.title.clone());
+        let mut name = base.clone();
+        let mut suffix = 2;
+        while exported.iter().any(|other| other.name == name) {
+            name = format!("{base}-{suffix}");
+            suffix += 1;
+        }
+        exported.push(ExportedPane {
+            name,
+            command: pane
+                .command
+                .as_deref()
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+            cwd: pane.cwd.clone(),
+            floating: pane.floating,
+            cols: (pane.cols > 0).then_some(pane.cols),
+            rows: (pane.rows > 0).then_some(pane.rows),
+        });
+    }
+    exported
+}
+
+/// Recreate an exported tab: open a new tab, launch its panes, and restore tiled pane sizes
+pub fn import(
+    plugin: Option<&str>,
+    path: &str,
+    name: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|err| format!("failed to read {path}: {err}"))?;
+    let export: TabExport =
+        serde_json::from_str(&contents).map_err(|err| format!("invalid tab file {path}: {err}"))?;
+    if export.version > EXPORT_VERSION {
+        return Err(format!(
+            "{path} was written by a newer zjctl (version {})",
+            export.version
+        )
+        .into());
+    }
+    if export.panes.is_empty() {
+        return Err(format!("{path} has no panes").into());
+    }
+    let name = name.unwrap_or(&export.name);
+    if list(plugin)?.iter().any(|t| t.name == name) {
+        return Err(format!("a tab named {name:?} already exists (pass --name)").into());
+    }
+
+    new(plugin, Some(name), export.panes[0].cwd.as_deref())?;
+    // The new tab opens with a default shell pane, replaced once the exported panes are up.
+    let placeholder = wait_for_focused_pane_in(plugin, name)?;
+
+    let mut resizes = Vec::new();
+    for spec in &export.panes {
+        let selector = pane::launch_pane(
+            plugin,
+            &LaunchOptions {
+                direction: None,
+                floating: spec.floating,
+                name: Some(&spec.name),
+                cwd: spec.cwd.as_deref(),
+                close_on_exit: false,
+                in_place: false,
+                start_suspended: false,
+                command: &spec.command,
+            },
+        )?;
+        println!("created {:<24} {selector}", spec.name);
+        if !spec.floating {
+            resizes.push((selector, spec.cols, spec.rows));
+        }
+    }
+    if let Some(placeholder) = placeholder {
+        pane::close(plugin, &placeholder, false, true)?;
+    }
+
+    // Sizes are best effort: the new tab may be a different size than the exported one.
+    let panes = panes::list(plugin)?;
+    let steps = resizes
+        .into_iter()
+        .filter_map(|(selector, cols, rows)| {
+            let pane = panes
+                .iter()
+                .find(|p| pane_id_to_selector(&p.id).as_deref() == Some(selector.as_str()))?;
+            let cols = cols.filter(|cols| *cols != pane.cols);
+            let rows = rows.filter(|rows| *rows != pane.rows);
+            (cols.is_some() || rows.is_some()).then(|| BalanceStep {
+                id: pane.id.clone(),
+                cols,
+                rows,
+            })
+        })
+        .collect();
+    if let Err(err) = apply_steps(plugin, steps) {
+        output::warn(err);
+    }
+    Ok(())
+}
+
+/// `id:` selector of the focused pane once the plugin sees it in tab `name`
+fn wait_for_focused_pane_in(
+    plugin: Option<&str>,
+    name: &str,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    for _ in 0..40 {
+        let focused = panes::list(plugin)?
+            .into_iter()
+            .find(|p| p.focused && p.tab_name == name);
+        if let Some(pane) = focused {
+            return Ok(pane_id_to_selector(&pane.id));
+        }
+        sleep(Duration::from_millis(50));
+    }
+    Ok(None)
+}
+
+/// Maximum resize steps per pane while balancing
+const BALANCE_MAX_STEPS: u32 = 200;
+
+/// A single resize needed to balance a tab
+#[derive(Debug, PartialEq, Eq)]
+struct BalanceStep {
+    id: String,
+    cols: Option<usize>,
+    rows: Option<usize>,
+}
+
+pub fn balance(plugin: Option<&str>, tab: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let panes = panes::list(plugin)?;
+    let tab_index = panes::resolve_tab(tab.unwrap_or("current"), &panes)?;
+    let steps = balance_plan(&panes, tab_index);
+
+    if steps.is_empty() {
+        println!("Tab {tab_index} is already balanced");
+        return Ok(());
+    }
+    apply_steps(plugin, steps)
+}
+
+/// Preset pane arrangements for `tab layout`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutPreset {
+    /// Full-height pane on the left takes `main_size`% of the width
+    MainVertical,
+    /// Full-width pane on top takes `main_size`% of the height
+    MainHorizontal,
+    /// All panes side by side with equal widths
+    EvenHorizontal,
+    /// All panes stacked with equal heights
+    EvenVertical,
+    /// Rows and columns of panes with equal widths and heights
+    Grid,
+}
+
+impl std::fmt::Display for LayoutPreset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            LayoutPreset::MainVertical => "main-vertical",
+            LayoutPreset::MainHorizontal => "main-horizontal",
+            LayoutPreset::EvenHorizontal => "even-horizontal",
+            LayoutPreset::EvenVertical => "even-vertical",
+            LayoutPreset::Grid => "grid",
+        })
+    }
+}
+
+impl std::str::FromStr for LayoutPreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "main-vertical" => Ok(LayoutPreset::MainVertical),
+            "main-horizontal" => Ok(LayoutPreset::MainHorizontal),
+            "even-horizontal" => Ok(LayoutPreset::EvenHorizontal),
+            "even-vertical" => Ok(LayoutPreset::EvenVertical),
+            "grid" => Ok(LayoutPreset::Grid),
+            other => Err(format!(
+                "unknown layout preset: {other} (expected main-vertical, main-horizontal, even-horizontal, even-vertical, or grid)"
+            )),
+        }
+    }
+}
+
+pub fn layout(
+    plugin: Option<&str>,
+    preset: &str,
+    tab: Option<&str>,
+    main_size: u8,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let preset: LayoutPreset = preset.parse()?;
+    if !(10..=90).contains(&main_size) {
+        return Err("main_size must be between 10 and 90".into());
+    }
+
+    let panes = panes::list(plugin)?;
+    let tab_index = panes::resolve_tab(tab.unwrap_or("current"), &panes)?;
+    check_layout(&panes, tab_index, preset)
+        .map_err(|why| format!("tab {tab_index} cannot be arranged as {preset}: {why} (zjctl resizes panes but cannot re-split them)"))?;
+    let steps = layout_plan(&panes, tab_index, preset, main_size);
+
+    if steps.is_empty() {
+        println!("Tab {tab_index} already matches the layout");
+        return Ok(());
+    }
+    apply_steps(plugin, steps)
+}
+
+fn apply_steps(
+    plugin: Option<&str>,
+    steps: Vec<BalanceStep>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut outcomes = Outcomes::new();
+    for step in steps {
+        let selector = pane_id_to_selector(&step.id).unwrap_or_else(|| step.id.clone());
+        if !outcomes.keep_going() {
+            outcomes.skipped(selector);
+            continue;
+        }
+        match pane::resize_to(
+            plugin,
+            &selector,
+            step.cols,
+            step.rows,
+            None,
+            BALANCE_MAX_STEPS,
+        ) {
+            Ok(()) => {
+                match (step.cols, step.rows) {
+                    (Some(cols), _) => println!("resized {selector} to {cols} cols"),
+                    (_, Some(rows)) => println!("resized {selector} to {rows} rows"),
+                    _ => {}
+                }
+                outcomes.ok(selector);
+            }
+            Err(err) => {
+                // Neighbouring resizes can make a target unreachable; keep going with the rest.
+                output::warn(format_args!("could not resize {selector}: {err}"));
+                outcomes.failed(selector, err);
+            }
+        }
+    }
+    outcomes.finish("resizes")?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Dim {
+    Cols,
+    Rows,
+}
+
+/// Plan resizes that even out tiled panes sharing a row (widths) or a column (heights).
+///
+/// Panes are grouped by identical vertical extent (same `y` and `rows`) for width balancing and
+/// identical horizontal extent (same `x` and `cols`) for height balancing. The last pane in each
+/// group absorbs the rounding remainder, so it is never resized directly.
+fn balance_plan(panes: &[PaneInfo], tab_index: usize) -> Vec<BalanceStep> {
+    layout_plan(panes, tab_index, LayoutPreset::Grid, 50)
+}
+
+fn tiled_in(panes: &[PaneInfo], tab_index: usize) -> Vec<&PaneInfo> {
+    panes
+        .iter()
+        .filter(|p| p.tab_index == tab_index && p.pane_type == "terminal")
+        .filter(|p| !p.floating && !p.suppressed && p.rows > 0 && p.cols > 0)
+        .collect()
+}
+
+/// Whether the tab's split structure can take `preset` by resizing alone; the reason if not
+fn check_layout(panes: &[PaneInfo], tab_index: usize, preset: LayoutPreset) -> Result<(), String> {
+    let tiled = tiled_in(panes, tab_index);
+    if tiled.len() < 2 {
+        return Ok(());
+    }
+    let distinct = |key: fn(&PaneInfo) -> (usize, usize)| {
+        tiled.iter().map(|p| key(p)).collect::<BTreeSet<_>>().len()
+    };
+    let rows = distinct(|p| (p.y, p.rows));
+    let cols = distinct(|p| (p.x, p.cols));
+    let (left, top) = (extent(&tiled, Dim::Cols).0, extent(&tiled, Dim::Rows).0);
+    match preset {
+        LayoutPreset::MainVertical => {
+            let main = top_left(&tiled);
+            if main.x != left || main.rows != extent(&tiled, Dim::Rows).1 {
+                return Err("the top-left pane does not span the tab's height".to_string());
+            }
+        }
+        LayoutPreset::MainHorizontal => {
+            let main = top_left(&tiled);
+            if main.y != top || main.cols != extent(&tiled, Dim::Cols).1 {
+                return Err("the top-left pane does not span the tab's width".to_string());
+            }
+        }
+        LayoutPreset::EvenHorizontal if rows > 1 => {
+            return Err("some panes are stacked".to_string());
+        }
+        LayoutPreset::EvenVertical if cols > 1 => {
+            return Err("some panes are side by side".to_string());
+        }
+        LayoutPreset::Grid if rows * cols != tiled.len() => {
+            return Err("the panes do not line up in rows and columns".to_string());
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn top_left<'a>(tiled: &[&'a PaneInfo]) -> &'a PaneInfo {
+    tiled
+        .iter()
+        .min_by_key(|p| (p.y, p.x))
+        .expect("tiled panes are not empty")
+}
+
+/// Start and length of the area `panes` cover along `dim`
+fn extent(panes: &[&PaneInfo], dim: Dim) -> (usize, usize) {
+    let (start, end) = match dim {
+        Dim::Cols => (
+            panes.iter().map(|p| p.x).min(),
+            panes.iter().map(|p| p.x + p.cols).max(),
+        ),
+        Dim::Rows => (
+            panes.iter().map(|p| p.y).min(),
+            panes.iter().map(|p| p.y + p.rows).max(),
+        ),
+    };
+    let start = start.unwrap_or(0);
+    (start, end.unwrap_or(start) - start)
+}
+
+/// Plan resizes for a preset. Presets work within the tab's existing split structure: panes are
+/// resized, never re-split, so [`check_layout`] first tells whether the structure fits.
+fn layout_plan(
+    panes: &[PaneInfo],
+    tab_index: usize,
+    preset: LayoutPreset,
+    main_size: u8,
+) -> Vec<BalanceStep> {
+    let tiled = tiled_in(panes, tab_index);
+    let row_bands = bands(&tiled, Dim::Cols);
+    let col_bands = bands(&tiled, Dim::Rows);
+    let top_left = tiled
+        .iter()
+        .min_by_key(|p| (p.y, p.x))
+        .map(|p| p.id.as_str());
+    let contains_top_left =
+        |band: &&Vec<&PaneInfo>| band.iter().any(|p| Some(p.id.as_str()) == top_left);
+
+    let mut steps = Vec::new();
+    match preset {
+        LayoutPreset::EvenHorizontal => {
+            for band in &row_bands {
+                steps.extend(even_steps(band, Dim::Cols));
+            }
+        }
+        LayoutPreset::EvenVertical => {
+            for band in &col_bands {
+                steps.extend(even_steps(band, Dim::Rows));
+            }
+        }
+        LayoutPreset::Grid => {
+            for band in &row_bands {
+                steps.extend(even_steps(band, Dim::Cols));
+            }
+            for band in &col_bands {
+                steps.extend(even_steps(band, Dim::Rows));
+            }
+        }
+        LayoutPreset::MainVertical => {
+            steps.extend(main_plan(
+                &tiled,
+                &row_bands,
+                contains_top_left,
+                Dim::Cols,
+                main_size,
+            ));
+        }
+        LayoutPreset::MainHorizontal => {
+            steps.extend(main_plan(
+                &tiled,
+                &col_bands,
+                contains_top_left,
+                Dim::Rows,
+                main_size,
+            ));
+        }
+    }
+    steps
+}
+
+/// Group panes that sit side by side (`Dim::Cols`) or stacked (`Dim::Rows`), ordered along `dim`
+fn bands<'a>(tiled: &[&'a PaneInfo], dim: Dim) -> Vec<Vec<&'a PaneInfo>> {
+    let mut bands: BTreeMap<(usize, usize), Vec<&PaneInfo>> = BTreeMap::new();
+    for pane in tiled {
+        let key = match dim {
+            Dim::Cols => (pane.y, pane.rows),
+            Dim::Rows => (pane.x, pane.cols),
+        };
+        bands.entry(key).or_default().push(pane);
+    }
+    bands
+        .into_values()
+        .filter(|band| band.len() > 1)
+        .map(|mut band| {
+            band.sort_by_key(|p| match dim {
+                Dim::Cols => p.x,
+                Dim::Rows => p.y,
+            });
+            band
+        })
+        .collect()
+}
+
+fn size(pane: &PaneInfo, dim: Dim) -> usize {
+    match dim {
+        Dim::Cols => pane.cols,
+        Dim::Rows => pane.rows,
+    }
+}
+
+fn step(pane: &PaneInfo, dim: Dim, target: usize) -> Option<BalanceStep> {
+    if size(pane, dim) == target {
+        return None;
+    }
+    Some(BalanceStep {
+        id: pane.id.clone(),
+        cols: matches!(dim, Dim::Cols).then_some(target),
+        rows: matches!(dim, Dim::Rows).then_some(target),
+    })
+}
+
+fn even_steps(band: &[&PaneInfo], dim: Dim) -> Vec<BalanceStep> {
+    let target = band.iter().map(|p| size(p, dim)).sum::<usize>() / band.len();
+    band[..band.len() - 1]
+        .iter()
+        .filter_map(|p| step(p, dim, target))
+        .collect()
+}
+
+/// Size the top-left pane for a main-* preset: its band shares out the rest when every pane is
+/// in it, otherwise the other panes keep their arrangement beside (or below) the main pane
+fn main_plan(
+    tiled: &[&PaneInfo],
+    bands: &[Vec<&PaneInfo>],
+    contains_top_left: impl Fn(&&Vec<&PaneInfo>) -> bool,
+    dim: Dim,
+    main_size: u8,
+) -> Vec<BalanceStep> {
+    match bands.iter().find(contains_top_left) {
+        Some(band) if band.len() == tiled.len() => main_steps(band, dim, main_size),
+        _ if tiled.len() > 1 => {
+            let main = top_left(tiled);
+            let others: Vec<&PaneInfo> =
+                tiled.iter().copied().filter(|p| p.id != main.id).collect();
+            let total = size(main, dim) + extent(&others, dim).1;
+            step(main, dim, total * usize::from(main_size) / 100)
+                .into_iter()
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn main_steps(band: &[&PaneInfo], dim: Dim, main_size: u8) -> Vec<BalanceStep> {
+    let total = band.iter().map(|p| size(p, dim)).sum::<usize>();
+    let main = total * usize::from(main_size) / 100;
+    let rest = (total - main) / (band.len() - 1);
+
+    let mut steps: Vec<BalanceStep> = step(band[0], dim, main).into_iter().collect();
+    steps.extend(
+        band[1..band.len() - 1]
+            .iter()
+            .filter_map(|p| step(p, dim, rest)),
+    );
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pane(id: &str, x: usize, y: usize, cols: usize, rows: usize) -> PaneInfo {
+        PaneInfo {
+            id: id.to_string(),
+            pane_type: "terminal".to_string(),
+            tab_name: "tab".to_string(),
+            x,
+            y,
+            cols,
+            rows,
+            ..PaneInfo::default()
+        }
+    }
+
+    #[test]
+    fn export_panes_orders_by_position_and_dedupes_names() {
+        let mut right = pane("terminal:2", 40, 0, 40, 20);
+        right.title = "zsh".to_string();
+        right.cwd = Some("/src/api".to_string());
+        let mut left = pane("terminal:1", 0, 0, 40, 20);
+        left.title = "zsh".to_string();
+        let mut server = pane("terminal:3", 10, 5, 30, 10);
+        server.name = Some("server".to_string());
+        server.command = Some("npm run dev".to_string());
+        server.floating = true;
+        let mut other_tab = pane("terminal:4", 0, 0, 80, 20);
+        other_tab.tab_index = 1;
+
+        let exported = export_panes(&[server, right, left, other_tab], 0);
+        let names: Vec<&str> = exported.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["zsh", "zsh-2", "server"]);
+        assert_eq!(exported[1].cwd.as_deref(), Some("/src/api"));
+        assert_eq!(exported[2].command, ["npm", "run", "dev"]);
+        assert!(exported[2].floating);
+        assert_eq!((exported[0].cols, exported[0].rows), (Some(40), Some(20)));
+    }
+
+    fn tab(index: usize, name: &str, active: bool) -> TabInfo {
+        TabInfo {
+            index,
+            name: name.to_string(),
+            active,
+            ..TabInfo::default()
+        }
+    }
+
+    #[test]
+    fn resolve_tab_spec_accepts_current_index_and_name() {
+        let tabs = vec![tab(0, "editor", false), tab(1, "logs", true)];
+        assert_eq!(resolve_tab_spec("current", &tabs), Ok(1));
+        assert_eq!(resolve_tab_spec("0", &tabs), Ok(0));
+        assert_eq!(resolve_tab_spec("logs", &tabs), Ok(1));
+        assert!(resolve_tab_spec("5", &tabs).is_err());
+        assert!(resolve_tab_spec("missing", &tabs)
+            .unwrap_err()
+            .contains("0=\"editor\""));
+    }
+
+    #[test]
+    fn resolve_tab_spec_rejects_duplicate_names() {
+        let tabs = vec![tab(0, "shell", true), tab(1, "shell", false)];
+        assert!(resolve_tab_spec("shell", &tabs).is_err());
+    }
+
+    #[test]
+    fn move_offset_handles_directions_and_positions() {
+        assert_eq!(move_offset("left", 2, 4), Ok(-1));
+        assert_eq!(move_offset("right", 2, 4), Ok(1));
+        assert_eq!(move_offset("0", 2, 4), Ok(-2));
+        assert_eq!(move_offset("3", 2, 4), Ok(1));
+        assert!(move_offset("4", 2, 4).is_err());
+        assert!(move_offset("up", 2, 4).is_err());
+    }
+
+    #[test]
+    fn balance_plan_evens_out_side_by_side_panes() {
+        let panes = vec![
+            pane("terminal:1", 0, 0, 30, 40),
+            pane("terminal:2", 32, 0, 90, 40),
+        ];
+        assert_eq!(
+            balance_plan(&panes, 0),
+            vec![BalanceStep {
+                id: "terminal:1".to_string(),
+                cols: Some(60),
+                rows: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn balance_plan_evens_out_stacked_panes() {
+        let panes = vec![
+            pane("terminal:1", 0, 0, 80, 10),
+            pane("terminal:2", 0, 12, 80, 30),
+        ];
+        assert_eq!(
+            balance_plan(&panes, 0),
+            vec![BalanceStep {
+                id: "terminal:1".to_string(),
+                cols: None,
+                rows: Some(20),
+            }]
+        );
+    }
+
+    #[test]
+    fn layout_plan_main_vertical_grows_leftmost_pane() {
+        let panes = vec![
+            pane("terminal:1", 0, 0, 40, 40),
+            pane("terminal:2", 42, 0, 40, 40),
+            pane("terminal:3", 84, 0, 40, 40),
+        ];
+        assert_eq!(
+            layout_plan(&panes, 0, LayoutPreset::MainVertical, 60),
+            vec![
+                BalanceStep {
+                    id: "terminal:1".to_string(),
+                    cols: Some(72),
+                    rows: None,
+                },
+                BalanceStep {
+                    id: "terminal:2".to_string(),
+                    cols: Some(24),
+                    rows: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn layout_plan_even_horizontal_only_touches_widths() {
+        let panes = vec![
+            pane("terminal:1", 0, 0, 30, 10),
+            pane("terminal:2", 32, 0, 90, 10),
+            pane("terminal:3", 0, 12, 30, 30),
+        ];
+        let steps = layout_plan(&panes, 0, LayoutPreset::EvenHorizontal, 60);
+        assert!(steps.iter().all(|s| s.rows.is_none()));
+        assert_eq!(steps.len(), 1);
+    }
+
+    #[test]
+    fn layout_plan_main_vertical_sizes_main_pane_beside_a_stack() {
+        let panes = vec![
+            pane("terminal:1", 0, 0, 60, 40),
+            pane("terminal:2", 60, 0, 60, 20),
+            pane("terminal:3", 60, 20, 60, 20),
+        ];
+        assert_eq!(check_layout(&panes, 0, LayoutPreset::MainVertical), Ok(()));
+        assert_eq!(
+            layout_plan(&panes, 0, LayoutPreset::MainVertical, 75),
+            vec![BalanceStep {
+                id: "terminal:1".to_string(),
+                cols: Some(90),
+                rows: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn check_layout_rejects_presets_the_splits_cannot_take() {
+        let stack_beside_main = vec![
+            pane("terminal:1", 0, 0, 60, 40),
+            pane("terminal:2", 60, 0, 60, 20),
+            pane("terminal:3", 60, 20, 60, 20),
+        ];
+        for preset in [
+            LayoutPreset::MainHorizontal,
+            LayoutPreset::EvenHorizontal,
+            LayoutPreset::EvenVertical,
+            LayoutPreset::Grid,
+        ] {
+            assert!(
+                check_layout(&stack_beside_main, 0, preset).is_err(),
+                "{preset}"
+            );
+        }
+        let quarters = vec![
+            pane("terminal:1", 0, 0, 60, 20),
+            pane("terminal:2", 60, 0, 60, 20),
+            pane("terminal:3", 0, 20, 60, 20),
+            pane("terminal:4", 60, 20, 60, 20),
+        ];
+        assert_eq!(check_layout(&quarters, 0, LayoutPreset::Grid), Ok(()));
+        assert!(check_layout(&quarters, 0, LayoutPreset::MainVertical).is_err());
+    }
+
+    #[test]
+    fn layout_preset_parses_names() {
+        assert_eq!(
+            "main-vertical".parse::<LayoutPreset>(),
+            Ok(LayoutPreset::MainVertical)
+        );
+        assert_eq!("grid".parse::<LayoutPreset>(), Ok(LayoutPreset::Grid));
+        assert!("tiled".parse::<LayoutPreset>().is_err());
+    }
+
+    #[test]
+    fn balance_plan_ignores_balanced_floating_and_other_tabs() {
+        let mut floating = pane("terminal:3", 10, 10, 20, 5);
+        floating.floating = true;
+        let mut other_tab = pane("terminal:4", 0, 0, 10, 40);
+        other_tab.tab_index = 1;
+        let panes = vec![
+            pane("terminal:1", 0, 0, 60, 40),
+            pane("terminal:2", 62, 0, 60, 40),
+            floating,
+            other_tab,
+        ];
+        assert!(balance_plan(&panes, 0).is_empty());
+    }
+}
@@ -0,0 +1,121 @@
+//! Pane tags (`zjctl tag`)
+//!
+//! Tags live in the plugin like marks, so they last as long as the session's plugin instance
+//! and are dropped when their pane closes. Unlike a mark, a tag can be on many panes and a pane
+//! can carry many tags; `tag:NAME` selects every tagged pane, so `--all` sends to the lot.
+
+use serde::{Deserialize, Serialize};
+use zjctl_proto::methods;
+
+use crate::client::{self, ClientError};
+use crate::formats::{self, OutputFormat};
+use crate::output;
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Tag {
+    tag: String,
+    panes: Vec<String>,
+}
+
+/// Letters, digits, `_`, `-`, `.`: the characters that keep `tag:NAME` one selector predicate
+fn validate(tag: &str) -> Result<(), String> {
+    let valid = !tag.is_empty()
+        && tag
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "invalid tag: {tag:?} (use letters, digits, '_', '-', '.')"
+        ))
+    }
+}
+
+pub fn add(
+    plugin: Option<&str>,
+    tag: &str,
+    selector: &str,
+    all: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    validate(tag)?;
+    let params = serde_json::json!({ "tag": tag, "selector": selector, "all": all });
+    let result: Tag = serde_json::from_value(client::rpc_call(plugin, methods::TAG_ADD, params)?)?;
+    for pane in &result.panes {
+        println!("tag:{tag} + {pane}");
+    }
+    Ok(())
+}
+
+/// Untag the panes matching `selector`, or every pane carrying the tag
+pub fn rm(
+    plugin: Option<&str>,
+    tag: &str,
+    selector: Option<&str>,
+    all: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut params = serde_json::json!({ "tag": tag, "all": all });
+    if let Some(selector) = selector {
+        params["selector"] = serde_json::json!(selector);
+    }
+    let result: Tag =
+        serde_json::from_value(client::rpc_call(plugin, methods::TAG_REMOVE, params)?)?;
+    if result.panes.is_empty() {
+        return Err(Box::new(ClientError::no_match(format!(
+            "no pane is tagged {tag} (see: zjctl tag ls)"
+        ))));
+    }
+    for pane in &result.panes {
+        println!("tag:{tag} - {pane}");
+    }
+    Ok(())
+}
+
+pub fn ls(plugin: Option<&str>, output: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
+    let tags: Vec<Tag> = serde_json::from_value(client::rpc_call(
+        plugin,
+        methods::TAGS_LIST,
+        serde_json::json!({}),
+    )?)?;
+    if output == OutputFormat::Tsv {
+        // tag, pane: one line per tagged pane
+        for tag in &tags {
+            for pane in &tag.panes {
+                println!("{}", formats::tsv_row([&tag.tag, pane]));
+            }
+        }
+        return Ok(());
+    }
+    if formats::print_document(output, &tags)? {
+        return Ok(());
+    }
+    if tags.is_empty() {
+        println!("No tags (add one with: zjctl tag add NAME --pane SELECTOR)");
+        return Ok(());
+    }
+    let panes_width = output::flex_width(17, 12, 60);
+    println!("{}", output::header(&format!("{:<16} PANES", "TAG")));
+    println!("{}", output::rule(17 + panes_width));
+    for tag in &tags {
+        println!(
+            "{:<16} {}",
+            tag.tag,
+            output::truncate(&tag.panes.join(", "), panes_width)
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_must_stay_one_selector_predicate() {
+        assert!(validate("backend").is_ok());
+        assert!(validate("logs.v2_a-b").is_ok());
+        assert!(validate("").is_err());
+        assert!(validate("a,b").is_err());
+        assert!(validate("a+cmd:x").is_err());
+    }
+}
@@ -0,0 +1,144 @@
+//! Wait until a selector resolves to one or more panes
+
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use zjctl_proto::PaneSelector;
+
+use crate::commands::pane::pane_id_to_selector;
+use crate::commands::panes::{self, PaneInfo};
+use crate::progress::{Progress, ProgressFormat};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+pub fn run(
+    plugin: Option<&str>,
+    selector: &str,
+    timeout: f64,
+    unique: bool,
+    progress: Option<ProgressFormat>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if timeout <= 0.0 {
+        return Err("timeout must be greater than 0".into());
+    }
+    // Fail fast on malformed selectors instead of polling until the timeout.
+    selector
+        .parse::<PaneSelector>()
+        .map_err(|e| e.to_string())?;
+
+    let timeout_duration = Duration::from_secs_f64(timeout);
+    let mut progress = Progress::new("wait-for", progress).with_status_line();
+    let start = Instant::now();
+    let mut last_count = None;
+    let mut last_change = Instant::now();
+
+    loop {
+        let matched = panes::list_matching(plugin, selector)?;
+        if last_count != Some(matched.len()) {
+            last_count = Some(matched.len());
+            last_change = Instant::now();
+        }
+        let fields = serde_json::json!({
+            "matches": matched.len(),
+            "since_change": last_change.elapsed().as_secs_f64(),
+        });
+        if is_satisfied(&matched, unique) {
+            progress.event("matched", fields);
+            for pane in &matched {
+                match pane_id_to_selector(&pane.id) {
+                    Some(selector) => println!("{selector}"),
+                    None => println!("{}", pane.id),
+                }
+            }
+            return Ok(());
+        }
+
+        if start.elapsed() >= timeout_duration {
+            progress.event("timeout", fields);
+            return Err(timeout_message(timeout, matched.len(), unique).into());
+        }
+        progress.update("waiting", fields);
+        sleep(POLL_INTERVAL);
+    }
+}
+
+fn is_satisfied(matched: &[PaneInfo], unique: bool) -> bool {
+    if unique {
+        matched.len() == 1
+    } else {
+        !matched.is_empty()
+    }
+}
+
+fn timeout_message(timeout: f64, count: usize, unique: bool) -> String {
+    if unique && count > 1 {
+        format!(
+            "timed out after {timeout:.1}s ({count} panes match selector; expected exactly one)"
+        )
+    } else {
+        format!("timed out after {timeout:.1}s (no panes match selector)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pane(id: &str) -> PaneInfo {
+        PaneInfo {
+            id: id.to_string(),
+            pane_type: "terminal".to_string(),
+            title: String::new(),
+            name: None,
+            terminal_title: None,
+            command: None,
+            cwd: None,
+            token: None,
+            tags: Vec::new(),
+            tab_index: 0,
+            tab_name: "tab".to_string(),
+            focused: false,
+            floating: false,
+            suppressed: false,
+            exited: false,
+            exit_status: None,
+            held: false,
+            x: 0,
+            y: 0,
+            rows: 0,
+            cols: 0,
+        }
+    }
+
+    #[test]
+    fn satisfied_by_any_match_when_not_unique() {
+        assert!(!is_satisfied(&[], false));
+        assert!(is_satisfied(&[pane("terminal:1")], false));
+        assert!(is_satisfied(
+            &[pane("terminal:1"), pane("terminal:2")],
+            false
+        ));
+    }
+
+    #[test]
+    fn unique_requires_exactly_one_match() {
+        assert!(!is_satisfied(&[], true));
+        assert!(is_satisfied(&[pane("terminal:1")], true));
+        assert!(!is_satisfied(
+            &[pane("terminal:1"), pane("terminal:2")],
+            true
+        ));
+    }
+
+    #[test]
+    fn timeout_message_reports_ambiguity() {
+        assert_eq!(
+            timeout_message(30.0, 2, true),
+            "timed out after 30.0s (2 panes match selector; expected exactly one)"
+        );
+        assert_eq!(
+            timeout_message(5.0, 0, true),
+            "timed out after 5.0s (no panes match selector)"
+        );
+    }
+}
@@ -0,0 +1,297 @@
+//! Keep a command pane alive by re-running it when its command exits
+
+use std::thread::sleep;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use zjctl_proto::methods;
+
+use crate::client::{self, ClientError};
+use crate::commands::pane::{self, LaunchOptions};
+use crate::commands::panes::{self, PaneInfo};
+use crate::daemon;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// How long a re-run pane may keep reporting its old exit before it counts as a new one
+const SETTLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct WatchdogOptions<'a> {
+    pub selector: &'a str,
+    pub restart_cmd: Option<&'a str>,
+    /// Give up after this many restarts (0 = never)
+    pub max_restarts: u32,
+    /// Delay before the first restart, doubled after each one
+    pub backoff: f64,
+}
+
+/// One line of the restart log printed to stdout
+#[derive(Debug, Serialize)]
+struct RestartRecord<'a> {
+    /// Unix timestamp in seconds
+    time: u64,
+    pane: &'a str,
+    restart: u32,
+    exit_status: Option<i32>,
+    /// `rerun` (same pane), `relaunch` (new pane from --restart-cmd), or `gave_up`
+    action: &'a str,
+    /// Backoff waited before acting, in seconds
+    delay: f64,
+}
+
+#[derive(Debug, PartialEq)]
+enum Action {
+    Wait,
+    Rerun,
+    Relaunch,
+}
+
+pub fn run(
+    plugin: Option<&str>,
+    options: WatchdogOptions<'_>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if options.backoff < 0.0 {
+        return Err("backoff must be >= 0".into());
+    }
+
+    let matched = panes::list_matching(plugin, options.selector)?;
+    let (mut target, name) = match matched.as_slice() {
+        [] => match options.restart_cmd {
+            Some(command) => {
+                let name = selector_name(options.selector);
+                let target = relaunch(plugin, command, name.as_deref())?;
+                eprintln!("watchdog: started {target}");
+                (target, name)
+            }
+            None => {
+                return Err(ClientError::no_match(format!(
+                    "no panes match selector: {}",
+                    options.selector
+                ))
+                .into())
+            }
+        },
+        [pane] => {
+            if pane.command.is_none() && options.restart_cmd.is_none() {
+                return Err(format!(
+                    "{} is not a command pane; its exit cannot be observed (launch it with `zjctl pane launch -- <cmd>` or pass --restart-cmd)",
+                    pane.id
+                )
+                .into());
+            }
+            let target =
+                pane::pane_id_to_selector(&pane.id).unwrap_or_else(|| options.selector.to_string());
+            (
+                target,
+                pane.name.clone().or_else(|| Some(pane.title.clone())),
+            )
+        }
+        _ => {
+            return Err(
+                ClientError::ambiguous(format!("{} panes match selector", matched.len())).into(),
+            )
+        }
+    };
+
+    let _registration = daemon::register("watchdog", options.selector);
+    let mut restarts = 0u32;
+    let mut settling: Option<Instant> = None;
+
+    loop {
+        let current = panes::list_matching(plugin, &target)?;
+        let pane = current.first();
+
+        // A freshly re-run pane can report its previous exit for a moment.
+        if let Some(since) = settling {
+            let still_exited = pane.is_some_and(|p| p.exited || p.held);
+            if still_exited && since.elapsed() < SETTLE_TIMEOUT {
+                sleep(POLL_INTERVAL);
+                continue;
+            }
+            settling = None;
+        }
+
+        if pane.is_none() && options.restart_cmd.is_none() {
+            return Err(format!("{target} was closed; pass --restart-cmd to relaunch it").into());
+        }
+
+        let action = next_action(pane, options.restart_cmd.is_some());
+        if action == Action::Wait {
+            sleep(POLL_INTERVAL);
+            continue;
+        }
+
+        let exit_status = pane.and_then(|p| p.exit_status);
+        if options.max_restarts > 0 && restarts >= options.max_restarts {
+            log_restart(&RestartRecord {
+                time: unix_time(),
+                pane: &target,
+                restart: restarts,
+                exit_status,
+                action: "gave_up",
+                delay: 0.0,
+            })?;
+            return Err(format!(
+                "{target} exited after {restarts} restarts; giving up (--max-restarts {})",
+                options.max_restarts
+            )
+            .into());
+        }
+
+        let delay = backoff_delay(options.backoff, restarts);
+        sleep(delay);
+        restarts += 1;
+
+        let action_name = match action {
+            Action::Rerun => {
+                client::rpc_call(
+                    plugin,
+                    methods::PANE_RERUN,
+                    serde_json::json!({ "selector": target }),
+                )?;
+                "rerun"
+            }
+            Action::Relaunch => {
+                let command = options.restart_cmd.unwrap_or_default();
+                target = relaunch(plugin, command, name.as_deref())?;
+                "relaunch"
+            }
+            Action::Wait => unreachable!(),
+        };
+        settling = Some(Instant::now());
+
+        log_restart(&RestartRecord {
+            time: unix_time(),
+            pane: &target,
+            restart: restarts,
+            exit_status,
+            action: action_name,
+            delay: delay.as_secs_f64(),
+        })?;
+    }
+}
+
+/// Decide what to do with the watched pane (`None` once it no longer exists)
+fn next_action(pane: Option<&PaneInfo>, can_relaunch: bool) -> Action {
+    match pane {
+        Some(p) if (p.exited || p.held) && p.command.is_some() => Action::Rerun,
+        // Exited but not re-runnable in place (no command recorded): replace it.
+        Some(p) if (p.exited || p.held) && can_relaunch => Action::Relaunch,
+        Some(_) => Action::Wait,
+        None if can_relaunch => Action::Relaunch,
+        None => Action::Wait,
+    }
+}
+
+/// Exponential backoff: `initial * 2^restarts`, capped at one minute
+fn backoff_delay(initial: f64, restarts: u32) -> Duration {
+    let secs = initial * 2f64.powi(restarts.min(16) as i32);
+    Duration::from_secs_f64(secs).min(MAX_BACKOFF)
+}
+
+/// Name to give a relaunched pane so `title:`/`name:` selectors keep matching it
+fn selector_name(selector: &str) -> Option<String> {
+    let (kind, value) = selector.split_once(':')?;
+    match kind {
+        "title" | "name" if !value.is_empty() && !value.starts_with('/') => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+fn relaunch(
+    plugin: Option<&str>,
+    command: &str,
+    name: Option<&str>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let argv = shell_command(command);
+    pane::launch_pane(
+        plugin,
+        &LaunchOptions {
+            direction: None,
+            floating: false,
+            name,
+            cwd: None,
+            close_on_exit: false,
+            in_place: false,
+            start_suspended: false,
+            command: &argv,
+        },
+    )
+}
+
+pub(crate) fn shell_command(command: &str) -> Vec<String> {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    vec![shell.to_string(), flag.to_string(), command.to_string()]
+}
+
+fn log_restart(record: &RestartRecord<'_>) -> Result<(), serde_json::Error> {
+    println!("{}", serde_json::to_string(record)?);
+    Ok(())
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pane(command: Option<&str>, exited: bool) -> PaneInfo {
+        PaneInfo {
+            id: "terminal:4".to_string(),
+            pane_type: "terminal".to_string(),
+            command: command.map(str::to_string),
+            exited,
+            ..PaneInfo::default()
+        }
+    }
+
+    #[test]
+    fn next_action_reruns_exited_command_panes() {
+        let running = pane(Some("npm run dev"), false);
+        let exited = pane(Some("npm run dev"), true);
+        let held = PaneInfo {
+            held: true,
+            ..pane(Some("npm run dev"), false)
+        };
+        assert_eq!(next_action(Some(&running), false), Action::Wait);
+        assert_eq!(next_action(Some(&exited), false), Action::Rerun);
+        assert_eq!(next_action(Some(&held), true), Action::Rerun);
+    }
+
+    #[test]
+    fn next_action_relaunches_only_with_restart_cmd() {
+        let shell = pane(None, true);
+        assert_eq!(next_action(None, true), Action::Relaunch);
+        assert_eq!(next_action(None, false), Action::Wait);
+        assert_eq!(next_action(Some(&shell), true), Action::Relaunch);
+        assert_eq!(next_action(Some(&shell), false), Action::Wait);
+    }
+
+    #[test]
+    fn backoff_doubles_and_caps() {
+        assert_eq!(backoff_delay(1.0, 0), Duration::from_secs(1));
+        assert_eq!(backoff_delay(1.0, 3), Duration::from_secs(8));
+        assert_eq!(backoff_delay(1.0, 40), MAX_BACKOFF);
+        assert_eq!(backoff_delay(0.0, 5), Duration::ZERO);
+    }
+
+    #[test]
+    fn selector_name_from_title_and_name() {
+        assert_eq!(
+            selector_name("title:dev-server").as_deref(),
+            Some("dev-server")
+        );
+        assert_eq!(selector_name("name:api").as_deref(), Some("api"));
+        assert_eq!(selector_name("title:/dev.*/"), None);
+        assert_eq!(selector_name("id:terminal:3"), None);
+    }
+}
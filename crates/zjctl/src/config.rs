@@ -0,0 +1,522 @@
+//! `~/.config/zjctl/config.toml`: team-wide defaults for command-line flags
+//!
+//! Keys are long flag names (`idle-time` or `idle_time`). Top-level keys apply to every
+//! command with that flag; a `[pane]` table applies to `pane` and its subcommands, and
+//! `[pane.wait-idle]` to that one command, the most specific table winning. `output = "json"`
+//! (or `"table"`) is shorthand for `json = true` (`false`); `"yaml"` and `"tsv"` only reach
+//! commands whose `--output` is a format. Flags given on the command line and environment variables
+//! still take precedence, since config values only replace clap's defaults. The bound on each
+//! RPC call (`zjctl --timeout`) is `rpc-timeout`, so a top-level `timeout` only sets the
+//! commands' own waits.
+//!
+//! ```toml
+//! plugin = "file:/opt/zjctl/zrpc.wasm"
+//! rpc-timeout = 10
+//! timeout = 120
+//!
+//! [pane.wait-idle]
+//! idle-time = 3.0
+//!
+//! [panes.ls]
+//! output = "json"
+//!
+//! [[enter-key]]
+//! pane = "cmd:claude"
+//! key = "cr"
+//! ```
+//!
+//! `[[enter-key]]` entries pick the Enter bytes `pane send` uses for the panes a selector
+//! matches, when `--enter-key` (or `ZJCTL_ENTER_KEY`, or an `enter-key` default) is not set;
+//! the first entry matching every target pane wins.
+//!
+//! Only the TOML needed for this is understood: tables, strings, numbers, booleans, and
+//! one-line arrays. `ZJCTL_CONFIG` points at another file; set it empty to ignore the file.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use clap::Command;
+
+use crate::paths;
+
+/// Flags configured under a key other than their long name, by arg id
+const KEYS: &[(&str, &str)] = &[("rpc_timeout", "rpc-timeout")];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    List(Vec<Value>),
+}
+
+impl Value {
+    /// Values as clap would receive them on the command line
+    fn to_args(&self) -> Vec<String> {
+        match self {
+            Value::Str(s) => vec![s.clone()],
+            Value::Int(n) => vec![n.to_string()],
+            Value::Float(n) => vec![n.to_string()],
+            Value::Bool(b) => vec![b.to_string()],
+            Value::List(items) => items.iter().flat_map(Value::to_args).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Table {
+    /// Command path, e.g. `["pane", "wait-idle"]`; empty for top-level keys
+    path: Vec<String>,
+    /// Flag name (kebab-case) -> value
+    values: Vec<(String, Value)>,
+    /// An entry of an array of tables (`[[enter-key]]`) rather than flag defaults
+    entry: bool,
+}
+
+/// Array of tables holding [`EnterKeyRule`]s
+const ENTER_KEY_RULES: &str = "enter-key";
+
+/// A `[[enter-key]]` entry: `key` is the `--enter-key` value for panes `pane` matches
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnterKeyRule {
+    pub pane: String,
+    pub key: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Config {
+    pub path: PathBuf,
+    tables: Vec<Table>,
+}
+
+/// `ZJCTL_CONFIG` or `~/.config/zjctl/config.toml`; `None` when there is no file
+pub fn load() -> Result<Option<Config>, String> {
+    let path = match std::env::var_os("ZJCTL_CONFIG") {
+        Some(path) if path.is_empty() => return Ok(None),
+        Some(path) => PathBuf::from(path),
+        None => match paths::config_dir() {
+            Some(dir) => dir.join("zjctl").join("config.toml"),
+            None => return Ok(None),
+        },
+    };
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(format!("failed to read {}: {err}", path.display())),
+    };
+    let tables = parse(&contents).map_err(|err| format!("{}: {err}", path.display()))?;
+    Ok(Some(Config { path, tables }))
+}
+
+impl Config {
+    /// Install the config values as defaults of `command`'s flags; also returns the keys and
+    /// tables that matched nothing
+    pub fn apply(&self, command: Command) -> (Command, Vec<String>) {
+        let mut used = HashSet::new();
+        let mut reached = HashSet::new();
+        let command = self.apply_to(command, &[], &mut used, &mut reached);
+
+        let mut unknown = Vec::new();
+        for (index, table) in self.tables.iter().enumerate() {
+            if table.entry {
+                if table.path != [ENTER_KEY_RULES] {
+                    unknown.push(format!("[[{}]] is not a known list", table.path.join(".")));
+                }
+                continue;
+            }
+            if !reached.contains(&table.path) {
+                unknown.push(format!("[{}] names no command", table.path.join(".")));
+                continue;
+            }
+            for (key, _) in &table.values {
+                if !used.contains(&(index, key.clone())) {
+                    let scope = if table.path.is_empty() {
+                        "any command".to_string()
+                    } else {
+                        format!("[{}]", table.path.join("."))
+                    };
+                    unknown.push(format!("{key} is not a flag of {scope}"));
+                }
+            }
+        }
+        (command, unknown)
+    }
+
+    fn apply_to(
+        &self,
+        mut command: Command,
+        path: &[String],
+        used: &mut HashSet<(usize, String)>,
+        reached: &mut HashSet<Vec<String>>,
+    ) -> Command {
+        reached.insert(path.to_vec());
+        // (id, key, free-form): derived args are named `idle_time` but spelled `--idle-time`
+        let flags: Vec<(String, String, bool)> = command
+            .get_arguments()
+            .filter(|arg| !arg.is_required_set())
+            .filter_map(|arg| {
+                let free_form = arg.get_possible_values().is_empty();
+                let id = arg.get_id().as_str();
+                let key = match KEYS.iter().find(|(arg_id, _)| *arg_id == id) {
+                    Some((_, key)) => key,
+                    None => arg.get_long()?,
+                };
+                Some((id.to_string(), key.to_string(), free_form))
+            })
+            .collect();
+        for (id, flag, free_form) in flags {
+            if let Some((index, value)) = self.lookup(path, &flag) {
+                // `output = "yaml"` names a format, not the file of `report --output FILE`.
+                if flag == "output" && free_form && is_format(value) {
+                    continue;
+                }
+                used.insert((index, flag.clone()));
+                let values = value.to_args();
+                command = command.mut_arg(id.as_str(), |arg| arg.default_values(values));
+            }
+        }
+
+        let subcommands: Vec<String> = command
+            .get_subcommands()
+            .map(|sub| sub.get_name().to_string())
+            .collect();
+        for name in subcommands {
+            let mut sub_path = path.to_vec();
+            sub_path.push(name.clone());
+            command =
+                command.mut_subcommand(name, |sub| self.apply_to(sub, &sub_path, used, reached));
+        }
+        command
+    }
+
+    /// `[[enter-key]]` entries in file order, and the problems with those that were skipped
+    pub fn enter_key_rules(&self) -> (Vec<EnterKeyRule>, Vec<String>) {
+        let mut rules = Vec::new();
+        let mut problems = Vec::new();
+        for table in self.tables.iter().filter(|table| table.entry) {
+            if table.path != [ENTER_KEY_RULES] {
+                continue;
+            }
+            let get = |name: &str| {
+                table
+                    .values
+                    .iter()
+                    .rev()
+                    .find_map(|(key, value)| match value {
+                        Value::Str(s) if key == name => Some(s.clone()),
+                        _ => None,
+                    })
+            };
+            match (get("pane"), get("key")) {
+                (Some(pane), Some(key)) => rules.push(EnterKeyRule { pane, key }),
+                _ => problems.push(format!(
+                    "[[{ENTER_KEY_RULES}]] needs string `pane` and `key` values"
+                )),
+            }
+        }
+        (rules, problems)
+    }
+
+    /// Value for `flag` from the most specific table that covers `path`
+    fn lookup(&self, path: &[String], flag: &str) -> Option<(usize, &Value)> {
+        self.tables
+            .iter()
+            .enumerate()
+            .filter(|(_, table)| !table.entry && path.starts_with(&table.path))
+            .filter_map(|(index, table)| {
+                let value = table.values.iter().rev().find(|(key, _)| key == flag)?;
+                Some((table.path.len(), index, &value.1))
+            })
+            .max_by_key(|(depth, index, _)| (*depth, *index))
+            .map(|(_, index, value)| (index, value))
+    }
+}
+
+fn parse(contents: &str) -> Result<Vec<Table>, String> {
+    let mut tables = vec![Table::default()];
+    for (i, raw) in contents.lines().enumerate() {
+        let err = |message: &str| format!("line {}: {message}", i + 1);
+        let line = strip_comment(raw).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix("[[") {
+            let header = header
+                .strip_suffix("]]")
+                .ok_or_else(|| err("expected `[[table]]`"))?;
+            let path = split_key(header).ok_or_else(|| err("invalid table name"))?;
+            tables.push(Table {
+                path,
+                values: Vec::new(),
+                entry: true,
+            });
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[') {
+            let header = header
+                .strip_suffix(']')
+                .ok_or_else(|| err("expected `[table]`"))?;
+            let path = split_key(header).ok_or_else(|| err("invalid table name"))?;
+            tables.push(Table {
+                path,
+                values: Vec::new(),
+                entry: false,
+            });
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| err("expected `key = value`"))?;
+        let key = key.trim().trim_matches('"').replace('_', "-");
+        if key.is_empty() {
+            return Err(err("missing key"));
+        }
+        let value = parse_value(value.trim()).ok_or_else(|| err("unsupported value"))?;
+        let table = tables.last_mut().expect("top-level table");
+        // `report --output FILE` keeps its own meaning.
+        match (key.as_str(), &value) {
+            ("output", Value::Str(format)) if matches!(format.as_str(), "json" | "table") => {
+                let json = Value::Bool(format == "json");
+                table.values.push(("json".to_string(), json));
+            }
+            _ => table.values.push((key, value)),
+        }
+    }
+    Ok(tables)
+}
+
+/// An `output` value for format flags (`json` and `table` become `json = true|false`)
+fn is_format(value: &Value) -> bool {
+    matches!(value, Value::Str(format) if matches!(format.as_str(), "yaml" | "tsv"))
+}
+
+/// `pane.wait-idle` or `pane."wait-idle"`
+fn split_key(key: &str) -> Option<Vec<String>> {
+    key.split('.')
+        .map(|part| {
+            let part = part.trim().trim_matches('"');
+            (!part.is_empty()).then(|| part.to_string())
+        })
+        .collect()
+}
+
+/// Drop a `#` comment that is not inside a string
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    for (i, c) in line.char_indices() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            ('#', None) => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn parse_value(value: &str) -> Option<Value> {
+    if let Some(inner) = value.strip_prefix('[') {
+        let inner = inner.strip_suffix(']')?;
+        return inner
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(parse_value)
+            .collect::<Option<_>>()
+            .map(Value::List);
+    }
+    if let Some(inner) = value.strip_prefix('"') {
+        return unescape(inner.strip_suffix('"')?).map(Value::Str);
+    }
+    if let Some(inner) = value.strip_prefix('\'') {
+        return Some(Value::Str(inner.strip_suffix('\'')?.to_string()));
+    }
+    match value {
+        "true" => return Some(Value::Bool(true)),
+        "false" => return Some(Value::Bool(false)),
+        _ => {}
+    }
+    let number = value.replace('_', "");
+    number
+        .parse()
+        .map(Value::Int)
+        .or_else(|_| number.parse().map(Value::Float))
+        .ok()
+}
+
+fn unescape(s: &str) -> Option<String> {
+    let mut out = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        out.push(match chars.next()? {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '"' => '"',
+            '\\' => '\\',
+            _ => return None,
+        });
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{Arg, ArgAction};
+
+    fn cli() -> Command {
+        Command::new("zjctl")
+            .arg(Arg::new("plugin").long("plugin"))
+            .arg(Arg::new("rpc_timeout").long("timeout"))
+            .subcommand(
+                Command::new("pane").subcommand(
+                    Command::new("wait-idle")
+                        .arg(Arg::new("timeout").long("timeout").default_value("30"))
+                        .arg(Arg::new("idle_time").long("idle-time").default_value("2")),
+                ),
+            )
+            .subcommand(
+                Command::new("panes").subcommand(
+                    Command::new("ls")
+                        .arg(Arg::new("json").long("json").action(ArgAction::SetTrue))
+                        .arg(
+                            Arg::new("output")
+                                .long("output")
+                                .value_parser(["table", "json", "yaml", "tsv"]),
+                        ),
+                ),
+            )
+            .subcommand(Command::new("report").arg(Arg::new("output").long("output")))
+    }
+
+    fn config(contents: &str) -> Config {
+        Config {
+            path: PathBuf::new(),
+            tables: parse(contents).unwrap(),
+        }
+    }
+
+    #[test]
+    fn specific_tables_win_and_flags_beat_config() {
+        let config = config(
+            "plugin = 'file:/opt/zrpc.wasm'  # shared\ntimeout = 120\n\n[pane.wait-idle]\nidle_time = 3.5\n\n[panes.ls]\noutput = \"json\"\n",
+        );
+        let (command, unknown) = config.apply(cli());
+        assert!(unknown.is_empty(), "{unknown:?}");
+
+        let matches =
+            command
+                .clone()
+                .get_matches_from(["zjctl", "pane", "wait-idle", "--timeout", "5"]);
+        assert_eq!(
+            matches.get_one::<String>("plugin").unwrap(),
+            "file:/opt/zrpc.wasm"
+        );
+        let (_, pane) = matches.subcommand().unwrap();
+        let (_, wait) = pane.subcommand().unwrap();
+        assert_eq!(wait.get_one::<String>("timeout").unwrap(), "5");
+        assert_eq!(wait.get_one::<String>("idle_time").unwrap(), "3.5");
+
+        let matches = command.get_matches_from(["zjctl", "panes", "ls"]);
+        let (_, panes) = matches.subcommand().unwrap();
+        assert!(panes.subcommand().unwrap().1.get_flag("json"));
+    }
+
+    #[test]
+    fn rpc_timeout_is_configured_apart_from_command_timeouts() {
+        let (command, unknown) = config("timeout = 120\n").apply(cli());
+        assert!(unknown.is_empty(), "{unknown:?}");
+        let matches = command.get_matches_from(["zjctl", "pane", "wait-idle"]);
+        assert!(matches.get_one::<String>("rpc_timeout").is_none());
+        let (_, pane) = matches.subcommand().unwrap();
+        assert_eq!(
+            pane.subcommand()
+                .unwrap()
+                .1
+                .get_one::<String>("timeout")
+                .unwrap(),
+            "120"
+        );
+
+        let (command, unknown) = config("rpc_timeout = 10\n").apply(cli());
+        assert!(unknown.is_empty(), "{unknown:?}");
+        let matches = command.get_matches_from(["zjctl", "pane", "wait-idle"]);
+        assert_eq!(matches.get_one::<String>("rpc_timeout").unwrap(), "10");
+        let (_, pane) = matches.subcommand().unwrap();
+        assert_eq!(
+            pane.subcommand()
+                .unwrap()
+                .1
+                .get_one::<String>("timeout")
+                .unwrap(),
+            "30"
+        );
+    }
+
+    #[test]
+    fn output_formats_skip_free_form_output_flags() {
+        let (command, unknown) = config("output = 'tsv'\n").apply(cli());
+        assert!(unknown.is_empty(), "{unknown:?}");
+        let matches = command.clone().get_matches_from(["zjctl", "panes", "ls"]);
+        let (_, panes) = matches.subcommand().unwrap();
+        let (_, ls) = panes.subcommand().unwrap();
+        assert_eq!(ls.get_one::<String>("output").unwrap(), "tsv");
+        let matches = command.get_matches_from(["zjctl", "report"]);
+        assert!(matches
+            .subcommand()
+            .unwrap()
+            .1
+            .get_one::<String>("output")
+            .is_none());
+    }
+
+    #[test]
+    fn enter_key_entries_are_rules_not_flag_defaults() {
+        let rules = config(
+            "[[enter-key]]\npane = 'cmd:claude'\nkey = 'cr'\n\n[[enter-key]]\npane = \"title:tui\"\n\n[[enter-key]]\npane = 'cmd:codex'\nkey = 'kitty'\n",
+        );
+        let (_, unknown) = rules.apply(cli());
+        assert!(unknown.is_empty(), "{unknown:?}");
+        let (rules, problems) = rules.enter_key_rules();
+        assert_eq!(
+            rules,
+            [
+                EnterKeyRule {
+                    pane: "cmd:claude".to_string(),
+                    key: "cr".to_string(),
+                },
+                EnterKeyRule {
+                    pane: "cmd:codex".to_string(),
+                    key: "kitty".to_string(),
+                },
+            ]
+        );
+        assert_eq!(problems.len(), 1);
+
+        let (_, unknown) = config("[[pane]]\nx = 1\n").apply(cli());
+        assert_eq!(unknown, ["[[pane]] is not a known list"]);
+    }
+
+    #[test]
+    fn unknown_keys_and_tables_are_reported() {
+        let (_, unknown) = config("timout = 1\n[pane.wiat-idle]\nx = 1\n").apply(cli());
+        assert_eq!(
+            unknown,
+            [
+                "timout is not a flag of any command",
+                "[pane.wiat-idle] names no command"
+            ]
+        );
+        assert!(parse("key = [1, 'a', true]\nbad").is_err());
+        assert_eq!(
+            parse_value("[1, 'a']"),
+            Some(Value::List(vec![Value::Int(1), Value::Str("a".into())]))
+        );
+    }
+}
@@ -0,0 +1,1609 @@
+//! Long-lived `zellij pipe` shared by CLI invocations (`zjctl daemon`).
+//!
+//! Spawning `zellij pipe` costs 100-300ms per call. `zellij pipe` without a payload sends each
+//! stdin line as its own message, so the daemon keeps one pipe open, listens on a Unix socket in
+//! the state directory, and multiplexes requests from every client over it, matching responses
+//! back by request id. Clients fall back to a direct pipe when no daemon is listening.
+//!
+//! The daemon also answers repeated `panes.list` calls from a short-lived snapshot, so a script
+//! running several commands back-to-back does not pay for a full list each time. The snapshot is
+//! only served after the plugin confirms its state generation has not moved (panes opened or
+//! closed by the user or by direct-pipe callers bump it), and any call through the daemon that
+//! may change the layout drops it.
+//!
+//! Besides RPC batches, a client may send a control line (`{"control":"status"}` or
+//! `{"control":"stop"}`) to inspect or shut down the daemon (`zjctl daemon status|stop`), or a
+//! history line (`{"history":"terminal:3"}`) for the snapshots kept by `--capture-history`.
+//!
+//! Long-running commands (`pane watch`, `pane pipe`, `watchdog`, `schedule run`, `events`,
+//! `on`) keep their own loops but send a register line and hold the connection open while
+//! they run, so `zjctl daemon status` can list them.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use zjctl_proto::{methods, Base64Bytes, RpcError, RpcErrorCode, RpcRequest, RpcResponse};
+
+use crate::auth::{self, Scope, TokenStore};
+use crate::client::{self, ClientError};
+use crate::history::{RecorderOptions, Snapshot};
+use crate::output;
+use crate::paths;
+use crate::zellij;
+
+/// One line sent by a client
+#[derive(Debug, Serialize, Deserialize)]
+struct DaemonRequest {
+    plugin: String,
+    configuration: String,
+    requests: Vec<RpcRequest>,
+    /// Skip the daemon's `panes.list` snapshot and ask the plugin
+    #[serde(default)]
+    no_cache: bool,
+    /// `ZJCTL_TOKEN`, for a daemon started with `--auth-tokens`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+    /// How long the caller waits for the plugin (`--timeout` plus held waits); the daemon
+    /// stops waiting then too
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timeout_ms: Option<u64>,
+}
+
+/// Control line sent by `zjctl daemon status|stop`
+#[derive(Debug, Serialize, Deserialize)]
+struct DaemonControl {
+    control: Control,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Control {
+    Status,
+    Stop,
+}
+
+/// History line sent by `zjctl pane capture --at|--history-list`
+#[derive(Debug, Serialize, Deserialize)]
+struct DaemonHistory {
+    /// Pane id, e.g. `terminal:3`
+    history: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+}
+
+/// Capture line sent by `pane capture` and the waits when the daemon refused to focus a pane
+/// for them: the daemon focuses it, reads the screen, and focuses back itself
+#[derive(Debug, Serialize, Deserialize)]
+struct DaemonCapture {
+    /// Pane selector
+    capture: String,
+    /// Scrollback too, not just the viewport
+    #[serde(default)]
+    full: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+}
+
+/// Register line sent by a long-running command; it is listed until it closes the connection
+#[derive(Debug, Serialize, Deserialize)]
+struct DaemonRegister {
+    register: Worker,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+}
+
+/// A long-running zjctl command registered with the daemon
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Worker {
+    /// The command: `watch`, `pipe`, `watchdog`, `schedule`, `events`, or `on`
+    pub kind: String,
+    /// What it works on (a selector, `from -> to`, an event)
+    pub target: String,
+    pub pid: u32,
+    /// Seconds since it registered (set by the daemon)
+    #[serde(default)]
+    pub running_secs: u64,
+}
+
+/// Any line a client may send
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ClientMessage {
+    Control(DaemonControl),
+    History(DaemonHistory),
+    Capture(DaemonCapture),
+    Register(DaemonRegister),
+    Rpc(DaemonRequest),
+}
+
+/// One line sent back by the daemon
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum DaemonReply {
+    Ok {
+        responses: Vec<RpcResponse>,
+    },
+    /// The daemon talks to a different plugin URL or configuration; use a direct pipe
+    Mismatch,
+    Error {
+        message: String,
+    },
+    /// The plugin did not answer within the caller's `timeout_ms`
+    Timeout,
+    Status {
+        daemon: DaemonStatus,
+    },
+    /// Snapshots of one pane, oldest first
+    History {
+        snapshots: Vec<Snapshot>,
+    },
+    /// Screen of the pane named by a capture line
+    Capture {
+        output: Base64Bytes,
+    },
+    /// The worker of a register line is listed until its connection closes
+    Registered,
+    /// Sent right before the daemon exits
+    Stopping,
+}
+
+/// What a running daemon reports about itself
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonStatus {
+    pub pid: u32,
+    pub plugin: String,
+    pub uptime_secs: u64,
+    pub cache_ttl_ms: u64,
+    /// Age of the `panes.list` snapshot, if one is held
+    pub cache_age_ms: Option<u64>,
+    /// RPC batches answered since start (including cache hits)
+    pub requests: u64,
+    /// Client connections being served right now
+    pub clients: usize,
+    /// Requests written to the pipe and still waiting for the plugin
+    pub pending: usize,
+    pub pipe_running: bool,
+    /// `zellij pipe` processes started so far (more than one means the pipe was respawned)
+    pub pipes_spawned: u64,
+    /// Address serving Prometheus metrics (`--metrics-listen`)
+    #[serde(default)]
+    pub metrics_listen: Option<String>,
+    /// Tokens file every call is checked against (`--auth-tokens`)
+    #[serde(default)]
+    pub auth_tokens: Option<String>,
+    /// Only `read`-scope calls are served (`--read-only`)
+    #[serde(default)]
+    pub read_only: bool,
+    /// Selectors whose panes are snapshotted (`--capture-history`)
+    #[serde(default)]
+    pub capture_history: Vec<String>,
+    #[serde(default)]
+    pub capture_interval_secs: u64,
+    #[serde(default)]
+    pub capture_keep: usize,
+    /// Long-running commands registered with the daemon
+    #[serde(default)]
+    pub workers: Vec<Worker>,
+}
+
+/// Set by `--no-cache`
+static NO_CACHE: AtomicBool = AtomicBool::new(false);
+
+/// Bypass the daemon's `panes.list` cache for every later call
+pub fn set_no_cache(no_cache: bool) {
+    NO_CACHE.store(no_cache, Ordering::Relaxed);
+}
+
+/// Set in the daemon process, whose own calls (capture history) use a direct pipe
+static SERVING: AtomicBool = AtomicBool::new(false);
+
+/// Methods that never change the session, so they leave the cached snapshot in place
+fn is_read_only(method: &str) -> bool {
+    matches!(
+        method,
+        methods::PANES_LIST
+            | methods::PANES_HISTORY
+            | methods::TABS_LIST
+            | methods::PANE_HISTORY
+            | methods::MARKS_LIST
+            | methods::TAGS_LIST
+    )
+}
+
+/// Methods the plugin may hold for a long time (`events.poll`). The plugin stops reading a pipe
+/// while it owes that pipe an answer, so these would stall every other call sharing the
+/// daemon's pipe; they get their own.
+fn is_long_poll(method: &str) -> bool {
+    method == methods::EVENTS_POLL
+}
+
+/// Last `panes.list` answer, reused for identical calls within `ttl` while the plugin's state
+/// generation is unchanged
+struct ListCache {
+    ttl: Duration,
+    entry: Option<CachedList>,
+    /// Bumped whenever a mutating call starts or finishes; a list answer is only stored when no
+    /// mutation overlapped it
+    epoch: u64,
+}
+
+#[derive(Clone)]
+struct CachedList {
+    params: serde_json::Value,
+    at: Instant,
+    /// Plugin state generation the list was taken at
+    generation: u64,
+    response: RpcResponse,
+}
+
+impl ListCache {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entry: None,
+            epoch: 0,
+        }
+    }
+
+    /// Snapshot that may answer `requests`, a lone, fresh `panes.list` with the same params. The
+    /// plugin still has to confirm it is current: see [`CachedList::revalidate`].
+    fn lookup(&self, requests: &[RpcRequest], now: Instant) -> Option<CachedList> {
+        let [request] = requests else { return None };
+        let entry = self.entry.as_ref()?;
+        if request.method != methods::PANES_LIST
+            || entry.params != request.params
+            || now.saturating_duration_since(entry.at) >= self.ttl
+        {
+            return None;
+        }
+        Some(entry.clone())
+    }
+
+    /// Called before forwarding; returns the epoch to hand back to [`ListCache::store`]
+    fn begin(&mut self, requests: &[RpcRequest]) -> u64 {
+        if requests
+            .iter()
+            .any(|request| !is_read_only(&request.method))
+        {
+            self.invalidate();
+        }
+        self.epoch
+    }
+
+    /// Called after forwarding with the epoch returned by [`ListCache::begin`]
+    fn store(
+        &mut self,
+        requests: &[RpcRequest],
+        responses: &[RpcResponse],
+        epoch: u64,
+        now: Instant,
+    ) {
+        if requests
+            .iter()
+            .any(|request| !is_read_only(&request.method))
+        {
+            self.invalidate();
+            return;
+        }
+        if self.ttl.is_zero() || epoch != self.epoch {
+            return;
+        }
+        if let ([request], [response]) = (requests, responses) {
+            // Plugins without a generation cannot confirm a snapshot, so it is not kept.
+            let generation = response
+                .result
+                .as_ref()
+                .and_then(|result| result["generation"].as_u64());
+            if let (true, true, Some(generation)) = (
+                request.method == methods::PANES_LIST,
+                response.ok,
+                generation,
+            ) {
+                self.entry = Some(CachedList {
+                    params: request.params.clone(),
+                    at: now,
+                    generation,
+                    response: response.clone(),
+                });
+            }
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.entry = None;
+        self.epoch += 1;
+    }
+
+    /// Age of the held snapshot, even when it is too old to be served
+    fn age(&self, now: Instant) -> Option<Duration> {
+        self.entry
+            .as_ref()
+            .map(|entry| now.saturating_duration_since(entry.at))
+    }
+}
+
+impl CachedList {
+    /// `request` asking the plugin for the list only if its generation moved
+    fn revalidate(&self, request: &RpcRequest) -> RpcRequest {
+        let mut request = request.clone();
+        request.params["if_generation"] = serde_json::json!(self.generation);
+        request
+    }
+
+    /// The snapshot, answered under the request's id, when `response` says nothing changed
+    fn reuse(&self, response: &RpcResponse) -> Option<RpcResponse> {
+        let result = response.result.as_ref()?;
+        if !response.ok
+            || result["unchanged"] != true
+            || result["generation"].as_u64() != Some(self.generation)
+        {
+            return None;
+        }
+        let mut cached = self.response.clone();
+        cached.id = response.id;
+        Some(cached)
+    }
+}
+
+/// Socket of the daemon serving `session` (`<state>/daemon/<session>.sock`)
+pub fn socket_path(session: Option<&str>) -> Option<PathBuf> {
+    let name = client::sanitize_session(session.unwrap_or("default"));
+    paths::state_dir().map(|dir| dir.join("daemon").join(format!("{name}.sock")))
+}
+
+/// Send `requests` through a running daemon for the current session.
+///
+/// `None` means there is no usable daemon (none running, `ZJCTL_NO_DAEMON` set, or it serves
+/// another plugin) and the caller should open its own pipe. With a `timeout`, a plugin that
+/// does not answer in time is a [`ClientError::Timeout`], as over a direct pipe.
+pub fn call(
+    plugin: &str,
+    configuration: &str,
+    requests: &[RpcRequest],
+    timeout: Option<Duration>,
+) -> Option<Result<Vec<RpcResponse>, ClientError>> {
+    if std::env::var_os("ZJCTL_NO_DAEMON").is_some_and(|v| !v.is_empty())
+        || SERVING.load(Ordering::Relaxed)
+        || requests.iter().any(|request| is_long_poll(&request.method))
+    {
+        return None;
+    }
+    let path = socket_path(zellij::session_name().as_deref())?;
+    let budget = timeout.map(|timeout| client::call_budget(requests, timeout));
+    let request = DaemonRequest {
+        plugin: plugin.to_string(),
+        configuration: configuration.to_string(),
+        requests: requests.to_vec(),
+        no_cache: NO_CACHE.load(Ordering::Relaxed),
+        token: auth::client_token(),
+        timeout_ms: budget.map(|budget| (budget.as_millis() as u64).max(1)),
+    };
+    match imp::exchange(&path, &request, budget)? {
+        Ok(DaemonReply::Ok { responses }) => Some(Ok(responses)),
+        Ok(DaemonReply::Mismatch) => None,
+        Ok(DaemonReply::Error { message }) => Some(Err(ClientError::Daemon(message))),
+        Ok(DaemonReply::Timeout) => Some(Err(ClientError::Timeout {
+            timeout: timeout.unwrap_or_default(),
+        })),
+        Ok(
+            DaemonReply::Status { .. }
+            | DaemonReply::History { .. }
+            | DaemonReply::Capture { .. }
+            | DaemonReply::Registered
+            | DaemonReply::Stopping,
+        ) => Some(Err(ClientError::Daemon(
+            "unexpected reply from daemon".to_string(),
+        ))),
+        Err(err) => Some(Err(ClientError::Daemon(err))),
+    }
+}
+
+/// Ask the daemon serving `session` to describe itself; `None` when no daemon is listening
+pub fn status(session: Option<&str>) -> Option<Result<DaemonStatus, String>> {
+    let path = socket_path(session)?;
+    match imp::control(&path, Control::Status, auth::client_token())? {
+        Ok(DaemonReply::Status { daemon }) => Some(Ok(daemon)),
+        Ok(DaemonReply::Error { message }) => Some(Err(message)),
+        Ok(_) => Some(Err("unexpected reply from daemon".to_string())),
+        Err(err) => Some(Err(err)),
+    }
+}
+
+/// Snapshots the daemon serving `session` keeps of `pane`; `None` when no daemon is listening
+pub fn history(session: Option<&str>, pane: &str) -> Option<Result<Vec<Snapshot>, String>> {
+    let path = socket_path(session)?;
+    let request = DaemonHistory {
+        history: pane.to_string(),
+        token: auth::client_token(),
+    };
+    match imp::history(&path, &request)? {
+        Ok(DaemonReply::History { snapshots }) => Some(Ok(snapshots)),
+        Ok(DaemonReply::Error { message }) => Some(Err(message)),
+        Ok(_) => Some(Err("unexpected reply from daemon".to_string())),
+        Err(err) => Some(Err(err)),
+    }
+}
+
+/// Have this session's daemon read a pane the caller may not focus (see [`DaemonCapture`]);
+/// `None` when no daemon is listening
+pub fn capture(selector: &str, full: bool) -> Option<Result<Vec<u8>, String>> {
+    if SERVING.load(Ordering::Relaxed) {
+        return None;
+    }
+    let path = socket_path(zellij::session_name().as_deref())?;
+    let request = DaemonCapture {
+        capture: selector.to_string(),
+        full,
+        token: auth::client_token(),
+    };
+    match imp::capture(&path, &request)? {
+        Ok(DaemonReply::Capture { output }) => Some(Ok(output.0)),
+        Ok(DaemonReply::Error { message }) => Some(Err(message)),
+        Ok(_) => Some(Err("unexpected reply from daemon".to_string())),
+        Err(err) => Some(Err(err)),
+    }
+}
+
+/// Keeps a long-running command listed by `zjctl daemon status` until dropped
+pub struct Registration {
+    _connection: imp::Connection,
+}
+
+/// List this command in the status of the session's daemon while the returned guard lives;
+/// `None` when no daemon is listening (or it refuses the caller's token)
+pub fn register(kind: &str, target: &str) -> Option<Registration> {
+    let path = socket_path(zellij::session_name().as_deref())?;
+    let request = DaemonRegister {
+        register: Worker {
+            kind: kind.to_string(),
+            target: target.to_string(),
+            pid: std::process::id(),
+            running_secs: 0,
+        },
+        token: auth::client_token(),
+    };
+    match imp::register(&path, &request)? {
+        Ok(connection) => Some(Registration {
+            _connection: connection,
+        }),
+        Err(err) => {
+            output::warn(format_args!("not listed by the daemon: {err}"));
+            None
+        }
+    }
+}
+
+/// Ask the daemon serving `session` to exit and wait until its socket is gone; `None` when no
+/// daemon is listening
+pub fn stop(session: Option<&str>) -> Option<Result<(), String>> {
+    let path = socket_path(session)?;
+    match imp::control(&path, Control::Stop, auth::client_token())? {
+        Ok(DaemonReply::Stopping) => {}
+        Ok(DaemonReply::Error { message }) => return Some(Err(message)),
+        Ok(_) => return Some(Err("unexpected reply from daemon".to_string())),
+        Err(err) => return Some(Err(err)),
+    }
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while imp::listening(&path) {
+        if Instant::now() >= deadline {
+            return Some(Err(format!(
+                "daemon is still listening on {}",
+                path.display()
+            )));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    Some(Ok(()))
+}
+
+/// `zjctl daemon` flags
+#[derive(Debug, Clone, Default)]
+pub struct ServeOptions {
+    /// How long `panes.list` answers are reused (zero disables the cache)
+    pub cache_ttl: Duration,
+    /// Serve Prometheus metrics over HTTP on this address
+    pub metrics_listen: Option<SocketAddr>,
+    /// Allow a `metrics_listen` address that is not loopback (`--metrics-allow-remote`)
+    pub metrics_allow_remote: bool,
+    /// Every call has to carry a token from this file with the scope it needs
+    pub auth_tokens: Option<PathBuf>,
+    /// Refuse every call that needs more than the `read` scope
+    pub read_only: bool,
+    /// Keep capture snapshots of some panes (`--capture-history`)
+    pub capture_history: Option<RecorderOptions>,
+}
+
+/// Serve the current session until interrupted
+pub fn serve(
+    plugin: &str,
+    configuration: String,
+    options: ServeOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = socket_path(zellij::session_name().as_deref())
+        .ok_or("unable to determine state directory (set HOME or XDG_STATE_HOME)")?;
+    if let Some(addr) = options.metrics_listen {
+        check_metrics_listen(addr, options.metrics_allow_remote)?;
+    }
+    SERVING.store(true, Ordering::Relaxed);
+    imp::serve(&path, plugin.to_string(), configuration, options)
+}
+
+/// Metrics are plain HTTP, so a Bearer token sent to them is readable on the wire; only
+/// loopback addresses are served unless the caller opted in
+fn check_metrics_listen(addr: SocketAddr, allow_remote: bool) -> Result<(), String> {
+    if addr.ip().is_loopback() || allow_remote {
+        return Ok(());
+    }
+    Err(format!(
+        "--metrics-listen {addr} is not a loopback address; metrics (and any Bearer token sent \
+         to them) travel in cleartext, pass --metrics-allow-remote to serve them anyway"
+    ))
+}
+
+/// Check `token` against the daemon's tokens: `unauthorized` without a known token,
+/// `policy_denied` when its scope is too narrow
+fn authorize(store: &TokenStore, token: Option<&str>, needed: Scope) -> Result<(), RpcError> {
+    let Some(token) = token else {
+        return Err(RpcError::new(
+            RpcErrorCode::Unauthorized,
+            "this daemon requires a token (set ZJCTL_TOKEN)",
+        ));
+    };
+    let entry = store.authenticate(token).ok_or_else(|| {
+        RpcError::new(
+            RpcErrorCode::Unauthorized,
+            "unknown token (check ZJCTL_TOKEN)",
+        )
+    })?;
+    if entry.scope < needed {
+        return Err(RpcError::new(
+            RpcErrorCode::PolicyDenied,
+            format!(
+                "token {} has the {} scope; this call needs {needed}",
+                entry.name, entry.scope
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::collections::{BTreeMap, HashMap};
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{SocketAddr, TcpListener, TcpStream};
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+    use std::process::{Child, ChildStdin, ChildStdout, Stdio};
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+    use std::sync::mpsc::{self, RecvTimeoutError};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use serde::Serialize;
+    use uuid::Uuid;
+    use zjctl_proto::{RpcError, RpcErrorCode, RpcRequest, RpcResponse};
+
+    use super::{
+        authorize, ClientMessage, Control, DaemonCapture, DaemonControl, DaemonHistory,
+        DaemonRegister, DaemonReply, DaemonRequest, DaemonStatus, ListCache, ServeOptions, Worker,
+    };
+    use crate::auth::{Scope, TokenFile};
+    use crate::commands::pane;
+    use crate::history::{self, CaptureHistory, RecorderOptions};
+    use crate::metrics::Metrics;
+    use crate::zellij;
+
+    /// Returns `None` when nothing is listening on `path`; a reply that takes longer than
+    /// `timeout` is [`DaemonReply::Timeout`]
+    pub(super) fn exchange(
+        path: &Path,
+        request: &DaemonRequest,
+        timeout: Option<Duration>,
+    ) -> Option<Result<DaemonReply, String>> {
+        let mut stream = UnixStream::connect(path).ok()?;
+        if stream.set_read_timeout(timeout).is_err() || stream.set_write_timeout(timeout).is_err() {
+            return Some(Err(
+                "failed to set a timeout on the daemon socket".to_string()
+            ));
+        }
+        Some(send_line(&mut stream, request))
+    }
+
+    /// Returns `None` when nothing is listening on `path`
+    pub(super) fn control(
+        path: &Path,
+        control: Control,
+        token: Option<String>,
+    ) -> Option<Result<DaemonReply, String>> {
+        let mut stream = UnixStream::connect(path).ok()?;
+        Some(send_line(&mut stream, &DaemonControl { control, token }))
+    }
+
+    /// Returns `None` when nothing is listening on `path`
+    pub(super) fn history(
+        path: &Path,
+        request: &DaemonHistory,
+    ) -> Option<Result<DaemonReply, String>> {
+        let mut stream = UnixStream::connect(path).ok()?;
+        Some(send_line(&mut stream, request))
+    }
+
+    /// Returns `None` when nothing is listening on `path`
+    pub(super) fn capture(
+        path: &Path,
+        request: &DaemonCapture,
+    ) -> Option<Result<DaemonReply, String>> {
+        let mut stream = UnixStream::connect(path).ok()?;
+        Some(send_line(&mut stream, request))
+    }
+
+    /// Held open by a registered worker; the daemon drops it from its status once this closes
+    pub(super) type Connection = UnixStream;
+
+    /// Returns `None` when nothing is listening on `path`
+    pub(super) fn register(
+        path: &Path,
+        request: &DaemonRegister,
+    ) -> Option<Result<Connection, String>> {
+        let mut stream = UnixStream::connect(path).ok()?;
+        Some(match send_line(&mut stream, request) {
+            Ok(DaemonReply::Registered) => Ok(stream),
+            Ok(DaemonReply::Error { message }) => Err(message),
+            Ok(_) => Err("unexpected reply from daemon".to_string()),
+            Err(err) => Err(err),
+        })
+    }
+
+    pub(super) fn listening(path: &Path) -> bool {
+        UnixStream::connect(path).is_ok()
+    }
+
+    fn send_line(stream: &mut UnixStream, request: &impl Serialize) -> Result<DaemonReply, String> {
+        let timed_out = |err: &std::io::Error| {
+            matches!(
+                err.kind(),
+                std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+            )
+        };
+        let line = serde_json::to_string(request).map_err(|err| err.to_string())?;
+        match writeln!(stream, "{line}") {
+            Ok(()) => {}
+            Err(err) if timed_out(&err) => return Ok(DaemonReply::Timeout),
+            Err(err) => return Err(format!("failed to write to daemon: {err}")),
+        }
+        let mut reply = String::new();
+        match BufReader::new(stream).read_line(&mut reply) {
+            Ok(_) => {}
+            Err(err) if timed_out(&err) => return Ok(DaemonReply::Timeout),
+            Err(err) => return Err(format!("failed to read from daemon: {err}")),
+        }
+        serde_json::from_str(&reply).map_err(|_| "daemon closed the connection".to_string())
+    }
+
+    struct Pipe {
+        child: Child,
+        stdin: ChildStdin,
+        generation: u64,
+    }
+
+    struct Shared {
+        path: PathBuf,
+        plugin: String,
+        configuration: String,
+        started: Instant,
+        /// RPC batches answered
+        served: AtomicU64,
+        /// Connections currently being handled
+        clients: AtomicUsize,
+        pipe: Mutex<Option<Pipe>>,
+        generation: Mutex<u64>,
+        /// Waiting requests: id -> (pipe generation, reply channel)
+        pending: Mutex<HashMap<Uuid, (u64, mpsc::Sender<RpcResponse>)>>,
+        cache: Mutex<ListCache>,
+        metrics: Metrics,
+        metrics_listen: Option<SocketAddr>,
+        auth: Option<Mutex<TokenFile>>,
+        read_only: bool,
+        capture: Option<(RecorderOptions, CaptureHistory)>,
+        /// Registered long-running commands, by registration number, and when they registered
+        workers: Mutex<BTreeMap<u64, (Worker, Instant)>>,
+        next_worker: AtomicU64,
+    }
+
+    pub(super) fn serve(
+        path: &Path,
+        plugin: String,
+        configuration: String,
+        options: ServeOptions,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let ServeOptions {
+            cache_ttl,
+            metrics_listen,
+            auth_tokens,
+            read_only,
+            capture_history,
+            ..
+        } = options;
+        let mut auth = auth_tokens.map(TokenFile::open).transpose()?;
+        if UnixStream::connect(path).is_ok() {
+            return Err(format!("a daemon is already listening on {}", path.display()).into());
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Left over from a daemon that was killed.
+        let _ = std::fs::remove_file(path);
+        let listener = UnixListener::bind(path)
+            .map_err(|err| format!("failed to listen on {}: {err}", path.display()))?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        let metrics_listener = metrics_listen
+            .map(|addr| {
+                TcpListener::bind(addr)
+                    .map_err(|err| format!("failed to listen for metrics on {addr}: {err}"))
+            })
+            .transpose()?;
+        eprintln!("daemon: listening on {} (Ctrl+C to stop)", path.display());
+        if let Some(tokens) = &mut auth {
+            eprintln!(
+                "daemon: token auth on ({} tokens in {})",
+                tokens.current().entries.len(),
+                tokens.path.display()
+            );
+        }
+        if read_only {
+            eprintln!("daemon: read-only (calls that change the session are refused)");
+        }
+        if let Some(options) = &capture_history {
+            eprintln!(
+                "daemon: capture history of {} every {}s, last {} per pane",
+                options.selectors.join(" "),
+                options.interval.as_secs(),
+                options.keep
+            );
+        }
+
+        let shared = Arc::new(Shared {
+            path: path.to_path_buf(),
+            plugin,
+            configuration,
+            started: Instant::now(),
+            served: AtomicU64::new(0),
+            clients: AtomicUsize::new(0),
+            pipe: Mutex::new(None),
+            generation: Mutex::new(0),
+            pending: Mutex::new(HashMap::new()),
+            cache: Mutex::new(ListCache::new(cache_ttl)),
+            metrics: Metrics::default(),
+            metrics_listen,
+            auth: auth.map(Mutex::new),
+            read_only,
+            capture: capture_history.map(|options| {
+                let keep = options.keep;
+                (options, CaptureHistory::new(keep))
+            }),
+            workers: Mutex::new(BTreeMap::new()),
+            next_worker: AtomicU64::new(0),
+        });
+        if shared.capture.is_some() {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || {
+                if let Some((options, history)) = &shared.capture {
+                    history::record_forever(history, &shared.plugin, options);
+                }
+            });
+        }
+        if let Some(listener) = metrics_listener {
+            eprintln!(
+                "daemon: metrics on http://{}/metrics",
+                listener.local_addr()?
+            );
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let Ok(stream) = stream else { continue };
+                    serve_metrics(&shared, stream);
+                }
+            });
+        }
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || handle_client(&shared, stream));
+        }
+        Ok(())
+    }
+
+    fn handle_client(shared: &Arc<Shared>, stream: UnixStream) {
+        shared.clients.fetch_add(1, Ordering::Relaxed);
+        serve_line(shared, stream);
+        shared.clients.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    fn serve_line(shared: &Arc<Shared>, stream: UnixStream) {
+        let mut line = String::new();
+        let Ok(mut writer) = stream.try_clone() else {
+            return;
+        };
+        let mut reader = BufReader::new(stream);
+        if reader.read_line(&mut line).is_err() {
+            return;
+        }
+        let mut stopping = false;
+        let mut registered = None;
+        let reply = match serde_json::from_str::<ClientMessage>(&line) {
+            Ok(ClientMessage::Control(DaemonControl { control, token })) => {
+                let needed = match control {
+                    Control::Status => Scope::Read,
+                    Control::Stop => Scope::Destructive,
+                };
+                match check_token(shared, token.as_deref(), needed) {
+                    Err(error) => DaemonReply::Error {
+                        message: error.message,
+                    },
+                    Ok(()) => match control {
+                        Control::Status => DaemonReply::Status {
+                            daemon: status(shared),
+                        },
+                        Control::Stop => {
+                            stopping = true;
+                            DaemonReply::Stopping
+                        }
+                    },
+                }
+            }
+            Ok(ClientMessage::History(DaemonHistory { history, token })) => {
+                match check_token(shared, token.as_deref(), Scope::Read) {
+                    Err(error) => DaemonReply::Error {
+                        message: error.message,
+                    },
+                    Ok(()) => match &shared.capture {
+                        Some((_, snapshots)) => DaemonReply::History {
+                            snapshots: snapshots.snapshots(&history),
+                        },
+                        None => DaemonReply::Error {
+                            message: "this daemon keeps no capture history (start it with \
+                                      --capture-history SELECTOR)"
+                                .to_string(),
+                        },
+                    },
+                }
+            }
+            Ok(ClientMessage::Capture(DaemonCapture {
+                capture,
+                full,
+                token,
+            })) => match check_token(shared, token.as_deref(), Scope::Read) {
+                Err(error) => DaemonReply::Error {
+                    message: error.message,
+                },
+                // The daemon's own focus-dump-restore, which read callers may not do themselves
+                Ok(()) => match pane::capture_bytes(Some(&shared.plugin), &capture, full) {
+                    Ok(output) => DaemonReply::Capture {
+                        output: output.into(),
+                    },
+                    Err(err) => DaemonReply::Error {
+                        message: err.to_string(),
+                    },
+                },
+            },
+            Ok(ClientMessage::Register(DaemonRegister { register, token })) => {
+                match check_token(shared, token.as_deref(), Scope::Read) {
+                    Err(error) => DaemonReply::Error {
+                        message: error.message,
+                    },
+                    Ok(()) => {
+                        registered = Some(register);
+                        DaemonReply::Registered
+                    }
+                }
+            }
+            // Refused before the plugin check, so a caller can't dodge the token or read-only mode
+            // by asking for a different plugin and falling back to a direct pipe.
+            Ok(ClientMessage::Rpc(request)) => match refusal(shared, &request) {
+                Some(error) => DaemonReply::Ok {
+                    responses: request
+                        .requests
+                        .iter()
+                        .map(|r| RpcResponse::error(r.id, error.clone()))
+                        .collect(),
+                },
+                None if request.plugin != shared.plugin
+                    || request.configuration != shared.configuration =>
+                {
+                    DaemonReply::Mismatch
+                }
+                None => {
+                    shared.served.fetch_add(1, Ordering::Relaxed);
+                    let budget = request.timeout_ms.map(Duration::from_millis);
+                    match cached_forward(shared, &request.requests, request.no_cache, budget) {
+                        Ok(responses) => DaemonReply::Ok { responses },
+                        Err(ForwardError::TimedOut) => DaemonReply::Timeout,
+                        Err(ForwardError::Failed(message)) => DaemonReply::Error { message },
+                    }
+                }
+            },
+            Err(err) => DaemonReply::Error {
+                message: format!("invalid request: {err}"),
+            },
+        };
+        if let Ok(json) = serde_json::to_string(&reply) {
+            let _ = writeln!(writer, "{json}");
+        }
+        if stopping {
+            shutdown(shared);
+        }
+        if let Some(worker) = registered {
+            hold_worker(shared, worker, reader);
+        }
+    }
+
+    /// List `worker` until it closes its connection (it sends nothing more)
+    fn hold_worker(shared: &Shared, worker: Worker, mut reader: BufReader<UnixStream>) {
+        let id = shared.next_worker.fetch_add(1, Ordering::Relaxed);
+        shared
+            .workers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(id, (worker, Instant::now()));
+        // Not a client waiting on the daemon.
+        shared.clients.fetch_sub(1, Ordering::Relaxed);
+        let mut discard = Vec::new();
+        while matches!(reader.read_until(b'\n', &mut discard), Ok(read) if read > 0) {
+            discard.clear();
+        }
+        shared.clients.fetch_add(1, Ordering::Relaxed);
+        shared
+            .workers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&id);
+    }
+
+    /// Why `request` may not run (token, scope, or read-only mode); a batch runs only if every
+    /// call is allowed
+    fn refusal(shared: &Shared, request: &DaemonRequest) -> Option<RpcError> {
+        let needed = request
+            .requests
+            .iter()
+            .map(|r| Scope::for_method(&r.method))
+            .max()
+            .unwrap_or(Scope::Read);
+        if let Err(error) = check_token(shared, request.token.as_deref(), needed) {
+            return Some(error);
+        }
+        let denied = request
+            .requests
+            .iter()
+            .find(|r| Scope::for_method(&r.method) > Scope::Read)
+            .filter(|_| shared.read_only)?;
+        Some(RpcError::new(
+            RpcErrorCode::PolicyDenied,
+            format!("the daemon is read-only; {} is not allowed", denied.method),
+        ))
+    }
+
+    /// [`authorize`] against the daemon's tokens file; everything is allowed without one
+    fn check_token(shared: &Shared, token: Option<&str>, needed: Scope) -> Result<(), RpcError> {
+        let Some(tokens) = &shared.auth else {
+            return Ok(());
+        };
+        let mut tokens = tokens.lock().unwrap_or_else(|e| e.into_inner());
+        authorize(tokens.current(), token, needed)
+    }
+
+    fn status(shared: &Shared) -> DaemonStatus {
+        let now = Instant::now();
+        let capture = shared.capture.as_ref().map(|(options, _)| options);
+        let (cache_ttl, cache_age) = {
+            let cache = shared.cache.lock().unwrap_or_else(|e| e.into_inner());
+            (cache.ttl, cache.age(now))
+        };
+        let pipe_running = shared
+            .pipe
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_mut()
+            .is_some_and(|p| matches!(p.child.try_wait(), Ok(None)));
+        DaemonStatus {
+            pid: std::process::id(),
+            plugin: shared.plugin.clone(),
+            uptime_secs: now.duration_since(shared.started).as_secs(),
+            cache_ttl_ms: cache_ttl.as_millis() as u64,
+            cache_age_ms: cache_age.map(|age| age.as_millis() as u64),
+            requests: shared.served.load(Ordering::Relaxed),
+            // Not counting the connection asking.
+            clients: shared.clients.load(Ordering::Relaxed).saturating_sub(1),
+            pending: shared
+                .pending
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .len(),
+            pipe_running,
+            pipes_spawned: *shared.generation.lock().unwrap_or_else(|e| e.into_inner()),
+            metrics_listen: shared.metrics_listen.map(|addr| addr.to_string()),
+            auth_tokens: shared.auth.as_ref().map(|tokens| {
+                let tokens = tokens.lock().unwrap_or_else(|e| e.into_inner());
+                tokens.path.display().to_string()
+            }),
+            read_only: shared.read_only,
+            capture_history: capture.map(|o| o.selectors.clone()).unwrap_or_default(),
+            capture_interval_secs: capture.map_or(0, |o| o.interval.as_secs()),
+            capture_keep: capture.map_or(0, |o| o.keep),
+            workers: shared
+                .workers
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .values()
+                .map(|(worker, since)| Worker {
+                    running_secs: now.duration_since(*since).as_secs(),
+                    ..worker.clone()
+                })
+                .collect(),
+        }
+    }
+
+    /// One HTTP/1.0-style exchange: `GET /metrics` gets the exposition, anything else a 404
+    fn serve_metrics(shared: &Shared, stream: TcpStream) {
+        let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+        let Ok(mut writer) = stream.try_clone() else {
+            return;
+        };
+        let mut reader = BufReader::new(stream);
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).is_err() {
+            return;
+        }
+        // The request has no body we care about; only `Authorization` is read from the headers.
+        let (mut header, mut bearer) = (String::new(), None);
+        while reader.read_line(&mut header).is_ok_and(|read| read > 2) {
+            if let Some((name, value)) = header.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("authorization") {
+                    bearer = value.trim().strip_prefix("Bearer ").map(str::to_string);
+                }
+            }
+            header.clear();
+        }
+        let mut parts = request_line.split_whitespace();
+        let (method, target) = (parts.next(), parts.next().unwrap_or_default());
+        let path = target.split('?').next().unwrap_or_default();
+        let denied = check_token(shared, bearer.as_deref(), Scope::Read).err();
+        let (status, content_type, body) = match (method, path) {
+            (Some("GET"), "/metrics") if denied.is_some() => (
+                "401 Unauthorized",
+                "text/plain; charset=utf-8",
+                format!(
+                    "{}\n",
+                    denied.map(|error| error.message).unwrap_or_default()
+                ),
+            ),
+            (Some("GET"), "/metrics") => (
+                "200 OK",
+                "text/plain; version=0.0.4; charset=utf-8",
+                shared.metrics.render(&status(shared)),
+            ),
+            _ => (
+                "404 Not Found",
+                "text/plain; charset=utf-8",
+                "not found (try /metrics)\n".to_string(),
+            ),
+        };
+        let _ = write!(
+            writer,
+            "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+    }
+
+    /// Stop listening, end the pipe, and exit; clients still waiting see a closed connection
+    fn shutdown(shared: &Shared) -> ! {
+        let _ = std::fs::remove_file(&shared.path);
+        if let Some(mut pipe) = shared.pipe.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            let _ = pipe.child.kill();
+            let _ = pipe.child.wait();
+        }
+        eprintln!("daemon: stopped");
+        std::process::exit(0);
+    }
+
+    /// [`forward`], answering fresh `panes.list` repeats from the cache once the plugin confirms
+    /// nothing changed; forwarded batches are recorded in the metrics
+    fn cached_forward(
+        shared: &Arc<Shared>,
+        requests: &[RpcRequest],
+        no_cache: bool,
+        budget: Option<Duration>,
+    ) -> Result<Vec<RpcResponse>, ForwardError> {
+        let (epoch, snapshot) = {
+            let mut cache = shared.cache.lock().unwrap_or_else(|e| e.into_inner());
+            let snapshot = if no_cache {
+                None
+            } else {
+                cache.lookup(requests, Instant::now())
+            };
+            (cache.begin(requests), snapshot)
+        };
+        let started = Instant::now();
+        let result = match &snapshot {
+            Some(snapshot) => forward(shared, &[snapshot.revalidate(&requests[0])], budget),
+            None => forward(shared, requests, budget),
+        };
+        shared.metrics.record(
+            requests,
+            result.as_deref().map_err(|_| ()),
+            started.elapsed(),
+        );
+        if let (Some(snapshot), Ok([response])) = (&snapshot, result.as_deref()) {
+            if let Some(cached) = snapshot.reuse(response) {
+                shared.metrics.cache_hit();
+                return Ok(vec![cached]);
+            }
+        }
+        let mut cache = shared.cache.lock().unwrap_or_else(|e| e.into_inner());
+        match &result {
+            Ok(responses) => cache.store(requests, responses, epoch, Instant::now()),
+            // The plugin may have acted on part of the batch.
+            Err(_) => cache.invalidate(),
+        }
+        result
+    }
+
+    /// Why [`forward`] gave up
+    enum ForwardError {
+        /// No answer within the caller's budget
+        TimedOut,
+        Failed(String),
+    }
+
+    impl From<String> for ForwardError {
+        fn from(message: String) -> Self {
+            ForwardError::Failed(message)
+        }
+    }
+
+    /// Send `requests` down the shared pipe and wait for their answers, for at most `budget`
+    fn forward(
+        shared: &Arc<Shared>,
+        requests: &[RpcRequest],
+        budget: Option<Duration>,
+    ) -> Result<Vec<RpcResponse>, ForwardError> {
+        if requests.is_empty() {
+            return Ok(Vec::new());
+        }
+        let line = match requests {
+            [request] => serde_json::to_string(request),
+            _ => serde_json::to_string(requests),
+        }
+        .map_err(|err| err.to_string())?;
+
+        let (tx, rx) = mpsc::channel();
+        {
+            let mut pipe = shared.pipe.lock().unwrap_or_else(|e| e.into_inner());
+            let mut attempts = 0;
+            loop {
+                attempts += 1;
+                let alive = pipe
+                    .as_mut()
+                    .is_some_and(|p| matches!(p.child.try_wait(), Ok(None)));
+                if !alive {
+                    *pipe = Some(spawn_pipe(shared)?);
+                }
+                let current = pipe.as_mut().expect("pipe was just spawned");
+                {
+                    let mut pending = shared.pending.lock().unwrap_or_else(|e| e.into_inner());
+                    for request in requests {
+                        pending.insert(request.id, (current.generation, tx.clone()));
+                    }
+                }
+                match writeln!(current.stdin, "{line}").and_then(|()| current.stdin.flush()) {
+                    Ok(()) => break,
+                    // The pipe died between the liveness check and the write: respawn once.
+                    Err(_) if attempts < 2 => *pipe = None,
+                    Err(err) => {
+                        forget(shared, requests);
+                        return Err(format!("failed to write to zellij pipe: {err}").into());
+                    }
+                }
+            }
+        }
+        drop(tx);
+
+        let deadline = budget.map(|budget| Instant::now() + budget);
+        let mut received = Vec::with_capacity(requests.len());
+        while received.len() < requests.len() {
+            let response = match deadline {
+                Some(deadline) => {
+                    rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                }
+                None => rx.recv().map_err(RecvTimeoutError::from),
+            };
+            match response {
+                Ok(response) => received.push(response),
+                Err(RecvTimeoutError::Timeout) => {
+                    forget(shared, requests);
+                    return Err(ForwardError::TimedOut);
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    forget(shared, requests);
+                    return Err(
+                        "zellij pipe exited before the plugin answered (is the zrpc plugin loaded?)"
+                            .to_string()
+                            .into(),
+                    );
+                }
+            }
+        }
+        Ok(requests
+            .iter()
+            .filter_map(|request| {
+                let index = received.iter().position(|resp| resp.id == request.id)?;
+                Some(received.swap_remove(index))
+            })
+            .collect())
+    }
+
+    fn forget(shared: &Shared, requests: &[RpcRequest]) {
+        let mut pending = shared.pending.lock().unwrap_or_else(|e| e.into_inner());
+        for request in requests {
+            pending.remove(&request.id);
+        }
+    }
+
+    fn spawn_pipe(shared: &Arc<Shared>) -> Result<Pipe, String> {
+        let mut child = zellij::command()
+            .args([
+                "pipe",
+                "--plugin",
+                shared.plugin.as_str(),
+                "--plugin-configuration",
+                shared.configuration.as_str(),
+                "--name",
+                "zjctl-rpc",
+            ])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|err| format!("failed to spawn zellij pipe: {err}"))?;
+        let stdin = child.stdin.take().ok_or("zellij pipe has no stdin")?;
+        let stdout = child.stdout.take().ok_or("zellij pipe has no stdout")?;
+
+        let generation = {
+            let mut generation = shared.generation.lock().unwrap_or_else(|e| e.into_inner());
+            *generation += 1;
+            *generation
+        };
+        let reader_shared = Arc::clone(shared);
+        thread::spawn(move || read_responses(&reader_shared, stdout, generation));
+        Ok(Pipe {
+            child,
+            stdin,
+            generation,
+        })
+    }
+
+    /// Route responses to their waiting clients; when the pipe closes, drop the senders of
+    /// requests still waiting on it so they fail instead of hanging.
+    fn read_responses(shared: &Shared, stdout: ChildStdout, generation: u64) {
+        let reader = BufReader::new(stdout);
+        for line in reader.lines() {
+            let Ok(line) = line else { break };
+            let Ok(response) = serde_json::from_str::<RpcResponse>(&line) else {
+                continue;
+            };
+            let waiter = shared
+                .pending
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(&response.id);
+            if let Some((_, tx)) = waiter {
+                let _ = tx.send(response);
+            }
+        }
+        shared
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|_, (pipe, _)| *pipe != generation);
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use std::path::Path;
+    use std::time::Duration;
+
+    use super::{
+        Control, DaemonCapture, DaemonHistory, DaemonRegister, DaemonReply, DaemonRequest,
+        ServeOptions,
+    };
+
+    pub(super) fn exchange(
+        _path: &Path,
+        _request: &DaemonRequest,
+        _timeout: Option<Duration>,
+    ) -> Option<Result<DaemonReply, String>> {
+        None
+    }
+
+    pub(super) fn control(
+        _path: &Path,
+        _control: Control,
+        _token: Option<String>,
+    ) -> Option<Result<DaemonReply, String>> {
+        None
+    }
+
+    pub(super) fn history(
+        _path: &Path,
+        _request: &DaemonHistory,
+    ) -> Option<Result<DaemonReply, String>> {
+        None
+    }
+
+    pub(super) fn capture(
+        _path: &Path,
+        _request: &DaemonCapture,
+    ) -> Option<Result<DaemonReply, String>> {
+        None
+    }
+
+    pub(super) type Connection = ();
+
+    pub(super) fn register(
+        _path: &Path,
+        _request: &DaemonRegister,
+    ) -> Option<Result<Connection, String>> {
+        None
+    }
+
+    pub(super) fn listening(_path: &Path) -> bool {
+        false
+    }
+
+    pub(super) fn serve(
+        _path: &Path,
+        _plugin: String,
+        _configuration: String,
+        _options: ServeOptions,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        Err(
+            "zjctl daemon requires Unix domain sockets and is not available on this platform"
+                .into(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn socket_path_uses_sanitized_session() {
+        let path = socket_path(Some("my session/1")).unwrap();
+        assert!(path.ends_with("daemon/my_session_1.sock"));
+        let path = socket_path(None).unwrap();
+        assert!(path.ends_with("daemon/default.sock"));
+    }
+
+    #[test]
+    fn remote_metrics_need_an_opt_in() {
+        let local: SocketAddr = "127.0.0.1:9920".parse().unwrap();
+        let remote: SocketAddr = "0.0.0.0:9920".parse().unwrap();
+        assert!(check_metrics_listen(local, false).is_ok());
+        assert!(check_metrics_listen("[::1]:9920".parse().unwrap(), false).is_ok());
+        let err = check_metrics_listen(remote, false).unwrap_err();
+        assert!(err.contains("--metrics-allow-remote"));
+        assert!(check_metrics_listen(remote, true).is_ok());
+    }
+
+    fn ok_response(request: &RpcRequest, result: serde_json::Value) -> RpcResponse {
+        RpcResponse::success(request.id, result).unwrap()
+    }
+
+    #[test]
+    fn list_cache_reuses_fresh_snapshot_with_new_id() {
+        let mut cache = ListCache::new(Duration::from_millis(500));
+        let start = Instant::now();
+        let first = RpcRequest::new(methods::PANES_LIST);
+        let epoch = cache.begin(std::slice::from_ref(&first));
+        let response = ok_response(
+            &first,
+            serde_json::json!({"generation": 4, "panes": [{"id": "terminal:1"}]}),
+        );
+        cache.store(
+            std::slice::from_ref(&first),
+            std::slice::from_ref(&response),
+            epoch,
+            start,
+        );
+
+        let again = RpcRequest::new(methods::PANES_LIST);
+        let snapshot = cache
+            .lookup(
+                std::slice::from_ref(&again),
+                start + Duration::from_millis(100),
+            )
+            .unwrap();
+        let revalidate = snapshot.revalidate(&again);
+        assert_eq!(revalidate.id, again.id);
+        assert_eq!(revalidate.params["if_generation"], 4);
+        let unchanged = ok_response(
+            &again,
+            serde_json::json!({"generation": 4, "unchanged": true}),
+        );
+        let hit = snapshot.reuse(&unchanged).unwrap();
+        assert_eq!(hit.id, again.id);
+        assert_eq!(hit.result, response.result);
+
+        assert!(cache
+            .lookup(
+                std::slice::from_ref(&again),
+                start + Duration::from_millis(500)
+            )
+            .is_none());
+        let filtered = RpcRequest::new(methods::PANES_LIST)
+            .with_params(serde_json::json!({"tab": "x"}))
+            .unwrap();
+        assert!(cache
+            .lookup(std::slice::from_ref(&filtered), start)
+            .is_none());
+    }
+
+    #[test]
+    fn list_cache_serves_nothing_the_plugin_does_not_confirm() {
+        let mut cache = ListCache::new(Duration::from_secs(5));
+        let now = Instant::now();
+        let list = RpcRequest::new(methods::PANES_LIST);
+        let taken = ok_response(&list, serde_json::json!({"generation": 4, "panes": []}));
+        let epoch = cache.begin(std::slice::from_ref(&list));
+        cache.store(
+            std::slice::from_ref(&list),
+            std::slice::from_ref(&taken),
+            epoch,
+            now,
+        );
+        let snapshot = cache.lookup(std::slice::from_ref(&list), now).unwrap();
+
+        // A pane opened outside the daemon moved the generation: the fresh list is used.
+        let changed = ok_response(
+            &list,
+            serde_json::json!({"generation": 5, "panes": [{"id": "terminal:2"}]}),
+        );
+        assert!(snapshot.reuse(&changed).is_none());
+
+        // Plugins without generations answer with a plain list, which is never kept.
+        let mut cache = ListCache::new(Duration::from_secs(5));
+        let epoch = cache.begin(std::slice::from_ref(&list));
+        let old = ok_response(&list, serde_json::json!([]));
+        cache.store(
+            std::slice::from_ref(&list),
+            std::slice::from_ref(&old),
+            epoch,
+            now,
+        );
+        assert!(cache.lookup(std::slice::from_ref(&list), now).is_none());
+    }
+
+    #[test]
+    fn list_cache_drops_snapshot_on_mutation() {
+        let mut cache = ListCache::new(Duration::from_secs(5));
+        let now = Instant::now();
+        let list = RpcRequest::new(methods::PANES_LIST);
+        let response = ok_response(&list, serde_json::json!({"generation": 1, "panes": []}));
+        let epoch = cache.begin(std::slice::from_ref(&list));
+
+        // A close that started while the list was in flight keeps it out of the cache.
+        let close = RpcRequest::new(methods::PANE_CLOSE);
+        cache.begin(std::slice::from_ref(&close));
+        cache.store(
+            std::slice::from_ref(&list),
+            std::slice::from_ref(&response),
+            epoch,
+            now,
+        );
+        assert!(cache.lookup(std::slice::from_ref(&list), now).is_none());
+
+        let epoch = cache.begin(std::slice::from_ref(&list));
+        cache.store(
+            std::slice::from_ref(&list),
+            std::slice::from_ref(&response),
+            epoch,
+            now,
+        );
+        assert!(cache.lookup(std::slice::from_ref(&list), now).is_some());
+        cache.begin(std::slice::from_ref(&close));
+        assert!(cache.lookup(std::slice::from_ref(&list), now).is_none());
+    }
+
+    #[test]
+    fn client_message_tells_control_from_rpc() {
+        let message: ClientMessage = serde_json::from_str(r#"{"control":"stop"}"#).unwrap();
+        assert!(matches!(
+            message,
+            ClientMessage::Control(DaemonControl {
+                control: Control::Stop,
+                ..
+            })
+        ));
+        let line = serde_json::to_string(&DaemonRequest {
+            plugin: "file:/zrpc.wasm".to_string(),
+            configuration: String::new(),
+            requests: Vec::new(),
+            no_cache: false,
+            token: None,
+            timeout_ms: None,
+        })
+        .unwrap();
+        let message: ClientMessage = serde_json::from_str(&line).unwrap();
+        assert!(
+            matches!(message, ClientMessage::Rpc(request) if request.plugin == "file:/zrpc.wasm")
+        );
+        let message: ClientMessage =
+            serde_json::from_str(r#"{"capture":"id:terminal:3","full":true}"#).unwrap();
+        assert!(
+            matches!(message, ClientMessage::Capture(DaemonCapture { capture, full: true, .. }) if capture == "id:terminal:3")
+        );
+        let message: ClientMessage = serde_json::from_str(
+            r#"{"register":{"kind":"watch","target":"id:terminal:3","pid":42}}"#,
+        )
+        .unwrap();
+        assert!(
+            matches!(message, ClientMessage::Register(DaemonRegister { register, .. }) if register.kind == "watch" && register.pid == 42)
+        );
+        let message: ClientMessage = serde_json::from_str(r#"{"history":"terminal:3"}"#).unwrap();
+        assert!(
+            matches!(message, ClientMessage::History(DaemonHistory { history, .. }) if history == "terminal:3")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn silent_daemon_times_out_with_the_callers_budget() {
+        use std::io::{BufRead, BufReader};
+        use std::os::unix::net::UnixListener;
+
+        let path = std::env::temp_dir().join(format!("zjctl-daemon-{}.sock", uuid::Uuid::new_v4()));
+        let listener = UnixListener::bind(&path).unwrap();
+        // Reads the request, then never answers.
+        let daemon = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut line = String::new();
+            BufReader::new(&stream).read_line(&mut line).unwrap();
+            let request: DaemonRequest = serde_json::from_str(&line).unwrap();
+            std::thread::sleep(Duration::from_millis(500));
+            request.timeout_ms
+        });
+        let request = DaemonRequest {
+            plugin: "file:/zrpc.wasm".to_string(),
+            configuration: String::new(),
+            requests: vec![RpcRequest::new(methods::PANES_LIST)],
+            no_cache: false,
+            token: None,
+            timeout_ms: Some(100),
+        };
+        let started = Instant::now();
+        let reply = imp::exchange(&path, &request, Some(Duration::from_millis(100)));
+        let elapsed = started.elapsed();
+        let _ = std::fs::remove_file(&path);
+        assert!(matches!(reply, Some(Ok(DaemonReply::Timeout))));
+        assert!(elapsed < Duration::from_millis(400));
+        assert_eq!(daemon.join().unwrap(), Some(100));
+    }
+
+    #[test]
+    fn reply_round_trips_with_status_tag() {
+        let json = serde_json::to_string(&DaemonReply::Mismatch).unwrap();
+        assert_eq!(json, r#"{"status":"mismatch"}"#);
+        let reply: DaemonReply =
+            serde_json::from_str(r#"{"status":"error","message":"boom"}"#).unwrap();
+        assert!(matches!(reply, DaemonReply::Error { message } if message == "boom"));
+    }
+}
@@ -0,0 +1,294 @@
+//! `--output table|json|yaml|tsv` for listing commands (`panes ls`, `status`, `doctor`,
+//! `tab ls`, `group ls`, `tag ls`)
+//!
+//! JSON and YAML render the same document, so a field added to one shows up in the other.
+//! TSV prints one record per line without a header, with tabs, newlines, and backslashes in
+//! values escaped (`\t`, `\n`, `\\`) and empty fields for missing values, so `cut -f` and
+//! `awk -F'\t'` can rely on the column order each command documents. `--json` stays as
+//! shorthand for `--output json`.
+
+use std::fmt::Write as _;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Aligned columns for people
+    #[default]
+    Table,
+    Json,
+    Yaml,
+    /// Tab-separated values, one record per line, no header
+    Tsv,
+}
+
+/// `--output FORMAT` and `--json`
+#[derive(Debug, Clone, Copy, Default, clap::Args)]
+pub struct OutputArgs {
+    /// Output format
+    #[arg(
+        long,
+        short = 'o',
+        value_enum,
+        value_name = "FORMAT",
+        env = "ZJCTL_OUTPUT"
+    )]
+    pub output: Option<OutputFormat>,
+    /// Output as JSON (same as --output json)
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl OutputArgs {
+    pub fn format(self) -> OutputFormat {
+        if self.json {
+            OutputFormat::Json
+        } else {
+            self.output.unwrap_or_default()
+        }
+    }
+}
+
+/// Print `value` as pretty JSON or YAML; `false` for the formats the caller renders itself
+pub fn print_document<T: Serialize>(
+    format: OutputFormat,
+    value: &T,
+) -> Result<bool, serde_json::Error> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value)?),
+        OutputFormat::Yaml => print!("{}", yaml(value)?),
+        OutputFormat::Table | OutputFormat::Tsv => return Ok(false),
+    }
+    Ok(true)
+}
+
+/// One TSV record
+pub fn tsv_row<I, S>(fields: I) -> String
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    fields
+        .into_iter()
+        .map(|field| {
+            field
+                .as_ref()
+                .replace('\\', r"\\")
+                .replace('\t', r"\t")
+                .replace('\n', r"\n")
+                .replace('\r', r"\r")
+        })
+        .collect::<Vec<_>>()
+        .join("\t")
+}
+
+/// A TSV field for an optional value: empty when missing
+pub fn tsv_opt<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// Block-style YAML for `value`, keeping the field order of its JSON form
+pub fn yaml<T: Serialize + ?Sized>(value: &T) -> Result<String, serde_json::Error> {
+    let doc: Doc = serde_json::from_str(&serde_json::to_string(value)?)?;
+    let mut out = String::new();
+    match &doc {
+        Doc::Map(entries) if !entries.is_empty() => yaml_map(&mut out, entries, 0),
+        Doc::Seq(items) if !items.is_empty() => yaml_seq(&mut out, items, 0),
+        scalar => {
+            out.push_str(&yaml_scalar(scalar));
+            out.push('\n');
+        }
+    }
+    Ok(out)
+}
+
+/// A JSON document whose objects keep their key order (`serde_json::Value` sorts them)
+enum Doc {
+    Scalar(serde_json::Value),
+    Seq(Vec<Doc>),
+    Map(Vec<(String, Doc)>),
+}
+
+impl<'de> serde::Deserialize<'de> for Doc {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(DocVisitor)
+    }
+}
+
+struct DocVisitor;
+
+impl<'de> serde::de::Visitor<'de> for DocVisitor {
+    type Value = Doc;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_unit<E>(self) -> Result<Doc, E> {
+        Ok(Doc::Scalar(serde_json::Value::Null))
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<Doc, E> {
+        Ok(Doc::Scalar(b.into()))
+    }
+
+    fn visit_i64<E>(self, n: i64) -> Result<Doc, E> {
+        Ok(Doc::Scalar(n.into()))
+    }
+
+    fn visit_u64<E>(self, n: u64) -> Result<Doc, E> {
+        Ok(Doc::Scalar(n.into()))
+    }
+
+    fn visit_f64<E>(self, n: f64) -> Result<Doc, E> {
+        Ok(Doc::Scalar(n.into()))
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Doc, E> {
+        Ok(Doc::Scalar(s.into()))
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Doc, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Doc::Seq(items))
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<Doc, A::Error> {
+        let mut entries = Vec::new();
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(Doc::Map(entries))
+    }
+}
+
+fn yaml_map(out: &mut String, entries: &[(String, Doc)], indent: usize) {
+    for (key, value) in entries {
+        let _ = write!(out, "{:indent$}{}:", "", yaml_key(key));
+        yaml_nested(out, value, indent);
+    }
+}
+
+fn yaml_seq(out: &mut String, items: &[Doc], indent: usize) {
+    for item in items {
+        let _ = write!(out, "{:indent$}-", "");
+        match item {
+            // A mapping starts on the dash's line: `- id: terminal:1`
+            Doc::Map(entries) if !entries.is_empty() => {
+                let mut nested = String::new();
+                yaml_map(&mut nested, entries, indent + 2);
+                out.push(' ');
+                out.push_str(&nested[indent + 2..]);
+            }
+            _ => yaml_nested(out, item, indent),
+        }
+    }
+}
+
+/// The value after `key:` or `-`: inline for scalars and empty collections, indented below
+/// otherwise
+fn yaml_nested(out: &mut String, value: &Doc, indent: usize) {
+    match value {
+        Doc::Map(entries) if !entries.is_empty() => {
+            out.push('\n');
+            yaml_map(out, entries, indent + 2);
+        }
+        Doc::Seq(items) if !items.is_empty() => {
+            out.push('\n');
+            yaml_seq(out, items, indent + 2);
+        }
+        scalar => {
+            let _ = writeln!(out, " {}", yaml_scalar(scalar));
+        }
+    }
+}
+
+fn yaml_key(key: &str) -> String {
+    if plain(key) {
+        key.to_string()
+    } else {
+        quote(key)
+    }
+}
+
+fn yaml_scalar(value: &Doc) -> String {
+    match value {
+        Doc::Scalar(serde_json::Value::String(s)) if plain(s) => s.clone(),
+        Doc::Scalar(serde_json::Value::String(s)) => quote(s),
+        Doc::Scalar(scalar) => scalar.to_string(),
+        Doc::Seq(_) => "[]".to_string(),
+        Doc::Map(_) => "{}".to_string(),
+    }
+}
+
+/// Strings that read back as the same string without quotes
+fn plain(s: &str) -> bool {
+    let reserved = matches!(
+        s.to_ascii_lowercase().as_str(),
+        "" | "null" | "~" | "true" | "false" | "yes" | "no" | "on" | "off"
+    );
+    let numeric = s.parse::<f64>().is_ok()
+        || s.starts_with(|c: char| c.is_ascii_digit() || c == '.' || c == '+');
+    !reserved
+        && !numeric
+        && !s.starts_with(|c: char| c.is_whitespace() || "-?:,[]{}#&*!|>'\"%@`".contains(c))
+        && !s.ends_with(char::is_whitespace)
+        && !s.contains(": ")
+        && !s.contains(" #")
+        && !s.ends_with(':')
+        && !s.chars().any(char::is_control)
+}
+
+/// JSON string syntax is valid double-quoted YAML
+fn quote(s: &str) -> String {
+    serde_json::Value::String(s.to_string()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn yaml_and_tsv_render_nested_documents_and_awkward_values() {
+        #[derive(Serialize)]
+        struct Status {
+            session: &'static str,
+            generation: u64,
+            focused: Option<String>,
+            tabs: serde_json::Value,
+            hints: [&'static str; 2],
+        }
+        let doc = Status {
+            session: "main",
+            generation: 7,
+            focused: None,
+            tabs: serde_json::json!([{"index": 0, "name": "Tab #1", "tags": []}, {"name": "2"}]),
+            hints: ["a: b", "true"],
+        };
+        assert_eq!(
+            yaml(&doc).unwrap(),
+            "session: main\ngeneration: 7\nfocused: null\n\
+             tabs:\n  - index: 0\n    name: \"Tab #1\"\n    tags: []\n  - name: \"2\"\n\
+             hints:\n  - \"a: b\"\n  - \"true\"\n"
+        );
+        assert_eq!(yaml(&serde_json::json!([])).unwrap(), "[]\n");
+
+        assert_eq!(
+            tsv_row(["terminal:1", "make\ttest", "a\\b\n", ""]),
+            "terminal:1\tmake\\ttest\ta\\\\b\\n\t"
+        );
+        assert_eq!(tsv_opt(Some(3)), "3");
+        assert_eq!(tsv_opt(None::<i32>), "");
+
+        let args = OutputArgs {
+            output: Some(OutputFormat::Tsv),
+            json: true,
+        };
+        assert_eq!(args.format(), OutputFormat::Json);
+        assert_eq!(OutputArgs::default().format(), OutputFormat::Table);
+    }
+}
@@ -0,0 +1,172 @@
+//! Saved pane groups (`zjctl group`)
+//!
+//! A group is a named selector, usually a list of alternatives such as
+//! `cmd:uvicorn,cmd:redis`. Groups are kept in `~/.config/zjctl/groups`, one `name = selector`
+//! per line, so they outlive sessions and are shared by every zjctl process. `group:NAME`
+//! works wherever a selector is accepted: the client expands it before a request leaves the
+//! CLI, so neither the daemon nor the plugin has to know about groups.
+
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+use zjctl_proto::{expand_groups, RpcError, RpcErrorCode, RpcRequest};
+
+use crate::paths;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Group {
+    pub name: String,
+    pub selector: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GroupStore {
+    pub groups: Vec<Group>,
+}
+
+impl GroupStore {
+    /// A missing file is an empty store
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => {
+                Self::parse(&contents).map_err(|err| format!("{}: {err}", path.display()))
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(format!("failed to read {}: {err}", path.display())),
+        }
+    }
+
+    /// `name = selector` lines; blank lines and lines starting with `#` are skipped
+    fn parse(contents: &str) -> Result<Self, String> {
+        let groups = contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(|(i, line)| {
+                let expected = || format!("line {}: expected `name = selector`", i + 1);
+                let (name, selector) = line.split_once('=').ok_or_else(expected)?;
+                let (name, selector) = (name.trim(), selector.trim());
+                if name.is_empty() || selector.is_empty() {
+                    return Err(expected());
+                }
+                Ok(Group {
+                    name: name.to_string(),
+                    selector: selector.to_string(),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { groups })
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::from("# zjctl pane groups: name = selector\n");
+        for group in &self.groups {
+            out.push_str(&format!("{} = {}\n", group.name, group.selector));
+        }
+        out
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|err| format!("failed to create {}: {err}", parent.display()))?;
+        }
+        std::fs::write(path, self.render())
+            .map_err(|err| format!("failed to write {}: {err}", path.display()))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Group> {
+        self.groups.iter().find(|group| group.name == name)
+    }
+
+    /// Save `selector` under `name`, replacing a group of that name
+    pub fn set(&mut self, name: &str, selector: &str) {
+        let group = Group {
+            name: name.to_string(),
+            selector: selector.to_string(),
+        };
+        match self.groups.iter_mut().find(|group| group.name == name) {
+            Some(existing) => *existing = group,
+            None => self.groups.push(group),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.groups.len();
+        self.groups.retain(|group| group.name != name);
+        self.groups.len() != before
+    }
+
+    /// `selector` with its `group:NAME` predicates replaced by the saved selectors
+    pub fn expand(&self, selector: &str) -> Result<String, String> {
+        let lookup = |name: &str| self.get(name).map(|group| group.selector.clone());
+        expand_groups(selector, &lookup).map_err(|err| err.to_string())
+    }
+}
+
+/// `~/.config/zjctl/groups`
+pub fn groups_path() -> Option<PathBuf> {
+    paths::config_dir().map(|dir| dir.join("zjctl").join("groups"))
+}
+
+/// Group names end up in selectors, so they stay within `[A-Za-z0-9_.-]`
+pub fn validate_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "invalid group name: {name:?} (use letters, digits, '_', '-', '.')"
+        ))
+    }
+}
+
+/// `requests` with `group:` selectors expanded; borrowed when none uses a group
+pub fn expand_requests(requests: &[RpcRequest]) -> Result<Cow<'_, [RpcRequest]>, RpcError> {
+    let uses_group = |request: &RpcRequest| {
+        request.params["selector"]
+            .as_str()
+            .is_some_and(|selector| selector.contains("group:"))
+    };
+    if !requests.iter().any(uses_group) {
+        return Ok(Cow::Borrowed(requests));
+    }
+    let invalid = |message: String| RpcError::new(RpcErrorCode::InvalidParams, message);
+    let path = groups_path().ok_or_else(|| invalid("no config directory for groups".into()))?;
+    let store = GroupStore::load(&path).map_err(invalid)?;
+
+    let mut expanded = requests.to_vec();
+    for request in expanded.iter_mut().filter(|request| uses_group(request)) {
+        let selector = request.params["selector"].as_str().unwrap_or_default();
+        let selector = store
+            .expand(selector)
+            .map_err(|err| invalid(err).with_hint("list saved groups with: zjctl group ls"))?;
+        request.params["selector"] = serde_json::Value::String(selector);
+    }
+    Ok(Cow::Owned(expanded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_round_trip_and_expand() {
+        let mut store = GroupStore::default();
+        store.set("backend", "cmd:uvicorn,cmd:redis");
+        store.set("backend", "cmd:uvicorn,cmd:redis,cmd:celery");
+        let loaded = GroupStore::parse(&store.render()).unwrap();
+        assert_eq!(loaded.groups.len(), 1);
+        assert_eq!(
+            loaded.expand("group:backend+tab:1").unwrap(),
+            "cmd:uvicorn+tab:1,cmd:redis+tab:1,cmd:celery+tab:1"
+        );
+        assert!(loaded.expand("group:frontend").is_err());
+        assert!(GroupStore::parse("backend cmd:x").is_err());
+        assert!(validate_name("api-v2").is_ok());
+        assert!(validate_name("a b").is_err());
+    }
+}
@@ -0,0 +1,183 @@
+//! Capture history for `zjctl daemon --capture-history SELECTOR`
+//!
+//! The daemon captures the viewport of every pane matching the selectors once per interval
+//! and keeps the last few snapshots of each pane in memory, skipping snapshots identical to
+//! the one before. `pane capture --at "2m ago"` then asks it for the newest snapshot taken at
+//! or before that time, and `--history-list` for what it holds. Panes other than the focused
+//! one are briefly focused to be captured, like `pane capture` does, so pick a selector and
+//! interval that leave room to work; a read-only daemon only records focused panes. Snapshots
+//! live in the daemon's memory and are gone when it stops.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use zjctl_proto::CaptureWindow;
+
+use crate::commands::pane::{self, pane_id_to_selector};
+use crate::commands::panes;
+use crate::commands::schedule::parse_interval;
+
+/// A pane's viewport at one moment
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Unix timestamp in milliseconds
+    pub time_ms: u64,
+    pub text: String,
+}
+
+/// `--capture-*` settings of the daemon
+#[derive(Debug, Clone)]
+pub struct RecorderOptions {
+    pub selectors: Vec<String>,
+    pub interval: Duration,
+    /// Snapshots kept per pane
+    pub keep: usize,
+    /// Skip panes that would have to be focused first
+    pub focused_only: bool,
+}
+
+#[derive(Debug)]
+pub struct CaptureHistory {
+    keep: usize,
+    /// Pane id (`terminal:3`) -> snapshots, oldest first
+    panes: Mutex<BTreeMap<String, VecDeque<Snapshot>>>,
+}
+
+impl CaptureHistory {
+    pub fn new(keep: usize) -> Self {
+        Self {
+            keep: keep.max(1),
+            panes: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Add a snapshot unless the pane still shows the same text
+    pub fn record(&self, pane: &str, snapshot: Snapshot) {
+        let mut panes = self.panes.lock().unwrap_or_else(|e| e.into_inner());
+        let snapshots = panes.entry(pane.to_string()).or_default();
+        if snapshots
+            .back()
+            .is_some_and(|last| last.text == snapshot.text)
+        {
+            return;
+        }
+        snapshots.push_back(snapshot);
+        while snapshots.len() > self.keep {
+            snapshots.pop_front();
+        }
+    }
+
+    pub fn snapshots(&self, pane: &str) -> Vec<Snapshot> {
+        let panes = self.panes.lock().unwrap_or_else(|e| e.into_inner());
+        panes
+            .get(pane)
+            .map(|snapshots| snapshots.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Drop panes that are gone
+    fn retain(&self, live: &[String]) {
+        let mut panes = self.panes.lock().unwrap_or_else(|e| e.into_inner());
+        panes.retain(|pane, _| live.contains(pane));
+    }
+}
+
+/// Newest snapshot taken at or before `time_ms`
+pub fn at(snapshots: &[Snapshot], time_ms: u64) -> Option<&Snapshot> {
+    snapshots
+        .iter()
+        .rev()
+        .find(|snapshot| snapshot.time_ms <= time_ms)
+}
+
+/// `2m ago`, `90s`, `1h30m ago`: how far back `pane capture --at` looks
+pub fn parse_ago(input: &str) -> Result<Duration, String> {
+    let input = input.trim();
+    let interval = input.strip_suffix("ago").unwrap_or(input);
+    parse_interval(interval).map_err(|_| {
+        format!("invalid time `{input}` (expected a duration back from now, e.g. `2m ago`)")
+    })
+}
+
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Capture the panes matching `options.selectors` every interval, forever
+pub fn record_forever(history: &CaptureHistory, plugin: &str, options: &RecorderOptions) {
+    loop {
+        let mut live = Vec::new();
+        for selector in &options.selectors {
+            let matched = match panes::list_matching(Some(plugin), selector) {
+                Ok(matched) => matched,
+                Err(err) => {
+                    eprintln!("daemon: capture history: {selector}: {err}");
+                    continue;
+                }
+            };
+            for info in matched {
+                if options.focused_only && !info.focused {
+                    continue;
+                }
+                let target = pane_id_to_selector(&info.id).unwrap_or_else(|| info.id.clone());
+                match pane::capture_bytes(Some(plugin), &target, false) {
+                    Ok(screen) => {
+                        let text = CaptureWindow::default()
+                            .apply(&String::from_utf8_lossy(&screen))
+                            .text;
+                        history.record(
+                            &info.id,
+                            Snapshot {
+                                time_ms: now_ms(),
+                                text,
+                            },
+                        );
+                    }
+                    Err(err) => eprintln!("daemon: capture history: {target}: {err}"),
+                }
+                live.push(info.id);
+            }
+        }
+        history.retain(&live);
+        std::thread::sleep(options.interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(time_ms: u64, text: &str) -> Snapshot {
+        Snapshot {
+            time_ms,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn keeps_changed_snapshots_within_the_limit() {
+        let history = CaptureHistory::new(2);
+        history.record("terminal:1", snapshot(1_000, "a"));
+        history.record("terminal:1", snapshot(2_000, "a"));
+        history.record("terminal:1", snapshot(3_000, "b"));
+        history.record("terminal:1", snapshot(4_000, "c"));
+        let snapshots = history.snapshots("terminal:1");
+        assert_eq!(snapshots, [snapshot(3_000, "b"), snapshot(4_000, "c")]);
+
+        assert_eq!(at(&snapshots, 3_500), Some(&snapshots[0]));
+        assert_eq!(at(&snapshots, 9_000), Some(&snapshots[1]));
+        assert_eq!(at(&snapshots, 2_000), None);
+
+        history.retain(&[]);
+        assert!(history.snapshots("terminal:1").is_empty());
+
+        assert_eq!(parse_ago("2m ago"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_ago("1h30m"), Ok(Duration::from_secs(5400)));
+        assert!(parse_ago("yesterday").is_err());
+    }
+}
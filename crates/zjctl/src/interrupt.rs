@@ -0,0 +1,60 @@
+//! Ctrl-C for long-running commands (`pane wait-idle`, `pane wait-for`, `pane wait-prompt`,
+//! `pane watch`, `pane exec`, `run`, `selftest`)
+//!
+//! Left alone, SIGINT kills zjctl wherever it is: with another pane focused for a capture, a
+//! command still running in a pane it was driving, or a scratch pane open. [`install`] turns
+//! the first SIGINT (or SIGTERM) into a flag instead; the polling loops notice it through
+//! [`check`] and unwind through their cleanup, which gives focus back, stops what zjctl started,
+//! and closes temporary panes. A `zellij pipe` call in flight ends on its own, since the pipe
+//! process gets the same Ctrl-C from the terminal. A second signal exits at once with status
+//! 130, in case cleanup hangs.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use signal_hook::consts::{SIGINT, SIGTERM};
+
+/// Exit status of an interrupted command (128 + SIGINT)
+pub const EXIT_CODE: i32 = 130;
+
+static FLAG: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+/// Catch SIGINT/SIGTERM from here on; later calls do nothing
+pub fn install() {
+    FLAG.get_or_init(|| {
+        let flag = Arc::new(AtomicBool::new(false));
+        for signal in [SIGINT, SIGTERM] {
+            // Registered first, so it sees the flag as it was before this signal set it.
+            let _ =
+                signal_hook::flag::register_conditional_shutdown(signal, EXIT_CODE, flag.clone());
+            let _ = signal_hook::flag::register(signal, flag.clone());
+        }
+        flag
+    });
+}
+
+pub fn interrupted() -> bool {
+    FLAG.get().is_some_and(|flag| flag.load(Ordering::Relaxed))
+}
+
+/// `Err` once the user pressed Ctrl-C
+pub fn check() -> Result<(), Interrupted> {
+    if interrupted() {
+        Err(Interrupted)
+    } else {
+        Ok(())
+    }
+}
+
+/// The command stopped because of Ctrl-C (exit status 130)
+#[derive(Debug)]
+pub struct Interrupted;
+
+impl fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("interrupted")
+    }
+}
+
+impl std::error::Error for Interrupted {}
@@ -2,10 +2,34 @@
 //!
 //! Provides pane-addressed operations via RPC to the zrpc plugin.
 
-use clap::{ArgAction, Parser, Subcommand};
+use activity::ChangeMask;
+use clap::{ArgAction, ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use commands::fleet::FleetLayout;
+use commands::pane::{ClearLine, EnterKey};
+use output::ColorChoice;
+use progress::ProgressFormat;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+use zjctl_proto::{CaptureRegion, CaptureWindow, PaneType};
 
+mod activity;
+mod auth;
 mod client;
 mod commands;
+mod config;
+mod daemon;
+mod formats;
+mod groups;
+mod history;
+mod interrupt;
+mod metrics;
+mod outcome;
+mod output;
+mod paths;
+mod progress;
+mod telemetry;
+mod template;
 mod zellij;
 
 const HELP_AFTER: &str = r#"Quickstart:
@@ -28,9 +52,30 @@ const HELP_AFTER: &str = r#"Quickstart:
 
 Selectors:
   id:terminal:N   id:plugin:N   focused
-  title:substring title:/regex/
+  title:substring title:/regex/      (name or terminal title)
+  name:substring  termtitle:substring
   cmd:substring   cmd:/regex/
-  tab:N:index:M
+  cwd:substring   cwd:/regex/        (working directory, when known)
+  mark:NAME       alias:NAME          (pane bookmarked with zjctl mark/alias set)
+  tag:NAME                            (every pane tagged with zjctl tag add)
+  token:VALUE                         (pane launched with --token; its ZJCTL_PANE_TOKEN)
+  tab:N:index:M   tab:N   floating
+  a+b  a&b        !a  not:a          (all of / none of)
+  a,b                                 (any of; looser than +)
+  group:NAME                          (selector saved with zjctl group create)
+  session:NAME:<selector>            (pane in another session)
+
+Sessions:
+  --session / ZJCTL_SESSION target another session; panes ls --all-sessions lists all.
+
+Exit codes:
+  1 error   2 usage   3 no match   4 ambiguous match   5 unknown method
+  6 invalid params   7 invalid request   8 plugin internal error   9 pane in scroll mode
+  10 zellij missing   11 plugin not installed   12 plugin not loaded   13 pipe failure
+  14 unauthorized (daemon token)   15 denied by read-only mode or token scope
+  16 some items of a multi-pane operation failed   17 plugin did not answer within --timeout
+  130 interrupted (Ctrl-C)
+  (pane exec/run pass on the command's own status; --json-errors prints errors as JSON)
 
 Plugin path:
   --plugin / ZJCTL_PLUGIN_PATH override the default plugin path.
@@ -68,6 +113,25 @@ const PANE_HELP: &str = r#"Pane examples:
 const PANES_HELP: &str = r#"Panes examples:
   zjctl panes ls
   zjctl panes ls --json
+  zjctl panes ls --output yaml
+
+  # Tab-separated, no header: id, type, tab index, tab name, focused, floating, exited,
+  # exit status, command, title (--all-sessions puts the session first)
+  zjctl panes ls -o tsv | awk -F'\t' '$7 == "true" {print $1}'
+  zjctl panes ls -o tsv | cut -f1,10
+
+  # Narrow big sessions (filtering happens in the plugin; flags combine)
+  zjctl panes ls --tab current --type terminal
+  zjctl panes ls --title /^worker/ --floating
+  zjctl panes ls --cmd cargo --json
+
+  # Custom one-line-per-pane output (fields match --json; takes precedence over --output)
+  zjctl panes ls --format '{?focused:*| }{id:<14} {tab_name:<10} {title}'
+  #   {field}  {field:<N} / {field:>N} pad  {?field:yes|no} / {!field:text}  {{ }} literal
+
+  # Close exited/held panes (preview first)
+  zjctl panes prune --dry-run
+  zjctl panes prune --tab current
 "#;
 
 const HELP_QUICKSTART: &str = r#"Quickstart:
@@ -103,6 +167,54 @@ const PANE_SEND_HELP: &str = r#"Examples:
 
   # Send without Enter
   zjctl pane send --pane id:terminal:3 --enter=false -- "ls -la"
+
+  # TUIs that want a carriage return (or the kitty-protocol Enter) for submit
+  zjctl pane send --pane title:claude --enter-key cr -- "run the tests"
+  # ...or once for all such panes, in ~/.config/zjctl/config.toml:
+  #   [[enter-key]]
+  #   pane = "cmd:claude"
+  #   key = "cr"
+
+  # Paste a large file into a REPL in 512-byte chunks, letting it catch up between writes
+  zjctl pane send --pane title:python --chunk-size 512 --chunk-verify -- "$(cat script.py)"
+
+  # Stream a heredoc or file from stdin (chunked, paced by --chunk-delay)
+  zjctl pane send --pane title:python --enter=false --stdin < script.py
+
+  # Clear anything the user half-typed at the prompt before injecting the command
+  zjctl pane send --pane title:shell --clear-line -- "make test"
+  zjctl pane send --pane title:repl --clear-line=ctrl-c -- "reload()"
+
+  # Retries with the same key are skipped for 10 minutes (safe for destructive commands)
+  zjctl pane send --pane title:db --idempotency-key drop-staging-1 -- "dropdb staging"
+
+  # Confirm each target (shows id, title, last screen line) before a multi-pane send
+  zjctl pane send --pane cmd:bash --all --interactive -- "git pull"
+
+  # The focused pane is scrolled back: return to the live screen first (or --force)
+  zjctl pane send --pane focused --exit-scroll -- "make"
+
+  # Exact bytes: escape sequences, control characters, or a binary file
+  zjctl pane send --pane title:vim --raw -- '\e:wq\r'
+  zjctl pane send --pane title:tui --hex -- 1b 5b 41
+  zjctl pane send --pane title:serial --raw --stdin < firmware.bin
+
+Sends to the focused pane fail (exit 9) while Zellij is in scroll or search mode, since
+the text would never reach the program.
+"#;
+
+const PANE_SEND_KEYS_HELP: &str = r#"Examples:
+  # Quit vim without saving
+  zjctl pane send-keys --pane title:vim Escape : q ! Enter
+
+  # Ctrl+C, then re-run the previous command
+  zjctl pane send-keys --pane id:terminal:3 C-c Up Enter
+
+  # Drive a TUI: page down, Alt+x, F5
+  zjctl pane send-keys --pane cmd:htop PageDown M-x F5
+
+Keys: C-/M-/S- modifiers, Enter, Tab, BTab, Escape, Space, BSpace, Up, Down, Left, Right,
+Home, End, Insert, Delete, PageUp, PageDown, F1-F12, or any single character.
 "#;
 
 const PANE_FOCUS_HELP: &str = r#"Examples:
@@ -112,6 +224,11 @@ const PANE_FOCUS_HELP: &str = r#"Examples:
   zjctl pane focus --pane id:terminal:3
 "#;
 
+const PANE_TOGGLE_HELP: &str = r#"Examples:
+  # Jump back to the previously focused pane (bind this to a key for ping-pong)
+  zjctl pane toggle
+"#;
+
 const PANE_INTERRUPT_HELP: &str = r#"Examples:
   # Send Ctrl+C
   zjctl pane interrupt --pane id:terminal:3
@@ -127,6 +244,31 @@ const PANE_CAPTURE_HELP: &str = r#"Examples:
   zjctl pane capture --pane focused
 
   zjctl pane capture --pane focused --full
+
+  # Invalid UTF-8 is replaced with U+FFFD; use base64 for byte-exact output
+  zjctl pane capture --pane focused --bytes-base64 | base64 -d > screen.bin
+
+  # Last 20 lines only
+  zjctl pane capture --pane title:build --lines 20
+
+  # One part of a TUI: its bottom line, or a block of rows and columns
+  zjctl pane capture --pane title:tests --region bottom:1
+  zjctl pane capture --pane title:htop --region "rows 0-4, cols 0-80"
+
+  # Fetch only new output: keep the offset token from each capture for the next one
+  offset=$(zjctl pane capture --pane title:build --json | jq -r .offset)
+  zjctl pane capture --pane title:build --since-offset "$offset" --json
+
+  # Recover what scrolled away: the daemon snapshots the build pane every 30s
+  zjctl daemon --capture-history title:build &
+  zjctl pane capture --pane title:build --history-list
+  zjctl pane capture --pane title:build --at "2m ago"
+
+A capture's last line may still be growing, so it is repeated in the next delta. When the
+offset can no longer be found (screen cleared, scrollback rotated past it) the whole
+scrollback is returned with "reset": true.
+--at prints the newest snapshot taken at or before that time; snapshots hold the viewport
+only and live in the daemon's memory.
 "#;
 
 const PANE_WAIT_HELP: &str = r#"What it does:
@@ -137,9 +279,210 @@ const PANE_WAIT_HELP: &str = r#"What it does:
   It focuses the pane while checking; by default it restores your previous focus
   (use `--no-restore` to keep focus on the pane).
 
+  Redraws that are not activity do not count as changes: cursor blinks and moves,
+  spinner glyphs, and clock-like times (12:04:59). Skip whole rows such as a status bar
+  with `--ignore-rows 0,-1` (negative rows count from the bottom); `--exact` counts
+  every other byte again.
+
 Examples:
   # After sending a command, wait until output settles
   zjctl pane wait-idle --pane focused --idle-time 2 --timeout 30
+
+  # Stream JSON progress records to stderr while waiting
+  zjctl pane wait-idle --pane focused --progress jsonl
+
+  # A TUI whose bottom row is a status bar with a live clock
+  zjctl pane wait-idle --pane title:htop --ignore-rows -1
+"#;
+
+const PANE_WAIT_FOR_HELP: &str = r#"Examples:
+  # Block until the dev server is up, then print the matching line
+  zjctl pane wait-for --pane title:server --match 'Listening on' --timeout 60
+
+  # Search scrollback too and get {pane, line, match, elapsed} as JSON
+  zjctl pane wait-for --pane id:terminal:3 --match 'tests? (passed|failed)' --full --json
+
+  # Only look at the summary line a test runner keeps at the bottom
+  zjctl pane wait-for --pane title:tests --match 'passed' --region bottom:1
+
+Only output written after the wait starts counts: a match already on screen is ignored, so
+start it before the output can appear (e.g. in the background, then send the command).
+
+Regions (--region, also on capture and wait-idle) are 0-based and end-exclusive and must fit
+in the pane: top:N, bottom:N, rows A-B, cols A-B, or a rows and a cols part joined by a comma.
+"#;
+
+const PANE_WAIT_PROMPT_HELP: &str = r#"Examples:
+  # Block until the shell is ready for input again
+  zjctl pane wait-prompt --pane title:build --timeout 600
+
+  # A REPL, or a custom prompt regex
+  zjctl pane wait-prompt --pane title:db --profile psql
+  zjctl pane wait-prompt --pane title:shell --prompt '❯ $'
+
+Profiles: sh (default), bash, zsh, fish, powershell, python, node, psql. Add or
+override profiles with `name = regex` lines in ~/.config/zjctl/prompts; the regex is
+matched against the last non-blank line of the visible screen.
+"#;
+
+const PANE_WAIT_EXIT_HELP: &str = r#"Examples:
+  # Start a build in its own pane and wait for it to finish
+  pane=$(zjctl pane launch -- make build)
+  zjctl pane wait-exit --pane "$pane" --timeout 600 --json
+
+Prints `<pane> exited with status N`, or with --json {"pane", "exit_code", "closed",
+"duration"}. zjctl itself succeeds whatever the status; check exit_code. A pane closed with
+its command (--close-on-exit) reports "closed": true and no exit code. Only panes started
+with a command can be waited on: Zellij does not report commands typed into a shell.
+"#;
+
+const PANE_WAIT_CLOSED_HELP: &str = r#"Examples:
+  # Block until the user closes the review pane (or error after 10 minutes)
+  zjctl pane wait-closed --pane title:review --timeout 600
+
+  # The pane may already be gone by the time this runs
+  zjctl pane wait-closed --pane title:review --allow-missing
+
+A selector that matches no pane fails with exit 3 unless --allow-missing is given.
+"#;
+
+const PANE_HISTORY_HELP: &str = r#"Examples:
+  # What has automation typed into this pane, and when?
+  zjctl pane history --pane title:server
+
+  # Last 5 sends as JSON (time_ms is a Unix timestamp in milliseconds)
+  zjctl pane history --pane id:terminal:3 --limit 5 --json
+
+The plugin keeps the last 50 texts sent through zjctl to each open pane.
+"#;
+
+const PANES_HISTORY_HELP: &str = r#"Examples:
+  # What ran in this pane before it went back to the shell prompt?
+  zjctl panes history --pane title:build
+
+  # Last 10 changes as JSON (time_ms is a Unix timestamp in milliseconds)
+  zjctl panes history --pane id:terminal:3 --limit 10 --json
+
+The plugin records a pane's title and command whenever either changes, keeping the last 50
+per open pane since it was loaded. Shells usually set the title to the running command, so
+the titles show what ran in a shell pane; `last_command` is the newest command of a command
+pane, kept after it exits.
+"#;
+
+const PANE_STATE_HELP: &str = r#"Examples:
+  # Pop a pane out into a floating window, and back
+  zjctl pane toggle-float --pane title:logs
+
+  # Keep a floating pane on top while working in the tiled ones
+  zjctl pane pin --pane title:logs
+  zjctl pane pin --pane title:logs --off
+
+  # Zoom a tiled pane to fill its tab (run again to restore)
+  zjctl pane toggle-fullscreen --pane title:editor
+
+Without --pane these act on the focused pane. No need to focus the pane first.
+"#;
+
+const PANE_MOVE_HELP: &str = r#"Examples:
+  # Move the test runner to tab 2, or to a tab by name
+  zjctl pane move --pane cmd:pytest --to-tab 2
+  zjctl pane move --pane title:logs --to-tab logs --focus
+
+  # Break every backend pane out into a fresh tab
+  zjctl pane move --pane group:backend --all --to-tab new --tab-name backend
+
+  # Swap a pane with its neighbour to the left
+  zjctl pane move --pane title:editor --position left
+
+Tabs are given as `tab ls` shows them: a 0-based index, a name, or `current`. With both
+--to-tab and --position the pane is moved to the tab first. Focus stays where it is unless
+--focus is given.
+"#;
+
+const PANE_CLEAR_HELP: &str = r#"Examples:
+  # Start a test from a blank screen and an empty scrollback
+  zjctl pane clear --pane title:under-test --scrollback
+  zjctl pane send --pane title:under-test -- "make check\n"
+  zjctl pane wait-for --pane title:under-test "passed"
+
+  # Drop the scrollback of every build pane but keep what they show
+  zjctl pane clear --pane group:build --all --scrollback --keep-screen
+
+The screen is cleared by sending Ctrl+L, which shells and most full-screen programs answer by
+redrawing on a blank screen, even with a half-typed command line. The scrollback is dropped
+by zellij itself, shortly after the screen was cleared. Plugin panes cannot be cleared.
+"#;
+
+const PANE_SET_CWD_HELP: &str = r#"Examples:
+  # Tell zjctl where a pane is, then address it by project
+  zjctl pane set-cwd --pane id:terminal:3 ~/src/api
+  zjctl pane send --pane cwd:src/api -- "cargo test\n"
+
+  # Keep every shell pane up to date (zsh; bash can use PROMPT_COMMAND)
+  chpwd() { [ -n "$ZELLIJ_PANE_ID" ] && zjctl pane set-cwd --pane "id:terminal:$ZELLIJ_PANE_ID" "$PWD" }
+
+Zellij does not tell plugins where panes are, so `cwd:` selectors only match panes
+launched with --cwd or reported with set-cwd.
+"#;
+
+const PANE_EXEC_HELP: &str = r#"Examples:
+  # Run a command in a shell pane; prints its output and exits with its status
+  zjctl pane exec --pane title:shell -- cargo test
+
+  # Structured result: pane, exit_code, output, duration
+  zjctl pane exec --pane id:terminal:3 --json --timeout 600 -- make build
+
+  # fish, PowerShell, and REPLs need a matching profile
+  zjctl pane exec --pane title:py --profile python -- 'sum(range(10))'
+
+The pane must be sitting at a prompt of the --profile shell (default: sh, any POSIX
+shell). Shell commands run through `eval` (or Invoke-Expression), so `cd` and exported
+variables persist. REPL profiles (python, node, psql) type the command as-is and return
+everything printed before the next prompt, with exit code 0.
+"#;
+
+const PANE_RUN_HELP: &str = r#"Examples:
+  # Run a command in every matching shell pane, four at a time
+  zjctl pane run --pane title:worker --all --parallel 4 -- git pull
+
+  # Aggregate pass/fail per pane as JSON, with live completion records on stderr
+  zjctl pane run --pane title:worker --all --json --progress jsonl -- make test
+
+Each pane must be sitting at a --profile prompt, as for `pane exec`. Results are
+printed as panes finish; the exit status is 1 when any pane failed or timed out.
+"#;
+
+const PANE_ON_CHANGE_HELP: &str = r#"Examples:
+  # Re-run a formatter whenever the REPL output settles (runs until the pane closes)
+  zjctl pane on-change --pane title:repl --exec 'black -q src/' &
+
+  # Hand the changed lines to a script: on stdin, and as a file path in place of {}
+  zjctl pane on-change --pane title:build --debounce 2 --exec 'notify-errors {}'
+
+The command runs through the shell with ZJCTL_PANE and ZJCTL_CHANGED_FILE set. Changed
+lines are those between the first and last line that differ since the last run.
+"#;
+
+const PANE_WATCH_HELP: &str = r#"Examples:
+  # Follow a pane's output (Ctrl+C to stop)
+  zjctl pane watch --pane title:server
+
+  # Block until the dev server is ready, echoing output meanwhile
+  zjctl pane watch --pane title:server --until 'Listening on' --timeout 120
+
+Only lines written after watch starts are printed. Exits when the pane closes.
+"#;
+
+const PANE_PIPE_HELP: &str = r#"Examples:
+  # Feed compiler errors from the build pane into a REPL pane
+  zjctl pane pipe --from title:build --to title:assistant --filter '^error'
+
+  # Mirror every new line, polling twice a second
+  zjctl pane pipe --from cmd:tail --to id:terminal:7 --interval 0.5
+
+Only lines written after the pipe starts are forwarded, each followed by Enter.
+Runs until Ctrl+C or until either pane closes. Until Zellij exposes pane contents
+to plugins, each poll briefly focuses the source pane to read it.
 "#;
 
 const PANE_RENAME_HELP: &str = r#"Examples:
@@ -156,11 +499,33 @@ const PANE_RESIZE_HELP: &str = r#"Examples:
   zjctl pane resize --pane focused --rows 40
 "#;
 
+const PANE_SCROLL_HELP: &str = r#"Examples:
+  # Scroll back half a screen in the build pane, then capture what is visible
+  zjctl pane scroll --pane title:build --up --half-page
+  zjctl pane capture --pane title:build
+
+  # Ten lines, three pages, or all the way
+  zjctl pane scroll --pane focused --up --count 10
+  zjctl pane scroll --pane focused --down --page --count 3
+  zjctl pane scroll --pane focused --bottom
+"#;
+
 const PANE_CLOSE_HELP: &str = r#"Examples:
   # Close a pane (safe by default)
   zjctl pane close --pane id:terminal:3
 
   zjctl pane close --pane focused --force
+
+  # Close every matching pane (the focused one is skipped unless --force)
+  zjctl pane close --pane cmd:sleep --all
+"#;
+
+const PANE_ONLY_HELP: &str = r#"Examples:
+  # Close every other terminal pane in the focused pane's tab (asks first)
+  zjctl pane only --pane focused
+
+  # Skip the confirmation prompt (required when stdin is not a terminal)
+  zjctl pane only --pane title:editor --yes
 "#;
 
 const PANE_LAUNCH_HELP: &str = r#"Examples:
@@ -168,6 +533,457 @@ const PANE_LAUNCH_HELP: &str = r#"Examples:
   zjctl pane launch -- "zsh"
 
   zjctl pane launch --direction right -- "python"
+
+  # Open a shell in a project the way `z api` would (zoxide), or with another resolver
+  zjctl pane launch --cwd-query api
+  zjctl pane launch --cwd-query api --cwd-resolver 'fd -td . ~/src | fzf --filter {} | head -1'
+
+  # Stamp the pane with ZJCTL_PANE_TOKEN and address it by that token later, even after
+  # its title or running program changes (prints token:<value>)
+  pane=$(zjctl pane launch --token -- ./deploy.sh)
+  zjctl pane capture --pane "$pane"
+
+Inside the pane, `zjctl pane send --pane "token:$ZJCTL_PANE_TOKEN" ...` targets itself.
+--cwd-query runs the resolver (default `zoxide query -- {}`) through the shell and opens the
+pane in the first directory it prints. Set a team-wide resolver with ZJCTL_CWD_RESOLVER or
+`cwd-resolver` under [pane.launch] in the config file.
+"#;
+
+const WAIT_FOR_HELP: &str = r#"Examples:
+  # Wait until a pane titled "server" shows up and print its selector
+  zjctl wait-for --pane title:server --timeout 30
+
+  # Require exactly one match (keeps waiting while the selector is ambiguous)
+  pane=$(zjctl wait-for --pane cmd:docker --unique)
+"#;
+
+const STATUSLINE_HELP: &str = r#"Examples:
+  # Default: focused title (command) | pane counts
+  zjctl statusline
+
+  # Custom line for a prompt or status bar
+  zjctl statusline --format '{tab}:{title}{?busy: ⏳}{?exited: ✗}'
+"#;
+
+const WATCHDOG_HELP: &str = r#"Examples:
+  # Re-run a command pane whenever it exits (1s, 2s, 4s... backoff)
+  zjctl watchdog --pane title:dev-server
+
+  # Relaunch the command if the pane is gone (or not started yet)
+  zjctl watchdog --pane title:dev-server --restart-cmd "npm run dev" --max-restarts 10
+
+  # Keep the restart log
+  zjctl watchdog --pane cmd:worker >> restarts.jsonl
+
+Each restart prints one JSON line to stdout: time, pane, restart, exit_status, action, delay.
+"#;
+
+const BATCH_HELP: &str = r#"Examples:
+  # Send, rename, and focus in a single round trip (JSON Lines on stdin)
+  zjctl batch <<'EOF'
+  {"method": "pane.send", "params": {"selector": "title:build", "text": "make\n"}}
+  {"method": "pane.rename", "params": {"selector": "title:build", "name": "building"}}
+  {"method": "pane.focus", "params": {"selector": "name:building"}}
+  EOF
+
+  # A JSON array from a file
+  zjctl batch --file calls.json
+
+Requests run in order; one JSON line per request is printed with its result or error.
+Exits with status 16 if any request failed (later requests still run), naming the first
+failure. With --fail-fast the requests are sent one at a time and none is sent after a
+failure (those print `"skipped": true`); with --allow-partial only a batch where every
+request failed is an error.
+"#;
+
+const RUN_HELP: &str = r#"Examples:
+  # Run a command in a throwaway floating pane; prints its output, exits with its status
+  zjctl run -- cargo test
+
+  # Shell syntax needs a shell
+  zjctl run --cwd ~/src/app -- sh -c 'make build && make test'
+
+  # Structured result, keeping the pane open to look at afterwards
+  zjctl run --json --keep --timeout 600 -- ./deploy.sh staging
+
+The command is the pane's own process, so no prompt is involved: zjctl waits until it
+exits, captures the pane's scrollback, closes the pane (unless --keep), and exits with
+the command's status. On --timeout the pane is left running and zjctl exits 1.
+"#;
+
+const SELFTEST_HELP: &str = r#"Examples:
+  # Check that zjctl, the plugin, and Zellij work together in this session
+  zjctl selftest
+
+  # Per-step status and timing for a bug report
+  zjctl selftest --json
+
+Steps: launch (a floating `sh` pane), send (a command printing a marker), wait (for the
+marker), capture, assert (the marker is on screen), resize, rename, close. After a failed
+step the rest are skipped, but the pane is still closed unless --keep. Exits 1 when a step
+failed; --output tsv prints step, status, seconds, detail, and error per line.
+"#;
+
+const DAEMON_HELP: &str = r#"Examples:
+  # Keep one zellij pipe open for this session (run it in a spare pane or the background)
+  zjctl daemon &
+
+  # Later calls from the same session go through it automatically
+  zjctl panes ls
+
+  # Bypass a running daemon for one call
+  ZJCTL_NO_DAEMON=1 zjctl panes ls
+
+  # Skip the daemon's short-lived pane list snapshot when stale data would hurt
+  zjctl --no-cache panes ls
+
+  # Expose RPC counts, latency, and errors to Prometheus at http://127.0.0.1:9920/metrics
+  zjctl daemon --metrics-listen 127.0.0.1:9920 &
+
+  # Inspect, stop, or restart this session's daemon
+  zjctl daemon status --json
+  zjctl daemon stop
+  zjctl daemon restart --cache-ttl 1000
+
+  # Snapshot the build pane every 30s so `pane capture --at "2m ago"` can look back
+  zjctl daemon --capture-history title:build &
+
+  # Require scoped tokens, e.g. before sharing the socket with another user
+  zjctl daemon token new ci --scope send      # prints the token once
+  zjctl daemon --auth-tokens &                # checks ~/.config/zjctl/tokens
+  ZJCTL_TOKEN=zjt_... zjctl pane send --pane title:build -- "make\n"
+
+The daemon listens on a Unix socket under the state directory ($XDG_STATE_HOME/zjctl/daemon).
+Calls made with a different --plugin or plugin configuration use their own pipe.
+Repeated `panes.list` calls are answered from a snapshot for --cache-ttl milliseconds, once the
+plugin confirms its state has not changed since; any call that may change the session through
+the daemon drops it.
+With --auth-tokens every call needs ZJCTL_TOKEN with a sufficient scope: read (listings,
+history, events), send (input, focus, renames, new panes/tabs), or destructive (closing
+panes/tabs, host commands, stopping the daemon). Only token hashes are stored; the file is
+re-read when it changes, so `daemon token rm` revokes at once. The metrics endpoint then wants
+`Authorization: Bearer TOKEN`. A missing or unknown token exits 14 (unauthorized); a token
+whose scope is too narrow exits 15 (policy_denied). Tokens and --read-only gate the daemon,
+not the session: a direct `zellij pipe` (or ZJCTL_NO_DAEMON=1) reaches the plugin without them.
+--metrics-listen serves plain HTTP and only binds loopback addresses unless
+--metrics-allow-remote is given.
+--read-only lets only listings, captures, status, and watches through (a capture may still
+briefly focus the pane it reads, then focus back); every other call is refused with
+policy_denied, for every client (the global --read-only flag does the same for one command
+without a daemon).
+--capture-history briefly focuses each matching pane that is not focused, like `pane capture`,
+every --capture-interval; a read-only daemon only snapshots focused panes. Identical
+consecutive snapshots are stored once and the last --capture-keep are kept per pane.
+--metrics-listen serves zjctl_rpc_requests_total, zjctl_rpc_errors_total,
+zjctl_rpc_duration_seconds, zjctl_panes, and zjctl_daemon_* in the Prometheus text format.
+"#;
+
+const STATUS_HELP: &str = r#"Examples:
+  zjctl status
+  zjctl status --output yaml
+
+  # One key<TAB>value line per field: session, zellij_version, plugin_version,
+  # zjctl_version, generation, active_tab, tabs, clients, focused, panes, update_available
+  zjctl status -o tsv | awk -F'\t' '$1 == "focused" {print $2}'
+
+--output accepts table (default), json, yaml, and tsv here and in panes ls, tab ls, group ls,
+tag ls, and doctor; ZJCTL_OUTPUT sets the default and --json is short for --output json.
+"#;
+
+const DOCTOR_HELP: &str = r#"Examples:
+  # One-off setup check
+  zjctl doctor
+
+  # Install, configure, and launch the plugin as needed (preview first with --dry-run)
+  zjctl doctor --fix --dry-run
+  zjctl doctor --fix
+
+  # Log when the plugin stops responding and when it recovers
+  zjctl doctor --watch --interval 10 | tee doctor.log
+
+  # Failing checks only (tab-separated name, status, detail)
+  zjctl doctor -o tsv | awk -F'\t' '$2 == "fail"'
+
+With --watch, the first pass prints every check and later passes print only checks whose
+status changed, e.g. `2024-05-01T12:03:10Z rpc: ok -> fail (no response from plugin)`.
+The release update check is skipped while watching. --output json then prints one object
+per change and --output tsv prints time, check, previous status, status, and detail; YAML
+is not supported with --watch.
+
+--fix handles a missing plugin file (embedded plugin or download), a missing load_plugins
+entry in config.kdl, and a plugin that is not running in this session, then re-runs the
+checks. Other failures still print the commands to run.
+"#;
+
+const BROADCAST_HELP: &str = r#"Examples:
+  # Source an env file in every shell of the current tab
+  zjctl broadcast -- 'source .env'
+
+  # Clear every pane in tab 2, or across the whole session
+  zjctl broadcast --tab 2 -- clear
+  zjctl broadcast --all-tabs --json -- clear
+
+Plugin panes are left out, and exited command panes are reported as skipped. Enter is sent
+as --enter-key (or ZJCTL_ENTER_KEY) bytes, like pane send.
+"#;
+
+const EVENTS_HELP: &str = r#"Examples:
+  # Stream every change as JSON lines (Ctrl+C to stop)
+  zjctl events
+
+  # React to panes exiting
+  zjctl events --event pane_exited | while read -r line; do notify-send "$line"; done
+
+Events: pane_opened, pane_closed, pane_title_changed, pane_exited, pane_focused,
+tab_opened, tab_closed, tab_renamed, tab_focused. Each line has `event`, `seq`, and
+`time_ms`, plus `pane` or `tab_index`/`name`. Tabs are identified by position.
+A {"event":"missed"} line means the plugin's log overflowed between polls;
+{"event":"resync"} means the plugin was reloaded.
+"#;
+
+const ON_HELP: &str = r#"Examples:
+  # Notify once when a build pane closes
+  zjctl on pane-closed --pane name:build -- notify-send "build pane closed"
+
+  # Log every exit of a command pane (runs until Ctrl+C)
+  zjctl on pane-exited --pane cmd:server --persistent -- sh -c 'echo "$ZJCTL_PANE exited $ZJCTL_EXIT_STATUS" >> exits.log'
+
+  # Re-tile whenever any pane opens
+  zjctl on pane-opened --persistent -- zjctl tab balance
+
+The command runs without a shell, with ZJCTL_EVENT, ZJCTL_PANE, ZJCTL_EVENT_JSON, and
+ZJCTL_EXIT_STATUS (pane-exited) set. Without --persistent, zjctl exits with the command's
+status after the first matching event; a pane-exited trap on a pane that already exited
+fires at once. pane-closed, pane-exited, pane-focused, and pane-title-changed need --pane
+to match a pane when the trap is set; pane-opened also matches panes that open later.
+"#;
+
+const FLEET_HELP: &str = r#"Examples:
+  # One ssh pane per host, named after the host
+  zjctl fleet open hosts.txt
+
+  # Floating panes, shared ssh options, and a name prefix to address the whole fleet
+  zjctl fleet open hosts.txt --layout floating --ssh-args "-A -o ConnectTimeout=5" --prefix ssh-
+  zjctl pane send --pane name:ssh- --all -- "uptime\n"
+  zjctl pane run --pane name:ssh- --all --parallel 8 -- df -h /
+
+  # hosts.txt: one destination per line; words after it are ssh arguments for that host
+  web1
+  admin@web2 -p 2222   # bastion
+
+Hosts that already have a pane under their name are skipped.
+"#;
+
+const HOST_HELP: &str = r#"Examples:
+  # Allow the plugin to run specific programs (off by default; colon-separated) with a
+  # load_plugins entry in config.kdl; that instance shares the list with the one zjctl talks to:
+  #   load_plugins {
+  #       "file:~/.config/zellij/plugins/zrpc.wasm" { host_exec_allow "pgrep:readlink"; }
+  #   }
+
+  # Run one on the host through the plugin
+  zjctl host exec -- pgrep -P 4242
+  zjctl host exec --json -- readlink /proc/4242/cwd
+
+stdout and stderr are passed through; zjctl exits with the command's status. A command still
+running after --timeout is left running and zjctl exits 1.
+"#;
+
+const IMPORT_HELP: &str = r#"Examples:
+  # Translate a tmux session script into a zjctl script, review it, then run it in Zellij
+  zjctl import tmux-script dev-tmux.sh > dev-zellij.sh
+  sh dev-zellij.sh
+
+  # Or keep just the panes and what is typed into them, as a layout file
+  zjctl import tmux-script dev-tmux.sh --to layout > dev.kdl
+  zjctl layout apply dev.kdl
+
+Translated: new-session/new-window (a named tab), split-window (pane launch; -h right,
+-v down, -b left/up), send-keys (pane send for text, send-keys for key names like C-c),
+select-pane (focus; -T renames), select-window, rename-window, select-layout (tab layout
+presets; tiled is grid), and kill-pane. -t targets may name a session, window (name or
+index; base-index and pane-base-index set in the script are honoured), and pane index.
+Unnamed windows are named window-N. Other shell lines are copied as they are; tmux
+commands without an equivalent stay as `# not translated:` comments and are listed on
+stderr with their line numbers. FILE may be - for stdin.
+"#;
+
+const LAYOUT_HELP: &str = r#"Examples:
+  # Launch the panes described in a layout file (KDL, or JSON with a .json extension)
+  zjctl layout apply dev.kdl
+
+  # dev.kdl
+  layout {
+      pane name="server" command="npm" cwd="~/app" {
+          args "run" "dev"
+      }
+      pane name="logs" direction="down" command="tail" {
+          args "-f" "app.log"
+      }
+  }
+
+Panes that already exist under the same name are kept, so applying twice is a no-op. One
+whose command or cwd differs from the layout is reported (action differs, with a warning);
+close it and apply again to recreate it. cwd is expanded (~, relative paths) on every launch.
+Prints the action (created/existing/differs), name, and selector of every pane.
+"#;
+
+const MARK_HELP: &str = r#"Examples:
+  # Bookmark the focused pane as 1, and the server pane as "api"
+  zjctl mark set 1
+  zjctl mark set api --pane title:server
+
+  # Jump back from anywhere (bind it to a key in your Zellij config)
+  zjctl mark jump 1
+
+  # Marks are selectors too
+  zjctl pane send --pane mark:api -- "rs
+"
+
+  zjctl mark ls
+  zjctl mark rm api
+
+Marks are kept by the plugin for the session and disappear when their pane closes.
+"#;
+
+const SESSION_HELP: &str = r#"Examples:
+  # Running and exited sessions (* marks the one zjctl runs inside)
+  zjctl session ls
+  zjctl session ls -o json
+
+  # Print the command to attach (zjctl can't attach for you)
+  zjctl session attach work
+  $(zjctl session attach work)
+
+  # Kill a running session, or delete an exited one
+  zjctl session kill scratch
+
+  # Rename the current session, or another one with --session
+  zjctl session rename api
+  zjctl --session scratch session rename old-scratch --json
+
+--output tsv prints name, created, current, exited. kill refuses the session zjctl targets
+unless --force. `zjctl doctor` reports the same data in its sessions and session checks.
+"#;
+
+const TAG_HELP: &str = r#"Examples:
+  # Tag the panes that make up the backend, and the log tails
+  zjctl tag add backend --pane cmd:uvicorn,cmd:redis --all
+  zjctl tag add logs --pane cmd:tail --all
+  zjctl tag add backend --pane focused
+
+  # Then reach them all at once
+  zjctl pane interrupt --pane tag:backend --all
+  zjctl pane send --pane tag:logs --all -- "\x0c"
+
+  zjctl tag ls
+  zjctl tag rm backend --pane focused
+  zjctl tag rm logs
+
+Tags are kept by the plugin for the session, so they last across zjctl calls and disappear
+with their pane. A pane can carry several tags (panes ls --json lists them); unlike
+group:NAME, a tag sticks to the panes it was added to rather than re-matching a selector.
+"#;
+
+const ASSERT_HELP: &str = r#"Examples:
+  # After bootstrapping a workspace in CI
+  zjctl assert panes --where cmd:node --count-at-least 3 --running
+  zjctl assert panes --where title:db --count 1 --tab services
+  zjctl assert panes --count-at-most 8
+
+Without a check flag, at least one pane has to match. All checks run against one pane
+listing; failed ones are printed as `- expected` / `+ found` lines followed by the matched
+panes, and zjctl exits 1.
+"#;
+
+const ALIAS_HELP: &str = r#"Examples:
+  # Name the API server pane once, then address it by that name from scripts
+  zjctl alias set api id:terminal:3
+  zjctl pane send --pane alias:api -- "rs"
+  zjctl pane capture --pane alias:api
+
+  zjctl alias ls
+  zjctl alias rm api
+
+Aliases and marks are one registry kept by the plugin: `alias:api` and `mark:api` name the
+same pane, and `zjctl mark jump api` focuses it. An alias survives title changes and
+programs exec'ing in the pane, and disappears when the pane closes.
+"#;
+
+const GROUP_HELP: &str = r#"Examples:
+  # Name the backend panes once
+  zjctl group create backend --panes cmd:uvicorn,cmd:redis
+
+  # Then use group:backend wherever a selector goes
+  zjctl pane send --pane group:backend --all -- "\x03"
+  zjctl pane capture --pane group:backend --all
+  zjctl pane focus --pane group:backend --next
+  zjctl pane send --pane group:backend+tab:2 --all -- "reload\n"
+
+  zjctl group ls
+  zjctl group ls -o tsv    # name, selector
+  zjctl group rm backend
+
+Groups are saved selectors in ~/.config/zjctl/groups (`name = selector` per line), so they
+last across sessions and follow panes by command or title rather than by id. A group can use
+other groups; it cannot be negated (`!group:NAME`).
+"#;
+
+const REPORT_HELP: &str = r#"Examples:
+  # One pane's screen as markdown
+  zjctl report --panes focused
+
+  # Every pytest pane, full scrollback (last 200 lines each), secrets masked
+  zjctl report --panes cmd:pytest --all --full --tail 200 --redact-secrets --output report.md
+
+  # Mask extra patterns (regex, repeatable)
+  zjctl report --panes title:api --redact 'user=\S+' --redact '10\.\d+\.\d+\.\d+'
+"#;
+
+const SCHEDULE_HELP: &str = r#"Examples:
+  # Fetch every 15 minutes in a visible pane (\n sends Enter)
+  zjctl schedule add --pane title:git --every 15m -- "git fetch\n"
+
+  # Inspect and remove schedules
+  zjctl schedule ls
+  zjctl schedule rm 1
+
+  # Deliver due schedules (keep this running, e.g. in its own pane)
+  zjctl schedule run
+
+Intervals: 90, 30s, 15m, 2h, 1d, or combinations like 1h30m.
+Schedules are stored in $XDG_STATE_HOME/zjctl/schedules.json.
+"#;
+
+const TAB_HELP: &str = r#"Tab examples:
+  # Inventory
+  zjctl tab ls
+  zjctl tab ls --json
+  zjctl tab ls -o tsv      # index, name, active, panes, floating panes
+
+  # Create, rename, switch, reorder, close
+  # (tabs are addressed as "current", a 0-based index, or an exact name)
+  zjctl tab new --name logs --cwd ~/project
+  zjctl tab rename --tab 2 build
+  zjctl tab focus logs
+  zjctl tab move --tab logs 0
+  zjctl tab move right
+  zjctl tab close --tab build
+
+  # Even out pane sizes in the current tab
+  zjctl tab balance
+
+  zjctl tab balance --tab 2
+
+  # Apply a preset arrangement (resizes within the existing splits; errors when they do not
+  # fit the preset, e.g. even-horizontal with stacked panes)
+  zjctl tab layout main-vertical --main-size 65
+  zjctl tab layout even-horizontal
+  zjctl tab layout grid
+
+  # Share one tab's panes (names, commands, cwds, sizes) and recreate them elsewhere
+  zjctl tab export --tab api --output api-tab.json
+  zjctl tab import api-tab.json --name api-review
 "#;
 
 /// zjctl - Missing CLI surface for Zellij
@@ -185,6 +1001,60 @@ pub struct Cli {
     #[arg(long, env = "ZJCTL_PLUGIN_PATH")]
     plugin: Option<String>,
 
+    /// Give up on an RPC call the plugin has not answered within SECONDS (exit 17)
+    #[arg(
+        id = "rpc_timeout",
+        long = "timeout",
+        value_name = "SECONDS",
+        env = "ZJCTL_TIMEOUT"
+    )]
+    rpc_timeout: Option<f64>,
+
+    /// Target a Zellij session other than the one zjctl runs inside
+    #[arg(long, global = true, env = "ZJCTL_SESSION")]
+    session: Option<String>,
+
+    /// Print errors to stderr as JSON ({"error": {kind, message, hints, exit_code}})
+    #[arg(long, global = true, env = "ZJCTL_JSON_ERRORS")]
+    json_errors: bool,
+
+    /// Ask the plugin directly instead of reusing a daemon's cached pane list
+    #[arg(long, global = true, env = "ZJCTL_NO_CACHE")]
+    no_cache: bool,
+
+    /// Refuse anything that could change the session (sends, focus, new or closed panes);
+    /// captures and waits may still briefly focus the pane they read
+    #[arg(long, global = true, env = "ZJCTL_READ_ONLY")]
+    read_only: bool,
+
+    /// Launch the zrpc plugin when it does not answer, then retry until it does
+    #[arg(long, global = true, env = "ZJCTL_AUTO_START")]
+    auto_start: bool,
+
+    /// Stop a batch or --all operation at the first item that fails
+    #[arg(
+        long,
+        global = true,
+        env = "ZJCTL_FAIL_FAST",
+        conflicts_with = "allow_partial"
+    )]
+    fail_fast: bool,
+
+    /// Only fail a batch or --all operation when no item succeeded
+    #[arg(long, global = true, env = "ZJCTL_ALLOW_PARTIAL")]
+    allow_partial: bool,
+
+    /// When to color output (auto: terminals only, and never when NO_COLOR is set)
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_name = "WHEN",
+        default_value_t = ColorChoice::Auto,
+        env = "ZJCTL_COLOR"
+    )]
+    color: ColorChoice,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -201,34 +1071,293 @@ enum Commands {
         #[command(subcommand)]
         cmd: PaneCommands,
     },
-    /// Show focused pane and tab status
-    Status {
-        /// Output as JSON
-        #[arg(long)]
-        json: bool,
+    /// Tab operations
+    Tab {
+        #[command(subcommand)]
+        cmd: TabCommands,
     },
-    /// Pass-through to zellij action
-    Action {
-        /// Arguments to pass to zellij action
-        #[arg(trailing_var_arg = true)]
-        args: Vec<String>,
+    /// Run several RPC calls in one round trip (JSON Lines or a JSON array)
+    #[command(after_help = BATCH_HELP)]
+    Batch {
+        /// Read calls from a file instead of stdin
+        #[arg(long)]
+        file: Option<String>,
     },
-    /// Run setup checks for zjctl + zrpc
-    Doctor {
-        /// Output diagnostics as JSON
+    /// Type the same text into every terminal pane of a tab (or the session)
+    #[command(after_help = BROADCAST_HELP)]
+    Broadcast {
+        /// Tab: "current" (default), a 0-based index, or an exact name
+        #[arg(long, conflicts_with = "all_tabs")]
+        tab: Option<String>,
+        /// Every tab in the session
+        #[arg(long)]
+        all_tabs: bool,
+        /// Type the text without pressing Enter
+        #[arg(long)]
+        no_enter: bool,
+        /// Bytes sent for Enter (cr, lf, crlf, or kitty's CSI 13 u)
+        #[arg(long, value_enum, env = "ZJCTL_ENTER_KEY", default_value = "lf")]
+        enter_key: EnterKey,
+        /// Print {sent_to, panes: [{pane, ok, skipped}]} as JSON
         #[arg(long)]
         json: bool,
+        /// Text to send (after --)
+        #[arg(last = true)]
+        text: Vec<String>,
     },
-    /// Agent-friendly quickstart
-    Help,
+    /// Keep one zellij pipe open and serve this session's calls through it
+    #[command(after_help = DAEMON_HELP, args_conflicts_with_subcommands = true)]
+    Daemon {
+        #[command(subcommand)]
+        cmd: Option<DaemonCommands>,
+        /// How long a `panes.list` answer is reused, in milliseconds (0 disables the cache)
+        #[arg(long, default_value_t = 500)]
+        cache_ttl: u64,
+        /// Serve Prometheus metrics over HTTP on this address (e.g. 127.0.0.1:9920)
+        #[arg(long, value_name = "ADDR")]
+        metrics_listen: Option<SocketAddr>,
+        /// Allow a --metrics-listen address that is not loopback (metrics are plain HTTP)
+        #[arg(long, requires = "metrics_listen")]
+        metrics_allow_remote: bool,
+        /// Require a token from this file for every call (default file: ~/.config/zjctl/tokens)
+        #[arg(long, value_name = "FILE", num_args = 0..=1, require_equals = true)]
+        auth_tokens: Option<Option<PathBuf>>,
+        /// Keep periodic viewport snapshots of the matching panes for `pane capture --at`
+        /// (repeatable)
+        #[arg(long, value_name = "SELECTOR")]
+        capture_history: Vec<String>,
+        /// Time between capture history snapshots, e.g. 30s or 2m
+        #[arg(long, value_name = "INTERVAL", default_value = "30s", value_parser = commands::schedule::parse_interval)]
+        capture_interval: Duration,
+        /// Snapshots kept per pane
+        #[arg(long, value_name = "N", default_value_t = 120)]
+        capture_keep: usize,
+    },
+    /// Stream pane, tab, and focus changes as JSON lines
+    #[command(after_help = EVENTS_HELP)]
+    Events {
+        /// Only print these events (repeatable), e.g. pane_exited
+        #[arg(long = "event", value_name = "NAME")]
+        events: Vec<String>,
+    },
+    /// Run a command when a pane event fires (once, or for every event with --persistent)
+    #[command(after_help = ON_HELP)]
+    On {
+        /// Event to wait for
+        event: commands::on::PaneEvent,
+        /// Only fire for panes matching this selector
+        #[arg(long)]
+        pane: Option<String>,
+        /// Run the command for every matching event instead of exiting after the first
+        #[arg(long)]
+        persistent: bool,
+        /// Command to run (after --)
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+    /// Open and manage ssh panes for a list of hosts
+    Fleet {
+        #[command(subcommand)]
+        cmd: FleetCommands,
+    },
+    /// Run allowlisted programs on the host through the plugin
+    Host {
+        #[command(subcommand)]
+        cmd: HostCommands,
+    },
+    /// Translate scripts written for other multiplexers into zjctl
+    Import {
+        #[command(subcommand)]
+        cmd: ImportCommands,
+    },
+    /// Declarative pane layouts
+    Layout {
+        #[command(subcommand)]
+        cmd: LayoutCommands,
+    },
+    /// Save selectors under a name for the `group:` selector
+    Group {
+        #[command(subcommand)]
+        cmd: GroupCommands,
+    },
+    /// Give panes stable names for scripts (the `alias:` selector)
+    Alias {
+        #[command(subcommand)]
+        cmd: AliasCommands,
+    },
+    /// List, attach to, kill, and rename Zellij sessions
+    Session {
+        #[command(subcommand)]
+        cmd: SessionCommands,
+    },
+    /// Tag panes and address them together with tag:NAME
+    Tag {
+        #[command(subcommand)]
+        cmd: TagCommands,
+    },
+    /// Bookmark panes and jump back to them
+    Mark {
+        #[command(subcommand)]
+        cmd: MarkCommands,
+    },
+    /// Capture panes into a markdown report (metadata + fenced output per pane)
+    #[command(after_help = REPORT_HELP)]
+    Report {
+        /// Pane selector
+        #[arg(long)]
+        panes: String,
+        /// Include every matching pane
+        #[arg(long)]
+        all: bool,
+        /// Include scrollback
+        #[arg(long)]
+        full: bool,
+        /// Keep only the last N lines of each pane
+        #[arg(long)]
+        tail: Option<usize>,
+        /// Replace matches of this regex with [REDACTED] (repeatable)
+        #[arg(long)]
+        redact: Vec<String>,
+        /// Redact common secrets (API keys, tokens, password=...)
+        #[arg(long)]
+        redact_secrets: bool,
+        /// Write the report to a file instead of stdout
+        #[arg(long, short)]
+        output: Option<String>,
+    },
+    /// Run a command in a temporary pane, print its output, and exit with its status
+    #[command(after_help = RUN_HELP)]
+    Run {
+        /// Working directory for the command
+        #[arg(long)]
+        cwd: Option<String>,
+        /// Name of the temporary pane
+        #[arg(long)]
+        name: Option<String>,
+        /// Open a tiled pane instead of a floating one
+        #[arg(long)]
+        tiled: bool,
+        /// Maximum time to wait for the command (seconds)
+        #[arg(long, default_value = "600.0")]
+        timeout: f64,
+        /// Leave the pane open after the command exits
+        #[arg(long)]
+        keep: bool,
+        /// Print {pane, exit_code, output, duration, closed} as JSON
+        #[arg(long)]
+        json: bool,
+        /// Command to run (after --)
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+    /// Run a scratch pane through launch, send, wait, capture, resize, rename, and close
+    #[command(after_help = SELFTEST_HELP)]
+    Selftest {
+        /// Seconds to wait for each step that polls (marker, rename, close)
+        #[arg(long, default_value = "10.0")]
+        timeout: f64,
+        /// Leave the scratch pane open (skips the close step)
+        #[arg(long)]
+        keep: bool,
+        #[command(flatten)]
+        output: formats::OutputArgs,
+    },
+    /// Periodic sends into panes
+    Schedule {
+        #[command(subcommand)]
+        cmd: ScheduleCommands,
+    },
+    /// Print a one-line summary for status bars and prompts
+    #[command(after_help = STATUSLINE_HELP)]
+    Statusline {
+        /// Template for the line (fields: session, tab, tab_index, focused, title, command,
+        /// panes, busy, exited)
+        #[arg(long)]
+        format: Option<String>,
+    },
+    /// Re-run a command pane whenever its command exits
+    #[command(after_help = WATCHDOG_HELP)]
+    Watchdog {
+        /// Pane selector (must match a single pane)
+        #[arg(long)]
+        pane: String,
+
+        /// Command to launch in a new pane when the watched pane is closed or missing
+        #[arg(long)]
+        restart_cmd: Option<String>,
+
+        /// Give up after this many restarts (0 = never)
+        #[arg(long, default_value = "5")]
+        max_restarts: u32,
+
+        /// Seconds to wait before the first restart (doubles each time, up to 60s)
+        #[arg(long, default_value = "1.0")]
+        backoff: f64,
+    },
+    /// Show focused pane and tab status
+    #[command(after_help = STATUS_HELP)]
+    Status {
+        #[command(flatten)]
+        output: formats::OutputArgs,
+    },
+    /// Wait until a selector matches and print the resolved pane selector(s)
+    #[command(after_help = WAIT_FOR_HELP)]
+    WaitFor {
+        /// Pane selector
+        #[arg(long)]
+        pane: String,
+        /// Maximum time to wait before erroring (seconds)
+        #[arg(long, default_value = "30.0")]
+        timeout: f64,
+        /// Wait until the selector matches exactly one pane
+        #[arg(long)]
+        unique: bool,
+        /// Emit machine-readable progress records on stderr
+        #[arg(long, value_enum)]
+        progress: Option<ProgressFormat>,
+    },
+    /// Check the pane inventory and exit 1 with a report when it is off
+    Assert {
+        #[command(subcommand)]
+        cmd: AssertCommands,
+    },
+    /// Pass-through to zellij action
+    Action {
+        /// Arguments to pass to zellij action
+        #[arg(trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+    /// Run setup checks for zjctl + zrpc
+    #[command(after_help = DOCTOR_HELP)]
+    Doctor {
+        #[command(flatten)]
+        output: formats::OutputArgs,
+        /// Keep re-running the checks and print each status change with a timestamp
+        #[arg(long)]
+        watch: bool,
+        /// Seconds between checks with --watch
+        #[arg(long, default_value = "5.0", requires = "watch")]
+        interval: f64,
+        /// Install, configure, and launch the plugin where the checks call for it
+        #[arg(long, conflicts_with = "watch")]
+        fix: bool,
+        /// With --fix, print what would be done without changing anything
+        #[arg(long, requires = "fix")]
+        dry_run: bool,
+    },
+    /// Agent-friendly quickstart
+    Help,
     /// Install the zrpc plugin
     Install {
         /// Print the commands that would be run
         #[arg(long)]
         print: bool,
-        /// Re-download plugin even if it exists
+        /// Reinstall the plugin even if it exists
         #[arg(long)]
         force: bool,
+        /// Download the latest release even when this build embeds the plugin
+        #[arg(long)]
+        download: bool,
         /// Attempt to load the plugin in the current Zellij session
         #[arg(long)]
         load: bool,
@@ -238,6 +1367,9 @@ enum Commands {
         /// Do not add the plugin to config.kdl load_plugins
         #[arg(long, conflicts_with = "auto_load")]
         no_auto_load: bool,
+        /// Emit machine-readable progress records on stderr
+        #[arg(long, value_enum)]
+        progress: Option<ProgressFormat>,
     },
 }
 
@@ -245,11 +1377,461 @@ enum Commands {
 #[command(after_help = PANES_HELP)]
 enum PanesCommands {
     /// List all panes
+    Ls {
+        #[command(flatten)]
+        output: formats::OutputArgs,
+        /// Render each pane with a template, e.g. '{?focused:*}{id:<14} {title}'
+        #[arg(long, conflicts_with = "json")]
+        format: Option<String>,
+        /// List panes from every running session (adds a `session` field)
+        #[arg(long)]
+        all_sessions: bool,
+        /// Only panes in this tab ("current" or a tab index)
+        #[arg(long)]
+        tab: Option<String>,
+        /// Only panes of this type (terminal or plugin)
+        #[arg(long = "type")]
+        pane_type: Option<PaneType>,
+        /// Only panes whose name or title matches (substring or /regex/)
+        #[arg(long)]
+        title: Option<String>,
+        /// Only panes whose command matches (substring or /regex/)
+        #[arg(long)]
+        cmd: Option<String>,
+        /// Only floating panes
+        #[arg(long)]
+        floating: bool,
+        /// Only the focused pane
+        #[arg(long)]
+        focused_only: bool,
+    },
+    /// Show the titles and commands a pane has shown
+    #[command(after_help = PANES_HISTORY_HELP)]
+    History {
+        /// Pane selector (must match a single pane)
+        #[arg(long)]
+        pane: String,
+        /// Only the last N changes
+        #[arg(long)]
+        limit: Option<usize>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Close all exited/held panes
+    Prune {
+        /// Only prune panes in this tab ("current" or a tab index)
+        #[arg(long)]
+        tab: Option<String>,
+        /// Print what would be closed without closing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum DaemonCommands {
+    /// Show whether a daemon serves this session, and what it is doing
+    Status {
+        /// Output JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Stop this session's daemon
+    Stop,
+    /// Stop this session's daemon and start a new one in the background
+    Restart {
+        /// Cache TTL for the new daemon in milliseconds (default: keep the old daemon's)
+        #[arg(long)]
+        cache_ttl: Option<u64>,
+        /// Metrics address for the new daemon (default: keep the old daemon's)
+        #[arg(long, value_name = "ADDR")]
+        metrics_listen: Option<SocketAddr>,
+        /// Allow a --metrics-listen address that is not loopback (metrics are plain HTTP)
+        #[arg(long, requires = "metrics_listen")]
+        metrics_allow_remote: bool,
+        /// Tokens file for the new daemon (default: keep the old daemon's)
+        #[arg(long, value_name = "FILE")]
+        auth_tokens: Option<PathBuf>,
+    },
+    /// Manage the tokens accepted by `zjctl daemon --auth-tokens`
+    Token {
+        #[command(subcommand)]
+        cmd: TokenCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum TokenCommands {
+    /// Create a token (replacing one with the same name) and print it once
+    New {
+        name: String,
+        /// What the token may do
+        #[arg(long, value_enum, default_value_t = auth::Scope::Read)]
+        scope: auth::Scope,
+        /// Tokens file (default: ~/.config/zjctl/tokens)
+        #[arg(long, value_name = "FILE")]
+        file: Option<PathBuf>,
+    },
+    /// List token names and scopes
+    Ls {
+        /// Output JSON
+        #[arg(long)]
+        json: bool,
+        /// Tokens file (default: ~/.config/zjctl/tokens)
+        #[arg(long, value_name = "FILE")]
+        file: Option<PathBuf>,
+    },
+    /// Revoke a token
+    Rm {
+        name: String,
+        /// Tokens file (default: ~/.config/zjctl/tokens)
+        #[arg(long, value_name = "FILE")]
+        file: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+#[command(after_help = FLEET_HELP)]
+enum FleetCommands {
+    /// Launch one ssh pane per host that does not have one yet
+    Open {
+        /// Hosts file (`-` reads stdin)
+        hosts: String,
+        /// Pane arrangement
+        #[arg(long, value_enum, default_value = "grid")]
+        layout: FleetLayout,
+        /// Extra ssh arguments for every host (whitespace-separated)
+        #[arg(long, allow_hyphen_values = true)]
+        ssh_args: Option<String>,
+        /// Prefix for the pane names (`<prefix><host>`)
+        #[arg(long, default_value = "")]
+        prefix: String,
+        /// Print {host, name, selector, action} records as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+#[command(after_help = HOST_HELP)]
+enum HostCommands {
+    /// Run a program allowed by the plugin's host_exec_allow and print its output
+    Exec {
+        /// Working directory for the command
+        #[arg(long)]
+        cwd: Option<String>,
+        /// Stop waiting for the command after this many seconds (it keeps running; max 600)
+        #[arg(long, default_value = "30.0")]
+        timeout: f64,
+        /// Output exit code, stdout, and stderr as JSON
+        #[arg(long)]
+        json: bool,
+        /// Program and arguments (after --)
+        #[arg(last = true)]
+        argv: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+#[command(after_help = MARK_HELP)]
+enum MarkCommands {
+    /// Bookmark a pane under a mark (replacing whatever the mark pointed at)
+    Set {
+        /// Mark name, e.g. 1 or api
+        mark: String,
+        /// Pane selector (must match a single pane)
+        #[arg(long, default_value = "focused")]
+        pane: String,
+    },
+    /// Focus the pane under a mark
+    Jump { mark: String },
+    /// List marks
     Ls {
         /// Output as JSON
         #[arg(long)]
         json: bool,
     },
+    /// Remove a mark
+    Rm { mark: String },
+}
+
+#[derive(Subcommand, Debug)]
+#[command(after_help = IMPORT_HELP)]
+enum ImportCommands {
+    /// Translate a shell script of tmux commands into a zjctl script or layout
+    TmuxScript {
+        /// Script to translate (- for stdin)
+        file: String,
+        /// What to print: a shell script of zjctl commands, or a layout for `layout apply`
+        #[arg(long, value_enum, default_value = "script")]
+        to: commands::import::ImportFormat,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+#[command(after_help = SESSION_HELP)]
+enum SessionCommands {
+    /// List sessions, including exited ones
+    Ls {
+        #[command(flatten)]
+        output: formats::OutputArgs,
+    },
+    /// Print the command that attaches to a session
+    Attach {
+        name: String,
+        /// Print {session, command, exited, inside_zellij} as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Kill a running session, or delete an exited one
+    Kill {
+        name: String,
+        /// Kill the session zjctl targets (usually the one it runs inside)
+        #[arg(long)]
+        force: bool,
+        /// Print {session, action} as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Rename the current session (or the one given with --session)
+    Rename {
+        /// New session name
+        name: String,
+        /// Print {session, action, renamed_to} as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+#[command(after_help = TAG_HELP)]
+enum TagCommands {
+    /// Add a tag to panes
+    Add {
+        /// Tag name, e.g. backend
+        tag: String,
+        /// Pane selector
+        #[arg(long, default_value = "focused")]
+        pane: String,
+        /// Tag every matching pane
+        #[arg(long)]
+        all: bool,
+    },
+    /// Remove a tag from panes (from every pane without --pane)
+    Rm {
+        tag: String,
+        /// Pane selector
+        #[arg(long)]
+        pane: Option<String>,
+        /// Untag every matching pane
+        #[arg(long, requires = "pane")]
+        all: bool,
+    },
+    /// List tags and their panes
+    Ls {
+        #[command(flatten)]
+        output: formats::OutputArgs,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+#[command(after_help = GROUP_HELP)]
+enum GroupCommands {
+    /// Save a selector as a group (replacing a group of that name)
+    Create {
+        /// Group name, e.g. backend
+        name: String,
+        /// Member panes, e.g. cmd:uvicorn,cmd:redis
+        #[arg(long)]
+        panes: String,
+    },
+    /// List groups
+    Ls {
+        #[command(flatten)]
+        output: formats::OutputArgs,
+    },
+    /// Remove a group
+    Rm { name: String },
+}
+
+#[derive(Subcommand, Debug)]
+#[command(after_help = ASSERT_HELP)]
+enum AssertCommands {
+    /// Count and check panes (all panes, or those matching --where)
+    Panes {
+        /// Only check panes matching this selector
+        #[arg(long = "where", value_name = "SELECTOR")]
+        selector: Option<String>,
+        /// Exactly this many panes match
+        #[arg(long)]
+        count: Option<usize>,
+        /// At least this many panes match
+        #[arg(long)]
+        count_at_least: Option<usize>,
+        /// At most this many panes match
+        #[arg(long)]
+        count_at_most: Option<usize>,
+        /// No matching pane's command has exited
+        #[arg(long)]
+        running: bool,
+        /// Every matching pane is in this tab (name or index)
+        #[arg(long)]
+        tab: Option<String>,
+        /// Output the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+#[command(after_help = ALIAS_HELP)]
+enum AliasCommands {
+    /// Name a pane (replacing whatever the alias pointed at)
+    Set {
+        /// Alias, e.g. api
+        alias: String,
+        /// Pane selector (must match a single pane)
+        #[arg(default_value = "focused")]
+        pane: String,
+    },
+    /// List aliases
+    Ls {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Remove an alias
+    Rm { alias: String },
+}
+
+#[derive(Subcommand, Debug)]
+#[command(after_help = LAYOUT_HELP)]
+enum LayoutCommands {
+    /// Launch the named panes of a layout file that do not exist yet
+    Apply {
+        /// Layout file (KDL, or JSON with a .json extension)
+        file: String,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+#[command(after_help = SCHEDULE_HELP)]
+enum ScheduleCommands {
+    /// Add a periodic send
+    Add {
+        /// Pane selector (resolved at every send)
+        #[arg(long)]
+        pane: String,
+        /// Interval, e.g. 30s, 15m, 1h30m
+        #[arg(long)]
+        every: String,
+        /// Text to send (after --); \n, \r, \t are expanded
+        #[arg(last = true)]
+        text: Vec<String>,
+    },
+    /// List schedules
+    Ls {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Remove a schedule
+    Rm {
+        /// Schedule ID (from `schedule ls`)
+        id: u32,
+    },
+    /// Deliver due schedules until interrupted, logging each send as a JSON line
+    Run,
+}
+
+#[derive(Subcommand, Debug)]
+#[command(after_help = TAB_HELP)]
+enum TabCommands {
+    /// List tabs
+    Ls {
+        #[command(flatten)]
+        output: formats::OutputArgs,
+        /// Render each tab with a template, e.g. '{?active:*| }{index} {name}'
+        #[arg(long, conflicts_with = "json")]
+        format: Option<String>,
+    },
+    /// Open a new tab (it becomes the active tab)
+    New {
+        /// Tab name
+        #[arg(long)]
+        name: Option<String>,
+        /// Working directory for the tab
+        #[arg(long)]
+        cwd: Option<String>,
+    },
+    /// Rename a tab
+    Rename {
+        /// Tab to rename ("current", a tab index, or a tab name)
+        #[arg(long)]
+        tab: Option<String>,
+        /// New name
+        name: String,
+    },
+    /// Close a tab
+    Close {
+        /// Tab to close ("current", a tab index, or a tab name)
+        #[arg(long)]
+        tab: Option<String>,
+        /// Close even if it is the last tab (ends the session)
+        #[arg(long)]
+        force: bool,
+    },
+    /// Switch to a tab
+    Focus {
+        /// Tab to focus ("current", a tab index, or a tab name)
+        tab: String,
+    },
+    /// Move a tab left/right or to a position
+    Move {
+        /// Tab to move ("current", a tab index, or a tab name)
+        #[arg(long)]
+        tab: Option<String>,
+        /// "left", "right", or a target tab index
+        to: String,
+    },
+    /// Write one tab's panes (names, commands, cwds, sizes) as JSON
+    Export {
+        /// Tab to export ("current", a tab index, or a tab name)
+        #[arg(long)]
+        tab: Option<String>,
+        /// Write to a file instead of stdout
+        #[arg(long, short)]
+        output: Option<String>,
+    },
+    /// Recreate a tab written by `tab export` in a new tab
+    Import {
+        /// File written by `tab export`
+        file: String,
+        /// Name for the new tab (default: the exported tab's name)
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Even out the sizes of tiled panes in a tab
+    Balance {
+        /// Tab to balance ("current" or a tab index)
+        #[arg(long)]
+        tab: Option<String>,
+    },
+    /// Resize panes in a tab into a preset arrangement
+    Layout {
+        /// Preset (main-vertical, main-horizontal, even-horizontal, even-vertical, grid)
+        preset: String,
+        /// Tab to arrange ("current" or a tab index)
+        #[arg(long)]
+        tab: Option<String>,
+        /// Share of the row/column given to the main pane (percent, main-* presets)
+        #[arg(long, default_value = "60")]
+        main_size: u8,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -263,75 +1845,490 @@ enum PaneCommands {
         pane: String,
         /// Send to all matching panes
         #[arg(long)]
-        all: bool,
-        /// Send Enter after the text (true/false)
-        #[arg(long, action = ArgAction::Set, default_value_t = true)]
-        enter: bool,
-        /// Delay before sending Enter (seconds)
-        #[arg(long, default_value = "1.0")]
-        delay_enter: f64,
-        /// Bytes to send (after --)
-        #[arg(last = true)]
-        bytes: Vec<String>,
+        all: bool,
+        /// Send Enter after the text (true/false)
+        #[arg(long, action = ArgAction::Set, default_value_t = true)]
+        enter: bool,
+        /// Delay before sending Enter (seconds)
+        #[arg(long, default_value = "1.0")]
+        delay_enter: f64,
+        /// Bytes sent for Enter (cr, lf, crlf, or kitty's CSI 13 u) [default: the config's
+        /// [[enter-key]] rule for the pane, else lf]
+        #[arg(long, value_enum, env = "ZJCTL_ENTER_KEY")]
+        enter_key: Option<EnterKey>,
+        /// Split text longer than this many bytes into separate writes (0 = never, the default)
+        #[arg(long, default_value = "0")]
+        chunk_size: usize,
+        /// Pause between chunks (seconds)
+        #[arg(long, default_value = "0.05")]
+        chunk_delay: f64,
+        /// After each chunk, wait until the pane's screen stops changing (single target only)
+        #[arg(long)]
+        chunk_verify: bool,
+        /// Preview each matching pane and confirm (y/n/all/quit) before sending
+        #[arg(long, requires = "all")]
+        interactive: bool,
+        /// Read the text from stdin (implied when nothing follows -- and stdin is not a TTY)
+        #[arg(long, conflicts_with = "interactive")]
+        stdin: bool,
+        /// Skip the send if this key was already delivered within --idempotency-window
+        #[arg(long, conflicts_with = "interactive")]
+        idempotency_key: Option<String>,
+        /// How long an idempotency key is remembered (seconds)
+        #[arg(long, default_value = "600", requires = "idempotency_key")]
+        idempotency_window: f64,
+        /// Discard partially typed input first (ctrl-u: Ctrl+E Ctrl+U; ctrl-c: Ctrl+C)
+        #[arg(
+            long,
+            value_enum,
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "ctrl-u",
+            env = "ZJCTL_CLEAR_LINE"
+        )]
+        clear_line: Option<ClearLine>,
+        /// Leave scroll/search mode before sending to the focused pane
+        #[arg(long, conflicts_with = "force")]
+        exit_scroll: bool,
+        /// Send even if the focused pane is in scroll/search mode
+        #[arg(long)]
+        force: bool,
+        /// Send exact bytes: expand \e, \xHH, \n... in the text, or pass stdin through
+        /// untouched (no Enter is added)
+        #[arg(long, conflicts_with_all = ["hex", "interactive", "clear_line", "chunk_verify"])]
+        raw: bool,
+        /// Send the bytes written as hex digits, e.g. `1b 5b 41` (no Enter is added)
+        #[arg(long, conflicts_with_all = ["interactive", "clear_line", "chunk_verify", "stdin"])]
+        hex: bool,
+        /// Bytes to send (after --)
+        #[arg(last = true)]
+        bytes: Vec<String>,
+    },
+    /// Send symbolic keys (C-c, M-x, Enter, Up, F5, ...) to a pane
+    #[command(after_help = PANE_SEND_KEYS_HELP)]
+    SendKeys {
+        /// Pane selector
+        #[arg(long)]
+        pane: String,
+        /// Send to all matching panes
+        #[arg(long)]
+        all: bool,
+        /// Leave scroll/search mode before sending to the focused pane
+        #[arg(long, conflicts_with = "force")]
+        exit_scroll: bool,
+        /// Send even if the focused pane is in scroll/search mode
+        #[arg(long)]
+        force: bool,
+        /// Key names, sent in order
+        #[arg(required = true)]
+        keys: Vec<String>,
+    },
+    /// Focus a pane
+    #[command(after_help = PANE_FOCUS_HELP)]
+    Focus {
+        /// Pane selector
+        #[arg(long)]
+        pane: String,
+        /// Cycle: focus the match after the focused pane (for selectors matching several)
+        #[arg(long)]
+        next: bool,
+    },
+    /// Focus the previously focused pane
+    #[command(after_help = PANE_TOGGLE_HELP)]
+    Toggle,
+    /// Send Ctrl+C to a pane
+    #[command(after_help = PANE_INTERRUPT_HELP)]
+    Interrupt {
+        /// Pane selector
+        #[arg(long)]
+        pane: String,
+        /// Send to all matching panes
+        #[arg(long)]
+        all: bool,
+    },
+    /// Send Escape to a pane
+    #[command(after_help = PANE_ESCAPE_HELP)]
+    Escape {
+        /// Pane selector
+        #[arg(long)]
+        pane: String,
+        /// Send to all matching panes
+        #[arg(long)]
+        all: bool,
+    },
+    /// Capture pane output to stdout
+    #[command(after_help = PANE_CAPTURE_HELP)]
+    Capture {
+        /// Pane selector
+        #[arg(long)]
+        pane: String,
+        /// Include scrollback
+        #[arg(long)]
+        full: bool,
+        /// Keep focus on captured pane
+        #[arg(long)]
+        no_restore: bool,
+        /// Print the exact captured bytes as base64 instead of lossy UTF-8 text
+        #[arg(long, conflicts_with_all = ["lines", "since_offset", "json"])]
+        bytes_base64: bool,
+        /// Only the last N lines
+        #[arg(long, value_name = "N")]
+        lines: Option<usize>,
+        /// Only what was added after this offset token (from an earlier `--json` capture);
+        /// implies --full
+        #[arg(long, value_name = "TOKEN")]
+        since_offset: Option<String>,
+        /// Output JSON with the text and the offset token for the next capture
+        #[arg(long)]
+        json: bool,
+        /// Capture every matching pane, each under a `==> pane (title) <==` header
+        #[arg(long, conflicts_with_all = ["since_offset", "bytes_base64", "no_restore"])]
+        all: bool,
+        /// Only this part of the visible screen: top:5, bottom:2, or "rows 10-20, cols 0-80"
+        /// (0-based, end-exclusive)
+        #[arg(
+            long,
+            value_name = "REGION",
+            conflicts_with_all = ["full", "since_offset", "bytes_base64"]
+        )]
+        region: Option<CaptureRegion>,
+        /// Print what the pane showed this long ago, e.g. "2m ago" (from the snapshots of
+        /// `zjctl daemon --capture-history`)
+        #[arg(
+            long,
+            value_name = "WHEN",
+            conflicts_with_all = ["all", "full", "no_restore", "bytes_base64", "since_offset", "region"]
+        )]
+        at: Option<String>,
+        /// List the snapshots the daemon keeps of the pane
+        #[arg(
+            long,
+            conflicts_with_all = ["at", "all", "full", "no_restore", "bytes_base64", "lines", "since_offset", "region"]
+        )]
+        history_list: bool,
+    },
+    /// Show the texts recently sent to a pane through zjctl
+    #[command(after_help = PANE_HISTORY_HELP)]
+    History {
+        /// Pane selector
+        #[arg(long)]
+        pane: String,
+        /// Only the last N entries
+        #[arg(long)]
+        limit: Option<usize>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Float a tiled pane or embed a floating one
+    #[command(after_help = PANE_STATE_HELP)]
+    ToggleFloat {
+        /// Pane selector
+        #[arg(long, default_value = "focused")]
+        pane: String,
+        /// Toggle every matching pane
+        #[arg(long)]
+        all: bool,
+    },
+    /// Make a tiled pane fill its tab, or restore it
+    #[command(after_help = PANE_STATE_HELP)]
+    ToggleFullscreen {
+        /// Pane selector (must match a single pane)
+        #[arg(long, default_value = "focused")]
+        pane: String,
+    },
+    /// Keep a floating pane above the tiled panes
+    #[command(after_help = PANE_STATE_HELP)]
+    Pin {
+        /// Pane selector
+        #[arg(long, default_value = "focused")]
+        pane: String,
+        /// Pin every matching pane
+        #[arg(long)]
+        all: bool,
+        /// Unpin instead
+        #[arg(long)]
+        off: bool,
+    },
+    /// Clear a pane's screen and optionally its scrollback
+    #[command(after_help = PANE_CLEAR_HELP)]
+    Clear {
+        /// Pane selector
+        #[arg(long)]
+        pane: String,
+        /// Clear every matching pane
+        #[arg(long)]
+        all: bool,
+        /// Also drop the scrollback
+        #[arg(long)]
+        scrollback: bool,
+        /// Leave the screen alone and only drop the scrollback
+        #[arg(long, requires = "scrollback")]
+        keep_screen: bool,
+    },
+    /// Move panes to another tab or swap them with a neighbour
+    #[command(
+        after_help = PANE_MOVE_HELP,
+        group(clap::ArgGroup::new("target").required(true).multiple(true).args(["to_tab", "position"]))
+    )]
+    Move {
+        /// Pane selector
+        #[arg(long, default_value = "focused")]
+        pane: String,
+        /// Destination tab: index, name, `current`, or `new`
+        #[arg(long, value_name = "TAB")]
+        to_tab: Option<String>,
+        /// Swap with the neighbouring pane in this direction
+        #[arg(long, value_parser = ["left", "right", "up", "down"])]
+        position: Option<String>,
+        /// Name of the tab created by `--to-tab new`
+        #[arg(long, requires = "to_tab")]
+        tab_name: Option<String>,
+        /// Move every matching pane
+        #[arg(long)]
+        all: bool,
+        /// Follow the panes to their new tab
+        #[arg(long)]
+        focus: bool,
+    },
+    /// Record a pane's working directory for `cwd:` selectors
+    #[command(after_help = PANE_SET_CWD_HELP)]
+    SetCwd {
+        /// Pane selector
+        #[arg(long, default_value = "focused")]
+        pane: String,
+        /// Working directory (relative paths resolve against the current directory)
+        dir: String,
+    },
+    /// Wait for pane output to stop changing
+    #[command(after_help = PANE_WAIT_HELP)]
+    WaitIdle {
+        /// Pane selector
+        #[arg(long)]
+        pane: String,
+        /// How long output must remain unchanged (seconds)
+        #[arg(long, default_value = "2.0")]
+        idle_time: f64,
+        /// Maximum time to wait before erroring (seconds)
+        #[arg(long, default_value = "30.0")]
+        timeout: f64,
+        /// Include scrollback when checking for changes
+        #[arg(long)]
+        full: bool,
+        /// Keep focus on the pane after waiting
+        #[arg(long)]
+        no_restore: bool,
+        /// Rows not checked for changes, e.g. 0,-1 (negative rows count from the bottom)
+        #[arg(
+            long,
+            value_name = "ROWS",
+            value_delimiter = ',',
+            allow_hyphen_values = true
+        )]
+        ignore_rows: Vec<i64>,
+        /// Count cursor, spinner, and clock redraws as changes
+        #[arg(long)]
+        exact: bool,
+        /// Only watch this part of the visible screen: top:5, bottom:2, or "rows 10-20, cols 0-80"
+        #[arg(long, value_name = "REGION", conflicts_with = "full")]
+        region: Option<CaptureRegion>,
+        /// Emit machine-readable progress records on stderr
+        #[arg(long, value_enum)]
+        progress: Option<ProgressFormat>,
+    },
+    /// Wait until new pane output matches a regex and print the matching line
+    #[command(after_help = PANE_WAIT_FOR_HELP)]
+    WaitFor {
+        /// Pane selector (must match a single pane)
+        #[arg(long)]
+        pane: String,
+        /// Regex to look for in output written after the wait starts
+        #[arg(long = "match")]
+        pattern: String,
+        /// Maximum time to wait before erroring (seconds)
+        #[arg(long, default_value = "30.0")]
+        timeout: f64,
+        /// Include scrollback
+        #[arg(long)]
+        full: bool,
+        /// Only search this part of the visible screen: top:5, bottom:2, or "rows 10-20, cols 0-80"
+        #[arg(long, value_name = "REGION", conflicts_with = "full")]
+        region: Option<CaptureRegion>,
+        /// Print {pane, line, match, elapsed} as JSON
+        #[arg(long)]
+        json: bool,
+        /// Emit machine-readable progress records on stderr
+        #[arg(long, value_enum)]
+        progress: Option<ProgressFormat>,
+    },
+    /// Wait until a pane shows a shell or REPL prompt
+    #[command(after_help = PANE_WAIT_PROMPT_HELP)]
+    WaitPrompt {
+        /// Pane selector (must match a single pane)
+        #[arg(long)]
+        pane: String,
+        /// Prompt profile (sh, bash, zsh, fish, powershell, python, node, psql, or custom)
+        #[arg(long)]
+        profile: Option<String>,
+        /// Regex for the prompt line, replacing the profile's
+        #[arg(long)]
+        prompt: Option<String>,
+        /// Maximum time to wait before erroring (seconds)
+        #[arg(long, default_value = "30.0")]
+        timeout: f64,
+        /// Print {pane, profile, prompt, elapsed} as JSON
+        #[arg(long)]
+        json: bool,
+        /// Emit machine-readable progress records on stderr
+        #[arg(long, value_enum)]
+        progress: Option<ProgressFormat>,
+    },
+    /// Wait for the command of a command pane to exit and report its exit status
+    #[command(after_help = PANE_WAIT_EXIT_HELP)]
+    WaitExit {
+        /// Pane selector (must match a single pane)
+        #[arg(long)]
+        pane: String,
+        /// Maximum time to wait before erroring (seconds)
+        #[arg(long, default_value = "30.0")]
+        timeout: f64,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+        /// Emit machine-readable progress records on stderr
+        #[arg(long, value_enum)]
+        progress: Option<ProgressFormat>,
     },
-    /// Focus a pane
-    #[command(after_help = PANE_FOCUS_HELP)]
-    Focus {
+    /// Wait for a pane to be closed
+    #[command(after_help = PANE_WAIT_CLOSED_HELP)]
+    WaitClosed {
         /// Pane selector
         #[arg(long)]
         pane: String,
+        /// Maximum time to wait before erroring (seconds)
+        #[arg(long, default_value = "30.0")]
+        timeout: f64,
+        /// Succeed when no pane matches (it may already be closed) instead of failing
+        #[arg(long)]
+        allow_missing: bool,
+        /// Emit machine-readable progress records on stderr
+        #[arg(long, value_enum)]
+        progress: Option<ProgressFormat>,
     },
-    /// Send Ctrl+C to a pane
-    #[command(after_help = PANE_INTERRUPT_HELP)]
-    Interrupt {
-        /// Pane selector
+    /// Run a shell command in a pane and return its output and exit code
+    #[command(after_help = PANE_EXEC_HELP)]
+    Exec {
+        /// Pane selector (must match a single pane)
         #[arg(long)]
         pane: String,
-        /// Send to all matching panes
+        /// Maximum time to wait for the command (seconds)
+        #[arg(long, default_value = "60.0")]
+        timeout: f64,
+        /// Prompt profile of the pane's shell or REPL (default: sh)
         #[arg(long)]
-        all: bool,
+        profile: Option<String>,
+        /// Regex for the prompt line, replacing the profile's (REPL profiles)
+        #[arg(long)]
+        prompt: Option<String>,
+        /// Print {pane, exit_code, output, duration} as JSON
+        #[arg(long)]
+        json: bool,
+        /// Command to run (after --)
+        #[arg(last = true)]
+        command: Vec<String>,
     },
-    /// Send Escape to a pane
-    #[command(after_help = PANE_ESCAPE_HELP)]
-    Escape {
+    /// Run a shell command in several panes with bounded concurrency
+    #[command(after_help = PANE_RUN_HELP)]
+    Run {
         /// Pane selector
         #[arg(long)]
         pane: String,
-        /// Send to all matching panes
+        /// Run in every matching pane (otherwise the selector must match one)
         #[arg(long)]
         all: bool,
+        /// Maximum number of panes running the command at once
+        #[arg(long, default_value_t = 4)]
+        parallel: usize,
+        /// Maximum time to wait for each pane (seconds)
+        #[arg(long, default_value = "60.0")]
+        timeout: f64,
+        /// Prompt profile of the panes' shell or REPL (default: sh)
+        #[arg(long)]
+        profile: Option<String>,
+        /// Regex for the prompt line, replacing the profile's (REPL profiles)
+        #[arg(long)]
+        prompt: Option<String>,
+        /// Print {passed, failed, results} as JSON
+        #[arg(long)]
+        json: bool,
+        /// Emit machine-readable progress records on stderr
+        #[arg(long, value_enum)]
+        progress: Option<ProgressFormat>,
+        /// Command to run (after --)
+        #[arg(last = true)]
+        command: Vec<String>,
     },
-    /// Capture pane output to stdout
-    #[command(after_help = PANE_CAPTURE_HELP)]
-    Capture {
-        /// Pane selector
+    /// Run a command whenever a pane's content changes
+    #[command(after_help = PANE_ON_CHANGE_HELP)]
+    OnChange {
+        /// Pane selector (must match a single pane)
         #[arg(long)]
         pane: String,
-        /// Include scrollback
+        /// Shell command to run; {} becomes the path of a file with the changed lines
+        #[arg(long)]
+        exec: String,
+        /// Wait until the content has been stable this long before running (seconds)
+        #[arg(long, default_value = "1.0")]
+        debounce: f64,
+        /// Poll interval (seconds)
+        #[arg(long, default_value = "0.5")]
+        interval: f64,
+        /// Compare the full scrollback instead of the visible screen
         #[arg(long)]
         full: bool,
-        /// Keep focus on captured pane
+        /// Rows not checked for changes, e.g. 0,-1 (negative rows count from the bottom)
+        #[arg(
+            long,
+            value_name = "ROWS",
+            value_delimiter = ',',
+            allow_hyphen_values = true
+        )]
+        ignore_rows: Vec<i64>,
+        /// Count cursor, spinner, and clock redraws as changes
         #[arg(long)]
-        no_restore: bool,
+        exact: bool,
     },
-    /// Wait for pane output to stop changing
-    #[command(after_help = PANE_WAIT_HELP)]
-    WaitIdle {
-        /// Pane selector
+    /// Stream new pane output to stdout (like tail -f)
+    #[command(after_help = PANE_WATCH_HELP)]
+    Watch {
+        /// Pane selector (must match a single pane)
         #[arg(long)]
         pane: String,
-        /// How long output must remain unchanged (seconds)
-        #[arg(long, default_value = "2.0")]
-        idle_time: f64,
-        /// Maximum time to wait before erroring (seconds)
-        #[arg(long, default_value = "30.0")]
-        timeout: f64,
-        /// Include scrollback when checking for changes
+        /// Exit successfully once a line matches this regex
         #[arg(long)]
-        full: bool,
-        /// Keep focus on the pane after waiting
+        until: Option<String>,
+        /// Poll interval (seconds)
+        #[arg(long, default_value = "0.5")]
+        interval: f64,
+        /// Give up after this many seconds
         #[arg(long)]
-        no_restore: bool,
+        timeout: Option<f64>,
+    },
+    /// Forward new output lines from one pane into another
+    #[command(after_help = PANE_PIPE_HELP)]
+    Pipe {
+        /// Source pane selector
+        #[arg(long)]
+        from: String,
+        /// Destination pane selector
+        #[arg(long)]
+        to: String,
+        /// Only forward lines matching this regex
+        #[arg(long)]
+        filter: Option<String>,
+        /// Poll interval (seconds)
+        #[arg(long, default_value = "1.0")]
+        interval: f64,
     },
     /// Rename a pane
     #[command(after_help = PANE_RENAME_HELP)]
@@ -370,16 +2367,58 @@ enum PaneCommands {
         #[arg(long, default_value = "200")]
         max_steps: u32,
     },
+    /// Scroll a pane's viewport through its scrollback
+    #[command(after_help = PANE_SCROLL_HELP)]
+    #[command(group(clap::ArgGroup::new("scroll").required(true).args(["up", "down", "top", "bottom"])))]
+    Scroll {
+        /// Pane selector (must match a single pane)
+        #[arg(long)]
+        pane: String,
+        /// Scroll up (back in time)
+        #[arg(long)]
+        up: bool,
+        /// Scroll down
+        #[arg(long)]
+        down: bool,
+        /// Jump to the top of the scrollback
+        #[arg(long)]
+        top: bool,
+        /// Jump back to the live screen
+        #[arg(long)]
+        bottom: bool,
+        /// Scroll by pages instead of lines
+        #[arg(long, conflicts_with_all = ["half_page", "top", "bottom"])]
+        page: bool,
+        /// Scroll by half pages instead of lines
+        #[arg(long, conflicts_with_all = ["page", "top", "bottom"])]
+        half_page: bool,
+        /// How many lines (or pages) to scroll
+        #[arg(long, default_value_t = 1, conflicts_with_all = ["top", "bottom"])]
+        count: usize,
+    },
     /// Close a pane (refuses to close focused unless --force)
     #[command(after_help = PANE_CLOSE_HELP)]
     Close {
         /// Pane selector
         #[arg(long)]
         pane: String,
+        /// Close all matching panes
+        #[arg(long)]
+        all: bool,
         /// Force closing focused pane
         #[arg(long)]
         force: bool,
     },
+    /// Close every other pane in the target pane's tab
+    #[command(after_help = PANE_ONLY_HELP)]
+    Only {
+        /// Pane selector
+        #[arg(long)]
+        pane: String,
+        /// Do not ask for confirmation
+        #[arg(long, short = 'y')]
+        yes: bool,
+    },
     /// Launch a new pane and print its selector
     #[command(after_help = PANE_LAUNCH_HELP)]
     Launch {
@@ -393,8 +2432,19 @@ enum PaneCommands {
         #[arg(long)]
         name: Option<String>,
         /// Working directory for the new pane
-        #[arg(long)]
+        #[arg(long, conflicts_with = "cwd_query")]
         cwd: Option<String>,
+        /// Look the working directory up with the resolver (zoxide by default)
+        #[arg(long, value_name = "QUERY")]
+        cwd_query: Option<String>,
+        /// Shell command that prints a directory for --cwd-query; `{}` is the query
+        #[arg(
+            long,
+            value_name = "COMMAND",
+            env = "ZJCTL_CWD_RESOLVER",
+            default_value = commands::pane::DEFAULT_CWD_RESOLVER
+        )]
+        cwd_resolver: String,
         /// Close the pane when the command exits
         #[arg(long)]
         close_on_exit: bool,
@@ -404,6 +2454,9 @@ enum PaneCommands {
         /// Start the command suspended until Enter is pressed
         #[arg(long)]
         start_suspended: bool,
+        /// Export ZJCTL_PANE_TOKEN (generated unless given) and print a token: selector
+        #[arg(long, value_name = "TOKEN", num_args = 0..=1, default_missing_value = "")]
+        token: Option<String>,
         /// Command to run in the new pane (after --)
         #[arg(last = true)]
         command: Vec<String>,
@@ -411,23 +2464,149 @@ enum PaneCommands {
 }
 
 fn main() {
-    let cli = Cli::parse();
+    let (args, selector_session) = match zellij::extract_selector_session(std::env::args_os()) {
+        Ok(extracted) => extracted,
+        Err(e) => {
+            output::error(e);
+            std::process::exit(1);
+        }
+    };
+    let matches = cli_command().get_matches_from(args);
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    output::init(cli.color);
+
+    let json_errors = cli.json_errors;
+    let span = telemetry::command_span(&command_path(&matches));
+    let result = select_session(cli.session.clone(), selector_session)
+        .and_then(|()| run(cli))
+        // Whatever broke after Ctrl-C (typically the `zellij pipe` call it also ended) is
+        // fallout of the interrupt.
+        .map_err(|e| {
+            if interrupt::interrupted() {
+                Box::new(interrupt::Interrupted)
+            } else {
+                e
+            }
+        });
+    span.end(result.is_ok());
+    telemetry::flush();
+
+    if let Err(e) = result {
+        if let Some(exit) = e.downcast_ref::<commands::pane::CommandExit>() {
+            std::process::exit(exit.0);
+        }
+        if json_errors {
+            eprintln!("{}", client::error_json(e.as_ref()));
+        } else {
+            output::error(&e);
+        }
+        std::process::exit(client::exit_code(e.as_ref()));
+    }
+}
+
+/// The clap command with defaults from the config file
+fn cli_command() -> clap::Command {
+    match config::load() {
+        Ok(Some(config)) => {
+            let (command, mut unknown) = config.apply(Cli::command());
+            let (rules, problems) = config.enter_key_rules();
+            unknown.extend(problems);
+            let mut enter_keys = Vec::new();
+            for rule in rules {
+                match EnterKey::from_str(&rule.key, true) {
+                    Ok(key) => enter_keys.push((rule.pane, key)),
+                    Err(_) => unknown.push(format!(
+                        "[[enter-key]] for {}: unknown key {:?} (expected cr, lf, crlf, or kitty)",
+                        rule.pane, rule.key
+                    )),
+                }
+            }
+            commands::pane::set_enter_key_rules(enter_keys);
+            for problem in unknown {
+                output::warn(format!("{}: {problem}", config.path.display()));
+            }
+            command
+        }
+        Ok(None) => Cli::command(),
+        Err(err) => {
+            output::error(err);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn select_session(
+    flag: Option<String>,
+    selector: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let session = match (flag, selector) {
+        (Some(flag), Some(selector)) if flag != selector => {
+            return Err(
+                format!("--session {flag} conflicts with selector session:{selector}").into(),
+            );
+        }
+        (flag, selector) => flag.or(selector),
+    };
+    if session.is_some() {
+        zellij::set_session(session);
+    }
+    Ok(())
+}
 
-    if let Err(e) = run(cli) {
-        eprintln!("Error: {}", e);
-        std::process::exit(1);
+/// Full subcommand path, e.g. `zjctl pane send`
+fn command_path(matches: &ArgMatches) -> String {
+    let mut path = String::from("zjctl");
+    let mut current = matches;
+    while let Some((name, sub)) = current.subcommand() {
+        path.push(' ');
+        path.push_str(name);
+        current = sub;
     }
+    path
 }
 
 fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
     let plugin = cli.plugin.as_deref();
+    daemon::set_no_cache(cli.no_cache);
+    client::set_read_only(cli.read_only);
+    client::set_auto_start(cli.auto_start);
+    let rpc_timeout = cli
+        .rpc_timeout
+        .map(|secs| {
+            Duration::try_from_secs_f64(secs).map_err(|_| format!("invalid --timeout: {secs}"))
+        })
+        .transpose()?;
+    client::set_timeout(rpc_timeout);
+    outcome::set_policy(if cli.fail_fast {
+        outcome::FailurePolicy::FailFast
+    } else if cli.allow_partial {
+        outcome::FailurePolicy::AllowPartial
+    } else {
+        outcome::FailurePolicy::Complete
+    });
 
     match cli.command {
         Commands::Action { args } => {
             commands::action::run(&args)?;
         }
-        Commands::Doctor { json } => {
-            commands::doctor::run(plugin, json)?;
+        Commands::Doctor {
+            output,
+            watch,
+            interval,
+            fix,
+            dry_run,
+        } => {
+            if watch {
+                let interval = Duration::from_secs_f64(interval.max(0.1));
+                commands::doctor::watch(plugin, interval, output.format())?;
+            } else {
+                let fix = match (fix, dry_run) {
+                    (false, _) => commands::doctor::FixMode::Off,
+                    (true, false) => commands::doctor::FixMode::Apply,
+                    (true, true) => commands::doctor::FixMode::DryRun,
+                };
+                commands::doctor::run(plugin, output.format(), fix)?;
+            }
         }
         Commands::Help => {
             print_help_quickstart();
@@ -435,19 +2614,364 @@ fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
         Commands::Install {
             print,
             force,
+            download,
             load,
             auto_load,
             no_auto_load,
+            progress,
         } => {
             let auto_load = if no_auto_load { false } else { auto_load };
-            commands::install::run(plugin, print, force, load, auto_load)?;
+            commands::install::run(plugin, print, force, download, load, auto_load, progress)?;
+        }
+        Commands::Statusline { format } => {
+            commands::statusline::run(plugin, format.as_deref())?;
         }
-        Commands::Status { json } => {
-            commands::status::run(plugin, json)?;
+        Commands::Watchdog {
+            pane,
+            restart_cmd,
+            max_restarts,
+            backoff,
+        } => {
+            let options = commands::watchdog::WatchdogOptions {
+                selector: &pane,
+                restart_cmd: restart_cmd.as_deref(),
+                max_restarts,
+                backoff,
+            };
+            commands::watchdog::run(plugin, options)?;
+        }
+        Commands::Status { output } => {
+            commands::status::run(plugin, output.format())?;
+        }
+        Commands::Assert { cmd } => match cmd {
+            AssertCommands::Panes {
+                selector,
+                count,
+                count_at_least,
+                count_at_most,
+                running,
+                tab,
+                json,
+            } => {
+                let expect = commands::assert::PaneExpectations {
+                    selector: selector.as_deref(),
+                    count,
+                    count_at_least,
+                    count_at_most,
+                    running,
+                    tab: tab.as_deref(),
+                };
+                commands::assert::panes(plugin, &expect, json)?;
+            }
+        },
+        Commands::WaitFor {
+            pane,
+            timeout,
+            unique,
+            progress,
+        } => {
+            commands::wait_for::run(plugin, &pane, timeout, unique, progress)?;
         }
         Commands::Panes { cmd } => match cmd {
-            PanesCommands::Ls { json } => {
-                commands::panes::ls(plugin, json)?;
+            PanesCommands::Ls {
+                output,
+                format,
+                all_sessions,
+                tab,
+                pane_type,
+                title,
+                cmd,
+                floating,
+                focused_only,
+            } => {
+                let filter = commands::panes::LsFilter {
+                    tab: tab.as_deref(),
+                    pane_type,
+                    title: title.as_deref(),
+                    command: cmd.as_deref(),
+                    floating,
+                    focused_only,
+                }
+                .to_filter()?;
+                if all_sessions {
+                    if cli.session.is_some() {
+                        return Err("--all-sessions cannot be combined with --session".into());
+                    }
+                    commands::panes::ls_all_sessions(
+                        plugin,
+                        output.format(),
+                        format.as_deref(),
+                        &filter,
+                    )?;
+                } else {
+                    commands::panes::ls(plugin, output.format(), format.as_deref(), &filter)?;
+                }
+            }
+            PanesCommands::History { pane, limit, json } => {
+                commands::panes::history(plugin, &pane, limit, json)?;
+            }
+            PanesCommands::Prune { tab, dry_run } => {
+                commands::panes::prune(plugin, tab.as_deref(), dry_run)?;
+            }
+        },
+        Commands::Batch { file } => {
+            commands::batch::run(plugin, file.as_deref())?;
+        }
+        Commands::Daemon {
+            cmd,
+            cache_ttl,
+            metrics_listen,
+            metrics_allow_remote,
+            auth_tokens,
+            capture_history,
+            capture_interval,
+            capture_keep,
+        } => match cmd {
+            None => {
+                let auth_tokens = auth_tokens
+                    .map(|path| commands::daemon::tokens_path(path.as_deref()))
+                    .transpose()?;
+                commands::daemon::run(
+                    plugin,
+                    daemon::ServeOptions {
+                        cache_ttl: Duration::from_millis(cache_ttl),
+                        metrics_listen,
+                        metrics_allow_remote,
+                        auth_tokens,
+                        read_only: cli.read_only,
+                        capture_history: (!capture_history.is_empty()).then_some(
+                            history::RecorderOptions {
+                                selectors: capture_history,
+                                interval: capture_interval,
+                                keep: capture_keep,
+                                focused_only: cli.read_only,
+                            },
+                        ),
+                    },
+                )?;
+            }
+            Some(DaemonCommands::Status { json }) => commands::daemon::status(json)?,
+            Some(DaemonCommands::Stop) => commands::daemon::stop()?,
+            Some(DaemonCommands::Restart {
+                cache_ttl,
+                metrics_listen,
+                metrics_allow_remote,
+                auth_tokens,
+            }) => {
+                commands::daemon::restart(
+                    plugin,
+                    cache_ttl,
+                    metrics_listen,
+                    metrics_allow_remote,
+                    auth_tokens,
+                    cli.read_only,
+                )?;
+            }
+            Some(DaemonCommands::Token { cmd }) => match cmd {
+                TokenCommands::New { name, scope, file } => {
+                    commands::daemon::token_new(file.as_deref(), &name, scope)?;
+                }
+                TokenCommands::Ls { json, file } => {
+                    commands::daemon::token_ls(file.as_deref(), json)?;
+                }
+                TokenCommands::Rm { name, file } => {
+                    commands::daemon::token_rm(file.as_deref(), &name)?;
+                }
+            },
+        },
+        Commands::Broadcast {
+            tab,
+            all_tabs,
+            no_enter,
+            enter_key,
+            json,
+            text,
+        } => {
+            let options = commands::broadcast::BroadcastOptions {
+                tab: tab.as_deref(),
+                all_tabs,
+                enter: (!no_enter).then_some(enter_key),
+                json,
+            };
+            commands::broadcast::run(plugin, options, &text)?;
+        }
+        Commands::Events { events } => {
+            commands::events::run(plugin, &events)?;
+        }
+        Commands::On {
+            event,
+            pane,
+            persistent,
+            command,
+        } => {
+            let options = commands::on::OnOptions {
+                event,
+                selector: pane.as_deref(),
+                persistent,
+                command: &command,
+            };
+            commands::on::run(plugin, &options)?;
+        }
+        Commands::Fleet { cmd } => match cmd {
+            FleetCommands::Open {
+                hosts,
+                layout,
+                ssh_args,
+                prefix,
+                json,
+            } => {
+                let options = commands::fleet::FleetOptions {
+                    hosts: &hosts,
+                    layout,
+                    ssh_args: ssh_args.as_deref(),
+                    prefix: &prefix,
+                    json,
+                };
+                commands::fleet::open(plugin, options)?;
+            }
+        },
+        Commands::Host { cmd } => match cmd {
+            HostCommands::Exec {
+                cwd,
+                timeout,
+                json,
+                argv,
+            } => {
+                commands::host::exec(plugin, &argv, cwd.as_deref(), timeout, json)?;
+            }
+        },
+        Commands::Import { cmd } => match cmd {
+            ImportCommands::TmuxScript { file, to } => commands::import::tmux_script(&file, to)?,
+        },
+        Commands::Layout { cmd } => match cmd {
+            LayoutCommands::Apply { file, json } => {
+                commands::layout::apply(plugin, &file, json)?;
+            }
+        },
+        Commands::Group { cmd } => match cmd {
+            GroupCommands::Create { name, panes } => commands::group::create(&name, &panes)?,
+            GroupCommands::Ls { output } => commands::group::ls(output.format())?,
+            GroupCommands::Rm { name } => commands::group::rm(&name)?,
+        },
+        Commands::Alias { cmd } => match cmd {
+            AliasCommands::Set { alias, pane } => commands::mark::set(plugin, &alias, &pane)?,
+            AliasCommands::Ls { json } => commands::mark::ls(plugin, json)?,
+            AliasCommands::Rm { alias } => commands::mark::rm(plugin, &alias)?,
+        },
+        Commands::Session { cmd } => match cmd {
+            SessionCommands::Ls { output } => commands::session::ls(output.format())?,
+            SessionCommands::Attach { name, json } => commands::session::attach(&name, json)?,
+            SessionCommands::Kill { name, force, json } => {
+                commands::session::kill(&name, force, json)?;
+            }
+            SessionCommands::Rename { name, json } => commands::session::rename(&name, json)?,
+        },
+        Commands::Tag { cmd } => match cmd {
+            TagCommands::Add { tag, pane, all } => commands::tag::add(plugin, &tag, &pane, all)?,
+            TagCommands::Rm { tag, pane, all } => {
+                commands::tag::rm(plugin, &tag, pane.as_deref(), all)?;
+            }
+            TagCommands::Ls { output } => commands::tag::ls(plugin, output.format())?,
+        },
+        Commands::Mark { cmd } => match cmd {
+            MarkCommands::Set { mark, pane } => commands::mark::set(plugin, &mark, &pane)?,
+            MarkCommands::Jump { mark } => commands::mark::jump(plugin, &mark)?,
+            MarkCommands::Ls { json } => commands::mark::ls(plugin, json)?,
+            MarkCommands::Rm { mark } => commands::mark::rm(plugin, &mark)?,
+        },
+        Commands::Report {
+            panes,
+            all,
+            full,
+            tail,
+            redact,
+            redact_secrets,
+            output,
+        } => {
+            let options = commands::report::ReportOptions {
+                selector: &panes,
+                all,
+                full,
+                tail,
+                redact: &redact,
+                redact_secrets,
+                output: output.as_deref(),
+            };
+            commands::report::run(plugin, options)?;
+        }
+        Commands::Run {
+            cwd,
+            name,
+            tiled,
+            timeout,
+            keep,
+            json,
+            command,
+        } => {
+            let options = commands::run::RunOptions {
+                cwd: cwd.as_deref(),
+                name: name.as_deref(),
+                tiled,
+                timeout,
+                keep,
+                json,
+            };
+            commands::run::run(plugin, &command, &options)?;
+        }
+        Commands::Selftest {
+            timeout,
+            keep,
+            output,
+        } => {
+            let options = commands::selftest::SelftestOptions {
+                timeout,
+                keep,
+                output: output.format(),
+            };
+            commands::selftest::run(plugin, &options)?;
+        }
+        Commands::Schedule { cmd } => match cmd {
+            ScheduleCommands::Add { pane, every, text } => {
+                commands::schedule::add(&pane, &every, &text)?;
+            }
+            ScheduleCommands::Ls { json } => commands::schedule::ls(json)?,
+            ScheduleCommands::Rm { id } => commands::schedule::rm(id)?,
+            ScheduleCommands::Run => commands::schedule::run(plugin)?,
+        },
+        Commands::Tab { cmd } => match cmd {
+            TabCommands::Ls { output, format } => {
+                commands::tab::ls(plugin, output.format(), format.as_deref())?;
+            }
+            TabCommands::New { name, cwd } => {
+                commands::tab::new(plugin, name.as_deref(), cwd.as_deref())?;
+            }
+            TabCommands::Rename { tab, name } => {
+                commands::tab::rename(plugin, tab.as_deref(), &name)?;
+            }
+            TabCommands::Close { tab, force } => {
+                commands::tab::close(plugin, tab.as_deref(), force)?;
+            }
+            TabCommands::Focus { tab } => {
+                commands::tab::focus(plugin, &tab)?;
+            }
+            TabCommands::Move { tab, to } => {
+                commands::tab::move_tab(plugin, tab.as_deref(), &to)?;
+            }
+            TabCommands::Export { tab, output } => {
+                commands::tab::export(plugin, tab.as_deref(), output.as_deref())?;
+            }
+            TabCommands::Import { file, name } => {
+                commands::tab::import(plugin, &file, name.as_deref())?;
+            }
+            TabCommands::Balance { tab } => {
+                commands::tab::balance(plugin, tab.as_deref())?;
+            }
+            TabCommands::Layout {
+                preset,
+                tab,
+                main_size,
+            } => {
+                commands::tab::layout(plugin, &preset, tab.as_deref(), main_size)?;
             }
         },
         Commands::Pane { cmd } => match cmd {
@@ -455,13 +2979,72 @@ fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                 pane,
                 all,
                 enter,
+                enter_key,
                 delay_enter,
+                interactive,
+                chunk_size,
+                chunk_delay,
+                chunk_verify,
+                stdin,
+                idempotency_key,
+                idempotency_window,
+                clear_line,
+                exit_scroll,
+                force,
+                raw,
+                hex,
                 bytes,
             } => {
-                commands::pane::send(plugin, &pane, all, enter, delay_enter, &bytes)?;
+                let encoding = if raw {
+                    commands::pane::SendEncoding::Raw
+                } else if hex {
+                    commands::pane::SendEncoding::Hex
+                } else {
+                    commands::pane::SendEncoding::Text
+                };
+                let options = commands::pane::SendOptions {
+                    selector: &pane,
+                    all,
+                    enter,
+                    enter_key,
+                    delay_enter,
+                    bytes: &bytes,
+                    interactive,
+                    chunk_size,
+                    chunk_delay,
+                    chunk_verify,
+                    stdin,
+                    idempotency_key: idempotency_key.as_deref().map(|key| {
+                        commands::pane::IdempotencyKey {
+                            key,
+                            window: idempotency_window,
+                        }
+                    }),
+                    clear_line,
+                    scroll: commands::pane::ScrollGuard::from_flags(exit_scroll, force),
+                    encoding,
+                };
+                commands::pane::send(plugin, options)?;
+            }
+            PaneCommands::SendKeys {
+                pane,
+                all,
+                exit_scroll,
+                force,
+                keys,
+            } => {
+                let scroll = commands::pane::ScrollGuard::from_flags(exit_scroll, force);
+                commands::pane::send_keys(plugin, &pane, all, &keys, scroll)?;
             }
-            PaneCommands::Focus { pane } => {
-                commands::pane::focus(plugin, &pane)?;
+            PaneCommands::Focus { pane, next } => {
+                if next {
+                    commands::pane::focus_next(plugin, &pane)?;
+                } else {
+                    commands::pane::focus(plugin, &pane)?;
+                }
+            }
+            PaneCommands::Toggle => {
+                commands::pane::toggle(plugin)?;
             }
             PaneCommands::Interrupt { pane, all } => {
                 commands::pane::interrupt(plugin, &pane, all)?;
@@ -473,8 +3056,77 @@ fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                 pane,
                 full,
                 no_restore,
+                bytes_base64,
+                lines,
+                since_offset,
+                json,
+                all,
+                region,
+                at,
+                history_list,
+            } => {
+                let window = CaptureWindow {
+                    lines,
+                    since_offset,
+                };
+                if at.is_some() || history_list {
+                    commands::pane::capture_history(plugin, &pane, at.as_deref(), &window, json)?;
+                    return Ok(());
+                }
+                if all {
+                    commands::pane::capture_all(plugin, &pane, full, &window, region, json)?;
+                    return Ok(());
+                }
+                let options = commands::pane::CaptureOptions {
+                    selector: &pane,
+                    full,
+                    no_restore,
+                    bytes_base64,
+                    window,
+                    region,
+                    json,
+                };
+                commands::pane::capture(plugin, options)?;
+            }
+            PaneCommands::History { pane, limit, json } => {
+                commands::pane::history(plugin, &pane, limit, json)?;
+            }
+            PaneCommands::ToggleFloat { pane, all } => {
+                commands::pane::toggle_float(plugin, &pane, all)?;
+            }
+            PaneCommands::ToggleFullscreen { pane } => {
+                commands::pane::toggle_fullscreen(plugin, &pane)?;
+            }
+            PaneCommands::Pin { pane, all, off } => {
+                commands::pane::pin(plugin, &pane, all, !off)?;
+            }
+            PaneCommands::Clear {
+                pane,
+                all,
+                scrollback,
+                keep_screen,
+            } => {
+                commands::pane::clear(plugin, &pane, all, !keep_screen, scrollback)?;
+            }
+            PaneCommands::Move {
+                pane,
+                to_tab,
+                position,
+                tab_name,
+                all,
+                focus,
             } => {
-                commands::pane::capture(plugin, &pane, full, no_restore)?;
+                let options = commands::pane::MoveOptions {
+                    to_tab: to_tab.as_deref(),
+                    position: position.as_deref(),
+                    tab_name: tab_name.as_deref(),
+                    all,
+                    focus,
+                };
+                commands::pane::move_panes(plugin, &pane, &options)?;
+            }
+            PaneCommands::SetCwd { pane, dir } => {
+                commands::pane::set_cwd(plugin, &pane, &dir)?;
             }
             PaneCommands::WaitIdle {
                 pane,
@@ -482,8 +3134,157 @@ fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                 timeout,
                 full,
                 no_restore,
+                ignore_rows,
+                exact,
+                region,
+                progress,
+            } => {
+                let options = commands::pane::WaitIdleOptions {
+                    selector: &pane,
+                    idle_time,
+                    timeout,
+                    full,
+                    no_restore,
+                    mask: ChangeMask { ignore_rows, exact },
+                    region,
+                    progress,
+                };
+                commands::pane::wait_idle(plugin, options)?;
+            }
+            PaneCommands::WaitFor {
+                pane,
+                pattern,
+                timeout,
+                full,
+                region,
+                json,
+                progress,
+            } => {
+                let options = commands::pane::WaitMatchOptions {
+                    selector: &pane,
+                    pattern: &pattern,
+                    timeout,
+                    full,
+                    region,
+                    json,
+                    progress,
+                };
+                commands::pane::wait_match(plugin, options)?;
+            }
+            PaneCommands::WaitPrompt {
+                pane,
+                profile,
+                prompt,
+                timeout,
+                json,
+                progress,
+            } => {
+                let profile = commands::prompt::resolve(profile.as_deref(), prompt.as_deref())?;
+                let options = commands::pane::WaitPromptOptions {
+                    selector: &pane,
+                    profile: &profile,
+                    timeout,
+                    json,
+                    progress,
+                };
+                commands::pane::wait_prompt(plugin, options)?;
+            }
+            PaneCommands::WaitExit {
+                pane,
+                timeout,
+                json,
+                progress,
+            } => {
+                commands::pane::wait_exit(plugin, &pane, timeout, json, progress)?;
+            }
+            PaneCommands::WaitClosed {
+                pane,
+                timeout,
+                allow_missing,
+                progress,
+            } => {
+                commands::pane::wait_closed(plugin, &pane, timeout, allow_missing, progress)?;
+            }
+            PaneCommands::Exec {
+                pane,
+                timeout,
+                profile,
+                prompt,
+                json,
+                command,
+            } => {
+                let profile = commands::prompt::resolve(profile.as_deref(), prompt.as_deref())?;
+                commands::pane::exec(plugin, &pane, &command, &profile, timeout, json)?;
+            }
+            PaneCommands::Run {
+                pane,
+                all,
+                parallel,
+                timeout,
+                profile,
+                prompt,
+                json,
+                progress,
+                command,
+            } => {
+                let profile = commands::prompt::resolve(profile.as_deref(), prompt.as_deref())?;
+                let options = commands::pane::RunOptions {
+                    selector: &pane,
+                    all,
+                    parallel,
+                    timeout,
+                    json,
+                    progress,
+                    profile: &profile,
+                };
+                commands::pane::run(plugin, options, &command)?;
+            }
+            PaneCommands::OnChange {
+                pane,
+                exec,
+                debounce,
+                interval,
+                full,
+                ignore_rows,
+                exact,
+            } => {
+                let options = commands::pane::OnChangeOptions {
+                    selector: &pane,
+                    exec: &exec,
+                    debounce,
+                    interval,
+                    full,
+                    mask: ChangeMask { ignore_rows, exact },
+                };
+                commands::pane::on_change(plugin, options)?;
+            }
+            PaneCommands::Watch {
+                pane,
+                until,
+                interval,
+                timeout,
+            } => {
+                let options = commands::pane::WatchOptions {
+                    selector: &pane,
+                    until: until.as_deref(),
+                    interval,
+                    timeout,
+                };
+                commands::pane::watch(plugin, options)?;
+            }
+            PaneCommands::Pipe {
+                from,
+                to,
+                filter,
+                interval,
             } => {
-                commands::pane::wait_idle(plugin, &pane, idle_time, timeout, full, no_restore)?;
+                let options = commands::pane::PipeOptions {
+                    from: &from,
+                    to: &to,
+                    filter: filter.as_deref(),
+                    interval,
+                };
+                commands::pane::pipe(plugin, options)?;
             }
             PaneCommands::Rename { pane, name } => {
                 commands::pane::rename(plugin, &pane, &name)?;
@@ -512,19 +3313,53 @@ fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                     },
                 )?;
             }
-            PaneCommands::Close { pane, force } => {
-                commands::pane::close(plugin, &pane, force)?;
+            PaneCommands::Scroll {
+                pane,
+                up,
+                down,
+                top,
+                bottom,
+                page,
+                half_page,
+                count,
+            } => {
+                commands::pane::scroll(
+                    plugin,
+                    commands::pane::ScrollOptions {
+                        selector: &pane,
+                        up,
+                        down,
+                        top,
+                        bottom,
+                        page,
+                        half_page,
+                        count,
+                    },
+                )?;
+            }
+            PaneCommands::Close { pane, all, force } => {
+                commands::pane::close(plugin, &pane, all, force)?;
+            }
+            PaneCommands::Only { pane, yes } => {
+                commands::pane::only(plugin, &pane, yes)?;
             }
             PaneCommands::Launch {
                 direction,
                 floating,
                 name,
                 cwd,
+                cwd_query,
+                cwd_resolver,
                 close_on_exit,
                 in_place,
                 start_suspended,
+                token,
                 command,
             } => {
+                let cwd = match cwd_query {
+                    Some(query) => Some(commands::pane::resolve_cwd(&cwd_resolver, &query)?),
+                    None => cwd,
+                };
                 let options = commands::pane::LaunchOptions {
                     direction: direction.as_deref(),
                     floating,
@@ -535,7 +3370,7 @@ fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                     start_suspended,
                     command: &command,
                 };
-                commands::pane::launch(plugin, options)?;
+                commands::pane::launch(plugin, options, token.as_deref())?;
             }
         },
     }
@@ -0,0 +1,315 @@
+//! Prometheus metrics for `zjctl daemon --metrics-listen`
+//!
+//! The daemon records every RPC it forwards (count, latency, and errors by method and code),
+//! its `panes.list` cache hits, and the pane count of the latest full listing. Watchers and
+//! scheduled sends run in their own processes, so they show up here through the methods they
+//! call. Everything is rendered in the text exposition format on `GET /metrics`.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use zjctl_proto::{methods, RpcRequest, RpcResponse};
+
+use crate::daemon::DaemonStatus;
+
+/// Upper bounds (seconds) of the RPC latency histogram buckets
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    methods: BTreeMap<String, MethodStats>,
+    cache_hits: u64,
+    /// Panes in the latest unfiltered `panes.list` answer
+    panes: Option<usize>,
+}
+
+#[derive(Debug, Default)]
+struct MethodStats {
+    requests: u64,
+    /// Error code (`no_match`, ..., or `transport` when the pipe failed) -> count
+    errors: BTreeMap<String, u64>,
+    /// Cumulative counts per bucket of `LATENCY_BUCKETS`
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    seconds: f64,
+}
+
+impl Metrics {
+    /// Record a forwarded batch: `Err` when the daemon could not get answers from the plugin
+    pub fn record(
+        &self,
+        requests: &[RpcRequest],
+        responses: Result<&[RpcResponse], ()>,
+        elapsed: Duration,
+    ) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let seconds = elapsed.as_secs_f64();
+        for request in requests {
+            let response = responses
+                .ok()
+                .and_then(|responses| responses.iter().find(|r| r.id == request.id));
+            let error = match response {
+                None => Some("transport".to_string()),
+                Some(response) => response.error.as_ref().map(|error| {
+                    serde_json::to_value(error.code)
+                        .ok()
+                        .and_then(|code| code.as_str().map(str::to_string))
+                        .unwrap_or_else(|| "unknown".to_string())
+                }),
+            };
+            if let Some(response) = response.filter(|response| response.ok) {
+                if request.method == methods::PANES_LIST && request.params.get("selector").is_none()
+                {
+                    if let Some(count) = pane_count(response) {
+                        inner.panes = Some(count);
+                    }
+                }
+            }
+            let stats = inner.methods.entry(request.method.clone()).or_default();
+            stats.requests += 1;
+            stats.seconds += seconds;
+            for (bucket, bound) in stats.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+                if seconds <= bound {
+                    *bucket += 1;
+                }
+            }
+            if let Some(code) = error {
+                *stats.errors.entry(code).or_default() += 1;
+            }
+        }
+    }
+
+    /// A `panes.list` answered from the daemon's snapshot
+    pub fn cache_hit(&self) {
+        self.inner
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .cache_hits += 1;
+    }
+
+    /// Text exposition of the recorded metrics plus the daemon's current `status`
+    pub fn render(&self, status: &DaemonStatus) -> String {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, samples: &[(String, String)]| {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} {kind}");
+            for (labels, value) in samples {
+                let _ = writeln!(out, "{name}{labels} {value}");
+            }
+        };
+        let plain = |value: String| vec![(String::new(), value)];
+
+        metric(
+            "zjctl_daemon_uptime_seconds",
+            "gauge",
+            "Seconds since the daemon started",
+            &plain(status.uptime_secs.to_string()),
+        );
+        metric(
+            "zjctl_daemon_batches_total",
+            "counter",
+            "RPC batches answered, including cache hits",
+            &plain(status.requests.to_string()),
+        );
+        metric(
+            "zjctl_daemon_cache_hits_total",
+            "counter",
+            "panes.list calls answered from the daemon's snapshot",
+            &plain(inner.cache_hits.to_string()),
+        );
+        metric(
+            "zjctl_daemon_clients",
+            "gauge",
+            "Client connections being served",
+            &plain(status.clients.to_string()),
+        );
+        metric(
+            "zjctl_daemon_pending_requests",
+            "gauge",
+            "Requests waiting on the plugin",
+            &plain(status.pending.to_string()),
+        );
+        metric(
+            "zjctl_daemon_pipes_spawned_total",
+            "counter",
+            "zellij pipe processes started",
+            &plain(status.pipes_spawned.to_string()),
+        );
+        if let Some(panes) = inner.panes {
+            metric(
+                "zjctl_panes",
+                "gauge",
+                "Panes in the latest full panes.list answer",
+                &plain(panes.to_string()),
+            );
+        }
+
+        let requests: Vec<_> = inner
+            .methods
+            .iter()
+            .map(|(method, stats)| (method_label(method), stats.requests.to_string()))
+            .collect();
+        metric(
+            "zjctl_rpc_requests_total",
+            "counter",
+            "RPC requests forwarded to the plugin, by method",
+            &requests,
+        );
+        let errors: Vec<_> = inner
+            .methods
+            .iter()
+            .flat_map(|(method, stats)| {
+                stats.errors.iter().map(move |(code, count)| {
+                    (
+                        format!(
+                            "{{method=\"{}\",code=\"{}\"}}",
+                            escape(method),
+                            escape(code)
+                        ),
+                        count.to_string(),
+                    )
+                })
+            })
+            .collect();
+        metric(
+            "zjctl_rpc_errors_total",
+            "counter",
+            "Failed RPC requests, by method and error code",
+            &errors,
+        );
+        let mut latency = Vec::new();
+        for (method, stats) in &inner.methods {
+            let method = escape(method);
+            for (count, bound) in stats.buckets.iter().zip(LATENCY_BUCKETS) {
+                latency.push((
+                    format!("_bucket{{method=\"{method}\",le=\"{bound}\"}}"),
+                    count.to_string(),
+                ));
+            }
+            latency.push((
+                format!("_bucket{{method=\"{method}\",le=\"+Inf\"}}"),
+                stats.requests.to_string(),
+            ));
+            latency.push((
+                format!("_sum{{method=\"{method}\"}}"),
+                stats.seconds.to_string(),
+            ));
+            latency.push((
+                format!("_count{{method=\"{method}\"}}"),
+                stats.requests.to_string(),
+            ));
+        }
+        metric(
+            "zjctl_rpc_duration_seconds",
+            "histogram",
+            "Time from forwarding a batch to its last answer, by method",
+            &latency,
+        );
+        out
+    }
+}
+
+fn method_label(method: &str) -> String {
+    format!("{{method=\"{}\"}}", escape(method))
+}
+
+/// Label values escape backslash, double quote, and newline
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n")
+}
+
+/// Number of panes in a `panes.list` result (envelope or bare array)
+fn pane_count(response: &RpcResponse) -> Option<usize> {
+    let result = response.result.as_ref()?;
+    result
+        .get("panes")
+        .unwrap_or(result)
+        .as_array()
+        .map(Vec::len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zjctl_proto::{RpcError, RpcErrorCode};
+
+    fn status() -> DaemonStatus {
+        DaemonStatus {
+            pid: 1,
+            plugin: "file:/tmp/zrpc.wasm".to_string(),
+            uptime_secs: 42,
+            cache_ttl_ms: 500,
+            cache_age_ms: None,
+            requests: 3,
+            clients: 0,
+            pending: 0,
+            pipe_running: true,
+            pipes_spawned: 1,
+            metrics_listen: None,
+            auth_tokens: None,
+            read_only: false,
+            capture_history: Vec::new(),
+            capture_interval_secs: 0,
+            capture_keep: 0,
+            workers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn render_counts_requests_errors_and_latency() {
+        let metrics = Metrics::default();
+        let list = RpcRequest::new(methods::PANES_LIST);
+        let listed = RpcResponse::success(
+            list.id,
+            serde_json::json!({"panes": [{"id": "terminal:1"}, {"id": "terminal:2"}]}),
+        )
+        .unwrap();
+        metrics.record(
+            std::slice::from_ref(&list),
+            Ok(std::slice::from_ref(&listed)),
+            Duration::from_millis(30),
+        );
+        let focus = RpcRequest::new(methods::PANE_FOCUS);
+        let failed = RpcResponse::error(focus.id, RpcError::new(RpcErrorCode::NoMatch, "none"));
+        metrics.record(
+            std::slice::from_ref(&focus),
+            Ok(std::slice::from_ref(&failed)),
+            Duration::from_millis(2),
+        );
+        metrics.record(
+            std::slice::from_ref(&focus),
+            Err(()),
+            Duration::from_secs(20),
+        );
+
+        let text = metrics.render(&status());
+        assert!(text.contains("zjctl_daemon_uptime_seconds 42\n"));
+        assert!(text.contains("zjctl_panes 2\n"));
+        assert!(text.contains("zjctl_rpc_requests_total{method=\"pane.focus\"} 2\n"));
+        assert!(
+            text.contains("zjctl_rpc_errors_total{method=\"pane.focus\",code=\"no_match\"} 1\n")
+        );
+        assert!(
+            text.contains("zjctl_rpc_errors_total{method=\"pane.focus\",code=\"transport\"} 1\n")
+        );
+        assert!(text
+            .contains("zjctl_rpc_duration_seconds_bucket{method=\"panes.list\",le=\"0.025\"} 0\n"));
+        assert!(text
+            .contains("zjctl_rpc_duration_seconds_bucket{method=\"panes.list\",le=\"0.05\"} 1\n"));
+        assert!(text
+            .contains("zjctl_rpc_duration_seconds_bucket{method=\"pane.focus\",le=\"+Inf\"} 2\n"));
+        assert!(text.contains("# TYPE zjctl_rpc_duration_seconds histogram\n"));
+    }
+}
@@ -0,0 +1,256 @@
+//! Results of operations on several items (`batch`, `pane capture --all`, `pane close --all`,
+//! `pane run --all`, `tab balance`/`layout`)
+//!
+//! Every item is attempted and the command fails once they are all done if any of them failed,
+//! with the counts and the first failure in the error. `--fail-fast` stops at the first failure
+//! instead (the remaining items are reported as skipped); `--allow-partial` only fails when
+//! nothing succeeded and warns about the failures otherwise. Items handled by the plugin in a
+//! single call (`pane close --all`) are all attempted either way.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use serde::Serialize;
+
+use crate::output;
+
+/// What a failed item means for the rest of the operation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FailurePolicy {
+    /// Attempt every item; fail if any failed
+    #[default]
+    Complete,
+    /// Stop at the first failure
+    FailFast,
+    /// Attempt every item; fail only if none succeeded
+    AllowPartial,
+}
+
+static POLICY: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_policy(policy: FailurePolicy) {
+    POLICY.store(policy as u8, Ordering::Relaxed);
+}
+
+pub fn policy() -> FailurePolicy {
+    match POLICY.load(Ordering::Relaxed) {
+        1 => FailurePolicy::FailFast,
+        2 => FailurePolicy::AllowPartial,
+        _ => FailurePolicy::Complete,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Ok,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemOutcome {
+    /// What the item was, e.g. `id:terminal:3` or `#2 pane.send`
+    pub item: String,
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Per-item results of one operation, in the order the items were handled
+#[derive(Debug)]
+pub struct Outcomes {
+    policy: FailurePolicy,
+    items: Vec<ItemOutcome>,
+}
+
+impl Default for Outcomes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Outcomes {
+    /// Outcomes under the policy set with `--fail-fast` / `--allow-partial`
+    pub fn new() -> Self {
+        Self::with_policy(policy())
+    }
+
+    pub fn with_policy(policy: FailurePolicy) -> Self {
+        Self {
+            policy,
+            items: Vec::new(),
+        }
+    }
+
+    pub fn ok(&mut self, item: impl Into<String>) {
+        self.push(item.into(), Status::Ok, None);
+    }
+
+    pub fn failed(&mut self, item: impl Into<String>, error: impl fmt::Display) {
+        self.push(item.into(), Status::Failed, Some(error.to_string()));
+    }
+
+    pub fn skipped(&mut self, item: impl Into<String>) {
+        self.push(item.into(), Status::Skipped, None);
+    }
+
+    fn push(&mut self, item: String, status: Status, error: Option<String>) {
+        self.items.push(ItemOutcome {
+            item,
+            status,
+            error,
+        });
+    }
+
+    /// Whether the next item should be attempted
+    pub fn keep_going(&self) -> bool {
+        self.policy != FailurePolicy::FailFast || self.count(Status::Failed) == 0
+    }
+
+    fn count(&self, status: Status) -> usize {
+        self.items
+            .iter()
+            .filter(|item| item.status == status)
+            .count()
+    }
+
+    pub fn summary(&self) -> Summary {
+        summarize(&self.items)
+    }
+
+    /// `Err` when the failures count under the policy; `noun` names the items (`panes`)
+    pub fn finish(self, noun: &'static str) -> Result<(), Failures> {
+        let summary = self.summary();
+        if summary.failed == 0 {
+            return Ok(());
+        }
+        let failures = Failures {
+            noun,
+            items: self.items,
+        };
+        if self.policy == FailurePolicy::AllowPartial && summary.succeeded > 0 {
+            output::warn(format_args!("{failures} (allowed by --allow-partial)"));
+            return Ok(());
+        }
+        Err(failures)
+    }
+}
+
+fn summarize(items: &[ItemOutcome]) -> Summary {
+    let count = |status| items.iter().filter(|item| item.status == status).count();
+    Summary {
+        total: items.len(),
+        succeeded: count(Status::Ok),
+        failed: count(Status::Failed),
+        skipped: count(Status::Skipped),
+        first_failure: items
+            .iter()
+            .find(|item| item.status == Status::Failed)
+            .cloned(),
+    }
+}
+
+/// Counts and the first failure of an operation
+#[derive(Debug, Clone, Serialize)]
+pub struct Summary {
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub first_failure: Option<ItemOutcome>,
+}
+
+/// Some or all items of an operation failed (exit status 16)
+#[derive(Debug)]
+pub struct Failures {
+    noun: &'static str,
+    pub items: Vec<ItemOutcome>,
+}
+
+impl Failures {
+    pub fn summary(&self) -> Summary {
+        summarize(&self.items)
+    }
+
+    /// `{summary, items}` for `--json-errors`
+    pub fn report(&self) -> serde_json::Value {
+        serde_json::json!({ "summary": self.summary(), "items": self.items })
+    }
+}
+
+impl fmt::Display for Failures {
+    /// `2 of 5 panes failed, 1 skipped; first failure: id:terminal:3: no panes match`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let summary = self.summary();
+        write!(
+            f,
+            "{} of {} {} failed",
+            summary.failed, summary.total, self.noun
+        )?;
+        if summary.skipped > 0 {
+            write!(f, ", {} skipped", summary.skipped)?;
+        }
+        if let Some(first) = &summary.first_failure {
+            write!(f, "; first failure: {}", first.item)?;
+            if let Some(error) = &first.error {
+                write!(f, ": {error}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Failures {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(policy: FailurePolicy, results: &[bool]) -> (Outcomes, usize) {
+        let mut outcomes = Outcomes::with_policy(policy);
+        let mut attempted = 0;
+        for (i, &ok) in results.iter().enumerate() {
+            let item = format!("item{i}");
+            if !outcomes.keep_going() {
+                outcomes.skipped(item);
+                continue;
+            }
+            attempted += 1;
+            if ok {
+                outcomes.ok(item);
+            } else {
+                outcomes.failed(item, "boom");
+            }
+        }
+        (outcomes, attempted)
+    }
+
+    #[test]
+    fn policies_decide_how_far_to_go_and_whether_to_fail() {
+        let (outcomes, attempted) = run(FailurePolicy::Complete, &[true, false, true, false]);
+        assert_eq!(attempted, 4);
+        let err = outcomes.finish("panes").unwrap_err();
+        assert_eq!(err.summary().succeeded, 2);
+        assert_eq!(
+            err.to_string(),
+            "2 of 4 panes failed; first failure: item1: boom"
+        );
+
+        let (outcomes, attempted) = run(FailurePolicy::FailFast, &[true, false, true, false]);
+        assert_eq!(attempted, 2);
+        let err = outcomes.finish("panes").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "1 of 4 panes failed, 2 skipped; first failure: item1: boom"
+        );
+        assert_eq!(err.items[3].status, Status::Skipped);
+
+        let (outcomes, _) = run(FailurePolicy::AllowPartial, &[true, false]);
+        assert!(outcomes.finish("panes").is_ok());
+        let (outcomes, _) = run(FailurePolicy::AllowPartial, &[false, false]);
+        assert!(outcomes.finish("panes").is_err());
+        let (outcomes, _) = run(FailurePolicy::FailFast, &[true, true]);
+        assert!(outcomes.finish("panes").is_ok());
+    }
+}
@@ -0,0 +1,176 @@
+//! Terminal styling shared by every command: color and table widths.
+//!
+//! Color is decided once at startup from `--color` (`auto` by default). `auto` colors a stream
+//! only when it is a terminal, `NO_COLOR` is unset or empty (<https://no-color.org>), and `TERM`
+//! is not `dumb`; `always` and `never` override all of that. Tables fit their free-text column
+//! to the terminal width (`COLUMNS`, then the terminal's own size) and keep fixed widths when
+//! stdout is not a terminal, so piped output does not depend on who runs it.
+
+use std::fmt::Display;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use clap::ValueEnum;
+
+/// `--color`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color terminals unless NO_COLOR is set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+static STDOUT_COLOR: AtomicBool = AtomicBool::new(false);
+static STDERR_COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Decide coloring for stdout and stderr; call once before printing anything
+pub fn init(choice: ColorChoice) {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    let dumb = std::env::var_os("TERM").is_some_and(|term| term == "dumb");
+    STDOUT_COLOR.store(
+        use_color(choice, no_color, dumb, std::io::stdout().is_terminal()),
+        Ordering::Relaxed,
+    );
+    STDERR_COLOR.store(
+        use_color(choice, no_color, dumb, std::io::stderr().is_terminal()),
+        Ordering::Relaxed,
+    );
+}
+
+fn use_color(choice: ColorChoice, no_color: bool, dumb: bool, terminal: bool) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => terminal && !no_color && !dumb,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Bold,
+    Dim,
+    Red,
+    Green,
+    Yellow,
+}
+
+impl Style {
+    fn code(self) -> &'static str {
+        match self {
+            Style::Bold => "1",
+            Style::Dim => "2",
+            Style::Red => "31",
+            Style::Green => "32",
+            Style::Yellow => "33",
+        }
+    }
+}
+
+fn styled(enabled: bool, style: Style, text: &str) -> String {
+    if enabled && !text.is_empty() {
+        format!("\x1b[{}m{text}\x1b[0m", style.code())
+    } else {
+        text.to_string()
+    }
+}
+
+/// `text` styled for stdout. Pad before painting: escape codes count toward `{:<N}` widths.
+pub fn paint(style: Style, text: &str) -> String {
+    styled(STDOUT_COLOR.load(Ordering::Relaxed), style, text)
+}
+
+/// `text` styled for stderr
+pub fn paint_err(style: Style, text: &str) -> String {
+    styled(STDERR_COLOR.load(Ordering::Relaxed), style, text)
+}
+
+/// Print `warning: <message>` to stderr
+pub fn warn(message: impl Display) {
+    eprintln!("{} {message}", paint_err(Style::Yellow, "warning:"));
+}
+
+/// Print `Error: <message>` to stderr
+pub fn error(message: impl Display) {
+    eprintln!("{} {message}", paint_err(Style::Red, "Error:"));
+}
+
+/// Bold table header line
+pub fn header(line: &str) -> String {
+    paint(Style::Bold, line)
+}
+
+/// Dashed line under a table header, `width` wide or the terminal width if narrower
+pub fn rule(width: usize) -> String {
+    let width = self::width().map_or(width, |columns| columns.min(width));
+    paint(Style::Dim, &"-".repeat(width))
+}
+
+/// Columns available on stdout: `COLUMNS` if set, else the terminal size; `None` when stdout is
+/// not a terminal
+pub fn width() -> Option<usize> {
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .filter(|&columns: &usize| columns > 0)
+        .or_else(|| terminal_size::terminal_size().map(|(width, _)| usize::from(width.0)))
+}
+
+/// Width for a table's flexible column: what is left of the terminal after `fixed` columns, at
+/// least `min`; `default` when stdout is not a terminal
+pub fn flex_width(fixed: usize, min: usize, default: usize) -> usize {
+    flex(width(), fixed, min, default)
+}
+
+fn flex(width: Option<usize>, fixed: usize, min: usize, default: usize) -> usize {
+    width.map_or(default, |width| width.saturating_sub(fixed).max(min))
+}
+
+/// Cut `s` to at most `max` characters, ending in `...` when shortened
+pub fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        return s.to_string();
+    }
+    let kept: String = s.chars().take(max.saturating_sub(3)).collect();
+    format!("{kept}...")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_color_needs_terminal_and_no_opt_out() {
+        assert!(use_color(ColorChoice::Auto, false, false, true));
+        assert!(!use_color(ColorChoice::Auto, true, false, true));
+        assert!(!use_color(ColorChoice::Auto, false, true, true));
+        assert!(!use_color(ColorChoice::Auto, false, false, false));
+        assert!(use_color(ColorChoice::Always, true, true, false));
+        assert!(!use_color(ColorChoice::Never, false, false, true));
+    }
+
+    #[test]
+    fn styled_wraps_only_when_enabled() {
+        assert_eq!(styled(true, Style::Red, "fail"), "\x1b[31mfail\x1b[0m");
+        assert_eq!(styled(false, Style::Red, "fail"), "fail");
+        assert_eq!(styled(true, Style::Red, ""), "");
+    }
+
+    #[test]
+    fn flex_fills_terminal_with_floor() {
+        assert_eq!(flex(Some(120), 80, 10, 30), 40);
+        assert_eq!(flex(Some(60), 80, 10, 30), 10);
+        assert_eq!(flex(None, 80, 10, 30), 30);
+    }
+
+    #[test]
+    fn truncate_counts_characters() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("abcdefghij", 8), "abcde...");
+        assert_eq!(truncate("ééééééé", 5), "éé...");
+    }
+}
@@ -0,0 +1,171 @@
+//! Platform-aware home/config discovery and `file:` plugin URL handling.
+//!
+//! Windows has no `HOME` by default and uses drive-letter paths with backslashes; these helpers
+//! keep install, doctor, and the client producing paths Zellij can load on every platform.
+
+use std::path::{Path, PathBuf};
+
+/// User home directory: `HOME`, then `USERPROFILE`, then `HOMEDRIVE` + `HOMEPATH`
+pub fn home_dir() -> Option<PathBuf> {
+    if let Some(home) = non_empty_var("HOME") {
+        return Some(PathBuf::from(home));
+    }
+    if let Some(profile) = non_empty_var("USERPROFILE") {
+        return Some(PathBuf::from(profile));
+    }
+    match (non_empty_var("HOMEDRIVE"), non_empty_var("HOMEPATH")) {
+        (Some(drive), Some(path)) => Some(PathBuf::from(format!("{drive}{path}"))),
+        _ => None,
+    }
+}
+
+/// Base configuration directory (the parent of `zellij/`)
+pub fn config_dir() -> Option<PathBuf> {
+    if let Some(dir) = non_empty_var("XDG_CONFIG_HOME") {
+        return Some(PathBuf::from(dir));
+    }
+    if cfg!(windows) {
+        if let Some(dir) = non_empty_var("APPDATA") {
+            return Some(PathBuf::from(dir));
+        }
+        return home_dir();
+    }
+    home_dir().map(|home| home.join(".config"))
+}
+
+/// Directory for zjctl's own persistent state (`$XDG_STATE_HOME/zjctl`)
+pub fn state_dir() -> Option<PathBuf> {
+    let base = if let Some(dir) = non_empty_var("XDG_STATE_HOME") {
+        PathBuf::from(dir)
+    } else if cfg!(windows) {
+        non_empty_var("LOCALAPPDATA")
+            .map(PathBuf::from)
+            .or_else(home_dir)?
+    } else {
+        home_dir()?.join(".local").join("state")
+    };
+    Some(base.join("zjctl"))
+}
+
+/// Format a local path as a Zellij `file:` URL, using forward slashes so drive-letter paths
+/// survive KDL quoting and shell copy/paste (`file:C:/Users/me/zrpc.wasm`).
+pub fn file_url(path: &Path) -> String {
+    format!("file:{}", to_slash(&path.display().to_string()))
+}
+
+/// Local path referenced by a `file:` URL (`file:/p`, `file:///p`, `file:///C:/p`, `file:~/p`)
+pub fn file_url_path(url: &str) -> Option<PathBuf> {
+    let raw = match url.strip_prefix("file://") {
+        Some(rest) => rest,
+        None if url.contains("://") => return None,
+        None => url.strip_prefix("file:").unwrap_or(url),
+    };
+    // `file:///C:/x` leaves `/C:/x`; the leading slash is not part of a Windows path.
+    let raw = match raw.strip_prefix('/') {
+        Some(rest) if has_drive_letter(rest) => rest,
+        _ => raw,
+    };
+    if raw.is_empty() {
+        return None;
+    }
+    Some(expand_tilde(raw))
+}
+
+/// Expand a leading `~` (followed by `/` or `\`) to the home directory
+pub fn expand_tilde(path: &str) -> PathBuf {
+    if path == "~" {
+        if let Some(home) = home_dir() {
+            return home;
+        }
+    }
+    if let Some(rest) = path.strip_prefix("~/").or_else(|| path.strip_prefix("~\\")) {
+        if let Some(home) = home_dir() {
+            return home.join(rest);
+        }
+    }
+    PathBuf::from(path)
+}
+
+/// Display a path relative to home as `~/...`, with forward slashes
+pub fn shorten_home(path: &Path) -> String {
+    if let Some(home) = home_dir() {
+        if let Ok(stripped) = path.strip_prefix(&home) {
+            let rel = to_slash(&stripped.display().to_string());
+            if rel.is_empty() {
+                return "~".to_string();
+            }
+            return format!("~/{}", rel);
+        }
+    }
+    to_slash(&path.display().to_string())
+}
+
+/// Convert Windows separators; POSIX paths may legitimately contain backslashes, so those are
+/// only rewritten on Windows or for drive-letter paths.
+fn to_slash(path: &str) -> String {
+    if cfg!(windows) || has_drive_letter(path) {
+        path.replace('\\', "/")
+    } else {
+        path.to_string()
+    }
+}
+
+fn has_drive_letter(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes.len() == 2 || bytes[2] == b'/' || bytes[2] == b'\\')
+}
+
+fn non_empty_var(key: &str) -> Option<String> {
+    std::env::var(key).ok().filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_url_uses_forward_slashes_for_drive_paths() {
+        assert_eq!(
+            file_url(Path::new(
+                r"C:\Users\me\AppData\Roaming\zellij\plugins\zrpc.wasm"
+            )),
+            "file:C:/Users/me/AppData/Roaming/zellij/plugins/zrpc.wasm"
+        );
+        assert_eq!(file_url(Path::new("/tmp/zrpc.wasm")), "file:/tmp/zrpc.wasm");
+    }
+
+    #[test]
+    fn file_url_path_accepts_common_forms() {
+        assert_eq!(
+            file_url_path("file:/tmp/zrpc.wasm"),
+            Some(PathBuf::from("/tmp/zrpc.wasm"))
+        );
+        assert_eq!(
+            file_url_path("file:///tmp/zrpc.wasm"),
+            Some(PathBuf::from("/tmp/zrpc.wasm"))
+        );
+        assert_eq!(
+            file_url_path("file:///C:/Users/me/zrpc.wasm"),
+            Some(PathBuf::from("C:/Users/me/zrpc.wasm"))
+        );
+        assert_eq!(
+            file_url_path("file:C:/Users/me/zrpc.wasm"),
+            Some(PathBuf::from("C:/Users/me/zrpc.wasm"))
+        );
+        assert_eq!(file_url_path("https://example.com/zrpc.wasm"), None);
+        assert_eq!(file_url_path("file:"), None);
+    }
+
+    #[test]
+    fn drive_letter_detection() {
+        assert!(has_drive_letter("C:"));
+        assert!(has_drive_letter(r"c:\x"));
+        assert!(has_drive_letter("D:/x"));
+        assert!(!has_drive_letter("/tmp"));
+        assert!(!has_drive_letter("ab:/x"));
+        assert!(!has_drive_letter("C:x"));
+    }
+}
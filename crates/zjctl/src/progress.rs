@@ -0,0 +1,202 @@
+//! Progress reporting for long-running commands.
+//!
+//! With `--progress jsonl`, records are written to stderr as one JSON object per line, so
+//! wrapping tools can show live status while stdout stays reserved for the command's result.
+//! Wait commands without `--progress` draw a single self-updating status line instead, but
+//! only when stderr is a terminal.
+
+use std::io::{IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+use serde_json::{Map, Value};
+
+/// Minimum time between periodic progress records
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+/// Minimum time between status line redraws
+const REDRAW_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Machine-readable progress output format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ProgressFormat {
+    /// One JSON object per line on stderr
+    Jsonl,
+}
+
+pub struct Progress {
+    command: &'static str,
+    format: Option<ProgressFormat>,
+    status_line: bool,
+    start: Instant,
+    polls: u64,
+    last_report: Option<Instant>,
+    drawn: bool,
+}
+
+impl Progress {
+    pub fn new(command: &'static str, format: Option<ProgressFormat>) -> Self {
+        Self {
+            command,
+            format,
+            status_line: false,
+            start: Instant::now(),
+            polls: 0,
+            last_report: None,
+            drawn: false,
+        }
+    }
+
+    /// Draw a self-updating status line when no `--progress` format was requested and
+    /// stderr is a terminal; piped output stays silent.
+    pub fn with_status_line(mut self) -> Self {
+        self.status_line = self.format.is_none() && std::io::stderr().is_terminal();
+        self
+    }
+
+    /// Periodic update, called once per poll; throttled so tight poll loops don't flood stderr
+    pub fn update(&mut self, state: &str, fields: Value) {
+        self.polls += 1;
+        let interval = if self.format.is_some() {
+            REPORT_INTERVAL
+        } else {
+            REDRAW_INTERVAL
+        };
+        if self
+            .last_report
+            .is_some_and(|last| last.elapsed() < interval)
+        {
+            return;
+        }
+        self.last_report = Some(Instant::now());
+        if self.format.is_some() {
+            self.emit(state, fields);
+        } else if self.status_line {
+            let line = status_line(self.command, self.start.elapsed(), self.polls, &fields);
+            let mut stderr = std::io::stderr();
+            let _ = write!(stderr, "\r\x1b[2K{line}");
+            let _ = stderr.flush();
+            self.drawn = true;
+        }
+    }
+
+    /// State transition or final record; always emitted
+    pub fn event(&mut self, state: &str, fields: Value) {
+        self.clear();
+        if self.format.is_some() {
+            self.last_report = Some(Instant::now());
+            self.emit(state, fields);
+        }
+    }
+
+    fn emit(&self, state: &str, fields: Value) {
+        let Some(format) = self.format else {
+            return;
+        };
+        let line = match format {
+            ProgressFormat::Jsonl => {
+                let mut record = record(self.command, self.start.elapsed(), state, fields);
+                record["polls"] = self.polls.into();
+                record.to_string()
+            }
+        };
+        let mut stderr = std::io::stderr();
+        let _ = writeln!(stderr, "{line}");
+    }
+
+    fn clear(&mut self) {
+        if self.drawn {
+            let mut stderr = std::io::stderr();
+            let _ = write!(stderr, "\r\x1b[2K");
+            let _ = stderr.flush();
+            self.drawn = false;
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        // Errors propagated with `?` skip the final event; don't leave a stale line behind.
+        self.clear();
+    }
+}
+
+fn record(command: &str, elapsed: Duration, state: &str, fields: Value) -> Value {
+    let mut record = Map::new();
+    record.insert("command".to_string(), command.into());
+    record.insert(
+        "elapsed".to_string(),
+        ((elapsed.as_secs_f64() * 1000.0).round() / 1000.0).into(),
+    );
+    record.insert("state".to_string(), state.into());
+    if let Value::Object(extra) = fields {
+        record.extend(extra);
+    }
+    Value::Object(record)
+}
+
+fn status_line(command: &str, elapsed: Duration, polls: u64, fields: &Value) -> String {
+    let mut line = format!("{command}: {:.1}s elapsed", elapsed.as_secs_f64());
+    if let Value::Object(fields) = fields {
+        for (key, value) in fields {
+            let key = key.replace('_', " ");
+            match value {
+                Value::Number(n) if n.is_f64() => {
+                    line.push_str(&format!(", {key} {:.1}s", n.as_f64().unwrap_or_default()));
+                }
+                Value::String(s) => line.push_str(&format!(", {key} {s}")),
+                other => line.push_str(&format!(", {key} {other}")),
+            }
+        }
+    }
+    line.push_str(&format!(", {polls} polls"));
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_merges_fields() {
+        let value = record(
+            "wait-idle",
+            Duration::from_millis(1500),
+            "waiting",
+            serde_json::json!({ "polls": 3 }),
+        );
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "command": "wait-idle",
+                "elapsed": 1.5,
+                "state": "waiting",
+                "polls": 3,
+            })
+        );
+    }
+
+    #[test]
+    fn status_line_formats_fields() {
+        let line = status_line(
+            "wait-idle",
+            Duration::from_millis(3300),
+            12,
+            &serde_json::json!({ "since_change": 1.04, "pane": "id:terminal:3" }),
+        );
+        assert_eq!(
+            line,
+            "wait-idle: 3.3s elapsed, pane id:terminal:3, since change 1.0s, 12 polls"
+        );
+    }
+
+    #[test]
+    fn update_is_throttled_but_events_are_not() {
+        let mut progress = Progress::new("wait-for", Some(ProgressFormat::Jsonl));
+        progress.update("waiting", Value::Null);
+        let first = progress.last_report.expect("first update reported");
+        progress.update("waiting", Value::Null);
+        assert_eq!(progress.last_report, Some(first));
+        progress.event("done", Value::Null);
+        assert!(progress.last_report.expect("event reported") >= first);
+    }
+}
@@ -0,0 +1,325 @@
+//! Optional OpenTelemetry span export.
+//!
+//! Built with the `otel` feature and enabled at runtime by setting `ZJCTL_OTEL_ENDPOINT` to an
+//! OTLP/HTTP collector (e.g. `http://localhost:4318`). Each CLI invocation records a command
+//! span with one child span per RPC call; spans are exported as OTLP JSON when the command
+//! finishes. A W3C `TRACEPARENT` in the environment makes the command span a child of the
+//! caller's trace. Without the feature every function here is a no-op.
+
+#[cfg(feature = "otel")]
+pub use otel::{command_span, flush, rpc_span};
+
+#[cfg(not(feature = "otel"))]
+pub use noop::{command_span, flush, rpc_span};
+
+#[cfg(not(feature = "otel"))]
+mod noop {
+    pub struct Span;
+
+    impl Span {
+        pub fn trace_id(&self) -> Option<String> {
+            None
+        }
+
+        pub fn end(self, _ok: bool) {}
+    }
+
+    pub fn command_span(_name: &str) -> Span {
+        Span
+    }
+
+    pub fn rpc_span(_method: &str) -> Span {
+        Span
+    }
+
+    pub fn flush() {}
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+    use std::sync::Mutex;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use serde_json::{json, Value};
+    use uuid::Uuid;
+
+    const ENDPOINT_ENV: &str = "ZJCTL_OTEL_ENDPOINT";
+
+    const SPAN_KIND_INTERNAL: u8 = 1;
+    const SPAN_KIND_CLIENT: u8 = 3;
+    const STATUS_OK: u8 = 1;
+    const STATUS_ERROR: u8 = 2;
+
+    static TRACER: Mutex<Option<Tracer>> = Mutex::new(None);
+
+    struct Tracer {
+        endpoint: String,
+        trace_id: String,
+        /// Span ID of the running command span; RPC spans are parented to it
+        command_span_id: Option<String>,
+        finished: Vec<Value>,
+    }
+
+    pub struct Span {
+        active: Option<ActiveSpan>,
+    }
+
+    struct ActiveSpan {
+        name: String,
+        kind: u8,
+        trace_id: String,
+        span_id: String,
+        parent_span_id: Option<String>,
+        start: u128,
+        attributes: Vec<(&'static str, String)>,
+        is_command: bool,
+    }
+
+    impl Span {
+        pub fn trace_id(&self) -> Option<String> {
+            self.active.as_ref().map(|span| span.trace_id.clone())
+        }
+
+        pub fn end(self, ok: bool) {
+            let Some(span) = self.active else {
+                return;
+            };
+            let mut tracer = TRACER.lock().unwrap_or_else(|e| e.into_inner());
+            let Some(tracer) = tracer.as_mut() else {
+                return;
+            };
+            if span.is_command {
+                tracer.command_span_id = None;
+            }
+            tracer.finished.push(span.finish(now_nanos(), ok));
+        }
+    }
+
+    impl ActiveSpan {
+        fn finish(self, end: u128, ok: bool) -> Value {
+            let attributes: Vec<Value> = self
+                .attributes
+                .iter()
+                .map(|(key, value)| json!({ "key": key, "value": { "stringValue": value } }))
+                .collect();
+            let mut span = json!({
+                "traceId": self.trace_id,
+                "spanId": self.span_id,
+                "name": self.name,
+                "kind": self.kind,
+                "startTimeUnixNano": self.start.to_string(),
+                "endTimeUnixNano": end.to_string(),
+                "attributes": attributes,
+                "status": { "code": if ok { STATUS_OK } else { STATUS_ERROR } },
+            });
+            if let Some(parent) = self.parent_span_id {
+                span["parentSpanId"] = parent.into();
+            }
+            span
+        }
+    }
+
+    /// Start the root span for a CLI command; initializes the tracer from the environment
+    pub fn command_span(name: &str) -> Span {
+        let Some(endpoint) = std::env::var(ENDPOINT_ENV)
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+        else {
+            return Span { active: None };
+        };
+        let parent = std::env::var("TRACEPARENT")
+            .ok()
+            .and_then(|value| parse_traceparent(&value));
+        let (trace_id, parent_span_id) = match parent {
+            Some((trace_id, span_id)) => (trace_id, Some(span_id)),
+            None => (hex(Uuid::new_v4().as_bytes()), None),
+        };
+        let span_id = new_span_id();
+
+        let mut tracer = TRACER.lock().unwrap_or_else(|e| e.into_inner());
+        *tracer = Some(Tracer {
+            endpoint: traces_url(&endpoint),
+            trace_id: trace_id.clone(),
+            command_span_id: Some(span_id.clone()),
+            finished: Vec::new(),
+        });
+
+        Span {
+            active: Some(ActiveSpan {
+                name: name.to_string(),
+                kind: SPAN_KIND_INTERNAL,
+                trace_id,
+                span_id,
+                parent_span_id,
+                start: now_nanos(),
+                attributes: vec![("zjctl.command", name.to_string())],
+                is_command: true,
+            }),
+        }
+    }
+
+    /// Start a client span for one RPC call, parented to the running command span
+    pub fn rpc_span(method: &str) -> Span {
+        let tracer = TRACER.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(tracer) = tracer.as_ref() else {
+            return Span { active: None };
+        };
+        Span {
+            active: Some(ActiveSpan {
+                name: format!("rpc {method}"),
+                kind: SPAN_KIND_CLIENT,
+                trace_id: tracer.trace_id.clone(),
+                span_id: new_span_id(),
+                parent_span_id: tracer.command_span_id.clone(),
+                start: now_nanos(),
+                attributes: vec![
+                    ("rpc.system", "zrpc".to_string()),
+                    ("rpc.method", method.to_string()),
+                ],
+                is_command: false,
+            }),
+        }
+    }
+
+    /// Export finished spans; failures are ignored so tracing never breaks a command
+    pub fn flush() {
+        let Some(tracer) = TRACER.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+            return;
+        };
+        if tracer.finished.is_empty() {
+            return;
+        }
+        let body = export_body(tracer.finished).to_string();
+        let child = Command::new("curl")
+            .args(["-sS", "--max-time", "2", "-X", "POST"])
+            .args([
+                "-H",
+                "Content-Type: application/json",
+                "--data-binary",
+                "@-",
+            ])
+            .arg(&tracer.endpoint)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        if let Ok(mut child) = child {
+            if let Some(mut stdin) = child.stdin.take() {
+                let _ = stdin.write_all(body.as_bytes());
+            }
+            let _ = child.wait();
+        }
+    }
+
+    fn export_body(spans: Vec<Value>) -> Value {
+        json!({
+            "resourceSpans": [{
+                "resource": {
+                    "attributes": [
+                        { "key": "service.name", "value": { "stringValue": "zjctl" } },
+                        { "key": "service.version", "value": { "stringValue": env!("CARGO_PKG_VERSION") } },
+                    ]
+                },
+                "scopeSpans": [{
+                    "scope": { "name": "zjctl" },
+                    "spans": spans,
+                }]
+            }]
+        })
+    }
+
+    fn traces_url(endpoint: &str) -> String {
+        let endpoint = endpoint.trim().trim_end_matches('/');
+        if endpoint.ends_with("/v1/traces") {
+            endpoint.to_string()
+        } else {
+            format!("{endpoint}/v1/traces")
+        }
+    }
+
+    /// Parse `00-<trace-id>-<parent-id>-<flags>` into (trace_id, parent_span_id)
+    fn parse_traceparent(value: &str) -> Option<(String, String)> {
+        let parts: Vec<&str> = value.trim().split('-').collect();
+        let [_version, trace_id, span_id, _flags] = parts.as_slice() else {
+            return None;
+        };
+        let valid = |id: &str, len: usize| {
+            id.len() == len
+                && id.chars().all(|c| c.is_ascii_hexdigit())
+                && id.chars().any(|c| c != '0')
+        };
+        if !valid(trace_id, 32) || !valid(span_id, 16) {
+            return None;
+        }
+        Some((trace_id.to_ascii_lowercase(), span_id.to_ascii_lowercase()))
+    }
+
+    fn new_span_id() -> String {
+        hex(&Uuid::new_v4().as_bytes()[..8])
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    fn now_nanos() -> u128 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn traces_url_appends_path_once() {
+            assert_eq!(
+                traces_url("http://localhost:4318"),
+                "http://localhost:4318/v1/traces"
+            );
+            assert_eq!(
+                traces_url("http://localhost:4318/v1/traces/"),
+                "http://localhost:4318/v1/traces"
+            );
+        }
+
+        #[test]
+        fn parses_valid_traceparent() {
+            assert_eq!(
+                parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"),
+                Some((
+                    "4bf92f3577b34da6a3ce929d0e0e4736".to_string(),
+                    "00f067aa0ba902b7".to_string()
+                ))
+            );
+            assert_eq!(parse_traceparent("00-0000-00f067aa0ba902b7-01"), None);
+            assert_eq!(
+                parse_traceparent("00-00000000000000000000000000000000-00f067aa0ba902b7-01"),
+                None
+            );
+        }
+
+        #[test]
+        fn finished_span_includes_parent_and_status() {
+            let span = ActiveSpan {
+                name: "rpc pane.send".to_string(),
+                kind: SPAN_KIND_CLIENT,
+                trace_id: "4bf92f3577b34da6a3ce929d0e0e4736".to_string(),
+                span_id: "00f067aa0ba902b7".to_string(),
+                parent_span_id: Some("b7ad6b7169203331".to_string()),
+                start: 1,
+                attributes: vec![("rpc.method", "pane.send".to_string())],
+                is_command: false,
+            };
+            let value = span.finish(2, false);
+            assert_eq!(value["parentSpanId"], "b7ad6b7169203331");
+            assert_eq!(value["status"]["code"], STATUS_ERROR);
+            assert_eq!(value["endTimeUnixNano"], "2");
+            assert_eq!(value["attributes"][0]["key"], "rpc.method");
+        }
+    }
+}
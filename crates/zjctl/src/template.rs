@@ -0,0 +1,265 @@
+//! Minimal `--format` templates for listing commands.
+//!
+//! Syntax, rendered against each item's JSON fields:
+//! - `{field}` inserts a field (`{a.b}` reaches into nested objects); missing or null is empty
+//! - `{field:<N}`, `{field:>N}` pad to width N (left/right aligned)
+//! - `{?field:text}` inserts `text` when the field is truthy, `{?field:text|other}` otherwise `other`
+//! - `{!field:text}` inserts `text` when the field is falsy
+//! - `{{` and `}}` are literal braces; `\t` and `\n` are tab and newline
+
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Field {
+        path: Vec<String>,
+        align: Align,
+        width: usize,
+    },
+    Conditional {
+        path: Vec<String>,
+        negate: bool,
+        then: String,
+        otherwise: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Right,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = source.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut spec = String::new();
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        spec.push(c);
+                    }
+                    if !closed {
+                        return Err(format!("unterminated placeholder `{{{spec}`"));
+                    }
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(parse_placeholder(&spec)?);
+                }
+                '}' => return Err("unmatched `}` (use `}}` for a literal brace)".to_string()),
+                '\\' if matches!(chars.peek(), Some('t' | 'n')) => {
+                    let escaped = chars.next();
+                    literal.push(if escaped == Some('t') { '\t' } else { '\n' });
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Self { segments })
+    }
+
+    pub fn render(&self, item: &impl Serialize) -> Result<String, serde_json::Error> {
+        Ok(self.render_value(&serde_json::to_value(item)?))
+    }
+
+    pub fn render_value(&self, value: &Value) -> String {
+        let mut out = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => out.push_str(text),
+                Segment::Field { path, align, width } => {
+                    let text = display(lookup(value, path));
+                    let pad = width.saturating_sub(text.chars().count());
+                    match align {
+                        Align::Left => {
+                            out.push_str(&text);
+                            out.extend(std::iter::repeat_n(' ', pad));
+                        }
+                        Align::Right => {
+                            out.extend(std::iter::repeat_n(' ', pad));
+                            out.push_str(&text);
+                        }
+                    }
+                }
+                Segment::Conditional {
+                    path,
+                    negate,
+                    then,
+                    otherwise,
+                } => {
+                    if truthy(lookup(value, path)) != *negate {
+                        out.push_str(then);
+                    } else {
+                        out.push_str(otherwise);
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+fn parse_placeholder(spec: &str) -> Result<Segment, String> {
+    if let Some(rest) = spec.strip_prefix('?').or_else(|| spec.strip_prefix('!')) {
+        let negate = spec.starts_with('!');
+        let (field, body) = rest
+            .split_once(':')
+            .ok_or_else(|| format!("conditional `{{{spec}}}` needs `:text`"))?;
+        let (then, otherwise) = body.split_once('|').unwrap_or((body, ""));
+        return Ok(Segment::Conditional {
+            path: parse_path(field, spec)?,
+            negate,
+            then: then.to_string(),
+            otherwise: otherwise.to_string(),
+        });
+    }
+
+    let (field, padding) = match spec.split_once(':') {
+        Some((field, padding)) => (field, Some(padding)),
+        None => (spec, None),
+    };
+    let (align, width) = match padding {
+        None => (Align::Left, 0),
+        Some(padding) => {
+            let (align, digits) = if let Some(d) = padding.strip_prefix('<') {
+                (Align::Left, d)
+            } else if let Some(d) = padding.strip_prefix('>') {
+                (Align::Right, d)
+            } else {
+                (Align::Left, padding)
+            };
+            let width = digits
+                .parse()
+                .map_err(|_| format!("invalid width in `{{{spec}}}`"))?;
+            (align, width)
+        }
+    };
+    Ok(Segment::Field {
+        path: parse_path(field, spec)?,
+        align,
+        width,
+    })
+}
+
+fn parse_path(field: &str, spec: &str) -> Result<Vec<String>, String> {
+    let field = field.trim();
+    if field.is_empty() || field.split('.').any(str::is_empty) {
+        return Err(format!("missing field name in `{{{spec}}}`"));
+    }
+    Ok(field.split('.').map(str::to_string).collect())
+}
+
+fn lookup<'a>(value: &'a Value, path: &[String]) -> &'a Value {
+    path.iter()
+        .try_fold(value, |current, key| current.get(key))
+        .unwrap_or(&Value::Null)
+}
+
+fn display(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str, value: Value) -> String {
+        Template::parse(template)
+            .expect("valid template")
+            .render_value(&value)
+    }
+
+    #[test]
+    fn renders_fields_and_padding() {
+        let pane = json!({ "id": "terminal:3", "title": "vim", "rows": 24 });
+        assert_eq!(
+            render("{id:<12}|{rows:>4}|{title}", pane),
+            "terminal:3  |  24|vim"
+        );
+    }
+
+    #[test]
+    fn renders_conditionals() {
+        let template = "{?focused:*| }{!floating:tiled}{title}";
+        assert_eq!(
+            render(
+                template,
+                json!({ "focused": true, "floating": false, "title": "a" })
+            ),
+            "*tileda"
+        );
+        assert_eq!(
+            render(
+                template,
+                json!({ "focused": false, "floating": true, "title": "b" })
+            ),
+            " b"
+        );
+    }
+
+    #[test]
+    fn missing_and_nested_fields() {
+        let value = json!({ "tab": { "name": "main" }, "command": null });
+        assert_eq!(
+            render("[{tab.name}][{command}][{nope}]", value),
+            "[main][][]"
+        );
+    }
+
+    #[test]
+    fn escapes_braces_and_whitespace() {
+        assert_eq!(render("{{{id}}}\\t.", json!({ "id": 1 })), "{1}\t.");
+    }
+
+    #[test]
+    fn rejects_malformed_templates() {
+        assert!(Template::parse("{id").is_err());
+        assert!(Template::parse("id}").is_err());
+        assert!(Template::parse("{?focused}").is_err());
+        assert!(Template::parse("{id:>x}").is_err());
+        assert!(Template::parse("{}").is_err());
+    }
+}
@@ -1,4 +1,11 @@
+use std::ffi::OsString;
 use std::process::Command;
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+/// Session chosen with `--session` or a `session:` selector prefix
+static SESSION_OVERRIDE: Mutex<Option<String>> = Mutex::new(None);
 
 pub fn command() -> Command {
     let mut cmd = Command::new("zellij");
@@ -10,10 +17,147 @@ pub fn command() -> Command {
 }
 
 pub fn session_args() -> Vec<String> {
-    match std::env::var("ZELLIJ_SESSION_NAME") {
-        Ok(session) if !session.is_empty() => vec!["--session".to_string(), session],
-        _ => Vec::new(),
+    match session_name() {
+        Some(session) => vec!["--session".to_string(), session],
+        None => Vec::new(),
+    }
+}
+
+/// Target `session` for every later zellij invocation (None restores the default)
+pub fn set_session(session: Option<String>) {
+    *SESSION_OVERRIDE.lock().unwrap_or_else(|e| e.into_inner()) = session;
+}
+
+/// Session zjctl talks to: the override, else the session it runs inside
+pub fn session_name() -> Option<String> {
+    let session = SESSION_OVERRIDE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    session
+        .or_else(|| std::env::var("ZELLIJ_SESSION_NAME").ok())
+        .filter(|session| !session.is_empty())
+}
+
+/// One line of `zellij list-sessions`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SessionEntry {
+    pub name: String,
+    /// Age as Zellij prints it, e.g. `2h 5m ago`
+    pub created: Option<String>,
+    /// The session the `zellij` process was started from
+    pub current: bool,
+    /// Exited but resurrectable (`zellij attach` brings it back)
+    pub exited: bool,
+}
+
+/// Names of running sessions (exited, resurrectable sessions are skipped)
+pub fn list_sessions() -> Result<Vec<String>, String> {
+    Ok(running(list_session_entries()?))
+}
+
+/// Every session `zellij list-sessions` knows, exited ones included
+pub fn list_session_entries() -> Result<Vec<SessionEntry>, String> {
+    let output = Command::new("zellij")
+        .args(["list-sessions", "--no-formatting"])
+        .output()
+        .map_err(|err| format!("failed to run zellij: {err}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // zellij exits non-zero when there are no sessions at all
+        if stderr.contains("No active zellij sessions") {
+            return Ok(Vec::new());
+        }
+        return Err(format!("zellij list-sessions failed: {}", stderr.trim()));
     }
+    Ok(parse_session_entries(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+fn parse_session_entries(output: &str) -> Vec<SessionEntry> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let name = line.split_whitespace().next()?;
+            let created = line
+                .split_once("[Created ")
+                .and_then(|(_, rest)| rest.split_once(']'))
+                .map(|(created, _)| created.to_string());
+            Some(SessionEntry {
+                name: name.to_string(),
+                created,
+                current: line.contains("(current)"),
+                exited: line.contains("EXITED"),
+            })
+        })
+        .collect()
+}
+
+fn running(sessions: Vec<SessionEntry>) -> Vec<String> {
+    sessions
+        .into_iter()
+        .filter(|session| !session.exited)
+        .map(|session| session.name)
+        .collect()
+}
+
+/// Split `session:<name>:<selector>` into the session and the plain selector
+pub fn split_session_selector(selector: &str) -> Option<(&str, &str)> {
+    let rest = selector.strip_prefix("session:")?;
+    let (session, selector) = rest.split_once(':')?;
+    if session.is_empty() || selector.is_empty() {
+        return None;
+    }
+    Some((session, selector))
+}
+
+/// Strip `session:<name>:` from `--pane` selectors, returning the session it names.
+///
+/// The plugin instance behind each session only knows its own panes, so the session has to be
+/// chosen before any RPC is sent rather than resolved by the selector parser. Arguments after
+/// `--` are payload (text to send, a command to run) and are left as they are.
+pub fn extract_selector_session(
+    args: impl IntoIterator<Item = OsString>,
+) -> Result<(Vec<OsString>, Option<String>), String> {
+    let mut session: Option<String> = None;
+    let mut take = |selector: &str| -> Result<Option<String>, String> {
+        let Some((name, rest)) = split_session_selector(selector) else {
+            return Ok(None);
+        };
+        match &session {
+            Some(existing) if existing != name => Err(format!(
+                "selectors name different sessions ({existing} and {name})"
+            )),
+            _ => {
+                session = Some(name.to_string());
+                Ok(Some(rest.to_string()))
+            }
+        }
+    };
+
+    let mut out = Vec::new();
+    let mut pane_value_next = false;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if !pane_value_next && arg == "--" {
+            out.push(arg);
+            out.extend(args);
+            break;
+        }
+        let text = arg.to_str().map(str::to_string);
+        let rewritten = match text.as_deref() {
+            Some(value) if pane_value_next => take(value)?.map(OsString::from),
+            Some(flag) => match flag.strip_prefix("--pane=") {
+                Some(value) => take(value)?.map(|rest| OsString::from(format!("--pane={rest}"))),
+                None => None,
+            },
+            None => None,
+        };
+        pane_value_next = text.as_deref() == Some("--pane");
+        out.push(rewritten.unwrap_or(arg));
+    }
+    Ok((out, session))
 }
 
 #[cfg(test)]
@@ -56,6 +200,118 @@ mod tests {
         });
     }
 
+    #[test]
+    fn session_override_wins_over_environment() {
+        with_env(&[("ZELLIJ_SESSION_NAME", Some("inside"))], || {
+            set_session(Some("other".to_string()));
+            let args = session_args();
+            set_session(None);
+            assert_eq!(args, vec!["--session".to_string(), "other".to_string()]);
+            assert_eq!(session_name().as_deref(), Some("inside"));
+        });
+    }
+
+    #[test]
+    fn parse_sessions_skips_exited() {
+        let output = "work [Created 2h ago] (current)\nold [Created 3d ago] (EXITED - attach to resurrect)\nscratch [Created 5m ago]\n";
+        assert_eq!(
+            running(parse_session_entries(output)),
+            vec!["work", "scratch"]
+        );
+    }
+
+    #[test]
+    fn parse_session_entries_reads_age_and_state() {
+        let output = "work [Created 2h 5m ago] (current)\nold [Created 3d ago] (EXITED - attach to resurrect)\n";
+        let sessions = parse_session_entries(output);
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].name, "work");
+        assert_eq!(sessions[0].created.as_deref(), Some("2h 5m ago"));
+        assert!(sessions[0].current && !sessions[0].exited);
+        assert!(!sessions[1].current && sessions[1].exited);
+    }
+
+    #[test]
+    fn split_session_selector_extracts_session() {
+        assert_eq!(
+            split_session_selector("session:work:title:server"),
+            Some(("work", "title:server"))
+        );
+        assert_eq!(split_session_selector("title:server"), None);
+        assert_eq!(split_session_selector("session::focused"), None);
+        assert_eq!(split_session_selector("session:work"), None);
+    }
+
+    fn os_args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn extract_selector_session_rewrites_pane_args() {
+        let (args, session) = extract_selector_session(os_args(&[
+            "zjctl",
+            "pane",
+            "send",
+            "--pane",
+            "session:work:title:server",
+            "--",
+            "ls",
+        ]))
+        .unwrap();
+        assert_eq!(session.as_deref(), Some("work"));
+        assert_eq!(
+            args,
+            os_args(&[
+                "zjctl",
+                "pane",
+                "send",
+                "--pane",
+                "title:server",
+                "--",
+                "ls"
+            ])
+        );
+
+        let (args, session) = extract_selector_session(os_args(&[
+            "zjctl",
+            "pane",
+            "focus",
+            "--pane=session:a:focused",
+        ]))
+        .unwrap();
+        assert_eq!(session.as_deref(), Some("a"));
+        assert_eq!(args[3], OsString::from("--pane=focused"));
+    }
+
+    #[test]
+    fn extract_selector_session_leaves_payload_after_double_dash() {
+        let argv = os_args(&[
+            "zjctl",
+            "pane",
+            "send",
+            "--pane",
+            "focused",
+            "--",
+            "--pane",
+            "session:prod:title:db",
+        ]);
+        let (args, session) = extract_selector_session(argv.clone()).unwrap();
+        assert_eq!(session, None);
+        assert_eq!(args, argv);
+    }
+
+    #[test]
+    fn extract_selector_session_rejects_mixed_sessions() {
+        assert!(extract_selector_session(os_args(&[
+            "zjctl",
+            "--pane",
+            "session:a:focused",
+            "--pane",
+            "session:b:focused",
+        ]))
+        .is_err());
+    }
+
     #[test]
     fn session_args_includes_session_name() {
         with_env(&[("ZELLIJ_SESSION_NAME", Some("test-session"))], || {
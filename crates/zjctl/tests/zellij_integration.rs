@@ -7,6 +7,7 @@ use std::time::{Duration, Instant};
 struct PaneInfo {
     id: String,
     pane_type: String,
+    focused: bool,
 }
 
 fn zjctl_bin() -> &'static str {
@@ -128,3 +129,70 @@ fn pane_send_is_single_delivery() {
         .args(["pane", "close", "--pane", selector.as_str(), "--force"])
         .status();
 }
+
+#[test]
+fn read_only_capture_of_unfocused_pane() {
+    if !should_run() {
+        eprintln!("skipping integration test (set ZJCTL_INTEGRATION=1 and ZELLIJ_SESSION_NAME)");
+        return;
+    }
+
+    let session = std::env::var("ZELLIJ_SESSION_NAME").expect("missing ZELLIJ_SESSION_NAME");
+    let pane_name = format!("zjctl-integ-{}", uuid::Uuid::new_v4().simple());
+
+    let before = list_panes();
+    let original = before
+        .iter()
+        .find(|p| p.focused)
+        .map(|p| p.id.clone())
+        .expect("no focused pane");
+
+    // The new pane takes focus, leaving the original one unfocused.
+    run_zellij(&[
+        "--session",
+        session.as_str(),
+        "action",
+        "new-pane",
+        "--name",
+        pane_name.as_str(),
+        "--",
+        "fish",
+    ]);
+
+    let start = Instant::now();
+    let timeout = Duration::from_secs(60);
+    let pane_id = loop {
+        let after = list_panes();
+        if let Some(id) = find_new_terminal(&before, &after) {
+            break id;
+        }
+        if start.elapsed() >= timeout {
+            panic!("timed out waiting for new pane");
+        }
+        sleep(Duration::from_millis(500));
+    };
+
+    let original_selector = format!("id:{original}");
+    let capture = Command::new(zjctl_bin())
+        .args([
+            "--read-only",
+            "pane",
+            "capture",
+            "--pane",
+            original_selector.as_str(),
+        ])
+        .output()
+        .expect("failed to run zjctl pane capture");
+    assert!(
+        capture.status.success(),
+        "read-only capture of an unfocused pane failed: {}",
+        String::from_utf8_lossy(&capture.stderr)
+    );
+    let focused = list_panes().into_iter().find(|p| p.focused).map(|p| p.id);
+    assert_eq!(focused.as_deref(), Some(pane_id.as_str()));
+
+    let selector = format!("id:{pane_id}");
+    let _ = Command::new(zjctl_bin())
+        .args(["pane", "close", "--pane", selector.as_str(), "--force"])
+        .status();
+}
@@ -11,16 +11,35 @@ Use: cargo build -p zjctl-zrpc --target wasm32-wasip1 --release"
 use std::collections::BTreeMap;
 use zellij_tile::prelude::*;
 use zjctl_proto::{
-    methods, PaneSelector, PaneType, RpcError, RpcErrorCode, RpcRequest, RpcResponse,
+    encode_keys, format_host_exec_allow, methods, parse_host_exec_allow, Base64Bytes, PaneFilter,
+    PaneSelector, PaneType, RpcEnvelope, RpcError, RpcErrorCode, RpcRequest, RpcResponse,
+    UpdateCheck, HOST_EXEC_ALLOW_FILE, HOST_EXEC_WAIT_MS, MAX_HOST_EXEC_WAIT_MS,
 };
 
+mod poll;
 mod state;
 
+use poll::{ClientPoll, PollMode};
 use state::PluginState;
 
 /// Expected pipe name for RPC messages
 const RPC_PIPE_NAME: &str = "zjctl-rpc";
-const CLIENT_POLL_SECS: f64 = 0.2;
+/// Context keys linking an async host result (command, web request) back to its pipe and request
+const PENDING_PIPE_KEY: &str = "zjctl_pipe";
+const PENDING_REQUEST_KEY: &str = "zjctl_request";
+/// Context keys carrying the name and working directory of a pane opened by `pane.launch`
+const LAUNCH_NAME_KEY: &str = "zjctl_launch_name";
+const LAUNCH_CWD_KEY: &str = "zjctl_launch_cwd";
+/// How long `pane.send` remembers an idempotency key when the request gives no window
+const DEFAULT_IDEMPOTENCY_WINDOW_SECS: f64 = 600.0;
+/// Longest `events.poll` wait; the CLI polls again after an empty answer
+const MAX_EVENTS_WAIT_MS: u64 = 30_000;
+/// How long requests arriving right after load are held back for the first pane, tab, and
+/// client updates (`system.ready` may ask for longer)
+const PRIME_WAIT_MS: u64 = 2_000;
+/// Longest `system.ready` wait
+const MAX_READY_WAIT_MS: u64 = 30_000;
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/mrshu/zjctl/releases/latest";
 
 register_plugin!(ZrpcPlugin);
 
@@ -29,56 +48,168 @@ register_plugin!(ZrpcPlugin);
 struct ZrpcPlugin {
     /// Current state snapshot
     state: PluginState,
+    /// Programs `host.exec` may run (empty = disabled): the `host_exec_allow` configuration the
+    /// user writes in config.kdl or a layout, as published to `/data` by the instance that has it.
+    /// zjctl never sends this key.
+    host_exec_allow: Vec<String>,
+    /// Whether this instance's own configuration set `host_exec_allow` (otherwise the list is
+    /// read again from `/data` on each call)
+    host_exec_configured: bool,
+    /// Running `host.exec` commands, answered with a timeout error at their deadline
+    exec_waiters: Vec<ExecWaiter>,
+    /// Whether the plugin may use the network for `system.check_update`; asked for by the first
+    /// such call (zjctl only makes it with `ZJCTL_UPDATE_CHECK=1`), not at load
+    web_access: WebAccess,
+    /// `system.check_update` calls waiting for the WebAccess prompt (host call contexts)
+    awaiting_web_access: Vec<BTreeMap<String, String>>,
+    /// Outstanding async requests per CLI pipe; the pipe stays blocked until they are answered
+    pending: BTreeMap<String, usize>,
+    /// `events.poll` calls waiting for an event newer than they have seen
+    event_waiters: Vec<EventWaiter>,
+    /// Pipe messages received before the host state was primed, handled once it is
+    held: Vec<HeldMessage>,
+    /// Set when a held message ran out of time; later messages are no longer held, so a
+    /// plugin that never gets its permissions answers at normal speed (with empty state)
+    priming_gave_up: bool,
+    /// When to list clients next (`client_poll` configuration)
+    poll: ClientPoll,
+    /// Whether the focused pane changed since the last client poll
+    clients_changed: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum WebAccess {
+    #[default]
+    NotRequested,
+    Requested,
+    Granted,
+    Denied,
+}
+
+struct HeldMessage {
+    pipe_id: String,
+    requests: Vec<RpcRequest>,
+    /// Handled at this Unix time (ms) even if the state is still not primed
+    deadline_ms: u64,
+}
+
+struct ExecWaiter {
+    context: BTreeMap<String, String>,
+    /// Answered with a timeout at this Unix time (ms) if the command is still running
+    deadline_ms: u64,
+}
+
+struct EventWaiter {
+    context: BTreeMap<String, String>,
+    since: u64,
+    /// Answered empty at this Unix time (ms) if nothing happens
+    deadline_ms: u64,
 }
 
 impl ZellijPlugin for ZrpcPlugin {
-    fn load(&mut self, _config: BTreeMap<String, String>) {
+    fn load(&mut self, config: BTreeMap<String, String>) {
         // Hide the plugin pane - we're a background service
         hide_self();
 
-        // Request required permissions
-        request_permission(&[
-            PermissionType::ReadApplicationState,
-            PermissionType::WriteToStdin,
-            PermissionType::ChangeApplicationState,
-            PermissionType::ReadCliPipes,
-        ]);
+        // Session name from the CLI's pipe configuration until ModeUpdate reports the real one
+        self.state.session_name = config.get("zjctl_session").cloned();
+        self.state.zellij_version = Some(get_zellij_version());
+        // zjctl's pipes start their own instance (their configuration differs), so the
+        // configured one publishes the allowlist for them.
+        let configured = config.get("host_exec_allow").map(|list| {
+            let programs = parse_host_exec_allow(list);
+            let _ = std::fs::write(HOST_EXEC_ALLOW_FILE, format_host_exec_allow(&programs));
+            programs
+        });
+        self.host_exec_configured = configured.is_some();
+        self.host_exec_allow = configured.unwrap_or_else(read_host_exec_allow);
+        self.poll = ClientPoll::new(
+            config
+                .get("client_poll")
+                .map(|mode| PollMode::parse(mode))
+                .unwrap_or_default(),
+        );
+
+        request_permission(&self.permissions());
 
         // Subscribe to state updates
         subscribe(&[
             EventType::PaneUpdate,
             EventType::TabUpdate,
+            EventType::ModeUpdate,
             EventType::ListClients,
             EventType::Timer,
             EventType::PermissionRequestResult,
+            EventType::RunCommandResult,
+            EventType::WebRequestResult,
+            EventType::CommandPaneOpened,
         ]);
 
         // Prime client focus state
         list_clients();
-        set_timeout(CLIENT_POLL_SECS);
+        self.arm_client_poll(true);
     }
 
     fn update(&mut self, event: Event) -> bool {
         match event {
             Event::PaneUpdate(manifest) => {
                 self.state.update_panes(manifest);
+                // Without a poll timer, pane updates (focus moves among them) refresh clients.
+                if self.poll.mode == PollMode::Off {
+                    list_clients();
+                }
             }
             Event::TabUpdate(tabs) => {
                 self.state.update_tabs(tabs);
             }
+            Event::ModeUpdate(mode) => {
+                self.state.update_mode(mode);
+            }
             Event::ListClients(clients) => {
+                let before = self.state.current_client_pane_id;
                 self.state.update_clients(clients);
+                self.clients_changed |= self.state.current_client_pane_id != before;
             }
-            Event::Timer(_) => {
-                list_clients();
-                set_timeout(CLIENT_POLL_SECS);
-            }
-            Event::PermissionRequestResult(_) => {
+            Event::PermissionRequestResult(status) => {
                 // After permissions are granted, we can query client focus reliably.
                 list_clients();
+                if self.web_access == WebAccess::Requested {
+                    self.web_access_answered(status);
+                }
+            }
+            Event::RunCommandResult(exit_code, stdout, stderr, context) => {
+                // Already answered when the command outlived its deadline.
+                let before = self.exec_waiters.len();
+                self.exec_waiters.retain(|waiter| waiter.context != context);
+                if self.exec_waiters.len() < before {
+                    let result = serde_json::json!({
+                        "exit_code": exit_code,
+                        "stdout": String::from_utf8_lossy(&stdout),
+                        "stderr": String::from_utf8_lossy(&stderr),
+                    });
+                    self.finish_pending(&context, Ok(result));
+                }
+            }
+            Event::WebRequestResult(status, _headers, body, context) => {
+                let result = update_check_result(status, &body);
+                self.finish_pending(&context, result);
+            }
+            Event::CommandPaneOpened(terminal_id, context) => {
+                let result = self.command_pane_opened(terminal_id, &context);
+                self.finish_pending(&context, Ok(result));
             }
             _ => {}
         }
+        if let Some(id) = self.focused_pane().map(|p| p.id_string()) {
+            self.state.record_focus(&id);
+        }
+        if self.poll.is_due(unix_time_ms()) {
+            list_clients();
+            self.arm_client_poll(false);
+        }
+        self.release_held();
+        self.answer_event_waiters();
+        self.expire_host_exec();
         false // Don't re-render
     }
 
@@ -108,7 +239,7 @@ impl ZellijPlugin for ZrpcPlugin {
             }
         };
 
-        let request: RpcRequest = match serde_json::from_str(&payload) {
+        let envelope: RpcEnvelope = match serde_json::from_str(&payload) {
             Ok(r) => r,
             Err(e) => {
                 self.send_error(
@@ -121,14 +252,129 @@ impl ZellijPlugin for ZrpcPlugin {
             }
         };
 
-        // Route to handler
-        self.handle_request(&pipe_id, request);
-
+        let requests = envelope.into_requests();
+        self.client_activity();
+        // Right after load the host has not reported panes, tabs, or clients yet; answering
+        // now would list nothing and resolve nothing.
+        if !self.state.primed.ready() && !self.priming_gave_up {
+            self.hold(pipe_id, requests);
+            return false;
+        }
+        self.route(&pipe_id, requests);
         false
     }
 }
 
 impl ZrpcPlugin {
+    /// Route requests to handlers, in order; a batch shares one pipe round trip. The pipe is
+    /// released once nothing in it waits on the host.
+    fn route(&mut self, pipe_id: &str, requests: Vec<RpcRequest>) {
+        let pipe_id = pipe_id.to_string();
+        for request in requests {
+            // Answered from update() once the host reports the result.
+            let started = match request.method.as_str() {
+                methods::HOST_EXEC => Some(self.start_host_exec(&pipe_id, &request)),
+                methods::SYSTEM_CHECK_UPDATE => Some(self.start_check_update(&pipe_id, &request)),
+                methods::PANE_LAUNCH => Some(self.start_pane_launch(&pipe_id, &request)),
+                methods::EVENTS_POLL if !self.events_ready(&request) => {
+                    Some(self.start_events_poll(&pipe_id, &request))
+                }
+                _ => None,
+            };
+            if let Some(started) = started {
+                if let Err(error) = started {
+                    self.write_response(&pipe_id, &RpcResponse::error(request.id, error));
+                }
+                continue;
+            }
+            let response = self.handle_request(request);
+            self.write_response(&pipe_id, &response);
+        }
+        if !self.pending.contains_key(&pipe_id) {
+            unblock_cli_pipe_input(&pipe_id);
+        }
+    }
+
+    /// Schedule the next client poll; `first` starts an adaptive schedule at its fastest
+    fn arm_client_poll(&mut self, first: bool) {
+        let changed = first || std::mem::take(&mut self.clients_changed);
+        if let Some(ms) = self.poll.next(unix_time_ms(), changed) {
+            set_timeout(ms as f64 / 1000.0);
+        }
+    }
+
+    /// An RPC arrived: poll soon in adaptive mode, or right away when polling is off, so focus
+    /// is fresh for the calls that usually follow
+    fn client_activity(&mut self) {
+        if self.poll.mode == PollMode::Off {
+            list_clients();
+        } else if let Some(ms) = self.poll.speed_up(unix_time_ms()) {
+            set_timeout(ms as f64 / 1000.0);
+        }
+    }
+
+    /// Keep a message until the state is primed; a `system.ready` in it sets how long
+    fn hold(&mut self, pipe_id: String, requests: Vec<RpcRequest>) {
+        let wait_ms = requests
+            .iter()
+            .filter(|request| request.method == methods::SYSTEM_READY)
+            .filter_map(|request| request.params["wait_ms"].as_u64())
+            .max()
+            .unwrap_or(PRIME_WAIT_MS)
+            .min(MAX_READY_WAIT_MS);
+        self.held.push(HeldMessage {
+            pipe_id,
+            requests,
+            deadline_ms: unix_time_ms().saturating_add(wait_ms),
+        });
+        // The client poll may be slow or off; make sure an event arrives at the deadline.
+        set_timeout(wait_ms as f64 / 1000.0);
+    }
+
+    /// Handle held messages once the state is primed or their time is up (checked on every
+    /// host event, including the timer armed by `hold`)
+    fn release_held(&mut self) {
+        if self.held.is_empty() {
+            return;
+        }
+        let ready = self.state.primed.ready();
+        let now = unix_time_ms();
+        let (due, waiting): (Vec<_>, Vec<_>) = std::mem::take(&mut self.held)
+            .into_iter()
+            .partition(|held| ready || now >= held.deadline_ms);
+        self.held = waiting;
+        if !ready && !due.is_empty() {
+            self.priming_gave_up = true;
+        }
+        for held in due {
+            self.route(&held.pipe_id, held.requests);
+        }
+    }
+
+    /// `{ready, panes, tabs, clients}`: whether each kind of host state has arrived
+    fn system_ready(&self) -> serde_json::Value {
+        let primed = self.state.primed;
+        serde_json::json!({
+            "ready": primed.ready(),
+            "panes": primed.panes,
+            "tabs": primed.tabs,
+            "clients": primed.clients,
+        })
+    }
+
+    /// Plugin-wide settings callers may change (`client_poll`), so they never have to be part of
+    /// the pipe's plugin configuration, which would start a separate plugin instance
+    fn system_configure(&mut self, request: &RpcRequest) -> serde_json::Value {
+        if let Some(value) = request.params["client_poll"].as_str() {
+            let mode = PollMode::parse(value);
+            if mode != self.poll.mode {
+                self.poll = ClientPoll::new(mode);
+                self.arm_client_poll(true);
+            }
+        }
+        serde_json::json!({ "client_poll": self.poll.mode.to_config() })
+    }
+
     fn focused_pane(&self) -> Option<&state::PaneEntry> {
         if let Some(pane_id) = self.state.current_client_pane_id {
             let (is_plugin, numeric_id) = match pane_id {
@@ -189,13 +435,42 @@ impl ZrpcPlugin {
         any.first().copied()
     }
 
-    fn handle_request(&mut self, pipe_id: &str, request: RpcRequest) {
+    fn handle_request(&mut self, request: RpcRequest) -> RpcResponse {
         let result = match request.method.as_str() {
             methods::PANES_LIST => self.handle_panes_list(&request),
+            methods::PANES_HISTORY => self.handle_panes_history(&request),
             methods::PANE_SEND => self.handle_pane_send(&request),
+            methods::PANE_BROADCAST => self.handle_pane_broadcast(&request),
             methods::PANE_FOCUS => self.handle_pane_focus(&request),
+            methods::PANE_FOCUS_PREVIOUS => self.handle_pane_focus_previous(&request),
             methods::PANE_RENAME => self.handle_pane_rename(&request),
             methods::PANE_RESIZE => self.handle_pane_resize(&request),
+            methods::PANE_SCROLL => self.handle_pane_scroll(&request),
+            methods::PANE_CLOSE => self.handle_pane_close(&request),
+            methods::PANE_RERUN => self.handle_pane_rerun(&request),
+            methods::PANE_HISTORY => self.handle_pane_history(&request),
+            methods::PANE_SET_CWD => self.handle_pane_set_cwd(&request),
+            methods::PANE_SET_TOKEN => self.handle_pane_set_token(&request),
+            methods::PANE_TOGGLE_FLOAT => self.handle_pane_toggle_float(&request),
+            methods::PANE_TOGGLE_FULLSCREEN => self.handle_pane_toggle_fullscreen(&request),
+            methods::PANE_PIN => self.handle_pane_pin(&request),
+            methods::PANE_MOVE => self.handle_pane_move(&request),
+            methods::PANE_CLEAR => self.handle_pane_clear(&request),
+            methods::MARK_SET => self.handle_mark_set(&request),
+            methods::MARK_REMOVE => self.handle_mark_remove(&request),
+            methods::MARKS_LIST => Ok(self.marks_list()),
+            methods::TAG_ADD => self.handle_tag_add(&request),
+            methods::TAG_REMOVE => self.handle_tag_remove(&request),
+            methods::TAGS_LIST => Ok(self.tags_list()),
+            methods::PANE_SEND_KEYS => self.handle_pane_send_keys(&request),
+            methods::TABS_LIST => self.handle_tabs_list(),
+            methods::EVENTS_POLL => Ok(self.events_result(request.params["since"].as_u64())),
+            methods::TAB_RENAME => self.handle_tab_rename(&request),
+            methods::TAB_NEW => self.handle_tab_new(&request),
+            methods::TAB_CLOSE => self.handle_tab_close(&request),
+            methods::TAB_FOCUS => self.handle_tab_focus(&request),
+            methods::SYSTEM_READY => Ok(self.system_ready()),
+            methods::SYSTEM_CONFIGURE => Ok(self.system_configure(&request)),
             _ => Err(RpcError::new(
                 RpcErrorCode::MethodNotFound,
                 format!("unknown method: {}", request.method),
@@ -204,22 +479,60 @@ impl ZrpcPlugin {
 
         match result {
             Ok(value) => {
-                let response =
-                    RpcResponse::success(request.id, value).expect("failed to serialize response");
-                self.send_response(pipe_id, response);
-            }
-            Err(error) => {
-                let response = RpcResponse::error(request.id, error);
-                self.send_response(pipe_id, response);
+                RpcResponse::success(request.id, value).expect("failed to serialize response")
             }
+            Err(error) => RpcResponse::error(request.id, error),
         }
     }
 
-    fn handle_panes_list(&self, _request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
+    fn handle_panes_list(&self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
+        // The daemon revalidates its snapshot: nothing changed since it was taken.
+        if request.params["if_generation"].as_u64() == Some(self.state.generation) {
+            return Ok(serde_json::json!({
+                "generation": self.state.generation,
+                "unchanged": true,
+            }));
+        }
         let focused_id = self.focused_pane().map(|p| p.id_string());
-        let panes = self.state.list_panes(focused_id.as_deref());
+        let mut panes = self.state.list_panes(focused_id.as_deref());
+
+        // Optional selector narrows the listing to the panes it resolves to.
+        if let Some(selector_str) = request.params["selector"].as_str() {
+            let selector: PaneSelector = selector_str.parse().map_err(|e| {
+                RpcError::new(
+                    RpcErrorCode::InvalidParams,
+                    format!("invalid selector: {}", e),
+                )
+            })?;
+            let matched: Vec<String> = self
+                .resolve_selector(&selector)?
+                .iter()
+                .map(|p| p.id_string())
+                .collect();
+            panes.retain(|p| matched.contains(&p.id));
+        }
+
+        // Optional filter from `panes ls` flags.
+        if let Some(filter) = request.params.get("filter") {
+            let filter: PaneFilter = serde_json::from_value(filter.clone()).map_err(|e| {
+                RpcError::new(
+                    RpcErrorCode::InvalidParams,
+                    format!("invalid filter: {}", e),
+                )
+            })?;
+            let current_tab = panes.iter().find(|p| p.focused).map(|p| p.tab_index);
+            panes.retain(|p| filter_matches(&filter, p, current_tab));
+        }
 
-        serde_json::to_value(&panes).map_err(|e| {
+        let result = state::PanesListResult {
+            session: self.state.session_name.clone(),
+            zellij_version: self.state.zellij_version.clone(),
+            plugin_version: env!("CARGO_PKG_VERSION").to_string(),
+            generation: self.state.generation,
+            panes,
+        };
+
+        serde_json::to_value(&result).map_err(|e| {
             RpcError::new(
                 RpcErrorCode::Internal,
                 format!("serialization error: {}", e),
@@ -227,13 +540,11 @@ impl ZrpcPlugin {
         })
     }
 
-    fn handle_pane_send(&self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
+    fn handle_pane_send(&mut self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
         let selector_str = request.params["selector"]
             .as_str()
             .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'selector'"))?;
-        let text = request.params["text"]
-            .as_str()
-            .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'text'"))?;
+        let payload = send_payload(request)?;
         let all = request.params["all"].as_bool().unwrap_or(false);
 
         let selector: PaneSelector = selector_str.parse().map_err(|e| {
@@ -246,26 +557,245 @@ impl ZrpcPlugin {
         let panes = self.resolve_selector(&selector)?;
 
         if panes.is_empty() {
-            return Err(RpcError::new(
-                RpcErrorCode::NoMatch,
-                "no panes match selector",
-            ));
+            return Err(no_match_error(selector_str));
         }
         if panes.len() > 1 && !all {
-            return Err(RpcError::new(
-                RpcErrorCode::AmbiguousMatch,
+            return Err(ambiguous_error(&panes, true));
+        }
+
+        let targets: Vec<(String, PaneId)> = panes
+            .iter()
+            .map(|pane| (pane.id_string(), pane.pane_id()))
+            .collect();
+
+        self.guard_scroll_mode(request, &panes)?;
+        let now = unix_time_ms();
+        if let Some(key) = request.params["idempotency_key"].as_str() {
+            let window_secs = request.params["idempotency_window"]
+                .as_f64()
+                .unwrap_or(DEFAULT_IDEMPOTENCY_WINDOW_SECS);
+            let window_ms = (window_secs.max(0.0) * 1000.0) as u64;
+            if !self.state.claim_idempotency_key(key, now, window_ms) {
+                return Ok(serde_json::json!({ "sent_to": 0, "duplicate": true }));
+            }
+        }
+        for (id, pane_id) in &targets {
+            match &payload {
+                SendPayload::Text(text) => {
+                    write_chars_to_pane_id(text, *pane_id);
+                    self.state.record_send(id, text, now);
+                }
+                SendPayload::Bytes(bytes) => {
+                    write_to_pane_id(bytes.0.clone(), *pane_id);
+                    self.state.record_send(id, &bytes.to_lossy_string(), now);
+                }
+            }
+        }
+
+        Ok(serde_json::json!({ "sent_to": targets.len() }))
+    }
+
+    /// Zellij keeps keystrokes for the focused pane while it is in scroll or search mode, so a
+    /// write there would silently vanish. `scroll_mode`: `fail` (default), `exit` (return to
+    /// normal mode and the live screen first), or `force`.
+    fn guard_scroll_mode(
+        &self,
+        request: &RpcRequest,
+        panes: &[&state::PaneEntry],
+    ) -> Result<(), RpcError> {
+        if !matches!(
+            self.state.input_mode,
+            Some(InputMode::Scroll | InputMode::Search | InputMode::EnterSearch)
+        ) {
+            return Ok(());
+        }
+        let focused = self.focused_pane().map(|p| p.pane_id());
+        let Some(pane) = panes.iter().find(|p| Some(p.pane_id()) == focused) else {
+            return Ok(());
+        };
+        match request.params["scroll_mode"].as_str().unwrap_or("fail") {
+            "force" => Ok(()),
+            "exit" => {
+                switch_to_input_mode(&InputMode::Normal);
+                scroll_to_bottom_in_pane_id(pane.pane_id());
+                Ok(())
+            }
+            _ => Err(RpcError::new(
+                RpcErrorCode::ScrollMode,
                 format!(
-                    "{} panes match selector; use --all to target all",
-                    panes.len()
+                    "{} is in scroll/search mode; input would not reach it",
+                    pane.id_string()
                 ),
+            )
+            .with_hint("pass --exit-scroll to leave scroll mode first, or --force to send anyway")),
+        }
+    }
+
+    fn handle_pane_broadcast(
+        &mut self,
+        request: &RpcRequest,
+    ) -> Result<serde_json::Value, RpcError> {
+        let text = request.params["text"]
+            .as_str()
+            .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'text'"))?;
+        let all_tabs = request.params["all_tabs"].as_bool().unwrap_or(false);
+        let tab = request.params["tab"].as_u64().map(|tab| tab as usize);
+        if tab.is_none() && !all_tabs {
+            return Err(RpcError::new(
+                RpcErrorCode::InvalidParams,
+                "missing 'tab' (or set 'all_tabs')",
             ));
         }
 
-        for pane in &panes {
-            write_chars_to_pane_id(text, pane.pane_id());
+        let mut panes: Vec<&state::PaneEntry> = self
+            .state
+            .panes
+            .values()
+            .filter(|p| !p.is_plugin && !p.suppressed)
+            .filter(|p| all_tabs || Some(p.tab_index) == tab)
+            .collect();
+        if panes.is_empty() {
+            return Err(
+                RpcError::new(RpcErrorCode::NoMatch, "no terminal panes in tab")
+                    .with_hint("run: zjctl panes ls to see available panes"),
+            );
+        }
+        panes.sort_by_key(|p| (p.tab_index, p.numeric_id));
+
+        let targets: Vec<(String, PaneId, bool)> = panes
+            .iter()
+            .map(|pane| (pane.id_string(), pane.pane_id(), pane.exited || pane.held))
+            .collect();
+        let now = unix_time_ms();
+        let mut results = Vec::new();
+        for (id, pane_id, exited) in targets {
+            if exited {
+                results.push(serde_json::json!({ "pane": id, "ok": false, "skipped": "exited" }));
+                continue;
+            }
+            write_chars_to_pane_id(text, pane_id);
+            self.state.record_send(&id, text, now);
+            results.push(serde_json::json!({ "pane": id, "ok": true }));
+        }
+        let sent_to = results.iter().filter(|r| r["ok"] == true).count();
+
+        Ok(serde_json::json!({ "sent_to": sent_to, "panes": results }))
+    }
+
+    fn handle_pane_send_keys(
+        &mut self,
+        request: &RpcRequest,
+    ) -> Result<serde_json::Value, RpcError> {
+        let selector_str = request.params["selector"]
+            .as_str()
+            .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'selector'"))?;
+        let names: Vec<&str> = request.params["keys"]
+            .as_array()
+            .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'keys'"))?
+            .iter()
+            .map(|key| {
+                key.as_str().ok_or_else(|| {
+                    RpcError::new(RpcErrorCode::InvalidParams, "'keys' must be strings")
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        let all = request.params["all"].as_bool().unwrap_or(false);
+        let text = encode_keys(&names).map_err(|e| {
+            RpcError::new(RpcErrorCode::InvalidParams, e.to_string()).with_hint(
+                "use names like C-c, M-x, Enter, Escape, Tab, Up, PageDown, F5, or a single character",
+            )
+        })?;
+
+        let selector: PaneSelector = selector_str.parse().map_err(|e| {
+            RpcError::new(
+                RpcErrorCode::InvalidParams,
+                format!("invalid selector: {}", e),
+            )
+        })?;
+
+        let panes = self.resolve_selector(&selector)?;
+
+        if panes.is_empty() {
+            return Err(no_match_error(selector_str));
+        }
+        if panes.len() > 1 && !all {
+            return Err(ambiguous_error(&panes, true));
+        }
+
+        let targets: Vec<(String, PaneId)> = panes
+            .iter()
+            .map(|pane| (pane.id_string(), pane.pane_id()))
+            .collect();
+        self.guard_scroll_mode(request, &panes)?;
+        let now = unix_time_ms();
+        for (id, pane_id) in &targets {
+            write_chars_to_pane_id(&text, *pane_id);
+            self.state.record_send(id, &text, now);
+        }
+
+        Ok(serde_json::json!({ "sent_to": targets.len() }))
+    }
+
+    fn handle_pane_history(&self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
+        let selector_str = request.params["selector"]
+            .as_str()
+            .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'selector'"))?;
+        let limit = request.params["limit"].as_u64().map(|n| n as usize);
+
+        let selector: PaneSelector = selector_str.parse().map_err(|e| {
+            RpcError::new(
+                RpcErrorCode::InvalidParams,
+                format!("invalid selector: {}", e),
+            )
+        })?;
+
+        let panes = self.resolve_selector(&selector)?;
+
+        if panes.is_empty() {
+            return Err(no_match_error(selector_str));
+        }
+        if panes.len() > 1 {
+            return Err(ambiguous_error(&panes, false));
         }
 
-        Ok(serde_json::json!({ "sent_to": panes.len() }))
+        let id = panes[0].id_string();
+        let history = self.state.send_history.get(&id);
+        let skip = match (history, limit) {
+            (Some(history), Some(limit)) => history.len().saturating_sub(limit),
+            _ => 0,
+        };
+        let entries: Vec<&state::SentText> = history
+            .map(|history| history.iter().skip(skip).collect())
+            .unwrap_or_default();
+
+        Ok(serde_json::json!({ "pane": id, "entries": entries }))
+    }
+
+    /// Titles and commands a pane has shown, plus the last command it is known to have run
+    fn handle_panes_history(&self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
+        let limit = request.params["limit"].as_u64().map(|n| n as usize);
+        let pane = self.select_panes(request, false)?[0];
+        let id = pane.id_string();
+        let history = self.state.title_history.get(&id);
+        let last_command = history.and_then(|history| {
+            history
+                .iter()
+                .rev()
+                .find_map(|change| change.command.clone())
+        });
+        let skip = match (history, limit) {
+            (Some(history), Some(limit)) => history.len().saturating_sub(limit),
+            _ => 0,
+        };
+        let entries: Vec<&state::TitleChange> = history
+            .map(|history| history.iter().skip(skip).collect())
+            .unwrap_or_default();
+
+        Ok(serde_json::json!({
+            "pane": id,
+            "last_command": last_command,
+            "entries": entries,
+        }))
     }
 
     fn handle_pane_focus(&self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
@@ -283,16 +813,10 @@ impl ZrpcPlugin {
         let panes = self.resolve_selector(&selector)?;
 
         if panes.is_empty() {
-            return Err(RpcError::new(
-                RpcErrorCode::NoMatch,
-                "no panes match selector",
-            ));
+            return Err(no_match_error(selector_str));
         }
         if panes.len() > 1 {
-            return Err(RpcError::new(
-                RpcErrorCode::AmbiguousMatch,
-                format!("{} panes match selector", panes.len()),
-            ));
+            return Err(ambiguous_error(&panes, false));
         }
 
         let pane = &panes[0];
@@ -302,7 +826,25 @@ impl ZrpcPlugin {
         Ok(serde_json::json!({ "focused": pane.id_string() }))
     }
 
-    fn handle_pane_rename(&self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
+    fn handle_pane_focus_previous(
+        &self,
+        _request: &RpcRequest,
+    ) -> Result<serde_json::Value, RpcError> {
+        let current = self.focused_pane().map(|p| p.id_string());
+        let pane = self
+            .state
+            .previous_focus(current.as_deref())
+            .ok_or_else(|| {
+                RpcError::new(RpcErrorCode::NoMatch, "no previously focused pane")
+                    .with_hint("focus another pane first, then run: zjctl pane toggle")
+            })?;
+
+        focus_pane_with_id(pane.pane_id(), true);
+
+        Ok(serde_json::json!({ "focused": pane.id_string() }))
+    }
+
+    fn handle_pane_rename(&mut self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
         let selector_str = request.params["selector"]
             .as_str()
             .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'selector'"))?;
@@ -320,33 +862,27 @@ impl ZrpcPlugin {
         let panes = self.resolve_selector(&selector)?;
 
         if panes.is_empty() {
-            return Err(RpcError::new(
-                RpcErrorCode::NoMatch,
-                "no panes match selector",
-            ));
+            return Err(no_match_error(selector_str));
         }
         if panes.len() > 1 {
-            return Err(RpcError::new(
-                RpcErrorCode::AmbiguousMatch,
-                format!("{} panes match selector", panes.len()),
-            ));
+            return Err(ambiguous_error(&panes, false));
         }
 
-        let pane = &panes[0];
-        rename_pane_with_id(pane.pane_id(), name);
+        let pane_id = panes[0].pane_id();
+        let key = panes[0].id_string();
+        rename_pane_with_id(pane_id, name);
+        self.state.set_name(&key, name);
 
-        Ok(serde_json::json!({ "renamed": pane.id_string() }))
+        Ok(serde_json::json!({ "renamed": key }))
     }
 
-    fn handle_pane_resize(&self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
+    fn handle_pane_set_cwd(&mut self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
         let selector_str = request.params["selector"]
             .as_str()
             .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'selector'"))?;
-        let resize_type = request.params["resize_type"]
+        let cwd = request.params["cwd"]
             .as_str()
-            .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'resize_type'"))?;
-        let direction = request.params["direction"].as_str();
-        let step = request.params["step"].as_u64().unwrap_or(1) as usize;
+            .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'cwd'"))?;
 
         let selector: PaneSelector = selector_str.parse().map_err(|e| {
             RpcError::new(
@@ -358,83 +894,728 @@ impl ZrpcPlugin {
         let panes = self.resolve_selector(&selector)?;
 
         if panes.is_empty() {
-            return Err(RpcError::new(
-                RpcErrorCode::NoMatch,
-                "no panes match selector",
-            ));
+            return Err(no_match_error(selector_str));
         }
         if panes.len() > 1 {
-            return Err(RpcError::new(
-                RpcErrorCode::AmbiguousMatch,
-                format!("{} panes match selector", panes.len()),
-            ));
+            return Err(ambiguous_error(&panes, false));
         }
 
-        let pane = &panes[0];
-        let resize = match resize_type {
-            "increase" => Resize::Increase,
-            "decrease" => Resize::Decrease,
-            _ => {
-                return Err(RpcError::new(
-                    RpcErrorCode::InvalidParams,
-                    "resize_type must be 'increase' or 'decrease'",
-                ))
-            }
-        };
+        let key = panes[0].id_string();
+        self.state.set_cwd(&key, cwd);
 
-        let dir = match direction {
-            Some("left") => Some(Direction::Left),
-            Some("right") => Some(Direction::Right),
-            Some("up") => Some(Direction::Up),
-            Some("down") => Some(Direction::Down),
-            None => None,
-            Some(d) => {
-                return Err(RpcError::new(
-                    RpcErrorCode::InvalidParams,
-                    format!("invalid direction: {}", d),
-                ))
-            }
-        };
+        Ok(serde_json::json!({ "pane": key, "cwd": cwd }))
+    }
 
-        let strategy = ResizeStrategy {
-            resize,
-            direction: dir,
-            invert_on_boundaries: true,
-        };
+    fn handle_pane_set_token(
+        &mut self,
+        request: &RpcRequest,
+    ) -> Result<serde_json::Value, RpcError> {
+        let selector_str = request.params["selector"]
+            .as_str()
+            .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'selector'"))?;
+        let token = request.params["token"]
+            .as_str()
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'token'"))?;
 
-        for _ in 0..step {
-            resize_pane_with_id(strategy, pane.pane_id());
+        let selector: PaneSelector = selector_str.parse().map_err(|e| {
+            RpcError::new(
+                RpcErrorCode::InvalidParams,
+                format!("invalid selector: {}", e),
+            )
+        })?;
+
+        let panes = self.resolve_selector(&selector)?;
+
+        if panes.is_empty() {
+            return Err(no_match_error(selector_str));
+        }
+        if panes.len() > 1 {
+            return Err(ambiguous_error(&panes, false));
         }
 
-        Ok(serde_json::json!({ "resized": pane.id_string() }))
+        let key = panes[0].id_string();
+        // A token names one pane; hand it over if another pane had it.
+        self.state.tokens.retain(|_, existing| existing != token);
+        self.state.tokens.insert(key.clone(), token.to_string());
+
+        Ok(serde_json::json!({ "pane": key, "token": token }))
     }
 
-    fn resolve_selector(
-        &self,
-        selector: &PaneSelector,
-    ) -> Result<Vec<&state::PaneEntry>, RpcError> {
-        match selector {
-            PaneSelector::Focused => Ok(self.focused_pane().into_iter().collect()),
-            PaneSelector::Id { pane_type, id } => {
-                let is_plugin = matches!(pane_type, PaneType::Plugin);
-                let found: Vec<_> = self
-                    .state
-                    .panes
-                    .values()
-                    .filter(|p| p.numeric_id == *id && p.is_plugin == is_plugin)
-                    .collect();
-                Ok(found)
-            }
-            PaneSelector::Title { pattern } => {
-                let matching: Vec<_> = self
-                    .state
-                    .panes
-                    .values()
-                    .filter(|p| pattern.matches(&p.title).unwrap_or(false))
-                    .collect();
-                Ok(matching)
-            }
-            PaneSelector::Command { pattern } => {
+    fn handle_mark_set(&mut self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
+        let mark = request.params["mark"]
+            .as_str()
+            .filter(|mark| !mark.is_empty())
+            .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'mark'"))?;
+        // Without `all`, a selection is exactly one pane.
+        let key = self.select_panes(request, false)?[0].id_string();
+        let previous = self.state.marks.insert(mark.to_string(), key.clone());
+        Ok(serde_json::json!({ "mark": mark, "pane": key, "previous": previous }))
+    }
+
+    fn handle_mark_remove(&mut self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
+        let mark = request.params["mark"]
+            .as_str()
+            .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'mark'"))?;
+        let removed = self.state.marks.remove(mark);
+        Ok(serde_json::json!({ "mark": mark, "pane": removed }))
+    }
+
+    /// `[{mark, pane, title}]`, sorted by mark
+    fn marks_list(&self) -> serde_json::Value {
+        let marks: Vec<_> = self
+            .state
+            .marks
+            .iter()
+            .map(|(mark, key)| {
+                serde_json::json!({
+                    "mark": mark,
+                    "pane": key,
+                    "title": self.state.panes.get(key).map(|p| p.title.as_str()),
+                })
+            })
+            .collect();
+        serde_json::Value::Array(marks)
+    }
+
+    fn handle_tag_add(&mut self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
+        let tag = tag_param(request)?;
+        let keys: Vec<String> = self
+            .select_panes(request, true)?
+            .iter()
+            .map(|pane| pane.id_string())
+            .collect();
+        for key in &keys {
+            self.state
+                .tags
+                .entry(key.clone())
+                .or_default()
+                .insert(tag.to_string());
+        }
+        Ok(serde_json::json!({ "tag": tag, "panes": keys }))
+    }
+
+    /// Untag the panes matching `selector`, or every pane without one
+    fn handle_tag_remove(&mut self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
+        let tag = tag_param(request)?;
+        let targets: Option<Vec<String>> = match request.params["selector"].as_str() {
+            Some(_) => Some(
+                self.select_panes(request, true)?
+                    .iter()
+                    .map(|pane| pane.id_string())
+                    .collect(),
+            ),
+            None => None,
+        };
+        let mut removed: Vec<String> = Vec::new();
+        for (key, tags) in &mut self.state.tags {
+            let targeted = targets.as_ref().is_none_or(|keys| keys.contains(key));
+            if targeted && tags.remove(tag) {
+                removed.push(key.clone());
+            }
+        }
+        self.state.tags.retain(|_, tags| !tags.is_empty());
+        removed.sort();
+        Ok(serde_json::json!({ "tag": tag, "panes": removed }))
+    }
+
+    /// `[{tag, panes}]`, sorted by tag, with pane IDs sorted within each tag
+    fn tags_list(&self) -> serde_json::Value {
+        let mut tags: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (key, pane_tags) in &self.state.tags {
+            for tag in pane_tags {
+                tags.entry(tag).or_default().push(key);
+            }
+        }
+        let tags: Vec<_> = tags
+            .into_iter()
+            .map(|(tag, mut panes)| {
+                panes.sort();
+                serde_json::json!({ "tag": tag, "panes": panes })
+            })
+            .collect();
+        serde_json::Value::Array(tags)
+    }
+
+    /// Panes addressed by the request's `selector`; several only when `all` is set and allowed
+    fn select_panes(
+        &self,
+        request: &RpcRequest,
+        supports_all: bool,
+    ) -> Result<Vec<&state::PaneEntry>, RpcError> {
+        let selector_str = request.params["selector"]
+            .as_str()
+            .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'selector'"))?;
+        let all = supports_all && request.params["all"].as_bool().unwrap_or(false);
+
+        let selector: PaneSelector = selector_str.parse().map_err(|e| {
+            RpcError::new(
+                RpcErrorCode::InvalidParams,
+                format!("invalid selector: {}", e),
+            )
+        })?;
+
+        let mut panes = self.resolve_selector(&selector)?;
+
+        if panes.is_empty() {
+            return Err(no_match_error(selector_str));
+        }
+        if panes.len() > 1 && !all {
+            return Err(ambiguous_error(&panes, supports_all));
+        }
+        panes.sort_by_key(|p| (p.tab_index, p.is_plugin, p.numeric_id));
+        Ok(panes)
+    }
+
+    fn handle_pane_toggle_float(
+        &self,
+        request: &RpcRequest,
+    ) -> Result<serde_json::Value, RpcError> {
+        let results: Vec<serde_json::Value> = self
+            .select_panes(request, true)?
+            .iter()
+            .map(|pane| {
+                toggle_pane_embed_or_eject_for_pane_id(pane.pane_id());
+                serde_json::json!({ "id": pane.id_string(), "floating": !pane.floating })
+            })
+            .collect();
+        Ok(serde_json::json!({ "results": results }))
+    }
+
+    fn handle_pane_toggle_fullscreen(
+        &self,
+        request: &RpcRequest,
+    ) -> Result<serde_json::Value, RpcError> {
+        let panes = self.select_panes(request, false)?;
+        let pane = panes[0];
+        if pane.floating {
+            return Err(RpcError::new(
+                RpcErrorCode::InvalidParams,
+                format!(
+                    "{} is floating; only tiled panes go fullscreen",
+                    pane.id_string()
+                ),
+            )
+            .with_hint("embed it first: zjctl pane toggle-float"));
+        }
+        toggle_pane_id_fullscreen(pane.pane_id());
+        Ok(serde_json::json!({ "toggled": pane.id_string() }))
+    }
+
+    fn handle_pane_pin(&self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
+        let pinned = request.params["pinned"].as_bool().unwrap_or(true);
+        let panes = self.select_panes(request, true)?;
+        if let Some(tiled) = panes.iter().find(|pane| !pane.floating) {
+            return Err(RpcError::new(
+                RpcErrorCode::InvalidParams,
+                format!(
+                    "{} is not floating; only floating panes can be pinned",
+                    tiled.id_string()
+                ),
+            )
+            .with_hint("float it first: zjctl pane toggle-float"));
+        }
+        let ids: Vec<String> = panes
+            .iter()
+            .map(|pane| {
+                set_floating_pane_pinned(pane.pane_id(), pinned);
+                pane.id_string()
+            })
+            .collect();
+        Ok(serde_json::json!({ "panes": ids, "pinned": pinned }))
+    }
+
+    /// Clear panes' screens (`screen`, default true) and/or scrollback (`scrollback`)
+    ///
+    /// Zellij can only drop a pane's scrollback itself; the screen is cleared by sending
+    /// Ctrl+L, which shells and most TUIs answer by redrawing on a blank screen.
+    fn handle_pane_clear(&self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
+        let screen = request.params["screen"].as_bool().unwrap_or(true);
+        let scrollback = request.params["scrollback"].as_bool().unwrap_or(false);
+        let panes = self.select_panes(request, true)?;
+        if let Some(plugin) = panes.iter().find(|pane| pane.is_plugin) {
+            return Err(RpcError::new(
+                RpcErrorCode::InvalidParams,
+                format!(
+                    "{} is a plugin pane; only terminal panes can be cleared",
+                    plugin.id_string()
+                ),
+            ));
+        }
+        let ids: Vec<String> = panes
+            .iter()
+            .map(|pane| {
+                if screen {
+                    write_chars_to_pane_id("\u{c}", pane.pane_id());
+                }
+                if scrollback {
+                    clear_screen_for_pane_id(pane.pane_id());
+                }
+                pane.id_string()
+            })
+            .collect();
+        Ok(serde_json::json!({ "panes": ids, "screen": screen, "scrollback": scrollback }))
+    }
+
+    /// Move panes to another tab (`tab`: 0-based position, or "new" with an optional
+    /// `tab_name`) and/or swap them with their neighbour in `direction`
+    fn handle_pane_move(&self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
+        let direction = match request.params["direction"].as_str() {
+            None => None,
+            Some("left") => Some(Direction::Left),
+            Some("right") => Some(Direction::Right),
+            Some("up") => Some(Direction::Up),
+            Some("down") => Some(Direction::Down),
+            Some(d) => {
+                return Err(RpcError::new(
+                    RpcErrorCode::InvalidParams,
+                    format!("invalid direction: {d}"),
+                ))
+            }
+        };
+        let tab = &request.params["tab"];
+        if tab.is_null() && direction.is_none() {
+            return Err(RpcError::new(
+                RpcErrorCode::InvalidParams,
+                "give 'tab' and/or 'direction'",
+            ));
+        }
+        let focus = request.params["focus"].as_bool().unwrap_or(false);
+        let panes = self.select_panes(request, true)?;
+        let ids: Vec<PaneId> = panes.iter().map(|pane| pane.pane_id()).collect();
+
+        let target_tab = match tab {
+            serde_json::Value::Null => None,
+            serde_json::Value::String(new) if new == "new" => {
+                let name = request.params["tab_name"].as_str().map(str::to_string);
+                break_panes_to_new_tab(&ids, name, focus);
+                Some(serde_json::json!("new"))
+            }
+            value => {
+                let index = value.as_u64().ok_or_else(|| {
+                    RpcError::new(
+                        RpcErrorCode::InvalidParams,
+                        "'tab' must be a non-negative integer or \"new\"",
+                    )
+                })? as usize;
+                if self.state.tabs.get(index).is_none() {
+                    return Err(
+                        RpcError::new(RpcErrorCode::NoMatch, format!("no tab {index}"))
+                            .with_hint("run: zjctl tab ls to see tab indexes"),
+                    );
+                }
+                let moving: Vec<PaneId> = panes
+                    .iter()
+                    .filter(|pane| pane.tab_index != index)
+                    .map(|pane| pane.pane_id())
+                    .collect();
+                if !moving.is_empty() {
+                    break_panes_to_tab_with_index(&moving, index, focus);
+                }
+                Some(serde_json::json!(index))
+            }
+        };
+        if let Some(direction) = direction {
+            for id in &ids {
+                move_pane_with_pane_id_in_direction(*id, direction);
+            }
+        }
+
+        let moved: Vec<String> = panes.iter().map(|pane| pane.id_string()).collect();
+        Ok(serde_json::json!({
+            "panes": moved,
+            "tab": target_tab,
+            "direction": request.params["direction"],
+        }))
+    }
+
+    fn handle_pane_resize(&self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
+        let selector_str = request.params["selector"]
+            .as_str()
+            .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'selector'"))?;
+        let resize_type = request.params["resize_type"]
+            .as_str()
+            .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'resize_type'"))?;
+        let direction = request.params["direction"].as_str();
+        let step = request.params["step"].as_u64().unwrap_or(1) as usize;
+
+        let selector: PaneSelector = selector_str.parse().map_err(|e| {
+            RpcError::new(
+                RpcErrorCode::InvalidParams,
+                format!("invalid selector: {}", e),
+            )
+        })?;
+
+        let panes = self.resolve_selector(&selector)?;
+
+        if panes.is_empty() {
+            return Err(no_match_error(selector_str));
+        }
+        if panes.len() > 1 {
+            return Err(ambiguous_error(&panes, false));
+        }
+
+        let pane = &panes[0];
+        let resize = match resize_type {
+            "increase" => Resize::Increase,
+            "decrease" => Resize::Decrease,
+            _ => {
+                return Err(RpcError::new(
+                    RpcErrorCode::InvalidParams,
+                    "resize_type must be 'increase' or 'decrease'",
+                ))
+            }
+        };
+
+        let dir = match direction {
+            Some("left") => Some(Direction::Left),
+            Some("right") => Some(Direction::Right),
+            Some("up") => Some(Direction::Up),
+            Some("down") => Some(Direction::Down),
+            None => None,
+            Some(d) => {
+                return Err(RpcError::new(
+                    RpcErrorCode::InvalidParams,
+                    format!("invalid direction: {}", d),
+                ))
+            }
+        };
+
+        let strategy = ResizeStrategy {
+            resize,
+            direction: dir,
+            invert_on_boundaries: true,
+        };
+
+        for _ in 0..step {
+            resize_pane_with_id(strategy, pane.pane_id());
+        }
+
+        Ok(serde_json::json!({ "resized": pane.id_string() }))
+    }
+
+    fn handle_pane_scroll(&self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
+        let selector_str = request.params["selector"]
+            .as_str()
+            .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'selector'"))?;
+        let direction = request.params["direction"]
+            .as_str()
+            .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'direction'"))?;
+        let unit = request.params["unit"].as_str().unwrap_or("line");
+        let count = request.params["count"].as_u64().unwrap_or(1) as usize;
+
+        let selector: PaneSelector = selector_str.parse().map_err(|e| {
+            RpcError::new(
+                RpcErrorCode::InvalidParams,
+                format!("invalid selector: {}", e),
+            )
+        })?;
+
+        let panes = self.resolve_selector(&selector)?;
+
+        if panes.is_empty() {
+            return Err(no_match_error(selector_str));
+        }
+        if panes.len() > 1 {
+            return Err(ambiguous_error(&panes, false));
+        }
+
+        let pane = &panes[0];
+        let pane_id = pane.pane_id();
+        match (direction, unit) {
+            ("top", _) => scroll_to_top_in_pane_id(pane_id),
+            ("bottom", _) => scroll_to_bottom_in_pane_id(pane_id),
+            ("up", "page") => (0..count).for_each(|_| page_scroll_up_in_pane_id(pane_id)),
+            ("down", "page") => (0..count).for_each(|_| page_scroll_down_in_pane_id(pane_id)),
+            ("up" | "down", "line" | "half_page") => {
+                // Zellij has no half-page scroll for a pane id, so it is done line by line.
+                let lines = if unit == "half_page" {
+                    (pane.rows / 2).max(1) * count
+                } else {
+                    count
+                };
+                for _ in 0..lines {
+                    if direction == "up" {
+                        scroll_up_in_pane_id(pane_id);
+                    } else {
+                        scroll_down_in_pane_id(pane_id);
+                    }
+                }
+            }
+            ("up" | "down", _) => {
+                return Err(RpcError::new(
+                    RpcErrorCode::InvalidParams,
+                    "unit must be 'line', 'half_page', or 'page'",
+                ))
+            }
+            (d, _) => {
+                return Err(RpcError::new(
+                    RpcErrorCode::InvalidParams,
+                    format!("invalid direction: {}", d),
+                ))
+            }
+        }
+
+        Ok(serde_json::json!({ "scrolled": pane.id_string() }))
+    }
+
+    fn handle_pane_close(&self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
+        let selector_str = request.params["selector"]
+            .as_str()
+            .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'selector'"))?;
+        let all = request.params["all"].as_bool().unwrap_or(false);
+        let force = request.params["force"].as_bool().unwrap_or(false);
+
+        let selector: PaneSelector = selector_str.parse().map_err(|e| {
+            RpcError::new(
+                RpcErrorCode::InvalidParams,
+                format!("invalid selector: {}", e),
+            )
+        })?;
+
+        let mut panes = self.resolve_selector(&selector)?;
+
+        if panes.is_empty() {
+            return Err(no_match_error(selector_str));
+        }
+        if panes.len() > 1 && !all {
+            return Err(ambiguous_error(&panes, true));
+        }
+
+        panes.sort_by_key(|p| (p.tab_index, p.is_plugin, p.numeric_id));
+        let focused_id = self.focused_pane().map(|p| p.id_string());
+
+        let results: Vec<serde_json::Value> = panes
+            .iter()
+            .map(|pane| {
+                let id = pane.id_string();
+                if !force && focused_id.as_deref() == Some(id.as_str()) {
+                    return serde_json::json!({
+                        "id": id,
+                        "closed": false,
+                        "error": "refusing to close focused pane (use --force)",
+                    });
+                }
+                close_pane_with_id(pane.pane_id());
+                serde_json::json!({ "id": id, "closed": true })
+            })
+            .collect();
+
+        Ok(serde_json::json!({ "results": results }))
+    }
+
+    fn handle_pane_rerun(&self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
+        let selector_str = request.params["selector"]
+            .as_str()
+            .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'selector'"))?;
+
+        let selector: PaneSelector = selector_str.parse().map_err(|e| {
+            RpcError::new(
+                RpcErrorCode::InvalidParams,
+                format!("invalid selector: {}", e),
+            )
+        })?;
+
+        let panes = self.resolve_selector(&selector)?;
+
+        if panes.is_empty() {
+            return Err(no_match_error(selector_str));
+        }
+        if panes.len() > 1 {
+            return Err(ambiguous_error(&panes, false));
+        }
+
+        let pane = &panes[0];
+        if pane.is_plugin || pane.command.is_none() {
+            return Err(RpcError::new(
+                RpcErrorCode::InvalidParams,
+                format!("{} is not a command pane", pane.id_string()),
+            )
+            .with_hint(
+                "only panes started with a command (e.g. `zjctl pane launch -- cmd`) can be re-run",
+            ));
+        }
+
+        rerun_command_pane(pane.numeric_id);
+        Ok(serde_json::json!({ "rerun": pane.id_string() }))
+    }
+
+    fn handle_tabs_list(&self) -> Result<serde_json::Value, RpcError> {
+        let result = state::TabsListResult {
+            session: self.state.session_name.clone(),
+            generation: self.state.generation,
+            tabs: self.state.list_tabs(),
+            clients: self.state.clients.clone(),
+        };
+
+        serde_json::to_value(&result).map_err(|e| {
+            RpcError::new(
+                RpcErrorCode::Internal,
+                format!("serialization error: {}", e),
+            )
+        })
+    }
+
+    fn handle_tab_rename(&self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
+        let name = request.params["name"]
+            .as_str()
+            .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'name'"))?;
+
+        let tab = self.resolve_tab(request)?;
+        // Zellij's tab APIs take 1-based positions.
+        rename_tab(tab.index as u32 + 1, name);
+
+        Ok(serde_json::json!({ "renamed": tab.index }))
+    }
+
+    fn handle_tab_new(&self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
+        let name = request.params["name"].as_str();
+        let cwd = request.params["cwd"].as_str();
+
+        new_tab(name, cwd);
+
+        Ok(serde_json::json!({ "created": name }))
+    }
+
+    fn handle_tab_close(&self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
+        let force = request.params["force"].as_bool().unwrap_or(false);
+        let tab = self.resolve_tab(request)?;
+
+        // Closing the last tab ends the session, which is rarely what a script means.
+        if self.state.tabs.len() <= 1 && !force {
+            return Err(RpcError::new(
+                RpcErrorCode::InvalidParams,
+                "refusing to close the last tab (this would end the session)",
+            )
+            .with_hint("pass force to close it anyway"));
+        }
+
+        close_tab_with_index(tab.index);
+
+        Ok(serde_json::json!({ "closed": tab.index }))
+    }
+
+    fn handle_tab_focus(&self, request: &RpcRequest) -> Result<serde_json::Value, RpcError> {
+        let tab = self.resolve_tab(request)?;
+        switch_tab_to(tab.index as u32 + 1);
+
+        Ok(serde_json::json!({ "focused": tab.index }))
+    }
+
+    /// Resolve the `tab` param (0-based position) or fall back to the active tab
+    fn resolve_tab(&self, request: &RpcRequest) -> Result<&state::TabEntry, RpcError> {
+        match &request.params["tab"] {
+            serde_json::Value::Null => self
+                .state
+                .tabs
+                .iter()
+                .find(|t| t.active)
+                .ok_or_else(|| RpcError::new(RpcErrorCode::NoMatch, "no active tab")),
+            value => {
+                let index = value.as_u64().ok_or_else(|| {
+                    RpcError::new(
+                        RpcErrorCode::InvalidParams,
+                        "'tab' must be a non-negative integer",
+                    )
+                })? as usize;
+                self.state.tabs.get(index).ok_or_else(|| {
+                    RpcError::new(RpcErrorCode::NoMatch, format!("no tab {index}"))
+                        .with_hint("run: zjctl tab ls to see tab indexes")
+                })
+            }
+        }
+    }
+
+    fn resolve_selector(
+        &self,
+        selector: &PaneSelector,
+    ) -> Result<Vec<&state::PaneEntry>, RpcError> {
+        match selector {
+            PaneSelector::Focused => Ok(self.focused_pane().into_iter().collect()),
+            PaneSelector::Id { pane_type, id } => {
+                let is_plugin = matches!(pane_type, PaneType::Plugin);
+                let found: Vec<_> = self
+                    .state
+                    .panes
+                    .values()
+                    .filter(|p| p.numeric_id == *id && p.is_plugin == is_plugin)
+                    .collect();
+                Ok(found)
+            }
+            PaneSelector::Title { pattern } => {
+                let matching: Vec<_> = self
+                    .state
+                    .panes
+                    .values()
+                    .filter(|p| {
+                        pattern.matches(&p.title).unwrap_or(false)
+                            || p.name
+                                .as_ref()
+                                .map(|n| pattern.matches(n).unwrap_or(false))
+                                .unwrap_or(false)
+                    })
+                    .collect();
+                Ok(matching)
+            }
+            PaneSelector::Name { pattern } => {
+                let matching: Vec<_> = self
+                    .state
+                    .panes
+                    .values()
+                    .filter(|p| {
+                        p.name
+                            .as_ref()
+                            .map(|n| pattern.matches(n).unwrap_or(false))
+                            .unwrap_or(false)
+                    })
+                    .collect();
+                Ok(matching)
+            }
+            PaneSelector::TerminalTitle { pattern } => {
+                let matching: Vec<_> = self
+                    .state
+                    .panes
+                    .values()
+                    .filter(|p| {
+                        p.terminal_title()
+                            .map(|t| pattern.matches(t).unwrap_or(false))
+                            .unwrap_or(false)
+                    })
+                    .collect();
+                Ok(matching)
+            }
+            PaneSelector::Cwd { pattern } => Ok(self
+                .state
+                .panes
+                .values()
+                .filter(|p| {
+                    p.cwd
+                        .as_ref()
+                        .is_some_and(|cwd| pattern.matches(cwd).unwrap_or(false))
+                })
+                .collect()),
+            PaneSelector::Mark { mark } => Ok(self
+                .state
+                .marks
+                .get(mark)
+                .and_then(|key| self.state.panes.get(key))
+                .into_iter()
+                .collect()),
+            PaneSelector::Tag { tag } => Ok(self
+                .state
+                .tags
+                .iter()
+                .filter(|(_, tags)| tags.contains(tag))
+                .filter_map(|(key, _)| self.state.panes.get(key))
+                .collect()),
+            PaneSelector::Token { token } => Ok(self
+                .state
+                .tokens
+                .iter()
+                .filter(|(_, t)| *t == token)
+                .filter_map(|(key, _)| self.state.panes.get(key))
+                .collect()),
+            PaneSelector::Command { pattern } => {
                 let matching: Vec<_> = self
                     .state
                     .panes
@@ -458,12 +1639,372 @@ impl ZrpcPlugin {
                 panes.sort_by_key(|p| (p.is_plugin, p.numeric_id));
                 Ok(panes.get(*index).copied().into_iter().collect())
             }
+            PaneSelector::Tab { tab } => Ok(self
+                .state
+                .panes
+                .values()
+                .filter(|p| p.tab_index == *tab)
+                .collect()),
+            PaneSelector::Floating => {
+                Ok(self.state.panes.values().filter(|p| p.floating).collect())
+            }
+            PaneSelector::All { selectors } => {
+                let mut matching: Vec<&state::PaneEntry> = self.state.panes.values().collect();
+                for selector in selectors {
+                    let found = self.resolve_selector(selector)?;
+                    matching.retain(|p| found.iter().any(|f| same_pane(f, p)));
+                }
+                Ok(matching)
+            }
+            PaneSelector::Any { selectors } => {
+                let mut matching: Vec<&state::PaneEntry> = Vec::new();
+                for selector in selectors {
+                    for pane in self.resolve_selector(selector)? {
+                        if !matching.iter().any(|m| same_pane(m, pane)) {
+                            matching.push(pane);
+                        }
+                    }
+                }
+                Ok(matching)
+            }
+            PaneSelector::Not { selector } => {
+                let excluded = self.resolve_selector(selector)?;
+                Ok(self
+                    .state
+                    .panes
+                    .values()
+                    .filter(|p| !excluded.iter().any(|e| same_pane(e, p)))
+                    .collect())
+            }
+        }
+    }
+
+    fn start_host_exec(&mut self, pipe_id: &str, request: &RpcRequest) -> Result<(), RpcError> {
+        if !self.host_exec_configured {
+            let was_enabled = !self.host_exec_allow.is_empty();
+            self.host_exec_allow = read_host_exec_allow();
+            if !was_enabled && !self.host_exec_allow.is_empty() {
+                // Published after this instance loaded: it has yet to ask for RunCommands.
+                request_permission(&self.permissions());
+                return Err(RpcError::new(
+                    RpcErrorCode::PolicyDenied,
+                    "host.exec was enabled after the plugin loaded",
+                )
+                .with_hint("grant the RunCommands permission if Zellij asks, then retry"));
+            }
+        }
+        if self.host_exec_allow.is_empty() {
+            return Err(
+                RpcError::new(RpcErrorCode::InvalidRequest, "host.exec is disabled")
+                    .with_hint(
+                        "allow specific programs with host_exec_allow \"prog1:prog2\" in a load_plugins entry for zrpc in config.kdl, then restart the session",
+                    ),
+            );
+        }
+        let argv: Vec<&str> = request.params["argv"]
+            .as_array()
+            .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'argv'"))?
+            .iter()
+            .map(|arg| {
+                arg.as_str().ok_or_else(|| {
+                    RpcError::new(RpcErrorCode::InvalidParams, "'argv' must be strings")
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        let program = *argv
+            .first()
+            .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "'argv' is empty"))?;
+        if !self
+            .host_exec_allow
+            .iter()
+            .any(|allowed| allowed == program)
+        {
+            return Err(RpcError::new(
+                RpcErrorCode::InvalidRequest,
+                format!("program not allowed by host.exec allowlist: {program}"),
+            )
+            .with_hint(format!(
+                "allowed programs: {}",
+                self.host_exec_allow.join(", ")
+            )));
+        }
+        let cwd = request.params["cwd"].as_str().unwrap_or(".");
+        let wait_ms = request.params["wait_ms"]
+            .as_u64()
+            .unwrap_or(HOST_EXEC_WAIT_MS)
+            .min(MAX_HOST_EXEC_WAIT_MS);
+
+        let context = self.defer(pipe_id, request);
+        run_command_with_env_variables_and_cwd(
+            &argv,
+            BTreeMap::new(),
+            std::path::PathBuf::from(cwd),
+            context.clone(),
+        );
+        self.exec_waiters.push(ExecWaiter {
+            context,
+            deadline_ms: unix_time_ms().saturating_add(wait_ms),
+        });
+        set_timeout(wait_ms as f64 / 1000.0);
+        Ok(())
+    }
+
+    /// Answer `host.exec` calls whose command outlived `wait_ms`, so the pipe (shared by every
+    /// caller of a daemon) is not held until it exits (checked on every host event, including
+    /// the timer armed for the deadline). The command itself keeps running.
+    fn expire_host_exec(&mut self) {
+        if self.exec_waiters.is_empty() {
+            return;
+        }
+        let now = unix_time_ms();
+        let (expired, running): (Vec<_>, Vec<_>) = std::mem::take(&mut self.exec_waiters)
+            .into_iter()
+            .partition(|waiter| now >= waiter.deadline_ms);
+        self.exec_waiters = running;
+        for waiter in expired {
+            let error = RpcError::new(RpcErrorCode::Internal, "host.exec timed out")
+                .with_hint("the command is still running on the host; pass a longer --timeout");
+            self.finish_pending(&waiter.context, Err(error));
+        }
+    }
+
+    /// Permissions to ask for: the base set, `RunCommands` when the user allowlisted host
+    /// commands, and `WebAccess` once an update check asked for it. Zellij replaces the granted
+    /// set with each request, so every request carries the whole list.
+    fn permissions(&self) -> Vec<PermissionType> {
+        let mut permissions = vec![
+            PermissionType::ReadApplicationState,
+            PermissionType::WriteToStdin,
+            PermissionType::ChangeApplicationState,
+            PermissionType::ReadCliPipes,
+            PermissionType::OpenTerminalsOrPlugins,
+        ];
+        if !self.host_exec_allow.is_empty() {
+            permissions.push(PermissionType::RunCommands);
         }
+        if self.web_access != WebAccess::NotRequested {
+            permissions.push(PermissionType::WebAccess);
+        }
+        permissions
+    }
+
+    fn start_check_update(&mut self, pipe_id: &str, request: &RpcRequest) -> Result<(), RpcError> {
+        match self.web_access {
+            WebAccess::Granted => {
+                let context = self.defer(pipe_id, request);
+                fetch_latest_release(context);
+            }
+            WebAccess::Denied => {
+                return Err(RpcError::new(
+                    RpcErrorCode::PolicyDenied,
+                    "WebAccess permission was denied",
+                )
+                .with_hint("reload the zrpc plugin to be asked again"));
+            }
+            WebAccess::NotRequested | WebAccess::Requested => {
+                // Answered once the user accepts or denies the prompt (at once when Zellij
+                // remembers an earlier answer).
+                let context = self.defer(pipe_id, request);
+                self.awaiting_web_access.push(context);
+                if self.web_access == WebAccess::NotRequested {
+                    self.web_access = WebAccess::Requested;
+                    request_permission(&self.permissions());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn web_access_answered(&mut self, status: PermissionStatus) {
+        let waiting = std::mem::take(&mut self.awaiting_web_access);
+        if status == PermissionStatus::Granted {
+            self.web_access = WebAccess::Granted;
+            waiting.into_iter().for_each(fetch_latest_release);
+            return;
+        }
+        self.web_access = WebAccess::Denied;
+        for context in waiting {
+            let error = RpcError::new(
+                RpcErrorCode::PolicyDenied,
+                "WebAccess permission was denied",
+            );
+            self.finish_pending(&context, Err(error));
+        }
+    }
+
+    /// Whether `events.poll` can be answered right away: a first call (no `since`), a call that
+    /// does not wait, or one that missed events. A `since` past the newest event means the
+    /// plugin restarted, so the caller is answered to resynchronize.
+    fn events_ready(&self, request: &RpcRequest) -> bool {
+        match request.params["since"].as_u64() {
+            None => true,
+            Some(since) => {
+                since != self.state.event_seq || request.params["wait_ms"].as_u64() == Some(0)
+            }
+        }
+    }
+
+    /// Park an `events.poll` until an event arrives or `wait_ms` passes
+    fn start_events_poll(&mut self, pipe_id: &str, request: &RpcRequest) -> Result<(), RpcError> {
+        let since = request.params["since"]
+            .as_u64()
+            .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'since'"))?;
+        let wait_ms = request.params["wait_ms"]
+            .as_u64()
+            .unwrap_or(MAX_EVENTS_WAIT_MS)
+            .min(MAX_EVENTS_WAIT_MS);
+        let context = self.defer(pipe_id, request);
+        self.event_waiters.push(EventWaiter {
+            context,
+            since,
+            deadline_ms: unix_time_ms().saturating_add(wait_ms),
+        });
+        set_timeout(wait_ms as f64 / 1000.0);
+        Ok(())
+    }
+
+    /// Answer parked `events.poll` calls that have news or ran out of time (checked on every
+    /// host event, including the timer armed for the deadline)
+    fn answer_event_waiters(&mut self) {
+        if self.event_waiters.is_empty() {
+            return;
+        }
+        let now = unix_time_ms();
+        let (ready, waiting): (Vec<_>, Vec<_>) = std::mem::take(&mut self.event_waiters)
+            .into_iter()
+            .partition(|waiter| waiter.since != self.state.event_seq || now >= waiter.deadline_ms);
+        self.event_waiters = waiting;
+        for waiter in ready {
+            let result = self.events_result(Some(waiter.since));
+            self.finish_pending(&waiter.context, Ok(result));
+        }
+    }
+
+    /// `{seq, events, missed}`: events after `since` (none for a first call) and how many were
+    /// already dropped from the plugin's log
+    fn events_result(&self, since: Option<u64>) -> serde_json::Value {
+        let since = since
+            .filter(|since| *since <= self.state.event_seq)
+            .unwrap_or(self.state.event_seq);
+        let (events, missed) = self.state.events_since(since);
+        serde_json::json!({
+            "seq": self.state.event_seq,
+            "events": events,
+            "missed": missed,
+        })
+    }
+
+    /// Open a command pane; answered from update() once the host reports the new pane's id
+    fn start_pane_launch(&mut self, pipe_id: &str, request: &RpcRequest) -> Result<(), RpcError> {
+        let argv: Vec<String> = request.params["command"]
+            .as_array()
+            .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'command'"))?
+            .iter()
+            .map(|arg| {
+                arg.as_str().map(str::to_string).ok_or_else(|| {
+                    RpcError::new(RpcErrorCode::InvalidParams, "'command' must be strings")
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        let Some((program, args)) = argv.split_first() else {
+            return Err(RpcError::new(
+                RpcErrorCode::InvalidParams,
+                "'command' is empty",
+            ));
+        };
+        let floating = request.params["floating"].as_bool().unwrap_or(false);
+        let in_place = request.params["in_place"].as_bool().unwrap_or(false);
+        if floating && in_place {
+            return Err(RpcError::new(
+                RpcErrorCode::InvalidParams,
+                "'floating' and 'in_place' are mutually exclusive",
+            ));
+        }
+
+        let command = CommandToRun {
+            path: program.into(),
+            args: args.to_vec(),
+            cwd: request.params["cwd"].as_str().map(Into::into),
+        };
+        let mut context = self.defer(pipe_id, request);
+        if let Some(name) = request.params["name"].as_str() {
+            context.insert(LAUNCH_NAME_KEY.to_string(), name.to_string());
+        }
+        if let Some(cwd) = request.params["cwd"].as_str() {
+            context.insert(LAUNCH_CWD_KEY.to_string(), cwd.to_string());
+        }
+        if floating {
+            open_command_pane_floating(command, None, context);
+        } else if in_place {
+            open_command_pane_in_place(command, context);
+        } else {
+            open_command_pane(command, context);
+        }
+        Ok(())
+    }
+
+    fn command_pane_opened(
+        &mut self,
+        terminal_id: u32,
+        context: &BTreeMap<String, String>,
+    ) -> serde_json::Value {
+        let id = format!("terminal:{terminal_id}");
+        if let Some(name) = context.get(LAUNCH_NAME_KEY) {
+            rename_terminal_pane(terminal_id, name);
+            self.state.set_name(&id, name);
+        }
+        if let Some(cwd) = context.get(LAUNCH_CWD_KEY) {
+            self.state.set_cwd(&id, cwd);
+        }
+        serde_json::json!({ "id": id })
+    }
+
+    /// Register `request` as answered later; returns the context to attach to the host call
+    fn defer(&mut self, pipe_id: &str, request: &RpcRequest) -> BTreeMap<String, String> {
+        *self.pending.entry(pipe_id.to_string()).or_default() += 1;
+        let mut context = BTreeMap::new();
+        context.insert(PENDING_PIPE_KEY.to_string(), pipe_id.to_string());
+        context.insert(PENDING_REQUEST_KEY.to_string(), request.id.to_string());
+        context
+    }
+
+    fn finish_pending(
+        &mut self,
+        context: &BTreeMap<String, String>,
+        result: Result<serde_json::Value, RpcError>,
+    ) {
+        let (Some(pipe_id), Some(id)) = (
+            context.get(PENDING_PIPE_KEY),
+            context
+                .get(PENDING_REQUEST_KEY)
+                .and_then(|id| id.parse::<uuid::Uuid>().ok()),
+        ) else {
+            return;
+        };
+
+        let response = match result {
+            Ok(value) => RpcResponse::success(id, value).expect("failed to serialize response"),
+            Err(error) => RpcResponse::error(id, error),
+        };
+        self.write_response(pipe_id, &response);
+
+        let remaining = self.pending.get_mut(pipe_id).map(|count| {
+            *count = count.saturating_sub(1);
+            *count
+        });
+        if remaining == Some(0) {
+            self.pending.remove(pipe_id);
+            unblock_cli_pipe_input(pipe_id);
+        }
+    }
+
+    fn write_response(&self, pipe_id: &str, response: &RpcResponse) {
+        let json = serde_json::to_string(response).expect("failed to serialize response");
+        cli_pipe_output(pipe_id, &format!("{json}\n"));
     }
 
     fn send_response(&self, pipe_id: &str, response: RpcResponse) {
-        let json = serde_json::to_string(&response).expect("failed to serialize response");
-        cli_pipe_output(pipe_id, &json);
+        self.write_response(pipe_id, &response);
         // Signal we're done with this pipe
         unblock_cli_pipe_input(pipe_id);
     }
@@ -480,3 +2021,173 @@ impl ZrpcPlugin {
         self.send_response(pipe_id, response);
     }
 }
+
+/// Ask GitHub for the latest release; the answer arrives as a `WebRequestResult`
+fn fetch_latest_release(context: BTreeMap<String, String>) {
+    let mut headers = BTreeMap::new();
+    headers.insert("User-Agent".to_string(), "zjctl-zrpc".to_string());
+    headers.insert(
+        "Accept".to_string(),
+        "application/vnd.github+json".to_string(),
+    );
+    web_request(
+        LATEST_RELEASE_URL,
+        HttpVerb::Get,
+        headers,
+        Vec::new(),
+        context,
+    );
+}
+
+/// Compare the running plugin against the latest GitHub release
+fn update_check_result(status: u16, body: &[u8]) -> Result<serde_json::Value, RpcError> {
+    if status != 200 {
+        return Err(RpcError::new(
+            RpcErrorCode::Internal,
+            format!("update check failed: HTTP {status}"),
+        ));
+    }
+    let release: serde_json::Value = serde_json::from_slice(body).map_err(|e| {
+        RpcError::new(
+            RpcErrorCode::Internal,
+            format!("update check failed: invalid response: {e}"),
+        )
+    })?;
+    let latest = release["tag_name"].as_str().ok_or_else(|| {
+        RpcError::new(
+            RpcErrorCode::Internal,
+            "update check failed: release has no tag",
+        )
+    })?;
+    let check = UpdateCheck::new(
+        env!("CARGO_PKG_VERSION"),
+        latest,
+        release["html_url"].as_str().map(str::to_string),
+    );
+    serde_json::to_value(&check).map_err(|e| {
+        RpcError::new(
+            RpcErrorCode::Internal,
+            format!("serialization error: {}", e),
+        )
+    })
+}
+
+/// What `pane.send` writes: `text`, or `bytes_b64` for bytes that JSON strings cannot carry
+enum SendPayload {
+    Text(String),
+    Bytes(Base64Bytes),
+}
+
+fn send_payload(request: &RpcRequest) -> Result<SendPayload, RpcError> {
+    match (
+        request.params["text"].as_str(),
+        request.params["bytes_b64"].as_str(),
+    ) {
+        (Some(text), None) => Ok(SendPayload::Text(text.to_string())),
+        (None, Some(encoded)) => Base64Bytes::decode(encoded)
+            .map(SendPayload::Bytes)
+            .map_err(|e| {
+                RpcError::new(
+                    RpcErrorCode::InvalidParams,
+                    format!("invalid 'bytes_b64': {e}"),
+                )
+            }),
+        (Some(_), Some(_)) => Err(RpcError::new(
+            RpcErrorCode::InvalidParams,
+            "'text' and 'bytes_b64' are mutually exclusive",
+        )),
+        (None, None) => Err(RpcError::new(
+            RpcErrorCode::InvalidParams,
+            "missing 'text' (or 'bytes_b64')",
+        )),
+    }
+}
+
+/// The allowlist published by the instance configured with `host_exec_allow` (empty when none
+/// has been loaded in this session)
+fn read_host_exec_allow() -> Vec<String> {
+    std::fs::read_to_string(HOST_EXEC_ALLOW_FILE)
+        .map(|list| parse_host_exec_allow(&list))
+        .unwrap_or_default()
+}
+
+fn unix_time_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn same_pane(a: &state::PaneEntry, b: &state::PaneEntry) -> bool {
+    a.is_plugin == b.is_plugin && a.numeric_id == b.numeric_id
+}
+
+/// Whether a listed pane passes a `panes.list` filter; `current_tab` is the focused pane's tab
+fn filter_matches(
+    filter: &PaneFilter,
+    pane: &state::PaneListItem,
+    current_tab: Option<usize>,
+) -> bool {
+    let pattern_matches = |pattern: &Option<zjctl_proto::StringPattern>, text: Option<&str>| {
+        pattern
+            .as_ref()
+            .is_none_or(|pattern| text.is_some_and(|text| pattern.matches(text).unwrap_or(false)))
+    };
+    let pane_type = match filter.pane_type {
+        Some(PaneType::Terminal) => Some("terminal"),
+        Some(PaneType::Plugin) => Some("plugin"),
+        None => None,
+    };
+    filter.tab.is_none_or(|tab| pane.tab_index == tab)
+        && (!filter.current_tab || current_tab == Some(pane.tab_index))
+        && pane_type.is_none_or(|kind| pane.pane_type == kind)
+        && (pattern_matches(&filter.title, Some(&pane.title))
+            || pattern_matches(&filter.title, pane.name.as_deref()))
+        && pattern_matches(&filter.command, pane.command.as_deref())
+        && filter
+            .floating
+            .is_none_or(|floating| pane.floating == floating)
+        && (!filter.focused || pane.focused)
+}
+
+/// The non-empty `tag` param of `tag.add` / `tag.remove`
+fn tag_param(request: &RpcRequest) -> Result<&str, RpcError> {
+    request.params["tag"]
+        .as_str()
+        .filter(|tag| !tag.is_empty())
+        .ok_or_else(|| RpcError::new(RpcErrorCode::InvalidParams, "missing 'tag'"))
+}
+
+/// Error for a selector that resolved to no panes
+fn no_match_error(selector: &str) -> RpcError {
+    let error = RpcError::new(RpcErrorCode::NoMatch, "no panes match selector")
+        .with_hint("run: zjctl panes ls to see available panes");
+    if selector.starts_with("title:") || selector.starts_with("cmd:") {
+        error.with_hint("substring selectors are case-insensitive; use /regex/ for exact patterns")
+    } else {
+        error
+    }
+}
+
+/// Error for a selector that resolved to several panes where one was expected
+fn ambiguous_error(panes: &[&state::PaneEntry], supports_all: bool) -> RpcError {
+    let message = if supports_all {
+        format!(
+            "{} panes match selector; use --all to target all",
+            panes.len()
+        )
+    } else {
+        format!("{} panes match selector", panes.len())
+    };
+    let mut ids: Vec<String> = panes
+        .iter()
+        .map(|p| format!("id:{}", p.id_string()))
+        .collect();
+    ids.sort();
+
+    let mut error = RpcError::new(RpcErrorCode::AmbiguousMatch, message);
+    if supports_all {
+        error = error.with_hint("add --all to target every matching pane");
+    }
+    error.with_hint(format!("use an exact selector: {}", ids.join(", ")))
+}
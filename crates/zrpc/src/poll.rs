@@ -0,0 +1,126 @@
+//! How often the plugin asks the host for its clients (the only way to learn which pane a
+//! client has focused)
+//!
+//! Configured with `client_poll`, in the plugin's configuration block or through
+//! `system.configure` (`ZJCTL_CLIENT_POLL`):
+//! - `adaptive` (default) or `adaptive:MIN-MAX`: every MIN ms (200) around RPCs and focus
+//!   changes, doubling while nothing happens up to MAX ms (5000)
+//! - `fixed` or `fixed:MS`: every MS ms (200)
+//! - `off`: no timer; clients are listed after each pipe message and pane update instead
+
+const DEFAULT_MIN_MS: u64 = 200;
+const DEFAULT_MAX_MS: u64 = 5_000;
+/// Shortest interval accepted from the configuration
+const FLOOR_MS: u64 = 50;
+/// Host timers may fire a little before the requested time
+const SLACK_MS: u64 = 25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PollMode {
+    Adaptive { min_ms: u64, max_ms: u64 },
+    Fixed { every_ms: u64 },
+    Off,
+}
+
+impl Default for PollMode {
+    fn default() -> Self {
+        PollMode::Adaptive {
+            min_ms: DEFAULT_MIN_MS,
+            max_ms: DEFAULT_MAX_MS,
+        }
+    }
+}
+
+impl PollMode {
+    /// Parse a `client_poll` value; anything unrecognised keeps the default
+    pub fn parse(value: &str) -> Self {
+        let (mode, args) = value.split_once(':').unwrap_or((value, ""));
+        match mode.trim() {
+            "off" => PollMode::Off,
+            "fixed" => PollMode::Fixed {
+                every_ms: args.trim().parse().unwrap_or(DEFAULT_MIN_MS).max(FLOOR_MS),
+            },
+            "adaptive" => {
+                let (min, max) = args.split_once('-').unwrap_or((args, ""));
+                let min_ms = min.trim().parse().unwrap_or(DEFAULT_MIN_MS).max(FLOOR_MS);
+                let max_ms = max.trim().parse().unwrap_or(DEFAULT_MAX_MS).max(min_ms);
+                PollMode::Adaptive { min_ms, max_ms }
+            }
+            _ => PollMode::default(),
+        }
+    }
+
+    /// The `client_poll` value that parses back to this mode
+    pub fn to_config(self) -> String {
+        match self {
+            PollMode::Adaptive { min_ms, max_ms } => format!("adaptive:{min_ms}-{max_ms}"),
+            PollMode::Fixed { every_ms } => format!("fixed:{every_ms}"),
+            PollMode::Off => "off".to_string(),
+        }
+    }
+}
+
+/// Poll schedule. The host cannot cancel a timer, so each poll records when the next one is
+/// due and timers firing before that (superseded by a speed-up) are ignored.
+#[derive(Debug, Default)]
+pub struct ClientPoll {
+    pub mode: PollMode,
+    /// Current adaptive interval
+    interval_ms: u64,
+    /// Unix time (ms) the next poll is due; `None` when no poll timer is armed
+    due_ms: Option<u64>,
+}
+
+impl ClientPoll {
+    pub fn new(mode: PollMode) -> Self {
+        Self {
+            mode,
+            interval_ms: 0,
+            due_ms: None,
+        }
+    }
+
+    /// Whether the next poll is due at `now_ms`. Checked on every host event, so a timer that
+    /// was superseded (or belongs to a request deadline) does nothing.
+    pub fn is_due(&self, now_ms: u64) -> bool {
+        self.due_ms
+            .is_some_and(|due| now_ms.saturating_add(SLACK_MS) >= due)
+    }
+
+    /// Interval until the next poll after one at `now_ms`; `changed` says whether focus moved
+    /// since the previous poll. `None` when polling is off.
+    pub fn next(&mut self, now_ms: u64, changed: bool) -> Option<u64> {
+        let interval = match self.mode {
+            PollMode::Off => {
+                self.due_ms = None;
+                return None;
+            }
+            PollMode::Fixed { every_ms } => every_ms,
+            PollMode::Adaptive { min_ms, max_ms } => {
+                if changed || self.interval_ms == 0 {
+                    min_ms
+                } else {
+                    self.interval_ms.saturating_mul(2).min(max_ms)
+                }
+            }
+        };
+        self.interval_ms = interval;
+        self.due_ms = Some(now_ms.saturating_add(interval));
+        Some(interval)
+    }
+
+    /// Shorten the wait after activity (an RPC) in adaptive mode. Returns the new interval when
+    /// a sooner poll timer has to be armed.
+    pub fn speed_up(&mut self, now_ms: u64) -> Option<u64> {
+        let PollMode::Adaptive { min_ms, .. } = self.mode else {
+            return None;
+        };
+        self.interval_ms = min_ms;
+        let due = now_ms.saturating_add(min_ms);
+        if self.due_ms.is_some_and(|current| current <= due) {
+            return None;
+        }
+        self.due_ms = Some(due);
+        Some(min_ms)
+    }
+}
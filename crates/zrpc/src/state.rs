@@ -1,7 +1,16 @@
 //! Plugin state management - tracks panes and tabs from Zellij events
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+/// Number of focus changes remembered for `pane.focus_previous`
+const FOCUS_HISTORY_LEN: usize = 16;
+/// Number of sent texts remembered per pane for `pane.history`
+const SEND_HISTORY_LEN: usize = 50;
+/// Number of title/command changes remembered per pane for `panes.history`
+const TITLE_HISTORY_LEN: usize = 50;
+/// Number of state events kept for `events.poll`
+const EVENT_LOG_LEN: usize = 256;
 use zellij_tile::prelude::*;
 
 /// Plugin state tracking panes and tabs
@@ -13,6 +22,126 @@ pub struct PluginState {
     pub tabs: Vec<TabEntry>,
     /// Focused pane of the current client (if known)
     pub current_client_pane_id: Option<PaneId>,
+    /// Clients attached to the session, as of the latest `ListClients`
+    pub clients: Vec<ClientEntry>,
+    /// Session name (from ModeUpdate, falling back to the pipe configuration)
+    pub session_name: Option<String>,
+    /// Input mode of the client (Scroll/Search swallow typed input for the focused pane)
+    pub input_mode: Option<InputMode>,
+    /// Version of the Zellij host
+    pub zellij_version: Option<String>,
+    /// Bumped on every observed state change, so clients can detect stale snapshots
+    pub generation: u64,
+    /// User-set pane names (set through zjctl), keyed by pane ID string
+    pub names: HashMap<String, String>,
+    /// Working directories reported through zjctl (launch `cwd`, `pane.set_cwd`), keyed by
+    /// pane ID string; Zellij does not expose them to plugins
+    pub cwds: HashMap<String, String>,
+    /// Identity tokens exported into panes as `ZJCTL_PANE_TOKEN` (`pane launch --token`),
+    /// keyed by pane ID string
+    pub tokens: HashMap<String, String>,
+    /// Pane bookmarks (`zjctl mark`): mark -> pane ID string; dropped when the pane closes
+    pub marks: BTreeMap<String, String>,
+    /// Pane tags (`zjctl tag`), keyed by pane ID string; dropped when the pane closes
+    pub tags: HashMap<String, BTreeSet<String>>,
+    /// Recently focused panes (oldest first), as pane ID strings
+    pub focus_history: VecDeque<String>,
+    /// Texts sent through zjctl (oldest first), keyed by pane ID string
+    pub send_history: HashMap<String, VecDeque<SentText>>,
+    /// Titles and commands each pane has shown (oldest first), keyed by pane ID string
+    pub title_history: HashMap<String, VecDeque<TitleChange>>,
+    /// Idempotency keys of delivered sends and when they expire (Unix ms)
+    pub idempotency_keys: HashMap<String, u64>,
+    /// Recent pane/tab/focus changes (oldest first) for `events.poll`
+    pub events: VecDeque<StateEvent>,
+    /// Sequence number of the newest event (0 before the first one)
+    pub event_seq: u64,
+    /// Which kinds of host state have arrived since the plugin loaded
+    pub primed: Primed,
+}
+
+/// Host state seen at least once; until all of it has arrived, pane lists and focus are empty
+/// or stale rather than wrong-but-plausible
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct Primed {
+    pub panes: bool,
+    pub tabs: bool,
+    pub clients: bool,
+}
+
+impl Primed {
+    pub fn ready(&self) -> bool {
+        self.panes && self.tabs && self.clients
+    }
+}
+
+/// One observed change, as streamed by `zjctl events`
+#[derive(Debug, Clone, Serialize)]
+pub struct StateEvent {
+    pub seq: u64,
+    /// Unix timestamp in milliseconds
+    pub time_ms: u64,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+/// Tabs are identified by position, so closing a tab before others reports the later ones as
+/// renamed.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum EventKind {
+    PaneOpened {
+        pane: String,
+        tab_index: usize,
+        title: String,
+    },
+    PaneClosed {
+        pane: String,
+    },
+    PaneTitleChanged {
+        pane: String,
+        title: String,
+    },
+    PaneExited {
+        pane: String,
+        exit_status: Option<i32>,
+    },
+    PaneFocused {
+        pane: String,
+    },
+    TabOpened {
+        tab_index: usize,
+        name: String,
+    },
+    TabClosed {
+        tab_index: usize,
+        name: String,
+    },
+    TabRenamed {
+        tab_index: usize,
+        name: String,
+    },
+    TabFocused {
+        tab_index: usize,
+        name: String,
+    },
+}
+
+/// One `pane.send` delivered to a pane
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SentText {
+    /// Unix timestamp in milliseconds
+    pub time_ms: u64,
+    pub text: String,
+}
+
+/// A title or command a pane showed from `time_ms` on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TitleChange {
+    /// Unix timestamp in milliseconds
+    pub time_ms: u64,
+    pub title: String,
+    pub command: Option<String>,
 }
 
 /// Information about a single pane
@@ -22,10 +151,14 @@ pub struct PaneEntry {
     pub numeric_id: u32,
     /// Whether this is a plugin pane (vs terminal)
     pub is_plugin: bool,
-    /// Pane title
+    /// Pane title as displayed by Zellij (the user-set name, if any, otherwise the terminal title)
     pub title: String,
+    /// User-set pane name (if set through zjctl)
+    pub name: Option<String>,
     /// Command running in pane (for terminals)
     pub command: Option<String>,
+    /// Working directory, if reported through zjctl
+    pub cwd: Option<String>,
     /// Tab index this pane belongs to
     pub tab_index: usize,
     /// Tab name
@@ -36,6 +169,16 @@ pub struct PaneEntry {
     pub floating: bool,
     /// Whether this pane is suppressed
     pub suppressed: bool,
+    /// Whether the pane's command exited (command panes only)
+    pub exited: bool,
+    /// Exit status of the pane's command, if it exited and is still shown
+    pub exit_status: Option<i32>,
+    /// Whether the pane is held (exited and waiting to be re-run or closed)
+    pub held: bool,
+    /// Pane column offset on screen (including frame)
+    pub x: usize,
+    /// Pane row offset on screen (including frame)
+    pub y: usize,
     /// Pane content rows (terminal size)
     pub rows: usize,
     /// Pane content columns (terminal size)
@@ -60,21 +203,36 @@ impl PaneEntry {
             PaneId::Terminal(self.numeric_id)
         }
     }
+
+    /// Dynamic terminal title, if Zellij isn't displaying the user-set name instead
+    pub fn terminal_title(&self) -> Option<&str> {
+        match &self.name {
+            Some(name) if *name == self.title => None,
+            _ => Some(self.title.as_str()),
+        }
+    }
 }
 
 /// Information about a tab
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 pub struct TabEntry {
     pub index: usize,
     pub name: String,
     pub active: bool,
+    /// A single pane fills the tab
+    pub fullscreen: bool,
+    /// Input is synced to all panes in the tab
+    pub sync_panes: bool,
+    pub floating_visible: bool,
+    pub swap_layout: Option<String>,
 }
 
 impl PluginState {
     /// Update pane state from PaneUpdate event
     pub fn update_panes(&mut self, manifest: PaneManifest) {
-        self.panes.clear();
+        self.generation += 1;
+        self.primed.panes = true;
+        let previous = std::mem::take(&mut self.panes);
 
         for (tab_index, panes) in manifest.panes {
             let tab_name = self
@@ -88,12 +246,19 @@ impl PluginState {
                     numeric_id: pane.id,
                     is_plugin: pane.is_plugin,
                     title: pane.title.clone(),
+                    name: None,
                     command: pane.terminal_command.clone(),
+                    cwd: None,
                     tab_index,
                     tab_name: tab_name.clone(),
                     focused: pane.is_focused,
                     floating: pane.is_floating,
                     suppressed: pane.is_suppressed,
+                    exited: pane.exited,
+                    exit_status: pane.exit_status,
+                    held: pane.is_held,
+                    x: pane.pane_x,
+                    y: pane.pane_y,
                     rows: pane.pane_content_rows,
                     cols: pane.pane_content_columns,
                 };
@@ -101,10 +266,121 @@ impl PluginState {
                 self.panes.insert(key, entry);
             }
         }
+
+        self.names.retain(|key, _| self.panes.contains_key(key));
+        self.cwds.retain(|key, _| self.panes.contains_key(key));
+        self.tokens.retain(|key, _| self.panes.contains_key(key));
+        self.marks.retain(|_, key| self.panes.contains_key(key));
+        self.tags.retain(|key, _| self.panes.contains_key(key));
+        self.send_history
+            .retain(|key, _| self.panes.contains_key(key));
+        self.title_history
+            .retain(|key, _| self.panes.contains_key(key));
+        self.record_titles();
+        for (key, name) in &self.names {
+            if let Some(entry) = self.panes.get_mut(key) {
+                entry.name = Some(name.clone());
+            }
+        }
+        for (key, cwd) in &self.cwds {
+            if let Some(entry) = self.panes.get_mut(key) {
+                entry.cwd = Some(cwd.clone());
+            }
+        }
+
+        // The first manifest describes the session as found, not changes to it.
+        if !previous.is_empty() {
+            self.record_pane_changes(&previous);
+        }
+    }
+
+    fn record_pane_changes(&mut self, previous: &HashMap<String, PaneEntry>) {
+        let mut closed: Vec<&String> = previous
+            .keys()
+            .filter(|key| !self.panes.contains_key(*key))
+            .collect();
+        closed.sort();
+        let mut current: Vec<&PaneEntry> = self.panes.values().collect();
+        current.sort_by_key(|p| (p.tab_index, p.is_plugin, p.numeric_id));
+
+        let mut changes = Vec::new();
+        for pane in current {
+            let id = pane.id_string();
+            match previous.get(&id) {
+                None => changes.push(EventKind::PaneOpened {
+                    pane: id,
+                    tab_index: pane.tab_index,
+                    title: pane.title.clone(),
+                }),
+                Some(old) => {
+                    if old.title != pane.title {
+                        changes.push(EventKind::PaneTitleChanged {
+                            pane: id.clone(),
+                            title: pane.title.clone(),
+                        });
+                    }
+                    if pane.exited && !old.exited {
+                        changes.push(EventKind::PaneExited {
+                            pane: id,
+                            exit_status: pane.exit_status,
+                        });
+                    }
+                }
+            }
+        }
+        changes.extend(
+            closed
+                .into_iter()
+                .map(|pane| EventKind::PaneClosed { pane: pane.clone() }),
+        );
+        for change in changes {
+            self.record_event(change);
+        }
+    }
+
+    /// Append each pane's title and command to its history when they changed
+    fn record_titles(&mut self) {
+        let now = crate::unix_time_ms();
+        for (key, pane) in &self.panes {
+            let history = self.title_history.entry(key.clone()).or_default();
+            if history
+                .back()
+                .is_some_and(|last| last.title == pane.title && last.command == pane.command)
+            {
+                continue;
+            }
+            history.push_back(TitleChange {
+                time_ms: now,
+                title: pane.title.clone(),
+                command: pane.command.clone(),
+            });
+            while history.len() > TITLE_HISTORY_LEN {
+                history.pop_front();
+            }
+        }
+    }
+
+    /// Remember a user-set pane name
+    pub fn set_name(&mut self, key: &str, name: &str) {
+        self.names.insert(key.to_string(), name.to_string());
+        if let Some(entry) = self.panes.get_mut(key) {
+            entry.name = Some(name.to_string());
+        }
+    }
+
+    /// Remember a pane's working directory
+    pub fn set_cwd(&mut self, key: &str, cwd: &str) {
+        self.cwds.insert(key.to_string(), cwd.to_string());
+        if let Some(entry) = self.panes.get_mut(key) {
+            entry.cwd = Some(cwd.to_string());
+        }
     }
 
     /// Update tab state from TabUpdate event
     pub fn update_tabs(&mut self, tabs: Vec<TabInfo>) {
+        self.generation += 1;
+        self.primed.tabs = true;
+        let previous = std::mem::take(&mut self.tabs);
         let max_position = tabs.iter().map(|t| t.position).max().unwrap_or(0);
         let mut entries: Vec<Option<TabEntry>> = vec![None; max_position.saturating_add(1)];
 
@@ -113,6 +389,10 @@ impl PluginState {
                 index: tab.position,
                 name: tab.name,
                 active: tab.active,
+                fullscreen: tab.is_fullscreen_active,
+                sync_panes: tab.is_sync_panes_active,
+                floating_visible: tab.are_floating_panes_visible,
+                swap_layout: tab.active_swap_layout_name,
             });
         }
 
@@ -124,12 +404,74 @@ impl PluginState {
                     index,
                     name: format!("Tab {}", index),
                     active: false,
+                    fullscreen: false,
+                    sync_panes: false,
+                    floating_visible: false,
+                    swap_layout: None,
                 })
             })
             .collect();
+
+        if !previous.is_empty() {
+            self.record_tab_changes(&previous);
+        }
+    }
+
+    fn record_tab_changes(&mut self, previous: &[TabEntry]) {
+        let mut changes = Vec::new();
+        for tab in &self.tabs {
+            let (tab_index, name) = (tab.index, tab.name.clone());
+            match previous.get(tab.index) {
+                None => changes.push(EventKind::TabOpened { tab_index, name }),
+                Some(old) if old.name != tab.name => {
+                    changes.push(EventKind::TabRenamed { tab_index, name })
+                }
+                Some(_) => {}
+            }
+        }
+        for old in previous.iter().skip(self.tabs.len()) {
+            changes.push(EventKind::TabClosed {
+                tab_index: old.index,
+                name: old.name.clone(),
+            });
+        }
+        let was_active = previous.iter().find(|t| t.active).map(|t| t.index);
+        if let Some(tab) = self.tabs.iter().find(|t| t.active) {
+            if was_active != Some(tab.index) {
+                changes.push(EventKind::TabFocused {
+                    tab_index: tab.index,
+                    name: tab.name.clone(),
+                });
+            }
+        }
+        for change in changes {
+            self.record_event(change);
+        }
     }
 
     pub fn update_clients(&mut self, clients: Vec<ClientInfo>) {
+        self.primed.clients = true;
+        let previous = self.current_client_pane_id;
+        self.set_current_client_pane(&clients);
+        self.clients = clients
+            .iter()
+            .map(|client| ClientEntry {
+                id: client.client_id,
+                pane: match client.pane_id {
+                    PaneId::Terminal(id) => format!("terminal:{id}"),
+                    PaneId::Plugin(id) => format!("plugin:{id}"),
+                },
+                command: Some(client.running_command.clone()).filter(|c| !c.is_empty()),
+                current: client.is_current_client,
+            })
+            .collect();
+        // Clients are polled on a timer; only count actual focus changes.
+        if self.current_client_pane_id != previous {
+            self.generation += 1;
+        }
+    }
+
+    fn set_current_client_pane(&mut self, clients: &[ClientInfo]) {
         if clients.is_empty() {
             self.current_client_pane_id = None;
             return;
@@ -145,6 +487,84 @@ impl PluginState {
             .map(|c| c.pane_id);
     }
 
+    /// Update session metadata from ModeUpdate event
+    pub fn update_mode(&mut self, mode: ModeInfo) {
+        self.input_mode = Some(mode.mode);
+        if let Some(name) = mode.session_name {
+            if self.session_name.as_deref() != Some(name.as_str()) {
+                self.session_name = Some(name);
+                self.generation += 1;
+            }
+        }
+    }
+
+    /// Record the currently focused pane, if it changed
+    pub fn record_focus(&mut self, id: &str) {
+        if self.focus_history.back().map(String::as_str) == Some(id) {
+            return;
+        }
+        self.record_event(EventKind::PaneFocused {
+            pane: id.to_string(),
+        });
+        self.focus_history.push_back(id.to_string());
+        while self.focus_history.len() > FOCUS_HISTORY_LEN {
+            self.focus_history.pop_front();
+        }
+    }
+
+    /// Append to the event log, dropping the oldest entries past the limit
+    pub fn record_event(&mut self, kind: EventKind) {
+        self.event_seq += 1;
+        self.events.push_back(StateEvent {
+            seq: self.event_seq,
+            time_ms: crate::unix_time_ms(),
+            kind,
+        });
+        while self.events.len() > EVENT_LOG_LEN {
+            self.events.pop_front();
+        }
+    }
+
+    /// Events newer than `since`, plus how many in between were already dropped from the log
+    pub fn events_since(&self, since: u64) -> (Vec<&StateEvent>, u64) {
+        let oldest = self.events.front().map_or(self.event_seq + 1, |e| e.seq);
+        let missed = oldest.saturating_sub(since + 1);
+        let events = self.events.iter().filter(|e| e.seq > since).collect();
+        (events, missed)
+    }
+
+    /// Remember text sent to a pane, dropping the oldest entries past the limit
+    pub fn record_send(&mut self, id: &str, text: &str, time_ms: u64) {
+        let history = self.send_history.entry(id.to_string()).or_default();
+        history.push_back(SentText {
+            time_ms,
+            text: text.to_string(),
+        });
+        while history.len() > SEND_HISTORY_LEN {
+            history.pop_front();
+        }
+    }
+
+    /// Claim an idempotency key for `window_ms`; `false` if it is still held by an earlier send
+    pub fn claim_idempotency_key(&mut self, key: &str, now_ms: u64, window_ms: u64) -> bool {
+        self.idempotency_keys.retain(|_, expires| *expires > now_ms);
+        if self.idempotency_keys.contains_key(key) {
+            return false;
+        }
+        self.idempotency_keys
+            .insert(key.to_string(), now_ms.saturating_add(window_ms));
+        true
+    }
+
+    /// Most recently focused pane other than `current` that still exists
+    pub fn previous_focus(&self, current: Option<&str>) -> Option<&PaneEntry> {
+        self.focus_history
+            .iter()
+            .rev()
+            .filter(|id| Some(id.as_str()) != current)
+            .find_map(|id| self.panes.get(id))
+    }
+
     pub fn active_tab_index(&self) -> Option<usize> {
         self.tabs.iter().find(|t| t.active).map(|t| t.index)
     }
@@ -155,22 +575,105 @@ impl PluginState {
             .values()
             .map(|p| {
                 let id = p.id_string();
+                let token = self.tokens.get(&id).cloned();
+                let tags = self
+                    .tags
+                    .get(&id)
+                    .map(|tags| tags.iter().cloned().collect())
+                    .unwrap_or_default();
                 PaneListItem {
                     focused: focused_id == Some(id.as_str()),
                     id,
                     pane_type: if p.is_plugin { "plugin" } else { "terminal" }.to_string(),
                     title: p.title.clone(),
+                    name: p.name.clone(),
+                    terminal_title: p.terminal_title().map(str::to_string),
                     command: p.command.clone(),
+                    cwd: p.cwd.clone(),
+                    token,
+                    tags,
                     tab_index: p.tab_index,
                     tab_name: p.tab_name.clone(),
                     floating: p.floating,
                     suppressed: p.suppressed,
+                    exited: p.exited,
+                    exit_status: p.exit_status,
+                    held: p.held,
+                    x: p.x,
+                    y: p.y,
                     rows: p.rows,
                     cols: p.cols,
                 }
             })
             .collect()
     }
+
+    /// List all tabs for the tabs.list command
+    pub fn list_tabs(&self) -> Vec<TabListItem> {
+        self.tabs
+            .iter()
+            .map(|t| {
+                let in_tab = || self.panes.values().filter(|p| p.tab_index == t.index);
+                TabListItem {
+                    index: t.index,
+                    name: t.name.clone(),
+                    active: t.active,
+                    panes: in_tab().filter(|p| !p.is_plugin).count(),
+                    floating_panes: in_tab().filter(|p| !p.is_plugin && p.floating).count(),
+                    fullscreen: t.fullscreen,
+                    sync_panes: t.sync_panes,
+                    floating_visible: t.floating_visible,
+                    swap_layout: t.swap_layout.clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Result envelope for the tabs.list command
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TabsListResult {
+    pub session: Option<String>,
+    pub generation: u64,
+    pub tabs: Vec<TabListItem>,
+    pub clients: Vec<ClientEntry>,
+}
+
+/// A client attached to the session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientEntry {
+    pub id: u16,
+    /// Pane the client has focused, e.g. `terminal:3`
+    pub pane: String,
+    /// What runs in that pane, when Zellij reports it
+    pub command: Option<String>,
+    /// The client the plugin acts for
+    pub current: bool,
+}
+
+/// Tab info for list response
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TabListItem {
+    pub index: usize,
+    pub name: String,
+    pub active: bool,
+    /// Terminal panes in the tab (tiled and floating)
+    pub panes: usize,
+    pub floating_panes: usize,
+    pub fullscreen: bool,
+    pub sync_panes: bool,
+    pub floating_visible: bool,
+    pub swap_layout: Option<String>,
+}
+
+/// Result envelope for the panes.list command
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PanesListResult {
+    pub session: Option<String>,
+    pub zellij_version: Option<String>,
+    pub plugin_version: String,
+    pub generation: u64,
+    pub panes: Vec<PaneListItem>,
 }
 
 /// Pane info for list response
@@ -179,12 +682,22 @@ pub struct PaneListItem {
     pub id: String,
     pub pane_type: String,
     pub title: String,
+    pub name: Option<String>,
+    pub terminal_title: Option<String>,
     pub command: Option<String>,
+    pub cwd: Option<String>,
+    pub token: Option<String>,
+    pub tags: Vec<String>,
     pub tab_index: usize,
     pub tab_name: String,
     pub focused: bool,
     pub floating: bool,
     pub suppressed: bool,
+    pub exited: bool,
+    pub exit_status: Option<i32>,
+    pub held: bool,
+    pub x: usize,
+    pub y: usize,
     pub rows: usize,
     pub cols: usize,
 }